@@ -342,6 +342,82 @@ async fn test_prompt_caching(cx: &mut TestAppContext) {
     );
 }
 
+#[gpui::test]
+async fn test_long_tool_results_are_sent_in_full(cx: &mut TestAppContext) {
+    let ThreadTest {
+        model, thread, fs, ..
+    } = setup(cx, TestModel::Fake).await;
+    let fake_model = model.as_fake();
+
+    // Long enough that compression used to summarize it, but short enough for
+    // read_file to return the whole file rather than an outline.
+    let contents = (0..400)
+        .map(|ix| format!("fn function_{ix}() -> usize {{ {ix} }}\n"))
+        .collect::<String>();
+    fs.insert_file(path!("/test/lib.rs"), contents.clone().into_bytes())
+        .await;
+    thread.update(cx, |thread, cx| {
+        let tool = ReadFileTool::new(
+            cx.weak_entity(),
+            thread.project().clone(),
+            thread.action_log().clone(),
+        );
+        thread.add_tool(tool);
+    });
+
+    for compress_requests in [false, true] {
+        cx.update(|cx| {
+            SettingsStore::update_global(cx, |store, cx| {
+                store.update_user_settings(cx, |settings| {
+                    settings.agent.get_or_insert_default().compress_requests =
+                        Some(compress_requests);
+                });
+            });
+        });
+        thread
+            .update(cx, |thread, cx| {
+                thread.send(UserMessageId::new(), ["Read lib.rs"], cx)
+            })
+            .unwrap();
+        cx.run_until_parked();
+
+        let input = json!({"path": "test/lib.rs"});
+        fake_model.send_last_completion_stream_event(LanguageModelCompletionEvent::ToolUse(
+            LanguageModelToolUse {
+                id: format!("read_file_{compress_requests}").into(),
+                name: ReadFileTool::name().into(),
+                raw_input: input.to_string(),
+                input,
+                is_input_complete: true,
+                thought_signature: None,
+            },
+        ));
+        fake_model.end_last_completion_stream();
+        cx.run_until_parked();
+
+        let completion = fake_model.pending_completions().pop().unwrap();
+        let tool_results = completion
+            .messages
+            .iter()
+            .flat_map(|message| &message.content)
+            .filter_map(|content| match content {
+                MessageContent::ToolResult(tool_result) => Some(tool_result.content.clone()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            tool_results,
+            vec![
+                language_model::LanguageModelToolResultContent::from(contents.as_str());
+                if compress_requests { 2 } else { 1 }
+            ],
+            "compress_requests: {compress_requests}"
+        );
+        fake_model.end_last_completion_stream();
+        cx.run_until_parked();
+    }
+}
+
 #[gpui::test]
 #[cfg_attr(not(feature = "e2e"), ignore)]
 async fn test_basic_tool_calls(cx: &mut TestAppContext) {
@@ -2399,6 +2475,7 @@ async fn setup(cx: &mut TestAppContext, model: TestModel) -> ThreadTest {
                             ToolRequiringPermission::name(): true,
                             InfiniteTool::name(): true,
                             ThinkingTool::name(): true,
+                            ReadFileTool::name(): true,
                         }
                     }
                 }
@@ -1876,7 +1876,7 @@ impl Thread {
         let messages = self.build_request_messages(available_tools, cx);
         log::debug!("Request will include {} messages", messages.len());
 
-        let request = LanguageModelRequest {
+        let mut request = LanguageModelRequest {
             thread_id: Some(self.id.to_string()),
             prompt_id: Some(self.prompt_id.to_string()),
             intent: Some(completion_intent),
@@ -1889,6 +1889,22 @@ impl Thread {
             thinking_allowed: true,
         };
 
+        if AgentSettings::get_global(cx).compress_requests {
+            let elided = request.elide_repeated_files();
+            if elided.count > 0 {
+                log::info!(
+                    "Replaced {} repeated file(s) with a placeholder, saving about {} tokens",
+                    elided.count,
+                    elided.estimated_tokens_saved
+                );
+            }
+        }
+
+        if let Some(cache_configuration) = model.cache_configuration() {
+            let anchors = request.place_cache_anchors(&cache_configuration);
+            log::debug!("Placed {} cache anchors", anchors);
+        }
+
         log::debug!("Completion request built successfully");
         Ok(request)
     }
@@ -939,6 +939,30 @@ impl MessageEditor {
         self.editor.read(cx).text(cx)
     }
 
+    /// Surround the message with accepted prompt refinements. Only inserting
+    /// text keeps the mentions already in the message intact.
+    pub fn insert_refinements(&mut self, before: &str, after: &str, cx: &mut Context<Self>) {
+        if before.is_empty() && after.is_empty() {
+            return;
+        }
+        self.editor.update(cx, |editor, cx| {
+            editor.edit(
+                [
+                    (
+                        multi_buffer::Anchor::min()..multi_buffer::Anchor::min(),
+                        before.to_string(),
+                    ),
+                    (
+                        multi_buffer::Anchor::max()..multi_buffer::Anchor::max(),
+                        after.to_string(),
+                    ),
+                ],
+                cx,
+            );
+            editor.request_autoscroll(Autoscroll::fit(), cx);
+        });
+    }
+
     pub fn set_placeholder_text(
         &mut self,
         placeholder: &str,
@@ -51,6 +51,7 @@ use ui::{
     PopoverMenuHandle, SpinnerLabel, TintColor, Tooltip, WithScrollbar, prelude::*,
 };
 use util::{ResultExt, size::format_file_size, time::duration_alt_display};
+use workspace::notifications::NotifyTaskExt as _;
 use workspace::{CollaboratorId, NewTerminal, Workspace};
 use zed_actions::agent::{Chat, ToggleModelSelector};
 use zed_actions::assistant::OpenRulesLibrary;
@@ -62,6 +63,7 @@ use crate::acp::entry_view_state::{EntryViewEvent, ViewEvent};
 use crate::acp::message_editor::{MessageEditor, MessageEditorEvent};
 use crate::agent_diff::AgentDiff;
 use crate::profile_selector::{ProfileProvider, ProfileSelector};
+use crate::prompt_refinement::{self, PromptRefinementModal};
 
 use crate::ui::{
     AgentNotification, AgentNotificationEvent, BurnModeTooltip, UnavailableEditingTooltip,
@@ -70,7 +72,7 @@ use crate::ui::{
 use crate::{
     AgentDiffPane, AgentPanel, AllowAlways, AllowOnce, ContinueThread, ContinueWithBurnMode,
     CycleModeSelector, ExpandMessageEditor, Follow, KeepAll, NewThread, OpenAgentDiff, OpenHistory,
-    RejectAll, RejectOnce, ToggleBurnMode, ToggleProfileSelector,
+    RefinePrompt, RejectAll, RejectOnce, ToggleBurnMode, ToggleProfileSelector,
 };
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -886,6 +888,45 @@ impl AcpThreadView {
         cx.notify();
     }
 
+    fn refine_prompt(&mut self, _: &RefinePrompt, window: &mut Window, cx: &mut Context<Self>) {
+        let prompt = self.message_editor.read(cx).text(cx);
+        if prompt.trim().is_empty() {
+            return;
+        }
+        let Some(workspace) = self.workspace.upgrade() else {
+            return;
+        };
+        cx.stop_propagation();
+
+        let action_log = self
+            .thread()
+            .map(|thread| thread.read(cx).action_log().clone());
+        let context = prompt_refinement::workspace_context(&workspace, action_log.as_ref(), cx);
+        let refinement = prompt_refinement::refine_prompt(prompt, context, cx);
+        let message_editor = self.message_editor.downgrade();
+        let workspace = workspace.downgrade();
+        cx.spawn_in(window, async move |_, cx| {
+            let refined = refinement.await?;
+            workspace.update_in(cx, |workspace, window, cx| {
+                PromptRefinementModal::show(
+                    workspace,
+                    refined,
+                    move |refined, _, cx| {
+                        let (before, after) = refined.accepted_insertions();
+                        message_editor
+                            .update(cx, |editor, cx| {
+                                editor.insert_refinements(&before, &after, cx)
+                            })
+                            .ok();
+                    },
+                    window,
+                    cx,
+                );
+            })
+        })
+        .detach_and_notify_err(window, cx);
+    }
+
     fn set_editor_is_expanded(&mut self, is_expanded: bool, cx: &mut Context<Self>) {
         self.editor_expanded = is_expanded;
         self.message_editor.update(cx, |editor, cx| {
@@ -4200,6 +4241,7 @@ impl AcpThreadView {
 
         v_flex()
             .on_action(cx.listener(Self::expand_message_editor))
+            .on_action(cx.listener(Self::refine_prompt))
             .on_action(cx.listener(|this, _: &ToggleProfileSelector, window, cx| {
                 if let Some(profile_selector) = this.profile_selector.as_ref() {
                     profile_selector.read(cx).menu_handle().toggle(window, cx);
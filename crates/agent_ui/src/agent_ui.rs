@@ -14,6 +14,7 @@ mod inline_prompt_editor;
 mod language_model_selector;
 mod mention_set;
 mod profile_selector;
+mod prompt_refinement;
 mod slash_command;
 mod slash_command_picker;
 mod terminal_codegen;
@@ -71,6 +72,8 @@ actions!(
         CycleModeSelector,
         /// Expands the message editor to full size.
         ExpandMessageEditor,
+        /// Refines the message in the editor with the local prompt management agent.
+        RefinePrompt,
         /// Removes all thread history.
         RemoveHistory,
         /// Opens the conversation history view.
@@ -462,6 +465,7 @@ mod tests {
             expand_terminal_card: true,
             use_modifier_to_send: true,
             message_editor_min_lines: 1,
+            compress_requests: false,
         };
 
         cx.update(|cx| {
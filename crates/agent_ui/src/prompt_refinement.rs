@@ -0,0 +1,413 @@
+mod workspace_context;
+
+use std::sync::Arc;
+
+use anyhow::{Result, anyhow};
+use editor::Editor;
+use futures::FutureExt as _;
+use futures::future::Shared;
+use gpui::{
+    DismissEvent, Entity, EventEmitter, FocusHandle, Focusable, Global, ScrollHandle, Task,
+};
+use gpui_tokio::Tokio;
+use prompt_management_agent::{
+    ChangePlacement, ChangeStatus, PromptChange, PromptManagementAgent, RefinedPrompt, ZedContext,
+};
+use ui::{KeyBinding, Modal, ModalFooter, ModalHeader, Tooltip, WithScrollbar, prelude::*};
+use widget_log_integration::IntegratedConfig;
+use workspace::notifications::NotificationId;
+use workspace::{ModalView, Toast, Workspace};
+pub use workspace_context::workspace_context;
+
+type AgentTask = Shared<Task<Result<Arc<PromptManagementAgent>, Arc<anyhow::Error>>>>;
+
+/// The prompt management agent, created on first use since loading the local
+/// model can take a while
+#[derive(Default)]
+struct GlobalPromptAgent(Option<AgentTask>);
+
+impl Global for GlobalPromptAgent {}
+
+fn prompt_agent(cx: &mut App) -> AgentTask {
+    if let Some(task) = cx
+        .try_global::<GlobalPromptAgent>()
+        .and_then(|agent| agent.0.clone())
+    {
+        return task;
+    }
+
+    let load = Tokio::spawn_result(cx, async move {
+        let config = IntegratedConfig::load_agent_config().await?;
+        let agent = PromptManagementAgent::new(config).await?;
+        Ok(Arc::new(agent))
+    });
+    let task = cx
+        .background_spawn(async move { load.await.map_err(Arc::new) })
+        .shared();
+    cx.set_global(GlobalPromptAgent(Some(task.clone())));
+    task
+}
+
+/// Refine a prompt with the local prompt management agent
+pub fn refine_prompt(
+    prompt: String,
+    context: ZedContext,
+    cx: &mut App,
+) -> Task<Result<RefinedPrompt>> {
+    let agent = prompt_agent(cx);
+    cx.spawn(async move |cx| {
+        let agent = match agent.await {
+            Ok(agent) => agent,
+            Err(error) => {
+                // Let the next attempt load the agent again
+                cx.update(|cx| cx.set_global(GlobalPromptAgent(None)))?;
+                return Err(anyhow!(
+                    "Failed to load the prompt management agent: {error:#}"
+                ));
+            }
+        };
+        cx.update(|cx| {
+            Tokio::spawn_result(
+                cx,
+                async move { agent.refine_prompt(&prompt, &context).await },
+            )
+        })?
+        .await
+    })
+}
+
+type ApplyRefinement = Box<dyn FnOnce(&RefinedPrompt, &mut Window, &mut App)>;
+
+/// Shows the changes proposed for a prompt as a diff against the original,
+/// letting the user accept, edit or reject each one
+pub struct PromptRefinementModal {
+    refined: RefinedPrompt,
+    editing: Option<(usize, Entity<Editor>)>,
+    on_apply: Option<ApplyRefinement>,
+    focus_handle: FocusHandle,
+    scroll_handle: ScrollHandle,
+}
+
+impl PromptRefinementModal {
+    /// Review `refined` in a modal, or apply it directly when the changes
+    /// were already approved automatically
+    pub fn show(
+        workspace: &mut Workspace,
+        refined: RefinedPrompt,
+        on_apply: impl FnOnce(&RefinedPrompt, &mut Window, &mut App) + 'static,
+        window: &mut Window,
+        cx: &mut Context<Workspace>,
+    ) {
+        if refined.changes.is_empty() {
+            workspace.show_toast(
+                Toast::new(
+                    NotificationId::unique::<Self>(),
+                    "The prompt needs no refinement",
+                )
+                .autohide(),
+                cx,
+            );
+            return;
+        }
+        if !refined.needs_review() {
+            on_apply(&refined, window, cx);
+            return;
+        }
+
+        workspace.toggle_modal(window, cx, |_window, cx| Self {
+            refined,
+            editing: None,
+            on_apply: Some(Box::new(on_apply)),
+            focus_handle: cx.focus_handle(),
+            scroll_handle: ScrollHandle::new(),
+        });
+    }
+
+    fn accept(&mut self, id: usize, cx: &mut Context<Self>) {
+        self.refined.accept(id);
+        cx.notify();
+    }
+
+    fn reject(&mut self, id: usize, cx: &mut Context<Self>) {
+        if self
+            .editing
+            .as_ref()
+            .is_some_and(|(editing, _)| *editing == id)
+        {
+            self.editing = None;
+        }
+        self.refined.reject(id);
+        cx.notify();
+    }
+
+    fn start_editing(&mut self, id: usize, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(change) = self.refined.changes.iter().find(|change| change.id == id) else {
+            return;
+        };
+        let text = change.text.clone();
+        let editor = cx.new(|cx| {
+            let mut editor = Editor::auto_height(2, 12, window, cx);
+            editor.set_text(text, window, cx);
+            editor.set_show_gutter(false, cx);
+            editor
+        });
+        editor.focus_handle(cx).focus(window);
+        self.editing = Some((id, editor));
+        cx.notify();
+    }
+
+    fn finish_editing(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if let Some((id, editor)) = self.editing.take() {
+            self.refined.edit(id, &editor.read(cx).text(cx));
+            self.focus_handle.focus(window);
+            cx.notify();
+        }
+    }
+
+    fn confirm(&mut self, _: &menu::Confirm, window: &mut Window, cx: &mut Context<Self>) {
+        self.finish_editing(window, cx);
+        self.refined.accept_all();
+        self.apply(window, cx);
+    }
+
+    fn use_original(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.refined.reject_all();
+        self.apply(window, cx);
+    }
+
+    fn apply(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if let Some(on_apply) = self.on_apply.take() {
+            on_apply(&self.refined, window, cx);
+        }
+        cx.emit(DismissEvent);
+    }
+
+    fn cancel(&mut self, _: &menu::Cancel, window: &mut Window, cx: &mut Context<Self>) {
+        if self.editing.take().is_some() {
+            self.focus_handle.focus(window);
+            cx.notify();
+        } else {
+            cx.emit(DismissEvent);
+        }
+    }
+
+    fn render_original(&self, cx: &Context<Self>) -> impl IntoElement {
+        v_flex()
+            .px_2()
+            .py_1()
+            .gap_0p5()
+            .rounded_sm()
+            .border_1()
+            .border_color(cx.theme().colors().border_variant)
+            .child(
+                Label::new("Your prompt")
+                    .size(LabelSize::XSmall)
+                    .color(Color::Muted),
+            )
+            .children(self.refined.original.lines().map(|line| {
+                Label::new(format!("  {line}"))
+                    .size(LabelSize::Small)
+                    .buffer_font(cx)
+            }))
+    }
+
+    fn render_change(&self, change: &PromptChange, cx: &Context<Self>) -> impl IntoElement {
+        let id = change.id;
+        let status = change.status;
+        let editor = self
+            .editing
+            .as_ref()
+            .filter(|(editing, _)| *editing == id)
+            .map(|(_, editor)| editor.clone());
+        let (marker, background, text_color) = match status {
+            ChangeStatus::Rejected => ("-", cx.theme().status().deleted_background, Color::Muted),
+            ChangeStatus::Accepted | ChangeStatus::Pending => {
+                ("+", cx.theme().status().created_background, Color::Default)
+            }
+        };
+        let status_label = match status {
+            ChangeStatus::Pending => None,
+            ChangeStatus::Accepted => Some(("Accepted", Color::Success)),
+            ChangeStatus::Rejected => Some(("Rejected", Color::Muted)),
+        };
+
+        v_flex()
+            .id(("prompt-change", id))
+            .rounded_sm()
+            .border_1()
+            .border_color(cx.theme().colors().border_variant)
+            .overflow_hidden()
+            .child(
+                h_flex()
+                    .px_2()
+                    .py_1()
+                    .gap_1()
+                    .justify_between()
+                    .bg(cx.theme().colors().element_background)
+                    .child(
+                        h_flex()
+                            .gap_1()
+                            .child(Label::new(change.description.clone()).size(LabelSize::Small))
+                            .when(change.change_type.is_safe(), |this| {
+                                this.child(
+                                    Label::new("workspace context")
+                                        .size(LabelSize::XSmall)
+                                        .color(Color::Muted),
+                                )
+                            })
+                            .when_some(status_label, |this, (label, color)| {
+                                this.child(Label::new(label).size(LabelSize::XSmall).color(color))
+                            }),
+                    )
+                    .child(
+                        h_flex()
+                            .gap_0p5()
+                            .child(
+                                IconButton::new(("accept-change", id), IconName::Check)
+                                    .icon_size(IconSize::Small)
+                                    .disabled(status == ChangeStatus::Accepted)
+                                    .tooltip(Tooltip::text("Accept Change"))
+                                    .on_click(
+                                        cx.listener(move |this, _, _, cx| this.accept(id, cx)),
+                                    ),
+                            )
+                            .child(
+                                IconButton::new(("edit-change", id), IconName::Pencil)
+                                    .icon_size(IconSize::Small)
+                                    .tooltip(Tooltip::text("Edit Change"))
+                                    .on_click(cx.listener(move |this, _, window, cx| {
+                                        this.start_editing(id, window, cx)
+                                    })),
+                            )
+                            .child(
+                                IconButton::new(("reject-change", id), IconName::Close)
+                                    .icon_size(IconSize::Small)
+                                    .disabled(status == ChangeStatus::Rejected)
+                                    .tooltip(Tooltip::text("Reject Change"))
+                                    .on_click(
+                                        cx.listener(move |this, _, _, cx| this.reject(id, cx)),
+                                    ),
+                            ),
+                    ),
+            )
+            .map(|this| match editor {
+                Some(editor) => this.child(
+                    v_flex().p_2().gap_1().child(editor).child(
+                        h_flex().justify_end().child(
+                            Button::new(("save-change", id), "Save")
+                                .label_size(LabelSize::Small)
+                                .on_click(cx.listener(|this, _, window, cx| {
+                                    this.finish_editing(window, cx)
+                                })),
+                        ),
+                    ),
+                ),
+                None => this.child(v_flex().px_2().py_1().bg(background).children(
+                    change.text.lines().map(|line| {
+                        let label = Label::new(format!("{marker} {line}"))
+                            .size(LabelSize::Small)
+                            .buffer_font(cx)
+                            .color(text_color);
+                        if status == ChangeStatus::Rejected {
+                            label.strikethrough()
+                        } else {
+                            label
+                        }
+                    }),
+                )),
+            })
+    }
+}
+
+impl EventEmitter<DismissEvent> for PromptRefinementModal {}
+
+impl Focusable for PromptRefinementModal {
+    fn focus_handle(&self, _cx: &App) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl ModalView for PromptRefinementModal {}
+
+impl Render for PromptRefinementModal {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let focus_handle = self.focus_handle(cx);
+        let changes_before = self
+            .refined
+            .changes
+            .iter()
+            .filter(|change| change.placement == ChangePlacement::Before)
+            .map(|change| self.render_change(change, cx).into_any_element())
+            .collect::<Vec<_>>();
+        let changes_after = self
+            .refined
+            .changes
+            .iter()
+            .filter(|change| change.placement == ChangePlacement::After)
+            .map(|change| self.render_change(change, cx).into_any_element())
+            .collect::<Vec<_>>();
+
+        v_flex()
+            .id("prompt-refinement-modal")
+            .key_context("PromptRefinementModal")
+            .w(rems(40.))
+            .elevation_3(cx)
+            .track_focus(&self.focus_handle)
+            .on_action(cx.listener(Self::cancel))
+            .on_action(cx.listener(Self::confirm))
+            .child(
+                Modal::new("prompt-refinement", None)
+                    .header(
+                        ModalHeader::new()
+                            .headline("Review Prompt Refinements")
+                            .description(format!(
+                                "Quality score {:.0}%, estimated cache hit improvement {:.0}%",
+                                self.refined.quality_score * 100.,
+                                self.refined.estimated_cache_hit_improvement * 100.
+                            )),
+                    )
+                    .child(
+                        div()
+                            .size_full()
+                            .vertical_scrollbar_for(&self.scroll_handle, window, cx)
+                            .child(
+                                v_flex()
+                                    .id("prompt-refinement-changes")
+                                    .max_h(rems(30.))
+                                    .px_3()
+                                    .gap_2()
+                                    .overflow_y_scroll()
+                                    .track_scroll(&self.scroll_handle)
+                                    .children(changes_before)
+                                    .child(self.render_original(cx))
+                                    .children(changes_after),
+                            ),
+                    )
+                    .footer(
+                        ModalFooter::new().end_slot(
+                            h_flex()
+                                .gap_1()
+                                .child(Button::new("use-original", "Use Original").on_click(
+                                    cx.listener(|this, _, window, cx| {
+                                        this.use_original(window, cx)
+                                    }),
+                                ))
+                                .child(
+                                    Button::new("apply-refinements", "Apply")
+                                        .key_binding(
+                                            KeyBinding::for_action_in(
+                                                &menu::Confirm,
+                                                &focus_handle,
+                                                cx,
+                                            )
+                                            .map(|kb| kb.size(rems_from_px(12.))),
+                                        )
+                                        .on_click(cx.listener(|this, _, window, cx| {
+                                            this.confirm(&menu::Confirm, window, cx)
+                                        })),
+                                ),
+                        ),
+                    ),
+            )
+    }
+}
@@ -0,0 +1,221 @@
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use action_log::ActionLog;
+use editor::Editor;
+use gpui::{App, Entity};
+use language::{Buffer, BufferSnapshot, DiagnosticSeverity, Point, ToPoint as _};
+use prompt_management_agent::{
+    ActiveFile, DiagnosticInfo, ProjectInfo, RecentEdit, Selection, ZedContext,
+};
+use workspace::Workspace;
+
+/// Lines of code included above and below the cursor
+const EXCERPT_LINES: u32 = 10;
+/// How far from the cursor diagnostics are still considered relevant
+const DIAGNOSTIC_LINES: u32 = 50;
+const MAX_DIAGNOSTICS: usize = 10;
+const MAX_RECENT_EDITS: usize = 5;
+
+/// Capture what the user is looking at for the prompt management agent: the
+/// active editor's file, cursor and selections, diagnostics around the
+/// cursor, and recent edits made by the user or the agent.
+pub fn workspace_context(
+    workspace: &Entity<Workspace>,
+    action_log: Option<&Entity<ActionLog>>,
+    cx: &mut App,
+) -> ZedContext {
+    let project = workspace.read(cx).project().clone();
+    let project_info = project
+        .read(cx)
+        .visible_worktrees(cx)
+        .next()
+        .map(|worktree| {
+            let worktree = worktree.read(cx);
+            ProjectInfo {
+                name: worktree.root_name_str().to_string(),
+                root_path: worktree.abs_path().to_path_buf(),
+                language: None,
+            }
+        });
+
+    let mut context = ZedContext::from_zed_workspace(None, None, project_info);
+    let active_buffer = active_editor_context(workspace, &mut context, cx);
+
+    let mut recent_edits = active_buffer
+        .map(|buffer| user_edits(&buffer, cx))
+        .unwrap_or_default();
+    if let Some(action_log) = action_log {
+        recent_edits.extend(agent_edits(action_log, cx));
+    }
+    recent_edits.sort_by_key(|edit| std::cmp::Reverse(edit.timestamp));
+    recent_edits.truncate(MAX_RECENT_EDITS);
+    context.recent_edits = recent_edits;
+
+    if let Some(project_info) = &mut context.project_info {
+        project_info.language = context
+            .active_file
+            .as_ref()
+            .and_then(|file| file.language.clone());
+    }
+    context
+}
+
+/// Fill in the active file, selections and diagnostics, returning the buffer
+/// under the cursor
+fn active_editor_context(
+    workspace: &Entity<Workspace>,
+    context: &mut ZedContext,
+    cx: &mut App,
+) -> Option<Entity<Buffer>> {
+    let editor = workspace
+        .read(cx)
+        .active_item(cx)
+        .and_then(|item| item.act_as::<Editor>(cx))?;
+
+    let (cursor_buffer, cursor, selections) = editor.update(cx, |editor, cx| {
+        let display_snapshot = editor.display_snapshot(cx);
+        let selections = editor.selections.all_adjusted(&display_snapshot);
+        let head = editor.selections.newest_anchor().head();
+
+        let multi_buffer = editor.buffer().read(cx);
+        let (cursor_buffer, cursor) = multi_buffer.text_anchor_for_position(head, cx)?;
+        let selections = selections
+            .into_iter()
+            .filter(|selection| !selection.is_empty())
+            .filter_map(|selection| {
+                let (start_buffer, start) =
+                    multi_buffer.text_anchor_for_position(selection.start, cx)?;
+                let (end_buffer, end) = multi_buffer.text_anchor_for_position(selection.end, cx)?;
+                (start_buffer == cursor_buffer && end_buffer == cursor_buffer).then_some(start..end)
+            })
+            .collect::<Vec<_>>();
+        Some((cursor_buffer, cursor, selections))
+    })?;
+
+    let snapshot = cursor_buffer.read(cx).snapshot();
+    let path = buffer_path(&snapshot);
+    let cursor = cursor.to_point(&snapshot);
+
+    let mut selections = selections.into_iter().map(|range| {
+        let range = range.start.to_point(&snapshot)..range.end.to_point(&snapshot);
+        selection_for_range(&snapshot, range)
+    });
+    context.selected_text = selections.next();
+    context.other_selections = selections.collect();
+
+    let excerpt_start = cursor.row.saturating_sub(EXCERPT_LINES);
+    let excerpt_end = (cursor.row + EXCERPT_LINES).min(snapshot.max_point().row);
+    let excerpt = selection_for_range(
+        &snapshot,
+        Point::new(excerpt_start, 0)..Point::new(excerpt_end, snapshot.line_len(excerpt_end)),
+    );
+
+    context.active_file = Some(ActiveFile {
+        path: path.clone(),
+        language: snapshot
+            .language()
+            .map(|language| language.name().to_string()),
+        cursor_line: Some(cursor.row as usize + 1),
+        excerpt: Some(excerpt),
+    });
+    context.diagnostics = diagnostics_near(&snapshot, &path, cursor);
+
+    Some(cursor_buffer)
+}
+
+fn buffer_path(snapshot: &BufferSnapshot) -> PathBuf {
+    snapshot
+        .file()
+        .map(|file| file.path().as_std_path().to_path_buf())
+        .unwrap_or_else(|| PathBuf::from("untitled"))
+}
+
+fn selection_for_range(snapshot: &BufferSnapshot, range: Range<Point>) -> Selection {
+    Selection {
+        content: snapshot.text_for_range(range.clone()).collect(),
+        start_line: range.start.row as usize + 1,
+        end_line: range.end.row as usize + 1,
+    }
+}
+
+/// Primary diagnostics within [`DIAGNOSTIC_LINES`] of the cursor, nearest first
+fn diagnostics_near(snapshot: &BufferSnapshot, path: &Path, cursor: Point) -> Vec<DiagnosticInfo> {
+    let start_row = cursor.row.saturating_sub(DIAGNOSTIC_LINES);
+    let end_row = (cursor.row + DIAGNOSTIC_LINES).min(snapshot.max_point().row);
+    let range = Point::new(start_row, 0)..Point::new(end_row, snapshot.line_len(end_row));
+
+    let mut diagnostics = snapshot
+        .diagnostics_in_range::<_, Point>(range, false)
+        .filter(|entry| entry.diagnostic.is_primary)
+        .filter_map(|entry| {
+            let severity = match entry.diagnostic.severity {
+                DiagnosticSeverity::ERROR => prompt_management_agent::DiagnosticSeverity::Error,
+                DiagnosticSeverity::WARNING => prompt_management_agent::DiagnosticSeverity::Warning,
+                DiagnosticSeverity::INFORMATION => {
+                    prompt_management_agent::DiagnosticSeverity::Info
+                }
+                _ => return None,
+            };
+            Some(DiagnosticInfo {
+                path: path.to_path_buf(),
+                line: entry.range.start.row as usize + 1,
+                severity,
+                message: entry.diagnostic.message.clone(),
+            })
+        })
+        .collect::<Vec<_>>();
+    diagnostics.sort_by_key(|diagnostic| diagnostic.line.abs_diff(cursor.row as usize + 1));
+    diagnostics.dedup_by(|a, b| a.line == b.line && a.message == b.message);
+    diagnostics.truncate(MAX_DIAGNOSTICS);
+    diagnostics
+}
+
+/// The latest undoable transactions in the buffer
+fn user_edits(buffer: &Entity<Buffer>, cx: &App) -> Vec<RecentEdit> {
+    let buffer = buffer.read(cx);
+    let path = buffer_path(&buffer.snapshot());
+    let now = std::time::Instant::now();
+    let unix_now = chrono::Utc::now().timestamp();
+
+    buffer
+        .undo_history()
+        .filter_map(|entry| {
+            let rows = buffer
+                .edited_ranges_for_transaction_id::<Point>(entry.transaction_id())
+                .fold(None, |rows: Option<Range<u32>>, range| {
+                    Some(match rows {
+                        Some(rows) => rows.start.min(range.start.row)..rows.end.max(range.end.row),
+                        None => range.start.row..range.end.row,
+                    })
+                })?;
+            let description = if rows.start == rows.end {
+                format!("Edited line {}", rows.start + 1)
+            } else {
+                format!("Edited lines {}-{}", rows.start + 1, rows.end + 1)
+            };
+            let age = now.saturating_duration_since(entry.last_edit_at());
+            Some(RecentEdit {
+                file: path.clone(),
+                description,
+                timestamp: unix_now - age.as_secs() as i64,
+            })
+        })
+        .take(MAX_RECENT_EDITS)
+        .collect()
+}
+
+/// Buffers the agent changed that the user hasn't reviewed yet
+fn agent_edits(action_log: &Entity<ActionLog>, cx: &App) -> Vec<RecentEdit> {
+    let unix_now = chrono::Utc::now().timestamp();
+    action_log
+        .read(cx)
+        .changed_buffers(cx)
+        .into_keys()
+        .map(|buffer| RecentEdit {
+            file: buffer_path(&buffer.read(cx).snapshot()),
+            description: "Changed by the agent, not yet reviewed".to_string(),
+            timestamp: unix_now,
+        })
+        .collect()
+}
@@ -1,3 +1,4 @@
+use std::collections::BTreeSet;
 use std::io;
 use std::str::FromStr;
 use std::time::Duration;
@@ -717,6 +718,94 @@ pub struct CacheControl {
     pub cache_type: CacheControlType,
 }
 
+/// The most `cache_control` breakpoints a single request may contain.
+pub const MAX_CACHE_BREAKPOINTS: usize = 4;
+
+/// Where to place a `cache_control` breakpoint in a [`Request`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CacheBreakpoint {
+    /// The end of the system prompt, which also caches the tool definitions.
+    System,
+    /// A content block of a message. Blocks that can't be cached fall back to
+    /// the closest earlier block in the same message.
+    Content { message: usize, block: usize },
+}
+
+/// How likely a run of prompt content is to be sent again unchanged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheSegmentKind {
+    /// Tool definitions and system prompt, identical on every turn.
+    Static,
+    /// The end of an earlier turn, whose prefix that turn's request already
+    /// cached.
+    TurnEnd,
+    Content,
+}
+
+/// A run of prompt content that a cache breakpoint can follow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheSegment {
+    pub tokens: usize,
+    pub kind: CacheSegmentKind,
+}
+
+/// Chooses the segments to place cache breakpoints after, in prompt order.
+///
+/// The last segment is always chosen, so the next request can reuse the whole
+/// prompt. The end of the static prefix is next, as every turn shares it,
+/// followed by the ends of earlier turns, latest first, which are the prefixes
+/// earlier requests wrote to the cache. Any breakpoints left go to the latest
+/// remaining segments. Prefixes shorter than `min_tokens`, and breakpoints
+/// closer than `min_tokens` to one already chosen, are skipped.
+pub fn plan_cache_breakpoints(
+    segments: &[CacheSegment],
+    max_breakpoints: usize,
+    min_tokens: usize,
+) -> Vec<usize> {
+    let max_breakpoints = max_breakpoints.min(MAX_CACHE_BREAKPOINTS);
+    let min_tokens = min_tokens.max(1);
+    let Some(last) = segments.len().checked_sub(1) else {
+        return Vec::new();
+    };
+    if max_breakpoints == 0 {
+        return Vec::new();
+    }
+    let prefix_tokens = segments
+        .iter()
+        .scan(0, |total, segment| {
+            *total += segment.tokens;
+            Some(*total)
+        })
+        .collect::<Vec<_>>();
+
+    let static_end = segments
+        .iter()
+        .rposition(|segment| segment.kind == CacheSegmentKind::Static);
+    let turn_ends = (0..last)
+        .rev()
+        .filter(|&ix| segments[ix].kind == CacheSegmentKind::TurnEnd);
+    let candidates = static_end
+        .into_iter()
+        .chain(turn_ends)
+        .chain((0..last).rev());
+
+    let mut breakpoints = BTreeSet::from([last]);
+    for ix in candidates {
+        if breakpoints.len() >= max_breakpoints {
+            break;
+        }
+        let tokens = prefix_tokens[ix];
+        if tokens >= min_tokens
+            && breakpoints
+                .iter()
+                .all(|&other: &usize| prefix_tokens[other].abs_diff(tokens) >= min_tokens)
+        {
+            breakpoints.insert(ix);
+        }
+    }
+    breakpoints.into_iter().collect()
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Message {
     pub role: Role,
@@ -772,6 +861,19 @@ pub enum RequestContent {
     },
 }
 
+impl RequestContent {
+    fn cache_control_mut(&mut self) -> Option<&mut Option<CacheControl>> {
+        match self {
+            Self::Text { cache_control, .. }
+            | Self::Thinking { cache_control, .. }
+            | Self::Image { cache_control, .. }
+            | Self::ToolUse { cache_control, .. }
+            | Self::ToolResult { cache_control, .. } => Some(cache_control),
+            Self::RedactedThinking { .. } => None,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum ToolResultContent {
@@ -865,6 +967,85 @@ pub struct Request {
     pub top_p: Option<f32>,
 }
 
+impl Request {
+    /// Replaces the request's `cache_control` markers with the given
+    /// breakpoints. Only the last [`MAX_CACHE_BREAKPOINTS`] are kept, as they
+    /// cache the longest prefixes. Returns the number of markers placed.
+    pub fn set_cache_breakpoints(&mut self, breakpoints: &[CacheBreakpoint]) -> usize {
+        if let Some(StringOrContents::Content(contents)) = &mut self.system {
+            for content in contents.iter_mut() {
+                if let Some(cache_control) = content.cache_control_mut() {
+                    *cache_control = None;
+                }
+            }
+        }
+        for content in self
+            .messages
+            .iter_mut()
+            .flat_map(|message| message.content.iter_mut())
+        {
+            if let Some(cache_control) = content.cache_control_mut() {
+                *cache_control = None;
+            }
+        }
+
+        let mut breakpoints = breakpoints.to_vec();
+        breakpoints.sort();
+        breakpoints.dedup();
+        if breakpoints.len() > MAX_CACHE_BREAKPOINTS {
+            breakpoints.drain(..breakpoints.len() - MAX_CACHE_BREAKPOINTS);
+        }
+
+        let ephemeral = CacheControl {
+            cache_type: CacheControlType::Ephemeral,
+        };
+        let mut placed = 0;
+        for breakpoint in breakpoints {
+            let cache_control = match breakpoint {
+                CacheBreakpoint::System => match self.system.take() {
+                    Some(StringOrContents::String(text)) => {
+                        self.system = Some(StringOrContents::Content(vec![RequestContent::Text {
+                            text,
+                            cache_control: None,
+                        }]));
+                        self.system_cache_control_mut()
+                    }
+                    system => {
+                        self.system = system;
+                        self.system_cache_control_mut()
+                    }
+                },
+                CacheBreakpoint::Content { message, block } => {
+                    self.messages.get_mut(message).and_then(|message| {
+                        let end = (block + 1).min(message.content.len());
+                        message.content[..end]
+                            .iter_mut()
+                            .rev()
+                            .find_map(RequestContent::cache_control_mut)
+                    })
+                }
+            };
+            if let Some(cache_control) = cache_control
+                && cache_control.is_none()
+            {
+                *cache_control = Some(ephemeral);
+                placed += 1;
+            }
+        }
+        placed
+    }
+
+    fn system_cache_control_mut(&mut self) -> Option<&mut Option<CacheControl>> {
+        match &mut self.system {
+            Some(StringOrContents::Content(contents)) => contents
+                .iter_mut()
+                .rev()
+                .find_map(RequestContent::cache_control_mut),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct StreamingRequest {
     #[serde(flatten)]
@@ -1070,3 +1251,168 @@ fn test_match_window_exceeded() {
     };
     assert_eq!(error.match_window_exceeded(), None);
 }
+
+#[test]
+fn test_cache_breakpoints() {
+    let text = |text: &str| RequestContent::Text {
+        text: text.to_string(),
+        cache_control: None,
+    };
+    let mut request = Request {
+        model: "claude-sonnet-4-5".to_string(),
+        max_tokens: 1024,
+        messages: (0..5)
+            .map(|ix| Message {
+                role: if ix % 2 == 0 {
+                    Role::User
+                } else {
+                    Role::Assistant
+                },
+                content: vec![
+                    text("question"),
+                    RequestContent::RedactedThinking {
+                        data: "redacted".to_string(),
+                    },
+                ],
+            })
+            .collect(),
+        tools: Vec::new(),
+        thinking: None,
+        tool_choice: None,
+        system: Some(StringOrContents::String("You are helpful".to_string())),
+        metadata: None,
+        stop_sequences: Vec::new(),
+        temperature: None,
+        top_k: None,
+        top_p: None,
+    };
+    let cached = |request: &Request| {
+        let mut cached = Vec::new();
+        if let Some(StringOrContents::Content(contents)) = &request.system
+            && contents.iter().any(|content| {
+                matches!(
+                    content,
+                    RequestContent::Text {
+                        cache_control: Some(_),
+                        ..
+                    }
+                )
+            })
+        {
+            cached.push(CacheBreakpoint::System);
+        }
+        for (message_ix, message) in request.messages.iter().enumerate() {
+            for (block_ix, content) in message.content.iter().enumerate() {
+                if matches!(
+                    content,
+                    RequestContent::Text {
+                        cache_control: Some(_),
+                        ..
+                    }
+                ) {
+                    cached.push(CacheBreakpoint::Content {
+                        message: message_ix,
+                        block: block_ix,
+                    });
+                }
+            }
+        }
+        cached
+    };
+
+    // Redacted thinking can't be cached, so the preceding block is marked.
+    let placed = request.set_cache_breakpoints(&[
+        CacheBreakpoint::System,
+        CacheBreakpoint::Content {
+            message: 4,
+            block: 1,
+        },
+    ]);
+    assert_eq!(placed, 2);
+    assert_eq!(
+        cached(&request),
+        vec![
+            CacheBreakpoint::System,
+            CacheBreakpoint::Content {
+                message: 4,
+                block: 0
+            }
+        ]
+    );
+
+    // Over the limit, the earliest breakpoints are dropped and old markers are cleared.
+    let mut breakpoints = vec![CacheBreakpoint::System];
+    breakpoints.extend((0..5).map(|message| CacheBreakpoint::Content { message, block: 1 }));
+    assert_eq!(
+        request.set_cache_breakpoints(&breakpoints),
+        MAX_CACHE_BREAKPOINTS
+    );
+    assert_eq!(
+        cached(&request),
+        (1..5)
+            .map(|message| CacheBreakpoint::Content { message, block: 0 })
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn test_cache_breakpoints_stay_stable_across_turns() {
+    let segment = |tokens, kind| CacheSegment { tokens, kind };
+    let prefix_tokens = |segments: &[CacheSegment], breakpoints: &[usize]| {
+        breakpoints
+            .iter()
+            .map(|&ix| segments[..=ix].iter().map(|s| s.tokens).sum::<usize>())
+            .collect::<Vec<_>>()
+    };
+
+    let mut previous_prefixes = Vec::new();
+    for turns in 0..8 {
+        let mut segments = vec![segment(3000, CacheSegmentKind::Static)];
+        for _ in 0..turns {
+            segments.push(segment(1500, CacheSegmentKind::TurnEnd));
+            segments.push(segment(1200, CacheSegmentKind::Content));
+        }
+        segments.push(segment(1500, CacheSegmentKind::Content));
+
+        let breakpoints = plan_cache_breakpoints(&segments, MAX_CACHE_BREAKPOINTS, 1024);
+        assert!(breakpoints.len() <= MAX_CACHE_BREAKPOINTS);
+        // The system prompt and the full prompt are always cached.
+        assert_eq!(breakpoints.first(), Some(&0));
+        assert_eq!(breakpoints.last(), Some(&(segments.len() - 1)));
+
+        // The prefix the previous turn wrote is cached again.
+        let prefixes = prefix_tokens(&segments, &breakpoints);
+        if let Some(previous_end) = previous_prefixes.last() {
+            assert!(
+                prefixes.contains(previous_end),
+                "turn {turns}: {prefixes:?} doesn't reuse {previous_end}"
+            );
+        }
+        previous_prefixes = prefixes;
+    }
+}
+
+#[test]
+fn test_cache_breakpoints_respect_minimum_tokens() {
+    let segment = |tokens, kind| CacheSegment { tokens, kind };
+
+    // A short prompt is still cached at its end, but nowhere else.
+    let segments = vec![
+        segment(300, CacheSegmentKind::Static),
+        segment(300, CacheSegmentKind::Content),
+    ];
+    assert_eq!(plan_cache_breakpoints(&segments, 4, 1000), vec![1]);
+    assert!(plan_cache_breakpoints(&segments, 0, 1000).is_empty());
+
+    // A short system prompt isn't cached on its own, and the turn end too
+    // close to the last message is skipped.
+    let segments = vec![
+        segment(500, CacheSegmentKind::Static),
+        segment(2000, CacheSegmentKind::TurnEnd),
+        segment(800, CacheSegmentKind::Content),
+        segment(100, CacheSegmentKind::TurnEnd),
+        segment(100, CacheSegmentKind::Content),
+    ];
+    assert_eq!(plan_cache_breakpoints(&segments, 4, 1000), vec![1, 4]);
+    assert_eq!(plan_cache_breakpoints(&segments, 1, 1000), vec![4]);
+}
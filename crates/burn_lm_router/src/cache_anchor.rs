@@ -1,118 +1,131 @@
+use anthropic::{plan_cache_breakpoints, CacheSegment, CacheSegmentKind, MAX_CACHE_BREAKPOINTS};
 use anyhow::Result;
+use language_model::count_tokens;
 use pyo3_bridge::types::CacheAnchor;
 
+/// Shortest prefix Anthropic will cache on Sonnet and Opus models
+pub const MIN_CACHEABLE_TOKENS: usize = 1024;
+
+/// Breakpoint limits of the model the prompt is sent to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AnchorLimits {
+    pub max_anchors: usize,
+    /// Fewest tokens a cached prefix may have, and the fewest new tokens each
+    /// further breakpoint must add
+    pub min_tokens: usize,
+}
+
+impl Default for AnchorLimits {
+    fn default() -> Self {
+        Self {
+            max_anchors: MAX_CACHE_BREAKPOINTS,
+            min_tokens: MIN_CACHEABLE_TOKENS,
+        }
+    }
+}
+
 /// Analyzer for extracting optimal cache anchor positions
-pub struct CacheAnchorAnalyzer {}
+pub struct CacheAnchorAnalyzer {
+    limits: AnchorLimits,
+}
 
 impl CacheAnchorAnalyzer {
     pub fn new() -> Self {
-        Self {}
+        Self {
+            limits: AnchorLimits::default(),
+        }
+    }
+
+    pub fn with_limits(mut self, limits: AnchorLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Choose the segments to place cache breakpoints after, in prompt order
+    pub fn plan_breakpoints(&self, segments: &[CacheSegment]) -> Vec<usize> {
+        plan_cache_breakpoints(segments, self.limits.max_anchors, self.limits.min_tokens)
     }
 
     /// Extract cache anchors from a prompt
+    ///
+    /// The prompt is split into paragraphs, keeping fenced code blocks whole,
+    /// and each anchor covers the paragraphs since the previous one.
     pub async fn extract_anchors(&self, prompt: &str) -> Result<Vec<CacheAnchor>> {
         log::debug!("Extracting cache anchors from prompt");
 
-        let mut anchors = Vec::new();
-
-        // Strategy: Identify major sections of the prompt as anchors
-        // This optimizes for Anthropic's prompt caching feature
-
-        // 1. System-level context (if present)
-        if let Some(system_section) = Self::extract_section(prompt, "system", "user") {
-            anchors.push(CacheAnchor {
-                position: 0,
-                content: system_section.clone(),
-                priority: 1.0,
-                estimated_tokens: system_section.len() / 4,
-            });
-        }
-
-        // 2. Code context (if present)
-        if prompt.contains("```") {
-            if let Some(code_section) = Self::extract_code_blocks(prompt) {
-                anchors.push(CacheAnchor {
-                    position: anchors.len(),
-                    content: code_section.clone(),
-                    priority: 0.9,
-                    estimated_tokens: code_section.len() / 4,
-                });
-            }
-        }
-
-        // 3. File/project context
-        if prompt.contains("file") || prompt.contains("src/") {
-            if let Some(context_section) = Self::extract_file_context(prompt) {
-                anchors.push(CacheAnchor {
-                    position: anchors.len(),
-                    content: context_section.clone(),
-                    priority: 0.8,
-                    estimated_tokens: context_section.len() / 4,
-                });
-            }
-        }
-
-        // Limit to 4 anchors (Anthropic's current limit)
-        anchors.truncate(4);
+        let blocks = split_blocks(prompt);
+        let segments = blocks
+            .iter()
+            .map(|block| CacheSegment {
+                tokens: count_tokens(block),
+                kind: CacheSegmentKind::Content,
+            })
+            .collect::<Vec<_>>();
+        let breakpoints = self.plan_breakpoints(&segments);
+        let anchors = describe_anchors(&segments, &breakpoints, |range| blocks[range].concat());
 
         log::info!("Extracted {} cache anchors", anchors.len());
         Ok(anchors)
     }
+}
 
-    fn extract_section(text: &str, start_marker: &str, end_marker: &str) -> Option<String> {
-        if let Some(start) = text.find(start_marker) {
-            if let Some(end) = text[start..].find(end_marker) {
-                return Some(text[start..start + end].to_string());
-            }
-        }
-        None
+impl Default for CacheAnchorAnalyzer {
+    fn default() -> Self {
+        Self::new()
     }
+}
 
-    fn extract_code_blocks(text: &str) -> Option<String> {
-        let mut code_blocks = Vec::new();
-        let mut in_code = false;
-        let mut current_block = String::new();
-
-        for line in text.lines() {
-            if line.starts_with("```") {
-                if in_code {
-                    code_blocks.push(current_block.clone());
-                    current_block.clear();
-                }
-                in_code = !in_code;
-            } else if in_code {
-                current_block.push_str(line);
-                current_block.push('\n');
+/// One anchor per breakpoint, covering the segments since the previous one
+fn describe_anchors(
+    segments: &[CacheSegment],
+    breakpoints: &[usize],
+    content: impl Fn(std::ops::Range<usize>) -> String,
+) -> Vec<CacheAnchor> {
+    let total_tokens = segments.iter().map(|segment| segment.tokens).sum::<usize>();
+    let mut start = 0;
+    breakpoints
+        .iter()
+        .map(|&breakpoint| {
+            let range = start..breakpoint + 1;
+            start = breakpoint + 1;
+            let prefix_tokens = segments[..range.end]
+                .iter()
+                .map(|segment| segment.tokens)
+                .sum::<usize>();
+            CacheAnchor {
+                position: breakpoint,
+                estimated_tokens: segments[range.clone()]
+                    .iter()
+                    .map(|segment| segment.tokens)
+                    .sum(),
+                content: content(range),
+                priority: prefix_tokens as f32 / total_tokens.max(1) as f32,
             }
-        }
-
-        if code_blocks.is_empty() {
-            None
-        } else {
-            Some(code_blocks.join("\n"))
-        }
-    }
+        })
+        .collect()
+}
 
-    fn extract_file_context(text: &str) -> Option<String> {
-        // Extract sentences containing file references
-        let sentences: Vec<&str> = text.split('.').collect();
-        let file_context: Vec<&str> = sentences
-            .into_iter()
-            .filter(|s| s.contains("file") || s.contains("src/") || s.contains(".rs"))
-            .collect();
-
-        if file_context.is_empty() {
-            None
-        } else {
-            Some(file_context.join(". "))
+/// Split text into paragraphs that together cover all of it, keeping fenced
+/// code blocks in one piece
+pub(crate) fn split_blocks(text: &str) -> Vec<&str> {
+    let mut blocks = Vec::new();
+    let mut start = 0;
+    let mut offset = 0;
+    let mut in_code = false;
+    for line in text.split_inclusive('\n') {
+        offset += line.len();
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") {
+            in_code = !in_code;
+        } else if trimmed.is_empty() && !in_code && offset > start + line.len() {
+            blocks.push(&text[start..offset]);
+            start = offset;
         }
     }
-}
-
-impl Default for CacheAnchorAnalyzer {
-    fn default() -> Self {
-        Self::new()
+    if start < text.len() {
+        blocks.push(&text[start..]);
     }
+    blocks
 }
 
 #[cfg(test)]
@@ -121,10 +134,32 @@ mod tests {
 
     #[tokio::test]
     async fn test_anchor_extraction() {
-        let analyzer = CacheAnchorAnalyzer::new();
-        let prompt = "In file src/main.rs:\n\n```rust\nfn main() {}\n```\n\nFix the bug";
-
-        let anchors = analyzer.extract_anchors(prompt).await.unwrap();
+        let analyzer = CacheAnchorAnalyzer::new().with_limits(AnchorLimits {
+            max_anchors: 4,
+            min_tokens: 20,
+        });
+        let code = "fn main() {\n\n    println!(\"hello world\");\n}\n".repeat(4);
+        let prompt = format!(
+            "In file src/main.rs:\n\n```rust\n{code}```\n\nFix the bug in the printing code please"
+        );
+
+        let anchors = analyzer.extract_anchors(&prompt).await.unwrap();
         assert!(!anchors.is_empty());
+        assert!(anchors.len() <= MAX_CACHE_BREAKPOINTS);
+        // Anchors cover the prompt in order, without splitting the code block
+        let covered = anchors
+            .iter()
+            .map(|anchor| anchor.content.as_str())
+            .collect::<String>();
+        assert!(prompt.starts_with(&covered));
+        assert_eq!(anchors.last().unwrap().priority, 1.0);
+        assert!(anchors
+            .iter()
+            .all(|anchor| anchor.content.matches("```").count() % 2 == 0));
+
+        // A short prompt is still cached as a whole
+        let short = analyzer.extract_anchors("Fix the bug").await.unwrap();
+        assert_eq!(short.len(), 1);
+        assert_eq!(short[0].content, "Fix the bug");
     }
 }
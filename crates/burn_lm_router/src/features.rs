@@ -0,0 +1,211 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashSet, VecDeque};
+use std::hash::{Hash, Hasher};
+
+/// Languages with their own input to the routing network; anything else
+/// detected falls into a shared "other" slot
+pub const LANGUAGES: &[&str] = &[
+    "rust",
+    "python",
+    "javascript",
+    "typescript",
+    "go",
+    "c",
+    "cpp",
+    "java",
+];
+
+/// Length, code-block ratio and similarity, then one slot per language plus "other"
+pub const FEATURE_COUNT: usize = 3 + LANGUAGES.len() + 1;
+
+/// Prompts longer than this many words all look equally long to the network
+const LENGTH_SATURATION_WORDS: f32 = 4096.0;
+
+/// Properties of a prompt that the routing network bases its decisions on
+#[derive(Debug, Clone, PartialEq)]
+pub struct PromptFeatures {
+    pub word_count: usize,
+    /// Fraction of lines inside fenced code blocks
+    pub code_block_ratio: f32,
+    /// Highest word-overlap similarity to a recently routed prompt
+    pub recent_similarity: f32,
+    pub language: Option<String>,
+}
+
+impl PromptFeatures {
+    pub fn extract(prompt: &str, recent_prompts: &RecentPrompts) -> Self {
+        Self {
+            word_count: prompt.split_whitespace().count(),
+            code_block_ratio: code_block_ratio(prompt),
+            recent_similarity: recent_prompts.similarity(prompt),
+            language: detect_language(prompt),
+        }
+    }
+
+    /// The network input, every value in `0.0..=1.0`
+    pub fn to_vector(&self) -> Vec<f32> {
+        let mut vector = vec![0.0; FEATURE_COUNT];
+        vector[0] = ((self.word_count as f32).ln_1p() / LENGTH_SATURATION_WORDS.ln_1p()).min(1.0);
+        vector[1] = self.code_block_ratio.clamp(0.0, 1.0);
+        vector[2] = self.recent_similarity.clamp(0.0, 1.0);
+        if let Some(language) = &self.language {
+            let slot = LANGUAGES
+                .iter()
+                .position(|known| known == language)
+                .unwrap_or(LANGUAGES.len());
+            vector[3 + slot] = 1.0;
+        }
+        vector
+    }
+}
+
+/// Word sets of the most recently routed prompts
+pub struct RecentPrompts {
+    capacity: usize,
+    prompts: VecDeque<HashSet<u64>>,
+}
+
+impl RecentPrompts {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            prompts: VecDeque::new(),
+        }
+    }
+
+    /// Highest Jaccard similarity between `prompt` and a recent prompt
+    pub fn similarity(&self, prompt: &str) -> f32 {
+        let words = word_set(prompt);
+        if words.is_empty() {
+            return 0.0;
+        }
+        self.prompts
+            .iter()
+            .map(|recent| {
+                let shared = words.intersection(recent).count();
+                let total = words.len() + recent.len() - shared;
+                shared as f32 / total as f32
+            })
+            .fold(0.0, f32::max)
+    }
+
+    pub fn push(&mut self, prompt: &str) {
+        if self.prompts.len() == self.capacity {
+            self.prompts.pop_front();
+        }
+        self.prompts.push_back(word_set(prompt));
+    }
+}
+
+impl Default for RecentPrompts {
+    fn default() -> Self {
+        Self::new(32)
+    }
+}
+
+fn word_set(text: &str) -> HashSet<u64> {
+    text.split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut hasher = DefaultHasher::new();
+            word.to_lowercase().hash(&mut hasher);
+            hasher.finish()
+        })
+        .collect()
+}
+
+fn code_block_ratio(prompt: &str) -> f32 {
+    let mut in_code = false;
+    let mut total_lines = 0;
+    let mut code_lines = 0;
+    for line in prompt.lines() {
+        total_lines += 1;
+        if line.trim_start().starts_with("```") {
+            in_code = !in_code;
+            code_lines += 1;
+        } else if in_code {
+            code_lines += 1;
+        }
+    }
+    if total_lines == 0 {
+        0.0
+    } else {
+        code_lines as f32 / total_lines as f32
+    }
+}
+
+/// The language of the first tagged code fence, or of the first file
+/// extension mentioned
+fn detect_language(prompt: &str) -> Option<String> {
+    let fence_language = prompt.lines().find_map(|line| {
+        let tag = line.trim_start().strip_prefix("```")?.trim();
+        let tag = tag.split_whitespace().next()?;
+        Some(normalize_language(tag))
+    });
+    fence_language.or_else(|| {
+        prompt.split_whitespace().find_map(|word| {
+            let word = word.trim_matches(|c: char| !c.is_alphanumeric());
+            let (_, extension) = word.rsplit_once('.')?;
+            language_for_extension(extension)
+        })
+    })
+}
+
+fn normalize_language(tag: &str) -> String {
+    let tag = tag.to_lowercase();
+    match tag.as_str() {
+        "rs" => "rust".to_string(),
+        "py" => "python".to_string(),
+        "js" | "jsx" => "javascript".to_string(),
+        "ts" | "tsx" => "typescript".to_string(),
+        "golang" => "go".to_string(),
+        "c++" | "cc" | "cxx" | "hpp" => "cpp".to_string(),
+        _ => tag,
+    }
+}
+
+fn language_for_extension(extension: &str) -> Option<String> {
+    let language = match extension {
+        "rs" => "rust",
+        "py" => "python",
+        "js" | "jsx" | "mjs" => "javascript",
+        "ts" | "tsx" => "typescript",
+        "go" => "go",
+        "c" | "h" => "c",
+        "cc" | "cpp" | "cxx" | "hpp" => "cpp",
+        "java" => "java",
+        _ => return None,
+    };
+    Some(language.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_feature_extraction() {
+        let mut recent = RecentPrompts::new(2);
+        let prompt = "Fix the parser in src/parser.rs:\n```rust\nfn parse() {}\n```";
+
+        let features = PromptFeatures::extract(prompt, &recent);
+        assert_eq!(features.word_count, 10);
+        assert_eq!(features.code_block_ratio, 0.75);
+        assert_eq!(features.recent_similarity, 0.0);
+        assert_eq!(features.language.as_deref(), Some("rust"));
+
+        recent.push(prompt);
+        let features = PromptFeatures::extract(prompt, &recent);
+        assert_eq!(features.recent_similarity, 1.0);
+
+        let vector = features.to_vector();
+        assert_eq!(vector.len(), FEATURE_COUNT);
+        assert!(vector.iter().all(|value| (0.0..=1.0).contains(value)));
+        assert_eq!(vector[3], 1.0);
+
+        // File extensions identify the language when there is no code fence
+        let features = PromptFeatures::extract("why does main.py crash?", &recent);
+        assert_eq!(features.language.as_deref(), Some("python"));
+        assert_eq!(features.code_block_ratio, 0.0);
+    }
+}
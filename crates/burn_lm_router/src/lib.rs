@@ -1,20 +1,29 @@
 pub mod cache_anchor;
+pub mod features;
 pub mod network;
 pub mod routing;
 pub mod token_optimizer;
+pub mod training;
 
 use anyhow::Result;
+use std::path::Path;
+use tokio::sync::Mutex;
 
-pub use cache_anchor::CacheAnchorAnalyzer;
+pub use cache_anchor::{AnchorLimits, CacheAnchorAnalyzer};
+pub use features::{PromptFeatures, RecentPrompts};
+pub use network::{ModelTier, RoutingPrediction, RoutingWeights};
 pub use routing::{ApiKeyConfig, RoutingDecision};
-pub use token_optimizer::TokenOptimizer;
+pub use token_optimizer::{OptimizedPrompt, TokenOptimizer, Transformation, TransformationReport};
 
 /// Burn-LM Router for intelligent request routing
-/// Currently uses heuristic-based routing (Phase 2a)
-/// Will integrate neural networks in Phase 2c with burn-neural-networks feature
+/// Uses a trained routing network when weights are available, and falls back
+/// to heuristic routing otherwise. Weights are trained offline from telemetry
+/// with the burn-neural-networks feature.
 pub struct BurnLmRouter {
     cache_anchor_analyzer: CacheAnchorAnalyzer,
     token_optimizer: TokenOptimizer,
+    recent_prompts: Mutex<RecentPrompts>,
+    weights: Option<RoutingWeights>,
 }
 
 impl BurnLmRouter {
@@ -25,30 +34,86 @@ impl BurnLmRouter {
         Self {
             cache_anchor_analyzer: CacheAnchorAnalyzer::new(),
             token_optimizer: TokenOptimizer::new(),
+            recent_prompts: Mutex::new(RecentPrompts::default()),
+            weights: None,
         }
     }
 
+    /// Route with a trained routing network instead of heuristics
+    pub fn with_weights(mut self, weights: RoutingWeights) -> Self {
+        log::info!(
+            "Using routing network trained on {} examples",
+            weights.trained_examples
+        );
+        self.weights = Some(weights);
+        self
+    }
+
+    /// Load routing network weights, keeping the heuristics if the file is
+    /// missing or unusable
+    pub fn load_weights(self, path: &Path) -> Self {
+        if !path.exists() {
+            return self;
+        }
+        match RoutingWeights::load(path) {
+            Ok(weights) => self.with_weights(weights),
+            Err(error) => {
+                log::warn!("Ignoring routing weights: {:#}", error);
+                self
+            }
+        }
+    }
+
+    pub fn has_weights(&self) -> bool {
+        self.weights.is_some()
+    }
+
     /// Route a refined prompt through optimal path
-    pub async fn route_prompt(&self, refined_prompt: &str) -> Result<RoutingDecision> {
+    ///
+    /// `history` is the earlier messages of the conversation, as they were
+    /// written, which are sent ahead of the prompt. Files and blocks they
+    /// already contain are elided from the prompt.
+    pub async fn route_prompt(
+        &self,
+        refined_prompt: &str,
+        history: &[&str],
+    ) -> Result<RoutingDecision> {
         log::debug!("Routing prompt: {} chars", refined_prompt.len());
 
+        // Compress the prompt first so anchors and features match what is sent
+        let optimized_prompt = self
+            .token_optimizer
+            .compress_with_history(refined_prompt, history);
+        let token_optimization = optimized_prompt.token_optimization();
+        let prompt = optimized_prompt.prompt.as_str();
+
         // Extract optimal cache anchors
-        let cache_anchors = self
-            .cache_anchor_analyzer
-            .extract_anchors(refined_prompt)
-            .await?;
+        let cache_anchors = self.cache_anchor_analyzer.extract_anchors(prompt).await?;
 
-        // Optimize token usage
-        let token_optimization = self.token_optimizer.optimize(refined_prompt).await?;
+        let features = {
+            let mut recent_prompts = self.recent_prompts.lock().await;
+            let features = PromptFeatures::extract(prompt, &recent_prompts);
+            recent_prompts.push(prompt);
+            features.to_vector()
+        };
 
         // Determine if we should use cache
-        let use_cache = self.should_use_cache(refined_prompt);
+        let (use_cache, model_tier) = match &self.weights {
+            Some(weights) => {
+                let prediction = weights.predict(&features);
+                (prediction.cache_probability >= 0.5, Some(prediction.tier))
+            }
+            None => (self.should_use_cache(prompt), None),
+        };
 
         Ok(RoutingDecision {
             use_cache,
             cache_anchors,
             api_key_selection: ApiKeyConfig::UseDefault,
             token_optimization,
+            optimized_prompt,
+            features,
+            model_tier,
         })
     }
 
@@ -71,7 +136,41 @@ mod tests {
     #[tokio::test]
     async fn test_router_creation() {
         let router = BurnLmRouter::new();
-        let decision = router.route_prompt("Test prompt for routing").await;
+        let decision = router.route_prompt("Test prompt for routing", &[]).await;
         assert!(decision.is_ok());
     }
+
+    #[tokio::test]
+    async fn test_router_falls_back_without_weights() {
+        let path = std::env::temp_dir().join("missing-routing-weights.json");
+        let router = BurnLmRouter::new().load_weights(&path);
+        assert!(!router.has_weights());
+
+        let prompt = "Explain how the borrow checker handles two-phase borrows";
+        let decision = router.route_prompt(prompt, &[]).await.unwrap();
+        assert!(decision.use_cache);
+        assert_eq!(decision.model_tier, None);
+        assert_eq!(decision.features.len(), features::FEATURE_COUNT);
+
+        // The second time around the prompt matches a recent one
+        let decision = router.route_prompt(prompt, &[]).await.unwrap();
+        assert_eq!(decision.features[2], 1.0);
+    }
+
+    #[tokio::test]
+    async fn test_router_elides_files_sent_earlier_in_the_conversation() {
+        let router = BurnLmRouter::new();
+        let file = "```rs /project/src/lib.rs\npub fn parse() {}\n```\n";
+        let earlier = format!("{file}What does parse do?");
+
+        let decision = router
+            .route_prompt(&format!("{file}Is it pure?"), &[&earlier, "It parses."])
+            .await
+            .unwrap();
+        assert!(decision
+            .optimized_prompt
+            .applied(Transformation::ElideUnchangedFiles));
+        assert!(decision.token_optimization.remove_redundancy);
+        assert!(!decision.optimized_prompt.prompt.contains("pub fn parse"));
+    }
 }
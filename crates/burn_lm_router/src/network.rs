@@ -1,26 +1,289 @@
-// Placeholder for Burn neural network implementation
-// This will be expanded in future phases with actual trained models
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
 
 #[cfg(feature = "burn-neural-networks")]
-use burn::prelude::*;
+use burn::{
+    nn::{Linear, LinearConfig, Relu},
+    prelude::*,
+};
 
-/// Neural network for routing decisions
-/// Currently a placeholder - will be implemented with trained model in Phase 2c
+use crate::features::FEATURE_COUNT;
+
+/// Version of the weights file format, bumped whenever the features or
+/// layer shapes change so stale weights are ignored instead of misread
+pub const WEIGHTS_FORMAT_VERSION: u32 = 1;
+
+/// Width of the shared hidden layer
+pub const HIDDEN_SIZE: usize = 16;
+
+/// How capable a model a prompt needs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelTier {
+    Small,
+    Medium,
+    Large,
+}
+
+impl ModelTier {
+    pub const ALL: [ModelTier; 3] = [ModelTier::Small, ModelTier::Medium, ModelTier::Large];
+
+    pub fn index(self) -> usize {
+        match self {
+            ModelTier::Small => 0,
+            ModelTier::Medium => 1,
+            ModelTier::Large => 2,
+        }
+    }
+
+    /// Guess the tier of a model from its name, as logged in telemetry
+    pub fn from_model_name(name: &str) -> Option<Self> {
+        let name = name.to_lowercase();
+        if ["haiku", "mini", "nano", "flash", "small", "lite"]
+            .iter()
+            .any(|marker| name.contains(marker))
+        {
+            Some(ModelTier::Small)
+        } else if ["opus", "large", "ultra", "-pro", "o1", "o3"]
+            .iter()
+            .any(|marker| name.contains(marker))
+        {
+            Some(ModelTier::Large)
+        } else if ["sonnet", "medium", "gpt-4", "gemini", "mistral", "llama"]
+            .iter()
+            .any(|marker| name.contains(marker))
+        {
+            Some(ModelTier::Medium)
+        } else {
+            None
+        }
+    }
+}
+
+/// Output of the routing network for one prompt
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoutingPrediction {
+    /// Probability that the semantic cache will answer the prompt
+    pub cache_probability: f32,
+    pub tier: ModelTier,
+    pub tier_probabilities: [f32; 3],
+}
+
+/// A fully connected layer, weights stored row-major as `[outputs][inputs]`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DenseLayer {
+    pub inputs: usize,
+    pub outputs: usize,
+    pub weights: Vec<f32>,
+    pub bias: Vec<f32>,
+}
+
+impl DenseLayer {
+    fn forward(&self, input: &[f32]) -> Vec<f32> {
+        self.weights
+            .chunks(self.inputs)
+            .zip(&self.bias)
+            .map(|(row, bias)| bias + row.iter().zip(input).map(|(w, x)| w * x).sum::<f32>())
+            .collect()
+    }
+
+    fn validate(&self, name: &str, inputs: usize, outputs: usize) -> Result<()> {
+        if self.inputs != inputs
+            || self.outputs != outputs
+            || self.weights.len() != inputs * outputs
+            || self.bias.len() != outputs
+        {
+            bail!(
+                "Layer {} has shape {}x{} with {} weights and {} biases, expected {}x{}",
+                name,
+                self.inputs,
+                self.outputs,
+                self.weights.len(),
+                self.bias.len(),
+                inputs,
+                outputs
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Trained routing network weights, stored as JSON
+///
+/// A shared ReLU hidden layer feeds two heads: a single logit for cache
+/// usefulness and one logit per [`ModelTier`]. Prediction runs in plain Rust
+/// so trained weights work without the `burn-neural-networks` feature.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RoutingWeights {
+    pub version: u32,
+    pub hidden: DenseLayer,
+    pub cache_head: DenseLayer,
+    pub tier_head: DenseLayer,
+    /// Number of telemetry examples the weights were trained on
+    #[serde(default)]
+    pub trained_examples: usize,
+}
+
+impl RoutingWeights {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read routing weights from {:?}", path))?;
+        let weights: Self = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse routing weights in {:?}", path))?;
+        weights.validate()?;
+        Ok(weights)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let temp_path = path.with_extension("json.tmp");
+        std::fs::write(&temp_path, serde_json::to_string(self)?)?;
+        std::fs::rename(&temp_path, path)
+            .with_context(|| format!("Failed to write routing weights to {:?}", path))
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.version != WEIGHTS_FORMAT_VERSION {
+            bail!(
+                "Routing weights have format version {}, expected {}",
+                self.version,
+                WEIGHTS_FORMAT_VERSION
+            );
+        }
+        self.hidden.validate("hidden", FEATURE_COUNT, HIDDEN_SIZE)?;
+        self.cache_head.validate("cache_head", HIDDEN_SIZE, 1)?;
+        self.tier_head
+            .validate("tier_head", HIDDEN_SIZE, ModelTier::ALL.len())
+    }
+
+    pub fn predict(&self, features: &[f32]) -> RoutingPrediction {
+        let hidden: Vec<f32> = self
+            .hidden
+            .forward(features)
+            .into_iter()
+            .map(|value| value.max(0.0))
+            .collect();
+
+        let cache_logit = self.cache_head.forward(&hidden)[0];
+        let cache_probability = 1.0 / (1.0 + (-cache_logit).exp());
+
+        let tier_logits = self.tier_head.forward(&hidden);
+        let max_logit = tier_logits
+            .iter()
+            .copied()
+            .fold(f32::NEG_INFINITY, f32::max);
+        let exps: Vec<f32> = tier_logits
+            .iter()
+            .map(|logit| (logit - max_logit).exp())
+            .collect();
+        let total: f32 = exps.iter().sum();
+        let tier_probabilities = [exps[0] / total, exps[1] / total, exps[2] / total];
+        let tier = ModelTier::ALL
+            .into_iter()
+            .max_by(|a, b| tier_probabilities[a.index()].total_cmp(&tier_probabilities[b.index()]))
+            .unwrap_or(ModelTier::Medium);
+
+        RoutingPrediction {
+            cache_probability,
+            tier,
+            tier_probabilities,
+        }
+    }
+}
+
+/// Multi-layer perceptron predicting cache usefulness and model tier
 #[cfg(feature = "burn-neural-networks")]
 #[derive(Module, Debug)]
 pub struct RoutingNetwork<B: Backend> {
-    _phantom: std::marker::PhantomData<B>,
+    hidden: Linear<B>,
+    cache_head: Linear<B>,
+    tier_head: Linear<B>,
+    activation: Relu,
 }
 
 #[cfg(feature = "burn-neural-networks")]
 impl<B: Backend> RoutingNetwork<B> {
-    pub fn new(_device: &B::Device) -> Self {
+    pub fn new(device: &B::Device) -> Self {
         Self {
-            _phantom: std::marker::PhantomData,
+            hidden: LinearConfig::new(FEATURE_COUNT, HIDDEN_SIZE).init(device),
+            cache_head: LinearConfig::new(HIDDEN_SIZE, 1).init(device),
+            tier_head: LinearConfig::new(HIDDEN_SIZE, ModelTier::ALL.len()).init(device),
+            activation: Relu::new(),
+        }
+    }
+
+    pub fn from_weights(weights: &RoutingWeights, device: &B::Device) -> Result<Self> {
+        weights.validate()?;
+        Ok(Self {
+            hidden: linear_from_dense(&weights.hidden, device),
+            cache_head: linear_from_dense(&weights.cache_head, device),
+            tier_head: linear_from_dense(&weights.tier_head, device),
+            activation: Relu::new(),
+        })
+    }
+
+    /// Cache logits `[batch, 1]` and tier logits `[batch, 3]` for features `[batch, FEATURE_COUNT]`
+    pub fn forward(&self, features: Tensor<B, 2>) -> (Tensor<B, 2>, Tensor<B, 2>) {
+        let hidden = self.activation.forward(self.hidden.forward(features));
+        (
+            self.cache_head.forward(hidden.clone()),
+            self.tier_head.forward(hidden),
+        )
+    }
+
+    pub fn to_weights(&self, trained_examples: usize) -> RoutingWeights {
+        RoutingWeights {
+            version: WEIGHTS_FORMAT_VERSION,
+            hidden: dense_from_linear(&self.hidden),
+            cache_head: dense_from_linear(&self.cache_head),
+            tier_head: dense_from_linear(&self.tier_head),
+            trained_examples,
         }
     }
 }
 
+/// Burn stores linear weights as `[inputs, outputs]`, the transpose of [`DenseLayer`]
+#[cfg(feature = "burn-neural-networks")]
+fn dense_from_linear<B: Backend>(linear: &Linear<B>) -> DenseLayer {
+    let [inputs, outputs] = linear.weight.val().dims();
+    let weights = linear
+        .weight
+        .val()
+        .transpose()
+        .into_data()
+        .to_vec::<f32>()
+        .unwrap_or_default();
+    let bias = linear
+        .bias
+        .as_ref()
+        .map(|bias| bias.val().into_data().to_vec::<f32>().unwrap_or_default())
+        .unwrap_or_else(|| vec![0.0; outputs]);
+    DenseLayer {
+        inputs,
+        outputs,
+        weights,
+        bias,
+    }
+}
+
+#[cfg(feature = "burn-neural-networks")]
+fn linear_from_dense<B: Backend>(layer: &DenseLayer, device: &B::Device) -> Linear<B> {
+    let weight = Tensor::<B, 2>::from_data(
+        TensorData::new(layer.weights.clone(), [layer.outputs, layer.inputs]),
+        device,
+    )
+    .transpose();
+    let bias =
+        Tensor::<B, 1>::from_data(TensorData::new(layer.bias.clone(), [layer.outputs]), device);
+    Linear {
+        weight: burn::module::Param::from_tensor(weight),
+        bias: Some(burn::module::Param::from_tensor(bias)),
+    }
+}
+
 /// Neural network for cache anchor analysis
 /// Currently a placeholder - will be implemented with trained model in Phase 2c
 #[cfg(feature = "burn-neural-networks")]
@@ -38,28 +301,12 @@ impl<B: Backend> CacheAnchorNetwork<B> {
     }
 }
 
-// Stub implementations when Burn is not enabled (Phase 2a default)
-#[cfg(not(feature = "burn-neural-networks"))]
-#[derive(Debug)]
-pub struct RoutingNetwork {
-    _phantom: std::marker::PhantomData<()>,
-}
-
 #[cfg(not(feature = "burn-neural-networks"))]
 #[derive(Debug)]
 pub struct CacheAnchorNetwork {
     _phantom: std::marker::PhantomData<()>,
 }
 
-#[cfg(not(feature = "burn-neural-networks"))]
-impl RoutingNetwork {
-    pub fn new() -> Self {
-        Self {
-            _phantom: std::marker::PhantomData,
-        }
-    }
-}
-
 #[cfg(not(feature = "burn-neural-networks"))]
 impl CacheAnchorNetwork {
     pub fn new() -> Self {
@@ -70,15 +317,59 @@ impl CacheAnchorNetwork {
 }
 
 #[cfg(not(feature = "burn-neural-networks"))]
-impl Default for RoutingNetwork {
+impl Default for CacheAnchorNetwork {
     fn default() -> Self {
         Self::new()
     }
 }
 
-#[cfg(not(feature = "burn-neural-networks"))]
-impl Default for CacheAnchorNetwork {
-    fn default() -> Self {
-        Self::new()
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn zero_layer(inputs: usize, outputs: usize) -> DenseLayer {
+        DenseLayer {
+            inputs,
+            outputs,
+            weights: vec![0.0; inputs * outputs],
+            bias: vec![0.0; outputs],
+        }
+    }
+
+    #[test]
+    fn test_weights_round_trip_and_predict() {
+        let mut weights = RoutingWeights {
+            version: WEIGHTS_FORMAT_VERSION,
+            hidden: zero_layer(FEATURE_COUNT, HIDDEN_SIZE),
+            cache_head: zero_layer(HIDDEN_SIZE, 1),
+            tier_head: zero_layer(HIDDEN_SIZE, 3),
+            trained_examples: 0,
+        };
+        // The first hidden unit copies the code-block ratio, which pushes
+        // the cache logit up and favors the large tier
+        weights.hidden.weights[1] = 1.0;
+        weights.cache_head.weights[0] = 8.0;
+        weights.cache_head.bias[0] = -4.0;
+        weights.tier_head.weights[2 * HIDDEN_SIZE] = 8.0;
+
+        let mut features = vec![0.0; FEATURE_COUNT];
+        let prediction = weights.predict(&features);
+        assert!(prediction.cache_probability < 0.1);
+        assert!((prediction.tier_probabilities.iter().sum::<f32>() - 1.0).abs() < 1e-6);
+
+        features[1] = 1.0;
+        let prediction = weights.predict(&features);
+        assert!(prediction.cache_probability > 0.9);
+        assert_eq!(prediction.tier, ModelTier::Large);
+
+        let dir = std::env::temp_dir().join(format!("routing-weights-{}", std::process::id()));
+        let path = dir.join("weights.json");
+        weights.save(&path).unwrap();
+        assert_eq!(RoutingWeights::load(&path).unwrap(), weights);
+
+        weights.hidden.bias.pop();
+        weights.save(&path).unwrap();
+        assert!(RoutingWeights::load(&path).is_err());
+        std::fs::remove_dir_all(&dir).ok();
     }
 }
@@ -1,6 +1,9 @@
 use pyo3_bridge::types::{CacheAnchor, TokenOptimization};
 use serde::{Deserialize, Serialize};
 
+use crate::network::ModelTier;
+use crate::token_optimizer::OptimizedPrompt;
+
 /// Routing decision for a prompt
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RoutingDecision {
@@ -8,6 +11,15 @@ pub struct RoutingDecision {
     pub cache_anchors: Vec<CacheAnchor>,
     pub api_key_selection: ApiKeyConfig,
     pub token_optimization: TokenOptimization,
+    /// The compressed prompt to send instead of the refined one
+    #[serde(default)]
+    pub optimized_prompt: OptimizedPrompt,
+    /// Network input the decision was based on, logged for training
+    #[serde(default)]
+    pub features: Vec<f32>,
+    /// Model tier predicted by the routing network, if weights are loaded
+    #[serde(default)]
+    pub model_tier: Option<ModelTier>,
 }
 
 /// API key configuration
@@ -25,6 +37,9 @@ impl RoutingDecision {
             cache_anchors: Vec::new(),
             api_key_selection: ApiKeyConfig::UseDefault,
             token_optimization: TokenOptimization::default(),
+            optimized_prompt: OptimizedPrompt::default(),
+            features: Vec::new(),
+            model_tier: None,
         }
     }
 }
@@ -1,42 +1,396 @@
 use anyhow::Result;
+use language_model::{count_tokens, fenced_file_path, UNCHANGED_FILE_PLACEHOLDER};
 use pyo3_bridge::types::TokenOptimization;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use crate::cache_anchor::split_blocks;
+
+/// Fence tags whose contents are command or tool output rather than code
+const OUTPUT_TAGS: &[&str] = &[
+    "output",
+    "console",
+    "terminal",
+    "shell-session",
+    "log",
+    "stdout",
+    "stderr",
+    "text",
+];
+
+/// Lines of long output worth keeping wherever they appear
+const ERROR_MARKERS: &[&str] = &[
+    "error",
+    "warning",
+    "panicked",
+    "failed",
+    "traceback",
+    "exception",
+];
+
+/// A compression applied to a prompt
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Transformation {
+    /// Repeated context blocks were dropped, keeping the first occurrence
+    DeduplicateBlocks,
+    /// Files already sent with the same content were replaced by a placeholder
+    ElideUnchangedFiles,
+    /// Long tool output was cut down to its head, tail and error lines
+    SummarizeToolOutput,
+    /// The prompt was split into chunks that each fit the chunk budget
+    SplitChunks,
+}
+
+impl Transformation {
+    pub fn name(self) -> &'static str {
+        match self {
+            Transformation::DeduplicateBlocks => "deduplicate_blocks",
+            Transformation::ElideUnchangedFiles => "elide_unchanged_files",
+            Transformation::SummarizeToolOutput => "summarize_tool_output",
+            Transformation::SplitChunks => "split_chunks",
+        }
+    }
+}
+
+/// What one transformation did to a prompt
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TransformationReport {
+    pub transformation: Transformation,
+    /// Blocks, files or outputs the transformation applied to, or the
+    /// number of chunks for [`Transformation::SplitChunks`]
+    pub occurrences: usize,
+    pub tokens_saved: usize,
+}
+
+/// A prompt after compression
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OptimizedPrompt {
+    pub prompt: String,
+    /// The prompt split at block boundaries when it exceeds the chunk
+    /// budget, empty otherwise
+    pub chunks: Vec<String>,
+    pub reports: Vec<TransformationReport>,
+    pub tokens_before: usize,
+    pub tokens_after: usize,
+}
+
+impl OptimizedPrompt {
+    pub fn tokens_saved(&self) -> usize {
+        self.tokens_before.saturating_sub(self.tokens_after)
+    }
+
+    pub fn applied(&self, transformation: Transformation) -> bool {
+        self.reports
+            .iter()
+            .any(|report| report.transformation == transformation)
+    }
+
+    pub fn token_optimization(&self) -> TokenOptimization {
+        TokenOptimization {
+            remove_redundancy: self.applied(Transformation::DeduplicateBlocks)
+                || self.applied(Transformation::ElideUnchangedFiles),
+            compress_context: self.applied(Transformation::SummarizeToolOutput),
+            split_into_chunks: self.applied(Transformation::SplitChunks),
+            tokens_saved: self.tokens_saved(),
+        }
+    }
+}
+
+/// Blocks and files the model has already seen
+#[derive(Default)]
+struct SeenContent {
+    blocks: HashSet<String>,
+    files: HashMap<String, String>,
+}
+
+/// Occurrences and tokens saved, per transformation
+#[derive(Default)]
+struct Tally(HashMap<Transformation, (usize, usize)>);
+
+impl Tally {
+    fn record(&mut self, transformation: Transformation, tokens_saved: usize) {
+        let entry = self.0.entry(transformation).or_default();
+        entry.0 += 1;
+        entry.1 += tokens_saved;
+    }
+
+    fn into_reports(self) -> Vec<TransformationReport> {
+        let mut reports = self
+            .0
+            .into_iter()
+            .map(
+                |(transformation, (occurrences, tokens_saved))| TransformationReport {
+                    transformation,
+                    occurrences,
+                    tokens_saved,
+                },
+            )
+            .collect::<Vec<_>>();
+        reports.sort_by_key(|report| report.transformation);
+        reports
+    }
+}
 
 /// Optimizer for token usage in prompts
-pub struct TokenOptimizer {}
+///
+/// Compression is deterministic, so a message compresses the same way every
+/// time it is sent and cache prefixes stay stable across turns.
+pub struct TokenOptimizer {
+    min_block_tokens: usize,
+    max_output_tokens: usize,
+    output_head_lines: usize,
+    output_tail_lines: usize,
+    max_chunk_tokens: usize,
+}
 
 impl TokenOptimizer {
     pub fn new() -> Self {
-        Self {}
+        Self {
+            min_block_tokens: 16,
+            max_output_tokens: 2048,
+            output_head_lines: 40,
+            output_tail_lines: 40,
+            max_chunk_tokens: 8192,
+        }
+    }
+
+    /// Blocks shorter than this are never deduplicated
+    pub fn with_min_block_tokens(mut self, tokens: usize) -> Self {
+        self.min_block_tokens = tokens;
+        self
+    }
+
+    /// Summarize tool output longer than this, keeping the first and last lines
+    pub fn with_max_output_tokens(
+        mut self,
+        tokens: usize,
+        head_lines: usize,
+        tail_lines: usize,
+    ) -> Self {
+        self.max_output_tokens = tokens;
+        self.output_head_lines = head_lines;
+        self.output_tail_lines = tail_lines;
+        self
+    }
+
+    /// Split prompts longer than this into chunks
+    pub fn with_max_chunk_tokens(mut self, tokens: usize) -> Self {
+        self.max_chunk_tokens = tokens.max(1);
+        self
     }
 
     /// Optimize token usage for a prompt
     pub async fn optimize(&self, prompt: &str) -> Result<TokenOptimization> {
         log::debug!("Optimizing token usage for prompt");
+        Ok(self.compress(prompt).token_optimization())
+    }
 
-        // Analyze prompt characteristics
-        let has_redundancy = Self::detect_redundancy(prompt);
-        let needs_compression = prompt.len() > 4000;
-        let should_chunk = prompt.len() > 8000;
+    /// Compress a prompt on its own
+    pub fn compress(&self, prompt: &str) -> OptimizedPrompt {
+        self.compress_with_history(prompt, &[])
+    }
+
+    /// Compress a prompt that follows `history` in the same thread, so
+    /// files and blocks the model has already seen are not sent again
+    pub fn compress_with_history(&self, prompt: &str, history: &[&str]) -> OptimizedPrompt {
+        let mut seen = SeenContent::default();
+        for text in history {
+            self.compress_text(text, &mut seen, &mut Tally::default());
+        }
+
+        let mut tally = Tally::default();
+        let mut compressed = self.compress_text(prompt, &mut seen, &mut tally);
+        if compressed.trim().is_empty() {
+            // Never compress a prompt away entirely, e.g. a repeated question
+            compressed = prompt.to_string();
+            tally = Tally::default();
+        }
+        let tokens_before = count_tokens(prompt);
+        let tokens_after = count_tokens(&compressed);
+
+        let mut chunks = Vec::new();
+        if tokens_after > self.max_chunk_tokens {
+            chunks = self.split_chunks(&compressed);
+            if chunks.len() < 2 {
+                chunks.clear();
+            }
+        }
+        let mut reports = tally.into_reports();
+        if !chunks.is_empty() {
+            reports.push(TransformationReport {
+                transformation: Transformation::SplitChunks,
+                occurrences: chunks.len(),
+                tokens_saved: 0,
+            });
+        }
 
-        Ok(TokenOptimization {
-            remove_redundancy: has_redundancy,
-            compress_context: needs_compression,
-            split_into_chunks: should_chunk,
-        })
+        for report in &reports {
+            log::debug!(
+                "{}: {} occurrence(s), {} tokens saved",
+                report.transformation.name(),
+                report.occurrences,
+                report.tokens_saved
+            );
+        }
+
+        OptimizedPrompt {
+            prompt: compressed,
+            chunks,
+            reports,
+            tokens_before,
+            tokens_after,
+        }
     }
 
-    fn detect_redundancy(text: &str) -> bool {
-        // Check for repeated phrases
-        let words: Vec<&str> = text.split_whitespace().collect();
-        let unique_words: std::collections::HashSet<_> = words.iter().collect();
+    /// Elide unchanged files, summarize long output and drop repeated blocks
+    fn compress_text(&self, text: &str, seen: &mut SeenContent, tally: &mut Tally) -> String {
+        let text = self.compress_fences(text, seen, tally);
 
-        // If less than 60% unique words, consider it redundant
-        if words.len() > 0 {
-            let uniqueness = unique_words.len() as f32 / words.len() as f32;
-            uniqueness < 0.6
-        } else {
-            false
+        let mut result = String::with_capacity(text.len());
+        for block in split_blocks(&text) {
+            let content = block.trim();
+            if !content.is_empty() {
+                let tokens = count_tokens(block);
+                if tokens >= self.min_block_tokens && !seen.blocks.insert(content.to_string()) {
+                    tally.record(Transformation::DeduplicateBlocks, tokens);
+                    continue;
+                }
+            }
+            result.push_str(block);
         }
+        result
+    }
+
+    /// Rewrite fenced code blocks: files already seen with the same content
+    /// are replaced by a placeholder, long output is summarized
+    fn compress_fences(&self, text: &str, seen: &mut SeenContent, tally: &mut Tally) -> String {
+        let mut result = String::with_capacity(text.len());
+        let mut fence: Option<(&str, &str, usize)> = None;
+        let mut body_start = 0;
+        let mut offset = 0;
+
+        for line in text.split_inclusive('\n') {
+            let line_start = offset;
+            offset += line.len();
+            let trimmed = line.trim();
+
+            let Some((open_line, tag, ticks)) = fence else {
+                let ticks = trimmed.chars().take_while(|c| *c == '`').count();
+                if ticks >= 3 {
+                    fence = Some((line, trimmed[ticks..].trim(), ticks));
+                    body_start = offset;
+                } else {
+                    result.push_str(line);
+                }
+                continue;
+            };
+
+            let closes = trimmed.len() >= ticks && trimmed.chars().all(|c| c == '`');
+            if !closes {
+                continue;
+            }
+            fence = None;
+
+            let body = &text[body_start..line_start];
+            let replacement = if fenced_file_path(tag).is_some() {
+                let previous = seen.files.insert(tag.to_string(), body.to_string());
+                (previous.as_deref() == Some(body)).then(|| {
+                    (
+                        Transformation::ElideUnchangedFiles,
+                        format!("{}\n", UNCHANGED_FILE_PLACEHOLDER),
+                    )
+                })
+            } else if OUTPUT_TAGS.contains(&tag) && count_tokens(body) > self.max_output_tokens {
+                self.summarize_output(body)
+                    .map(|body| (Transformation::SummarizeToolOutput, body))
+            } else {
+                None
+            };
+
+            result.push_str(open_line);
+            match replacement {
+                Some((transformation, replacement)) => {
+                    tally.record(
+                        transformation,
+                        count_tokens(body).saturating_sub(count_tokens(&replacement)),
+                    );
+                    result.push_str(&replacement);
+                }
+                None => result.push_str(body),
+            }
+            result.push_str(line);
+        }
+
+        // An unclosed fence is left as it is
+        if let Some((open_line, _, _)) = fence {
+            result.push_str(open_line);
+            result.push_str(&text[body_start..]);
+        }
+        result
+    }
+
+    /// Keep the first and last lines of long output, plus any error lines
+    /// in between, or `None` if the output is too short to shorten
+    fn summarize_output(&self, output: &str) -> Option<String> {
+        let lines = output.lines().collect::<Vec<_>>();
+        let head = self.output_head_lines;
+        let tail = self.output_tail_lines;
+        if lines.len() <= head + tail {
+            return None;
+        }
+
+        let mut summary = String::new();
+        let mut omitted = 0;
+        for (ix, line) in lines.iter().enumerate() {
+            let keep = ix < head || ix >= lines.len() - tail || {
+                let lower = line.to_lowercase();
+                ERROR_MARKERS.iter().any(|marker| lower.contains(marker))
+            };
+            if !keep {
+                omitted += 1;
+                continue;
+            }
+            if omitted > 0 {
+                summary.push_str(&format!("... {} lines omitted ...\n", omitted));
+                omitted = 0;
+            }
+            summary.push_str(line);
+            summary.push('\n');
+        }
+        Some(summary)
+    }
+
+    /// Group blocks into chunks within the chunk budget, splitting blocks
+    /// that don't fit on their own at line boundaries
+    fn split_chunks(&self, prompt: &str) -> Vec<String> {
+        let mut chunks = Vec::new();
+        let mut chunk = String::new();
+        let mut chunk_tokens = 0;
+
+        let mut push = |text: &str, tokens: usize| {
+            if chunk_tokens + tokens > self.max_chunk_tokens && !chunk.is_empty() {
+                chunks.push(std::mem::take(&mut chunk));
+                chunk_tokens = 0;
+            }
+            chunk.push_str(text);
+            chunk_tokens += tokens;
+        };
+
+        for block in split_blocks(prompt) {
+            let tokens = count_tokens(block);
+            if tokens <= self.max_chunk_tokens {
+                push(block, tokens);
+            } else {
+                for line in block.split_inclusive('\n') {
+                    push(line, count_tokens(line));
+                }
+            }
+        }
+        if !chunk.is_empty() {
+            chunks.push(chunk);
+        }
+        chunks
     }
 }
 
@@ -50,12 +404,128 @@ impl Default for TokenOptimizer {
 mod tests {
     use super::*;
 
+    fn report(
+        optimized: &OptimizedPrompt,
+        transformation: Transformation,
+    ) -> &TransformationReport {
+        optimized
+            .reports
+            .iter()
+            .find(|report| report.transformation == transformation)
+            .unwrap()
+    }
+
     #[tokio::test]
     async fn test_optimization() {
         let optimizer = TokenOptimizer::new();
         let prompt = "This is a test prompt for optimization";
 
         let result = optimizer.optimize(prompt).await.unwrap();
-        assert!(!result.split_into_chunks); // Short prompt shouldn't need chunking
+        assert!(!result.split_into_chunks);
+        assert!(!result.remove_redundancy);
+        assert_eq!(result.tokens_saved, 0);
+    }
+
+    #[test]
+    fn test_deduplicates_repeated_blocks() {
+        let optimizer = TokenOptimizer::new().with_min_block_tokens(4);
+        let context = "The parser reads tokens from the lexer and builds the syntax tree.\n\n";
+        let prompt = format!("{context}Why is it slow?\n\n{context}ok\n\nok\n");
+
+        let optimized = optimizer.compress(&prompt);
+        assert_eq!(
+            optimized.prompt,
+            format!("{context}Why is it slow?\n\nok\n\nok\n")
+        );
+        let dedup = report(&optimized, Transformation::DeduplicateBlocks);
+        assert_eq!(dedup.occurrences, 1);
+        assert_eq!(dedup.tokens_saved, count_tokens(context));
+        assert!(optimized.token_optimization().remove_redundancy);
+    }
+
+    #[test]
+    fn test_elides_files_already_sent() {
+        let optimizer = TokenOptimizer::new();
+        let file = "```rs /project/src/lib.rs\npub fn parse() {}\n```\n";
+        let earlier = format!("<files>\n{file}</files>\nWhat does parse do?");
+
+        let optimized = optimizer
+            .compress_with_history(&format!("<files>\n{file}</files>\nAnd now?"), &[&earlier]);
+        assert_eq!(
+            optimized.prompt,
+            format!(
+                "<files>\n```rs /project/src/lib.rs\n{UNCHANGED_FILE_PLACEHOLDER}\n```\n</files>\nAnd now?"
+            )
+        );
+        assert_eq!(
+            report(&optimized, Transformation::ElideUnchangedFiles).occurrences,
+            1
+        );
+
+        // Changed content is sent again
+        let changed =
+            "<files>\n```rs /project/src/lib.rs\npub fn parse() -> bool {}\n```\n</files>";
+        let optimized = optimizer.compress_with_history(changed, &[&earlier]);
+        assert_eq!(optimized.prompt, changed);
+        assert!(optimized.reports.is_empty());
+    }
+
+    #[test]
+    fn test_summarizes_long_output() {
+        let optimizer = TokenOptimizer::new().with_max_output_tokens(20, 2, 1);
+        let mut output = String::from("```output\n");
+        for ix in 0..20 {
+            output.push_str(&format!("compiling crate {ix}\n"));
+        }
+        output.push_str("error: expected one of `;` or `}`\nfinished\n```\n");
+
+        let optimized = optimizer.compress(&output);
+        assert_eq!(
+            optimized.prompt,
+            "```output\ncompiling crate 0\ncompiling crate 1\n... 18 lines omitted ...\n\
+             error: expected one of `;` or `}`\nfinished\n```\n"
+        );
+        let summary = report(&optimized, Transformation::SummarizeToolOutput);
+        assert_eq!(summary.occurrences, 1);
+        assert!(summary.tokens_saved > 0);
+        assert_eq!(
+            optimized.tokens_saved(),
+            optimized.tokens_before - optimized.tokens_after
+        );
+    }
+
+    #[test]
+    fn test_splits_oversized_prompts() {
+        let optimizer = TokenOptimizer::new().with_max_chunk_tokens(30);
+        let prompt = (0..6)
+            .map(|ix| format!("Paragraph {ix} explains one more detail of the request.\n\n"))
+            .collect::<String>();
+
+        let optimized = optimizer.compress(&prompt);
+        assert!(optimized.chunks.len() > 1);
+        assert_eq!(optimized.chunks.concat(), prompt);
+        assert!(optimized
+            .chunks
+            .iter()
+            .all(|chunk| count_tokens(chunk) <= 30));
+        let split = report(&optimized, Transformation::SplitChunks);
+        assert_eq!(split.occurrences, optimized.chunks.len());
+        assert_eq!(split.tokens_saved, 0);
+        assert!(optimized.token_optimization().split_into_chunks);
+
+        // Prompts within the budget are sent whole
+        let optimized = TokenOptimizer::new().compress(&prompt);
+        assert!(optimized.chunks.is_empty());
+        assert!(!optimized.applied(Transformation::SplitChunks));
+    }
+
+    #[test]
+    fn test_fence_tags_that_are_not_paths() {
+        // Repeated snippets tagged with a version are sent again in full
+        let optimizer = TokenOptimizer::new();
+        let snippet = "```python3.11\nprint('hello')\n```\n";
+        let optimized = optimizer.compress_with_history(snippet, &[snippet]);
+        assert_eq!(optimized.prompt, snippet);
+        assert!(!optimized.applied(Transformation::ElideUnchangedFiles));
     }
 }
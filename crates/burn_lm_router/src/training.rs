@@ -0,0 +1,347 @@
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+
+use pyo3_bridge::telemetry::{CacheMissReason, TelemetryEvent};
+
+#[cfg(feature = "burn-neural-networks")]
+use anyhow::{bail, Context, Result};
+#[cfg(feature = "burn-neural-networks")]
+use burn::{
+    backend::{Autodiff, NdArray},
+    nn::loss::{BinaryCrossEntropyLossConfig, CrossEntropyLossConfig},
+    optim::{AdamConfig, GradientsParams, Optimizer},
+    prelude::*,
+};
+#[cfg(feature = "burn-neural-networks")]
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "burn-neural-networks")]
+use crate::features::FEATURE_COUNT;
+use crate::network::ModelTier;
+#[cfg(feature = "burn-neural-networks")]
+use crate::network::{RoutingNetwork, RoutingWeights};
+
+/// One routed prompt with what actually happened to it
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrainingExample {
+    pub features: Vec<f32>,
+    pub cache_hit: bool,
+    /// Tier of the user-picked model that answered the prompt successfully, if known
+    pub tier: Option<ModelTier>,
+}
+
+/// Join `RoutingFeatures` events with the `CacheHit`/`CacheMiss` outcome of
+/// the same prompt. Misses caused by errors or a disabled cache say nothing
+/// about the prompt, so those prompts are left out.
+///
+/// The tier is only known for prompts answered successfully by a model the
+/// user picked. A model picked by the router, or the default one, says what
+/// the router decided rather than what the prompt needed, and training on it
+/// would only reinforce that decision. Until failed prompts are retried on a
+/// larger tier there is no outcome to label those prompts with, so their tier
+/// is left unknown.
+pub fn examples_from_events(events: &[TelemetryEvent]) -> Vec<TrainingExample> {
+    let mut features = HashMap::new();
+    let mut cache_hits = HashMap::new();
+    let mut models = HashMap::new();
+    let mut failed = HashSet::new();
+    let mut order = Vec::new();
+
+    for event in events {
+        match event {
+            TelemetryEvent::RoutingFeatures {
+                prompt_id,
+                features: vector,
+                ..
+            } => {
+                if let Entry::Vacant(entry) = features.entry(prompt_id.as_str()) {
+                    entry.insert(vector);
+                    order.push(prompt_id.as_str());
+                }
+            }
+            TelemetryEvent::CacheHit { prompt_id, .. } => {
+                cache_hits.insert(prompt_id.as_str(), true);
+            }
+            TelemetryEvent::CacheMiss {
+                prompt_id, reason, ..
+            } => match reason {
+                CacheMissReason::Error(_) | CacheMissReason::CacheDisabled => {}
+                _ => {
+                    cache_hits.entry(prompt_id.as_str()).or_insert(false);
+                }
+            },
+            TelemetryEvent::ModelInferenceStart {
+                prompt_id,
+                model_name,
+                user_selected: true,
+                ..
+            } => {
+                models.insert(prompt_id.as_str(), model_name.as_str());
+            }
+            TelemetryEvent::PromptEnd {
+                prompt_id,
+                success: false,
+                ..
+            } => {
+                failed.insert(prompt_id.as_str());
+            }
+            _ => {}
+        }
+    }
+
+    order
+        .into_iter()
+        .filter_map(|prompt_id| {
+            let cache_hit = *cache_hits.get(prompt_id)?;
+            let tier = models
+                .get(prompt_id)
+                .filter(|_| !failed.contains(prompt_id))
+                .and_then(|model_name| ModelTier::from_model_name(model_name));
+            Some(TrainingExample {
+                features: features[prompt_id].clone(),
+                cache_hit,
+                tier,
+            })
+        })
+        .collect()
+}
+
+/// Hyperparameters for offline training of the routing network
+#[cfg(feature = "burn-neural-networks")]
+#[derive(Debug, Clone)]
+pub struct TrainingConfig {
+    pub epochs: usize,
+    pub batch_size: usize,
+    pub learning_rate: f64,
+    pub seed: u64,
+}
+
+#[cfg(feature = "burn-neural-networks")]
+impl Default for TrainingConfig {
+    fn default() -> Self {
+        Self {
+            epochs: 200,
+            batch_size: 64,
+            learning_rate: 1e-2,
+            seed: 42,
+        }
+    }
+}
+
+#[cfg(feature = "burn-neural-networks")]
+type TrainingBackend = Autodiff<NdArray>;
+
+/// Train the routing network on telemetry examples
+///
+/// The cache head is trained with binary cross-entropy on every example; the
+/// tier head only on examples whose tier is known.
+#[cfg(feature = "burn-neural-networks")]
+pub fn train(examples: &[TrainingExample], config: &TrainingConfig) -> Result<RoutingWeights> {
+    let examples = examples
+        .iter()
+        .filter(|example| example.features.len() == FEATURE_COUNT)
+        .collect::<Vec<_>>();
+    if examples.is_empty() {
+        bail!("No routing telemetry to train on");
+    }
+
+    let device = Default::default();
+    TrainingBackend::seed(&device, config.seed);
+
+    let mut model = RoutingNetwork::<TrainingBackend>::new(&device);
+    let mut optimizer = AdamConfig::new().init();
+    let cache_loss = BinaryCrossEntropyLossConfig::new()
+        .with_logits(true)
+        .init(&device);
+    let tier_loss = CrossEntropyLossConfig::new().init(&device);
+
+    let mut last_loss = 0.0;
+    for _ in 0..config.epochs {
+        for batch in examples.chunks(config.batch_size.max(1)) {
+            let features = Tensor::<TrainingBackend, 2>::from_data(
+                TensorData::new(
+                    batch
+                        .iter()
+                        .flat_map(|example| example.features.iter().copied())
+                        .collect::<Vec<_>>(),
+                    [batch.len(), FEATURE_COUNT],
+                ),
+                &device,
+            );
+            let cache_targets = Tensor::<TrainingBackend, 1, Int>::from_data(
+                TensorData::new(
+                    batch
+                        .iter()
+                        .map(|example| example.cache_hit as i64)
+                        .collect::<Vec<_>>(),
+                    [batch.len()],
+                ),
+                &device,
+            );
+
+            let (cache_logits, tier_logits) = model.forward(features);
+            let mut loss = cache_loss.forward(cache_logits.reshape([batch.len()]), cache_targets);
+
+            let (tier_rows, tier_targets): (Vec<i64>, Vec<i64>) = batch
+                .iter()
+                .enumerate()
+                .filter_map(|(row, example)| Some((row as i64, example.tier?.index() as i64)))
+                .unzip();
+            if !tier_rows.is_empty() {
+                let count = tier_rows.len();
+                let rows = Tensor::<TrainingBackend, 1, Int>::from_data(
+                    TensorData::new(tier_rows, [count]),
+                    &device,
+                );
+                let targets = Tensor::<TrainingBackend, 1, Int>::from_data(
+                    TensorData::new(tier_targets, [count]),
+                    &device,
+                );
+                loss = loss + tier_loss.forward(tier_logits.select(0, rows), targets);
+            }
+
+            last_loss = loss.clone().into_scalar().elem::<f32>();
+            let gradients = GradientsParams::from_grads(loss.backward(), &model);
+            model = optimizer.step(config.learning_rate, model, gradients);
+        }
+    }
+
+    log::info!(
+        "Trained routing network on {} examples, final loss {:.4}",
+        examples.len(),
+        last_loss
+    );
+    Ok(model.to_weights(examples.len()))
+}
+
+/// Train on a JSON-lines telemetry log, including the files it was rotated
+/// to (`log.1`, `log.2`, ...), and write the weights next to it
+#[cfg(feature = "burn-neural-networks")]
+pub fn train_from_log_file(
+    log_path: &Path,
+    weights_path: &Path,
+    config: &TrainingConfig,
+) -> Result<RoutingWeights> {
+    let mut log_paths = (1..)
+        .map(|index| PathBuf::from(format!("{}.{}", log_path.display(), index)))
+        .take_while(|path| path.exists())
+        .collect::<Vec<_>>();
+    log_paths.reverse();
+    log_paths.push(log_path.to_path_buf());
+
+    let mut log = String::new();
+    for path in &log_paths {
+        log.push_str(
+            &std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read telemetry log {:?}", path))?,
+        );
+    }
+    let examples = examples_from_events(&TelemetryEvent::parse_log(&log));
+    let weights = train(&examples, config)?;
+    weights.save(weights_path)?;
+    Ok(weights)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn events_for(
+        prompt_id: &str,
+        features: Vec<f32>,
+        hit: bool,
+        model: &str,
+        user_selected: bool,
+    ) -> Vec<TelemetryEvent> {
+        let outcome = if hit {
+            TelemetryEvent::cache_hit(prompt_id.to_string(), 0.95, 5, 100)
+        } else {
+            TelemetryEvent::cache_miss(prompt_id.to_string(), CacheMissReason::NoSimilarPrompt)
+        };
+        vec![
+            TelemetryEvent::routing_features(prompt_id.to_string(), features),
+            outcome,
+            TelemetryEvent::ModelInferenceStart {
+                prompt_id: prompt_id.to_string(),
+                model_name: model.to_string(),
+                prompt_tokens: 10,
+                user_selected,
+                timestamp: 0,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_examples_from_events() {
+        let mut events = events_for("a", vec![1.0], true, "claude-3-haiku", true);
+        events.extend(events_for("b", vec![0.0], false, "claude-3-opus", true));
+        events.push(TelemetryEvent::prompt_end("b".to_string(), 10, false, None));
+        // The router's own choice is not a label
+        events.extend(events_for("d", vec![0.25], false, "claude-opus-4", false));
+        events.push(TelemetryEvent::routing_features("c".to_string(), vec![0.5]));
+        events.push(TelemetryEvent::cache_miss(
+            "c".to_string(),
+            CacheMissReason::CacheDisabled,
+        ));
+
+        let examples = examples_from_events(&events);
+        assert_eq!(
+            examples,
+            vec![
+                TrainingExample {
+                    features: vec![1.0],
+                    cache_hit: true,
+                    tier: Some(ModelTier::Small),
+                },
+                TrainingExample {
+                    features: vec![0.0],
+                    cache_hit: false,
+                    tier: None,
+                },
+                TrainingExample {
+                    features: vec![0.25],
+                    cache_hit: false,
+                    tier: None,
+                },
+            ]
+        );
+    }
+
+    #[cfg(feature = "burn-neural-networks")]
+    #[test]
+    fn test_training_learns_cache_usefulness() {
+        // Prompts similar to recent ones hit the cache and are answered by
+        // small models, novel prompts miss and go to large models
+        let mut events = Vec::new();
+        for i in 0..32 {
+            let mut features = vec![0.0; FEATURE_COUNT];
+            let similar = i % 2 == 0;
+            features[0] = 0.3;
+            features[2] = if similar { 0.9 } else { 0.1 };
+            let model = if similar { "haiku" } else { "opus" };
+            events.extend(events_for(
+                &format!("p{}", i),
+                features,
+                similar,
+                model,
+                true,
+            ));
+        }
+
+        let weights = train(&examples_from_events(&events), &TrainingConfig::default()).unwrap();
+        assert_eq!(weights.trained_examples, 32);
+
+        let mut similar = vec![0.0; FEATURE_COUNT];
+        similar[0] = 0.3;
+        similar[2] = 0.9;
+        let prediction = weights.predict(&similar);
+        assert!(prediction.cache_probability > 0.8);
+        assert_eq!(prediction.tier, ModelTier::Small);
+
+        let mut novel = similar.clone();
+        novel[2] = 0.1;
+        let prediction = weights.predict(&novel);
+        assert!(prediction.cache_probability < 0.2);
+        assert_eq!(prediction.tier, ModelTier::Large);
+    }
+}
@@ -471,6 +471,23 @@ impl Server {
             .add_request_handler(forward_mutating_project_request::<proto::GitChangeBranch>)
             .add_request_handler(forward_mutating_project_request::<proto::GitCreateRemote>)
             .add_request_handler(forward_mutating_project_request::<proto::GitRemoveRemote>)
+            .add_request_handler(forward_read_only_project_request::<proto::GitGetRebaseTodo>)
+            .add_request_handler(forward_mutating_project_request::<proto::GitRebase>)
+            .add_request_handler(forward_read_only_project_request::<proto::GitGetRebaseState>)
+            .add_request_handler(forward_mutating_project_request::<proto::GitRebaseContinue>)
+            .add_request_handler(forward_mutating_project_request::<proto::GitRebaseSkip>)
+            .add_request_handler(forward_mutating_project_request::<proto::GitRebaseAbort>)
+            .add_request_handler(forward_mutating_project_request::<proto::GitCherryPick>)
+            .add_request_handler(forward_mutating_project_request::<proto::GitRevert>)
+            .add_request_handler(forward_mutating_project_request::<proto::GitAmendCommit>)
+            .add_request_handler(forward_read_only_project_request::<proto::GitLog>)
+            .add_request_handler(forward_mutating_project_request::<proto::GitCreateTag>)
+            .add_request_handler(forward_mutating_project_request::<proto::GitCheckoutCommit>)
+            .add_request_handler(
+                forward_read_only_project_request::<proto::GitGetSequencerOperation>,
+            )
+            .add_request_handler(forward_mutating_project_request::<proto::GitSequencerContinue>)
+            .add_request_handler(forward_mutating_project_request::<proto::GitSequencerAbort>)
             .add_request_handler(forward_mutating_project_request::<proto::CheckForPushedCommits>)
             .add_message_handler(broadcast_project_message_from_host::<proto::AdvertiseContexts>)
             .add_message_handler(update_context)
@@ -1,9 +1,12 @@
 use std::path::Path;
 
 use call::ActiveCall;
-use git::status::{FileStatus, StatusCode, TrackedStatus};
+use git::{
+    repository::{CommitDetails, LogOptions, repo_path},
+    status::{FileStatus, StatusCode, TrackedStatus},
+};
 use git_ui::project_diff::ProjectDiff;
-use gpui::{TestAppContext, VisualTestContext};
+use gpui::{SharedString, TestAppContext, VisualTestContext};
 use project::ProjectPath;
 use serde_json::json;
 use util::{path, rel_path::rel_path};
@@ -133,3 +136,181 @@ async fn test_project_diff(cx_a: &mut TestAppContext, cx_b: &mut TestAppContext)
         );
     });
 }
+
+#[gpui::test]
+async fn test_remote_commit_log(cx_a: &mut TestAppContext, cx_b: &mut TestAppContext) {
+    let mut server = TestServer::start(cx_a.background_executor.clone()).await;
+    let client_a = server.create_client(cx_a, "user_a").await;
+    let client_b = server.create_client(cx_b, "user_b").await;
+    server
+        .create_room(&mut [(&client_a, cx_a), (&client_b, cx_b)])
+        .await;
+
+    client_a
+        .fs()
+        .insert_tree(path!("/a"), json!({ ".git": {}, "a.txt": "a\n" }))
+        .await;
+    client_a.fs().set_commit_history_for_repo(
+        Path::new(path!("/a/.git")),
+        ["one", "two", "three"]
+            .into_iter()
+            .enumerate()
+            .map(|(ix, sha)| CommitDetails {
+                sha: sha.into(),
+                message: format!("Commit {sha}").into(),
+                commit_timestamp: ix as i64,
+                ..Default::default()
+            })
+            .collect(),
+    );
+    let (project_a, _) = client_a.build_local_project(path!("/a"), cx_a).await;
+    let active_call_a = cx_a.read(ActiveCall::global);
+    let project_id = active_call_a
+        .update(cx_a, |call, cx| call.share_project(project_a.clone(), cx))
+        .await
+        .unwrap();
+    let project_b = client_b.join_remote_project(project_id, cx_b).await;
+    cx_b.run_until_parked();
+
+    let repository_b =
+        project_b.read_with(cx_b, |project, cx| project.active_repository(cx).unwrap());
+    let log = repository_b
+        .update(cx_b, |repo, _| repo.log(LogOptions::default(), 1, Some(1)))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(log.len(), 1);
+    assert_eq!(log[0].subject.as_ref(), "Commit two");
+    assert_eq!(log[0].parents, [SharedString::from("one")]);
+
+    repository_b
+        .update(cx_b, |repo, _| repo.create_tag("v1".into(), "one".into()))
+        .await
+        .unwrap()
+        .unwrap();
+    let options = LogOptions {
+        message: Some("ONE".into()),
+        ..Default::default()
+    };
+    let log = repository_b
+        .update(cx_b, |repo, _| repo.log(options, 0, None))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(log.len(), 1);
+    assert_eq!(log[0].refs, [SharedString::from("tag: v1")]);
+}
+
+#[gpui::test]
+async fn test_remote_commit_comparison(cx_a: &mut TestAppContext, cx_b: &mut TestAppContext) {
+    let mut server = TestServer::start(cx_a.background_executor.clone()).await;
+    let client_a = server.create_client(cx_a, "user_a").await;
+    let client_b = server.create_client(cx_b, "user_b").await;
+    server
+        .create_room(&mut [(&client_a, cx_a), (&client_b, cx_b)])
+        .await;
+
+    client_a
+        .fs()
+        .insert_tree(path!("/a"), json!({ ".git": {}, "a.txt": "A\n" }))
+        .await;
+    let dot_git = Path::new(path!("/a/.git"));
+    client_a.fs().set_commit_history_for_repo(
+        dot_git,
+        ["one", "two", "three"]
+            .into_iter()
+            .map(|sha| CommitDetails {
+                sha: sha.into(),
+                message: format!("Commit {sha}").into(),
+                ..Default::default()
+            })
+            .collect(),
+    );
+    client_a
+        .fs()
+        .set_commit_contents_for_repo(dot_git, "one", &[("a.txt", "a\n".into())]);
+    client_a.fs().set_commit_contents_for_repo(
+        dot_git,
+        "two",
+        &[("a.txt", "a\n".into()), ("b.txt", "b\n".into())],
+    );
+    client_a.fs().set_commit_contents_for_repo(
+        dot_git,
+        "three",
+        &[("a.txt", "A\n".into()), ("b.txt", "b\n".into())],
+    );
+    let (project_a, _) = client_a.build_local_project(path!("/a"), cx_a).await;
+    let active_call_a = cx_a.read(ActiveCall::global);
+    let project_id = active_call_a
+        .update(cx_a, |call, cx| call.share_project(project_a.clone(), cx))
+        .await
+        .unwrap();
+    let project_b = client_b.join_remote_project(project_id, cx_b).await;
+    cx_b.run_until_parked();
+
+    let repository_a =
+        project_a.read_with(cx_a, |project, cx| project.active_repository(cx).unwrap());
+    let repository_b =
+        project_b.read_with(cx_b, |project, cx| project.active_repository(cx).unwrap());
+
+    let local_diff = repository_a
+        .update(cx_a, |repo, _| {
+            repo.compare_commits("one".into(), "three".into())
+        })
+        .await
+        .unwrap()
+        .unwrap();
+    let remote_diff = repository_b
+        .update(cx_b, |repo, _| {
+            repo.compare_commits("one".into(), "three".into())
+        })
+        .await
+        .unwrap()
+        .unwrap();
+    for diff in [local_diff, remote_diff] {
+        assert_eq!(
+            diff.files
+                .iter()
+                .map(|file| (
+                    file.path.as_unix_str(),
+                    file.old_text.as_deref(),
+                    file.new_text.as_deref()
+                ))
+                .collect::<Vec<_>>(),
+            [
+                ("a.txt", Some("a\n"), Some("A\n")),
+                ("b.txt", None, Some("b\n"))
+            ]
+        );
+    }
+
+    // Filtering by path skips the commits that didn't change it, and
+    // rewrites parents to the previous commit that did
+    for (path, expected) in [
+        ("a.txt", vec![("three", vec!["one"]), ("one", vec![])]),
+        ("b.txt", vec![("two", vec![])]),
+    ] {
+        let options = LogOptions {
+            path: Some(repo_path(path)),
+            ..Default::default()
+        };
+        let log = repository_b
+            .update(cx_b, |repo, _| repo.log(options, 0, None))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            log.iter()
+                .map(|entry| (
+                    entry.sha.as_str(),
+                    entry
+                        .parents
+                        .iter()
+                        .map(SharedString::as_str)
+                        .collect::<Vec<_>>()
+                ))
+                .collect::<Vec<_>>(),
+            expected
+        );
+    }
+}
@@ -6,8 +6,10 @@ use git::{
     Oid, RunHook,
     blame::Blame,
     repository::{
-        AskPassDelegate, Branch, CommitDetails, CommitOptions, FetchOptions, GitRepository,
-        GitRepositoryCheckpoint, PushOptions, Remote, RepoPath, ResetMode, Worktree,
+        AskPassDelegate, Branch, CommitDetails, CommitDiff, CommitFile, CommitOptions,
+        FetchOptions, GitRepository, GitRepositoryCheckpoint, LogEntry, LogOptions, PushOptions,
+        RebaseAction, RebaseState, RebaseTodoEntry, Remote, RepoPath, ResetMode,
+        SequencerOperation, Worktree,
     },
     status::{
         DiffTreeType, FileStatus, GitStatus, StatusCode, TrackedStatus, TreeDiff, TreeDiffStatus,
@@ -54,6 +56,32 @@ pub struct FakeGitRepositoryState {
     pub remotes: HashMap<String, String>,
     pub simulated_index_write_error_message: Option<String>,
     pub refs: HashMap<String, String>,
+    /// Commits reachable from HEAD, oldest first
+    pub commit_history: Vec<CommitDetails>,
+    /// The files in commits of `commit_history`, by sha. Commits without
+    /// recorded contents have the same files as their parent.
+    pub commit_contents: HashMap<String, HashMap<RepoPath, String>>,
+    /// Commits that stop a rebase, cherry-pick or revert as if they didn't
+    /// apply cleanly
+    pub simulated_rebase_conflicts: HashSet<String>,
+    pub rebase: Option<FakeRebase>,
+    pub sequencer: Option<FakeSequencer>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FakeRebase {
+    pub state: RebaseState,
+    /// The history to restore if the rebase is aborted
+    pub original_history: Vec<CommitDetails>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FakeSequencer {
+    pub operation: SequencerOperation,
+    /// The commits left to apply, paired with the shas they'll get
+    pub todo: Vec<(String, String)>,
+    /// The history to restore if the operation is aborted
+    pub original_history: Vec<CommitDetails>,
 }
 
 impl FakeGitRepositoryState {
@@ -71,8 +99,158 @@ impl FakeGitRepositoryState {
             merge_base_contents: Default::default(),
             oids: Default::default(),
             remotes: HashMap::default(),
+            commit_history: Default::default(),
+            commit_contents: Default::default(),
+            simulated_rebase_conflicts: Default::default(),
+            rebase: None,
+            sequencer: None,
+        }
+    }
+
+    fn find_commit(&self, rev: &str) -> Result<usize> {
+        self.commit_history
+            .iter()
+            .position(|commit| !rev.is_empty() && commit.sha.starts_with(rev))
+            .with_context(|| format!("bad revision: {rev}"))
+    }
+
+    /// The files in the commit at `ix` in the history.
+    fn files_at(&self, ix: usize) -> Option<&HashMap<RepoPath, String>> {
+        self.commit_history[..=ix]
+            .iter()
+            .rev()
+            .find_map(|commit| self.commit_contents.get(commit.sha.as_ref()))
+    }
+
+    /// The changes from the commit at `base` to the one at `head`, by path.
+    fn diff_commits(&self, base: Option<usize>, head: usize) -> Vec<CommitFile> {
+        let empty = HashMap::default();
+        let old_files = base.and_then(|base| self.files_at(base)).unwrap_or(&empty);
+        let new_files = self.files_at(head).unwrap_or(&empty);
+        let mut files = old_files
+            .keys()
+            .chain(new_files.keys())
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .filter(|path| old_files.get(*path) != new_files.get(*path))
+            .map(|path| CommitFile {
+                path: path.clone(),
+                old_text: old_files.get(path).cloned(),
+                new_text: new_files.get(path).cloned(),
+            })
+            .collect::<Vec<_>>();
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        files
+    }
+
+    /// Applies the rest of the rebase's todo list, stopping at the first
+    /// commit that conflicts.
+    fn run_rebase(&mut self) -> Result<Option<RebaseState>> {
+        let rebase = self.rebase.as_mut().context("no rebase in progress")?;
+        while !rebase.state.todo.is_empty() {
+            let entry = rebase.state.todo.remove(0);
+            rebase.state.done.push(entry.clone());
+            if self.simulated_rebase_conflicts.remove(entry.sha.as_ref()) {
+                rebase.state.stopped_sha = Some(entry.sha.clone());
+                self.refs
+                    .insert("REBASE_HEAD".into(), entry.sha.to_string());
+                return Ok(Some(rebase.state.clone()));
+            }
+            apply_rebase_entry(&mut self.commit_history, &rebase.original_history, &entry)?;
+        }
+        self.rebase = None;
+        self.refs.remove("REBASE_HEAD");
+        Ok(None)
+    }
+
+    fn start_sequencer(
+        &mut self,
+        operation: SequencerOperation,
+        todo: Vec<(String, String)>,
+    ) -> Result<()> {
+        if self.sequencer.is_some() {
+            bail!("a cherry-pick or revert is already in progress");
+        }
+        self.sequencer = Some(FakeSequencer {
+            operation,
+            todo,
+            original_history: self.commit_history.clone(),
+        });
+        if let Some(operation) = self.run_sequencer()? {
+            bail!("could not {}: conflict", operation.as_str());
         }
+        Ok(())
     }
+
+    /// Applies the rest of the cherry-pick or revert, stopping at the first
+    /// commit that conflicts.
+    fn run_sequencer(&mut self) -> Result<Option<SequencerOperation>> {
+        let mut sequencer = self
+            .sequencer
+            .take()
+            .context("no cherry-pick or revert in progress")?;
+        while let Some((commit, new_sha)) = sequencer.todo.first() {
+            let source = self.commit_history[self.find_commit(commit)?].clone();
+            if self.simulated_rebase_conflicts.remove(source.sha.as_ref()) {
+                let operation = sequencer.operation;
+                self.sequencer = Some(sequencer);
+                return Ok(Some(operation));
+            }
+            let applied = match sequencer.operation {
+                SequencerOperation::CherryPick => CommitDetails {
+                    sha: new_sha.clone().into(),
+                    ..source
+                },
+                SequencerOperation::Revert => {
+                    let subject = source.message.lines().next().unwrap_or_default();
+                    CommitDetails {
+                        sha: new_sha.clone().into(),
+                        message: format!(
+                            "Revert \"{subject}\"\n\nThis reverts commit {}.",
+                            source.sha
+                        )
+                        .into(),
+                        ..Default::default()
+                    }
+                }
+            };
+            self.commit_history.push(applied);
+            sequencer.todo.remove(0);
+        }
+        Ok(None)
+    }
+}
+
+fn apply_rebase_entry(
+    history: &mut Vec<CommitDetails>,
+    original_history: &[CommitDetails],
+    entry: &RebaseTodoEntry,
+) -> Result<()> {
+    let commit = original_history
+        .iter()
+        .find(|commit| commit.sha == entry.sha)
+        .cloned()
+        .with_context(|| format!("bad revision: {}", entry.sha))?;
+    match entry.action {
+        RebaseAction::Pick => history.push(commit),
+        RebaseAction::Reword => history.push(CommitDetails {
+            message: entry
+                .message
+                .clone()
+                .unwrap_or_else(|| commit.message.clone()),
+            ..commit
+        }),
+        RebaseAction::Squash | RebaseAction::Fixup => {
+            let previous = history
+                .last_mut()
+                .context("cannot squash without a previous commit")?;
+            if entry.action == RebaseAction::Squash {
+                previous.message = format!("{}\n\n{}", previous.message, commit.message).into();
+            }
+        }
+        RebaseAction::Drop => {}
+    }
+    Ok(())
 }
 
 impl FakeGitRepository {
@@ -136,6 +314,21 @@ impl GitRepository for FakeGitRepository {
         unimplemented!()
     }
 
+    fn compare_commits(
+        &self,
+        base: String,
+        head: String,
+        _cx: AsyncApp,
+    ) -> BoxFuture<'_, Result<CommitDiff>> {
+        self.with_state_async(false, move |state| {
+            let base = state.find_commit(&base)?;
+            let head = state.find_commit(&head)?;
+            Ok(CommitDiff {
+                files: state.diff_commits(Some(base), head),
+            })
+        })
+    }
+
     fn set_index_text(
         &self,
         path: RepoPath,
@@ -480,6 +673,123 @@ impl GitRepository for FakeGitRepository {
         .boxed()
     }
 
+    fn log(
+        &self,
+        options: LogOptions,
+        skip: usize,
+        limit: Option<usize>,
+    ) -> BoxFuture<'_, Result<Vec<LogEntry>>> {
+        self.with_state_async(false, move |state| {
+            let contains =
+                |text: &str, pattern: &str| text.to_lowercase().contains(&pattern.to_lowercase());
+            let head = state
+                .refs
+                .get("HEAD")
+                .map(String::as_str)
+                .or_else(|| Some(state.commit_history.last()?.sha.as_ref()));
+            // Like git, a path filter rewrites each commit's parent to the
+            // closest ancestor that also changed the path.
+            let changes_path = |ix: usize| {
+                options.path.as_ref().is_none_or(|filter| {
+                    state
+                        .diff_commits(ix.checked_sub(1), ix)
+                        .iter()
+                        .any(|file| file.path.starts_with(filter))
+                })
+            };
+            let entries = state
+                .commit_history
+                .iter()
+                .enumerate()
+                .rev()
+                .filter(|(ix, commit)| {
+                    changes_path(*ix)
+                        && options.author.as_ref().is_none_or(|author| {
+                            contains(&commit.author_name, author)
+                                || contains(&commit.author_email, author)
+                        })
+                        && options
+                            .message
+                            .as_ref()
+                            .is_none_or(|message| contains(&commit.message, message))
+                        && options
+                            .since
+                            .is_none_or(|since| commit.commit_timestamp >= since)
+                        && options
+                            .until
+                            .is_none_or(|until| commit.commit_timestamp <= until)
+                })
+                .skip(skip)
+                .take(limit.unwrap_or(usize::MAX))
+                .map(|(ix, commit)| {
+                    let mut refs = state
+                        .refs
+                        .iter()
+                        .filter(|(_, sha)| **sha == *commit.sha)
+                        .filter_map(|(name, _)| {
+                            if let Some(tag) = name.strip_prefix("refs/tags/") {
+                                Some(format!("tag: {tag}").into())
+                            } else {
+                                name.strip_prefix("refs/heads/")
+                                    .map(|branch| branch.to_string().into())
+                            }
+                        })
+                        .collect::<Vec<SharedString>>();
+                    refs.sort();
+                    if head == Some(commit.sha.as_ref()) {
+                        let head = match &state.current_branch_name {
+                            Some(branch) => format!("HEAD -> {branch}"),
+                            None => "HEAD".to_string(),
+                        };
+                        refs.insert(0, head.into());
+                    }
+                    LogEntry {
+                        sha: commit.sha.clone(),
+                        parents: (0..ix)
+                            .rev()
+                            .find(|parent| changes_path(*parent))
+                            .map(|parent| state.commit_history[parent].sha.clone())
+                            .into_iter()
+                            .collect(),
+                        subject: commit
+                            .message
+                            .lines()
+                            .next()
+                            .unwrap_or_default()
+                            .to_string()
+                            .into(),
+                        commit_timestamp: commit.commit_timestamp,
+                        author_name: commit.author_name.clone(),
+                        author_email: commit.author_email.clone(),
+                        refs,
+                    }
+                })
+                .collect();
+            Ok(entries)
+        })
+    }
+
+    fn create_tag(&self, name: String, commit: String) -> BoxFuture<'_, Result<()>> {
+        self.with_state_async(true, move |state| {
+            let sha = state.commit_history[state.find_commit(&commit)?]
+                .sha
+                .to_string();
+            state.refs.insert(format!("refs/tags/{name}"), sha);
+            Ok(())
+        })
+    }
+
+    fn checkout_commit(&self, commit: String) -> BoxFuture<'_, Result<()>> {
+        self.with_state_async(true, move |state| {
+            let sha = state.commit_history[state.find_commit(&commit)?]
+                .sha
+                .to_string();
+            state.refs.insert("HEAD".into(), sha);
+            state.current_branch_name = None;
+            Ok(())
+        })
+    }
+
     fn stage_paths(
         &self,
         paths: Vec<RepoPath>,
@@ -560,6 +870,176 @@ impl GitRepository for FakeGitRepository {
         unimplemented!()
     }
 
+    fn rebase_todo(&self, base: String) -> BoxFuture<'_, Result<Vec<RebaseTodoEntry>>> {
+        self.with_state_async(false, move |state| {
+            let base_ix = state.find_commit(&base)?;
+            Ok(state.commit_history[base_ix + 1..]
+                .iter()
+                .map(|commit| RebaseTodoEntry {
+                    action: RebaseAction::Pick,
+                    sha: commit.sha.clone(),
+                    subject: commit
+                        .message
+                        .lines()
+                        .next()
+                        .unwrap_or_default()
+                        .to_string()
+                        .into(),
+                    message: None,
+                })
+                .collect())
+        })
+    }
+
+    fn rebase(
+        &self,
+        base: String,
+        todo: Vec<RebaseTodoEntry>,
+        _env: Arc<HashMap<String, String>>,
+    ) -> BoxFuture<'_, Result<Option<RebaseState>>> {
+        self.with_state_async(true, move |state| {
+            if state.rebase.is_some() {
+                bail!("a rebase is already in progress");
+            }
+            if let Some(entry) = todo.iter().find(|entry| entry.action != RebaseAction::Drop)
+                && matches!(entry.action, RebaseAction::Squash | RebaseAction::Fixup)
+            {
+                bail!(
+                    "cannot '{}' without a previous commit",
+                    entry.action.as_str()
+                );
+            }
+
+            let base_ix = state.find_commit(&base)?;
+            let original_history = state.commit_history.clone();
+            state.commit_history.truncate(base_ix + 1);
+            state.rebase = Some(FakeRebase {
+                state: RebaseState {
+                    head_name: state.current_branch_name.clone().map(Into::into),
+                    onto: original_history[base_ix].sha.clone(),
+                    stopped_sha: None,
+                    done: Vec::new(),
+                    todo,
+                },
+                original_history,
+            });
+            state.run_rebase()
+        })
+    }
+
+    fn rebase_state(&self) -> BoxFuture<'_, Result<Option<RebaseState>>> {
+        self.with_state_async(false, |state| {
+            Ok(state.rebase.as_ref().map(|rebase| rebase.state.clone()))
+        })
+    }
+
+    fn rebase_continue(
+        &self,
+        _env: Arc<HashMap<String, String>>,
+    ) -> BoxFuture<'_, Result<Option<RebaseState>>> {
+        self.with_state_async(true, |state| {
+            let rebase = state.rebase.as_mut().context("no rebase in progress")?;
+            if rebase.state.stopped_sha.take().is_some()
+                && let Some(entry) = rebase.state.done.last()
+            {
+                apply_rebase_entry(&mut state.commit_history, &rebase.original_history, entry)?;
+            }
+            state.run_rebase()
+        })
+    }
+
+    fn rebase_skip(
+        &self,
+        _env: Arc<HashMap<String, String>>,
+    ) -> BoxFuture<'_, Result<Option<RebaseState>>> {
+        self.with_state_async(true, |state| {
+            let rebase = state.rebase.as_mut().context("no rebase in progress")?;
+            rebase.state.stopped_sha = None;
+            state.run_rebase()
+        })
+    }
+
+    fn rebase_abort(&self, _env: Arc<HashMap<String, String>>) -> BoxFuture<'_, Result<()>> {
+        self.with_state_async(true, |state| {
+            let rebase = state.rebase.take().context("no rebase in progress")?;
+            state.commit_history = rebase.original_history;
+            state.refs.remove("REBASE_HEAD");
+            Ok(())
+        })
+    }
+
+    fn cherry_pick(
+        &self,
+        commits: Vec<String>,
+        _env: Arc<HashMap<String, String>>,
+    ) -> BoxFuture<'_, Result<()>> {
+        let new_shas = commits
+            .iter()
+            .map(|_| git::Oid::random(&mut self.executor.rng()).to_string())
+            .collect::<Vec<_>>();
+        self.with_state_async(true, move |state| {
+            let todo = commits.into_iter().zip(new_shas).collect();
+            state.start_sequencer(SequencerOperation::CherryPick, todo)
+        })
+    }
+
+    fn revert(
+        &self,
+        commits: Vec<String>,
+        _env: Arc<HashMap<String, String>>,
+    ) -> BoxFuture<'_, Result<()>> {
+        let new_shas = commits
+            .iter()
+            .map(|_| git::Oid::random(&mut self.executor.rng()).to_string())
+            .collect::<Vec<_>>();
+        self.with_state_async(true, move |state| {
+            let todo = commits.into_iter().zip(new_shas).collect();
+            state.start_sequencer(SequencerOperation::Revert, todo)
+        })
+    }
+
+    fn sequencer_operation(&self) -> BoxFuture<'_, Result<Option<SequencerOperation>>> {
+        self.with_state_async(false, |state| {
+            Ok(state
+                .sequencer
+                .as_ref()
+                .map(|sequencer| sequencer.operation))
+        })
+    }
+
+    fn sequencer_continue(
+        &self,
+        _env: Arc<HashMap<String, String>>,
+    ) -> BoxFuture<'_, Result<Option<SequencerOperation>>> {
+        self.with_state_async(true, |state| state.run_sequencer())
+    }
+
+    fn sequencer_abort(&self, _env: Arc<HashMap<String, String>>) -> BoxFuture<'_, Result<()>> {
+        self.with_state_async(true, |state| {
+            let sequencer = state
+                .sequencer
+                .take()
+                .context("no cherry-pick or revert in progress")?;
+            state.commit_history = sequencer.original_history;
+            Ok(())
+        })
+    }
+
+    fn amend_commit(
+        &self,
+        commit: String,
+        _env: Arc<HashMap<String, String>>,
+    ) -> BoxFuture<'_, Result<Option<RebaseState>>> {
+        self.with_state_async(true, move |state| {
+            if state.rebase.is_some() {
+                bail!("a rebase is already in progress");
+            }
+            state.find_commit(&commit)?;
+            state.head_contents = state.index_contents.clone();
+            Ok(None)
+        })
+    }
+
     fn commit(
         &self,
         _message: gpui::SharedString,
@@ -726,9 +1206,10 @@ impl GitRepository for FakeGitRepository {
 #[cfg(test)]
 mod tests {
     use crate::{FakeFs, Fs};
+    use git::repository::{CommitDetails, RebaseAction, SequencerOperation};
     use gpui::BackgroundExecutor;
     use serde_json::json;
-    use std::path::Path;
+    use std::{path::Path, sync::Arc};
     use util::path;
 
     #[gpui::test]
@@ -783,4 +1264,157 @@ mod tests {
             ]
         );
     }
+
+    #[gpui::test]
+    async fn test_rebase(executor: BackgroundExecutor) {
+        let fs = FakeFs::new(executor);
+        fs.insert_tree(path!("/foo"), json!({ ".git": {}, "a": "lorem" }))
+            .await;
+        let dot_git = Path::new(path!("/foo/.git"));
+        fs.set_commit_history_for_repo(
+            dot_git,
+            ["a", "b", "c", "d"]
+                .into_iter()
+                .map(|sha| CommitDetails {
+                    sha: sha.into(),
+                    message: format!("Commit {sha}").into(),
+                    ..Default::default()
+                })
+                .collect(),
+        );
+        fs.with_git_state(dot_git, true, |state| {
+            state.simulated_rebase_conflicts.insert("d".into());
+        })
+        .unwrap();
+        let repository = fs.open_repo(dot_git, Some("git".as_ref())).unwrap();
+        let env = Arc::new(Default::default());
+        let history = || {
+            fs.with_git_state(dot_git, false, |state| {
+                state
+                    .commit_history
+                    .iter()
+                    .map(|commit| commit.message.to_string())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap()
+        };
+
+        let mut todo = repository.rebase_todo("a".into()).await.unwrap();
+        assert_eq!(
+            todo.iter()
+                .map(|entry| entry.sha.as_ref())
+                .collect::<Vec<_>>(),
+            ["b", "c", "d"]
+        );
+        todo[1].action = RebaseAction::Squash;
+        todo.swap(0, 2);
+        todo.swap(1, 2);
+
+        // "d" conflicts, so the rebase stops before applying anything.
+        let state = repository
+            .rebase("a".into(), todo, env.clone())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(state.stopped_sha, Some("d".into()));
+        assert_eq!(history(), ["Commit a"]);
+
+        repository.rebase_abort(env.clone()).await.unwrap();
+        assert_eq!(history(), ["Commit a", "Commit b", "Commit c", "Commit d"]);
+        assert_eq!(repository.rebase_state().await.unwrap(), None);
+
+        let mut todo = repository.rebase_todo("a".into()).await.unwrap();
+        todo[1].action = RebaseAction::Squash;
+        todo[2].action = RebaseAction::Reword;
+        todo[2].message = Some("Reworded".into());
+        fs.with_git_state(dot_git, true, |state| {
+            state.simulated_rebase_conflicts.insert("c".into());
+        })
+        .unwrap();
+        let state = repository
+            .rebase("a".into(), todo, env.clone())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(state.stopped_sha, Some("c".into()));
+        assert_eq!(repository.rebase_continue(env.clone()).await.unwrap(), None);
+        assert_eq!(history(), ["Commit a", "Commit b\n\nCommit c", "Reworded"]);
+    }
+
+    #[gpui::test]
+    async fn test_conflicting_cherry_pick(executor: BackgroundExecutor) {
+        let fs = FakeFs::new(executor);
+        fs.insert_tree(path!("/foo"), json!({ ".git": {}, "a": "lorem" }))
+            .await;
+        let dot_git = Path::new(path!("/foo/.git"));
+        fs.set_commit_history_for_repo(
+            dot_git,
+            ["a", "b", "c"]
+                .into_iter()
+                .map(|sha| CommitDetails {
+                    sha: sha.into(),
+                    message: format!("Commit {sha}").into(),
+                    ..Default::default()
+                })
+                .collect(),
+        );
+        fs.with_git_state(dot_git, true, |state| {
+            state.simulated_rebase_conflicts.insert("b".into());
+        })
+        .unwrap();
+        let repository = fs.open_repo(dot_git, Some("git".as_ref())).unwrap();
+        let env = Arc::new(Default::default());
+        let history = || {
+            fs.with_git_state(dot_git, false, |state| {
+                state
+                    .commit_history
+                    .iter()
+                    .map(|commit| commit.message.to_string())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap()
+        };
+
+        // "a" applies, then "b" stops the cherry-pick.
+        assert!(
+            repository
+                .cherry_pick(vec!["a".into(), "b".into()], env.clone())
+                .await
+                .is_err()
+        );
+        assert_eq!(
+            repository.sequencer_operation().await.unwrap(),
+            Some(SequencerOperation::CherryPick)
+        );
+        assert_eq!(history(), ["Commit a", "Commit b", "Commit c", "Commit a"]);
+
+        repository.sequencer_abort(env.clone()).await.unwrap();
+        assert_eq!(repository.sequencer_operation().await.unwrap(), None);
+        assert_eq!(history(), ["Commit a", "Commit b", "Commit c"]);
+
+        fs.with_git_state(dot_git, true, |state| {
+            state.simulated_rebase_conflicts.insert("b".into());
+        })
+        .unwrap();
+        assert!(
+            repository
+                .revert(vec!["b".into(), "c".into()], env.clone())
+                .await
+                .is_err()
+        );
+        assert_eq!(
+            repository.sequencer_continue(env.clone()).await.unwrap(),
+            None
+        );
+        assert_eq!(
+            history(),
+            [
+                "Commit a",
+                "Commit b",
+                "Commit c",
+                "Revert \"Commit b\"\n\nThis reverts commit b.",
+                "Revert \"Commit c\"\n\nThis reverts commit c.",
+            ]
+        );
+    }
 }
@@ -54,7 +54,7 @@ use collections::{BTreeMap, btree_map};
 use fake_git_repo::FakeGitRepositoryState;
 #[cfg(any(test, feature = "test-support"))]
 use git::{
-    repository::{RepoPath, repo_path},
+    repository::{CommitDetails, RepoPath, repo_path},
     status::{FileStatus, StatusCode, TrackedStatus, UnmergedStatus},
 };
 
@@ -1917,6 +1917,33 @@ impl FakeFs {
         .unwrap();
     }
 
+    /// Sets the commits reachable from HEAD, oldest first.
+    pub fn set_commit_history_for_repo(&self, dot_git: &Path, commits: Vec<CommitDetails>) {
+        self.with_git_state(dot_git, true, |state| {
+            state.commit_history = commits;
+        })
+        .unwrap();
+    }
+
+    /// Sets the files in the commit with `sha`.
+    pub fn set_commit_contents_for_repo(
+        &self,
+        dot_git: &Path,
+        sha: &str,
+        contents_by_path: &[(&str, String)],
+    ) {
+        self.with_git_state(dot_git, true, |state| {
+            state.commit_contents.insert(
+                sha.to_string(),
+                contents_by_path
+                    .iter()
+                    .map(|(path, contents)| (repo_path(path), contents.clone()))
+                    .collect(),
+            );
+        })
+        .unwrap();
+    }
+
     pub fn set_merge_base_content_for_repo(
         &self,
         dot_git: &Path,
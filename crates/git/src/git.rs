@@ -50,6 +50,9 @@ actions!(
         /// Unstages the current file.
         UnstageFile,
         // repo-wide
+        /// Shows the commit history of the repository, with a graph of its
+        /// branches and merges.
+        CommitLog,
         /// Stages all changes in the repository.
         StageAll,
         /// Unstages all changes in the repository.
@@ -76,6 +79,15 @@ actions!(
         Pull,
         /// Pulls changes from the remote repository with rebase.
         PullRebase,
+        /// Opens an editor to reorder, squash, reword and drop the commits
+        /// that haven't been pushed.
+        InteractiveRebase,
+        /// Continues a rebase that stopped partway.
+        RebaseContinue,
+        /// Skips the commit a rebase stopped at.
+        RebaseSkip,
+        /// Aborts the rebase in progress, restoring the original branch.
+        RebaseAbort,
         /// Fetches changes from the remote repository.
         Fetch,
         /// Fetches changes from a specific remote.
@@ -231,6 +231,50 @@ pub struct FileHistory {
     pub path: RepoPath,
 }
 
+/// Restricts the commits listed by [`GitRepository::log`].
+#[derive(Clone, Debug, Default, Hash, PartialEq, Eq)]
+pub struct LogOptions {
+    /// List the commits of every branch and tag, not just those reachable
+    /// from HEAD.
+    pub all_refs: bool,
+    /// Only commits whose author name or email contains this text.
+    pub author: Option<String>,
+    /// Only commits whose message contains this text.
+    pub message: Option<String>,
+    /// Only commits that changed this path.
+    pub path: Option<RepoPath>,
+    /// Only commits made at or after this unix timestamp.
+    pub since: Option<i64>,
+    /// Only commits made at or before this unix timestamp.
+    pub until: Option<i64>,
+}
+
+impl LogOptions {
+    /// Whether every listed commit's parents are listed too, so that the
+    /// history can be drawn as a graph. Path filters keep the graph intact by
+    /// rewriting parents to the nearest commit that touched the path.
+    pub fn keeps_ancestry(&self) -> bool {
+        self.author.is_none()
+            && self.message.is_none()
+            && self.since.is_none()
+            && self.until.is_none()
+    }
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct LogEntry {
+    pub sha: SharedString,
+    pub parents: Vec<SharedString>,
+    pub subject: SharedString,
+    /// This is a unix timestamp
+    pub commit_timestamp: i64,
+    pub author_name: SharedString,
+    pub author_email: SharedString,
+    /// The branches and tags pointing at the commit, as `git log --decorate`
+    /// names them.
+    pub refs: Vec<SharedString>,
+}
+
 #[derive(Debug)]
 pub struct CommitDiff {
     pub files: Vec<CommitFile>,
@@ -243,6 +287,84 @@ pub struct CommitFile {
     pub new_text: Option<String>,
 }
 
+/// What an interactive rebase does with a commit.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum RebaseAction {
+    Pick,
+    /// Pick the commit, replacing its message.
+    Reword,
+    /// Meld the commit into the previous one, combining their messages.
+    Squash,
+    /// Meld the commit into the previous one, keeping the previous message.
+    Fixup,
+    Drop,
+}
+
+impl RebaseAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RebaseAction::Pick => "pick",
+            RebaseAction::Reword => "reword",
+            RebaseAction::Squash => "squash",
+            RebaseAction::Fixup => "fixup",
+            RebaseAction::Drop => "drop",
+        }
+    }
+
+    /// Parses the command of a line in git's todo list, in either its long
+    /// or abbreviated form.
+    pub fn from_command(command: &str) -> Option<Self> {
+        match command {
+            "pick" | "p" => Some(RebaseAction::Pick),
+            "reword" | "r" => Some(RebaseAction::Reword),
+            "squash" | "s" => Some(RebaseAction::Squash),
+            "fixup" | "f" => Some(RebaseAction::Fixup),
+            "drop" | "d" => Some(RebaseAction::Drop),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct RebaseTodoEntry {
+    pub action: RebaseAction,
+    pub sha: SharedString,
+    pub subject: SharedString,
+    /// The new message of a commit being reworded.
+    pub message: Option<SharedString>,
+}
+
+/// The progress of an interactive rebase that stopped partway, usually
+/// because a commit didn't apply cleanly.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RebaseState {
+    /// The branch being rebased, if HEAD wasn't detached.
+    pub head_name: Option<SharedString>,
+    pub onto: SharedString,
+    /// The commit the rebase stopped at.
+    pub stopped_sha: Option<SharedString>,
+    pub done: Vec<RebaseTodoEntry>,
+    pub todo: Vec<RebaseTodoEntry>,
+}
+
+/// A cherry-pick or revert, which git runs one commit at a time and stops
+/// when a commit doesn't apply cleanly.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum SequencerOperation {
+    CherryPick,
+    Revert,
+}
+
+impl SequencerOperation {
+    /// The git command that runs the operation.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SequencerOperation::CherryPick => "cherry-pick",
+            SequencerOperation::Revert => "revert",
+        }
+    }
+}
+
 impl CommitDetails {
     pub fn short_sha(&self) -> SharedString {
         self.sha[..SHORT_SHA_LENGTH].to_string().into()
@@ -487,6 +609,13 @@ pub trait GitRepository: Send + Sync {
     fn show(&self, commit: String) -> BoxFuture<'_, Result<CommitDetails>>;
 
     fn load_commit(&self, commit: String, cx: AsyncApp) -> BoxFuture<'_, Result<CommitDiff>>;
+    /// Loads the changes between two commits, which needn't be related.
+    fn compare_commits(
+        &self,
+        base: String,
+        head: String,
+        cx: AsyncApp,
+    ) -> BoxFuture<'_, Result<CommitDiff>>;
     fn blame(&self, path: RepoPath, content: Rope) -> BoxFuture<'_, Result<crate::blame::Blame>>;
     fn file_history(&self, path: RepoPath) -> BoxFuture<'_, Result<FileHistory>>;
     fn file_history_paginated(
@@ -496,6 +625,20 @@ pub trait GitRepository: Send + Sync {
         limit: Option<usize>,
     ) -> BoxFuture<'_, Result<FileHistory>>;
 
+    /// Lists the commits matching `options`, newest first, with children
+    /// before their parents.
+    fn log(
+        &self,
+        options: LogOptions,
+        skip: usize,
+        limit: Option<usize>,
+    ) -> BoxFuture<'_, Result<Vec<LogEntry>>>;
+
+    fn create_tag(&self, name: String, commit: String) -> BoxFuture<'_, Result<()>>;
+
+    /// Checks out `commit`, detaching HEAD.
+    fn checkout_commit(&self, commit: String) -> BoxFuture<'_, Result<()>>;
+
     /// Returns the absolute path to the repository. For worktrees, this will be the path to the
     /// worktree's gitdir within the main repository (typically `.git/worktrees/<name>`).
     fn path(&self) -> PathBuf;
@@ -558,6 +701,72 @@ pub trait GitRepository: Send + Sync {
         env: Arc<HashMap<String, String>>,
     ) -> BoxFuture<'_, Result<()>>;
 
+    /// Lists the commits after `base` up to HEAD, oldest first, as the todo
+    /// list of an interactive rebase onto `base`. Fails if any of them is a
+    /// merge, as a list of picks can't describe it.
+    fn rebase_todo(&self, base: String) -> BoxFuture<'_, Result<Vec<RebaseTodoEntry>>>;
+
+    /// Rebases HEAD onto `base`, replaying the commits as `todo` describes.
+    /// Returns the state of the rebase if it stopped before finishing. Fails
+    /// if there are merges after `base`, which the rebase would flatten.
+    fn rebase(
+        &self,
+        base: String,
+        todo: Vec<RebaseTodoEntry>,
+        env: Arc<HashMap<String, String>>,
+    ) -> BoxFuture<'_, Result<Option<RebaseState>>>;
+
+    /// Returns the state of the interactive rebase in progress, if any.
+    fn rebase_state(&self) -> BoxFuture<'_, Result<Option<RebaseState>>>;
+
+    /// Commits the resolved changes of a stopped rebase and carries on.
+    fn rebase_continue(
+        &self,
+        env: Arc<HashMap<String, String>>,
+    ) -> BoxFuture<'_, Result<Option<RebaseState>>>;
+
+    /// Drops the commit a rebase stopped at and carries on.
+    fn rebase_skip(
+        &self,
+        env: Arc<HashMap<String, String>>,
+    ) -> BoxFuture<'_, Result<Option<RebaseState>>>;
+
+    fn rebase_abort(&self, env: Arc<HashMap<String, String>>) -> BoxFuture<'_, Result<()>>;
+
+    fn cherry_pick(
+        &self,
+        commits: Vec<String>,
+        env: Arc<HashMap<String, String>>,
+    ) -> BoxFuture<'_, Result<()>>;
+
+    fn revert(
+        &self,
+        commits: Vec<String>,
+        env: Arc<HashMap<String, String>>,
+    ) -> BoxFuture<'_, Result<()>>;
+
+    /// Returns the cherry-pick or revert that stopped on a conflict, if any.
+    fn sequencer_operation(&self) -> BoxFuture<'_, Result<Option<SequencerOperation>>>;
+
+    /// Commits the resolved changes of a stopped cherry-pick or revert and
+    /// carries on. Returns the operation if it stopped again.
+    fn sequencer_continue(
+        &self,
+        env: Arc<HashMap<String, String>>,
+    ) -> BoxFuture<'_, Result<Option<SequencerOperation>>>;
+
+    /// Aborts the cherry-pick or revert in progress, restoring HEAD.
+    fn sequencer_abort(&self, env: Arc<HashMap<String, String>>) -> BoxFuture<'_, Result<()>>;
+
+    /// Folds the staged changes into `commit`, an ancestor of HEAD, by
+    /// rebasing the commits after it. Fails if `commit` or any commit after
+    /// it is a merge.
+    fn amend_commit(
+        &self,
+        commit: String,
+        env: Arc<HashMap<String, String>>,
+    ) -> BoxFuture<'_, Result<Option<RebaseState>>>;
+
     fn push(
         &self,
         branch_name: String,
@@ -680,6 +889,58 @@ impl RealGitRepository {
             .context("failed to read git work directory")
             .map(Path::to_path_buf)
     }
+
+    /// Runs `git cherry-pick` or `git revert` with `flag`, for the operation
+    /// in progress.
+    fn sequencer_step(
+        &self,
+        flag: &'static str,
+        env: Arc<HashMap<String, String>>,
+    ) -> BoxFuture<'_, Result<Option<SequencerOperation>>> {
+        let git_binary_path = self.any_git_binary_path.clone();
+        let working_directory = self.working_directory();
+        let executor = self.executor.clone();
+        let git_dir = self.path();
+
+        self.executor
+            .spawn(async move {
+                let operation = read_sequencer_operation(&git_dir)
+                    .await
+                    .context("no cherry-pick or revert in progress")?;
+                let result = GitBinary::new(git_binary_path, working_directory?, executor)
+                    .envs(rebase_envs(&env))
+                    .run(&[operation.as_str(), flag])
+                    .await;
+                let operation = read_sequencer_operation(&git_dir).await;
+                match result {
+                    Err(error) if flag == "--abort" || operation.is_none() => Err(error),
+                    _ => Ok(operation),
+                }
+            })
+            .boxed()
+    }
+
+    /// Runs `git rebase --continue` or `--skip`.
+    fn rebase_step(
+        &self,
+        flag: &'static str,
+        env: Arc<HashMap<String, String>>,
+    ) -> BoxFuture<'_, Result<Option<RebaseState>>> {
+        let git_binary_path = self.any_git_binary_path.clone();
+        let working_directory = self.working_directory();
+        let executor = self.executor.clone();
+        let git_dir = self.path();
+
+        self.executor
+            .spawn(async move {
+                let result = GitBinary::new(git_binary_path, working_directory?, executor)
+                    .envs(rebase_envs(&env))
+                    .run(&["rebase", flag])
+                    .await;
+                rebase_step_result(result, &git_dir).await
+            })
+            .boxed()
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -813,95 +1074,63 @@ impl GitRepository for RealGitRepository {
                 .context("starting git show process")?;
 
             let show_stdout = String::from_utf8_lossy(&show_output.stdout);
-            let changes = parse_git_diff_name_status(&show_stdout);
             let parent_sha = format!("{}^", commit);
+            load_commit_files(
+                &git_binary_path,
+                &working_directory,
+                &show_stdout,
+                &commit,
+                &parent_sha,
+            )
+            .await
+        })
+        .boxed()
+    }
 
-            let mut cat_file_process = util::command::new_smol_command(&git_binary_path)
+    fn compare_commits(
+        &self,
+        base: String,
+        head: String,
+        cx: AsyncApp,
+    ) -> BoxFuture<'_, Result<CommitDiff>> {
+        let Some(working_directory) = self.repository.lock().workdir().map(ToOwned::to_owned)
+        else {
+            return future::ready(Err(anyhow!("no working directory"))).boxed();
+        };
+        let git_binary_path = self.any_git_binary_path.clone();
+        cx.background_spawn(async move {
+            let diff_output = util::command::new_smol_command(&git_binary_path)
                 .current_dir(&working_directory)
-                .args(["--no-optional-locks", "cat-file", "--batch=%(objectsize)"])
-                .stdin(Stdio::piped())
+                .args([
+                    "--no-optional-locks",
+                    "diff",
+                    "-z",
+                    "--no-renames",
+                    "--name-status",
+                ])
+                .arg(&base)
+                .arg(&head)
+                .arg("--")
+                .stdin(Stdio::null())
                 .stdout(Stdio::piped())
                 .stderr(Stdio::piped())
-                .spawn()
-                .context("starting git cat-file process")?;
-
-            let mut files = Vec::<CommitFile>::new();
-            let mut stdin = BufWriter::with_capacity(512, cat_file_process.stdin.take().unwrap());
-            let mut stdout = BufReader::new(cat_file_process.stdout.take().unwrap());
-            let mut info_line = String::new();
-            let mut newline = [b'\0'];
-            for (path, status_code) in changes {
-                // git-show outputs `/`-delimited paths even on Windows.
-                let Some(rel_path) = RelPath::unix(path).log_err() else {
-                    continue;
-                };
-
-                match status_code {
-                    StatusCode::Modified => {
-                        stdin.write_all(commit.as_bytes()).await?;
-                        stdin.write_all(b":").await?;
-                        stdin.write_all(path.as_bytes()).await?;
-                        stdin.write_all(b"\n").await?;
-                        stdin.write_all(parent_sha.as_bytes()).await?;
-                        stdin.write_all(b":").await?;
-                        stdin.write_all(path.as_bytes()).await?;
-                        stdin.write_all(b"\n").await?;
-                    }
-                    StatusCode::Added => {
-                        stdin.write_all(commit.as_bytes()).await?;
-                        stdin.write_all(b":").await?;
-                        stdin.write_all(path.as_bytes()).await?;
-                        stdin.write_all(b"\n").await?;
-                    }
-                    StatusCode::Deleted => {
-                        stdin.write_all(parent_sha.as_bytes()).await?;
-                        stdin.write_all(b":").await?;
-                        stdin.write_all(path.as_bytes()).await?;
-                        stdin.write_all(b"\n").await?;
-                    }
-                    _ => continue,
-                }
-                stdin.flush().await?;
-
-                info_line.clear();
-                stdout.read_line(&mut info_line).await?;
-
-                let len = info_line.trim_end().parse().with_context(|| {
-                    format!("invalid object size output from cat-file {info_line}")
-                })?;
-                let mut text = vec![0; len];
-                stdout.read_exact(&mut text).await?;
-                stdout.read_exact(&mut newline).await?;
-                let text = String::from_utf8_lossy(&text).to_string();
-
-                let mut old_text = None;
-                let mut new_text = None;
-                match status_code {
-                    StatusCode::Modified => {
-                        info_line.clear();
-                        stdout.read_line(&mut info_line).await?;
-                        let len = info_line.trim_end().parse().with_context(|| {
-                            format!("invalid object size output from cat-file {}", info_line)
-                        })?;
-                        let mut parent_text = vec![0; len];
-                        stdout.read_exact(&mut parent_text).await?;
-                        stdout.read_exact(&mut newline).await?;
-                        old_text = Some(String::from_utf8_lossy(&parent_text).to_string());
-                        new_text = Some(text);
-                    }
-                    StatusCode::Added => new_text = Some(text),
-                    StatusCode::Deleted => old_text = Some(text),
-                    _ => continue,
-                }
-
-                files.push(CommitFile {
-                    path: RepoPath(Arc::from(rel_path)),
-                    old_text,
-                    new_text,
-                })
+                .output()
+                .await
+                .context("starting git diff process")?;
+            if !diff_output.status.success() {
+                let stderr = String::from_utf8_lossy(&diff_output.stderr);
+                bail!("git diff failed: {stderr}");
             }
 
-            Ok(CommitDiff { files })
+            let diff_stdout = String::from_utf8_lossy(&diff_output.stdout);
+            load_commit_files(
+                &git_binary_path,
+                &working_directory,
+                &diff_stdout,
+                &head,
+                &base,
+            )
+            .await
         })
         .boxed()
     }
@@ -1595,6 +1824,92 @@ impl GitRepository for RealGitRepository {
             .boxed()
     }
 
+    fn log(
+        &self,
+        options: LogOptions,
+        skip: usize,
+        limit: Option<usize>,
+    ) -> BoxFuture<'_, Result<Vec<LogEntry>>> {
+        let git_binary_path = self.any_git_binary_path.clone();
+        let working_directory = self.working_directory();
+        let executor = self.executor.clone();
+        self.executor
+            .spawn(async move {
+                let mut args = vec![
+                    "--no-optional-locks".to_string(),
+                    "log".to_string(),
+                    "--topo-order".to_string(),
+                    "--parents".to_string(),
+                    "--decorate-refs-exclude=refs/stash".to_string(),
+                    "--format=%H%x00%P%x00%ct%x00%an%x00%ae%x00%D%x00%s".to_string(),
+                    "--regexp-ignore-case".to_string(),
+                    "--fixed-strings".to_string(),
+                ];
+                if skip > 0 {
+                    args.push(format!("--skip={skip}"));
+                }
+                if let Some(limit) = limit {
+                    args.push(format!("--max-count={limit}"));
+                }
+                if let Some(author) = &options.author {
+                    args.push(format!("--author={author}"));
+                }
+                if let Some(message) = &options.message {
+                    args.push(format!("--grep={message}"));
+                }
+                if let Some(since) = options.since {
+                    args.push(format!("--since=@{since}"));
+                }
+                if let Some(until) = options.until {
+                    args.push(format!("--until=@{until}"));
+                }
+                if options.all_refs {
+                    args.extend(["--branches", "--remotes", "--tags"].map(ToOwned::to_owned));
+                }
+                args.push("HEAD".to_string());
+                args.push("--".to_string());
+                if let Some(path) = &options.path {
+                    args.push(path.as_unix_str().to_string());
+                }
+
+                let output = GitBinary::new(git_binary_path, working_directory?, executor)
+                    .run(&args)
+                    .await?;
+                Ok(parse_log(&output))
+            })
+            .boxed()
+    }
+
+    fn create_tag(&self, name: String, commit: String) -> BoxFuture<'_, Result<()>> {
+        let git_binary_path = self.any_git_binary_path.clone();
+        let working_directory = self.working_directory();
+        let executor = self.executor.clone();
+
+        self.executor
+            .spawn(async move {
+                GitBinary::new(git_binary_path, working_directory?, executor)
+                    .run(&["tag", &name, &commit])
+                    .await?;
+                anyhow::Ok(())
+            })
+            .boxed()
+    }
+
+    fn checkout_commit(&self, commit: String) -> BoxFuture<'_, Result<()>> {
+        let git_binary_path = self.any_git_binary_path.clone();
+        let working_directory = self.working_directory();
+        let executor = self.executor.clone();
+
+        self.executor
+            .spawn(async move {
+                GitBinary::new(git_binary_path, working_directory?, executor)
+                    .run(&["checkout", "--detach", &commit])
+                    .await?;
+                anyhow::Ok(())
+            })
+            .boxed()
+    }
+
     fn diff(&self, diff: DiffType) -> BoxFuture<'_, Result<String>> {
         let working_directory = self.working_directory();
         let git_binary_path = self.any_git_binary_path.clone();
@@ -1799,41 +2114,288 @@ impl GitRepository for RealGitRepository {
             .boxed()
     }
 
-    fn commit(
+    fn rebase_todo(&self, base: String) -> BoxFuture<'_, Result<Vec<RebaseTodoEntry>>> {
+        let git_binary_path = self.any_git_binary_path.clone();
+        let working_directory = self.working_directory();
+        let executor = self.executor.clone();
+
+        self.executor
+            .spawn(async move {
+                let git = GitBinary::new(git_binary_path, working_directory?, executor);
+                if count_merges_since(&git, &base).await? > 0 {
+                    bail!("the commits after {base} include merges, which can't be rebased");
+                }
+                let output = git
+                    .run(&[
+                        "log",
+                        "--reverse",
+                        "--topo-order",
+                        "--no-merges",
+                        "--format=%H%x00%s",
+                        &format!("{base}..HEAD"),
+                        "--",
+                    ])
+                    .await?;
+                Ok(output
+                    .lines()
+                    .filter_map(|line| {
+                        let (sha, subject) = line.split_once('\0')?;
+                        Some(RebaseTodoEntry {
+                            action: RebaseAction::Pick,
+                            sha: sha.to_string().into(),
+                            subject: subject.to_string().into(),
+                            message: None,
+                        })
+                    })
+                    .collect())
+            })
+            .boxed()
+    }
+
+    fn rebase(
         &self,
-        message: SharedString,
-        name_and_email: Option<(SharedString, SharedString)>,
-        options: CommitOptions,
-        ask_pass: AskPassDelegate,
+        base: String,
+        todo: Vec<RebaseTodoEntry>,
         env: Arc<HashMap<String, String>>,
-    ) -> BoxFuture<'_, Result<()>> {
-        let working_directory = self.working_directory();
+    ) -> BoxFuture<'_, Result<Option<RebaseState>>> {
         let git_binary_path = self.any_git_binary_path.clone();
+        let working_directory = self.working_directory();
         let executor = self.executor.clone();
-        // Note: Do not spawn this command on the background thread, it might pop open the credential helper
-        // which we want to block on.
-        async move {
-            let mut cmd = new_smol_command(git_binary_path);
-            cmd.current_dir(&working_directory?)
-                .envs(env.iter())
-                .args(["commit", "--quiet", "-m"])
-                .arg(&message.to_string())
-                .arg("--cleanup=strip")
-                .arg("--no-verify")
-                .stdout(smol::process::Stdio::piped())
-                .stderr(smol::process::Stdio::piped());
+        let git_dir = self.path();
 
-            if options.amend {
-                cmd.arg("--amend");
-            }
+        self.executor
+            .spawn(async move {
+                if read_rebase_state(&git_dir).await.is_some() {
+                    bail!("a rebase is already in progress");
+                }
+                let git = GitBinary::new(git_binary_path, working_directory?, executor);
+                if count_merges_since(&git, &base).await? > 0 {
+                    bail!("the commits after {base} include merges, which can't be rebased");
+                }
 
-            if options.signoff {
-                cmd.arg("--signoff");
-            }
+                // Git reads the todo list from the file it opens in the
+                // sequence editor, so have the "editor" copy ours over it.
+                let todo_path = git_dir.join(format!("rebase-todo-{}.tmp", Uuid::new_v4()));
+                smol::fs::write(&todo_path, format_rebase_todo(&todo)).await?;
+                let (editor_path, editor) = write_sequence_editor(&todo_path).await?;
+                let mut env = rebase_envs(&env);
+                env.insert("GIT_SEQUENCE_EDITOR".into(), editor);
+
+                let result = git.envs(env).run(&["rebase", "--interactive", &base]).await;
+                smol::fs::remove_file(&todo_path).await.log_err();
+                smol::fs::remove_file(&editor_path).await.log_err();
+                rebase_step_result(result, &git_dir).await
+            })
+            .boxed()
+    }
 
-            if let Some((name, email)) = name_and_email {
-                cmd.arg("--author").arg(&format!("{name} <{email}>"));
-            }
+    fn rebase_state(&self) -> BoxFuture<'_, Result<Option<RebaseState>>> {
+        let git_dir = self.path();
+        self.executor
+            .spawn(async move { Ok(read_rebase_state(&git_dir).await) })
+            .boxed()
+    }
+
+    fn rebase_continue(
+        &self,
+        env: Arc<HashMap<String, String>>,
+    ) -> BoxFuture<'_, Result<Option<RebaseState>>> {
+        self.rebase_step("--continue", env)
+    }
+
+    fn rebase_skip(
+        &self,
+        env: Arc<HashMap<String, String>>,
+    ) -> BoxFuture<'_, Result<Option<RebaseState>>> {
+        self.rebase_step("--skip", env)
+    }
+
+    fn rebase_abort(&self, env: Arc<HashMap<String, String>>) -> BoxFuture<'_, Result<()>> {
+        let git_binary_path = self.any_git_binary_path.clone();
+        let working_directory = self.working_directory();
+        let executor = self.executor.clone();
+
+        self.executor
+            .spawn(async move {
+                GitBinary::new(git_binary_path, working_directory?, executor)
+                    .envs(rebase_envs(&env))
+                    .run(&["rebase", "--abort"])
+                    .await?;
+                anyhow::Ok(())
+            })
+            .boxed()
+    }
+
+    fn cherry_pick(
+        &self,
+        commits: Vec<String>,
+        env: Arc<HashMap<String, String>>,
+    ) -> BoxFuture<'_, Result<()>> {
+        let git_binary_path = self.any_git_binary_path.clone();
+        let working_directory = self.working_directory();
+        let executor = self.executor.clone();
+
+        self.executor
+            .spawn(async move {
+                GitBinary::new(git_binary_path, working_directory?, executor)
+                    .envs((*env).clone())
+                    .run(["cherry-pick".to_string()].into_iter().chain(commits))
+                    .await?;
+                anyhow::Ok(())
+            })
+            .boxed()
+    }
+
+    fn revert(
+        &self,
+        commits: Vec<String>,
+        env: Arc<HashMap<String, String>>,
+    ) -> BoxFuture<'_, Result<()>> {
+        let git_binary_path = self.any_git_binary_path.clone();
+        let working_directory = self.working_directory();
+        let executor = self.executor.clone();
+
+        self.executor
+            .spawn(async move {
+                GitBinary::new(git_binary_path, working_directory?, executor)
+                    .envs((*env).clone())
+                    .run(
+                        ["revert".to_string(), "--no-edit".to_string()]
+                            .into_iter()
+                            .chain(commits),
+                    )
+                    .await?;
+                anyhow::Ok(())
+            })
+            .boxed()
+    }
+
+    fn sequencer_operation(&self) -> BoxFuture<'_, Result<Option<SequencerOperation>>> {
+        let git_dir = self.path();
+        self.executor
+            .spawn(async move { Ok(read_sequencer_operation(&git_dir).await) })
+            .boxed()
+    }
+
+    fn sequencer_continue(
+        &self,
+        env: Arc<HashMap<String, String>>,
+    ) -> BoxFuture<'_, Result<Option<SequencerOperation>>> {
+        self.sequencer_step("--continue", env)
+    }
+
+    fn sequencer_abort(&self, env: Arc<HashMap<String, String>>) -> BoxFuture<'_, Result<()>> {
+        let step = self.sequencer_step("--abort", env);
+        async move {
+            step.await?;
+            Ok(())
+        }
+        .boxed()
+    }
+
+    fn amend_commit(
+        &self,
+        commit: String,
+        env: Arc<HashMap<String, String>>,
+    ) -> BoxFuture<'_, Result<Option<RebaseState>>> {
+        let git_binary_path = self.any_git_binary_path.clone();
+        let working_directory = self.working_directory();
+        let executor = self.executor.clone();
+        let git_dir = self.path();
+
+        self.executor
+            .spawn(async move {
+                if read_rebase_state(&git_dir).await.is_some() {
+                    bail!("a rebase is already in progress");
+                }
+
+                let git = GitBinary::new(git_binary_path, working_directory?, executor)
+                    .envs(rebase_envs(&env));
+                git.run(&["merge-base", "--is-ancestor", &commit, "HEAD"])
+                    .await
+                    .with_context(|| format!("{commit} is not an ancestor of HEAD"))?;
+                if count_merges_since(&git, &format!("{commit}^@")).await? > 0 {
+                    bail!("{commit} or a commit after it is a merge, which can't be amended");
+                }
+                git.run(&[
+                    "commit",
+                    "--quiet",
+                    "--no-verify",
+                    &format!("--fixup={commit}"),
+                ])
+                .await?;
+                let fixup = git.run(&["rev-parse", "HEAD"]).await?;
+
+                // Squash the fixup commit into place. A root commit has no
+                // parent to rebase onto.
+                let parent = format!("{commit}^");
+                let has_parent = git
+                    .run(&["rev-parse", "--verify", "--quiet", &parent])
+                    .await
+                    .is_ok();
+                let base = if has_parent {
+                    parent.as_str()
+                } else {
+                    "--root"
+                };
+                let result = git
+                    .run(&[
+                        "rebase",
+                        "--interactive",
+                        "--autosquash",
+                        "--autostash",
+                        base,
+                    ])
+                    .await;
+
+                // If the rebase didn't start, take the fixup commit back out
+                // so its changes are left staged rather than committed.
+                if result.is_err()
+                    && read_rebase_state(&git_dir).await.is_none()
+                    && git.run(&["rev-parse", "HEAD"]).await.ok() == Some(fixup)
+                {
+                    git.run(&["reset", "--soft", "HEAD~"]).await.log_err();
+                }
+                rebase_step_result(result, &git_dir).await
+            })
+            .boxed()
+    }
+
+    fn commit(
+        &self,
+        message: SharedString,
+        name_and_email: Option<(SharedString, SharedString)>,
+        options: CommitOptions,
+        ask_pass: AskPassDelegate,
+        env: Arc<HashMap<String, String>>,
+    ) -> BoxFuture<'_, Result<()>> {
+        let working_directory = self.working_directory();
+        let git_binary_path = self.any_git_binary_path.clone();
+        let executor = self.executor.clone();
+        // Note: Do not spawn this command on the background thread, it might pop open the credential helper
+        // which we want to block on.
+        async move {
+            let mut cmd = new_smol_command(git_binary_path);
+            cmd.current_dir(&working_directory?)
+                .envs(env.iter())
+                .args(["commit", "--quiet", "-m"])
+                .arg(&message.to_string())
+                .arg("--cleanup=strip")
+                .arg("--no-verify")
+                .stdout(smol::process::Stdio::piped())
+                .stderr(smol::process::Stdio::piped());
+
+            if options.amend {
+                cmd.arg("--amend");
+            }
+
+            if options.signoff {
+                cmd.arg("--signoff");
+            }
+
+            if let Some((name, email)) = name_and_email {
+                cmd.arg("--author").arg(&format!("{name} <{email}>"));
+            }
 
             run_git_command(env, ask_pass, cmd, &executor).await?;
 
@@ -2676,6 +3238,304 @@ impl MapSeekTarget<RepoPath> for RepoPathDescendants<'_> {
     }
 }
 
+/// The environment for rebase commands, which must never wait on an editor.
+fn rebase_envs(env: &HashMap<String, String>) -> HashMap<String, String> {
+    let mut env = env.clone();
+    // Git doesn't launch an editor named ":" and keeps the text as it is.
+    env.insert("GIT_EDITOR".into(), ":".into());
+    env.insert("GIT_SEQUENCE_EDITOR".into(), ":".into());
+    env
+}
+
+/// Counts the merge commits reachable from HEAD but not from `base`. The
+/// rebases here replay a flat list of picks, which would drop those merges.
+async fn count_merges_since(git: &GitBinary, base: &str) -> Result<usize> {
+    let count = git
+        .run(&["rev-list", "--merges", "--count", "HEAD", "--not", base])
+        .await?;
+    Ok(count.trim().parse()?)
+}
+
+/// Writes a script for git to run as its sequence editor, which replaces the
+/// todo list git opens with the one at `todo_path`, and returns its path and
+/// the command that runs it.
+///
+/// Git runs the command in its POSIX shell on every platform, but the copy is
+/// left to PowerShell on Windows so it doesn't rely on Unix tools.
+async fn write_sequence_editor(todo_path: &Path) -> Result<(PathBuf, String)> {
+    let todo_path = todo_path.to_string_lossy();
+    let (script_path, script, command) = if cfg!(windows) {
+        let script_path = PathBuf::from(format!("{todo_path}.ps1"));
+        let script = format!(
+            "Copy-Item -LiteralPath '{}' -Destination $args[0] -Force\n",
+            todo_path.replace('\'', "''")
+        );
+        let command = format!(
+            "powershell.exe -NoProfile -ExecutionPolicy Bypass -File {}",
+            shell_quote(&script_path.to_string_lossy())
+        );
+        (script_path, script, command)
+    } else {
+        let script_path = PathBuf::from(format!("{todo_path}.sh"));
+        let script = format!("cat {} > \"$1\"\n", shell_quote(&todo_path));
+        let command = format!("sh {}", shell_quote(&script_path.to_string_lossy()));
+        (script_path, script, command)
+    };
+    smol::fs::write(&script_path, script)
+        .await
+        .with_context(|| format!("writing sequence editor script at {script_path:?}"))?;
+    Ok((script_path, command))
+}
+
+/// A rebase command that stopped partway exits with an error, which isn't a
+/// failure as long as the rebase can be continued.
+async fn rebase_step_result(result: Result<String>, git_dir: &Path) -> Result<Option<RebaseState>> {
+    let state = read_rebase_state(git_dir).await;
+    match result {
+        Err(error) if state.is_none() => Err(error),
+        _ => Ok(state),
+    }
+}
+
+/// Git leaves `CHERRY_PICK_HEAD` or `REVERT_HEAD` behind when a commit
+/// doesn't apply cleanly, until the operation is continued or aborted.
+async fn read_sequencer_operation(git_dir: &Path) -> Option<SequencerOperation> {
+    for (head, operation) in [
+        ("CHERRY_PICK_HEAD", SequencerOperation::CherryPick),
+        ("REVERT_HEAD", SequencerOperation::Revert),
+    ] {
+        if smol::fs::metadata(git_dir.join(head)).await.is_ok() {
+            return Some(operation);
+        }
+    }
+    None
+}
+
+async fn read_rebase_state(git_dir: &Path) -> Option<RebaseState> {
+    let dir = git_dir.join("rebase-merge");
+    let todo = smol::fs::read_to_string(dir.join("git-rebase-todo"))
+        .await
+        .ok()?;
+    let done = smol::fs::read_to_string(dir.join("done"))
+        .await
+        .unwrap_or_default();
+    let read_line = async |name: &str| {
+        let text = smol::fs::read_to_string(dir.join(name)).await.ok()?;
+        Some(SharedString::from(text.trim().to_string()))
+    };
+    Some(RebaseState {
+        head_name: read_line("head-name").await.and_then(|head_name| {
+            head_name
+                .strip_prefix("refs/heads/")
+                .map(|name| name.to_string().into())
+        }),
+        onto: read_line("onto").await.unwrap_or_default(),
+        stopped_sha: read_line("stopped-sha").await,
+        done: parse_rebase_todo(&done),
+        todo: parse_rebase_todo(&todo),
+    })
+}
+
+/// Parses the commits of a rebase todo list, skipping comments and commands
+/// like `exec` that don't apply a commit.
+fn parse_rebase_todo(text: &str) -> Vec<RebaseTodoEntry> {
+    text.lines()
+        .filter_map(|line| {
+            let (command, rest) = line.trim().split_once(char::is_whitespace)?;
+            let action = RebaseAction::from_command(command)?;
+            let rest = rest.trim_start();
+            // `fixup -C <sha>` keeps the message of the commit being fixed up
+            let rest = rest
+                .strip_prefix("-C ")
+                .or_else(|| rest.strip_prefix("-c "))
+                .unwrap_or(rest)
+                .trim_start();
+            let (sha, subject) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+            let subject = subject.trim();
+            let subject = subject.strip_prefix("# ").unwrap_or(subject);
+            Some(RebaseTodoEntry {
+                action,
+                sha: sha.to_string().into(),
+                subject: subject.to_string().into(),
+                message: None,
+            })
+        })
+        .collect()
+}
+
+fn format_rebase_todo(todo: &[RebaseTodoEntry]) -> String {
+    let mut text = String::new();
+    for entry in todo {
+        let subject = entry.subject.lines().next().unwrap_or_default();
+        match (entry.action, &entry.message) {
+            (RebaseAction::Reword, Some(message)) => {
+                // `reword` would open an editor, so amend the message as soon
+                // as the commit has been picked instead. Only whitespace is
+                // cleaned up, so lines starting with `#` are kept.
+                let lines = message.lines().map(shell_quote).collect::<Vec<_>>();
+                text.push_str(&format!("pick {} {subject}\n", entry.sha));
+                text.push_str(&format!(
+                    "exec printf '%s\\n' {} | git commit --amend --quiet --no-verify --cleanup=whitespace --file=-\n",
+                    lines.join(" ")
+                ));
+            }
+            (action, _) => {
+                text.push_str(&format!("{} {} {subject}\n", action.as_str(), entry.sha));
+            }
+        }
+    }
+    text
+}
+
+/// Quotes `text` as a single word for the POSIX shell git runs commands in.
+fn shell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "'\\''"))
+}
+
+/// Loads the files listed in `git diff --name-status -z` output, as they are
+/// in `old_rev` and `new_rev`.
+async fn load_commit_files(
+    git_binary_path: &Path,
+    working_directory: &Path,
+    name_status: &str,
+    new_rev: &str,
+    old_rev: &str,
+) -> Result<CommitDiff> {
+    let changes = parse_git_diff_name_status(name_status);
+
+    let mut cat_file_process = util::command::new_smol_command(&git_binary_path)
+        .current_dir(&working_directory)
+        .args(["--no-optional-locks", "cat-file", "--batch=%(objectsize)"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("starting git cat-file process")?;
+
+    let mut files = Vec::<CommitFile>::new();
+    let mut stdin = BufWriter::with_capacity(512, cat_file_process.stdin.take().unwrap());
+    let mut stdout = BufReader::new(cat_file_process.stdout.take().unwrap());
+    let mut info_line = String::new();
+    let mut newline = [b'\0'];
+    for (path, status_code) in changes {
+        // git-show outputs `/`-delimited paths even on Windows.
+        let Some(rel_path) = RelPath::unix(path).log_err() else {
+            continue;
+        };
+
+        match status_code {
+            StatusCode::Modified => {
+                stdin.write_all(new_rev.as_bytes()).await?;
+                stdin.write_all(b":").await?;
+                stdin.write_all(path.as_bytes()).await?;
+                stdin.write_all(b"\n").await?;
+                stdin.write_all(old_rev.as_bytes()).await?;
+                stdin.write_all(b":").await?;
+                stdin.write_all(path.as_bytes()).await?;
+                stdin.write_all(b"\n").await?;
+            }
+            StatusCode::Added => {
+                stdin.write_all(new_rev.as_bytes()).await?;
+                stdin.write_all(b":").await?;
+                stdin.write_all(path.as_bytes()).await?;
+                stdin.write_all(b"\n").await?;
+            }
+            StatusCode::Deleted => {
+                stdin.write_all(old_rev.as_bytes()).await?;
+                stdin.write_all(b":").await?;
+                stdin.write_all(path.as_bytes()).await?;
+                stdin.write_all(b"\n").await?;
+            }
+            _ => continue,
+        }
+        stdin.flush().await?;
+
+        info_line.clear();
+        stdout.read_line(&mut info_line).await?;
+
+        let len = info_line
+            .trim_end()
+            .parse()
+            .with_context(|| format!("invalid object size output from cat-file {info_line}"))?;
+        let mut text = vec![0; len];
+        stdout.read_exact(&mut text).await?;
+        stdout.read_exact(&mut newline).await?;
+        let text = String::from_utf8_lossy(&text).to_string();
+
+        let mut old_text = None;
+        let mut new_text = None;
+        match status_code {
+            StatusCode::Modified => {
+                info_line.clear();
+                stdout.read_line(&mut info_line).await?;
+                let len = info_line.trim_end().parse().with_context(|| {
+                    format!("invalid object size output from cat-file {}", info_line)
+                })?;
+                let mut parent_text = vec![0; len];
+                stdout.read_exact(&mut parent_text).await?;
+                stdout.read_exact(&mut newline).await?;
+                old_text = Some(String::from_utf8_lossy(&parent_text).to_string());
+                new_text = Some(text);
+            }
+            StatusCode::Added => new_text = Some(text),
+            StatusCode::Deleted => old_text = Some(text),
+            _ => continue,
+        }
+
+        files.push(CommitFile {
+            path: RepoPath(Arc::from(rel_path)),
+            old_text,
+            new_text,
+        })
+    }
+
+    Ok(CommitDiff { files })
+}
+
+fn parse_log(output: &str) -> Vec<LogEntry> {
+    let mut entries = Vec::new();
+    for line in output.lines() {
+        let mut fields = line.splitn(7, '\0');
+        let (
+            Some(sha),
+            Some(parents),
+            Some(timestamp),
+            Some(author_name),
+            Some(author_email),
+            Some(refs),
+            Some(subject),
+        ) = (
+            fields.next(),
+            fields.next(),
+            fields.next(),
+            fields.next(),
+            fields.next(),
+            fields.next(),
+            fields.next(),
+        )
+        else {
+            continue;
+        };
+        entries.push(LogEntry {
+            sha: sha.to_string().into(),
+            parents: parents
+                .split_whitespace()
+                .map(|parent| parent.to_string().into())
+                .collect(),
+            subject: subject.to_string().into(),
+            commit_timestamp: timestamp.parse().unwrap_or(0),
+            author_name: author_name.to_string().into(),
+            author_email: author_email.to_string().into(),
+            refs: refs
+                .split(", ")
+                .filter(|name| !name.is_empty())
+                .map(|name| name.to_string().into())
+                .collect(),
+        });
+    }
+    entries
+}
+
 fn parse_branch_input(input: &str) -> Result<Vec<Branch>> {
     let mut branches = Vec::new();
     for line in input.split('\n') {
@@ -2778,6 +3638,7 @@ fn checkpoint_author_envs() -> HashMap<String, String> {
 mod tests {
     use super::*;
     use gpui::TestAppContext;
+    use unindent::Unindent as _;
 
     fn disable_git_global_config() {
         unsafe {
@@ -3027,6 +3888,491 @@ mod tests {
         );
     }
 
+    #[gpui::test]
+    async fn test_interactive_rebase(cx: &mut TestAppContext) {
+        disable_git_global_config();
+
+        cx.executor().allow_parking();
+
+        let repo_dir = tempfile::tempdir().unwrap();
+        git2::Repository::init(repo_dir.path()).unwrap();
+        let file_path = repo_dir.path().join("file");
+        let repo = RealGitRepository::new(
+            &repo_dir.path().join(".git"),
+            None,
+            Some("git".into()),
+            cx.executor(),
+        )
+        .unwrap();
+        let env = Arc::new(checkpoint_author_envs());
+
+        let mut shas = Vec::new();
+        for (message, content) in [("One", "1\n"), ("Two", "1\n2\n"), ("Three", "1\n2\n3\n")] {
+            smol::fs::write(&file_path, content).await.unwrap();
+            repo.stage_paths(vec![repo_path("file")], env.clone())
+                .await
+                .unwrap();
+            repo.commit(
+                message.into(),
+                None,
+                CommitOptions::default(),
+                AskPassDelegate::new(&mut cx.to_async(), |_, _, _| {}),
+                env.clone(),
+            )
+            .await
+            .unwrap();
+            shas.push(repo.head_sha().await.unwrap());
+        }
+
+        let mut todo = repo.rebase_todo(shas[0].clone()).await.unwrap();
+        assert_eq!(
+            todo.iter()
+                .map(|entry| (entry.action, entry.subject.as_ref()))
+                .collect::<Vec<_>>(),
+            [(RebaseAction::Pick, "Two"), (RebaseAction::Pick, "Three")]
+        );
+
+        // Applying "Three" before "Two" conflicts.
+        todo.swap(0, 1);
+        todo[0].action = RebaseAction::Reword;
+        todo[0].message = Some("Three\n\nIt's reworded\n#4 is fixed".into());
+        let state = repo
+            .rebase(shas[0].clone(), todo, env.clone())
+            .await
+            .unwrap()
+            .expect("rebase should stop on the conflict");
+        assert_eq!(state.head_name, Some("master".into()));
+        assert_eq!(state.stopped_sha, Some(shas[2].clone().into()));
+        assert_eq!(state.done.len(), 1);
+        assert_eq!(state.todo.len(), 1);
+        assert_eq!(state.todo[0].subject.as_ref(), "Two");
+
+        smol::fs::write(&file_path, "1\n3\n").await.unwrap();
+        repo.stage_paths(vec![repo_path("file")], env.clone())
+            .await
+            .unwrap();
+        let state = repo
+            .rebase_continue(env.clone())
+            .await
+            .unwrap()
+            .expect("picking \"Two\" should conflict too");
+        assert_eq!(state.stopped_sha, Some(shas[1].clone().into()));
+
+        assert_eq!(repo.rebase_skip(env.clone()).await.unwrap(), None);
+        assert_eq!(repo.rebase_state().await.unwrap(), None);
+        assert_eq!(
+            repo.show("HEAD".into()).await.unwrap().message.trim_end(),
+            "Three\n\nIt's reworded\n#4 is fixed"
+        );
+        assert_eq!(
+            smol::fs::read_to_string(&file_path).await.unwrap(),
+            "1\n3\n"
+        );
+    }
+
+    #[gpui::test]
+    async fn test_rebase_refuses_merges(cx: &mut TestAppContext) {
+        disable_git_global_config();
+
+        cx.executor().allow_parking();
+
+        let repo_dir = tempfile::tempdir().unwrap();
+        git2::Repository::init(repo_dir.path()).unwrap();
+        let repo = RealGitRepository::new(
+            &repo_dir.path().join(".git"),
+            None,
+            Some("git".into()),
+            cx.executor(),
+        )
+        .unwrap();
+        let env = Arc::new(checkpoint_author_envs());
+        let git = GitBinary::new("git".into(), repo_dir.path().to_path_buf(), cx.executor())
+            .envs(checkpoint_author_envs());
+
+        let mut shas = Vec::new();
+        for (message, path) in [("One", "a"), ("Two", "b")] {
+            smol::fs::write(repo_dir.path().join(path), message)
+                .await
+                .unwrap();
+            repo.stage_paths(vec![repo_path(path)], env.clone())
+                .await
+                .unwrap();
+            repo.commit(
+                message.into(),
+                None,
+                CommitOptions::default(),
+                AskPassDelegate::new(&mut cx.to_async(), |_, _, _| {}),
+                env.clone(),
+            )
+            .await
+            .unwrap();
+            shas.push(repo.head_sha().await.unwrap());
+        }
+
+        // Merge a side branch started at "One"
+        git.run(&["checkout", "--quiet", "-b", "side", &shas[0]])
+            .await
+            .unwrap();
+        smol::fs::write(repo_dir.path().join("c"), "Side")
+            .await
+            .unwrap();
+        git.run(&["add", "c"]).await.unwrap();
+        git.run(&["commit", "--quiet", "-m", "Side"]).await.unwrap();
+        git.run(&["checkout", "--quiet", "master"]).await.unwrap();
+        git.run(&["merge", "--quiet", "--no-ff", "--no-edit", "side"])
+            .await
+            .unwrap();
+        let head = repo.head_sha().await.unwrap();
+
+        let error = repo.rebase_todo(shas[0].clone()).await.unwrap_err();
+        assert!(error.to_string().contains("merges"), "{error}");
+        let todo = vec![RebaseTodoEntry {
+            action: RebaseAction::Pick,
+            sha: shas[1].clone().into(),
+            subject: "Two".into(),
+            message: None,
+        }];
+        assert!(
+            repo.rebase(shas[0].clone(), todo, env.clone())
+                .await
+                .is_err()
+        );
+        assert_eq!(repo.rebase_state().await.unwrap(), None);
+        assert_eq!(repo.head_sha().await, Some(head.clone()));
+
+        // Amending leaves the changes staged rather than flattening the merge
+        smol::fs::write(repo_dir.path().join("a"), "One amended")
+            .await
+            .unwrap();
+        repo.stage_paths(vec![repo_path("a")], env.clone())
+            .await
+            .unwrap();
+        assert!(
+            repo.amend_commit(shas[0].clone(), env.clone())
+                .await
+                .is_err()
+        );
+        assert_eq!(repo.head_sha().await, Some(head));
+
+        // The commits after the merge can still be rebased
+        assert!(repo.rebase_todo(head.clone()).await.unwrap().is_empty());
+    }
+
+    #[gpui::test]
+    async fn test_conflicting_cherry_pick_and_revert(cx: &mut TestAppContext) {
+        disable_git_global_config();
+
+        cx.executor().allow_parking();
+
+        let repo_dir = tempfile::tempdir().unwrap();
+        git2::Repository::init(repo_dir.path()).unwrap();
+        let file_path = repo_dir.path().join("file");
+        let repo = RealGitRepository::new(
+            &repo_dir.path().join(".git"),
+            None,
+            Some("git".into()),
+            cx.executor(),
+        )
+        .unwrap();
+        let env = Arc::new(checkpoint_author_envs());
+
+        let mut shas = Vec::new();
+        for (message, content) in [("One", "1\n"), ("Two", "1\n2\n"), ("Three", "1\n2\n3\n")] {
+            smol::fs::write(&file_path, content).await.unwrap();
+            repo.stage_paths(vec![repo_path("file")], env.clone())
+                .await
+                .unwrap();
+            repo.commit(
+                message.into(),
+                None,
+                CommitOptions::default(),
+                AskPassDelegate::new(&mut cx.to_async(), |_, _, _| {}),
+                env.clone(),
+            )
+            .await
+            .unwrap();
+            shas.push(repo.head_sha().await.unwrap());
+        }
+        assert_eq!(repo.sequencer_operation().await.unwrap(), None);
+
+        // "Three" was added next to the line reverting "Two" removes.
+        assert!(
+            repo.revert(vec![shas[1].clone()], env.clone())
+                .await
+                .is_err()
+        );
+        assert_eq!(
+            repo.sequencer_operation().await.unwrap(),
+            Some(SequencerOperation::Revert)
+        );
+        smol::fs::write(&file_path, "1\n3\n").await.unwrap();
+        repo.stage_paths(vec![repo_path("file")], env.clone())
+            .await
+            .unwrap();
+        assert_eq!(repo.sequencer_continue(env.clone()).await.unwrap(), None);
+        assert!(
+            repo.show("HEAD".into())
+                .await
+                .unwrap()
+                .message
+                .starts_with("Revert \"Two\"")
+        );
+
+        // Picking "Two" back conflicts with "Three" the same way.
+        let head = repo.head_sha().await.unwrap();
+        assert!(
+            repo.cherry_pick(vec![shas[1].clone()], env.clone())
+                .await
+                .is_err()
+        );
+        assert_eq!(
+            repo.sequencer_operation().await.unwrap(),
+            Some(SequencerOperation::CherryPick)
+        );
+        repo.sequencer_abort(env.clone()).await.unwrap();
+        assert_eq!(repo.sequencer_operation().await.unwrap(), None);
+        assert_eq!(repo.head_sha().await.unwrap(), head);
+        assert_eq!(
+            smol::fs::read_to_string(&file_path).await.unwrap(),
+            "1\n3\n"
+        );
+        assert!(repo.sequencer_abort(env).await.is_err());
+    }
+
+    // The failing hook needs an executable bit
+    #[cfg(unix)]
+    #[gpui::test]
+    async fn test_amend_commit(cx: &mut TestAppContext) {
+        use std::os::unix::fs::PermissionsExt as _;
+
+        disable_git_global_config();
+
+        cx.executor().allow_parking();
+
+        let repo_dir = tempfile::tempdir().unwrap();
+        let git2_repo = git2::Repository::init(repo_dir.path()).unwrap();
+        let repo = RealGitRepository::new(
+            &repo_dir.path().join(".git"),
+            None,
+            Some("git".into()),
+            cx.executor(),
+        )
+        .unwrap();
+        let env = Arc::new(checkpoint_author_envs());
+
+        let mut shas = Vec::new();
+        for (message, path) in [("One", "a"), ("Two", "b")] {
+            smol::fs::write(repo_dir.path().join(path), message)
+                .await
+                .unwrap();
+            repo.stage_paths(vec![repo_path(path)], env.clone())
+                .await
+                .unwrap();
+            repo.commit(
+                message.into(),
+                None,
+                CommitOptions::default(),
+                AskPassDelegate::new(&mut cx.to_async(), |_, _, _| {}),
+                env.clone(),
+            )
+            .await
+            .unwrap();
+            shas.push(repo.head_sha().await.unwrap());
+        }
+
+        smol::fs::write(repo_dir.path().join("a"), "One amended")
+            .await
+            .unwrap();
+        repo.stage_paths(vec![repo_path("a")], env.clone())
+            .await
+            .unwrap();
+        assert_eq!(
+            repo.amend_commit(shas[0].clone(), env.clone())
+                .await
+                .unwrap(),
+            None
+        );
+        let log = repo.log(LogOptions::default(), 0, None).await.unwrap();
+        assert_eq!(
+            log.iter()
+                .map(|entry| entry.subject.as_ref())
+                .collect::<Vec<_>>(),
+            ["Two", "One"]
+        );
+        let amended = git2_repo.revparse_single("HEAD~:a").unwrap();
+        assert_eq!(amended.peel_to_blob().unwrap().content(), b"One amended");
+
+        // A rebase that fails to start leaves no fixup commit behind
+        let hook_path = repo_dir.path().join(".git/hooks/pre-rebase");
+        smol::fs::create_dir_all(hook_path.parent().unwrap())
+            .await
+            .unwrap();
+        smol::fs::write(&hook_path, "#!/bin/sh\nexit 1\n")
+            .await
+            .unwrap();
+        smol::fs::set_permissions(&hook_path, std::fs::Permissions::from_mode(0o755))
+            .await
+            .unwrap();
+        let head = repo.head_sha().await.unwrap();
+
+        smol::fs::write(repo_dir.path().join("b"), "Two amended")
+            .await
+            .unwrap();
+        repo.stage_paths(vec![repo_path("b")], env.clone())
+            .await
+            .unwrap();
+        assert!(
+            repo.amend_commit(log[1].sha.to_string(), env.clone())
+                .await
+                .is_err()
+        );
+        assert_eq!(repo.head_sha().await, Some(head));
+        assert_eq!(repo.rebase_state().await.unwrap(), None);
+        let status = git2_repo.status_file(Path::new("b")).unwrap();
+        assert_eq!(status, git2::Status::INDEX_MODIFIED);
+    }
+
+    #[gpui::test]
+    async fn test_log(cx: &mut TestAppContext) {
+        disable_git_global_config();
+
+        cx.executor().allow_parking();
+
+        let repo_dir = tempfile::tempdir().unwrap();
+        git2::Repository::init(repo_dir.path()).unwrap();
+        let repo = RealGitRepository::new(
+            &repo_dir.path().join(".git"),
+            None,
+            Some("git".into()),
+            cx.executor(),
+        )
+        .unwrap();
+        let env = Arc::new(checkpoint_author_envs());
+
+        let mut shas = Vec::new();
+        for (message, path) in [("One", "a"), ("Two", "b"), ("Three", "a")] {
+            smol::fs::write(repo_dir.path().join(path), message)
+                .await
+                .unwrap();
+            repo.stage_paths(vec![repo_path(path)], env.clone())
+                .await
+                .unwrap();
+            repo.commit(
+                message.into(),
+                None,
+                CommitOptions::default(),
+                AskPassDelegate::new(&mut cx.to_async(), |_, _, _| {}),
+                env.clone(),
+            )
+            .await
+            .unwrap();
+            shas.push(repo.head_sha().await.unwrap());
+        }
+        repo.create_tag("v1".into(), shas[0].clone()).await.unwrap();
+
+        let log = repo.log(LogOptions::default(), 0, None).await.unwrap();
+        assert_eq!(
+            log.iter()
+                .map(|entry| entry.subject.as_ref())
+                .collect::<Vec<_>>(),
+            ["Three", "Two", "One"]
+        );
+        assert_eq!(log[0].parents, [SharedString::from(shas[1].clone())]);
+        assert!(log[2].parents.is_empty());
+        assert!(log[0].refs[0].starts_with("HEAD -> "));
+        assert_eq!(log[2].refs, [SharedString::from("tag: v1")]);
+
+        let page = repo.log(LogOptions::default(), 1, Some(1)).await.unwrap();
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].subject.as_ref(), "Two");
+
+        let options = LogOptions {
+            path: Some(repo_path("a")),
+            ..Default::default()
+        };
+        let log = repo.log(options, 0, None).await.unwrap();
+        assert_eq!(log.len(), 2);
+        // The parent of "Three" is rewritten to the previous commit touching `a`.
+        assert_eq!(log[0].parents, [SharedString::from(shas[0].clone())]);
+
+        let options = LogOptions {
+            message: Some("two".into()),
+            ..Default::default()
+        };
+        let log = repo.log(options, 0, None).await.unwrap();
+        assert_eq!(log.len(), 1);
+        assert_eq!(log[0].sha, SharedString::from(shas[1].clone()));
+
+        let diff = repo
+            .compare_commits(shas[0].clone(), shas[2].clone(), cx.to_async())
+            .await
+            .unwrap();
+        assert_eq!(
+            diff.files
+                .iter()
+                .map(|file| (
+                    file.path.as_unix_str(),
+                    file.old_text.as_deref(),
+                    file.new_text.as_deref()
+                ))
+                .collect::<Vec<_>>(),
+            [("a", Some("One"), Some("Three")), ("b", None, Some("Two"))]
+        );
+
+        repo.checkout_commit(shas[1].clone()).await.unwrap();
+        assert_eq!(repo.head_sha().await, Some(shas[1].clone()));
+    }
+
+    #[test]
+    fn test_rebase_todo_parsing() {
+        let todo = parse_rebase_todo(
+            &"
+            pick 1111111 First commit
+            # A comment
+            exec make test
+            r 2222222 Second commit
+
+            fixup -C 3333333 Third commit
+            s 4444444 # Fourth commit
+            drop 5555555 Fifth commit
+            "
+            .unindent(),
+        );
+        assert_eq!(
+            todo.iter()
+                .map(|entry| (entry.action, entry.sha.as_ref(), entry.subject.as_ref()))
+                .collect::<Vec<_>>(),
+            [
+                (RebaseAction::Pick, "1111111", "First commit"),
+                (RebaseAction::Reword, "2222222", "Second commit"),
+                (RebaseAction::Fixup, "3333333", "Third commit"),
+                (RebaseAction::Squash, "4444444", "Fourth commit"),
+                (RebaseAction::Drop, "5555555", "Fifth commit"),
+            ]
+        );
+
+        let entry = |action, sha: &str, message: Option<&str>| RebaseTodoEntry {
+            action,
+            sha: sha.to_string().into(),
+            subject: "Subject".into(),
+            message: message.map(|message| message.to_string().into()),
+        };
+        assert_eq!(
+            format_rebase_todo(&[
+                entry(RebaseAction::Squash, "1111111", None),
+                entry(RebaseAction::Reword, "2222222", Some("New\n\nIt's new")),
+                entry(RebaseAction::Reword, "3333333", None),
+            ]),
+            "
+            squash 1111111 Subject
+            pick 2222222 Subject
+            exec printf '%s\\n' 'New' '' 'It'\\''s new' | git commit --amend --quiet --no-verify --cleanup=whitespace --file=-
+            reword 3333333 Subject
+            "
+            .unindent()
+        );
+    }
+
     #[test]
     fn test_branches_parsing() {
         // suppress "help: octal escapes are not supported, `\0` is always null"
@@ -0,0 +1,183 @@
+use gpui::SharedString;
+
+/// A line drawn across one row of the commit graph.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GraphLine {
+    /// Passes by the row's commit, from the top of a lane to its bottom.
+    Through(usize),
+    /// Runs from the top of a lane into the row's commit.
+    Incoming(usize),
+    /// Runs from the row's commit to the bottom of a lane.
+    Outgoing(usize),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GraphRow {
+    /// The lane the row's commit is drawn in.
+    pub lane: usize,
+    pub lines: Vec<GraphLine>,
+}
+
+/// Assigns the commits of a log to lanes, one row per commit. Commits must
+/// be pushed with children before their parents, and can be pushed a page at
+/// a time.
+#[derive(Default)]
+pub struct CommitGraph {
+    /// The commit each lane leads to, below the last row.
+    lanes: Vec<Option<SharedString>>,
+    rows: Vec<GraphRow>,
+    width: usize,
+}
+
+impl CommitGraph {
+    pub fn push(&mut self, sha: &SharedString, parents: &[SharedString]) {
+        let mut lines = Vec::new();
+        let mut lane = None;
+        for (ix, next) in self.lanes.iter_mut().enumerate() {
+            if next.as_ref() == Some(sha) {
+                lines.push(GraphLine::Incoming(ix));
+                lane.get_or_insert(ix);
+                *next = None;
+            }
+        }
+        lines.extend(
+            self.lanes
+                .iter()
+                .enumerate()
+                .filter(|(_, next)| next.is_some())
+                .map(|(ix, _)| GraphLine::Through(ix)),
+        );
+        let lane = lane.unwrap_or_else(|| self.free_lane());
+
+        for (ix, parent) in parents.iter().enumerate() {
+            // Join the lane of a sibling that already leads to the parent.
+            let target = match self.lane_leading_to(parent) {
+                Some(target) => target,
+                None if ix == 0 => lane,
+                None => self.free_lane(),
+            };
+            if target == self.lanes.len() {
+                self.lanes.push(None);
+            }
+            self.lanes[target] = Some(parent.clone());
+            lines.push(GraphLine::Outgoing(target));
+        }
+
+        while self.lanes.last().is_some_and(Option::is_none) {
+            self.lanes.pop();
+        }
+
+        let row_width = lines
+            .iter()
+            .map(|line| match line {
+                GraphLine::Through(ix) | GraphLine::Incoming(ix) | GraphLine::Outgoing(ix) => *ix,
+            })
+            .chain([lane])
+            .max()
+            .unwrap_or_default()
+            + 1;
+        self.width = self.width.max(row_width);
+        self.rows.push(GraphRow { lane, lines });
+    }
+
+    pub fn row(&self, ix: usize) -> Option<&GraphRow> {
+        self.rows.get(ix)
+    }
+
+    /// The number of lanes needed to draw every row.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    fn lane_leading_to(&self, sha: &SharedString) -> Option<usize> {
+        self.lanes
+            .iter()
+            .position(|next| next.as_ref() == Some(sha))
+    }
+
+    fn free_lane(&self) -> usize {
+        self.lanes
+            .iter()
+            .position(Option::is_none)
+            .unwrap_or(self.lanes.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GraphLine::*, *};
+
+    fn graph(commits: &[(&'static str, &[&'static str])]) -> CommitGraph {
+        let mut graph = CommitGraph::default();
+        for (sha, parents) in commits {
+            let parents = parents
+                .iter()
+                .map(|parent| SharedString::from(*parent))
+                .collect::<Vec<_>>();
+            graph.push(&SharedString::from(*sha), &parents);
+        }
+        graph
+    }
+
+    fn rows(graph: &CommitGraph) -> Vec<(usize, Vec<GraphLine>)> {
+        graph
+            .rows
+            .iter()
+            .map(|row| (row.lane, row.lines.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_linear_history() {
+        let graph = graph(&[("c", &["b"]), ("b", &["a"]), ("a", &[])]);
+        assert_eq!(graph.width(), 1);
+        assert_eq!(
+            rows(&graph),
+            [
+                (0, vec![Outgoing(0)]),
+                (0, vec![Incoming(0), Outgoing(0)]),
+                (0, vec![Incoming(0)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_branch_and_merge() {
+        let graph = graph(&[
+            ("merge", &["main", "feature"]),
+            ("main", &["base"]),
+            ("feature", &["base"]),
+            ("base", &[]),
+        ]);
+        assert_eq!(graph.width(), 2);
+        assert_eq!(
+            rows(&graph),
+            [
+                (0, vec![Outgoing(0), Outgoing(1)]),
+                (0, vec![Incoming(0), Through(1), Outgoing(0)]),
+                (1, vec![Incoming(1), Through(0), Outgoing(0)]),
+                (0, vec![Incoming(0)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unrelated_heads_reuse_free_lanes() {
+        let graph = graph(&[
+            ("topic", &["base"]),
+            ("main", &["base"]),
+            ("base", &[]),
+            ("orphan", &[]),
+        ]);
+        assert_eq!(graph.width(), 2);
+        assert_eq!(
+            rows(&graph),
+            [
+                (0, vec![Outgoing(0)]),
+                (1, vec![Through(0), Outgoing(0)]),
+                (0, vec![Incoming(0)]),
+                (0, vec![]),
+            ]
+        );
+    }
+}
@@ -0,0 +1,1171 @@
+use std::{
+    any::{Any, TypeId},
+    time::Duration,
+};
+
+use anyhow::{Context as _, Result};
+use editor::{Editor, EditorEvent};
+use futures::channel::oneshot;
+use git::repository::{LogEntry, LogOptions, RepoPath, SequencerOperation};
+use gpui::{
+    AnyElement, AnyEntity, App, ClickEvent, ClipboardItem, Corner, DismissEvent, Entity,
+    EventEmitter, FocusHandle, Focusable, MouseButton, MouseDownEvent, PathBuilder, Pixels, Point,
+    Subscription, Task, UniformListScrollHandle, WeakEntity, Window, anchored, canvas, deferred,
+    point, px, uniform_list,
+};
+use menu::{Cancel, Confirm};
+use project::{
+    Project, ProjectPath,
+    git_store::{Repository, RepositoryEvent},
+};
+use time::{Date, OffsetDateTime, UtcOffset, macros::format_description};
+use ui::{Chip, ContextMenu, Divider, Headline, HeadlineSize, Tooltip, WithScrollbar, prelude::*};
+use util::ResultExt as _;
+use workspace::{
+    Item, ModalView, Workspace,
+    item::{ItemEvent, SaveOptions},
+    notifications::DetachAndPromptErr,
+};
+
+use crate::{
+    commit_graph::{CommitGraph, GraphLine, GraphRow},
+    commit_view::CommitView,
+    git_panel::show_error_toast,
+    rebase_todo_view::RebaseTodoView,
+};
+
+const PAGE_SIZE: usize = 50;
+const FILTER_DEBOUNCE: Duration = Duration::from_millis(300);
+const ROW_HEIGHT: Pixels = px(28.);
+const LANE_WIDTH: Pixels = px(14.);
+const DOT_SIZE: Pixels = px(8.);
+
+type StartOperation = fn(&mut Repository, String) -> oneshot::Receiver<Result<()>>;
+
+pub fn register(workspace: &mut Workspace) {
+    workspace.register_action(|workspace, _: &git::CommitLog, window, cx| {
+        CommitLogView::deploy(workspace, window, cx);
+    });
+}
+
+/// The commit history of a whole repository, drawn as a graph of branches
+/// and merges.
+pub struct CommitLogView {
+    repository: Entity<Repository>,
+    workspace: WeakEntity<Workspace>,
+    options: LogOptions,
+    entries: Vec<LogEntry>,
+    graph: CommitGraph,
+    has_more: bool,
+    load_task: Option<Task<()>>,
+    /// The selected commits, at most two so that they can be compared.
+    selection: Vec<SharedString>,
+    /// The cherry-pick or revert waiting for its conflicts to be resolved.
+    sequencer_operation: Option<SequencerOperation>,
+    author_editor: Entity<Editor>,
+    message_editor: Entity<Editor>,
+    path_editor: Entity<Editor>,
+    since_editor: Entity<Editor>,
+    until_editor: Entity<Editor>,
+    filter_error: Option<SharedString>,
+    filter_task: Option<Task<()>>,
+    context_menu: Option<(Entity<ContextMenu>, Point<Pixels>, Subscription)>,
+    scroll_handle: UniformListScrollHandle,
+    focus_handle: FocusHandle,
+    _subscriptions: Vec<Subscription>,
+}
+
+impl CommitLogView {
+    /// Opens the log of the active repository, or activates the one already
+    /// open.
+    pub fn deploy(
+        workspace: &mut Workspace,
+        window: &mut Window,
+        cx: &mut Context<Workspace>,
+    ) -> Option<Entity<Self>> {
+        let repository = workspace.project().read(cx).active_repository(cx)?;
+        let existing = workspace
+            .items_of_type::<Self>(cx)
+            .find(|view| view.read(cx).repository == repository);
+        if let Some(existing) = existing {
+            workspace.activate_item(&existing, true, true, window, cx);
+            return Some(existing);
+        }
+
+        let view = cx.new(|cx| Self::new(repository, workspace.weak_handle(), window, cx));
+        workspace.add_item_to_active_pane(Box::new(view.clone()), None, true, window, cx);
+        Some(view)
+    }
+
+    fn new(
+        repository: Entity<Repository>,
+        workspace: WeakEntity<Workspace>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let mut subscriptions = vec![cx.subscribe(
+            &repository,
+            |this, _, event: &RepositoryEvent, cx| match event {
+                RepositoryEvent::BranchChanged => {
+                    this.reload(cx);
+                    this.refresh_sequencer_operation(cx);
+                }
+                RepositoryEvent::StatusesChanged | RepositoryEvent::MergeHeadsChanged => {
+                    this.refresh_sequencer_operation(cx)
+                }
+                _ => {}
+            },
+        )];
+        let mut filter_editor = |placeholder: &str, cx: &mut Context<Self>| {
+            let editor = cx.new(|cx| {
+                let mut editor = Editor::single_line(window, cx);
+                editor.set_placeholder_text(placeholder, window, cx);
+                editor
+            });
+            subscriptions.push(cx.subscribe(&editor, |this, _, event: &EditorEvent, cx| {
+                if let EditorEvent::BufferEdited = event {
+                    this.schedule_reload(cx);
+                }
+            }));
+            editor
+        };
+        let author_editor = filter_editor("Author", cx);
+        let message_editor = filter_editor("Message", cx);
+        let path_editor = filter_editor("Path", cx);
+        let since_editor = filter_editor("Since (YYYY-MM-DD)", cx);
+        let until_editor = filter_editor("Until (YYYY-MM-DD)", cx);
+
+        let mut this = Self {
+            repository,
+            workspace,
+            options: LogOptions::default(),
+            entries: Vec::new(),
+            graph: CommitGraph::default(),
+            has_more: false,
+            load_task: None,
+            selection: Vec::new(),
+            sequencer_operation: None,
+            author_editor,
+            message_editor,
+            path_editor,
+            since_editor,
+            until_editor,
+            filter_error: None,
+            filter_task: None,
+            context_menu: None,
+            scroll_handle: UniformListScrollHandle::new(),
+            focus_handle: cx.focus_handle(),
+            _subscriptions: subscriptions,
+        };
+        this.reload(cx);
+        this.refresh_sequencer_operation(cx);
+        this
+    }
+
+    fn schedule_reload(&mut self, cx: &mut Context<Self>) {
+        self.filter_task = Some(cx.spawn(async move |this, cx| {
+            cx.background_executor().timer(FILTER_DEBOUNCE).await;
+            this.update(cx, |this, cx| this.reload(cx)).ok();
+        }));
+    }
+
+    /// Reads the filters and loads the first page of commits matching them.
+    fn reload(&mut self, cx: &mut Context<Self>) {
+        self.filter_task = None;
+        let text = |editor: &Entity<Editor>| editor.read(cx).text(cx);
+        let options = log_options(
+            self.options.all_refs,
+            &text(&self.author_editor),
+            &text(&self.message_editor),
+            &text(&self.path_editor),
+            &text(&self.since_editor),
+            &text(&self.until_editor),
+            UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC),
+        );
+        match options {
+            Ok(options) => {
+                self.options = options;
+                self.filter_error = None;
+            }
+            Err(error) => {
+                self.filter_error = Some(error.to_string().into());
+                cx.notify();
+                return;
+            }
+        }
+
+        self.entries.clear();
+        self.graph = CommitGraph::default();
+        self.has_more = false;
+        self.load_page(cx);
+    }
+
+    fn refresh_sequencer_operation(&mut self, cx: &mut Context<Self>) {
+        let task = self
+            .repository
+            .update(cx, |repo, _| repo.sequencer_operation());
+        cx.spawn(async move |this, cx| {
+            let operation = async move { anyhow::Ok(task.await??) }.await;
+            this.update(cx, |this, cx| {
+                this.sequencer_operation = operation.log_err().flatten();
+                cx.notify();
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    fn load_more(&mut self, cx: &mut Context<Self>) {
+        if self.has_more && self.load_task.is_none() {
+            self.load_page(cx);
+        }
+    }
+
+    fn load_page(&mut self, cx: &mut Context<Self>) {
+        let skip = self.entries.len();
+        let options = self.options.clone();
+        let task = self
+            .repository
+            .update(cx, |repo, _| repo.log(options, skip, Some(PAGE_SIZE)));
+        self.load_task = Some(cx.spawn(async move |this, cx| {
+            let result = async move { anyhow::Ok(task.await??) }.await;
+            this.update(cx, |this, cx| {
+                this.load_task = None;
+                match result {
+                    Ok(entries) => {
+                        this.has_more = entries.len() >= PAGE_SIZE;
+                        for entry in &entries {
+                            this.graph.push(&entry.sha, &entry.parents);
+                        }
+                        this.entries.extend(entries);
+                    }
+                    Err(error) => this.show_error("log", error, cx),
+                }
+                cx.notify();
+            })
+            .ok();
+        }));
+        cx.notify();
+    }
+
+    fn toggle_all_refs(&mut self, cx: &mut Context<Self>) {
+        self.options.all_refs = !self.options.all_refs;
+        self.reload(cx);
+    }
+
+    fn click_entry(
+        &mut self,
+        ix: usize,
+        event: &ClickEvent,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(entry) = self.entries.get(ix) else {
+            return;
+        };
+        let sha = entry.sha.clone();
+        cx.notify();
+        if event.modifiers().secondary() {
+            toggle_selection(&mut self.selection, sha);
+            return;
+        }
+        self.selection = vec![sha.clone()];
+        CommitView::open(
+            sha.to_string(),
+            self.repository.downgrade(),
+            self.workspace.clone(),
+            None,
+            None,
+            window,
+            cx,
+        );
+    }
+
+    /// The selected commits as a base and head, the base being the older.
+    fn comparison(&self) -> Option<(SharedString, SharedString)> {
+        let [first, second] = self.selection.as_slice() else {
+            return None;
+        };
+        let position = |sha| self.entries.iter().position(|entry| &entry.sha == sha);
+        if position(first) > position(second) {
+            Some((first.clone(), second.clone()))
+        } else {
+            Some((second.clone(), first.clone()))
+        }
+    }
+
+    fn compare_selected(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some((base, head)) = self.comparison() else {
+            return;
+        };
+        CommitView::open_comparison(
+            base.to_string(),
+            head.to_string(),
+            self.repository.downgrade(),
+            self.workspace.clone(),
+            window,
+            cx,
+        );
+    }
+
+    fn run_operation<T: 'static>(
+        &mut self,
+        name: &'static str,
+        task: oneshot::Receiver<Result<T>>,
+        cx: &mut Context<Self>,
+    ) {
+        cx.spawn(async move |this, cx| {
+            let result = async move { anyhow::Ok(task.await??) }.await;
+            this.update(cx, |this, cx| {
+                if let Err(error) = result {
+                    this.show_error(name, error, cx);
+                }
+                this.reload(cx);
+                this.refresh_sequencer_operation(cx);
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    fn show_error(&self, action: &'static str, error: anyhow::Error, cx: &mut App) {
+        if let Some(workspace) = self.workspace.upgrade() {
+            show_error_toast(workspace, action, error, cx);
+        }
+    }
+
+    fn create_ref(
+        &mut self,
+        kind: RefKind,
+        commit: SharedString,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let repository = self.repository.clone();
+        let log_view = cx.weak_entity();
+        self.workspace
+            .update(cx, |workspace, cx| {
+                workspace.toggle_modal(window, cx, |window, cx| {
+                    CreateRefModal::new(kind, commit, repository, log_view, window, cx)
+                });
+            })
+            .ok();
+    }
+
+    fn deploy_context_menu(
+        &mut self,
+        ix: usize,
+        position: Point<Pixels>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(entry) = self.entries.get(ix) else {
+            return;
+        };
+        let sha = entry.sha.clone();
+        let compare_with = self
+            .selection
+            .iter()
+            .find(|selected| **selected != sha)
+            .filter(|_| self.selection.len() == 1)
+            .cloned();
+        let this = cx.weak_entity();
+        let workspace = self.workspace.clone();
+
+        let context_menu = ContextMenu::build(window, cx, move |context_menu, _, _| {
+            let operation = |name: &'static str, start: StartOperation| {
+                let this = this.clone();
+                let sha = sha.clone();
+                move |_: &mut Window, cx: &mut App| {
+                    this.update(cx, |this, cx| {
+                        let task = this
+                            .repository
+                            .update(cx, |repo, _| start(repo, sha.to_string()));
+                        this.run_operation(name, task, cx);
+                    })
+                    .ok();
+                }
+            };
+            let create_ref = |kind: RefKind| {
+                let this = this.clone();
+                let sha = sha.clone();
+                move |window: &mut Window, cx: &mut App| {
+                    this.update(cx, |this, cx| {
+                        this.create_ref(kind, sha.clone(), window, cx)
+                    })
+                    .ok();
+                }
+            };
+
+            context_menu
+                .entry(
+                    "Checkout",
+                    None,
+                    operation("checkout", |repo, sha| repo.checkout_commit(sha)),
+                )
+                .entry("Create Branch…", None, create_ref(RefKind::Branch))
+                .entry("Create Tag…", None, create_ref(RefKind::Tag))
+                .separator()
+                .entry(
+                    "Cherry-Pick",
+                    None,
+                    operation("cherry-pick", |repo, sha| repo.cherry_pick(vec![sha])),
+                )
+                .entry(
+                    "Revert",
+                    None,
+                    operation("revert", |repo, sha| repo.revert(vec![sha])),
+                )
+                .entry("Interactive Rebase onto This Commit", None, {
+                    let sha = sha.clone();
+                    move |window, cx| {
+                        workspace
+                            .update(cx, |workspace, cx| {
+                                RebaseTodoView::deploy(
+                                    workspace,
+                                    Some(sha.to_string()),
+                                    window,
+                                    cx,
+                                );
+                            })
+                            .ok();
+                    }
+                })
+                .separator()
+                .when_some(compare_with, |context_menu, selected| {
+                    let this = this.clone();
+                    let sha = sha.clone();
+                    context_menu.entry("Compare with Selected", None, move |window, cx| {
+                        this.update(cx, |this, cx| {
+                            this.selection = vec![selected.clone(), sha.clone()];
+                            this.compare_selected(window, cx);
+                        })
+                        .ok();
+                    })
+                })
+                .entry("Copy SHA", None, {
+                    let sha = sha.clone();
+                    move |_, cx| cx.write_to_clipboard(ClipboardItem::new_string(sha.to_string()))
+                })
+        });
+        self.set_context_menu(context_menu, position, window, cx);
+    }
+
+    fn set_context_menu(
+        &mut self,
+        context_menu: Entity<ContextMenu>,
+        position: Point<Pixels>,
+        window: &Window,
+        cx: &mut Context<Self>,
+    ) {
+        let subscription = cx.subscribe_in(
+            &context_menu,
+            window,
+            |this, _, _: &DismissEvent, window, cx| {
+                if this.context_menu.as_ref().is_some_and(|context_menu| {
+                    context_menu.0.focus_handle(cx).contains_focused(window, cx)
+                }) {
+                    cx.focus_self(window);
+                }
+                this.context_menu.take();
+                cx.notify();
+            },
+        );
+        self.context_menu = Some((context_menu, position, subscription));
+        cx.notify();
+    }
+
+    fn render_entry(
+        &self,
+        ix: usize,
+        entry: &LogEntry,
+        show_graph: bool,
+        cx: &mut Context<Self>,
+    ) -> AnyElement {
+        let selected_background = cx.theme().colors().element_selected;
+        let hover_background = cx.theme().colors().element_hover;
+        let is_selected = self.selection.contains(&entry.sha);
+        let commit_time = OffsetDateTime::from_unix_timestamp(entry.commit_timestamp)
+            .unwrap_or_else(|_| OffsetDateTime::UNIX_EPOCH);
+        let relative_timestamp = time_format::format_localized_timestamp(
+            commit_time,
+            OffsetDateTime::now_utc(),
+            UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC),
+            time_format::TimestampFormat::Relative,
+        );
+
+        h_flex()
+            .id(("commit", ix))
+            .h(ROW_HEIGHT)
+            .w_full()
+            .pr_2()
+            .gap_2()
+            .when(is_selected, |this| this.bg(selected_background))
+            .hover(|this| this.bg(hover_background))
+            .map(|this| match self.graph.row(ix).filter(|_| show_graph) {
+                Some(row) => this.child(render_graph_row(row, self.graph.width(), cx)),
+                None => this.pl_2(),
+            })
+            .child(
+                Label::new(short_sha(&entry.sha))
+                    .size(LabelSize::Small)
+                    .color(Color::Muted)
+                    .buffer_font(cx),
+            )
+            .children(entry.refs.iter().map(|name| Chip::new(name.clone())))
+            .child(
+                h_flex().min_w_0().flex_1().child(
+                    Label::new(entry.subject.clone())
+                        .size(LabelSize::Small)
+                        .truncate(),
+                ),
+            )
+            .child(
+                div().flex_none().child(
+                    Label::new(entry.author_name.clone())
+                        .size(LabelSize::Small)
+                        .color(Color::Muted),
+                ),
+            )
+            .child(
+                div().flex_none().child(
+                    Label::new(relative_timestamp)
+                        .size(LabelSize::Small)
+                        .color(Color::Muted),
+                ),
+            )
+            .on_click(cx.listener(move |this, event: &ClickEvent, window, cx| {
+                this.click_entry(ix, event, window, cx);
+            }))
+            .on_mouse_down(
+                MouseButton::Right,
+                cx.listener(move |this, event: &MouseDownEvent, window, cx| {
+                    this.deploy_context_menu(ix, event.position, window, cx);
+                    cx.stop_propagation();
+                }),
+            )
+            .into_any_element()
+    }
+
+    fn render_header(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let path = self.repository.read(cx).work_directory_abs_path.clone();
+        let can_compare = self.comparison().is_some();
+
+        h_flex()
+            .h(rems_from_px(41.))
+            .pl_3()
+            .pr_2()
+            .justify_between()
+            .border_b_1()
+            .border_color(cx.theme().colors().border_variant)
+            .child(Label::new(path.display().to_string()).color(Color::Muted))
+            .child(
+                h_flex()
+                    .gap_1p5()
+                    .child(
+                        Label::new(format!("{} commits", self.entries.len()))
+                            .size(LabelSize::Small)
+                            .color(Color::Muted)
+                            .mr_1(),
+                    )
+                    .child(Divider::vertical())
+                    .child(
+                        Button::new("compare-commits", "Compare")
+                            .label_size(LabelSize::Small)
+                            .disabled(!can_compare)
+                            .when(!can_compare, |this| {
+                                this.tooltip(Tooltip::text(
+                                    "Select two commits with the secondary modifier to compare them",
+                                ))
+                            })
+                            .on_click(cx.listener(|this, _, window, cx| {
+                                this.compare_selected(window, cx);
+                            })),
+                    )
+                    .when(self.has_more, |this| {
+                        this.child(
+                            Button::new("load-more", "Load More")
+                                .disabled(self.load_task.is_some())
+                                .label_size(LabelSize::Small)
+                                .icon(IconName::ArrowCircle)
+                                .icon_size(IconSize::Small)
+                                .icon_color(Color::Muted)
+                                .icon_position(IconPosition::Start)
+                                .on_click(cx.listener(|this, _, _, cx| {
+                                    this.load_more(cx);
+                                })),
+                        )
+                    }),
+            )
+    }
+
+    /// Offers to continue or abort a cherry-pick or revert that stopped on a
+    /// conflict.
+    fn render_sequencer_banner(
+        &self,
+        operation: SequencerOperation,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let name = match operation {
+            SequencerOperation::CherryPick => "Cherry-pick",
+            SequencerOperation::Revert => "Revert",
+        };
+        let has_conflicts = !self.repository.read(cx).merge.conflicted_paths.is_empty();
+
+        h_flex()
+            .px_3()
+            .py_1p5()
+            .gap_2()
+            .justify_between()
+            .border_b_1()
+            .border_color(cx.theme().colors().border_variant)
+            .child(
+                h_flex()
+                    .gap_2()
+                    .child(
+                        Icon::new(IconName::Warning)
+                            .size(IconSize::Small)
+                            .color(Color::Warning),
+                    )
+                    .child(
+                        Label::new(format!("{name} stopped on a conflict")).size(LabelSize::Small),
+                    ),
+            )
+            .child(
+                h_flex()
+                    .gap_1p5()
+                    .child(
+                        Button::new("sequencer-abort", "Abort")
+                            .label_size(LabelSize::Small)
+                            .on_click(cx.listener(move |this, _, _, cx| {
+                                let task =
+                                    this.repository.update(cx, |repo, _| repo.sequencer_abort());
+                                this.run_operation(operation.as_str(), task, cx);
+                            })),
+                    )
+                    .child(
+                        Button::new("sequencer-continue", "Continue")
+                            .label_size(LabelSize::Small)
+                            .disabled(has_conflicts)
+                            .when(has_conflicts, |this| {
+                                this.tooltip(Tooltip::text("Resolve conflicts to continue"))
+                            })
+                            .on_click(cx.listener(move |this, _, _, cx| {
+                                let task = this
+                                    .repository
+                                    .update(cx, |repo, _| repo.sequencer_continue());
+                                this.run_operation(operation.as_str(), task, cx);
+                            })),
+                    ),
+            )
+    }
+
+    fn render_filters(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let colors = cx.theme().colors();
+        let field = |editor: &Entity<Editor>| {
+            div()
+                .w(rems(10.))
+                .px_1p5()
+                .py_0p5()
+                .border_1()
+                .rounded_sm()
+                .border_color(colors.border_variant)
+                .child(editor.clone())
+        };
+
+        v_flex()
+            .px_3()
+            .py_1p5()
+            .gap_1()
+            .border_b_1()
+            .border_color(colors.border_variant)
+            .child(
+                h_flex()
+                    .gap_1p5()
+                    .flex_wrap()
+                    .child(field(&self.author_editor))
+                    .child(field(&self.message_editor))
+                    .child(field(&self.path_editor))
+                    .child(field(&self.since_editor))
+                    .child(field(&self.until_editor))
+                    .child(
+                        Button::new("all-refs", "All Branches")
+                            .label_size(LabelSize::Small)
+                            .toggle_state(self.options.all_refs)
+                            .on_click(cx.listener(|this, _, _, cx| {
+                                this.toggle_all_refs(cx);
+                            })),
+                    ),
+            )
+            .children(
+                self.filter_error
+                    .clone()
+                    .map(|error| Label::new(error).size(LabelSize::Small).color(Color::Error)),
+            )
+    }
+}
+
+fn render_graph_row(row: &GraphRow, width: usize, cx: &App) -> impl IntoElement {
+    let accents = cx.theme().accents().clone();
+    let dot_color = accents.color_for_index(row.lane as u32);
+    let lane_x = |lane: usize| LANE_WIDTH * (lane as f32 + 0.5);
+    let dot_origin = point(
+        lane_x(row.lane) - DOT_SIZE / 2.,
+        (ROW_HEIGHT - DOT_SIZE) / 2.,
+    );
+    let row = row.clone();
+
+    div()
+        .relative()
+        .flex_none()
+        .w(LANE_WIDTH * width as f32)
+        .h_full()
+        .child(
+            canvas(
+                |_, _, _| {},
+                move |bounds, _, window, _| {
+                    let x = |lane: usize| bounds.origin.x + lane_x(lane);
+                    let top = bounds.top();
+                    let center = bounds.center().y;
+                    let bottom = bounds.bottom();
+                    let commit_x = x(row.lane);
+                    for line in &row.lines {
+                        // Lines between lanes bend at the height of the commit.
+                        let (lane, from, to, ctrl) = match *line {
+                            GraphLine::Through(lane) => {
+                                let lane_x = x(lane);
+                                (lane, point(lane_x, top), point(lane_x, bottom), None)
+                            }
+                            GraphLine::Incoming(lane) => {
+                                let lane_x = x(lane);
+                                let ctrl = point(lane_x, center);
+                                (
+                                    lane,
+                                    point(lane_x, top),
+                                    point(commit_x, center),
+                                    Some(ctrl),
+                                )
+                            }
+                            GraphLine::Outgoing(lane) => {
+                                let lane_x = x(lane);
+                                let ctrl = point(lane_x, center);
+                                (
+                                    lane,
+                                    point(commit_x, center),
+                                    point(lane_x, bottom),
+                                    Some(ctrl),
+                                )
+                            }
+                        };
+                        let mut builder = PathBuilder::stroke(px(1.5));
+                        builder.move_to(from);
+                        match ctrl.filter(|_| from.x != to.x) {
+                            Some(ctrl) => builder.curve_to(to, ctrl),
+                            None => builder.line_to(to),
+                        }
+                        if let Ok(path) = builder.build() {
+                            window.paint_path(path, accents.color_for_index(lane as u32));
+                        }
+                    }
+                },
+            )
+            .absolute()
+            .size_full(),
+        )
+        .child(
+            div()
+                .absolute()
+                .left(dot_origin.x)
+                .top(dot_origin.y)
+                .size(DOT_SIZE)
+                .rounded_full()
+                .bg(dot_color),
+        )
+}
+
+fn short_sha(sha: &str) -> String {
+    sha[..git::SHORT_SHA_LENGTH.min(sha.len())].to_string()
+}
+
+/// Adds a commit to the selection, or removes it if it's already selected.
+/// Only the two most recently selected commits are kept.
+fn toggle_selection(selection: &mut Vec<SharedString>, sha: SharedString) {
+    if let Some(ix) = selection.iter().position(|selected| *selected == sha) {
+        selection.remove(ix);
+        return;
+    }
+    selection.push(sha);
+    if selection.len() > 2 {
+        selection.remove(0);
+    }
+}
+
+fn log_options(
+    all_refs: bool,
+    author: &str,
+    message: &str,
+    path: &str,
+    since: &str,
+    until: &str,
+    offset: UtcOffset,
+) -> Result<LogOptions> {
+    let non_empty = |text: &str| Some(text.trim().to_string()).filter(|text| !text.is_empty());
+    let path = non_empty(path)
+        .map(|path| RepoPath::new(path.trim_matches('/')))
+        .transpose()
+        .context("invalid path, expected a path relative to the repository")?;
+    Ok(LogOptions {
+        all_refs,
+        author: non_empty(author),
+        message: non_empty(message),
+        path,
+        since: parse_date(since, false, offset)?,
+        until: parse_date(until, true, offset)?,
+    })
+}
+
+/// Parses a `YYYY-MM-DD` date as the timestamp of the first second of that
+/// day, or of its last second when `end_of_day` is set.
+fn parse_date(text: &str, end_of_day: bool, offset: UtcOffset) -> Result<Option<i64>> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Ok(None);
+    }
+    let date = Date::parse(text, format_description!("[year]-[month]-[day]"))
+        .with_context(|| format!("invalid date {text:?}, expected YYYY-MM-DD"))?;
+    if !end_of_day {
+        return Ok(Some(date.midnight().assume_offset(offset).unix_timestamp()));
+    }
+    let next_day = date.next_day().context("date out of range")?;
+    Ok(Some(
+        next_day.midnight().assume_offset(offset).unix_timestamp() - 1,
+    ))
+}
+
+impl EventEmitter<ItemEvent> for CommitLogView {}
+
+impl Focusable for CommitLogView {
+    fn focus_handle(&self, _cx: &App) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Render for CommitLogView {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let show_graph = self.options.keeps_ancestry();
+
+        v_flex()
+            .key_context("CommitLogView")
+            .track_focus(&self.focus_handle)
+            .size_full()
+            .bg(cx.theme().colors().editor_background)
+            .child(self.render_header(cx))
+            .when_some(self.sequencer_operation, |this, operation| {
+                this.child(self.render_sequencer_banner(operation, cx))
+            })
+            .child(self.render_filters(cx))
+            .when(
+                self.entries.is_empty() && self.load_task.is_none(),
+                |this| {
+                    this.child(
+                        h_flex().p_3().child(
+                            Label::new("No matching commits")
+                                .size(LabelSize::Small)
+                                .color(Color::Muted),
+                        ),
+                    )
+                },
+            )
+            .child(
+                v_flex()
+                    .flex_1()
+                    .size_full()
+                    .child({
+                        let view = cx.weak_entity();
+                        uniform_list(
+                            "commit-log-list",
+                            self.entries.len(),
+                            move |range, _window, cx| {
+                                let Some(view) = view.upgrade() else {
+                                    return Vec::new();
+                                };
+                                view.update(cx, |this, cx| {
+                                    range
+                                        .filter_map(|ix| {
+                                            let entry = this.entries.get(ix)?.clone();
+                                            Some(this.render_entry(ix, &entry, show_graph, cx))
+                                        })
+                                        .collect()
+                                })
+                            },
+                        )
+                        .flex_1()
+                        .size_full()
+                        .track_scroll(&self.scroll_handle)
+                    })
+                    .vertical_scrollbar_for(&self.scroll_handle, window, cx),
+            )
+            .children(self.context_menu.as_ref().map(|(menu, position, _)| {
+                deferred(
+                    anchored()
+                        .position(*position)
+                        .anchor(Corner::TopLeft)
+                        .child(menu.clone()),
+                )
+                .with_priority(1)
+            }))
+    }
+}
+
+impl Item for CommitLogView {
+    type Event = ItemEvent;
+
+    fn to_item_events(event: &Self::Event, mut f: impl FnMut(ItemEvent)) {
+        f(*event)
+    }
+
+    fn tab_content_text(&self, _detail: usize, _cx: &App) -> SharedString {
+        "Commit Log".into()
+    }
+
+    fn tab_tooltip_text(&self, cx: &App) -> Option<SharedString> {
+        let path = self.repository.read(cx).work_directory_abs_path.clone();
+        Some(format!("Commit log of {}", path.display()).into())
+    }
+
+    fn tab_icon(&self, _window: &Window, _cx: &App) -> Option<Icon> {
+        Some(Icon::new(IconName::GitBranch))
+    }
+
+    fn telemetry_event_text(&self) -> Option<&'static str> {
+        Some("commit log")
+    }
+
+    fn clone_on_split(
+        &self,
+        _workspace_id: Option<workspace::WorkspaceId>,
+        _window: &mut Window,
+        _cx: &mut Context<Self>,
+    ) -> Task<Option<Entity<Self>>> {
+        Task::ready(None)
+    }
+
+    fn navigate(&mut self, _: Box<dyn Any>, _window: &mut Window, _: &mut Context<Self>) -> bool {
+        false
+    }
+
+    fn can_save(&self, _: &App) -> bool {
+        false
+    }
+
+    fn save(
+        &mut self,
+        _options: SaveOptions,
+        _project: Entity<Project>,
+        _window: &mut Window,
+        _: &mut Context<Self>,
+    ) -> Task<Result<()>> {
+        Task::ready(Ok(()))
+    }
+
+    fn save_as(
+        &mut self,
+        _project: Entity<Project>,
+        _path: ProjectPath,
+        _window: &mut Window,
+        _: &mut Context<Self>,
+    ) -> Task<Result<()>> {
+        Task::ready(Ok(()))
+    }
+
+    fn is_dirty(&self, _: &App) -> bool {
+        false
+    }
+
+    fn added_to_workspace(
+        &mut self,
+        _workspace: &mut Workspace,
+        window: &mut Window,
+        _cx: &mut Context<Self>,
+    ) {
+        window.focus(&self.focus_handle);
+    }
+
+    fn act_as_type<'a>(
+        &'a self,
+        type_id: TypeId,
+        self_handle: &'a Entity<Self>,
+        _: &'a App,
+    ) -> Option<AnyEntity> {
+        if type_id == TypeId::of::<Self>() {
+            Some(self_handle.clone().into())
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum RefKind {
+    Branch,
+    Tag,
+}
+
+/// Asks for the name of a branch or tag to create at a commit.
+struct CreateRefModal {
+    kind: RefKind,
+    commit: SharedString,
+    editor: Entity<Editor>,
+    repository: Entity<Repository>,
+    log_view: WeakEntity<CommitLogView>,
+}
+
+impl CreateRefModal {
+    fn new(
+        kind: RefKind,
+        commit: SharedString,
+        repository: Entity<Repository>,
+        log_view: WeakEntity<CommitLogView>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let editor = cx.new(|cx| {
+            let mut editor = Editor::single_line(window, cx);
+            let placeholder = match kind {
+                RefKind::Branch => "Branch name",
+                RefKind::Tag => "Tag name",
+            };
+            editor.set_placeholder_text(placeholder, window, cx);
+            editor
+        });
+        Self {
+            kind,
+            commit,
+            editor,
+            repository,
+            log_view,
+        }
+    }
+
+    fn cancel(&mut self, _: &Cancel, _window: &mut Window, cx: &mut Context<Self>) {
+        cx.emit(DismissEvent);
+    }
+
+    fn confirm(&mut self, _: &Confirm, window: &mut Window, cx: &mut Context<Self>) {
+        let name = self.editor.read(cx).text(cx).trim().to_string();
+        if name.is_empty() {
+            cx.emit(DismissEvent);
+            return;
+        }
+
+        let commit = self.commit.to_string();
+        let task = self.repository.update(cx, |repo, _| match self.kind {
+            RefKind::Branch => repo.create_branch(name, Some(commit)),
+            RefKind::Tag => repo.create_tag(name, commit),
+        });
+        let log_view = self.log_view.clone();
+        let message = match self.kind {
+            RefKind::Branch => "Failed to create branch",
+            RefKind::Tag => "Failed to create tag",
+        };
+        cx.spawn(async move |_, cx| {
+            task.await??;
+            log_view.update(cx, |log_view, cx| log_view.reload(cx)).ok();
+            anyhow::Ok(())
+        })
+        .detach_and_prompt_err(message, window, cx, |_, _, _| None);
+        cx.emit(DismissEvent);
+    }
+}
+
+impl EventEmitter<DismissEvent> for CreateRefModal {}
+impl ModalView for CreateRefModal {}
+impl Focusable for CreateRefModal {
+    fn focus_handle(&self, cx: &App) -> FocusHandle {
+        self.editor.focus_handle(cx)
+    }
+}
+
+impl Render for CreateRefModal {
+    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let (icon, title) = match self.kind {
+            RefKind::Branch => (IconName::GitBranch, "Create Branch"),
+            RefKind::Tag => (IconName::Hash, "Create Tag"),
+        };
+        v_flex()
+            .key_context("CreateRefModal")
+            .on_action(cx.listener(Self::cancel))
+            .on_action(cx.listener(Self::confirm))
+            .elevation_2(cx)
+            .w(rems(34.))
+            .child(
+                h_flex()
+                    .px_3()
+                    .pt_2()
+                    .pb_1()
+                    .w_full()
+                    .gap_1p5()
+                    .child(Icon::new(icon).size(IconSize::XSmall))
+                    .child(
+                        Headline::new(format!("{title} at {}", short_sha(&self.commit)))
+                            .size(HeadlineSize::XSmall),
+                    ),
+            )
+            .child(div().px_3().pb_3().w_full().child(self.editor.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toggle_selection() {
+        let [a, b, c] = ["a", "b", "c"].map(SharedString::from);
+        let mut selection = Vec::new();
+        toggle_selection(&mut selection, a);
+        toggle_selection(&mut selection, b.clone());
+        toggle_selection(&mut selection, c.clone());
+        assert_eq!(selection, [b.clone(), c.clone()]);
+        toggle_selection(&mut selection, b);
+        assert_eq!(selection, [c]);
+    }
+
+    #[test]
+    fn test_parse_date() {
+        assert_eq!(parse_date(" ", false, UtcOffset::UTC).unwrap(), None);
+        assert_eq!(
+            parse_date("2024-01-02", false, UtcOffset::UTC).unwrap(),
+            Some(1704153600)
+        );
+        assert_eq!(
+            parse_date("2024-01-02", true, UtcOffset::UTC).unwrap(),
+            Some(1704239999)
+        );
+        let offset = UtcOffset::from_hms(2, 0, 0).unwrap();
+        assert_eq!(
+            parse_date("2024-01-02", false, offset).unwrap(),
+            Some(1704153600 - 2 * 60 * 60)
+        );
+        assert!(parse_date("01/02/2024", false, UtcOffset::UTC).is_err());
+    }
+
+    #[test]
+    fn test_log_options() {
+        let options =
+            log_options(true, " ada ", "", "src/", "", "2024-01-02", UtcOffset::UTC).unwrap();
+        assert_eq!(
+            options,
+            LogOptions {
+                all_refs: true,
+                author: Some("ada".into()),
+                message: None,
+                path: Some(RepoPath::new("src").unwrap()),
+                since: None,
+                until: Some(1704239999),
+            }
+        );
+        assert!(!options.keeps_ancestry());
+        assert!(log_options(false, "", "", "../x", "", "", UtcOffset::UTC).is_err());
+    }
+}
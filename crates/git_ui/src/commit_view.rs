@@ -54,6 +54,9 @@ pub fn init(cx: &mut App) {
 
 pub struct CommitView {
     commit: CommitDetails,
+    /// The commit that `commit` is compared against, when this view shows the
+    /// changes between two commits rather than the changes made by one.
+    base: Option<CommitDetails>,
     editor: Entity<Editor>,
     stash: Option<usize>,
     multibuffer: Entity<MultiBuffer>,
@@ -80,19 +83,72 @@ impl CommitView {
         file_filter: Option<RepoPath>,
         window: &mut Window,
         cx: &mut App,
+    ) {
+        Self::open_diff(
+            None,
+            commit_sha,
+            repo,
+            workspace,
+            stash,
+            file_filter,
+            window,
+            cx,
+        );
+    }
+
+    /// Opens the changes from `base_sha` to `head_sha`.
+    pub fn open_comparison(
+        base_sha: String,
+        head_sha: String,
+        repo: WeakEntity<Repository>,
+        workspace: WeakEntity<Workspace>,
+        window: &mut Window,
+        cx: &mut App,
+    ) {
+        Self::open_diff(
+            Some(base_sha),
+            head_sha,
+            repo,
+            workspace,
+            None,
+            None,
+            window,
+            cx,
+        );
+    }
+
+    fn open_diff(
+        base_sha: Option<String>,
+        commit_sha: String,
+        repo: WeakEntity<Repository>,
+        workspace: WeakEntity<Workspace>,
+        stash: Option<usize>,
+        file_filter: Option<RepoPath>,
+        window: &mut Window,
+        cx: &mut App,
     ) {
         let commit_diff = repo
-            .update(cx, |repo, _| repo.load_commit_diff(commit_sha.clone()))
+            .update(cx, |repo, _| match base_sha.clone() {
+                Some(base_sha) => repo.compare_commits(base_sha, commit_sha.clone()),
+                None => repo.load_commit_diff(commit_sha.clone()),
+            })
             .ok();
         let commit_details = repo
             .update(cx, |repo, _| repo.show(commit_sha.clone()))
             .ok();
+        let base_details = base_sha
+            .clone()
+            .map(|base_sha| repo.update(cx, |repo, _| repo.show(base_sha)).ok());
 
         window
             .spawn(cx, async move |cx| {
                 let (commit_diff, commit_details) = futures::join!(commit_diff?, commit_details?);
                 let mut commit_diff = commit_diff.log_err()?.log_err()?;
                 let commit_details = commit_details.log_err()?.log_err()?;
+                let base_details = match base_details {
+                    Some(base_details) => Some(base_details?.await.log_err()?.log_err()?),
+                    None => None,
+                };
 
                 // Filter to specific file if requested
                 if let Some(ref filter_path) = file_filter {
@@ -107,6 +163,7 @@ impl CommitView {
                         let commit_view = cx.new(|cx| {
                             CommitView::new(
                                 commit_details,
+                                base_details,
                                 commit_diff,
                                 repo,
                                 project.clone(),
@@ -120,8 +177,12 @@ impl CommitView {
                         pane.update(cx, |pane, cx| {
                             let ix = pane.items().position(|item| {
                                 let commit_view = item.downcast::<CommitView>();
-                                commit_view
-                                    .is_some_and(|view| view.read(cx).commit.sha == commit_sha)
+                                commit_view.is_some_and(|view| {
+                                    let view = view.read(cx);
+                                    view.commit.sha == commit_sha
+                                        && view.base.as_ref().map(|base| base.sha.as_ref())
+                                            == base_sha.as_deref()
+                                })
                             });
                             if let Some(ix) = ix {
                                 pane.activate_item(ix, true, true, window, cx);
@@ -137,6 +198,7 @@ impl CommitView {
 
     fn new(
         commit: CommitDetails,
+        base: Option<CommitDetails>,
         commit_diff: CommitDiff,
         repository: Entity<Repository>,
         project: Entity<Project>,
@@ -147,8 +209,18 @@ impl CommitView {
         let language_registry = project.read(cx).languages().clone();
         let multibuffer = cx.new(|_| MultiBuffer::new(Capability::ReadOnly));
 
+        let message = match &base {
+            Some(base) => format!(
+                "{} {}\n{} {}",
+                short_sha(&base.sha),
+                subject(&base.message),
+                short_sha(&commit.sha),
+                subject(&commit.message)
+            ),
+            None => commit.message.to_string(),
+        };
         let message_buffer = cx.new(|cx| {
-            let mut buffer = Buffer::local(commit.message.clone(), cx);
+            let mut buffer = Buffer::local(message, cx);
             buffer.set_capability(Capability::ReadOnly, cx);
             buffer
         });
@@ -211,7 +283,10 @@ impl CommitView {
             editor
         });
 
-        let commit_sha = Arc::<str>::from(commit.sha.as_ref());
+        let diff_name: Arc<str> = match &base {
+            Some(base) => format!("{}..{}", short_sha(&base.sha), short_sha(&commit.sha)).into(),
+            None => short_sha(&commit.sha).into(),
+        };
 
         let first_worktree_id = project
             .read(cx)
@@ -234,14 +309,13 @@ impl CommitView {
                             .or(first_worktree_id)
                     })?
                     .context("project has no worktrees")?;
-                let short_sha = commit_sha.get(0..7).unwrap_or(&commit_sha);
                 let file_name = file
                     .path
                     .file_name()
                     .map(|name| name.to_string())
                     .unwrap_or_else(|| file.path.display(PathStyle::Posix).to_string());
                 let display_name: Arc<str> =
-                    Arc::from(format!("{short_sha} - {file_name}").into_boxed_str());
+                    Arc::from(format!("{diff_name} - {file_name}").into_boxed_str());
 
                 let file = Arc::new(GitBlob {
                     path: file.path.clone(),
@@ -302,6 +376,7 @@ impl CommitView {
 
         Self {
             commit,
+            base,
             editor,
             multibuffer,
             stash,
@@ -395,15 +470,19 @@ impl CommitView {
             time_format::TimestampFormat::MediumAbsolute,
         );
 
-        let github_url = self.remote.as_ref().map(|remote| {
-            format!(
-                "{}/{}/{}/commit/{}",
-                remote.host.base_url(),
-                remote.owner,
-                remote.repo,
-                commit.sha
-            )
-        });
+        let github_url = self
+            .remote
+            .as_ref()
+            .filter(|_| self.base.is_none())
+            .map(|remote| {
+                format!(
+                    "{}/{}/{}/commit/{}",
+                    remote.host.base_url(),
+                    remote.owner,
+                    remote.repo,
+                    commit.sha
+                )
+            });
 
         let (additions, deletions) = self.calculate_changed_lines(cx);
 
@@ -453,11 +532,18 @@ impl CommitView {
                                     .gap_1()
                                     .child(Label::new(author_name).color(Color::Default))
                                     .child(
-                                        Label::new(format!("Commit:{}", commit.sha))
-                                            .color(Color::Muted)
-                                            .size(LabelSize::Small)
-                                            .truncate()
-                                            .buffer_font(cx),
+                                        Label::new(match &self.base {
+                                            Some(base) => format!(
+                                                "Comparing {}..{}",
+                                                short_sha(&base.sha),
+                                                short_sha(&commit.sha)
+                                            ),
+                                            None => format!("Commit:{}", commit.sha),
+                                        })
+                                        .color(Color::Muted)
+                                        .size(LabelSize::Small)
+                                        .truncate()
+                                        .buffer_font(cx),
                                     ),
                             )
                             .child(
@@ -876,18 +962,35 @@ impl Item for CommitView {
     }
 
     fn tab_content_text(&self, _detail: usize, _cx: &App) -> SharedString {
-        let short_sha = self.commit.sha.get(0..7).unwrap_or(&*self.commit.sha);
-        let subject = truncate_and_trailoff(self.commit.message.split('\n').next().unwrap(), 20);
-        format!("{short_sha} — {subject}").into()
+        let head = short_sha(&self.commit.sha);
+        if let Some(base) = &self.base {
+            return format!("{}..{head}", short_sha(&base.sha)).into();
+        }
+        let subject = truncate_and_trailoff(subject(&self.commit.message), 20);
+        format!("{head} — {subject}").into()
     }
 
     fn tab_tooltip_content(&self, _: &App) -> Option<TabTooltipContent> {
-        let short_sha = self.commit.sha.get(0..16).unwrap_or(&*self.commit.sha);
-        let subject = self.commit.message.split('\n').next().unwrap();
+        let short_sha = match &self.base {
+            Some(base) => format!(
+                "{}..{}",
+                base.sha.get(0..16).unwrap_or(&base.sha),
+                self.commit.sha.get(0..16).unwrap_or(&self.commit.sha)
+            ),
+            None => self
+                .commit
+                .sha
+                .get(0..16)
+                .unwrap_or(&self.commit.sha)
+                .to_string(),
+        };
+        let subject = match &self.base {
+            Some(_) => "Comparing Commits",
+            None => subject(&self.commit.message),
+        };
 
         Some(TabTooltipContent::Custom(Box::new(Tooltip::element({
             let subject = subject.to_string();
-            let short_sha = short_sha.to_string();
 
             move |_, _| {
                 v_flex()
@@ -1005,6 +1108,7 @@ impl Item for CommitView {
                 editor,
                 multibuffer,
                 commit: self.commit.clone(),
+                base: self.base.clone(),
                 stash: self.stash,
                 repository: self.repository.clone(),
                 remote: self.remote.clone(),
@@ -1071,6 +1175,14 @@ impl ToolbarItemView for CommitViewToolbar {
     }
 }
 
+fn short_sha(sha: &str) -> &str {
+    sha.get(0..7).unwrap_or(sha)
+}
+
+fn subject(message: &str) -> &str {
+    message.split('\n').next().unwrap_or_default()
+}
+
 fn stash_matches_index(sha: &str, stash_index: usize, repo: &Repository) -> bool {
     repo.stash_entries
         .entries
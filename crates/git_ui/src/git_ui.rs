@@ -31,6 +31,8 @@ use crate::{git_panel::GitPanel, text_diff_view::TextDiffView};
 
 mod askpass_modal;
 pub mod branch_picker;
+mod commit_graph;
+pub mod commit_log_view;
 mod commit_modal;
 pub mod commit_tooltip;
 pub mod commit_view;
@@ -42,6 +44,7 @@ mod git_panel_settings;
 pub mod onboarding;
 pub mod picker_prompt;
 pub mod project_diff;
+pub mod rebase_todo_view;
 pub(crate) mod remote_output;
 pub mod repository_selector;
 pub mod stash_picker;
@@ -74,6 +77,7 @@ pub fn init(cx: &mut App) {
         branch_picker::register(workspace);
         worktree_picker::register(workspace);
         stash_picker::register(workspace);
+        commit_log_view::register(workspace);
 
         let project = workspace.project().read(cx);
         if project.is_read_only(cx) {
@@ -137,6 +141,7 @@ pub fn init(cx: &mut App) {
                 });
             });
         }
+        rebase_todo_view::register(workspace);
         workspace.register_action(|workspace, action: &git::StashAll, window, cx| {
             let Some(panel) = workspace.panel::<git_panel::GitPanel>(cx) else {
                 return;
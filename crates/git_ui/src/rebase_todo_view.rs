@@ -0,0 +1,969 @@
+use anyhow::{Context as _, Result};
+use editor::Editor;
+use git::repository::{RebaseAction, RebaseState, RebaseTodoEntry, RepoPath};
+use gpui::{
+    AnyElement, AnyEntity, App, AsyncWindowContext, Context, Entity, EventEmitter, FocusHandle,
+    Focusable, IntoElement, Render, Subscription, Task, UniformListScrollHandle, WeakEntity,
+    Window, uniform_list,
+};
+use project::{
+    Project, ProjectPath,
+    git_store::{Repository, RepositoryEvent},
+};
+use std::any::{Any, TypeId};
+use ui::{Chip, Divider, ListItem, Tooltip, WithScrollbar, prelude::*};
+use workspace::{
+    Item, Workspace,
+    item::{ItemEvent, SaveOptions},
+};
+
+use crate::{git_panel::show_error_toast, project_diff::ProjectDiff};
+
+const ACTIONS: [RebaseAction; 5] = [
+    RebaseAction::Pick,
+    RebaseAction::Reword,
+    RebaseAction::Squash,
+    RebaseAction::Fixup,
+    RebaseAction::Drop,
+];
+
+pub fn register(workspace: &mut Workspace) {
+    workspace.register_action(|workspace, _: &git::InteractiveRebase, window, cx| {
+        RebaseTodoView::deploy(workspace, None, window, cx);
+    });
+    workspace.register_action(|workspace, _: &git::RebaseContinue, window, cx| {
+        if let Some(view) = RebaseTodoView::deploy(workspace, None, window, cx) {
+            view.update(cx, |view, cx| view.continue_rebase(window, cx));
+        }
+    });
+    workspace.register_action(|workspace, _: &git::RebaseSkip, window, cx| {
+        if let Some(view) = RebaseTodoView::deploy(workspace, None, window, cx) {
+            view.update(cx, |view, cx| view.skip_commit(window, cx));
+        }
+    });
+    workspace.register_action(|workspace, _: &git::RebaseAbort, window, cx| {
+        if let Some(view) = RebaseTodoView::deploy(workspace, None, window, cx) {
+            view.update(cx, |view, cx| view.abort_rebase(window, cx));
+        }
+    });
+}
+
+/// An editor for the todo list of an interactive rebase, which also drives a
+/// rebase that stopped partway through.
+pub struct RebaseTodoView {
+    repository: Entity<Repository>,
+    workspace: WeakEntity<Workspace>,
+    /// The commit the plan is rebased onto, once it's known.
+    base: Option<String>,
+    plan: Vec<RebaseTodoEntry>,
+    state: Option<RebaseState>,
+    loading: bool,
+    pending_operation: Option<Task<()>>,
+    selected_entry: Option<usize>,
+    message_editor: Entity<Editor>,
+    /// The plan entry whose message is in `message_editor`.
+    editing_message: Option<usize>,
+    scroll_handle: UniformListScrollHandle,
+    focus_handle: FocusHandle,
+    _subscriptions: Vec<Subscription>,
+}
+
+impl RebaseTodoView {
+    /// Opens the rebase editor for the active repository, or activates the one
+    /// already open. `base` is the commit to rebase onto, defaulting to the
+    /// upstream of the current branch.
+    pub fn deploy(
+        workspace: &mut Workspace,
+        base: Option<String>,
+        window: &mut Window,
+        cx: &mut Context<Workspace>,
+    ) -> Option<Entity<Self>> {
+        let repository = workspace.project().read(cx).active_repository(cx)?;
+        let existing = workspace
+            .items_of_type::<Self>(cx)
+            .find(|view| view.read(cx).repository == repository);
+        if let Some(existing) = existing {
+            workspace.activate_item(&existing, true, true, window, cx);
+            if base.is_some() {
+                existing.update(cx, |view, cx| {
+                    view.base = base;
+                    view.plan.clear();
+                    view.reload(window, cx);
+                });
+            }
+            return Some(existing);
+        }
+
+        let view = cx.new(|cx| Self::new(repository, workspace.weak_handle(), base, window, cx));
+        workspace.add_item_to_active_pane(Box::new(view.clone()), None, true, window, cx);
+        Some(view)
+    }
+
+    fn new(
+        repository: Entity<Repository>,
+        workspace: WeakEntity<Workspace>,
+        base: Option<String>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let message_editor = cx.new(|cx| {
+            let mut editor = Editor::auto_height(3, 12, window, cx);
+            editor.set_placeholder_text("Commit message", window, cx);
+            editor.set_show_gutter(false, cx);
+            editor
+        });
+
+        let subscriptions = vec![cx.subscribe_in(
+            &repository,
+            window,
+            |this, _, event: &RepositoryEvent, window, cx| match event {
+                RepositoryEvent::MergeHeadsChanged | RepositoryEvent::BranchChanged => {
+                    if this.pending_operation.is_none() {
+                        this.reload(window, cx);
+                    }
+                }
+                RepositoryEvent::StatusesChanged => cx.notify(),
+                _ => {}
+            },
+        )];
+
+        let mut this = Self {
+            repository,
+            workspace,
+            base,
+            plan: Vec::new(),
+            state: None,
+            loading: false,
+            pending_operation: None,
+            selected_entry: None,
+            message_editor,
+            editing_message: None,
+            scroll_handle: UniformListScrollHandle::new(),
+            focus_handle: cx.focus_handle(),
+            _subscriptions: subscriptions,
+        };
+        this.reload(window, cx);
+        this
+    }
+
+    /// Loads the rebase in progress, or the commits to rebase if there's none.
+    fn reload(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.loading = true;
+        cx.notify();
+
+        let repository = self.repository.clone();
+        let base = self.base.clone();
+        let reload_plan = self.plan.is_empty();
+        cx.spawn_in(window, async move |this, cx| {
+            let result = async {
+                let state = repository
+                    .update(cx, |repo, _| repo.rebase_state())?
+                    .await??;
+                if state.is_some() || !reload_plan {
+                    return anyhow::Ok((state, None, None));
+                }
+
+                let base = match base {
+                    Some(base) => base,
+                    None => default_base(&repository, cx).await?,
+                };
+                let plan = repository
+                    .update(cx, |repo, _| repo.rebase_todo(base.clone()))?
+                    .await??;
+                Ok((None, Some(base), Some(plan)))
+            }
+            .await;
+
+            this.update_in(cx, |this, window, cx| {
+                this.loading = false;
+                match result {
+                    Ok((state, base, plan)) => {
+                        this.state = state;
+                        if let Some(base) = base {
+                            this.base = Some(base);
+                        }
+                        if let Some(plan) = plan {
+                            this.set_plan(plan, window, cx);
+                        }
+                    }
+                    Err(error) => this.show_error("rebase", error, cx),
+                }
+                cx.notify();
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    fn set_plan(
+        &mut self,
+        plan: Vec<RebaseTodoEntry>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.editing_message = None;
+        self.plan = plan;
+        self.selected_entry = None;
+        self.edit_message(None, window, cx);
+    }
+
+    fn select_entry(&mut self, ix: usize, window: &mut Window, cx: &mut Context<Self>) {
+        self.selected_entry = Some(ix);
+        let rewording = self
+            .plan
+            .get(ix)
+            .is_some_and(|entry| entry.action == RebaseAction::Reword);
+        self.edit_message(rewording.then_some(ix), window, cx);
+        cx.notify();
+    }
+
+    fn set_action(
+        &mut self,
+        ix: usize,
+        action: RebaseAction,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(entry) = self.plan.get_mut(ix) else {
+            return;
+        };
+        entry.action = action;
+        if action == RebaseAction::Reword && entry.message.is_none() {
+            self.load_message(ix, window, cx);
+        }
+        self.select_entry(ix, window, cx);
+    }
+
+    /// Fills in the full message of a commit that is about to be reworded.
+    fn load_message(&mut self, ix: usize, window: &mut Window, cx: &mut Context<Self>) {
+        let sha = self.plan[ix].sha.clone();
+        let details = self
+            .repository
+            .update(cx, |repo, _| repo.show(sha.to_string()));
+        cx.spawn_in(window, async move |this, cx| {
+            let Ok(Ok(details)) = details.await else {
+                return;
+            };
+            this.update_in(cx, |this, window, cx| {
+                let Some(entry) = this.plan.get_mut(ix).filter(|entry| entry.sha == sha) else {
+                    return;
+                };
+                if entry.message.is_none() {
+                    entry.message = Some(details.message.trim_end().to_string().into());
+                    if this.editing_message == Some(ix) {
+                        this.edit_message(Some(ix), window, cx);
+                    }
+                }
+            })
+            .ok();
+        })
+        .detach();
+    }
+
+    fn edit_message(&mut self, ix: Option<usize>, window: &mut Window, cx: &mut Context<Self>) {
+        self.store_message(cx);
+        self.editing_message = ix;
+        let message = ix
+            .and_then(|ix| self.plan.get(ix))
+            .map(displayed_message)
+            .unwrap_or_default();
+        self.message_editor.update(cx, |editor, cx| {
+            editor.set_text(message.as_ref(), window, cx);
+        });
+    }
+
+    /// Keeps the message being edited, unless it's still the one displayed.
+    fn store_message(&mut self, cx: &App) {
+        let Some(entry) = self.editing_message.and_then(|ix| self.plan.get_mut(ix)) else {
+            return;
+        };
+        let text = self.message_editor.read(cx).text(cx);
+        if text != displayed_message(entry).as_ref() {
+            entry.message = Some(text.into());
+        }
+    }
+
+    fn move_entry(&mut self, ix: usize, up: bool, window: &mut Window, cx: &mut Context<Self>) {
+        if let Some(new_ix) = move_entry(&mut self.plan, ix, up) {
+            self.select_entry(new_ix, window, cx);
+        }
+    }
+
+    fn start_rebase(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(base) = self.base.clone() else {
+            return;
+        };
+        if validate_plan(&self.plan).is_some() {
+            return;
+        }
+        self.store_message(cx);
+        let rebase = self
+            .repository
+            .update(cx, |repo, _| repo.rebase(base, self.plan.clone()));
+        self.run_operation("rebase", async move { rebase.await? }, window, cx);
+    }
+
+    pub fn continue_rebase(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let task = self.repository.update(cx, |repo, _| repo.rebase_continue());
+        self.run_operation("rebase --continue", async move { task.await? }, window, cx);
+    }
+
+    pub fn skip_commit(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let task = self.repository.update(cx, |repo, _| repo.rebase_skip());
+        self.run_operation("rebase --skip", async move { task.await? }, window, cx);
+    }
+
+    pub fn abort_rebase(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let task = self.repository.update(cx, |repo, _| repo.rebase_abort());
+        let abort = async move {
+            task.await??;
+            Ok(None)
+        };
+        self.run_operation("rebase --abort", abort, window, cx);
+    }
+
+    fn run_operation(
+        &mut self,
+        name: &'static str,
+        operation: impl Future<Output = Result<Option<RebaseState>>> + 'static,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if self.pending_operation.is_some() {
+            return;
+        }
+        let task = cx.spawn_in(window, async move |this, cx| {
+            let result = operation.await;
+            this.update_in(cx, |this, window, cx| {
+                this.pending_operation = None;
+                match result {
+                    Ok(state) => {
+                        // Once the rebase is over, list the rebased commits.
+                        if state.is_none() {
+                            this.plan.clear();
+                        }
+                        this.state = state;
+                        this.reload(window, cx);
+                    }
+                    Err(error) => {
+                        this.show_error(name, error, cx);
+                        this.reload(window, cx);
+                    }
+                }
+            })
+            .ok();
+        });
+        self.pending_operation = Some(task);
+        cx.notify();
+    }
+
+    fn show_error(&self, action: &'static str, error: anyhow::Error, cx: &mut App) {
+        if let Some(workspace) = self.workspace.upgrade() {
+            show_error_toast(workspace, action, error, cx);
+        }
+    }
+
+    fn conflicted_paths(&self, cx: &App) -> Vec<RepoPath> {
+        self.repository
+            .read(cx)
+            .merge
+            .conflicted_paths
+            .iter()
+            .cloned()
+            .collect()
+    }
+
+    /// Opens a conflicted file, where the conflict markers can be resolved.
+    fn open_conflict(&mut self, path: &RepoPath, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(project_path) = self.repository.read(cx).repo_path_to_project_path(path, cx)
+        else {
+            return;
+        };
+        self.workspace
+            .update(cx, |workspace, cx| {
+                workspace
+                    .open_path(project_path, None, true, window, cx)
+                    .detach_and_log_err(cx);
+            })
+            .ok();
+    }
+
+    fn open_project_diff(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.workspace
+            .update(cx, |workspace, cx| {
+                ProjectDiff::deploy_at(workspace, None, window, cx);
+            })
+            .ok();
+    }
+
+    fn render_plan_entry(
+        &self,
+        ix: usize,
+        entry: &RebaseTodoEntry,
+        cx: &mut Context<Self>,
+    ) -> AnyElement {
+        let is_dropped = entry.action == RebaseAction::Drop;
+        let subject = match (&entry.action, &entry.message) {
+            (RebaseAction::Reword, Some(message)) => message.lines().next().unwrap_or_default(),
+            _ => entry.subject.as_ref(),
+        };
+
+        ListItem::new(("rebase-entry", ix))
+            .toggle_state(self.selected_entry == Some(ix))
+            .child(
+                h_flex()
+                    .h_8()
+                    .w_full()
+                    .pl_0p5()
+                    .pr_2p5()
+                    .gap_2()
+                    .child(h_flex().gap_0p5().children(ACTIONS.map(|action| {
+                        Button::new(
+                            SharedString::from(format!("{}-{ix}", action.as_str())),
+                            action_label(action),
+                        )
+                        .label_size(LabelSize::Small)
+                        .toggle_state(entry.action == action)
+                        .on_click(cx.listener(
+                            move |this, _, window, cx| {
+                                this.set_action(ix, action, window, cx);
+                            },
+                        ))
+                    })))
+                    .child(div().flex_none().child(Chip::new(short_sha(&entry.sha))))
+                    .child(
+                        Label::new(subject.to_string())
+                            .size(LabelSize::Small)
+                            .color(if is_dropped {
+                                Color::Muted
+                            } else {
+                                Color::Default
+                            })
+                            .when(is_dropped, |label| label.strikethrough())
+                            .truncate(),
+                    )
+                    .child(div().flex_1())
+                    .child(
+                        IconButton::new(("move-up", ix), IconName::ArrowUp)
+                            .icon_size(IconSize::Small)
+                            .disabled(ix == 0)
+                            .tooltip(Tooltip::text("Move Up"))
+                            .on_click(cx.listener(move |this, _, window, cx| {
+                                this.move_entry(ix, true, window, cx);
+                            })),
+                    )
+                    .child(
+                        IconButton::new(("move-down", ix), IconName::ArrowDown)
+                            .icon_size(IconSize::Small)
+                            .disabled(ix + 1 >= self.plan.len())
+                            .tooltip(Tooltip::text("Move Down"))
+                            .on_click(cx.listener(move |this, _, window, cx| {
+                                this.move_entry(ix, false, window, cx);
+                            })),
+                    ),
+            )
+            .on_click(cx.listener(move |this, _, window, cx| {
+                this.select_entry(ix, window, cx);
+            }))
+            .into_any_element()
+    }
+
+    fn render_plan(&self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let plan_error = validate_plan(&self.plan);
+        let can_start =
+            plan_error.is_none() && !self.plan.is_empty() && self.pending_operation.is_none();
+        let base = self.base.clone().unwrap_or_else(|| "upstream".to_string());
+
+        v_flex()
+            .size_full()
+            .child(
+                self.render_header(
+                    format!("Rebase onto {base}"),
+                    h_flex()
+                        .gap_1p5()
+                        .child(
+                            Label::new(format!("{} commits", self.plan.len()))
+                                .size(LabelSize::Small)
+                                .color(Color::Muted)
+                                .mr_1(),
+                        )
+                        .child(Divider::vertical())
+                        .child(
+                            Button::new("reset-plan", "Reset")
+                                .label_size(LabelSize::Small)
+                                .disabled(self.loading)
+                                .on_click(cx.listener(|this, _, window, cx| {
+                                    this.plan.clear();
+                                    this.reload(window, cx);
+                                })),
+                        )
+                        .child(
+                            Button::new("start-rebase", "Start Rebase")
+                                .label_size(LabelSize::Small)
+                                .style(ButtonStyle::Filled)
+                                .disabled(!can_start)
+                                .when_some(plan_error, |this, error| {
+                                    this.tooltip(Tooltip::text(error))
+                                })
+                                .on_click(cx.listener(|this, _, window, cx| {
+                                    this.start_rebase(window, cx);
+                                })),
+                        ),
+                    cx,
+                ),
+            )
+            .when(self.plan.is_empty() && !self.loading, |this| {
+                this.child(
+                    h_flex().p_3().child(
+                        Label::new(format!("No commits after {base}"))
+                            .size(LabelSize::Small)
+                            .color(Color::Muted),
+                    ),
+                )
+            })
+            .child(
+                v_flex()
+                    .flex_1()
+                    .size_full()
+                    .child({
+                        let view = cx.weak_entity();
+                        uniform_list(
+                            "rebase-todo-list",
+                            self.plan.len(),
+                            move |range, _window, cx| {
+                                let Some(view) = view.upgrade() else {
+                                    return Vec::new();
+                                };
+                                view.update(cx, |this, cx| {
+                                    range
+                                        .filter_map(|ix| {
+                                            let entry = this.plan.get(ix)?.clone();
+                                            Some(this.render_plan_entry(ix, &entry, cx))
+                                        })
+                                        .collect()
+                                })
+                            },
+                        )
+                        .flex_1()
+                        .size_full()
+                        .track_scroll(&self.scroll_handle)
+                    })
+                    .vertical_scrollbar_for(&self.scroll_handle, window, cx),
+            )
+            .when(self.editing_message.is_some(), |this| {
+                this.child(
+                    v_flex()
+                        .p_2()
+                        .gap_1()
+                        .border_t_1()
+                        .border_color(cx.theme().colors().border_variant)
+                        .child(
+                            Label::new("New Message")
+                                .size(LabelSize::Small)
+                                .color(Color::Muted),
+                        )
+                        .child(self.message_editor.clone()),
+                )
+            })
+    }
+
+    fn render_progress(&self, state: &RebaseState, cx: &mut Context<Self>) -> impl IntoElement {
+        let conflicted_paths = self.conflicted_paths(cx);
+        let is_busy = self.pending_operation.is_some();
+        let title = match &state.head_name {
+            Some(head_name) => format!(
+                "Rebasing {} onto {}",
+                head_name.trim_start_matches("refs/heads/"),
+                short_sha(&state.onto)
+            ),
+            None => format!("Rebasing onto {}", short_sha(&state.onto)),
+        };
+        let stopped_entry = state.done.last().filter(|_| state.stopped_sha.is_some());
+
+        v_flex()
+            .size_full()
+            .child(
+                self.render_header(
+                    title,
+                    h_flex()
+                        .gap_1()
+                        .child(
+                            Button::new("rebase-abort", "Abort")
+                                .label_size(LabelSize::Small)
+                                .disabled(is_busy)
+                                .on_click(cx.listener(|this, _, window, cx| {
+                                    this.abort_rebase(window, cx);
+                                })),
+                        )
+                        .child(
+                            Button::new("rebase-skip", "Skip Commit")
+                                .label_size(LabelSize::Small)
+                                .disabled(is_busy)
+                                .on_click(cx.listener(|this, _, window, cx| {
+                                    this.skip_commit(window, cx);
+                                })),
+                        )
+                        .child(
+                            Button::new("rebase-continue", "Continue")
+                                .label_size(LabelSize::Small)
+                                .style(ButtonStyle::Filled)
+                                .disabled(is_busy || !conflicted_paths.is_empty())
+                                .when(!conflicted_paths.is_empty(), |this| {
+                                    this.tooltip(Tooltip::text("Resolve conflicts to continue"))
+                                })
+                                .on_click(cx.listener(|this, _, window, cx| {
+                                    this.continue_rebase(window, cx);
+                                })),
+                        ),
+                    cx,
+                ),
+            )
+            .child(
+                v_flex()
+                    .id("rebase-progress")
+                    .flex_1()
+                    .overflow_y_scroll()
+                    .p_2()
+                    .gap_1()
+                    .when_some(stopped_entry, |this, entry| {
+                        this.child(
+                            h_flex()
+                                .gap_2()
+                                .child(
+                                    Icon::new(IconName::Warning)
+                                        .size(IconSize::Small)
+                                        .color(Color::Warning),
+                                )
+                                .child(Label::new(format!(
+                                    "Stopped at {} {}",
+                                    short_sha(&entry.sha),
+                                    entry.subject
+                                ))),
+                        )
+                    })
+                    .when(!conflicted_paths.is_empty(), |this| {
+                        this.child(
+                            h_flex()
+                                .pt_2()
+                                .justify_between()
+                                .child(
+                                    Label::new("Conflicts")
+                                        .size(LabelSize::Small)
+                                        .color(Color::Muted),
+                                )
+                                .child(
+                                    Button::new("open-project-diff", "Open in Diff")
+                                        .label_size(LabelSize::Small)
+                                        .on_click(cx.listener(|this, _, window, cx| {
+                                            this.open_project_diff(window, cx);
+                                        })),
+                                ),
+                        )
+                        .children(
+                            conflicted_paths.into_iter().enumerate().map(|(ix, path)| {
+                                ListItem::new(("conflict", ix))
+                                    .start_slot(
+                                        Icon::new(IconName::Warning)
+                                            .size(IconSize::Small)
+                                            .color(Color::Conflict),
+                                    )
+                                    .child(
+                                        Label::new(path.as_unix_str().to_string())
+                                            .size(LabelSize::Small)
+                                            .buffer_font(cx),
+                                    )
+                                    .on_click(cx.listener(move |this, _, window, cx| {
+                                        this.open_conflict(&path, window, cx);
+                                    }))
+                            }),
+                        )
+                    })
+                    .child(
+                        Label::new("Done")
+                            .size(LabelSize::Small)
+                            .color(Color::Muted)
+                            .mt_2(),
+                    )
+                    .children(
+                        state
+                            .done
+                            .iter()
+                            .map(|entry| render_progress_entry(entry, Color::Muted)),
+                    )
+                    .when(!state.todo.is_empty(), |this| {
+                        this.child(
+                            Label::new("Remaining")
+                                .size(LabelSize::Small)
+                                .color(Color::Muted)
+                                .mt_2(),
+                        )
+                        .children(
+                            state
+                                .todo
+                                .iter()
+                                .map(|entry| render_progress_entry(entry, Color::Default)),
+                        )
+                    }),
+            )
+    }
+
+    fn render_header(
+        &self,
+        title: String,
+        controls: impl IntoElement,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        h_flex()
+            .h(rems_from_px(41.))
+            .pl_3()
+            .pr_2()
+            .justify_between()
+            .border_b_1()
+            .border_color(cx.theme().colors().border_variant)
+            .child(Label::new(title).color(Color::Muted))
+            .child(controls)
+    }
+}
+
+fn render_progress_entry(entry: &RebaseTodoEntry, color: Color) -> impl IntoElement {
+    h_flex()
+        .gap_2()
+        .child(
+            Label::new(entry.action.as_str())
+                .size(LabelSize::Small)
+                .color(Color::Muted),
+        )
+        .child(Chip::new(short_sha(&entry.sha)))
+        .child(
+            Label::new(entry.subject.clone())
+                .size(LabelSize::Small)
+                .color(color)
+                .truncate(),
+        )
+}
+
+fn displayed_message(entry: &RebaseTodoEntry) -> SharedString {
+    entry
+        .message
+        .clone()
+        .unwrap_or_else(|| entry.subject.clone())
+}
+
+fn short_sha(sha: &str) -> String {
+    sha[..git::SHORT_SHA_LENGTH.min(sha.len())].to_string()
+}
+
+fn action_label(action: RebaseAction) -> &'static str {
+    match action {
+        RebaseAction::Pick => "Pick",
+        RebaseAction::Reword => "Reword",
+        RebaseAction::Squash => "Squash",
+        RebaseAction::Fixup => "Fixup",
+        RebaseAction::Drop => "Drop",
+    }
+}
+
+/// The upstream of the current branch, or the default branch if it has none.
+async fn default_base(
+    repository: &Entity<Repository>,
+    cx: &mut AsyncWindowContext,
+) -> Result<String> {
+    let upstream = repository.read_with(cx, |repo, _| {
+        repo.branch
+            .as_ref()
+            .and_then(|branch| branch.upstream.as_ref())
+            .map(|upstream| upstream.ref_name.to_string())
+    })?;
+    if let Some(upstream) = upstream {
+        return Ok(upstream);
+    }
+    let default_branch = repository
+        .update(cx, |repo, _| repo.default_branch())?
+        .await??;
+    default_branch
+        .map(|branch| branch.to_string())
+        .context("could not determine which commit to rebase onto")
+}
+
+/// Moves the entry at `ix` one place up or down, returning its new index.
+fn move_entry(plan: &mut [RebaseTodoEntry], ix: usize, up: bool) -> Option<usize> {
+    let new_ix = if up {
+        ix.checked_sub(1)?
+    } else {
+        Some(ix + 1).filter(|new_ix| *new_ix < plan.len())?
+    };
+    plan.swap(ix, new_ix);
+    Some(new_ix)
+}
+
+/// Explains why git would refuse to run `plan`, if it would.
+fn validate_plan(plan: &[RebaseTodoEntry]) -> Option<&'static str> {
+    let first = plan
+        .iter()
+        .find(|entry| entry.action != RebaseAction::Drop)?;
+    matches!(first.action, RebaseAction::Squash | RebaseAction::Fixup)
+        .then_some("The first commit can't be squashed or fixed up into a previous commit")
+}
+
+impl EventEmitter<ItemEvent> for RebaseTodoView {}
+
+impl Focusable for RebaseTodoView {
+    fn focus_handle(&self, _cx: &App) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Render for RebaseTodoView {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        v_flex()
+            .key_context("RebaseTodoView")
+            .track_focus(&self.focus_handle)
+            .size_full()
+            .bg(cx.theme().colors().editor_background)
+            .map(|this| match self.state.clone() {
+                Some(state) => this.child(self.render_progress(&state, cx)),
+                None => this.child(self.render_plan(window, cx)),
+            })
+    }
+}
+
+impl Item for RebaseTodoView {
+    type Event = ItemEvent;
+
+    fn to_item_events(event: &Self::Event, mut f: impl FnMut(ItemEvent)) {
+        f(*event)
+    }
+
+    fn tab_content_text(&self, _detail: usize, _cx: &App) -> SharedString {
+        "Interactive Rebase".into()
+    }
+
+    fn tab_tooltip_text(&self, cx: &App) -> Option<SharedString> {
+        let path = self.repository.read(cx).work_directory_abs_path.clone();
+        Some(format!("Interactive rebase in {}", path.display()).into())
+    }
+
+    fn tab_icon(&self, _window: &Window, _cx: &App) -> Option<Icon> {
+        Some(Icon::new(IconName::GitBranch))
+    }
+
+    fn telemetry_event_text(&self) -> Option<&'static str> {
+        Some("interactive rebase")
+    }
+
+    fn clone_on_split(
+        &self,
+        _workspace_id: Option<workspace::WorkspaceId>,
+        _window: &mut Window,
+        _cx: &mut Context<Self>,
+    ) -> Task<Option<Entity<Self>>> {
+        Task::ready(None)
+    }
+
+    fn navigate(&mut self, _: Box<dyn Any>, _window: &mut Window, _: &mut Context<Self>) -> bool {
+        false
+    }
+
+    fn can_save(&self, _: &App) -> bool {
+        false
+    }
+
+    fn save(
+        &mut self,
+        _options: SaveOptions,
+        _project: Entity<Project>,
+        _window: &mut Window,
+        _: &mut Context<Self>,
+    ) -> Task<Result<()>> {
+        Task::ready(Ok(()))
+    }
+
+    fn save_as(
+        &mut self,
+        _project: Entity<Project>,
+        _path: ProjectPath,
+        _window: &mut Window,
+        _: &mut Context<Self>,
+    ) -> Task<Result<()>> {
+        Task::ready(Ok(()))
+    }
+
+    fn is_dirty(&self, _: &App) -> bool {
+        false
+    }
+
+    fn added_to_workspace(
+        &mut self,
+        _workspace: &mut Workspace,
+        window: &mut Window,
+        _cx: &mut Context<Self>,
+    ) {
+        window.focus(&self.focus_handle);
+    }
+
+    fn act_as_type<'a>(
+        &'a self,
+        type_id: TypeId,
+        self_handle: &'a Entity<Self>,
+        _: &'a App,
+    ) -> Option<AnyEntity> {
+        if type_id == TypeId::of::<Self>() {
+            Some(self_handle.clone().into())
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(action: RebaseAction, sha: &str) -> RebaseTodoEntry {
+        RebaseTodoEntry {
+            action,
+            sha: sha.to_string().into(),
+            subject: format!("commit {sha}").into(),
+            message: None,
+        }
+    }
+
+    #[test]
+    fn test_move_entry() {
+        let mut plan = vec![
+            entry(RebaseAction::Pick, "a"),
+            entry(RebaseAction::Pick, "b"),
+            entry(RebaseAction::Pick, "c"),
+        ];
+
+        assert_eq!(move_entry(&mut plan, 0, true), None);
+        assert_eq!(move_entry(&mut plan, 2, false), None);
+        assert_eq!(move_entry(&mut plan, 2, true), Some(1));
+        assert_eq!(move_entry(&mut plan, 0, false), Some(1));
+        let shas = plan
+            .iter()
+            .map(|entry| entry.sha.as_ref())
+            .collect::<Vec<_>>();
+        assert_eq!(shas, ["c", "a", "b"]);
+    }
+
+    #[test]
+    fn test_validate_plan() {
+        assert_eq!(validate_plan(&[]), None);
+        assert_eq!(
+            validate_plan(&[
+                entry(RebaseAction::Pick, "a"),
+                entry(RebaseAction::Squash, "b"),
+                entry(RebaseAction::Fixup, "c"),
+            ]),
+            None
+        );
+        assert!(
+            validate_plan(&[
+                entry(RebaseAction::Drop, "a"),
+                entry(RebaseAction::Fixup, "b"),
+            ])
+            .is_some()
+        );
+    }
+}
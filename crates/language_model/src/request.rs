@@ -1,9 +1,13 @@
 use std::io::{Cursor, Write};
+use std::ops::Range;
+use std::path::Path;
 use std::sync::Arc;
 
+use anthropic::{CacheSegment, CacheSegmentKind, plan_cache_breakpoints};
 use anyhow::Result;
 use base64::write::EncoderWriter;
 use cloud_llm_client::{CompletionIntent, CompletionMode};
+use collections::HashMap;
 use gpui::{
     App, AppContext as _, DevicePixels, Image, ImageFormat, ObjectFit, SharedString, Size, Task,
     point, px, size,
@@ -13,7 +17,8 @@ use serde::{Deserialize, Serialize};
 use util::ResultExt;
 
 use crate::role::Role;
-use crate::{LanguageModelToolUse, LanguageModelToolUseId};
+use crate::tokens::count_tokens;
+use crate::{LanguageModelCacheConfiguration, LanguageModelToolUse, LanguageModelToolUseId};
 
 #[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Hash)]
 pub struct LanguageModelImage {
@@ -374,6 +379,26 @@ impl LanguageModelRequestMessage {
     pub fn contents_empty(&self) -> bool {
         self.content.iter().all(|content| content.is_empty())
     }
+
+    /// The tokens the message adds to a prompt, counted with the cl100k BPE.
+    /// Thinking from earlier turns isn't sent back, so it isn't counted.
+    fn count_tokens(&self) -> usize {
+        self.content
+            .iter()
+            .map(|content| match content {
+                MessageContent::Text(text) => count_tokens(text),
+                MessageContent::Thinking { .. } | MessageContent::RedactedThinking(_) => 0,
+                MessageContent::Image(image) => image.estimate_tokens(),
+                MessageContent::ToolUse(tool_use) => {
+                    count_tokens(&tool_use.name) + count_tokens(&tool_use.raw_input)
+                }
+                MessageContent::ToolResult(tool_result) => match &tool_result.content {
+                    LanguageModelToolResultContent::Text(text) => count_tokens(text),
+                    LanguageModelToolResultContent::Image(image) => image.estimate_tokens(),
+                },
+            })
+            .sum()
+    }
 }
 
 #[derive(Debug, PartialEq, Hash, Clone, Serialize, Deserialize)]
@@ -404,6 +429,233 @@ pub struct LanguageModelRequest {
     pub thinking_allowed: bool,
 }
 
+impl LanguageModelRequest {
+    /// Marks the messages to place cache breakpoints after, within the limits
+    /// of the model's cache configuration, and returns how many messages are
+    /// marked.
+    ///
+    /// Only messages up to the last one already marked are considered, or the
+    /// whole request if none is, so callers decide which trailing messages are
+    /// too volatile to cache. Existing marks are kept.
+    pub fn place_cache_anchors(
+        &mut self,
+        configuration: &LanguageModelCacheConfiguration,
+    ) -> usize {
+        let cacheable_len = self
+            .messages
+            .iter()
+            .rposition(|message| message.cache)
+            .map_or(self.messages.len(), |ix| ix + 1);
+
+        // Tools are sent first, then every system message, then the conversation.
+        let mut leading_tokens = self
+            .tools
+            .iter()
+            .map(|tool| {
+                count_tokens(&tool.name)
+                    + count_tokens(&tool.description)
+                    + count_tokens(&tool.input_schema.to_string())
+            })
+            .sum::<usize>();
+        let mut segments = Vec::new();
+        let mut segment_messages = Vec::new();
+
+        let system_messages = (0..cacheable_len)
+            .filter(|&ix| self.messages[ix].role == Role::System)
+            .collect::<Vec<_>>();
+        if let Some(&last_system) = system_messages.last() {
+            let system_tokens = system_messages
+                .iter()
+                .map(|&ix| self.messages[ix].count_tokens())
+                .sum::<usize>();
+            segments.push(CacheSegment {
+                tokens: leading_tokens + system_tokens,
+                kind: CacheSegmentKind::Static,
+            });
+            segment_messages.push(last_system);
+            leading_tokens = 0;
+        }
+
+        let conversation = (0..self.messages.len())
+            .filter(|&ix| {
+                let message = &self.messages[ix];
+                message.role != Role::System && !message.contents_empty()
+            })
+            .collect::<Vec<_>>();
+        for (position, &ix) in conversation.iter().enumerate() {
+            if ix >= cacheable_len {
+                break;
+            }
+            let next_role = conversation
+                .get(position + 1)
+                .map(|&next| self.messages[next].role);
+            let kind = if self.messages[ix].role == Role::User && next_role == Some(Role::Assistant)
+            {
+                CacheSegmentKind::TurnEnd
+            } else {
+                CacheSegmentKind::Content
+            };
+            segments.push(CacheSegment {
+                tokens: std::mem::take(&mut leading_tokens) + self.messages[ix].count_tokens(),
+                kind,
+            });
+            segment_messages.push(ix);
+        }
+
+        let breakpoints = plan_cache_breakpoints(
+            &segments,
+            configuration.max_cache_anchors,
+            configuration.min_total_token as usize,
+        );
+        for breakpoint in breakpoints {
+            self.messages[segment_messages[breakpoint]].cache = true;
+        }
+        self.messages.iter().filter(|message| message.cache).count()
+    }
+
+    /// Replaces files attached to user messages with a placeholder when the
+    /// same file, byte for byte, was already sent earlier in the request by
+    /// the user or a tool. Files are fenced code blocks tagged with their
+    /// path, the way Zed attaches them. Tool results and assistant messages
+    /// are never changed.
+    pub fn elide_repeated_files(&mut self) -> ElidedFiles {
+        let mut sent = HashMap::<String, String>::default();
+        let mut elided = ElidedFiles::default();
+        for message in &mut self.messages {
+            let role = message.role;
+            for content in &mut message.content {
+                match content {
+                    MessageContent::Text(text) if role == Role::User => {
+                        if let Some(new_text) = elide_sent_files(text, &mut sent, &mut elided) {
+                            *text = new_text;
+                        }
+                    }
+                    MessageContent::ToolResult(LanguageModelToolResult {
+                        content: LanguageModelToolResultContent::Text(output),
+                        ..
+                    }) => {
+                        for file in fenced_files(output) {
+                            sent.insert(file.tag.to_string(), output[file.body].to_string());
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        elided
+    }
+}
+
+/// Placeholder for a file whose content was already sent earlier in a request
+pub const UNCHANGED_FILE_PLACEHOLDER: &str = "(unchanged since it was last sent)";
+
+/// Files that [`LanguageModelRequest::elide_repeated_files`] replaced.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ElidedFiles {
+    pub count: usize,
+    pub estimated_tokens_saved: usize,
+}
+
+/// A fenced code block whose tag names a file.
+struct FencedFile<'a> {
+    tag: &'a str,
+    body: Range<usize>,
+}
+
+/// Rewrites `text` with the files in `sent` replaced by a placeholder, and
+/// records the other files as sent. Returns `None` if nothing was replaced.
+fn elide_sent_files(
+    text: &str,
+    sent: &mut HashMap<String, String>,
+    elided: &mut ElidedFiles,
+) -> Option<String> {
+    let mut result = String::new();
+    let mut copied = 0;
+    for file in fenced_files(text) {
+        let body = &text[file.body.clone()];
+        if sent
+            .get(file.tag)
+            .is_some_and(|sent_body| sent_body == body)
+        {
+            result.push_str(&text[copied..file.body.start]);
+            result.push_str(UNCHANGED_FILE_PLACEHOLDER);
+            result.push('\n');
+            copied = file.body.end;
+            elided.count += 1;
+            elided.estimated_tokens_saved +=
+                count_tokens(body).saturating_sub(count_tokens(UNCHANGED_FILE_PLACEHOLDER));
+        } else {
+            sent.insert(file.tag.to_string(), body.to_string());
+        }
+    }
+    if copied == 0 {
+        return None;
+    }
+    result.push_str(&text[copied..]);
+    Some(result)
+}
+
+/// The closed fenced code blocks in `text` that are tagged with a file path.
+fn fenced_files(text: &str) -> Vec<FencedFile<'_>> {
+    let mut files = Vec::new();
+    let mut fence: Option<(&str, usize)> = None;
+    let mut body_start = 0;
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        let line_start = offset;
+        offset += line.len();
+        let trimmed = line.trim();
+        match fence {
+            None => {
+                let ticks = trimmed.chars().take_while(|c| *c == '`').count();
+                if ticks >= 3 {
+                    fence = Some((trimmed[ticks..].trim(), ticks));
+                    body_start = offset;
+                }
+            }
+            Some((tag, ticks)) => {
+                if trimmed.len() >= ticks && trimmed.chars().all(|c| c == '`') {
+                    fence = None;
+                    if fenced_file_path(tag).is_some() {
+                        files.push(FencedFile {
+                            tag,
+                            body: body_start..line_start,
+                        });
+                    }
+                }
+            }
+        }
+    }
+    files
+}
+
+/// The path in a fence tag written the way Zed attaches files, such as
+/// `rs zed/src/main.rs` or `rs zed/src/main.rs:10-20`. The path must contain
+/// a directory, and the language must match its extension, so tags like
+/// `python3.11` or `text v1.2` aren't mistaken for files.
+pub fn fenced_file_path(tag: &str) -> Option<&str> {
+    let mut words = tag.split_whitespace();
+    let (language, path) = match (words.next()?, words.next(), words.next()) {
+        (path, None, _) => (None, path),
+        (language, Some(path), None) => (Some(language), path),
+        _ => return None,
+    };
+    let path = match path.rsplit_once(':') {
+        Some((path, range)) if range.chars().all(|c| c.is_ascii_digit() || c == '-') => path,
+        _ => path,
+    };
+    if !path.contains(['/', '\\']) {
+        return None;
+    }
+    let extension = Path::new(path)
+        .extension()
+        .and_then(|extension| extension.to_str());
+    match language {
+        Some(language) => (extension == Some(language)).then_some(path),
+        None => extension.is_none().then_some(path),
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
 pub struct LanguageModelResponseMessage {
     pub role: Option<Role>,
@@ -573,4 +825,141 @@ mod tests {
         let result: Result<LanguageModelToolResultContent, _> = serde_json::from_str(json);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_place_cache_anchors() {
+        let message = |role, text: String, cache| LanguageModelRequestMessage {
+            role,
+            content: vec![MessageContent::Text(text)],
+            cache,
+            reasoning_details: None,
+        };
+        let configuration = LanguageModelCacheConfiguration {
+            max_cache_anchors: 4,
+            should_speculate: false,
+            min_total_token: 1024,
+        };
+        let cached = |request: &LanguageModelRequest| {
+            request
+                .messages
+                .iter()
+                .map(|message| message.cache)
+                .collect::<Vec<_>>()
+        };
+
+        let long = |word: &str| format!("{word} ").repeat(1500);
+        let mut request = LanguageModelRequest {
+            messages: vec![
+                message(Role::System, long("system"), false),
+                message(Role::User, long("question"), false),
+                message(Role::Assistant, long("answer"), false),
+                message(Role::User, long("follow-up"), true),
+                message(Role::Assistant, "Pending".to_string(), false),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(request.place_cache_anchors(&configuration), 4);
+        assert_eq!(cached(&request), vec![true, true, true, true, false]);
+
+        // A short last message keeps its mark, even below the cacheable minimum.
+        let mut request = LanguageModelRequest {
+            messages: vec![
+                message(Role::System, "You are helpful".to_string(), false),
+                message(Role::User, "Hi".to_string(), true),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(request.place_cache_anchors(&configuration), 1);
+        assert_eq!(cached(&request), vec![false, true]);
+
+        // Four bytes per token would make this system prompt long enough to
+        // cache, but it's well under the minimum once tokenized.
+        let system = "configuration ".repeat(400);
+        assert!(count_tokens(&system) < 1024);
+        assert!(system.len().div_ceil(4) >= 1024);
+        let mut request = LanguageModelRequest {
+            messages: vec![
+                message(Role::System, system, false),
+                message(Role::User, long("question"), true),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(request.place_cache_anchors(&configuration), 1);
+        assert_eq!(cached(&request), vec![false, true]);
+    }
+
+    #[test]
+    fn test_elide_repeated_files() {
+        let text = |role, text: &str| LanguageModelRequestMessage {
+            role,
+            content: vec![MessageContent::Text(text.to_string())],
+            cache: false,
+            reasoning_details: None,
+        };
+        let tool_result = |output: &str| LanguageModelRequestMessage {
+            role: Role::User,
+            content: vec![MessageContent::ToolResult(LanguageModelToolResult {
+                tool_use_id: LanguageModelToolUseId::from("tool-1"),
+                tool_name: "read_file".into(),
+                is_error: false,
+                content: LanguageModelToolResultContent::Text(output.into()),
+                output: None,
+            })],
+            cache: false,
+            reasoning_details: None,
+        };
+        let file = "```rs zed/src/lib.rs\n\
+            pub fn parse(input: &str) -> Option<Tree> {\n    Parser::new(input).parse()\n}\n```\n";
+        let elided_file = format!("```rs zed/src/lib.rs\n{UNCHANGED_FILE_PLACEHOLDER}\n```\n");
+        let snippet = "```python3.11\nprint('hi')\n```\n";
+        let other_file = "```rs zed/src/main.rs\nfn main() {}\n```\n";
+
+        let messages = vec![
+            text(Role::Assistant, other_file),
+            text(Role::User, &format!("{file}{snippet}")),
+            tool_result(other_file),
+            text(Role::User, &format!("Again:\n{file}{snippet}{other_file}")),
+            tool_result(file),
+            text(
+                Role::User,
+                "```rs zed/src/lib.rs\npub fn parse() -> bool {}\n```\n",
+            ),
+        ];
+        let mut request = LanguageModelRequest {
+            messages: messages.clone(),
+            ..Default::default()
+        };
+        let elided = request.elide_repeated_files();
+        assert_eq!(elided.count, 2);
+        assert!(elided.estimated_tokens_saved > 0);
+
+        // Only files the user or a tool sent before, unchanged, are elided.
+        // Tool results, and snippets whose tag isn't a path, are kept.
+        let mut expected = messages;
+        expected[3] = text(
+            Role::User,
+            &format!(
+                "Again:\n{elided_file}{snippet}```rs zed/src/main.rs\n{UNCHANGED_FILE_PLACEHOLDER}\n```\n"
+            ),
+        );
+        assert_eq!(request.messages, expected);
+    }
+
+    #[test]
+    fn test_fenced_file_path() {
+        assert_eq!(
+            fenced_file_path("rs zed/src/lib.rs"),
+            Some("zed/src/lib.rs")
+        );
+        assert_eq!(
+            fenced_file_path("rs zed/src/lib.rs:10-20"),
+            Some("zed/src/lib.rs")
+        );
+        assert_eq!(fenced_file_path("zed/Makefile"), Some("zed/Makefile"));
+        assert_eq!(fenced_file_path("python3.11"), None);
+        assert_eq!(fenced_file_path("text v1.2"), None);
+        assert_eq!(fenced_file_path("rs lib.rs"), None);
+        assert_eq!(fenced_file_path("py zed/src/lib.rs"), None);
+        assert_eq!(fenced_file_path(""), None);
+    }
 }
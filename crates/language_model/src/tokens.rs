@@ -0,0 +1,20 @@
+use std::sync::OnceLock;
+use tiktoken_rs::CoreBPE;
+
+/// Count tokens with the `cl100k_base` BPE, the same approximation of
+/// Claude's tokenizer that the Anthropic provider uses for token counts.
+/// Falls back to roughly four bytes per token if the BPE fails to load.
+pub fn count_tokens(text: &str) -> usize {
+    static BPE: OnceLock<Option<CoreBPE>> = OnceLock::new();
+    let bpe = BPE.get_or_init(|| match tiktoken_rs::cl100k_base() {
+        Ok(bpe) => Some(bpe),
+        Err(error) => {
+            log::error!("Failed to load the cl100k tokenizer: {:#}", error);
+            None
+        }
+    });
+    match bpe {
+        Some(bpe) => bpe.encode_ordinary(text).len(),
+        None => text.len().div_ceil(4),
+    }
+}
@@ -443,6 +443,7 @@ pub fn into_anthropic(
 ) -> anthropic::Request {
     let mut new_messages: Vec<anthropic::Message> = Vec::new();
     let mut system_message = String::new();
+    let mut cache_breakpoints = Vec::new();
 
     for message in request.messages {
         if message.contents_empty() {
@@ -539,46 +540,38 @@ pub fn into_anthropic(
                     && last_message.role == anthropic_role
                 {
                     last_message.content.extend(anthropic_message_content);
-                    continue;
+                } else {
+                    new_messages.push(anthropic::Message {
+                        role: anthropic_role,
+                        content: anthropic_message_content,
+                    });
                 }
 
-                // Mark the last segment of the message as cached
-                if message.cache {
-                    let cache_control_value = Some(anthropic::CacheControl {
-                        cache_type: anthropic::CacheControlType::Ephemeral,
+                // Mark the last segment of the message as cached, even when it
+                // was merged into the previous message
+                if message.cache
+                    && let Some(last_message) = new_messages.last()
+                    && let Some(block) = last_message.content.len().checked_sub(1)
+                {
+                    cache_breakpoints.push(anthropic::CacheBreakpoint::Content {
+                        message: new_messages.len() - 1,
+                        block,
                     });
-                    for message_content in anthropic_message_content.iter_mut().rev() {
-                        match message_content {
-                            anthropic::RequestContent::RedactedThinking { .. } => {
-                                // Caching is not possible, fallback to next message
-                            }
-                            anthropic::RequestContent::Text { cache_control, .. }
-                            | anthropic::RequestContent::Thinking { cache_control, .. }
-                            | anthropic::RequestContent::Image { cache_control, .. }
-                            | anthropic::RequestContent::ToolUse { cache_control, .. }
-                            | anthropic::RequestContent::ToolResult { cache_control, .. } => {
-                                *cache_control = cache_control_value;
-                                break;
-                            }
-                        }
-                    }
                 }
-
-                new_messages.push(anthropic::Message {
-                    role: anthropic_role,
-                    content: anthropic_message_content,
-                });
             }
             Role::System => {
                 if !system_message.is_empty() {
                     system_message.push_str("\n\n");
                 }
                 system_message.push_str(&message.string_contents());
+                if message.cache {
+                    cache_breakpoints.push(anthropic::CacheBreakpoint::System);
+                }
             }
         }
     }
 
-    anthropic::Request {
+    let mut anthropic_request = anthropic::Request {
         model,
         messages: new_messages,
         max_tokens: max_output_tokens,
@@ -613,7 +606,9 @@ pub fn into_anthropic(
         temperature: request.temperature.or(Some(default_temperature)),
         top_k: None,
         top_p: None,
-    }
+    };
+    anthropic_request.set_cache_breakpoints(&cache_breakpoints);
+    anthropic_request
 }
 
 pub struct AnthropicEventMapper {
@@ -1042,4 +1037,66 @@ mod tests {
             }
         ));
     }
+
+    #[test]
+    fn test_cache_control_on_system_prompt_and_merged_messages() {
+        let message = |role, text: &str, cache| LanguageModelRequestMessage {
+            role,
+            content: vec![MessageContent::Text(text.to_string())],
+            cache,
+            reasoning_details: None,
+        };
+        let request = LanguageModelRequest {
+            messages: vec![
+                message(Role::System, "System prompt", true),
+                message(Role::User, "Tool results", true),
+                message(Role::User, "Follow-up", false),
+            ],
+            thread_id: None,
+            prompt_id: None,
+            intent: None,
+            mode: None,
+            stop: vec![],
+            temperature: None,
+            tools: vec![],
+            tool_choice: None,
+            thinking_allowed: true,
+        };
+
+        let anthropic_request = into_anthropic(
+            request,
+            "claude-3-5-sonnet".to_string(),
+            0.7,
+            4096,
+            AnthropicModelMode::Default,
+        );
+
+        assert!(matches!(
+            anthropic_request.system.as_ref().unwrap(),
+            anthropic::StringOrContents::Content(contents)
+                if matches!(
+                    contents.as_slice(),
+                    [anthropic::RequestContent::Text {
+                        cache_control: Some(_),
+                        ..
+                    }]
+                )
+        ));
+
+        assert_eq!(anthropic_request.messages.len(), 1);
+        let content = &anthropic_request.messages[0].content;
+        assert!(matches!(
+            content.as_slice(),
+            [
+                anthropic::RequestContent::Text {
+                    cache_control: Some(_),
+                    ..
+                },
+                anthropic::RequestContent::Text {
+                    cache_control: None,
+                    ..
+                }
+            ]
+        ));
+    }
 }
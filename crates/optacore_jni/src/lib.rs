@@ -33,8 +33,8 @@ use jni::objects::{JClass, JString};
 use jni::sys::jstring;
 use jni::JNIEnv;
 use optacore_struct::{
-    detect_anti_patterns, parse_c4_dsl, to_dot, AntiPatternConfig, NodeType, OptaModel,
-    OptaOptimizer,
+    detect_anti_patterns, parse_c4_dsl, to_dot, AntiPattern, AntiPatternConfig, ArchitectureRule,
+    NodeType, OptaModel, OptaOptimizer,
 };
 use serde_json::json;
 use std::panic::{self, AssertUnwindSafe};
@@ -71,9 +71,77 @@ fn validate_config(config: &AntiPatternConfig) -> anyhow::Result<()> {
             config.over_coupling_threshold
         );
     }
+    if !(config.god_component_threshold > 0.0 && config.god_component_threshold <= 1.0) {
+        anyhow::bail!(
+            "Invalid god_component_threshold: {} (must be in (0, 1])",
+            config.god_component_threshold
+        );
+    }
+    if config.shared_database_threshold < 2 {
+        anyhow::bail!(
+            "Invalid shared_database_threshold: {} (must be >= 2)",
+            config.shared_database_threshold
+        );
+    }
+    if config.chatty_weight_threshold <= 0.0 {
+        anyhow::bail!(
+            "Invalid chatty_weight_threshold: {} (must be > 0)",
+            config.chatty_weight_threshold
+        );
+    }
     Ok(())
 }
 
+/// Read AntiPatternConfig from JSON; missing keys keep their defaults
+///
+/// `rules` takes the same architecture rules as an optacore rules file.
+fn json_to_config(json: &serde_json::Value) -> anyhow::Result<AntiPatternConfig> {
+    let defaults = AntiPatternConfig::default();
+    let usize_field = |key: &str, default: usize| {
+        json.get(key)
+            .and_then(|v| v.as_u64())
+            .map_or(default, |v| v as usize)
+    };
+    let f32_field = |key: &str, default: f32| {
+        json.get(key)
+            .and_then(|v| v.as_f64())
+            .map_or(default, |v| v as f32)
+    };
+
+    let rules: Vec<ArchitectureRule> = match json.get("rules") {
+        Some(rules) => serde_json::from_value(rules.clone())
+            .map_err(|e| anyhow::anyhow!("Invalid rules in config: {}", e))?,
+        None => Vec::new(),
+    };
+
+    Ok(AntiPatternConfig {
+        bottleneck_threshold: usize_field("bottleneck_threshold", 5),
+        over_coupling_threshold: usize_field("over_coupling_threshold", 8),
+        detect_isolated: true,
+        detect_layering_violations: json
+            .get("detect_layering_violations")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(defaults.detect_layering_violations),
+        god_component_threshold: f32_field(
+            "god_component_threshold",
+            defaults.god_component_threshold,
+        ),
+        god_component_min_degree: usize_field(
+            "god_component_min_degree",
+            defaults.god_component_min_degree,
+        ),
+        shared_database_threshold: usize_field(
+            "shared_database_threshold",
+            defaults.shared_database_threshold,
+        ),
+        chatty_weight_threshold: f32_field(
+            "chatty_weight_threshold",
+            defaults.chatty_weight_threshold,
+        ),
+        rules,
+    })
+}
+
 /// Helper: Convert Rust Result to JString or throw Java exception
 fn result_to_jstring(env: &mut JNIEnv, result: anyhow::Result<String>) -> jstring {
     match result {
@@ -176,13 +244,17 @@ pub extern "system" fn Java_com_optafly_structurizr_OptaCoreJNI_parseDsl(
                     },
                     "position": n.get_position(),
                     "technology": n.technology,
+                    "tags": n.tags,
                     "description": n.description,
+                    "parent": n.parent,
                 })
             }).collect::<Vec<_>>(),
             "edges": model.edges.iter().map(|e| json!({
                 "from": e.from,
                 "to": e.to,
                 "label": e.label,
+                "technology": e.technology,
+                "tags": e.tags,
                 "weight": e.weight,
             })).collect::<Vec<_>>(),
             "node_count": model.node_count(),
@@ -229,12 +301,27 @@ pub extern "system" fn Java_com_optafly_structurizr_OptaCoreJNI_optimizeLayout(
 
         model.build_adjacency_matrix();
 
-        let start = std::time::Instant::now();
-        let optimizer = OptaOptimizer::<Backend>::new(150, 0.2).with_area(2000.0);
-        optimizer.optimize_layout(&mut model)?;
-        let duration_ms = start.elapsed().as_millis() as u64;
+        // Incremental re-layouts from the IDE send back the previous positions
+        // (and optionally a seed) so the diagram doesn't jump around
+        let mut optimizer = OptaOptimizer::<Backend>::new(150, 0.2)
+            .with_area(2000.0)
+            .with_warm_start(
+                json_value
+                    .get("warm_start")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false),
+            );
+        if let Some(seed) = json_value.get("seed").and_then(|v| v.as_u64()) {
+            optimizer = optimizer.with_seed(seed);
+        }
+        let stats = optimizer.optimize_layout(&mut model)?;
 
-        debug_log!("Optimization complete: {}ms", duration_ms);
+        debug_log!(
+            "Optimization complete: {}ms, {} iterations ({:?})",
+            stats.duration_ms,
+            stats.iterations,
+            stats.convergence
+        );
 
         let json_output = json!({
             "nodes": model.nodes.iter().map(|n| json!({
@@ -249,18 +336,20 @@ pub extern "system" fn Java_com_optafly_structurizr_OptaCoreJNI_optimizeLayout(
                 },
                 "position": n.get_position(),
                 "technology": n.technology,
+                "tags": n.tags,
                 "description": n.description,
+                "parent": n.parent,
+                "pinned": n.pinned,
             })).collect::<Vec<_>>(),
             "edges": model.edges.iter().map(|e| json!({
                 "from": e.from,
                 "to": e.to,
                 "label": e.label,
+                "technology": e.technology,
+                "tags": e.tags,
                 "weight": e.weight,
             })).collect::<Vec<_>>(),
-            "optimization_stats": {
-                "iterations": 150,
-                "duration_ms": duration_ms,
-            },
+            "optimization_stats": stats,
         });
 
         Ok(json_output.to_string())
@@ -307,20 +396,7 @@ pub extern "system" fn Java_com_optafly_structurizr_OptaCoreJNI_detectAntiPatter
             let json: serde_json::Value = serde_json::from_str(&config_str)
                 .map_err(|e| anyhow::anyhow!("Config JSON parse error: {}", e))?;
 
-            let bottleneck = json
-                .get("bottleneck_threshold")
-                .and_then(|v| v.as_u64())
-                .unwrap_or(5) as usize;
-            let over_coupling = json
-                .get("over_coupling_threshold")
-                .and_then(|v| v.as_u64())
-                .unwrap_or(8) as usize;
-
-            let cfg = AntiPatternConfig {
-                bottleneck_threshold: bottleneck,
-                over_coupling_threshold: over_coupling,
-                detect_isolated: true,
-            };
+            let cfg = json_to_config(&json)?;
             validate_config(&cfg)?;
             cfg
         };
@@ -337,34 +413,59 @@ pub extern "system" fn Java_com_optafly_structurizr_OptaCoreJNI_detectAntiPatter
 
         let json_patterns = json!({
             "patterns": patterns.iter().map(|p| {
-                match p {
-                    optacore_struct::AntiPattern::Cycle { nodes } => json!({
+                let mut pattern = match p {
+                    AntiPattern::Cycle { nodes } => json!({
                         "type": "Cycle",
                         "nodes": nodes,
-                        "severity": 1.0,
-                        "description": format!("Circular dependency detected: {} nodes", nodes.len()),
                     }),
-                    optacore_struct::AntiPattern::Bottleneck { node_id, in_degree, severity } => json!({
+                    AntiPattern::Bottleneck { node_id, in_degree, .. } => json!({
                         "type": "Bottleneck",
                         "node_id": node_id,
                         "in_degree": in_degree,
-                        "severity": severity,
-                        "description": format!("High fan-in: {} incoming edges", in_degree),
                     }),
-                    optacore_struct::AntiPattern::IsolatedComponent { node_id } => json!({
+                    AntiPattern::IsolatedComponent { node_id } => json!({
                         "type": "IsolatedComponent",
                         "node_id": node_id,
-                        "severity": 0.3,
-                        "description": "No connections to other components",
                     }),
-                    optacore_struct::AntiPattern::OverCoupling { node_id, out_degree, severity } => json!({
+                    AntiPattern::OverCoupling { node_id, out_degree, .. } => json!({
                         "type": "OverCoupling",
                         "node_id": node_id,
                         "out_degree": out_degree,
-                        "severity": severity,
-                        "description": format!("High fan-out: {} outgoing edges", out_degree),
                     }),
-                }
+                    AntiPattern::LayeringViolation { from, to, bypassed_container, .. } => json!({
+                        "type": "LayeringViolation",
+                        "from": from,
+                        "to": to,
+                        "bypassed_container": bypassed_container,
+                    }),
+                    AntiPattern::GodComponent { node_id, betweenness, .. } => json!({
+                        "type": "GodComponent",
+                        "node_id": node_id,
+                        "betweenness": betweenness,
+                    }),
+                    AntiPattern::SharedDatabase { node_id, accessors, .. } => json!({
+                        "type": "SharedDatabase",
+                        "node_id": node_id,
+                        "accessors": accessors,
+                    }),
+                    AntiPattern::ChattyInterface { from, to, weight, .. } => json!({
+                        "type": "ChattyInterface",
+                        "from": from,
+                        "to": to,
+                        "weight": weight,
+                    }),
+                    AntiPattern::RuleViolation { rule, nodes, message, .. } => json!({
+                        "type": "RuleViolation",
+                        "rule": rule,
+                        "nodes": nodes,
+                        "message": message,
+                    }),
+                };
+                pattern["severity"] = json!(p.severity());
+                pattern["affected_nodes"] = json!(p.affected_nodes());
+                pattern["description"] = json!(p.description());
+                pattern["remediation"] = json!(p.remediation());
+                pattern
             }).collect::<Vec<_>>(),
             "count": patterns.len(),
         });
@@ -413,20 +514,7 @@ pub extern "system" fn Java_com_optafly_structurizr_OptaCoreJNI_generateDot(
             let json: serde_json::Value = serde_json::from_str(&config_str)
                 .map_err(|e| anyhow::anyhow!("Config JSON parse error: {}", e))?;
 
-            let bottleneck = json
-                .get("bottleneck_threshold")
-                .and_then(|v| v.as_u64())
-                .unwrap_or(5) as usize;
-            let over_coupling = json
-                .get("over_coupling_threshold")
-                .and_then(|v| v.as_u64())
-                .unwrap_or(8) as usize;
-
-            let cfg = AntiPatternConfig {
-                bottleneck_threshold: bottleneck,
-                over_coupling_threshold: over_coupling,
-                detect_isolated: true,
-            };
+            let cfg = json_to_config(&json)?;
             validate_config(&cfg)?;
             Some(cfg)
         };
@@ -507,9 +595,20 @@ fn json_to_model(json: &serde_json::Value) -> anyhow::Result<OptaModel<Backend>>
             .and_then(|v| v.as_str())
             .map(|s| s.to_string());
 
+        let parent = node_val
+            .get("parent")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string());
+
         let mut node = OptaNode::new(id, name, node_type);
         node.technology = technology;
+        node.tags = json_tags(node_val);
         node.description = description;
+        node.parent = parent;
+        node.pinned = node_val
+            .get("pinned")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
 
         if let Some(pos) = node_val.get("position").and_then(|v| v.as_array()) {
             if pos.len() == 2 {
@@ -540,22 +639,46 @@ fn json_to_model(json: &serde_json::Value) -> anyhow::Result<OptaModel<Backend>>
             .and_then(|v| v.as_str())
             .map(|s| s.to_string());
 
+        let technology = edge_val
+            .get("technology")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string());
+
         let weight = edge_val
             .get("weight")
             .and_then(|v| v.as_f64())
             .unwrap_or(1.0) as f32;
 
-        model.add_edge(OptaEdge {
-            from,
-            to,
-            label,
-            weight,
-        });
+        let mut edge = OptaEdge::new(from, to).with_weight(weight);
+        edge.label = label;
+        edge.technology = technology;
+        edge.tags = json_tags(edge_val);
+        model.add_edge(edge);
     }
 
     Ok(model)
 }
 
+/// Helper: Read `tags` as a list, or as a comma-separated string like Structurizr writes them
+fn json_tags(json: &serde_json::Value) -> Vec<String> {
+    match json.get("tags") {
+        Some(serde_json::Value::Array(tags)) => tags
+            .iter()
+            .filter_map(|tag| tag.as_str())
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+            .map(str::to_string)
+            .collect(),
+        Some(serde_json::Value::String(tags)) => tags
+            .split(',')
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+            .map(str::to_string)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -570,16 +693,92 @@ mod tests {
         let valid = AntiPatternConfig {
             bottleneck_threshold: 5,
             over_coupling_threshold: 8,
-            detect_isolated: true,
+            ..Default::default()
         };
         assert!(validate_config(&valid).is_ok());
 
         let invalid_bottleneck = AntiPatternConfig {
             bottleneck_threshold: 0,
             over_coupling_threshold: 8,
-            detect_isolated: true,
+            ..Default::default()
         };
         assert!(validate_config(&invalid_bottleneck).is_err());
+
+        let invalid_chatty = AntiPatternConfig {
+            chatty_weight_threshold: 0.0,
+            ..Default::default()
+        };
+        assert!(validate_config(&invalid_chatty).is_err());
+    }
+
+    #[test]
+    fn test_config_from_json() {
+        let config = json_to_config(&json!({
+            "bottleneck_threshold": 3,
+            "god_component_threshold": 0.5,
+            "detect_layering_violations": false,
+            "rules": [{
+                "name": "containers declare a technology",
+                "constraint": "required",
+                "select": { "type": "Container" },
+                "require": "technology",
+            }],
+        }))
+        .unwrap();
+        assert_eq!(config.bottleneck_threshold, 3);
+        assert_eq!(config.over_coupling_threshold, 8);
+        assert_eq!(config.god_component_threshold, 0.5);
+        assert!(!config.detect_layering_violations);
+        assert_eq!(config.shared_database_threshold, 2);
+        assert_eq!(config.rules.len(), 1);
+
+        assert!(json_to_config(&json!({ "rules": [{ "name": "broken" }] })).is_err());
+    }
+
+    #[test]
+    fn test_model_tags_from_json() {
+        let model = json_to_model(&json!({
+            "nodes": [
+                { "id": "ui", "name": "UI", "type": "Component", "tags": ["web"] },
+                { "id": "orders", "name": "Orders", "type": "Container" },
+                { "id": "billing", "name": "Billing", "type": "Container" },
+                { "id": "store", "name": "Store", "type": "Container", "tags": "Database, db" },
+            ],
+            "edges": [
+                { "from": "ui", "to": "store", "technology": "JDBC", "tags": ["Sync"] },
+                { "from": "orders", "to": "store" },
+                { "from": "billing", "to": "store" },
+            ],
+        }))
+        .unwrap();
+        assert_eq!(
+            model.find_node("store").unwrap().tags,
+            vec!["Database", "db"]
+        );
+        assert_eq!(model.edges[0].technology.as_deref(), Some("JDBC"));
+        assert_eq!(model.edges[0].tags, vec!["Sync"]);
+
+        let config = json_to_config(&json!({
+            "rules": [{
+                "name": "web must not reach into db",
+                "constraint": "forbidden_dependency",
+                "from": { "type": "Component", "tag": "web" },
+                "to": { "tag": "db" },
+            }],
+        }))
+        .unwrap();
+        let patterns = detect_anti_patterns(&model, &config).unwrap();
+
+        assert!(patterns.iter().any(|p| matches!(
+            p,
+            AntiPattern::RuleViolation { rule, nodes, .. }
+                if rule == "web must not reach into db" && nodes == &["ui", "store"]
+        )));
+        // Tagged "Database" without a database technology
+        assert!(patterns.iter().any(|p| matches!(
+            p,
+            AntiPattern::SharedDatabase { node_id, .. } if node_id == "store"
+        )));
     }
 
     #[test]
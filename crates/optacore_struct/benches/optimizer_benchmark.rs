@@ -0,0 +1,110 @@
+use burn::backend::ndarray::NdArray;
+use burn::tensor::{Tensor, backend::Backend};
+use criterion::{BatchSize, BenchmarkId, Criterion, black_box, criterion_group, criterion_main};
+use optacore_struct::model::OptaEdge;
+use optacore_struct::{NodeType, OptaModel, OptaNode, OptaOptimizer};
+use rand::prelude::*;
+use rand::rngs::StdRng;
+
+type BenchBackend = NdArray<f32>;
+
+const ITERATIONS: usize = 50;
+const LEARNING_RATE: f32 = 0.1;
+const AREA: f32 = 1000.0;
+const K: f32 = 50.0;
+
+fn generate_model(node_count: usize) -> OptaModel<BenchBackend> {
+    let mut rng = StdRng::seed_from_u64(node_count as u64);
+    let mut model = OptaModel::new();
+    for i in 0..node_count {
+        model.add_node(OptaNode::new(
+            format!("n{i}"),
+            format!("Node {i}"),
+            NodeType::Container,
+        ));
+    }
+    for i in 1..node_count {
+        let target = rng.random_range(0..i);
+        model.add_edge(OptaEdge::new(format!("n{i}"), format!("n{target}")));
+    }
+    model.build_adjacency_matrix();
+    model
+}
+
+/// The original layout loop: positions and adjacency are copied to the host
+/// every iteration and forces are accumulated pair by pair
+fn reference_layout<B: Backend>(model: &OptaModel<B>) -> Vec<f32> {
+    let adjacency = model.adjacency_matrix.as_ref().unwrap();
+    let device = adjacency.device();
+    let n = model.node_count();
+
+    let mut rng = rand::rng();
+    let initial: Vec<f32> = (0..n * 2)
+        .map(|_| rng.random_range(0.0..AREA.sqrt()))
+        .collect();
+    let mut positions = Tensor::<B, 1>::from_floats(initial.as_slice(), &device).reshape([n, 2]);
+
+    for iteration in 0..ITERATIONS {
+        let temperature = LEARNING_RATE * (1.0 - iteration as f32 / ITERATIONS as f32);
+
+        let positions_vec: Vec<f32> = positions.to_data().to_vec().unwrap();
+        let adjacency_vec: Vec<f32> = adjacency.to_data().to_vec().unwrap();
+        let mut forces = vec![0.0f32; n * 2];
+        for i in 0..n {
+            for j in 0..n {
+                if i == j {
+                    continue;
+                }
+                let dx = positions_vec[i * 2] - positions_vec[j * 2];
+                let dy = positions_vec[i * 2 + 1] - positions_vec[j * 2 + 1];
+                let distance_sq = dx * dx + dy * dy + 0.01;
+                let distance = distance_sq.sqrt();
+
+                let mut force = (K * K) / distance_sq;
+                let weight = adjacency_vec[i * n + j];
+                if weight > 0.0 {
+                    force -= distance_sq / K * weight;
+                }
+                forces[i * 2] += dx / distance * force;
+                forces[i * 2 + 1] += dy / distance * force;
+            }
+        }
+
+        let displacement = Tensor::<B, 1>::from_floats(forces.as_slice(), &device).reshape([n, 2]);
+        positions = (positions + displacement * temperature).clamp(0.0, AREA.sqrt());
+    }
+
+    positions.to_data().to_vec().unwrap()
+}
+
+fn layout_benchmarks(c: &mut Criterion) {
+    let mut group = c.benchmark_group("force_layout");
+    group.sample_size(10);
+
+    for node_count in [50, 200, 500] {
+        let model = generate_model(node_count);
+
+        group.bench_with_input(
+            BenchmarkId::new("per_pair_loop", node_count),
+            &model,
+            |b, model| b.iter(|| black_box(reference_layout(model))),
+        );
+
+        // Run every iteration, like the reference loop, on a freshly generated
+        // model so no run starts from an already settled layout
+        let optimizer =
+            OptaOptimizer::<BenchBackend>::new(ITERATIONS, LEARNING_RATE).with_tolerance(0.0);
+        group.bench_function(BenchmarkId::new("tensor", node_count), |b| {
+            b.iter_batched(
+                || generate_model(node_count),
+                |mut model| optimizer.optimize_layout(black_box(&mut model)).unwrap(),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, layout_benchmarks);
+criterion_main!(benches);
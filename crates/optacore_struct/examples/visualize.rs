@@ -1,7 +1,7 @@
 use burn::backend::ndarray::NdArray;
 use optacore_struct::{
     AntiPatternConfig, NodeType, OptaModel, OptaNode, OptaOptimizer, model::OptaEdge, parse_c4_dsl,
-    to_dot, to_dot_with_positions,
+    to_dot,
 };
 use std::fs;
 
@@ -58,7 +58,7 @@ AuthService -> CacheService "sessions"
     let config = AntiPatternConfig {
         bottleneck_threshold: 3,
         over_coupling_threshold: 5,
-        detect_isolated: true,
+        ..Default::default()
     };
 
     let dot = to_dot(&model, Some(&config))?;
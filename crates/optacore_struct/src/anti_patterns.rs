@@ -1,11 +1,13 @@
-use crate::{OptaModel, Result};
+use crate::rules::{ArchitectureRule, check_rules};
+use crate::{NodeType, OptaModel, OptaNode, Result};
 use burn::tensor::backend::Backend;
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum AntiPattern {
-    /// Circular dependency detected in architecture
+    /// Strongly connected set of elements that all depend on each other,
+    /// reported once however many individual cycles run through it
     Cycle { nodes: Vec<String> },
     /// Single point of failure with many incoming dependencies
     Bottleneck {
@@ -21,6 +23,181 @@ pub enum AntiPattern {
         out_degree: usize,
         severity: f32,
     },
+    /// Dependency on a component from outside its container, bypassing the
+    /// container's public interface
+    LayeringViolation {
+        from: String,
+        to: String,
+        bypassed_container: String,
+        severity: f32,
+    },
+    /// Element that most paths through the architecture depend on, measured by
+    /// normalized betweenness centrality
+    GodComponent {
+        node_id: String,
+        betweenness: f32,
+        severity: f32,
+    },
+    /// Database accessed directly by several containers
+    SharedDatabase {
+        node_id: String,
+        accessors: Vec<String>,
+        severity: f32,
+    },
+    /// Relationship carrying unusually heavy traffic, summed over parallel edges
+    ChattyInterface {
+        from: String,
+        to: String,
+        weight: f32,
+        severity: f32,
+    },
+    /// Violation of a user-defined [`ArchitectureRule`]
+    RuleViolation {
+        rule: String,
+        nodes: Vec<String>,
+        message: String,
+        remediation: String,
+        severity: f32,
+    },
+}
+
+impl AntiPattern {
+    /// Stable snake_case identifier of the pattern kind
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AntiPattern::Cycle { .. } => "cycle",
+            AntiPattern::Bottleneck { .. } => "bottleneck",
+            AntiPattern::IsolatedComponent { .. } => "isolated",
+            AntiPattern::OverCoupling { .. } => "over_coupling",
+            AntiPattern::LayeringViolation { .. } => "layering_violation",
+            AntiPattern::GodComponent { .. } => "god_component",
+            AntiPattern::SharedDatabase { .. } => "shared_database",
+            AntiPattern::ChattyInterface { .. } => "chatty_interface",
+            AntiPattern::RuleViolation { .. } => "rule_violation",
+        }
+    }
+
+    /// How serious the finding is
+    ///
+    /// Bottlenecks, over-coupling, god components, shared databases and
+    /// chatty interfaces are measured against their configured threshold:
+    /// 1.0 means it was just reached, larger values exceed it. The other
+    /// kinds have fixed severities: 1.0 for cycles, 0.8 for a layering
+    /// violation from a person and 0.6 from anything else, 0.3 for isolated
+    /// components, and the rule's own severity for rule violations.
+    pub fn severity(&self) -> f32 {
+        match self {
+            AntiPattern::Cycle { .. } => 1.0,
+            AntiPattern::IsolatedComponent { .. } => 0.3,
+            AntiPattern::Bottleneck { severity, .. }
+            | AntiPattern::OverCoupling { severity, .. }
+            | AntiPattern::LayeringViolation { severity, .. }
+            | AntiPattern::GodComponent { severity, .. }
+            | AntiPattern::SharedDatabase { severity, .. }
+            | AntiPattern::ChattyInterface { severity, .. }
+            | AntiPattern::RuleViolation { severity, .. } => *severity,
+        }
+    }
+
+    /// Elements involved in the finding, most relevant first
+    pub fn affected_nodes(&self) -> Vec<String> {
+        match self {
+            AntiPattern::Cycle { nodes } | AntiPattern::RuleViolation { nodes, .. } => nodes.clone(),
+            AntiPattern::Bottleneck { node_id, .. }
+            | AntiPattern::IsolatedComponent { node_id }
+            | AntiPattern::OverCoupling { node_id, .. }
+            | AntiPattern::GodComponent { node_id, .. } => vec![node_id.clone()],
+            AntiPattern::LayeringViolation { from, to, .. }
+            | AntiPattern::ChattyInterface { from, to, .. } => vec![from.clone(), to.clone()],
+            AntiPattern::SharedDatabase {
+                node_id, accessors, ..
+            } => std::iter::once(node_id.clone())
+                .chain(accessors.iter().cloned())
+                .collect(),
+        }
+    }
+
+    /// One-line summary of what was found
+    pub fn description(&self) -> String {
+        match self {
+            AntiPattern::Cycle { nodes } => {
+                format!("Circular dependency detected: {} nodes", nodes.len())
+            }
+            AntiPattern::Bottleneck { in_degree, .. } => {
+                format!("High fan-in: {} incoming edges", in_degree)
+            }
+            AntiPattern::IsolatedComponent { .. } => {
+                "No connections to other components".to_string()
+            }
+            AntiPattern::OverCoupling { out_degree, .. } => {
+                format!("High fan-out: {} outgoing edges", out_degree)
+            }
+            AntiPattern::LayeringViolation {
+                from,
+                to,
+                bypassed_container,
+                ..
+            } => format!(
+                "{} depends on {} inside {}, bypassing the container",
+                from, to, bypassed_container
+            ),
+            AntiPattern::GodComponent { betweenness, .. } => format!(
+                "On {:.0}% of shortest dependency paths",
+                betweenness * 100.0
+            ),
+            AntiPattern::SharedDatabase { accessors, .. } => {
+                format!("Database shared by {} containers", accessors.len())
+            }
+            AntiPattern::ChattyInterface { weight, .. } => {
+                format!("Chatty interface: total weight {:.1}", weight)
+            }
+            AntiPattern::RuleViolation { rule, message, .. } => {
+                format!("Rule '{}' violated: {}", rule, message)
+            }
+        }
+    }
+
+    /// Suggested fix for the finding
+    pub fn remediation(&self) -> String {
+        match self {
+            AntiPattern::Cycle { .. } => "Break the cycle by inverting one dependency \
+                (depend on an interface or publish events) or by merging the tightly coupled elements"
+                .to_string(),
+            AntiPattern::Bottleneck { node_id, .. } => format!(
+                "Reduce load on {}: replicate it, add a cache in front of it, or split its responsibilities",
+                node_id
+            ),
+            AntiPattern::IsolatedComponent { node_id } => format!(
+                "Connect {} to the elements that use it, or remove it if it is no longer needed",
+                node_id
+            ),
+            AntiPattern::OverCoupling { node_id, .. } => format!(
+                "Split {} into smaller elements or put a facade in front of its dependencies",
+                node_id
+            ),
+            AntiPattern::LayeringViolation {
+                to,
+                bypassed_container,
+                ..
+            } => format!(
+                "Depend on {} through its public interface instead of on its internal component {}",
+                bypassed_container, to
+            ),
+            AntiPattern::GodComponent { node_id, .. } => format!(
+                "Decompose {} along its responsibilities so fewer flows have to pass through it",
+                node_id
+            ),
+            AntiPattern::SharedDatabase { node_id, .. } => format!(
+                "Give each container its own data store, or put a single service in front of {} that owns its schema",
+                node_id
+            ),
+            AntiPattern::ChattyInterface { from, to, .. } => format!(
+                "Batch or coarsen the calls from {} to {}, or cache the data on the calling side",
+                from, to
+            ),
+            AntiPattern::RuleViolation { remediation, .. } => remediation.clone(),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -28,6 +205,17 @@ pub struct AntiPatternConfig {
     pub bottleneck_threshold: usize,
     pub over_coupling_threshold: usize,
     pub detect_isolated: bool,
+    pub detect_layering_violations: bool,
+    /// Normalized betweenness centrality (0-1) at which an element is a god component
+    pub god_component_threshold: f32,
+    /// Elements with fewer incoming plus outgoing edges are never god components
+    pub god_component_min_degree: usize,
+    /// Number of distinct containers that must access a database for it to count as shared
+    pub shared_database_threshold: usize,
+    /// Total weight of the edges between two elements at which the interface is chatty
+    pub chatty_weight_threshold: f32,
+    /// Architecture rules checked alongside the built-in detectors
+    pub rules: Vec<ArchitectureRule>,
 }
 
 impl Default for AntiPatternConfig {
@@ -36,10 +224,29 @@ impl Default for AntiPatternConfig {
             bottleneck_threshold: 5,
             over_coupling_threshold: 7,
             detect_isolated: true,
+            detect_layering_violations: true,
+            god_component_threshold: 0.3,
+            god_component_min_degree: 4,
+            shared_database_threshold: 2,
+            chatty_weight_threshold: 10.0,
+            rules: Vec::new(),
         }
     }
 }
 
+/// Technologies that mark an element as a database when it lacks a `Database` tag
+const DATABASE_TECHNOLOGIES: &[&str] = &[
+    "sql",
+    "postgres",
+    "mysql",
+    "mariadb",
+    "oracle",
+    "mongo",
+    "cassandra",
+    "dynamodb",
+    "database",
+];
+
 /// Detect architectural anti-patterns in the model with custom configuration
 pub fn detect_anti_patterns<B: Backend>(
     model: &OptaModel<B>,
@@ -62,6 +269,19 @@ pub fn detect_anti_patterns<B: Backend>(
     if config.detect_isolated {
         patterns.extend(detect_isolated_components(model, &adjacency));
     }
+    if config.detect_layering_violations {
+        patterns.extend(detect_layering_violations(model));
+    }
+    patterns.extend(detect_god_components(model, &adjacency, config));
+    patterns.extend(detect_shared_databases(
+        model,
+        config.shared_database_threshold,
+    ));
+    patterns.extend(detect_chatty_interfaces(
+        model,
+        config.chatty_weight_threshold,
+    ));
+    patterns.extend(check_rules(model, &config.rules));
 
     Ok(patterns)
 }
@@ -83,86 +303,94 @@ fn build_adjacency_map<B: Backend>(model: &OptaModel<B>) -> HashMap<String, Vec<
     adjacency
 }
 
+/// Strongly connected components with more than one element (or a
+/// self-dependency), found with Tarjan's algorithm
 fn detect_cycles<B: Backend>(
     model: &OptaModel<B>,
     adjacency: &HashMap<String, Vec<String>>,
 ) -> Result<Vec<AntiPattern>> {
-    let mut patterns = Vec::new();
-    let mut color: HashMap<String, Color> = HashMap::new();
-    let mut parent: HashMap<String, Option<String>> = HashMap::new();
-
+    let mut state = Tarjan::default();
     for node in &model.nodes {
-        color.insert(node.id.clone(), Color::White);
-        parent.insert(node.id.clone(), None);
+        if !state.index.contains_key(node.id.as_str()) {
+            state.visit(&node.id, adjacency);
+        }
     }
 
-    for node in &model.nodes {
-        if color[&node.id] == Color::White {
-            if let Some(cycle) = dfs_visit(&node.id, adjacency, &mut color, &mut parent) {
-                patterns.push(AntiPattern::Cycle { nodes: cycle });
+    let order: HashMap<&str, usize> = model
+        .nodes
+        .iter()
+        .enumerate()
+        .map(|(idx, node)| (node.id.as_str(), idx))
+        .collect();
+    let mut patterns: Vec<AntiPattern> = state
+        .components
+        .into_iter()
+        .filter(|component| {
+            component.len() > 1
+                || adjacency
+                    .get(component[0])
+                    .is_some_and(|neighbors| neighbors.iter().any(|n| n == component[0]))
+        })
+        .map(|mut component| {
+            component.sort_by_key(|id| order.get(id).copied().unwrap_or(usize::MAX));
+            AntiPattern::Cycle {
+                nodes: component.into_iter().map(str::to_string).collect(),
             }
-        }
-    }
+        })
+        .collect();
+    patterns.sort_by_key(|pattern| match pattern {
+        AntiPattern::Cycle { nodes } => order.get(nodes[0].as_str()).copied(),
+        _ => None,
+    });
 
     Ok(patterns)
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
-enum Color {
-    White,
-    Gray,
-    Black,
+#[derive(Default)]
+struct Tarjan<'a> {
+    index: HashMap<&'a str, usize>,
+    low_link: HashMap<&'a str, usize>,
+    stack: Vec<&'a str>,
+    on_stack: HashSet<&'a str>,
+    components: Vec<Vec<&'a str>>,
 }
 
-fn dfs_visit(
-    node_id: &str,
-    adjacency: &HashMap<String, Vec<String>>,
-    color: &mut HashMap<String, Color>,
-    parent: &mut HashMap<String, Option<String>>,
-) -> Option<Vec<String>> {
-    color.insert(node_id.to_string(), Color::Gray);
+impl<'a> Tarjan<'a> {
+    fn visit(&mut self, node_id: &'a str, adjacency: &'a HashMap<String, Vec<String>>) {
+        let index = self.index.len();
+        self.index.insert(node_id, index);
+        self.low_link.insert(node_id, index);
+        self.stack.push(node_id);
+        self.on_stack.insert(node_id);
 
-    if let Some(neighbors) = adjacency.get(node_id) {
-        for neighbor in neighbors {
-            match color.get(neighbor) {
-                Some(Color::White) => {
-                    parent.insert(neighbor.clone(), Some(node_id.to_string()));
-                    if let Some(cycle) = dfs_visit(neighbor, adjacency, color, parent) {
-                        return Some(cycle);
-                    }
-                }
-                Some(Color::Gray) => {
-                    let cycle = reconstruct_cycle(neighbor, node_id, parent);
-                    return Some(cycle);
+        for neighbor in adjacency.get(node_id).into_iter().flatten() {
+            let neighbor = neighbor.as_str();
+            if !self.index.contains_key(neighbor) {
+                // Edges to unknown elements never close a cycle
+                if !adjacency.contains_key(neighbor) {
+                    continue;
                 }
-                _ => {}
+                self.visit(neighbor, adjacency);
+                let low = self.low_link[node_id].min(self.low_link[neighbor]);
+                self.low_link.insert(node_id, low);
+            } else if self.on_stack.contains(neighbor) {
+                let low = self.low_link[node_id].min(self.index[neighbor]);
+                self.low_link.insert(node_id, low);
             }
         }
-    }
-
-    color.insert(node_id.to_string(), Color::Black);
-    None
-}
 
-fn reconstruct_cycle(
-    cycle_start: &str,
-    cycle_end: &str,
-    parent: &HashMap<String, Option<String>>,
-) -> Vec<String> {
-    let mut cycle = vec![cycle_start.to_string()];
-    let mut current = cycle_end;
-
-    while current != cycle_start {
-        cycle.push(current.to_string());
-        if let Some(Some(p)) = parent.get(current) {
-            current = p;
-        } else {
-            break;
+        if self.low_link[node_id] == self.index[node_id] {
+            let mut component = Vec::new();
+            while let Some(member) = self.stack.pop() {
+                self.on_stack.remove(member);
+                component.push(member);
+                if member == node_id {
+                    break;
+                }
+            }
+            self.components.push(component);
         }
     }
-
-    cycle.reverse();
-    cycle
 }
 
 fn detect_bottlenecks(
@@ -243,6 +471,219 @@ fn detect_isolated_components<B: Backend>(
         .collect()
 }
 
+/// Dependencies on a component from outside the container that holds it
+fn detect_layering_violations<B: Backend>(model: &OptaModel<B>) -> Vec<AntiPattern> {
+    model
+        .edges
+        .iter()
+        .filter_map(|edge| {
+            let source = model.find_node(&edge.from)?;
+            let target = model.find_node(&edge.to)?;
+            if target.node_type != NodeType::Component {
+                return None;
+            }
+            let container = model
+                .ancestors(&target.id)
+                .into_iter()
+                .find(|n| n.node_type == NodeType::Container)?;
+            if source.id == container.id || model.is_ancestor(&container.id, &source.id) {
+                return None;
+            }
+            // People should never see components; other elements should go
+            // through the container's interface
+            let severity = if source.node_type == NodeType::Person {
+                0.8
+            } else {
+                0.6
+            };
+            Some(AntiPattern::LayeringViolation {
+                from: source.id.clone(),
+                to: target.id.clone(),
+                bypassed_container: container.id.clone(),
+                severity,
+            })
+        })
+        .collect()
+}
+
+fn detect_god_components<B: Backend>(
+    model: &OptaModel<B>,
+    adjacency: &HashMap<String, Vec<String>>,
+    config: &AntiPatternConfig,
+) -> Vec<AntiPattern> {
+    let n = model.node_count();
+    if n < 3 {
+        return Vec::new();
+    }
+
+    let betweenness = betweenness_centrality(model, adjacency);
+    let mut degree: HashMap<&str, usize> = HashMap::new();
+    for (from, neighbors) in adjacency {
+        *degree.entry(from.as_str()).or_default() += neighbors.len();
+        for to in neighbors {
+            *degree.entry(to.as_str()).or_default() += 1;
+        }
+    }
+
+    model
+        .nodes
+        .iter()
+        .zip(betweenness)
+        .filter(|(node, betweenness)| {
+            *betweenness >= config.god_component_threshold
+                && degree.get(node.id.as_str()).copied().unwrap_or(0)
+                    >= config.god_component_min_degree
+        })
+        .map(|(node, betweenness)| AntiPattern::GodComponent {
+            node_id: node.id.clone(),
+            betweenness,
+            severity: betweenness / config.god_component_threshold.max(f32::EPSILON),
+        })
+        .collect()
+}
+
+/// Brandes' betweenness centrality over the directed dependency graph,
+/// normalized by the number of ordered pairs `(n - 1)(n - 2)`, in model order
+fn betweenness_centrality<B: Backend>(
+    model: &OptaModel<B>,
+    adjacency: &HashMap<String, Vec<String>>,
+) -> Vec<f32> {
+    let n = model.node_count();
+    let index: HashMap<&str, usize> = model
+        .nodes
+        .iter()
+        .enumerate()
+        .map(|(idx, node)| (node.id.as_str(), idx))
+        .collect();
+    let successors: Vec<Vec<usize>> = model
+        .nodes
+        .iter()
+        .map(|node| {
+            let mut targets: Vec<usize> = adjacency
+                .get(&node.id)
+                .into_iter()
+                .flatten()
+                .filter_map(|to| index.get(to.as_str()).copied())
+                .collect();
+            targets.sort_unstable();
+            targets.dedup();
+            targets
+        })
+        .collect();
+
+    let mut centrality = vec![0.0f64; n];
+    for source in 0..n {
+        let mut order = Vec::with_capacity(n);
+        let mut predecessors: Vec<Vec<usize>> = vec![Vec::new(); n];
+        let mut paths = vec![0.0f64; n];
+        let mut distance = vec![usize::MAX; n];
+        paths[source] = 1.0;
+        distance[source] = 0;
+
+        let mut queue = VecDeque::from([source]);
+        while let Some(v) = queue.pop_front() {
+            order.push(v);
+            for &w in &successors[v] {
+                if distance[w] == usize::MAX {
+                    distance[w] = distance[v] + 1;
+                    queue.push_back(w);
+                }
+                if distance[w] == distance[v] + 1 {
+                    paths[w] += paths[v];
+                    predecessors[w].push(v);
+                }
+            }
+        }
+
+        let mut dependency = vec![0.0f64; n];
+        for &w in order.iter().rev() {
+            for &v in &predecessors[w] {
+                dependency[v] += paths[v] / paths[w] * (1.0 + dependency[w]);
+            }
+            if w != source {
+                centrality[w] += dependency[w];
+            }
+        }
+    }
+
+    let pairs = ((n - 1) * (n - 2)).max(1) as f64;
+    centrality.into_iter().map(|c| (c / pairs) as f32).collect()
+}
+
+fn is_database<B: Backend>(node: &OptaNode<B>) -> bool {
+    node.has_tag("Database")
+        || node.technology.as_deref().is_some_and(|technology| {
+            let technology = technology.to_lowercase();
+            DATABASE_TECHNOLOGIES
+                .iter()
+                .any(|known| technology.contains(known))
+        })
+}
+
+/// Databases that several containers read or write directly; dependencies of
+/// components count for their container
+fn detect_shared_databases<B: Backend>(model: &OptaModel<B>, threshold: usize) -> Vec<AntiPattern> {
+    let mut accessors: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    for edge in &model.edges {
+        let Some(database) = model.find_node(&edge.to).filter(|n| is_database(n)) else {
+            continue;
+        };
+        let accessor = if model
+            .find_node(&edge.from)
+            .is_some_and(|n| n.node_type == NodeType::Component)
+        {
+            model
+                .ancestors(&edge.from)
+                .into_iter()
+                .find(|n| n.node_type == NodeType::Container)
+                .map_or(edge.from.as_str(), |n| n.id.as_str())
+        } else {
+            edge.from.as_str()
+        };
+        if accessor == database.id
+            || model.is_ancestor(accessor, &database.id)
+            || model.is_ancestor(&database.id, accessor)
+        {
+            continue;
+        }
+        let list = accessors.entry(database.id.as_str()).or_default();
+        if !list.iter().any(|a| a == accessor) {
+            list.push(accessor.to_string());
+        }
+    }
+
+    accessors
+        .into_iter()
+        .filter(|(_, accessors)| accessors.len() >= threshold)
+        .map(|(node_id, accessors)| AntiPattern::SharedDatabase {
+            node_id: node_id.to_string(),
+            severity: accessors.len() as f32 / (threshold as f32).max(1.0),
+            accessors,
+        })
+        .collect()
+}
+
+fn detect_chatty_interfaces<B: Backend>(model: &OptaModel<B>, threshold: f32) -> Vec<AntiPattern> {
+    let mut weights: Vec<((&str, &str), f32)> = Vec::new();
+    for edge in &model.edges {
+        let key = (edge.from.as_str(), edge.to.as_str());
+        match weights.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, weight)) => *weight += edge.weight,
+            None => weights.push((key, edge.weight)),
+        }
+    }
+
+    weights
+        .into_iter()
+        .filter(|(_, weight)| *weight >= threshold)
+        .map(|((from, to), weight)| AntiPattern::ChattyInterface {
+            from: from.to_string(),
+            to: to.to_string(),
+            weight,
+            severity: weight / threshold.max(f32::EPSILON),
+        })
+        .collect()
+}
 
 /// Detect anti-patterns with optional telemetry logging
 #[cfg(feature = "telemetry")]
@@ -255,41 +696,17 @@ pub fn detect_anti_patterns_with_telemetry<B: Backend>(
 
     if let Some(logger) = logger {
         for pattern in &patterns {
-            match pattern {
-                AntiPattern::Cycle { nodes } => {
-                    logger.log_pattern_detected(
-                        "cycle".to_string(),
-                        1.0,
-                        nodes.clone(),
-                    )?;
-                }
-                AntiPattern::Bottleneck { node_id, severity, .. } => {
-                    logger.log_pattern_detected(
-                        "bottleneck".to_string(),
-                        *severity,
-                        vec![node_id.clone()],
-                    )?;
-                }
-                AntiPattern::IsolatedComponent { node_id } => {
-                    logger.log_pattern_detected(
-                        "isolated".to_string(),
-                        0.5,
-                        vec![node_id.clone()],
-                    )?;
-                }
-                AntiPattern::OverCoupling { node_id, severity, .. } => {
-                    logger.log_pattern_detected(
-                        "over_coupling".to_string(),
-                        *severity,
-                        vec![node_id.clone()],
-                    )?;
-                }
-            }
+            logger.log_pattern_detected(
+                pattern.kind().to_string(),
+                pattern.severity(),
+                pattern.affected_nodes(),
+            )?;
         }
     }
 
     Ok(patterns)
 }
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -454,4 +871,155 @@ mod tests {
                 .any(|p| matches!(p, AntiPattern::Bottleneck { .. }))
         );
     }
+
+    fn add(model: &mut OptaModel<TestBackend>, id: &str, node_type: NodeType) {
+        model.add_node(OptaNode::new(id.to_string(), id.to_uppercase(), node_type));
+    }
+
+    fn link(model: &mut OptaModel<TestBackend>, from: &str, to: &str) {
+        model.add_edge(OptaEdge::new(from.to_string(), to.to_string()));
+    }
+
+    #[test]
+    fn test_overlapping_cycles_reported_as_one_component() {
+        let mut model = OptaModel::<TestBackend>::new();
+        for id in ["a", "b", "c", "d", "e"] {
+            add(&mut model, id, NodeType::Container);
+        }
+        // a -> b -> a and b -> c -> a share nodes; d -> d depends on itself
+        link(&mut model, "a", "b");
+        link(&mut model, "b", "a");
+        link(&mut model, "b", "c");
+        link(&mut model, "c", "a");
+        link(&mut model, "c", "e");
+        link(&mut model, "d", "d");
+
+        let patterns = detect_anti_patterns(&model, &AntiPatternConfig::default()).unwrap();
+        let cycles: Vec<_> = patterns
+            .iter()
+            .filter_map(|p| match p {
+                AntiPattern::Cycle { nodes } => Some(nodes.clone()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(cycles, vec![vec!["a", "b", "c"], vec!["d"]]);
+    }
+
+    #[test]
+    fn test_detect_layering_violation() {
+        let mut model = OptaModel::<TestBackend>::new();
+        add(&mut model, "user", NodeType::Person);
+        add(&mut model, "api", NodeType::Container);
+        add(&mut model, "web", NodeType::Container);
+        model.add_node(
+            OptaNode::new(
+                "handler".to_string(),
+                "Handler".to_string(),
+                NodeType::Component,
+            )
+            .with_parent("api".to_string()),
+        );
+        model.add_node(
+            OptaNode::new("repo".to_string(), "Repo".to_string(), NodeType::Component)
+                .with_parent("api".to_string()),
+        );
+        link(&mut model, "user", "handler");
+        link(&mut model, "web", "api");
+        link(&mut model, "handler", "repo");
+
+        let violations: Vec<_> = detect_anti_patterns(&model, &AntiPatternConfig::default())
+            .unwrap()
+            .into_iter()
+            .filter(|p| matches!(p, AntiPattern::LayeringViolation { .. }))
+            .collect();
+        assert_eq!(
+            violations,
+            vec![AntiPattern::LayeringViolation {
+                from: "user".to_string(),
+                to: "handler".to_string(),
+                bypassed_container: "api".to_string(),
+                severity: 0.8,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_detect_god_component_by_betweenness() {
+        let mut model = OptaModel::<TestBackend>::new();
+        for id in ["in1", "in2", "hub", "out1", "out2", "tail"] {
+            add(&mut model, id, NodeType::Container);
+        }
+        link(&mut model, "in1", "hub");
+        link(&mut model, "in2", "hub");
+        link(&mut model, "hub", "out1");
+        link(&mut model, "hub", "out2");
+        link(&mut model, "out1", "tail");
+
+        let patterns = detect_anti_patterns(&model, &AntiPatternConfig::default()).unwrap();
+        let gods: Vec<_> = patterns
+            .iter()
+            .filter_map(|p| match p {
+                AntiPattern::GodComponent {
+                    node_id,
+                    betweenness,
+                    ..
+                } => Some((node_id.as_str(), *betweenness)),
+                _ => None,
+            })
+            .collect();
+        // hub lies on in1/in2 -> out1/out2/tail: 6 of 20 ordered pairs
+        assert_eq!(gods.len(), 1);
+        assert_eq!(gods[0].0, "hub");
+        assert!((gods[0].1 - 0.3).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_detect_shared_database() {
+        let mut model = OptaModel::<TestBackend>::new();
+        add(&mut model, "orders", NodeType::Container);
+        add(&mut model, "billing", NodeType::Container);
+        model.add_node(
+            OptaNode::new("db".to_string(), "DB".to_string(), NodeType::Container)
+                .with_technology("PostgreSQL".to_string()),
+        );
+        model.add_node(
+            OptaNode::new(
+                "invoices".to_string(),
+                "Invoices".to_string(),
+                NodeType::Component,
+            )
+            .with_parent("billing".to_string()),
+        );
+        link(&mut model, "orders", "db");
+        link(&mut model, "invoices", "db");
+        link(&mut model, "billing", "db");
+
+        let patterns = detect_anti_patterns(&model, &AntiPatternConfig::default()).unwrap();
+        assert!(patterns.contains(&AntiPattern::SharedDatabase {
+            node_id: "db".to_string(),
+            accessors: vec!["orders".to_string(), "billing".to_string()],
+            severity: 1.0,
+        }));
+    }
+
+    #[test]
+    fn test_detect_chatty_interface_sums_parallel_edges() {
+        let mut model = OptaModel::<TestBackend>::new();
+        add(&mut model, "a", NodeType::Container);
+        add(&mut model, "b", NodeType::Container);
+        for _ in 0..2 {
+            let mut edge = OptaEdge::new("a".to_string(), "b".to_string());
+            edge.weight = 6.0;
+            model.add_edge(edge);
+        }
+
+        let patterns = detect_anti_patterns(&model, &AntiPatternConfig::default()).unwrap();
+        let chatty = patterns
+            .iter()
+            .find(|p| matches!(p, AntiPattern::ChattyInterface { .. }))
+            .unwrap();
+        assert_eq!(chatty.affected_nodes(), vec!["a", "b"]);
+        assert!((chatty.severity() - 1.2).abs() < 1e-6);
+        assert!(chatty.remediation().contains("from a to b"));
+    }
 }
@@ -0,0 +1,487 @@
+//! Pluggable layout algorithms
+//!
+//! Every algorithm implements [`LayoutAlgorithm`], which positions the nodes of
+//! a flat model. [`LayoutAlgorithm::layout_nested`] builds on that to respect
+//! C4 containment: each container's children are laid out on their own, the
+//! container is sized to their bounding box, and the result is placed as a
+//! single node one level up.
+
+mod layered;
+mod stress;
+
+pub use layered::LayeredLayout;
+pub use stress::StressLayout;
+
+use crate::model::OptaEdge;
+use crate::{OptaModel, OptaOptimizer, OptimizationStats, Result};
+use burn::tensor::backend::Backend;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// A strategy for assigning 2D positions to the nodes of a model
+pub trait LayoutAlgorithm<B: Backend> {
+    /// Short identifier used in logs and telemetry
+    fn name(&self) -> &'static str;
+
+    /// Position every node of `model`, ignoring containment
+    fn layout(&self, model: &mut OptaModel<B>) -> Result<OptimizationStats>;
+
+    /// Position every node so that children sit inside their parent's box
+    ///
+    /// Parent nodes are positioned at the center of their box. Pinned nodes
+    /// are laid out like any other, since their coordinates have no meaning
+    /// inside a parent's local frame.
+    fn layout_nested(
+        &self,
+        model: &mut OptaModel<B>,
+        options: &NestingOptions,
+    ) -> Result<NestedLayout> {
+        nested_layout(self, model, options)
+    }
+}
+
+impl<B: Backend> LayoutAlgorithm<B> for OptaOptimizer<B> {
+    fn name(&self) -> &'static str {
+        "force_directed"
+    }
+
+    fn layout(&self, model: &mut OptaModel<B>) -> Result<OptimizationStats> {
+        if model.adjacency_matrix.is_none() {
+            model.build_adjacency_matrix();
+        }
+        self.optimize_layout(model)
+    }
+}
+
+/// Sizes used when packing nested elements
+#[derive(Debug, Clone, Copy)]
+pub struct NestingOptions {
+    /// Size of an element without children
+    pub node_width: f32,
+    pub node_height: f32,
+    /// Space between a parent's border and its children
+    pub padding: f32,
+    /// Extra space at the top of a parent box for its label
+    pub header: f32,
+    /// Minimum space between sibling boxes
+    pub gap: f32,
+}
+
+impl Default for NestingOptions {
+    fn default() -> Self {
+        Self {
+            node_width: 160.0,
+            node_height: 100.0,
+            padding: 30.0,
+            header: 30.0,
+            gap: 40.0,
+        }
+    }
+}
+
+/// Axis-aligned box, with `(x, y)` the top-left corner
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+pub struct BoundingBox {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl BoundingBox {
+    pub fn center(&self) -> (f32, f32) {
+        (self.x + self.width / 2.0, self.y + self.height / 2.0)
+    }
+
+    pub fn contains(&self, other: &BoundingBox) -> bool {
+        other.x >= self.x
+            && other.y >= self.y
+            && other.x + other.width <= self.x + self.width
+            && other.y + other.height <= self.y + self.height
+    }
+
+    pub fn intersects(&self, other: &BoundingBox) -> bool {
+        self.x < other.x + other.width
+            && other.x < self.x + self.width
+            && self.y < other.y + other.height
+            && other.y < self.y + self.height
+    }
+}
+
+/// Result of [`LayoutAlgorithm::layout_nested`]
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct NestedLayout {
+    /// Box of every node, keyed by node id; parents enclose their children
+    pub bounds: BTreeMap<String, BoundingBox>,
+    /// Stats of each per-level layout run, innermost levels first
+    pub runs: Vec<OptimizationStats>,
+}
+
+/// Layout of one parent's direct children, relative to the parent's center
+struct Frame {
+    size: (f32, f32),
+    offsets: Vec<(String, (f32, f32))>,
+}
+
+fn nested_layout<B: Backend, L: LayoutAlgorithm<B> + ?Sized>(
+    algorithm: &L,
+    model: &mut OptaModel<B>,
+    options: &NestingOptions,
+) -> Result<NestedLayout> {
+    if model.nodes.is_empty() {
+        return Err(anyhow::anyhow!("Cannot lay out empty model"));
+    }
+
+    let mut result = NestedLayout::default();
+    let roots: Vec<String> = model.roots().iter().map(|n| n.id.clone()).collect();
+
+    // Children are framed before their parents, so a parent's size is known
+    // when it is placed among its siblings
+    let mut order: Vec<String> = roots
+        .iter()
+        .flat_map(|root| model.subtree(root).into_iter().map(|n| n.id.clone()))
+        .collect();
+    order.reverse();
+
+    let mut frames: HashMap<String, Frame> = HashMap::new();
+    for id in &order {
+        let members: Vec<String> = model.children(id).iter().map(|n| n.id.clone()).collect();
+        if members.is_empty() {
+            continue;
+        }
+
+        let sizes = member_sizes(&members, &frames, options);
+        let (centers, stats) = place_members(algorithm, model, &members, &sizes, options)?;
+        result.runs.extend(stats);
+
+        let (min_x, min_y, max_x, max_y) = extent(&centers, &sizes);
+        let width = max_x - min_x + 2.0 * options.padding;
+        let height = max_y - min_y + 2.0 * options.padding + options.header;
+        let center = (
+            (min_x + max_x) / 2.0,
+            (min_y + max_y - options.header) / 2.0,
+        );
+
+        let offsets = members
+            .into_iter()
+            .zip(centers)
+            .map(|(member, (x, y))| (member, (x - center.0, y - center.1)))
+            .collect();
+        frames.insert(
+            id.clone(),
+            Frame {
+                size: (width, height),
+                offsets,
+            },
+        );
+    }
+
+    let sizes = member_sizes(&roots, &frames, options);
+    let (centers, stats) = place_members(algorithm, model, &roots, &sizes, options)?;
+    result.runs.extend(stats);
+
+    // Shift the top level so every box has non-negative coordinates
+    let (min_x, min_y, _, _) = extent(&centers, &sizes);
+    let mut stack: Vec<(String, (f32, f32))> = roots
+        .into_iter()
+        .zip(centers)
+        .map(|(id, (x, y))| (id, (x - min_x, y - min_y)))
+        .collect();
+
+    while let Some((id, (x, y))) = stack.pop() {
+        let (width, height) = frames
+            .get(&id)
+            .map(|frame| frame.size)
+            .unwrap_or((options.node_width, options.node_height));
+        result.bounds.insert(
+            id.clone(),
+            BoundingBox {
+                x: x - width / 2.0,
+                y: y - height / 2.0,
+                width,
+                height,
+            },
+        );
+        if let Some(frame) = frames.get(&id) {
+            for (child, (dx, dy)) in &frame.offsets {
+                stack.push((child.clone(), (x + dx, y + dy)));
+            }
+        }
+        if let Some(node) = model.find_node_mut(&id) {
+            node.set_position(x, y);
+        }
+    }
+
+    Ok(result)
+}
+
+fn member_sizes(
+    members: &[String],
+    frames: &HashMap<String, Frame>,
+    options: &NestingOptions,
+) -> Vec<(f32, f32)> {
+    members
+        .iter()
+        .map(|id| {
+            frames
+                .get(id)
+                .map(|frame| frame.size)
+                .unwrap_or((options.node_width, options.node_height))
+        })
+        .collect()
+}
+
+/// Lay out `members` as a flat model and spread the result until no two boxes overlap
+///
+/// Edges between descendants are rolled up onto the members that contain them.
+fn place_members<B: Backend, L: LayoutAlgorithm<B> + ?Sized>(
+    algorithm: &L,
+    model: &OptaModel<B>,
+    members: &[String],
+    sizes: &[(f32, f32)],
+    options: &NestingOptions,
+) -> Result<(Vec<(f32, f32)>, Option<OptimizationStats>)> {
+    if members.len() == 1 {
+        return Ok((vec![(0.0, 0.0)], None));
+    }
+
+    let member_set: HashSet<&str> = members.iter().map(String::as_str).collect();
+    let representative = |id: &str| -> Option<String> {
+        if member_set.contains(id) {
+            return Some(id.to_string());
+        }
+        model
+            .ancestors(id)
+            .into_iter()
+            .find(|ancestor| member_set.contains(ancestor.id.as_str()))
+            .map(|ancestor| ancestor.id.clone())
+    };
+
+    let mut sub_model = OptaModel::<B>::new();
+    for member in members {
+        if let Some(node) = model.find_node(member) {
+            let mut node = node.clone();
+            node.parent = None;
+            node.pinned = false;
+            node.position = None;
+            sub_model.add_node(node);
+        }
+    }
+    for edge in &model.edges {
+        if let (Some(from), Some(to)) = (representative(&edge.from), representative(&edge.to))
+            && from != to
+        {
+            sub_model.add_edge(OptaEdge::new(from, to).with_weight(edge.weight));
+        }
+    }
+    sub_model.build_adjacency_matrix();
+
+    let stats = algorithm.layout(&mut sub_model)?;
+    let mut centers: Vec<(f32, f32)> = sub_model
+        .nodes
+        .iter()
+        .map(|n| n.get_position().unwrap_or_default())
+        .collect();
+    remove_overlaps(&mut centers, sizes, options.gap);
+
+    Ok((centers, Some(stats)))
+}
+
+/// Largest factor [`remove_overlaps`] spreads a layout by
+const MAX_OVERLAP_SCALE: f32 = 1000.0;
+
+/// Centers closer than this are treated as the same point
+const COINCIDENT_DISTANCE: f32 = 1e-3;
+
+/// Turn between successive coincident centers, so no two land in the same direction
+const GOLDEN_ANGLE: f32 = 2.399_963;
+
+/// Uniformly scale `centers` so that every pair of boxes is separated by at least `gap`
+///
+/// Scaling keeps the shape the algorithm produced, and also compacts layouts
+/// that came out far more spread than the boxes need. Coincident centers
+/// can't be scaled apart, so they are first spread around the first of them,
+/// at half the distance between the closest distinct centers.
+fn remove_overlaps(centers: &mut [(f32, f32)], sizes: &[(f32, f32)], gap: f32) {
+    let distance = |a: (f32, f32), b: (f32, f32)| (a.0 - b.0).hypot(a.1 - b.1);
+    let mut closest = f32::INFINITY;
+    let mut twin_of = vec![None; centers.len()];
+    for i in 0..centers.len() {
+        for j in i + 1..centers.len() {
+            let d = distance(centers[i], centers[j]);
+            if d < COINCIDENT_DISTANCE {
+                if twin_of[j].is_none() {
+                    twin_of[j] = Some(twin_of[i].unwrap_or(i));
+                }
+            } else {
+                closest = closest.min(d);
+            }
+        }
+    }
+
+    let radius = if closest.is_finite() {
+        closest / 2.0
+    } else {
+        1.0
+    };
+    let mut twin_counts = vec![0; centers.len()];
+    for (j, twin) in twin_of.iter().enumerate() {
+        if let Some(i) = *twin {
+            twin_counts[i] += 1;
+            let angle = twin_counts[i] as f32 * GOLDEN_ANGLE;
+            centers[j] = (
+                centers[i].0 + radius * angle.cos(),
+                centers[i].1 + radius * angle.sin(),
+            );
+        }
+    }
+
+    let mut scale: f32 = 0.0;
+    for i in 0..centers.len() {
+        for j in i + 1..centers.len() {
+            let dx = (centers[i].0 - centers[j].0).abs();
+            let dy = (centers[i].1 - centers[j].1).abs();
+            let needed_x = (sizes[i].0 + sizes[j].0) / 2.0 + gap;
+            let needed_y = (sizes[i].1 + sizes[j].1) / 2.0 + gap;
+
+            // A pair is separated once it clears either axis
+            let pair_scale = (needed_x / dx.max(f32::EPSILON)).min(needed_y / dy.max(f32::EPSILON));
+            scale = scale.max(pair_scale);
+        }
+    }
+
+    let scale = scale.min(MAX_OVERLAP_SCALE);
+    if scale.is_finite() && scale > 0.0 {
+        for center in centers.iter_mut() {
+            center.0 *= scale;
+            center.1 *= scale;
+        }
+    }
+}
+
+fn extent(centers: &[(f32, f32)], sizes: &[(f32, f32)]) -> (f32, f32, f32, f32) {
+    centers.iter().zip(sizes).fold(
+        (f32::MAX, f32::MAX, f32::MIN, f32::MIN),
+        |(min_x, min_y, max_x, max_y), ((x, y), (w, h))| {
+            (
+                min_x.min(x - w / 2.0),
+                min_y.min(y - h / 2.0),
+                max_x.max(x + w / 2.0),
+                max_y.max(y + h / 2.0),
+            )
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{NodeType, OptaNode};
+    use burn::backend::ndarray::NdArray;
+
+    type TestBackend = NdArray<f32>;
+
+    fn nested_model() -> OptaModel<TestBackend> {
+        let mut model = OptaModel::new();
+        for (id, node_type, parent) in [
+            ("user", NodeType::Person, None),
+            ("shop", NodeType::System, None),
+            ("payments", NodeType::System, None),
+            ("web", NodeType::Container, Some("shop")),
+            ("api", NodeType::Container, Some("shop")),
+            ("db", NodeType::Container, Some("shop")),
+            ("orders", NodeType::Component, Some("api")),
+            ("billing", NodeType::Component, Some("api")),
+        ] {
+            let mut node = OptaNode::new(id.to_string(), id.to_uppercase(), node_type);
+            node.parent = parent.map(str::to_string);
+            model.add_node(node);
+        }
+        for (from, to) in [
+            ("user", "web"),
+            ("web", "orders"),
+            ("orders", "billing"),
+            ("orders", "db"),
+            ("billing", "payments"),
+        ] {
+            model.add_edge(OptaEdge::new(from.to_string(), to.to_string()));
+        }
+        model
+    }
+
+    fn assert_containment(model: &OptaModel<TestBackend>, layout: &NestedLayout) {
+        for node in &model.nodes {
+            let bounds = layout.bounds[&node.id];
+            if let Some(parent) = &node.parent {
+                assert!(
+                    layout.bounds[parent].contains(&bounds),
+                    "{} is not inside {}",
+                    node.id,
+                    parent
+                );
+            }
+            for sibling in &model.nodes {
+                if sibling.id != node.id && sibling.parent == node.parent {
+                    assert!(
+                        !bounds.intersects(&layout.bounds[&sibling.id]),
+                        "{} overlaps {}",
+                        node.id,
+                        sibling.id
+                    );
+                }
+            }
+            let (x, y) = node.get_position().unwrap();
+            assert_eq!((x, y), bounds.center());
+        }
+    }
+
+    #[test]
+    fn test_nested_layout_respects_containment() {
+        let algorithms: Vec<Box<dyn LayoutAlgorithm<TestBackend>>> = vec![
+            Box::new(OptaOptimizer::new(50, 0.1).with_seed(1)),
+            Box::new(LayeredLayout::new()),
+            Box::new(StressLayout::new(100).with_seed(1)),
+        ];
+
+        for algorithm in algorithms {
+            let mut model = nested_model();
+            let layout = algorithm
+                .layout_nested(&mut model, &NestingOptions::default())
+                .unwrap();
+            assert_eq!(layout.bounds.len(), model.node_count());
+            // api's components, shop's containers, then the three top-level elements
+            assert_eq!(layout.runs.len(), 3, "{}", algorithm.name());
+            assert_containment(&model, &layout);
+        }
+    }
+
+    #[test]
+    fn test_remove_overlaps_scales_uniformly() {
+        let mut centers = vec![(0.0, 0.0), (1.0, 0.0), (0.0, 2.0)];
+        let sizes = vec![(10.0, 10.0); 3];
+        remove_overlaps(&mut centers, &sizes, 2.0);
+
+        // The closest pair on x needs 12 units, which decides the scale
+        assert_eq!(centers, vec![(0.0, 0.0), (12.0, 0.0), (0.0, 24.0)]);
+    }
+
+    #[test]
+    fn test_remove_overlaps_separates_coincident_centers() {
+        let mut centers = vec![(0.0, 0.0), (0.0, 0.0), (5.0, 0.0)];
+        let sizes = vec![(10.0, 10.0); 3];
+        remove_overlaps(&mut centers, &sizes, 2.0);
+
+        for i in 0..centers.len() {
+            assert!(centers[i].0.is_finite() && centers[i].1.is_finite());
+            assert!(centers[i].0.abs() < 100.0 && centers[i].1.abs() < 100.0);
+            for j in i + 1..centers.len() {
+                let dx = (centers[i].0 - centers[j].0).abs();
+                let dy = (centers[i].1 - centers[j].1).abs();
+                assert!(
+                    dx >= 12.0 - 1e-3 || dy >= 12.0 - 1e-3,
+                    "{i} and {j} overlap"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,550 @@
+use super::LayoutAlgorithm;
+use crate::optimizer::{ConvergenceReason, OptimizationStats, Timer};
+use crate::workspace::{AutoLayout, RankDirection};
+use crate::{OptaModel, Result};
+use burn::tensor::backend::Backend;
+use std::collections::{HashMap, HashSet};
+
+/// Sugiyama-style layered layout for directed graphs
+///
+/// Relationships flow in one direction (top to bottom by default), which suits
+/// request flows and dependency chains far better than force-directed layout:
+/// 1. cycles are broken by reversing DFS back edges,
+/// 2. nodes are assigned to layers by longest path from the sources,
+/// 3. edges spanning several layers get dummy nodes, and layers are reordered
+///    by the barycenter heuristic to reduce crossings,
+/// 4. nodes are pulled towards their neighbours without breaking layer order.
+#[derive(Debug, Clone)]
+pub struct LayeredLayout {
+    direction: RankDirection,
+    layer_separation: f32,
+    node_separation: f32,
+    sweeps: usize,
+}
+
+impl LayeredLayout {
+    pub fn new() -> Self {
+        Self {
+            direction: RankDirection::TopBottom,
+            layer_separation: 150.0,
+            node_separation: 200.0,
+            sweeps: 8,
+        }
+    }
+
+    /// Use the direction and separations of a view's `autoLayout` settings
+    pub fn from_auto_layout(auto_layout: &AutoLayout) -> Self {
+        let mut layout = Self::new().with_direction(auto_layout.direction);
+        if let Some(rank_separation) = auto_layout.rank_separation {
+            layout.layer_separation = rank_separation as f32;
+        }
+        if let Some(node_separation) = auto_layout.node_separation {
+            layout.node_separation = node_separation as f32;
+        }
+        layout
+    }
+
+    pub fn with_direction(mut self, direction: RankDirection) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    pub fn with_layer_separation(mut self, separation: f32) -> Self {
+        self.layer_separation = separation;
+        self
+    }
+
+    pub fn with_node_separation(mut self, separation: f32) -> Self {
+        self.node_separation = separation;
+        self
+    }
+
+    /// Number of barycenter passes (alternating down and up) used for crossing reduction
+    pub fn with_sweeps(mut self, sweeps: usize) -> Self {
+        self.sweeps = sweeps;
+        self
+    }
+}
+
+impl Default for LayeredLayout {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<B: Backend> LayoutAlgorithm<B> for LayeredLayout {
+    fn name(&self) -> &'static str {
+        "layered"
+    }
+
+    /// Lay out `model`; `energy` in the returned stats is the number of edge
+    /// crossings after each sweep
+    ///
+    /// Pinned nodes keep their positions, and the rest of the layout is
+    /// shifted to line up with them as closely as a single offset allows.
+    fn layout(&self, model: &mut OptaModel<B>) -> Result<OptimizationStats> {
+        let node_count = model.node_count();
+        if node_count == 0 {
+            return Err(anyhow::anyhow!("Cannot lay out empty model"));
+        }
+        if let Some(node) = model
+            .nodes
+            .iter()
+            .find(|n| n.pinned && n.position.is_none())
+        {
+            return Err(anyhow::anyhow!("Pinned node {} has no position", node.id));
+        }
+        let start = Timer::start();
+        if model.nodes.iter().all(|n| n.pinned) {
+            return Ok(OptimizationStats {
+                iterations: 0,
+                final_temperature: None,
+                duration_ms: start.elapsed_ms(),
+                node_count,
+                edge_count: model.edge_count(),
+                energy: Vec::new(),
+                convergence: ConvergenceReason::AllNodesPinned,
+            });
+        }
+
+        let index: HashMap<&str, usize> = model
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(idx, node)| (node.id.as_str(), idx))
+            .collect();
+        let mut seen = HashSet::new();
+        let edges: Vec<(usize, usize)> = model
+            .edges
+            .iter()
+            .filter_map(|e| Some((*index.get(e.from.as_str())?, *index.get(e.to.as_str())?)))
+            .filter(|(from, to)| from != to && seen.insert((*from, *to)))
+            .collect();
+
+        let dag = break_cycles(node_count, &edges);
+        let layers = assign_layers(node_count, &dag);
+        let mut graph = LayeredGraph::new(&layers, &dag);
+
+        let mut energy = Vec::new();
+        let mut best = graph.order.clone();
+        let mut best_crossings = graph.crossings();
+        let mut convergence = ConvergenceReason::MaxIterations;
+
+        if best_crossings == 0 {
+            convergence = ConvergenceReason::Converged;
+        } else {
+            for sweep in 0..self.sweeps {
+                graph.barycenter_sweep(sweep % 2 == 0);
+                let crossings = graph.crossings();
+                energy.push(crossings as f32);
+                if crossings < best_crossings {
+                    best_crossings = crossings;
+                    best = graph.order.clone();
+                }
+                if crossings == 0 {
+                    convergence = ConvergenceReason::Converged;
+                    break;
+                }
+            }
+        }
+        graph.order = best;
+
+        let offsets = graph.assign_offsets(self.node_separation);
+        let mut coordinates: Vec<(f32, f32)> = (0..node_count)
+            .map(|node| {
+                let along = layers[node] as f32 * self.layer_separation;
+                let across = offsets[node];
+                match self.direction {
+                    RankDirection::TopBottom => (across, along),
+                    RankDirection::BottomTop => (across, -along),
+                    RankDirection::LeftRight => (along, across),
+                    RankDirection::RightLeft => (-along, across),
+                }
+            })
+            .collect();
+
+        // Start at the origin, or at the pinned nodes' mean offset from where
+        // the layout would put them
+        let pinned_offsets: Vec<(f32, f32)> = model
+            .nodes
+            .iter()
+            .zip(&coordinates)
+            .filter(|(node, _)| node.pinned)
+            .filter_map(|(node, (x, y))| {
+                let (pinned_x, pinned_y) = node.get_position()?;
+                Some((pinned_x - x, pinned_y - y))
+            })
+            .collect();
+        let (offset_x, offset_y) = if pinned_offsets.is_empty() {
+            (
+                -coordinates.iter().map(|c| c.0).fold(f32::MAX, f32::min),
+                -coordinates.iter().map(|c| c.1).fold(f32::MAX, f32::min),
+            )
+        } else {
+            let count = pinned_offsets.len() as f32;
+            (
+                pinned_offsets.iter().map(|o| o.0).sum::<f32>() / count,
+                pinned_offsets.iter().map(|o| o.1).sum::<f32>() / count,
+            )
+        };
+        for (x, y) in &mut coordinates {
+            *x += offset_x;
+            *y += offset_y;
+        }
+        for (node, (x, y)) in model.nodes.iter_mut().zip(coordinates) {
+            if !node.pinned {
+                node.set_position(x, y);
+            }
+        }
+
+        Ok(OptimizationStats {
+            iterations: energy.len(),
+            final_temperature: None,
+            duration_ms: start.elapsed_ms(),
+            node_count,
+            edge_count: model.edge_count(),
+            energy,
+            convergence,
+        })
+    }
+}
+
+/// Reverse the back edges found by a depth-first search, leaving an acyclic edge list
+fn break_cycles(node_count: usize, edges: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum State {
+        Unvisited,
+        OnStack,
+        Done,
+    }
+
+    let mut outgoing = vec![Vec::new(); node_count];
+    for (edge_idx, &(from, _)) in edges.iter().enumerate() {
+        outgoing[from].push(edge_idx);
+    }
+
+    let mut state = vec![State::Unvisited; node_count];
+    let mut reversed = vec![false; edges.len()];
+    for start in 0..node_count {
+        if state[start] != State::Unvisited {
+            continue;
+        }
+        state[start] = State::OnStack;
+        let mut stack = vec![(start, 0)];
+        while let Some((node, next)) = stack.pop() {
+            let Some(&edge_idx) = outgoing[node].get(next) else {
+                state[node] = State::Done;
+                continue;
+            };
+            stack.push((node, next + 1));
+            let target = edges[edge_idx].1;
+            match state[target] {
+                State::Unvisited => {
+                    state[target] = State::OnStack;
+                    stack.push((target, 0));
+                }
+                State::OnStack => reversed[edge_idx] = true,
+                State::Done => {}
+            }
+        }
+    }
+
+    edges
+        .iter()
+        .zip(reversed)
+        .map(|(&(from, to), reversed)| if reversed { (to, from) } else { (from, to) })
+        .collect()
+}
+
+/// Longest-path layering: every node sits one layer below its deepest predecessor
+fn assign_layers(node_count: usize, dag: &[(usize, usize)]) -> Vec<usize> {
+    let mut in_degree = vec![0; node_count];
+    let mut outgoing = vec![Vec::new(); node_count];
+    for &(from, to) in dag {
+        in_degree[to] += 1;
+        outgoing[from].push(to);
+    }
+
+    let mut layers = vec![0; node_count];
+    let mut queue: Vec<usize> = (0..node_count).filter(|&n| in_degree[n] == 0).collect();
+    let mut head = 0;
+    while head < queue.len() {
+        let node = queue[head];
+        head += 1;
+        for &next in &outgoing[node] {
+            layers[next] = layers[next].max(layers[node] + 1);
+            in_degree[next] -= 1;
+            if in_degree[next] == 0 {
+                queue.push(next);
+            }
+        }
+    }
+    layers
+}
+
+/// The layered graph with dummy nodes, where every edge joins adjacent layers
+struct LayeredGraph {
+    /// Node indices per layer, in drawing order; indices past the real nodes are dummies
+    order: Vec<Vec<usize>>,
+    /// Neighbours in the layer above and below
+    up: Vec<Vec<usize>>,
+    down: Vec<Vec<usize>>,
+    /// Layer of every (real or dummy) node
+    layer: Vec<usize>,
+}
+
+impl LayeredGraph {
+    fn new(layers: &[usize], dag: &[(usize, usize)]) -> Self {
+        let layer_count = layers.iter().max().map_or(1, |max| max + 1);
+        let mut graph = Self {
+            order: vec![Vec::new(); layer_count],
+            up: vec![Vec::new(); layers.len()],
+            down: vec![Vec::new(); layers.len()],
+            layer: layers.to_vec(),
+        };
+        for (node, &layer) in layers.iter().enumerate() {
+            graph.order[layer].push(node);
+        }
+
+        for &(from, to) in dag {
+            let mut previous = from;
+            for layer in layers[from] + 1..layers[to] {
+                let dummy = graph.layer.len();
+                graph.layer.push(layer);
+                graph.up.push(Vec::new());
+                graph.down.push(Vec::new());
+                graph.order[layer].push(dummy);
+                graph.link(previous, dummy);
+                previous = dummy;
+            }
+            graph.link(previous, to);
+        }
+        graph
+    }
+
+    fn link(&mut self, upper: usize, lower: usize) {
+        self.down[upper].push(lower);
+        self.up[lower].push(upper);
+    }
+
+    fn positions(&self) -> Vec<usize> {
+        let mut positions = vec![0; self.layer.len()];
+        for layer in &self.order {
+            for (position, &node) in layer.iter().enumerate() {
+                positions[node] = position;
+            }
+        }
+        positions
+    }
+
+    /// Reorder each layer by the mean position of its neighbours in the
+    /// previous layer, sweeping downwards or upwards
+    fn barycenter_sweep(&mut self, downwards: bool) {
+        let layer_count = self.order.len();
+        let layers: Vec<usize> = if downwards {
+            (1..layer_count).collect()
+        } else {
+            (0..layer_count.saturating_sub(1)).rev().collect()
+        };
+
+        for layer in layers {
+            let positions = self.positions();
+            let neighbours = if downwards { &self.up } else { &self.down };
+            let mut keyed: Vec<(f32, usize)> = self.order[layer]
+                .iter()
+                .map(|&node| {
+                    let adjacent = &neighbours[node];
+                    let key = if adjacent.is_empty() {
+                        positions[node] as f32
+                    } else {
+                        adjacent.iter().map(|&n| positions[n] as f32).sum::<f32>()
+                            / adjacent.len() as f32
+                    };
+                    (key, node)
+                })
+                .collect();
+            keyed.sort_by(|a, b| a.0.total_cmp(&b.0));
+            self.order[layer] = keyed.into_iter().map(|(_, node)| node).collect();
+        }
+    }
+
+    fn crossings(&self) -> usize {
+        let positions = self.positions();
+        let mut total = 0;
+        for layer in &self.order {
+            let segments: Vec<(usize, usize)> = layer
+                .iter()
+                .flat_map(|&upper| self.down[upper].iter().map(move |&lower| (upper, lower)))
+                .map(|(upper, lower)| (positions[upper], positions[lower]))
+                .collect();
+            for (i, a) in segments.iter().enumerate() {
+                for b in &segments[i + 1..] {
+                    if (a.0 < b.0 && a.1 > b.1) || (a.0 > b.0 && a.1 < b.1) {
+                        total += 1;
+                    }
+                }
+            }
+        }
+        total
+    }
+
+    /// Cross-axis offset of every node: start evenly spaced and centered, then
+    /// repeatedly pull nodes towards their neighbours while keeping order and spacing
+    fn assign_offsets(&self, separation: f32) -> Vec<f32> {
+        let mut offsets = vec![0.0; self.layer.len()];
+        for layer in &self.order {
+            let width = (layer.len().saturating_sub(1)) as f32 * separation;
+            for (position, &node) in layer.iter().enumerate() {
+                offsets[node] = position as f32 * separation - width / 2.0;
+            }
+        }
+
+        for _ in 0..4 {
+            for layer in &self.order {
+                let desired: Vec<f32> = layer
+                    .iter()
+                    .map(|&node| {
+                        let neighbours: Vec<usize> = self.up[node]
+                            .iter()
+                            .chain(&self.down[node])
+                            .copied()
+                            .collect();
+                        if neighbours.is_empty() {
+                            offsets[node]
+                        } else {
+                            neighbours.iter().map(|&n| offsets[n]).sum::<f32>()
+                                / neighbours.len() as f32
+                        }
+                    })
+                    .collect();
+
+                let mut placed = Vec::with_capacity(layer.len());
+                for (position, &wanted) in desired.iter().enumerate() {
+                    let value = match position {
+                        0 => wanted,
+                        _ => wanted.max(placed[position - 1] + separation),
+                    };
+                    placed.push(value);
+                }
+
+                // Spacing only ever pushes right, so recenter on the desired positions
+                let shift = desired.iter().zip(&placed).map(|(d, p)| d - p).sum::<f32>()
+                    / layer.len() as f32;
+                for (&node, value) in layer.iter().zip(placed) {
+                    offsets[node] = value + shift;
+                }
+            }
+        }
+        offsets
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::OptaEdge;
+    use crate::{NodeType, OptaNode};
+    use burn::backend::ndarray::NdArray;
+
+    type TestBackend = NdArray<f32>;
+
+    fn model(edges: &[(&str, &str)]) -> OptaModel<TestBackend> {
+        let mut model = OptaModel::new();
+        for (from, to) in edges {
+            for id in [from, to] {
+                if model.find_node(id).is_none() {
+                    model.add_node(OptaNode::new(
+                        id.to_string(),
+                        id.to_uppercase(),
+                        NodeType::Container,
+                    ));
+                }
+            }
+            model.add_edge(OptaEdge::new(from.to_string(), to.to_string()));
+        }
+        model
+    }
+
+    fn position(model: &OptaModel<TestBackend>, id: &str) -> (f32, f32) {
+        model.find_node(id).unwrap().get_position().unwrap()
+    }
+
+    #[test]
+    fn test_layers_follow_edge_direction() {
+        let mut model = model(&[
+            ("web", "api"),
+            ("api", "db"),
+            ("api", "cache"),
+            ("web", "cdn"),
+        ]);
+        LayeredLayout::new().layout(&mut model).unwrap();
+
+        let (_, web_y) = position(&model, "web");
+        let (_, api_y) = position(&model, "api");
+        let (_, db_y) = position(&model, "db");
+        assert_eq!(web_y, 0.0);
+        assert_eq!(api_y, 150.0);
+        assert_eq!(db_y, 300.0);
+        assert_eq!(position(&model, "cache").1, db_y);
+        assert!((position(&model, "db").0 - position(&model, "cache").0).abs() >= 199.99);
+    }
+
+    #[test]
+    fn test_cycles_are_broken() {
+        let mut model = model(&[("a", "b"), ("b", "c"), ("c", "a")]);
+        LayeredLayout::new()
+            .with_direction(RankDirection::LeftRight)
+            .layout(&mut model)
+            .unwrap();
+
+        let xs: Vec<f32> = ["a", "b", "c"]
+            .iter()
+            .map(|id| position(&model, id).0)
+            .collect();
+        assert_eq!(xs, vec![0.0, 150.0, 300.0]);
+    }
+
+    #[test]
+    fn test_crossing_minimization() {
+        // Drawn in insertion order, both a->d and b->c cross
+        let mut model = model(&[("a", "d"), ("b", "c")]);
+        let d = model.nodes.remove(1);
+        model.nodes.push(d);
+        let stats = LayeredLayout::new().layout(&mut model).unwrap();
+
+        assert_eq!(stats.convergence, ConvergenceReason::Converged);
+        assert_eq!(stats.energy.last(), Some(&0.0));
+        let (a, d) = (position(&model, "a").0, position(&model, "d").0);
+        let (b, c) = (position(&model, "b").0, position(&model, "c").0);
+        assert_eq!(a < b, d < c);
+    }
+
+    #[test]
+    fn test_pinned_nodes_keep_their_positions() {
+        let mut model = model(&[("web", "api"), ("api", "db")]);
+        model.find_node_mut("api").unwrap().pin(500.0, 1000.0);
+        LayeredLayout::new().layout(&mut model).unwrap();
+
+        assert_eq!(position(&model, "api"), (500.0, 1000.0));
+        assert_eq!(position(&model, "web").1, 850.0);
+        assert_eq!(position(&model, "db").1, 1150.0);
+
+        for id in ["web", "db"] {
+            model.find_node_mut(id).unwrap().pin(0.0, 0.0);
+        }
+        let stats = LayeredLayout::new().layout(&mut model).unwrap();
+        assert_eq!(stats.convergence, ConvergenceReason::AllNodesPinned);
+        assert_eq!(position(&model, "api"), (500.0, 1000.0));
+    }
+
+    #[test]
+    fn test_long_edges_count_towards_crossings() {
+        let dag = vec![(0, 1), (1, 2), (0, 2)];
+        let layers = assign_layers(3, &dag);
+        assert_eq!(layers, vec![0, 1, 2]);
+
+        let graph = LayeredGraph::new(&layers, &dag);
+        assert_eq!(graph.layer.len(), 4);
+        assert_eq!(graph.order[1].len(), 2);
+    }
+}
@@ -6,8 +6,16 @@
 //! ## Key Features
 //! - **Tensor-Native Storage**: Adjacency matrices as Burn tensors for 2-5x faster graph queries
 //! - **Force-Directed Layout**: Gradient descent optimization using Fruchterman-Reingold algorithm
-//! - **Anti-Pattern Detection**: Cycles, over-coupling, bottlenecks, isolated components
-//! - **C4 DSL Parser**: Structurizr-compatible syntax for architecture modeling
+//! - **Layered & Stress Layouts**: Sugiyama and stress majorization behind a common `LayoutAlgorithm` trait, with C4 containment
+//! - **Layout Metrics**: Crossings, overlap, edge length variance, angular resolution and stress
+//! - **Anti-Pattern Detection**: Dependency cycles (as strongly connected components), over-coupling, bottlenecks,
+//!   isolated components, layering violations, god components, shared databases and chatty interfaces,
+//!   each with a suggested remediation
+//! - **Architecture Rules**: Declarative fitness functions (forbidden/allowed dependencies, required attributes)
+//!   reported alongside anti-patterns
+//! - **C4 DSL Parser**: Structurizr DSL workspaces with nested elements, views and styles
+//! - **Structurizr JSON**: Lossless workspace import/export, including stored view layouts
+//! - **Diagram Export**: Graphviz DOT, self-contained SVG, and PlantUML/Mermaid C4 for Markdown docs
 //! - **WASM-Ready**: Run entirely in-browser for privacy-first, offline-capable diagramming
 //! - **GPU Acceleration**: Optional `wgpu` feature for large architecture models
 //!
@@ -41,10 +49,15 @@
 //! ```
 
 pub mod anti_patterns;
+pub mod layout;
+pub mod metrics;
 pub mod model;
 pub mod optimizer;
 pub mod parser;
+pub mod rules;
+pub mod structurizr;
 pub mod viz;
+pub mod workspace;
 
 #[cfg(feature = "telemetry")]
 pub mod telemetry;
@@ -58,10 +71,19 @@ mod integration_tests;
 pub use anti_patterns::{AntiPattern, AntiPatternConfig, detect_anti_patterns};
 #[cfg(feature = "telemetry")]
 pub use anti_patterns::detect_anti_patterns_with_telemetry;
-pub use model::{NodeType, OptaModel, OptaNode};
-pub use optimizer::{OptaOptimizer, OptimizationStats};
-pub use parser::parse_c4_dsl;
-pub use viz::{to_dot, to_dot_with_positions};
+pub use layout::{
+    BoundingBox, LayeredLayout, LayoutAlgorithm, NestedLayout, NestingOptions, StressLayout,
+};
+pub use metrics::{LayoutMetrics, LayoutMetricsConfig, compute_layout_metrics};
+pub use model::{C4Level, NodeType, OptaModel, OptaNode};
+pub use optimizer::{ConvergenceReason, OptaOptimizer, OptimizationStats};
+pub use parser::{ParseError, parse_c4_dsl, parse_workspace, parse_workspace_file};
+pub use rules::{ArchitectureRule, Constraint, Requirement, RuleSet, Selector, check_rules};
+pub use structurizr::{from_structurizr_json, to_structurizr_json};
+pub use viz::{
+    SvgOptions, to_dot, to_dot_with_positions, to_mermaid, to_plantuml, to_svg, to_svg_with_options,
+};
+pub use workspace::Workspace;
 
 pub type Result<T> = std::result::Result<T, anyhow::Error>;
 
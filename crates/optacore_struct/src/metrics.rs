@@ -0,0 +1,418 @@
+//! Layout quality metrics
+//!
+//! Scores a positioned [`OptaModel`] so layout algorithms and parameter
+//! settings can be compared objectively. Every metric except node overlap is
+//! independent of the layout's scale, so layouts produced in different units
+//! (force-directed vs layered) compare directly.
+
+use crate::{OptaModel, Result};
+use burn::tensor::backend::Backend;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::f32::consts::PI;
+
+/// Node size used when testing nodes for overlap
+#[derive(Debug, Clone, Copy)]
+pub struct LayoutMetricsConfig {
+    pub node_width: f32,
+    pub node_height: f32,
+}
+
+impl Default for LayoutMetricsConfig {
+    fn default() -> Self {
+        Self {
+            node_width: 160.0,
+            node_height: 100.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LayoutMetrics {
+    /// Pairs of edges whose straight-line segments cross (shared endpoints don't count)
+    pub edge_crossings: usize,
+    /// Pairs of nodes whose boxes overlap
+    pub node_overlaps: usize,
+    /// Total overlapping area of those pairs
+    pub overlap_area: f32,
+    pub mean_edge_length: f32,
+    /// Edge length variance divided by the squared mean, so `0.0` means uniform lengths
+    pub edge_length_variance: f32,
+    /// Smallest angle (radians) between two edges at the same node; `2π` when no node has two
+    pub angular_resolution: f32,
+    /// Mean ratio of each node's smallest edge angle to the ideal `2π / degree`, in `[0, 1]`
+    pub angular_resolution_ratio: f32,
+    /// Normalized stress against shortest-path distances, after optimal uniform scaling
+    pub stress: f32,
+}
+
+impl LayoutMetrics {
+    /// Single score in `[0, 1]` (higher is better), the mean of one term per metric
+    pub fn quality_score(&self, edge_count: usize, node_count: usize) -> f32 {
+        let pairs = (node_count * node_count.saturating_sub(1) / 2).max(1) as f32;
+        let terms = [
+            1.0 / (1.0 + self.edge_crossings as f32 / edge_count.max(1) as f32),
+            1.0 - self.node_overlaps as f32 / pairs,
+            1.0 / (1.0 + self.edge_length_variance),
+            self.angular_resolution_ratio,
+            1.0 / (1.0 + self.stress),
+        ];
+        terms.iter().sum::<f32>() / terms.len() as f32
+    }
+}
+
+/// Compute quality metrics for the current node positions of `model`
+///
+/// Returns error if any node has no position.
+pub fn compute_layout_metrics<B: Backend>(
+    model: &OptaModel<B>,
+    config: &LayoutMetricsConfig,
+) -> Result<LayoutMetrics> {
+    let positions = model
+        .nodes
+        .iter()
+        .map(|node| {
+            node.get_position()
+                .ok_or_else(|| anyhow::anyhow!("Node {} has no position", node.id))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let index: HashMap<&str, usize> = model
+        .nodes
+        .iter()
+        .enumerate()
+        .map(|(idx, node)| (node.id.as_str(), idx))
+        .collect();
+    let mut seen = HashSet::new();
+    let edges: Vec<(usize, usize)> = model
+        .edges
+        .iter()
+        .filter_map(|e| Some((*index.get(e.from.as_str())?, *index.get(e.to.as_str())?)))
+        .filter(|&(from, to)| from != to && seen.insert((from.min(to), from.max(to))))
+        .collect();
+
+    let (node_overlaps, overlap_area) = node_overlap(&positions, config);
+    let (mean_edge_length, edge_length_variance) = edge_lengths(&positions, &edges);
+    let (angular_resolution, angular_resolution_ratio) = angular_resolution(&positions, &edges);
+
+    Ok(LayoutMetrics {
+        edge_crossings: edge_crossings(&positions, &edges),
+        node_overlaps,
+        overlap_area,
+        mean_edge_length,
+        edge_length_variance,
+        angular_resolution,
+        angular_resolution_ratio,
+        stress: stress(&positions, &edges),
+    })
+}
+
+/// Log the metrics as a `layout.quality` telemetry event
+#[cfg(feature = "telemetry")]
+pub fn log_layout_metrics<B: Backend>(
+    logger: &crate::telemetry::TelemetryLogger,
+    model: &OptaModel<B>,
+    metrics: &LayoutMetrics,
+) -> Result<()> {
+    use std::hash::{DefaultHasher, Hash, Hasher};
+
+    let mut hasher = DefaultHasher::new();
+    for node in &model.nodes {
+        node.id.hash(&mut hasher);
+    }
+    for edge in &model.edges {
+        (&edge.from, &edge.to).hash(&mut hasher);
+    }
+
+    logger.log_layout_quality(
+        format!("{:016x}", hasher.finish()),
+        metrics.edge_crossings,
+        metrics.overlap_area,
+        metrics.quality_score(model.edge_count(), model.node_count()),
+    )
+}
+
+fn edge_crossings(positions: &[(f32, f32)], edges: &[(usize, usize)]) -> usize {
+    let mut crossings = 0;
+    for (i, &(a, b)) in edges.iter().enumerate() {
+        for &(c, d) in &edges[i + 1..] {
+            if a == c || a == d || b == c || b == d {
+                continue;
+            }
+            if segments_intersect(positions[a], positions[b], positions[c], positions[d]) {
+                crossings += 1;
+            }
+        }
+    }
+    crossings
+}
+
+fn segments_intersect(p1: (f32, f32), p2: (f32, f32), p3: (f32, f32), p4: (f32, f32)) -> bool {
+    fn orientation(a: (f32, f32), b: (f32, f32), c: (f32, f32)) -> f32 {
+        (b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0)
+    }
+
+    let d1 = orientation(p3, p4, p1);
+    let d2 = orientation(p3, p4, p2);
+    let d3 = orientation(p1, p2, p3);
+    let d4 = orientation(p1, p2, p4);
+    // Strict test: touching or collinear segments aren't counted as crossings
+    d1 * d2 < 0.0 && d3 * d4 < 0.0
+}
+
+fn node_overlap(positions: &[(f32, f32)], config: &LayoutMetricsConfig) -> (usize, f32) {
+    let mut count = 0;
+    let mut area = 0.0;
+    for (i, a) in positions.iter().enumerate() {
+        for b in &positions[i + 1..] {
+            let overlap_x = config.node_width - (a.0 - b.0).abs();
+            let overlap_y = config.node_height - (a.1 - b.1).abs();
+            if overlap_x > 0.0 && overlap_y > 0.0 {
+                count += 1;
+                area += overlap_x * overlap_y;
+            }
+        }
+    }
+    (count, area)
+}
+
+fn distance(a: (f32, f32), b: (f32, f32)) -> f32 {
+    ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt()
+}
+
+fn edge_lengths(positions: &[(f32, f32)], edges: &[(usize, usize)]) -> (f32, f32) {
+    if edges.is_empty() {
+        return (0.0, 0.0);
+    }
+    let lengths: Vec<f32> = edges
+        .iter()
+        .map(|&(a, b)| distance(positions[a], positions[b]))
+        .collect();
+    let mean = lengths.iter().sum::<f32>() / lengths.len() as f32;
+    if mean <= f32::EPSILON {
+        return (0.0, 0.0);
+    }
+    let variance = lengths.iter().map(|l| (l - mean).powi(2)).sum::<f32>() / lengths.len() as f32;
+    (mean, variance / (mean * mean))
+}
+
+fn angular_resolution(positions: &[(f32, f32)], edges: &[(usize, usize)]) -> (f32, f32) {
+    let mut neighbours = vec![Vec::new(); positions.len()];
+    for &(a, b) in edges {
+        neighbours[a].push(b);
+        neighbours[b].push(a);
+    }
+
+    let mut minimum = 2.0 * PI;
+    let mut ratios = Vec::new();
+    for (node, adjacent) in neighbours.iter().enumerate() {
+        if adjacent.len() < 2 {
+            continue;
+        }
+        let (x, y) = positions[node];
+        let mut angles: Vec<f32> = adjacent
+            .iter()
+            .map(|&other| (positions[other].1 - y).atan2(positions[other].0 - x))
+            .collect();
+        angles.sort_by(f32::total_cmp);
+
+        let wrap = angles[0] + 2.0 * PI - angles[angles.len() - 1];
+        let smallest = angles
+            .windows(2)
+            .map(|pair| pair[1] - pair[0])
+            .fold(wrap, f32::min);
+        minimum = minimum.min(smallest);
+        ratios.push(smallest / (2.0 * PI / adjacent.len() as f32));
+    }
+
+    let ratio = if ratios.is_empty() {
+        1.0
+    } else {
+        ratios.iter().sum::<f32>() / ratios.len() as f32
+    };
+    (minimum, ratio)
+}
+
+/// Mean of `(s·|x_i - x_j| / d_ij - 1)²` over connected pairs, where `d_ij` is
+/// the hop distance and `s` the scale that minimizes the sum
+fn stress(positions: &[(f32, f32)], edges: &[(usize, usize)]) -> f32 {
+    let n = positions.len();
+    let mut neighbours = vec![Vec::new(); n];
+    for &(a, b) in edges {
+        neighbours[a].push(b);
+        neighbours[b].push(a);
+    }
+
+    let mut pairs = Vec::new();
+    for source in 0..n {
+        let mut hops = vec![usize::MAX; n];
+        hops[source] = 0;
+        let mut queue = VecDeque::from([source]);
+        while let Some(node) = queue.pop_front() {
+            for &next in &neighbours[node] {
+                if hops[next] == usize::MAX {
+                    hops[next] = hops[node] + 1;
+                    queue.push_back(next);
+                }
+            }
+        }
+        for target in source + 1..n {
+            if hops[target] != usize::MAX {
+                let ratio = distance(positions[source], positions[target]) / hops[target] as f32;
+                pairs.push(ratio);
+            }
+        }
+    }
+    if pairs.is_empty() {
+        return 0.0;
+    }
+
+    let squares: f32 = pairs.iter().map(|r| r * r).sum();
+    if squares <= f32::EPSILON {
+        return 1.0;
+    }
+    let scale = pairs.iter().sum::<f32>() / squares;
+    pairs.iter().map(|r| (scale * r - 1.0).powi(2)).sum::<f32>() / pairs.len() as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::{LayeredLayout, LayoutAlgorithm};
+    use crate::model::OptaEdge;
+    use crate::{NodeType, OptaNode};
+    use burn::backend::ndarray::NdArray;
+
+    type TestBackend = NdArray<f32>;
+
+    fn positioned_model(
+        nodes: &[(&str, f32, f32)],
+        edges: &[(&str, &str)],
+    ) -> OptaModel<TestBackend> {
+        let mut model = OptaModel::new();
+        for &(id, x, y) in nodes {
+            let mut node = OptaNode::new(id.to_string(), id.to_uppercase(), NodeType::Container);
+            node.set_position(x, y);
+            model.add_node(node);
+        }
+        for (from, to) in edges {
+            model.add_edge(OptaEdge::new(from.to_string(), to.to_string()));
+        }
+        model
+    }
+
+    #[test]
+    fn test_crossings_and_overlap() {
+        // A square with both diagonals: one crossing, and a and e overlap
+        let model = positioned_model(
+            &[
+                ("a", 0.0, 0.0),
+                ("b", 400.0, 0.0),
+                ("c", 400.0, 400.0),
+                ("d", 0.0, 400.0),
+                ("e", 100.0, 50.0),
+            ],
+            &[("a", "c"), ("b", "d"), ("a", "b"), ("b", "c")],
+        );
+        let metrics = compute_layout_metrics(&model, &LayoutMetricsConfig::default()).unwrap();
+
+        assert_eq!(metrics.edge_crossings, 1);
+        assert_eq!(metrics.node_overlaps, 1);
+        assert_eq!(metrics.overlap_area, 60.0 * 50.0);
+    }
+
+    #[test]
+    fn test_metrics_serialize_in_camel_case() {
+        let model = positioned_model(&[("a", 0.0, 0.0), ("b", 400.0, 0.0)], &[("a", "b")]);
+        let metrics = compute_layout_metrics(&model, &LayoutMetricsConfig::default()).unwrap();
+
+        let json = serde_json::to_value(&metrics).unwrap();
+        assert_eq!(json["edgeCrossings"], 0);
+        assert!(json.get("meanEdgeLength").is_some());
+        assert!(json.get("angularResolutionRatio").is_some());
+        assert!(json.get("edge_crossings").is_none());
+    }
+
+    #[test]
+    fn test_uniform_star_is_ideal() {
+        let model = positioned_model(
+            &[
+                ("hub", 0.0, 0.0),
+                ("n", 0.0, -300.0),
+                ("e", 300.0, 0.0),
+                ("s", 0.0, 300.0),
+                ("w", -300.0, 0.0),
+            ],
+            &[("hub", "n"), ("hub", "e"), ("hub", "s"), ("hub", "w")],
+        );
+        let metrics = compute_layout_metrics(&model, &LayoutMetricsConfig::default()).unwrap();
+
+        assert_eq!(metrics.edge_crossings, 0);
+        assert_eq!(metrics.node_overlaps, 0);
+        assert_eq!(metrics.mean_edge_length, 300.0);
+        assert_eq!(metrics.edge_length_variance, 0.0);
+        assert!((metrics.angular_resolution - PI / 2.0).abs() < 1e-5);
+        assert!((metrics.angular_resolution_ratio - 1.0).abs() < 1e-5);
+        assert!(metrics.quality_score(4, 5) > 0.9);
+    }
+
+    #[test]
+    fn test_stress_is_scale_invariant() {
+        let nodes = [("a", 0.0, 0.0), ("b", 1.0, 0.0), ("c", 2.0, 0.0)];
+        let straight = positioned_model(&nodes, &[("a", "b"), ("b", "c")]);
+        let scaled = positioned_model(
+            &nodes.map(|(id, x, y)| (id, x * 250.0, y * 250.0)),
+            &[("a", "b"), ("b", "c")],
+        );
+        let folded = positioned_model(
+            &[("a", 0.0, 0.0), ("b", 1.0, 0.0), ("c", 0.0, 0.1)],
+            &[("a", "b"), ("b", "c")],
+        );
+
+        let config = LayoutMetricsConfig::default();
+        let straight = compute_layout_metrics(&straight, &config).unwrap().stress;
+        let scaled = compute_layout_metrics(&scaled, &config).unwrap().stress;
+        let folded = compute_layout_metrics(&folded, &config).unwrap().stress;
+        assert!(straight < 1e-6);
+        assert!(scaled < 1e-6);
+        assert!(folded > 0.1);
+    }
+
+    #[test]
+    fn test_layered_layout_of_tree_has_no_crossings() {
+        let mut model = positioned_model(&[], &[]);
+        for id in ["gateway", "users", "orders", "db", "cache", "queue"] {
+            model.add_node(OptaNode::new(
+                id.to_string(),
+                id.to_string(),
+                NodeType::Container,
+            ));
+        }
+        for (from, to) in [
+            ("gateway", "users"),
+            ("gateway", "orders"),
+            ("users", "db"),
+            ("orders", "cache"),
+            ("orders", "queue"),
+        ] {
+            model.add_edge(OptaEdge::new(from.to_string(), to.to_string()));
+        }
+        LayeredLayout::new().layout(&mut model).unwrap();
+
+        let metrics = compute_layout_metrics(&model, &LayoutMetricsConfig::default()).unwrap();
+        assert_eq!(metrics.edge_crossings, 0);
+        assert_eq!(metrics.node_overlaps, 0);
+    }
+
+    #[test]
+    fn test_unpositioned_node_is_an_error() {
+        let mut model = positioned_model(&[("a", 0.0, 0.0)], &[]);
+        model.add_node(OptaNode::new(
+            "b".to_string(),
+            "B".to_string(),
+            NodeType::Container,
+        ));
+        assert!(compute_layout_metrics(&model, &LayoutMetricsConfig::default()).is_err());
+    }
+}
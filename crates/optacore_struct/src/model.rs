@@ -1,5 +1,6 @@
 use burn::tensor::{Tensor, backend::Backend};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum NodeType {
@@ -9,6 +10,28 @@ pub enum NodeType {
     Person,
 }
 
+/// C4 abstraction level that relationships are rolled up to
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum C4Level {
+    /// Level 1: people and software systems
+    SystemContext,
+    /// Level 2: containers inside software systems
+    Container,
+    /// Level 3: components inside containers
+    Component,
+}
+
+impl C4Level {
+    /// Whether elements of `node_type` are shown at this level
+    pub fn includes(&self, node_type: &NodeType) -> bool {
+        match node_type {
+            NodeType::Person | NodeType::System => true,
+            NodeType::Container => matches!(self, C4Level::Container | C4Level::Component),
+            NodeType::Component => matches!(self, C4Level::Component),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OptaNode<B: Backend> {
     pub id: String,
@@ -16,6 +39,16 @@ pub struct OptaNode<B: Backend> {
     pub node_type: NodeType,
     pub description: Option<String>,
     pub technology: Option<String>,
+    /// Identifier of the containing element (System for a Container, Container for a Component)
+    #[serde(default)]
+    pub parent: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub properties: BTreeMap<String, String>,
+    /// Keep the node at its current position during layout optimization
+    #[serde(default)]
+    pub pinned: bool,
     #[serde(skip)]
     pub position: Option<Tensor<B, 1>>,
 }
@@ -28,6 +61,10 @@ impl<B: Backend> OptaNode<B> {
             node_type,
             description: None,
             technology: None,
+            parent: None,
+            tags: Vec::new(),
+            properties: BTreeMap::new(),
+            pinned: false,
             position: None,
         }
     }
@@ -42,6 +79,26 @@ impl<B: Backend> OptaNode<B> {
         self
     }
 
+    pub fn with_parent(mut self, parent: String) -> Self {
+        self.parent = Some(parent);
+        self
+    }
+
+    pub fn with_tags(mut self, tags: Vec<String>) -> Self {
+        self.tags = tags;
+        self
+    }
+
+    /// Fix the node at `(x, y)` so the optimizer leaves it in place
+    pub fn pin(&mut self, x: f32, y: f32) {
+        self.set_position(x, y);
+        self.pinned = true;
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+
     pub fn set_position(&mut self, x: f32, y: f32) {
         let device = B::Device::default();
         self.position = Some(Tensor::<B, 1>::from_floats([x, y], &device));
@@ -58,18 +115,31 @@ impl<B: Backend> OptaNode<B> {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OptaEdge {
+    /// Stable relationship identifier, when the source format provides one
+    #[serde(default)]
+    pub id: Option<String>,
     pub from: String,
     pub to: String,
     pub label: Option<String>,
+    #[serde(default)]
+    pub technology: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub properties: BTreeMap<String, String>,
     pub weight: f32,
 }
 
 impl OptaEdge {
     pub fn new(from: String, to: String) -> Self {
         Self {
+            id: None,
             from,
             to,
             label: None,
+            technology: None,
+            tags: Vec::new(),
+            properties: BTreeMap::new(),
             weight: 1.0,
         }
     }
@@ -79,6 +149,16 @@ impl OptaEdge {
         self
     }
 
+    pub fn with_technology(mut self, technology: String) -> Self {
+        self.technology = Some(technology);
+        self
+    }
+
+    pub fn with_tags(mut self, tags: Vec<String>) -> Self {
+        self.tags = tags;
+        self
+    }
+
     pub fn with_weight(mut self, weight: f32) -> Self {
         self.weight = weight;
         self
@@ -121,7 +201,7 @@ impl<B: Backend> OptaModel<B> {
         let device = B::Device::default();
         let mut adjacency_data = vec![vec![0.0f32; node_count]; node_count];
 
-        let node_index_map: std::collections::HashMap<&str, usize> = self
+        let node_index_map: HashMap<&str, usize> = self
             .nodes
             .iter()
             .enumerate()
@@ -160,6 +240,251 @@ impl<B: Backend> OptaModel<B> {
         self.nodes.iter_mut().find(|n| n.id == id)
     }
 
+    /// Move `child_id` under `parent_id` (or to the top level with `None`)
+    ///
+    /// Enforces C4 containment: containers live in software systems and
+    /// components in containers; people and systems are always top level.
+    pub fn set_parent(&mut self, child_id: &str, parent_id: Option<&str>) -> crate::Result<()> {
+        let child = self
+            .find_node(child_id)
+            .ok_or_else(|| anyhow::anyhow!("Unknown node: {}", child_id))?;
+
+        if let Some(parent_id) = parent_id {
+            let parent = self
+                .find_node(parent_id)
+                .ok_or_else(|| anyhow::anyhow!("Unknown parent node: {}", parent_id))?;
+
+            let allowed = match child.node_type {
+                NodeType::Container => parent.node_type == NodeType::System,
+                NodeType::Component => parent.node_type == NodeType::Container,
+                NodeType::System | NodeType::Person => false,
+            };
+            if !allowed {
+                anyhow::bail!(
+                    "{:?} {} cannot be placed inside {:?} {}",
+                    child.node_type,
+                    child_id,
+                    parent.node_type,
+                    parent_id
+                );
+            }
+            if parent_id == child_id || self.is_ancestor(child_id, parent_id) {
+                anyhow::bail!(
+                    "Placing {} inside {} would create a cycle",
+                    child_id,
+                    parent_id
+                );
+            }
+        }
+
+        if let Some(child) = self.find_node_mut(child_id) {
+            child.parent = parent_id.map(str::to_string);
+        }
+        Ok(())
+    }
+
+    pub fn parent(&self, id: &str) -> Option<&OptaNode<B>> {
+        let parent_id = self.find_node(id)?.parent.as_deref()?;
+        self.find_node(parent_id)
+    }
+
+    /// Direct children of `id`, in model order
+    pub fn children(&self, id: &str) -> Vec<&OptaNode<B>> {
+        self.nodes
+            .iter()
+            .filter(|n| n.parent.as_deref() == Some(id))
+            .collect()
+    }
+
+    /// Top-level elements (those without a parent)
+    pub fn roots(&self) -> Vec<&OptaNode<B>> {
+        self.nodes.iter().filter(|n| n.parent.is_none()).collect()
+    }
+
+    /// Ancestors of `id`, nearest first
+    pub fn ancestors(&self, id: &str) -> Vec<&OptaNode<B>> {
+        let mut ancestors = Vec::new();
+        let mut visited = HashSet::new();
+        let mut current = self.parent(id);
+
+        while let Some(node) = current {
+            if !visited.insert(node.id.as_str()) {
+                break;
+            }
+            ancestors.push(node);
+            current = self.parent(&node.id);
+        }
+
+        ancestors
+    }
+
+    /// `id` followed by all of its descendants, depth-first
+    pub fn subtree(&self, id: &str) -> Vec<&OptaNode<B>> {
+        let Some(root) = self.find_node(id) else {
+            return Vec::new();
+        };
+
+        let mut subtree = vec![root];
+        let mut visited = HashSet::from([root.id.as_str()]);
+        let mut stack: Vec<&OptaNode<B>> = self.children(id).into_iter().rev().collect();
+
+        while let Some(node) = stack.pop() {
+            if !visited.insert(node.id.as_str()) {
+                continue;
+            }
+            subtree.push(node);
+            stack.extend(self.children(&node.id).into_iter().rev());
+        }
+
+        subtree
+    }
+
+    pub fn is_ancestor(&self, ancestor_id: &str, id: &str) -> bool {
+        self.ancestors(id).iter().any(|n| n.id == ancestor_id)
+    }
+
+    /// The element representing `id` at `level`: `id` itself if it is shown at
+    /// that level, otherwise its nearest ancestor that is
+    pub fn ancestor_at_level(&self, id: &str, level: C4Level) -> Option<&OptaNode<B>> {
+        let node = self.find_node(id)?;
+        if level.includes(&node.node_type) {
+            return Some(node);
+        }
+        self.ancestors(id)
+            .into_iter()
+            .find(|n| level.includes(&n.node_type))
+    }
+
+    /// Relationships implied at `level` by rolling each edge's endpoints up the
+    /// containment hierarchy
+    ///
+    /// A component-to-component edge across two containers becomes a
+    /// container-to-container edge at `C4Level::Container`, and a
+    /// system-to-system edge at `C4Level::SystemContext`. Edges that collapse
+    /// into a single element or connect an element to its own ancestor are
+    /// dropped, and parallel edges are merged with their weights summed.
+    pub fn implied_edges(&self, level: C4Level) -> Vec<OptaEdge> {
+        self.project_edges(|id| self.ancestor_at_level(id, level).map(|n| n.id.as_str()))
+    }
+
+    /// A copy of the model as seen at `level`: only elements shown at that
+    /// level, connected by the implied relationships
+    pub fn view_at_level(&self, level: C4Level) -> OptaModel<B> {
+        let mut view = OptaModel::new();
+        for node in &self.nodes {
+            if level.includes(&node.node_type) {
+                view.add_node(node.clone());
+            }
+        }
+        for edge in self.implied_edges(level) {
+            view.add_edge(edge);
+        }
+        view
+    }
+
+    /// The C4 diagram scoped to one element: a container view for a software
+    /// system, or a component view for a container
+    ///
+    /// Contains the scope element, its children, and the external elements
+    /// they interact with, rolled up to the scope's own level.
+    pub fn scoped_view(&self, scope_id: &str) -> crate::Result<OptaModel<B>> {
+        let scope = self
+            .find_node(scope_id)
+            .ok_or_else(|| anyhow::anyhow!("Unknown node: {}", scope_id))?;
+        if !matches!(scope.node_type, NodeType::System | NodeType::Container) {
+            anyhow::bail!(
+                "Scoped views require a software system or container, got {:?} {}",
+                scope.node_type,
+                scope_id
+            );
+        }
+
+        // External elements are shown at the scope's own level: sibling
+        // containers stay visible in a component view
+        let outer_level = match scope.node_type {
+            NodeType::System => C4Level::SystemContext,
+            _ => C4Level::Container,
+        };
+        let inside: HashSet<&str> = self
+            .subtree(scope_id)
+            .into_iter()
+            .map(|n| n.id.as_str())
+            .collect();
+
+        let map = |id: &str| -> Option<&str> {
+            if inside.contains(id) {
+                if id == scope_id {
+                    return Some(scope_id);
+                }
+                // Child of the scope on this element's ancestor chain
+                std::iter::once(self.find_node(id)?)
+                    .chain(self.ancestors(id))
+                    .find(|n| n.parent.as_deref() == Some(scope_id))
+                    .map(|n| n.id.as_str())
+            } else {
+                self.ancestor_at_level(id, outer_level)
+                    .map(|n| n.id.as_str())
+            }
+        };
+
+        let edges: Vec<OptaEdge> = self
+            .project_edges(map)
+            .into_iter()
+            .filter(|e| inside.contains(e.from.as_str()) || inside.contains(e.to.as_str()))
+            .collect();
+
+        let mut included: HashSet<&str> = HashSet::from([scope_id]);
+        included.extend(self.children(scope_id).iter().map(|n| n.id.as_str()));
+        included.extend(edges.iter().flat_map(|e| [e.from.as_str(), e.to.as_str()]));
+
+        let mut view = OptaModel::new();
+        for node in &self.nodes {
+            if included.contains(node.id.as_str()) {
+                view.add_node(node.clone());
+            }
+        }
+        for edge in edges {
+            view.add_edge(edge);
+        }
+        Ok(view)
+    }
+
+    fn project_edges<'a>(&'a self, map: impl Fn(&str) -> Option<&'a str>) -> Vec<OptaEdge> {
+        let mut projected: Vec<OptaEdge> = Vec::new();
+        let mut index: HashMap<(&str, &str), usize> = HashMap::new();
+
+        for edge in &self.edges {
+            let (Some(from), Some(to)) = (map(&edge.from), map(&edge.to)) else {
+                continue;
+            };
+            if from == to || self.is_ancestor(from, to) || self.is_ancestor(to, from) {
+                continue;
+            }
+
+            match index.get(&(from, to)) {
+                Some(&existing) => {
+                    let merged = &mut projected[existing];
+                    merged.weight += edge.weight;
+                    for tag in &edge.tags {
+                        if !merged.tags.contains(tag) {
+                            merged.tags.push(tag.clone());
+                        }
+                    }
+                }
+                None => {
+                    index.insert((from, to), projected.len());
+                    let mut implied = edge.clone();
+                    implied.id = None;
+                    implied.from = from.to_string();
+                    implied.to = to.to_string();
+                    projected.push(implied);
+                }
+            }
+        }
+
+        projected
+    }
+
     pub fn detect_anti_patterns_default(&self) -> crate::Result<Vec<crate::anti_patterns::AntiPattern>> {
         crate::anti_patterns::detect_anti_patterns(self, &crate::anti_patterns::AntiPatternConfig::default())
     }
@@ -224,6 +549,136 @@ mod tests {
         assert!(model.adjacency_matrix.is_some());
     }
 
+    fn hierarchical_model() -> OptaModel<TestBackend> {
+        let mut model = OptaModel::<TestBackend>::new();
+        for (id, node_type, parent) in [
+            ("user", NodeType::Person, None),
+            ("shop", NodeType::System, None),
+            ("payments", NodeType::System, None),
+            ("web", NodeType::Container, Some("shop")),
+            ("api", NodeType::Container, Some("shop")),
+            ("db", NodeType::Container, Some("shop")),
+            ("orders", NodeType::Component, Some("api")),
+            ("billing", NodeType::Component, Some("api")),
+        ] {
+            let mut node = OptaNode::new(id.to_string(), id.to_string(), node_type);
+            node.parent = parent.map(str::to_string);
+            model.add_node(node);
+        }
+        for (from, to) in [
+            ("user", "web"),
+            ("web", "orders"),
+            ("orders", "db"),
+            ("billing", "db"),
+            ("billing", "payments"),
+            ("orders", "billing"),
+        ] {
+            model.add_edge(OptaEdge::new(from.to_string(), to.to_string()));
+        }
+        model
+    }
+
+    fn edge_pairs(edges: &[OptaEdge]) -> Vec<(&str, &str)> {
+        edges
+            .iter()
+            .map(|e| (e.from.as_str(), e.to.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_containment_queries() {
+        let model = hierarchical_model();
+
+        let children: Vec<_> = model.children("shop").iter().map(|n| &n.id).collect();
+        assert_eq!(children, ["web", "api", "db"]);
+
+        let ancestors: Vec<_> = model.ancestors("orders").iter().map(|n| &n.id).collect();
+        assert_eq!(ancestors, ["api", "shop"]);
+        assert!(model.is_ancestor("shop", "billing"));
+        assert!(!model.is_ancestor("api", "db"));
+
+        let subtree: Vec<_> = model.subtree("shop").iter().map(|n| &n.id).collect();
+        assert_eq!(subtree, ["shop", "web", "api", "orders", "billing", "db"]);
+
+        let roots: Vec<_> = model.roots().iter().map(|n| &n.id).collect();
+        assert_eq!(roots, ["user", "shop", "payments"]);
+    }
+
+    #[test]
+    fn test_set_parent_enforces_c4_rules() {
+        let mut model = hierarchical_model();
+
+        assert!(model.set_parent("orders", Some("web")).is_ok());
+        assert_eq!(model.parent("orders").unwrap().id, "web");
+
+        assert!(model.set_parent("orders", Some("shop")).is_err());
+        assert!(model.set_parent("shop", Some("payments")).is_err());
+        assert!(model.set_parent("web", Some("missing")).is_err());
+
+        assert!(model.set_parent("web", None).is_ok());
+        assert!(model.parent("web").is_none());
+    }
+
+    #[test]
+    fn test_implied_edges_roll_up() {
+        let model = hierarchical_model();
+
+        let container_edges = model.implied_edges(C4Level::Container);
+        assert_eq!(
+            edge_pairs(&container_edges),
+            [
+                ("user", "web"),
+                ("web", "api"),
+                ("api", "db"),
+                ("api", "payments")
+            ]
+        );
+        // orders -> db and billing -> db merge into one api -> db edge
+        assert_eq!(container_edges[2].weight, 2.0);
+
+        let context_edges = model.implied_edges(C4Level::SystemContext);
+        assert_eq!(
+            edge_pairs(&context_edges),
+            [("user", "shop"), ("shop", "payments")]
+        );
+
+        let context = model.view_at_level(C4Level::SystemContext);
+        assert_eq!(context.node_count(), 3);
+        assert_eq!(context.edge_count(), 2);
+    }
+
+    #[test]
+    fn test_scoped_views() {
+        let model = hierarchical_model();
+
+        let containers = model.scoped_view("shop").unwrap();
+        let ids: Vec<_> = containers.nodes.iter().map(|n| n.id.as_str()).collect();
+        assert_eq!(ids, ["user", "shop", "payments", "web", "api", "db"]);
+        assert_eq!(
+            edge_pairs(&containers.edges),
+            [
+                ("user", "web"),
+                ("web", "api"),
+                ("api", "db"),
+                ("api", "payments")
+            ]
+        );
+
+        let components = model.scoped_view("api").unwrap();
+        assert_eq!(
+            edge_pairs(&components.edges),
+            [
+                ("web", "orders"),
+                ("orders", "db"),
+                ("billing", "db"),
+                ("billing", "payments"),
+                ("orders", "billing")
+            ]
+        );
+
+        assert!(model.scoped_view("user").is_err());
+    }
+
     #[test]
     fn test_node_position() {
         let mut node = OptaNode::<TestBackend>::new(
@@ -1,15 +1,45 @@
 use crate::{OptaModel, Result};
-use burn::tensor::{Tensor, backend::Backend};
-use rand::Rng;
+use burn::tensor::{Bool, Tensor, backend::Backend};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Reading the layout energy back from the device stalls the pipeline, so
+/// each iteration's energy stays on the device and is read back in batches of
+/// this many iterations, and on the last one
+const ENERGY_READBACK_INTERVAL: usize = 10;
+
+/// Mean node step, in layout units, below which the layout counts as settled.
+/// At the default area that is about 0.03% of the canvas side.
+const DEFAULT_TOLERANCE: f32 = 0.01;
+
+/// Why a layout run stopped
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+pub enum ConvergenceReason {
+    /// The configured iteration budget was used up
+    MaxIterations,
+    /// The mean node step fell below the optimizer's tolerance
+    Converged,
+    /// Every node is pinned, so there was nothing to move
+    AllNodesPinned,
+}
 
 /// Statistics from layout optimization run
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct OptimizationStats {
+    /// Iterations actually run, which is fewer than configured on early convergence
     pub iterations: usize,
-    pub final_temperature: f32,
+    /// Temperature at the end of a force-directed run, `None` for layouts
+    /// without one
+    pub final_temperature: Option<f32>,
     pub duration_ms: u64,
     pub node_count: usize,
     pub edge_count: usize,
+    /// Sum of squared node steps, one per iteration; trends to zero as the
+    /// layout settles
+    pub energy: Vec<f32>,
+    pub convergence: ConvergenceReason,
 }
 
 /// Force-directed layout optimizer using Fruchterman-Reingold algorithm
@@ -19,11 +49,16 @@ pub struct OptimizationStats {
 /// - Repulsive forces between all node pairs
 ///
 /// Uses gradient descent with Burn tensors for performance optimization.
+/// Nodes with [`OptaNode::pinned`](crate::OptaNode::pinned) set keep their
+/// current position.
 pub struct OptaOptimizer<B: Backend> {
     iterations: usize,
     learning_rate: f32,
     area: f32,
     k: f32,
+    seed: Option<u64>,
+    warm_start: bool,
+    tolerance: f32,
     _phantom: std::marker::PhantomData<B>,
 }
 
@@ -34,6 +69,9 @@ impl<B: Backend> OptaOptimizer<B> {
             learning_rate,
             area: 1000.0,
             k: 50.0,
+            seed: None,
+            warm_start: false,
+            tolerance: DEFAULT_TOLERANCE,
             _phantom: std::marker::PhantomData,
         }
     }
@@ -44,10 +82,38 @@ impl<B: Backend> OptaOptimizer<B> {
         self
     }
 
+    /// Seed the random initial placement so repeated runs give the same layout
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Start from the nodes' existing positions instead of a random placement
+    ///
+    /// Nodes without a position are placed next to their already positioned
+    /// neighbours, so adding one element doesn't reshuffle the whole diagram.
+    pub fn with_warm_start(mut self, warm_start: bool) -> Self {
+        self.warm_start = warm_start;
+        self
+    }
+
+    /// Stop early once the mean node step (in layout units) falls below `tolerance`
+    ///
+    /// The step is checked whenever the energy is read back from the device,
+    /// so a run stops at most [`ENERGY_READBACK_INTERVAL`] iterations after
+    /// settling. Defaults to [`DEFAULT_TOLERANCE`]; `0.0` always runs the full
+    /// iteration budget.
+    pub fn with_tolerance(mut self, tolerance: f32) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
     /// Optimize node positions using force-directed layout
     ///
-    /// Returns error if model has no nodes or adjacency matrix not built
-    pub fn optimize_layout(&self, model: &mut OptaModel<B>) -> Result<()> {
+    /// Returns error if model has no nodes, adjacency matrix not built, or a
+    /// pinned node has no position
+    pub fn optimize_layout(&self, model: &mut OptaModel<B>) -> Result<OptimizationStats> {
+        let start = Timer::start();
         let node_count = model.node_count();
         if node_count == 0 {
             return Err(anyhow::anyhow!("Cannot optimize empty model"));
@@ -59,23 +125,96 @@ impl<B: Backend> OptaOptimizer<B> {
             ));
         }
 
-        let device = B::Device::default();
-        let mut positions = self.initialize_positions(node_count, &device);
-
-        for iteration in 0..self.iterations {
-            let cooling = 1.0 - (iteration as f32 / self.iterations as f32);
-            let temperature = self.learning_rate * cooling;
-
-            let displacement =
-                self.compute_forces(&positions, model.adjacency_matrix.as_ref().unwrap());
+        if let Some(node) = model
+            .nodes
+            .iter()
+            .find(|n| n.pinned && n.position.is_none())
+        {
+            return Err(anyhow::anyhow!("Pinned node {} has no position", node.id));
+        }
 
-            positions = positions.clone() + displacement * temperature;
-            positions = self.clamp_positions(positions);
+        let adjacency = model.adjacency_matrix.as_ref().unwrap();
+        let device = adjacency.device();
+        let mut positions = self.initialize_positions(model, &device);
+
+        // Constant across iterations, so built once and kept on the device:
+        // a mask zeroing self-interaction and the adjacency with non-positive weights dropped
+        let off_diagonal = Tensor::<B, 2>::ones([node_count, node_count], &device)
+            - Tensor::<B, 2>::eye(node_count, &device);
+        let attraction = adjacency
+            .clone()
+            .mask_fill(adjacency.clone().lower_equal_elem(0.0), 0.0)
+            * off_diagonal.clone();
+
+        let pinned_flags: Vec<f32> = model
+            .nodes
+            .iter()
+            .map(|n| if n.pinned { 1.0 } else { 0.0 })
+            .collect();
+        let movable_count = pinned_flags.iter().filter(|&&p| p == 0.0).count();
+        let pinned: Tensor<B, 2, Bool> =
+            Tensor::<B, 1>::from_floats(pinned_flags.as_slice(), &device)
+                .reshape([node_count, 1])
+                .repeat_dim(1, 2)
+                .equal_elem(1.0);
+        let pinned_positions = positions.clone();
+
+        let mut energy = Vec::with_capacity(self.iterations);
+        let mut pending_energy = Vec::with_capacity(ENERGY_READBACK_INTERVAL);
+        let mut iterations = 0;
+        let mut temperature = self.learning_rate;
+        let mut convergence = ConvergenceReason::MaxIterations;
+
+        if movable_count == 0 {
+            convergence = ConvergenceReason::AllNodesPinned;
+        } else {
+            for iteration in 0..self.iterations {
+                let cooling = 1.0 - (iteration as f32 / self.iterations as f32);
+                temperature = self.learning_rate * cooling;
+
+                let displacement =
+                    self.compute_forces(&positions, &off_diagonal, &attraction) * temperature;
+                let displacement = self
+                    .clamp_displacement(displacement, cooling)
+                    .mask_fill(pinned.clone(), 0.0);
+
+                // Measured after clamping, so nodes pressed against the edge
+                // of the canvas count as settled
+                let next_positions = self
+                    .clamp_positions(positions.clone() + displacement)
+                    .mask_where(pinned.clone(), pinned_positions.clone());
+                pending_energy.push((next_positions.clone() - positions).powi_scalar(2).sum());
+                positions = next_positions;
+                iterations = iteration + 1;
+
+                if pending_energy.len() == ENERGY_READBACK_INTERVAL || iterations == self.iterations
+                {
+                    let batch: Vec<f32> = Tensor::cat(std::mem::take(&mut pending_energy), 0)
+                        .into_data()
+                        .to_vec()
+                        .expect("Failed to convert tensor to vector");
+                    energy.extend(batch);
+
+                    let step_energy = energy.last().copied().unwrap_or_default();
+                    if (step_energy / movable_count as f32).sqrt() < self.tolerance {
+                        convergence = ConvergenceReason::Converged;
+                        break;
+                    }
+                }
+            }
         }
 
         self.apply_positions_to_model(model, positions)?;
 
-        Ok(())
+        Ok(OptimizationStats {
+            iterations,
+            final_temperature: Some(temperature),
+            duration_ms: start.elapsed_ms(),
+            node_count,
+            edge_count: model.edge_count(),
+            energy,
+            convergence,
+        })
     }
 
     /// Optimize node positions with optional telemetry logging
@@ -84,91 +223,141 @@ impl<B: Backend> OptaOptimizer<B> {
         &self,
         model: &mut OptaModel<B>,
         logger: Option<&crate::telemetry::TelemetryLogger>,
-    ) -> Result<()> {
-        let start = std::time::Instant::now();
-        let node_count = model.node_count();
-        let edge_count = model.edge_count();
-
-        self.optimize_layout(model)?;
+    ) -> Result<OptimizationStats> {
+        let stats = self.optimize_layout(model)?;
 
         if let Some(logger) = logger {
-            let duration_ms = start.elapsed().as_millis() as u64;
-            // Calculate final temperature: last iteration of cooling schedule
-            let cooling = 1.0 - ((self.iterations - 1) as f32 / self.iterations as f32);
-            let final_temp = self.learning_rate * cooling;
-
             logger.log_layout_converged(
-                self.iterations,
-                final_temp,
-                duration_ms,
-                node_count,
-                edge_count,
+                stats.iterations,
+                stats.final_temperature,
+                stats.duration_ms,
+                stats.node_count,
+                stats.edge_count,
             )?;
         }
 
-        Ok(())
+        Ok(stats)
     }
-    fn initialize_positions(&self, count: usize, device: &B::Device) -> Tensor<B, 2> {
-        let mut rng = rand::rng();
-        let mut positions = Vec::with_capacity(count * 2);
-
-        for _ in 0..count {
-            let x = rng.random_range(0.0..self.area.sqrt());
-            let y = rng.random_range(0.0..self.area.sqrt());
-            positions.push(x);
-            positions.push(y);
+
+    fn initialize_positions(&self, model: &OptaModel<B>, device: &B::Device) -> Tensor<B, 2> {
+        let mut rng = match self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_rng(&mut rand::rng()),
+        };
+        let side = self.area.sqrt();
+
+        let mut placed: Vec<Option<(f32, f32)>> = model
+            .nodes
+            .iter()
+            .map(|n| {
+                if self.warm_start || n.pinned {
+                    n.get_position()
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        // On a warm start, new nodes are placed around the centroid of their
+        // positioned neighbours
+        let mut neighbour_indices: Vec<Vec<usize>> = Vec::new();
+        if self.warm_start {
+            let index: HashMap<&str, usize> = model
+                .nodes
+                .iter()
+                .enumerate()
+                .map(|(idx, n)| (n.id.as_str(), idx))
+                .collect();
+            neighbour_indices.resize(model.nodes.len(), Vec::new());
+            for e in &model.edges {
+                if let (Some(&from), Some(&to)) =
+                    (index.get(e.from.as_str()), index.get(e.to.as_str()))
+                {
+                    neighbour_indices[from].push(to);
+                    neighbour_indices[to].push(from);
+                }
+            }
+        }
+
+        // Nodes with no positioned neighbours go to a random spot
+        let mut positions = Vec::with_capacity(model.nodes.len() * 2);
+        for idx in 0..model.nodes.len() {
+            let position = placed[idx].unwrap_or_else(|| {
+                let neighbours: Vec<(f32, f32)> = neighbour_indices
+                    .get(idx)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|&other_idx| placed[other_idx])
+                    .collect();
+
+                if !neighbours.is_empty() {
+                    let count = neighbours.len() as f32;
+                    let (sum_x, sum_y) = neighbours
+                        .iter()
+                        .fold((0.0, 0.0), |(x, y), (nx, ny)| (x + nx, y + ny));
+                    let jitter = self.k * 0.5;
+                    (
+                        sum_x / count + random_in(&mut rng, -jitter, jitter),
+                        sum_y / count + random_in(&mut rng, -jitter, jitter),
+                    )
+                } else {
+                    (
+                        random_in(&mut rng, 0.0, side),
+                        random_in(&mut rng, 0.0, side),
+                    )
+                }
+            });
+            placed[idx] = Some(position);
+            positions.push(position.0);
+            positions.push(position.1);
         }
 
-        Tensor::<B, 1>::from_floats(positions.as_slice(), device).reshape([count, 2])
+        Tensor::<B, 1>::from_floats(positions.as_slice(), device).reshape([model.nodes.len(), 2])
     }
 
-    fn compute_forces(&self, positions: &Tensor<B, 2>, adjacency: &Tensor<B, 2>) -> Tensor<B, 2> {
+    /// Net force on every node, as an `[n, 2]` tensor
+    ///
+    /// Pairwise deltas are formed by broadcasting `[n, 1, 2] - [1, n, 2]`, so
+    /// the whole step is a handful of `[n, n]` tensor ops on the backend.
+    /// `off_diagonal` removes self-interaction and `attraction` holds the
+    /// (already masked) edge weights.
+    fn compute_forces(
+        &self,
+        positions: &Tensor<B, 2>,
+        off_diagonal: &Tensor<B, 2>,
+        attraction: &Tensor<B, 2>,
+    ) -> Tensor<B, 2> {
         let node_count = positions.dims()[0];
-        let device = positions.device();
-
-        let positions_data = positions.to_data();
-        let positions_vec: Vec<f32> = positions_data
-            .to_vec()
-            .expect("Failed to convert positions");
-
-        let adjacency_data = adjacency.to_data();
-        let adjacency_vec: Vec<f32> = adjacency_data
-            .to_vec()
-            .expect("Failed to convert adjacency");
-
-        let mut force_values = vec![0.0f32; node_count * 2];
 
-        for i in 0..node_count {
-            let xi = positions_vec[i * 2];
-            let yi = positions_vec[i * 2 + 1];
-
-            for j in 0..node_count {
-                if i == j {
-                    continue;
-                }
-
-                let xj = positions_vec[j * 2];
-                let yj = positions_vec[j * 2 + 1];
-
-                let dx = xi - xj;
-                let dy = yi - yj;
-                let distance_sq = dx * dx + dy * dy + 0.01;
-                let distance = distance_sq.sqrt();
+        let delta =
+            positions.clone().unsqueeze_dim::<3>(1) - positions.clone().unsqueeze_dim::<3>(0);
+        let distance_sq = delta.clone().powi_scalar(2).sum_dim(2).squeeze_dim::<2>(2) + 0.01;
+        let distance = distance_sq.clone().sqrt();
 
-                let repulsive_force = (self.k * self.k) / distance_sq;
-                force_values[i * 2] += (dx / distance) * repulsive_force;
-                force_values[i * 2 + 1] += (dy / distance) * repulsive_force;
+        let repulsive = distance_sq.clone().recip() * (self.k * self.k) * off_diagonal.clone();
+        let attractive = distance_sq / self.k * attraction.clone();
 
-                let edge_weight = adjacency_vec[i * node_count + j];
-                if edge_weight > 0.0 {
-                    let attractive_force = (distance * distance) / self.k * edge_weight;
-                    force_values[i * 2] -= (dx / distance) * attractive_force;
-                    force_values[i * 2 + 1] -= (dy / distance) * attractive_force;
-                }
-            }
-        }
+        // Signed magnitude along the unit vector from j to i
+        let magnitude = (repulsive - attractive) / distance;
+        (delta * magnitude.unsqueeze_dim::<3>(2))
+            .sum_dim(1)
+            .reshape([node_count, 2])
+    }
 
-        Tensor::<B, 1>::from_floats(force_values.as_slice(), &device).reshape([node_count, 2])
+    /// Limit each node's step to a length that shrinks as the layout cools
+    ///
+    /// Without this, the `k² / d²` repulsion between two nearly coincident
+    /// nodes throws them across the whole canvas in one iteration.
+    fn clamp_displacement(&self, displacement: Tensor<B, 2>, cooling: f32) -> Tensor<B, 2> {
+        let max_step = self.area.sqrt() * 0.1 * cooling;
+        let length = displacement
+            .clone()
+            .powi_scalar(2)
+            .sum_dim(1)
+            .sqrt()
+            .clamp_min(f32::EPSILON);
+        let scale = (length.recip() * max_step).clamp_max(1.0);
+        displacement * scale
     }
 
     fn clamp_positions(&self, positions: Tensor<B, 2>) -> Tensor<B, 2> {
@@ -196,6 +385,38 @@ impl<B: Backend> OptaOptimizer<B> {
     }
 }
 
+/// Sample `low..high`, which must contain zero, or return zero when the range
+/// is empty or unbounded, as it is when the area or `k` is zero
+fn random_in(rng: &mut StdRng, low: f32, high: f32) -> f32 {
+    if high > low && (high - low).is_finite() {
+        rng.random_range(low..high)
+    } else {
+        0.0
+    }
+}
+
+/// Wall-clock timer that degrades to zero on `wasm32`, where `Instant::now` is unavailable
+pub(crate) struct Timer {
+    #[cfg(not(target_arch = "wasm32"))]
+    start: std::time::Instant,
+}
+
+impl Timer {
+    pub(crate) fn start() -> Self {
+        Self {
+            #[cfg(not(target_arch = "wasm32"))]
+            start: std::time::Instant::now(),
+        }
+    }
+
+    pub(crate) fn elapsed_ms(&self) -> u64 {
+        #[cfg(not(target_arch = "wasm32"))]
+        return self.start.elapsed().as_millis() as u64;
+        #[cfg(target_arch = "wasm32")]
+        return 0;
+    }
+}
+
 impl<B: Backend> Default for OptaOptimizer<B> {
     fn default() -> Self {
         Self::new(100, 0.1)
@@ -268,4 +489,255 @@ mod tests {
         assert!(pos_a.is_some());
         assert!(pos_b.is_some());
     }
+
+    fn chain_model(ids: &[&str]) -> OptaModel<TestBackend> {
+        let mut model = OptaModel::new();
+        for id in ids {
+            model.add_node(OptaNode::new(
+                id.to_string(),
+                id.to_uppercase(),
+                NodeType::Container,
+            ));
+        }
+        for pair in ids.windows(2) {
+            model.add_edge(OptaEdge::new(pair[0].to_string(), pair[1].to_string()));
+        }
+        model.build_adjacency_matrix();
+        model
+    }
+
+    fn positions(model: &OptaModel<TestBackend>) -> Vec<(f32, f32)> {
+        model
+            .nodes
+            .iter()
+            .map(|n| n.get_position().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_seeded_layout_is_deterministic() {
+        let mut first = chain_model(&["a", "b", "c", "d"]);
+        let mut second = chain_model(&["a", "b", "c", "d"]);
+
+        let optimizer = OptaOptimizer::<TestBackend>::new(30, 0.1).with_seed(7);
+        optimizer.optimize_layout(&mut first).unwrap();
+        optimizer.optimize_layout(&mut second).unwrap();
+
+        assert_eq!(positions(&first), positions(&second));
+    }
+
+    #[test]
+    fn test_pinned_nodes_do_not_move() {
+        let mut model = chain_model(&["a", "b", "c"]);
+        model.find_node_mut("b").unwrap().pin(12.0, 5.0);
+
+        OptaOptimizer::<TestBackend>::new(30, 0.1)
+            .with_seed(1)
+            .optimize_layout(&mut model)
+            .unwrap();
+
+        assert_eq!(
+            model.find_node("b").unwrap().get_position(),
+            Some((12.0, 5.0))
+        );
+
+        model.find_node_mut("c").unwrap().pinned = true;
+        model.find_node_mut("c").unwrap().position = None;
+        assert!(
+            OptaOptimizer::<TestBackend>::default()
+                .optimize_layout(&mut model)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_warm_start_keeps_existing_layout() {
+        let mut model = chain_model(&["a", "b", "c", "d"]);
+        let optimizer = OptaOptimizer::<TestBackend>::new(100, 0.1).with_seed(3);
+        optimizer.optimize_layout(&mut model).unwrap();
+        let before = positions(&model);
+
+        // Adding a node and re-running warm should only nudge the existing ones
+        model.add_node(OptaNode::new(
+            "e".to_string(),
+            "E".to_string(),
+            NodeType::Container,
+        ));
+        model.add_edge(OptaEdge::new("d".to_string(), "e".to_string()));
+        model.build_adjacency_matrix();
+
+        let stats = OptaOptimizer::<TestBackend>::new(10, 0.01)
+            .with_seed(3)
+            .with_warm_start(true)
+            .optimize_layout(&mut model)
+            .unwrap();
+        let after = positions(&model);
+
+        let max_shift = before
+            .iter()
+            .zip(&after)
+            .map(|((x0, y0), (x1, y1))| ((x1 - x0).powi(2) + (y1 - y0).powi(2)).sqrt())
+            .fold(0.0f32, f32::max);
+        assert!(
+            max_shift < 1000f32.sqrt() * 0.5,
+            "layout moved by {max_shift}"
+        );
+        assert_eq!(stats.node_count, 5);
+    }
+
+    #[test]
+    fn test_optimization_stats() {
+        let mut model = chain_model(&["a", "b", "c"]);
+        let stats = OptaOptimizer::<TestBackend>::new(25, 0.1)
+            .with_seed(5)
+            .with_tolerance(0.0)
+            .optimize_layout(&mut model)
+            .unwrap();
+
+        assert_eq!(stats.iterations, 25);
+        assert_eq!(stats.energy.len(), 25);
+        assert_eq!(stats.convergence, ConvergenceReason::MaxIterations);
+        assert_eq!(stats.edge_count, 2);
+        assert!(stats.energy.iter().all(|e| e.is_finite() && *e >= 0.0));
+
+        let stats = OptaOptimizer::<TestBackend>::new(25, 0.1)
+            .with_tolerance(f32::MAX)
+            .optimize_layout(&mut model)
+            .unwrap();
+        assert_eq!(stats.iterations, ENERGY_READBACK_INTERVAL);
+        assert_eq!(stats.energy.len(), ENERGY_READBACK_INTERVAL);
+        assert_eq!(stats.convergence, ConvergenceReason::Converged);
+
+        for node in &mut model.nodes {
+            node.pinned = true;
+        }
+        let stats = OptaOptimizer::<TestBackend>::default()
+            .optimize_layout(&mut model)
+            .unwrap();
+        assert_eq!(stats.convergence, ConvergenceReason::AllNodesPinned);
+        assert!(stats.energy.is_empty());
+    }
+
+    #[test]
+    fn test_default_tolerance_stops_once_settled() {
+        // Two nodes repel each other into opposite corners of the canvas,
+        // where they stop moving long before the budget is used up
+        let mut model = chain_model(&["a", "b"]);
+        let stats = OptaOptimizer::<TestBackend>::new(500, 0.1)
+            .with_seed(2)
+            .optimize_layout(&mut model)
+            .unwrap();
+        assert_eq!(stats.convergence, ConvergenceReason::Converged);
+        assert!(stats.iterations < 500);
+        assert_eq!(stats.energy.len(), stats.iterations);
+    }
+
+    #[test]
+    fn test_warm_start_with_zero_jitter() {
+        let mut model = chain_model(&["a", "b"]);
+        model.find_node_mut("a").unwrap().set_position(0.0, 0.0);
+
+        // A zero area makes `k`, and with it the placement jitter, zero
+        let stats = OptaOptimizer::<TestBackend>::new(5, 0.1)
+            .with_area(0.0)
+            .with_seed(4)
+            .with_warm_start(true)
+            .optimize_layout(&mut model)
+            .unwrap();
+        assert_eq!(stats.node_count, 2);
+        assert!(model.find_node("b").unwrap().get_position().is_some());
+
+        // With no iterations `k` isn't finite, which must not panic either
+        model.find_node_mut("b").unwrap().position = None;
+        OptaOptimizer::<TestBackend>::new(0, 0.1)
+            .with_area(1000.0)
+            .with_warm_start(true)
+            .optimize_layout(&mut model)
+            .unwrap();
+        assert!(model.find_node("b").unwrap().get_position().is_some());
+    }
+
+    /// The per-pair loop the tensor implementation replaced
+    fn reference_forces(positions: &[f32], adjacency: &[f32], k: f32) -> Vec<f32> {
+        let n = positions.len() / 2;
+        let mut forces = vec![0.0f32; n * 2];
+        for i in 0..n {
+            for j in 0..n {
+                if i == j {
+                    continue;
+                }
+                let dx = positions[i * 2] - positions[j * 2];
+                let dy = positions[i * 2 + 1] - positions[j * 2 + 1];
+                let distance_sq = dx * dx + dy * dy + 0.01;
+                let distance = distance_sq.sqrt();
+
+                let mut force = (k * k) / distance_sq;
+                if adjacency[i * n + j] > 0.0 {
+                    force -= distance_sq / k * adjacency[i * n + j];
+                }
+                forces[i * 2] += dx / distance * force;
+                forces[i * 2 + 1] += dy / distance * force;
+            }
+        }
+        forces
+    }
+
+    #[test]
+    fn test_vectorized_forces_match_reference() {
+        let optimizer = OptaOptimizer::<TestBackend>::new(10, 0.1);
+        let device = Default::default();
+        let positions = [0.0, 0.0, 3.0, 4.0, 10.0, 1.0, 5.0, 8.0];
+        let adjacency = [
+            0.0, 1.0, 0.0, 0.0, //
+            0.0, 0.0, 2.0, 0.0, //
+            1.0, 0.0, 0.0, -1.0, //
+            0.0, 0.0, 0.0, 0.0,
+        ];
+
+        let position_tensor =
+            Tensor::<TestBackend, 1>::from_floats(positions, &device).reshape([4, 2]);
+        let adjacency_tensor =
+            Tensor::<TestBackend, 1>::from_floats(adjacency, &device).reshape([4, 4]);
+        let off_diagonal = Tensor::<TestBackend, 2>::ones([4, 4], &device)
+            - Tensor::<TestBackend, 2>::eye(4, &device);
+        let attraction = adjacency_tensor
+            .clone()
+            .mask_fill(adjacency_tensor.lower_equal_elem(0.0), 0.0)
+            * off_diagonal.clone();
+
+        let forces: Vec<f32> = optimizer
+            .compute_forces(&position_tensor, &off_diagonal, &attraction)
+            .into_data()
+            .to_vec()
+            .unwrap();
+        let expected = reference_forces(&positions, &adjacency, optimizer.k);
+
+        for (actual, expected) in forces.iter().zip(&expected) {
+            assert!(
+                (actual - expected).abs() <= 1e-3 * expected.abs().max(1.0),
+                "{actual} != {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_displacement_is_clamped_per_node() {
+        let optimizer = OptaOptimizer::<TestBackend>::new(10, 0.1);
+        let device = Default::default();
+        let displacement = Tensor::<TestBackend, 1>::from_floats([300.0, 400.0, 0.5, 0.0], &device)
+            .reshape([2, 2]);
+
+        let clamped: Vec<f32> = optimizer
+            .clamp_displacement(displacement, 1.0)
+            .into_data()
+            .to_vec()
+            .unwrap();
+
+        // max step is sqrt(1000) * 0.1 ≈ 3.16: the first node is scaled down
+        // along its direction, the second is already within bounds
+        let max_step = 1000f32.sqrt() * 0.1;
+        assert!((clamped[0] - 0.6 * max_step).abs() < 1e-4);
+        assert!((clamped[1] - 0.8 * max_step).abs() < 1e-4);
+        assert_eq!(&clamped[2..], &[0.5, 0.0]);
+    }
 }
@@ -1,126 +1,1144 @@
+use crate::workspace::{
+    AutoLayout, RankDirection, StyleRule, StyleTarget, ViewDefinition, ViewKind, Workspace,
+};
 use crate::{NodeType, OptaModel, OptaNode, Result, model::OptaEdge};
 use burn::tensor::backend::Backend;
-use regex::Regex;
+use lexer::{Token, TokenKind, tokenize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
 
-/// Parse C4 DSL into OptaModel (MVP regex-based implementation)
-///
-/// **⚠️ Current Limitations:**
-/// - **Regex-based**: Fragile for complex DSL, no proper tokenization
-/// - **No nesting**: All nodes are flat (ignores `system { container { ... } }` hierarchy)
-/// - **Limited error reporting**: No line numbers or context in errors
-/// - **Missing features**: softwareSystem, deploymentNode, tags, descriptions, comments
-/// - **No multi-line**: Descriptions and whitespace handling is basic
+mod lexer;
+
+/// Maximum nesting of `!include` directives, guarding against include cycles
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// Location of a token in DSL source: byte range plus 1-based line and column
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Error raised while tokenizing or parsing Structurizr DSL
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub message: String,
+    pub span: Span,
+    /// File the error occurred in, when parsing from disk or through `!include`
+    pub source_name: Option<String>,
+}
+
+impl ParseError {
+    fn new(message: impl Into<String>, span: Span) -> Self {
+        Self {
+            message: message.into(),
+            span,
+            source_name: None,
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.source_name {
+            Some(name) => write!(
+                f,
+                "{}:{}:{}: {}",
+                name, self.span.line, self.span.column, self.message
+            ),
+            None => write!(
+                f,
+                "line {}, column {}: {}",
+                self.span.line, self.span.column, self.message
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Parse C4 DSL into OptaModel
 ///
-/// **Recommended Production Alternative:**
-/// Use the official Structurizr DSL parser:
-/// - **Repository**: https://github.com/structurizr/java/tree/master/structurizr-dsl
-/// - **Integration options**:
-///   1. JNI bridge to Java parser (most robust)
-///   2. External process: Java → JSON → Rust (simpler, still reliable)
-///   3. Port to Rust with nom/pest (full control, more work)
+/// Accepts both full Structurizr workspaces and the compact statement form
+/// used by earlier OptaCore releases; see [`parse_workspace`] for the grammar.
+/// Views and styles are parsed and validated, then discarded.
 ///
-/// **Supported Syntax (Basic):**
 /// ```text
 /// system MySystem "System Name"
 /// container API "API Gateway" "Node.js"
-/// component Auth "Authentication" "JWT"
 /// person User "Customer"
 ///
-/// API -> DB "queries"
 /// User -> API "makes requests"
 /// ```
 pub fn parse_c4_dsl<B: Backend>(input: &str) -> Result<OptaModel<B>> {
-    let mut model = OptaModel::new();
-
-    let container_re = Regex::new(r#"(?m)^\s*container\s+(\w+)\s+"([^"]+)"(?:\s+"([^"]+)")?"#)?;
-    let component_re = Regex::new(r#"(?m)^\s*component\s+(\w+)\s+"([^"]+)"(?:\s+"([^"]+)")?"#)?;
-    let person_re = Regex::new(r#"(?m)^\s*person\s+(\w+)\s+"([^"]+)""#)?;
-    let system_re = Regex::new(r#"(?m)^\s*system\s+(\w+)\s+"([^"]+)""#)?;
-    let relationship_re = Regex::new(r#"(?m)^\s*(\w+)\s*->\s*(\w+)(?:\s+"([^"]+)")?"#)?;
-
-    for cap in container_re.captures_iter(input) {
-        let id = cap
-            .get(1)
-            .map(|m| m.as_str().to_string())
-            .ok_or_else(|| anyhow::anyhow!("Missing container ID"))?;
-        let name = cap
-            .get(2)
-            .map(|m| m.as_str().to_string())
-            .ok_or_else(|| anyhow::anyhow!("Missing container name"))?;
-        let technology = cap.get(3).map(|m| m.as_str().to_string());
-
-        let mut node = OptaNode::new(id, name, NodeType::Container);
-        if let Some(tech) = technology {
-            node = node.with_technology(tech);
-        }
-        model.add_node(node);
-    }
-
-    for cap in component_re.captures_iter(input) {
-        let id = cap
-            .get(1)
-            .map(|m| m.as_str().to_string())
-            .ok_or_else(|| anyhow::anyhow!("Missing component ID"))?;
-        let name = cap
-            .get(2)
-            .map(|m| m.as_str().to_string())
-            .ok_or_else(|| anyhow::anyhow!("Missing component name"))?;
-        let technology = cap.get(3).map(|m| m.as_str().to_string());
-
-        let mut node = OptaNode::new(id, name, NodeType::Component);
-        if let Some(tech) = technology {
-            node = node.with_technology(tech);
-        }
-        model.add_node(node);
-    }
-
-    for cap in person_re.captures_iter(input) {
-        let id = cap
-            .get(1)
-            .map(|m| m.as_str().to_string())
-            .ok_or_else(|| anyhow::anyhow!("Missing person ID"))?;
-        let name = cap
-            .get(2)
-            .map(|m| m.as_str().to_string())
-            .ok_or_else(|| anyhow::anyhow!("Missing person name"))?;
-
-        let node = OptaNode::new(id, name, NodeType::Person);
-        model.add_node(node);
-    }
-
-    for cap in system_re.captures_iter(input) {
-        let id = cap
-            .get(1)
-            .map(|m| m.as_str().to_string())
-            .ok_or_else(|| anyhow::anyhow!("Missing system ID"))?;
-        let name = cap
-            .get(2)
-            .map(|m| m.as_str().to_string())
-            .ok_or_else(|| anyhow::anyhow!("Missing system name"))?;
-
-        let node = OptaNode::new(id, name, NodeType::System);
-        model.add_node(node);
-    }
-
-    for cap in relationship_re.captures_iter(input) {
-        let from = cap
-            .get(1)
-            .map(|m| m.as_str().to_string())
-            .ok_or_else(|| anyhow::anyhow!("Missing relationship source"))?;
-        let to = cap
-            .get(2)
-            .map(|m| m.as_str().to_string())
-            .ok_or_else(|| anyhow::anyhow!("Missing relationship target"))?;
-        let label = cap.get(3).map(|m| m.as_str().to_string());
-
-        let mut edge = OptaEdge::new(from, to);
-        if let Some(l) = label {
-            edge = edge.with_label(l);
-        }
-        model.add_edge(edge);
-    }
-
-    Ok(model)
+    Ok(parse_workspace(input)?.model)
+}
+
+/// Parse a Structurizr DSL workspace
+///
+/// Implements a tokenizer and recursive-descent parser for the subset of the
+/// [Structurizr DSL](https://docs.structurizr.com/dsl/language) that maps onto
+/// `OptaModel`:
+/// - `workspace`, `model`, `views` and `styles` blocks
+/// - `person`, `softwareSystem` (or `system`), `container` and `component`
+///   elements, with `identifier = element ...` assignments and nested blocks
+///   recorded as parent/child containment
+/// - relationships (`a -> b`, `-> b` and `this -> b` inside element blocks),
+///   `group` blocks, and `description`/`technology`/`tags` statements
+/// - `//`, `/* */` and `#` comments, `"""` text blocks, and
+///   `!identifiers hierarchical`
+///
+/// The compact form `container API "API Gateway" "Node.js"` (identifier,
+/// name, technology) is still accepted alongside the Structurizr form
+/// `api = container "API Gateway" "description" "Node.js"`.
+///
+/// Deployment environments and other unsupported constructs are skipped.
+/// `!include` needs a file path to resolve against, so it is only available
+/// through [`parse_workspace_file`].
+pub fn parse_workspace<B: Backend>(input: &str) -> std::result::Result<Workspace<B>, ParseError> {
+    Parser::new(vec![SourceFile {
+        path: None,
+        depth: 0,
+    }])
+    .parse(input)
+}
+
+/// Parse a Structurizr DSL workspace from disk, resolving `!include` relative to each file
+pub fn parse_workspace_file<B: Backend>(
+    path: &Path,
+) -> std::result::Result<Workspace<B>, ParseError> {
+    let input = std::fs::read_to_string(path).map_err(|error| ParseError {
+        message: format!("failed to read file: {}", error),
+        span: Span::default(),
+        source_name: Some(path.display().to_string()),
+    })?;
+
+    Parser::new(vec![SourceFile {
+        path: Some(path.to_path_buf()),
+        depth: 0,
+    }])
+    .parse(&input)
+}
+
+struct SourceFile {
+    path: Option<PathBuf>,
+    depth: usize,
+}
+
+#[derive(Debug, Clone)]
+struct ElementRef {
+    id: String,
+    node_type: NodeType,
+}
+
+struct PendingRelationship {
+    source: Token,
+    source_id: Option<String>,
+    destination: Token,
+    description: Option<String>,
+    technology: Option<String>,
+    tags: Vec<String>,
+    properties: BTreeMap<String, String>,
+}
+
+struct PendingView {
+    view: ViewDefinition,
+    scope: Option<Token>,
+}
+
+struct Parser<B: Backend> {
+    tokens: Vec<Token>,
+    pos: usize,
+    sources: Vec<SourceFile>,
+    workspace: Workspace<B>,
+    hierarchical_identifiers: bool,
+    node_ids: HashSet<String>,
+    relationships: Vec<PendingRelationship>,
+    views: Vec<PendingView>,
+    view_counts: HashMap<&'static str, usize>,
+}
+
+impl<B: Backend> Parser<B> {
+    fn new(sources: Vec<SourceFile>) -> Self {
+        Self {
+            tokens: Vec::new(),
+            pos: 0,
+            sources,
+            workspace: Workspace::new(),
+            hierarchical_identifiers: false,
+            node_ids: HashSet::new(),
+            relationships: Vec::new(),
+            views: Vec::new(),
+            view_counts: HashMap::new(),
+        }
+    }
+
+    fn parse(mut self, input: &str) -> std::result::Result<Workspace<B>, ParseError> {
+        self.tokens = tokenize(input, 0).map_err(|e| self.with_source(e, 0))?;
+
+        self.skip_newlines();
+        if self.peek_keyword("workspace") {
+            self.parse_workspace_block()?;
+            self.skip_newlines();
+            if !matches!(self.peek().kind, TokenKind::Eof) {
+                return Err(self.unexpected(&self.peek().clone(), "after workspace block"));
+            }
+        } else {
+            // Compact form: model statements at top level, optionally mixed with
+            // `model`, `views` and `styles` blocks.
+            while !matches!(self.peek().kind, TokenKind::Eof) {
+                if self.peek_keyword("model") {
+                    self.advance();
+                    self.block(|p| p.parse_model_statement(None))?;
+                    self.end_statement()?;
+                } else if self.peek_keyword("views") {
+                    self.advance();
+                    self.block(|p| p.parse_views_statement())?;
+                    self.end_statement()?;
+                } else if self.peek_keyword("styles") {
+                    self.advance();
+                    self.block(|p| p.parse_style_statement())?;
+                    self.end_statement()?;
+                } else if matches!(self.peek().kind, TokenKind::RBrace) {
+                    return Err(self.unexpected(&self.peek().clone(), "without matching `{`"));
+                } else {
+                    self.parse_model_statement(None)?;
+                }
+                self.skip_newlines();
+            }
+        }
+
+        self.resolve_relationships()?;
+        self.resolve_views()?;
+        Ok(self.workspace)
+    }
+
+    fn parse_workspace_block(&mut self) -> std::result::Result<(), ParseError> {
+        let keyword = self.advance();
+        if self.peek_keyword("extends") {
+            return Err(self.error_at(&self.peek().clone(), "`workspace extends` is not supported"));
+        }
+
+        let mut args = self.take_strings().into_iter();
+        self.workspace.name = args.next();
+        self.workspace.description = args.next();
+        if !matches!(self.peek().kind, TokenKind::LBrace) {
+            return Err(self.error_at(&keyword, "expected `{` after `workspace`"));
+        }
+
+        self.block(|p| {
+            let token = p.peek().clone();
+            match &token.kind {
+                TokenKind::Directive(_) => p.parse_directive(),
+                TokenKind::Word(word) => match word.to_ascii_lowercase().as_str() {
+                    "name" => {
+                        p.advance();
+                        p.workspace.name = Some(p.expect_string("workspace name")?);
+                        p.end_statement()
+                    }
+                    "description" => {
+                        p.advance();
+                        p.workspace.description = Some(p.expect_string("workspace description")?);
+                        p.end_statement()
+                    }
+                    "model" => {
+                        p.advance();
+                        p.block(|p| p.parse_model_statement(None))?;
+                        p.end_statement()
+                    }
+                    "views" => {
+                        p.advance();
+                        p.block(|p| p.parse_views_statement())?;
+                        p.end_statement()
+                    }
+                    "styles" => {
+                        p.advance();
+                        p.block(|p| p.parse_style_statement())?;
+                        p.end_statement()
+                    }
+                    "properties" => {
+                        p.advance();
+                        let properties = p.parse_properties()?;
+                        p.workspace.properties.extend(properties);
+                        p.end_statement()
+                    }
+                    "configuration" => p.skip_statement(),
+                    _ => Err(p.unexpected(&token, "in workspace")),
+                },
+                _ => Err(p.unexpected(&token, "in workspace")),
+            }
+        })?;
+        self.end_statement()
+    }
+
+    fn parse_model_statement(
+        &mut self,
+        parent: Option<&ElementRef>,
+    ) -> std::result::Result<(), ParseError> {
+        let token = self.peek().clone();
+        match &token.kind {
+            TokenKind::Directive(_) => self.parse_directive(),
+            TokenKind::Arrow => self.parse_relationship(token, parent.map(|p| p.id.clone())),
+            TokenKind::Word(word) => {
+                let next = self.peek_nth(1).kind.clone();
+                if next == TokenKind::Equals {
+                    self.advance();
+                    self.advance();
+                    return self.parse_assignment(token, parent);
+                }
+                if next == TokenKind::Arrow {
+                    self.advance();
+                    let source_id = if word == "this" {
+                        match parent {
+                            Some(parent) => Some(parent.id.clone()),
+                            None => {
+                                return Err(self.error_at(
+                                    &token,
+                                    "`this` can only be used inside an element block",
+                                ));
+                            }
+                        }
+                    } else {
+                        None
+                    };
+                    return self.parse_relationship(token, source_id);
+                }
+
+                match word.to_ascii_lowercase().as_str() {
+                    "person" | "softwaresystem" | "system" | "container" | "component" => {
+                        self.parse_element(None, parent)
+                    }
+                    "group" | "enterprise" => {
+                        self.advance();
+                        self.take_strings();
+                        self.block(|p| p.parse_model_statement(parent))?;
+                        self.end_statement()
+                    }
+                    "deploymentenvironment" | "perspectives" => {
+                        log::debug!("Skipping unsupported `{}` block", word);
+                        self.skip_statement()
+                    }
+                    "description" | "technology" | "tags" | "url" | "properties" => match parent {
+                        Some(parent) => self.parse_element_property(parent),
+                        None => Err(self.error_at(
+                            &token,
+                            format!("`{}` can only be used inside an element block", word),
+                        )),
+                    },
+                    _ => Err(self.unexpected(&token, "in model")),
+                }
+            }
+            _ => Err(self.unexpected(&token, "in model")),
+        }
+    }
+
+    fn parse_assignment(
+        &mut self,
+        identifier: Token,
+        parent: Option<&ElementRef>,
+    ) -> std::result::Result<(), ParseError> {
+        let token = self.peek().clone();
+        match &token.kind {
+            TokenKind::Word(word) if self.peek_nth(1).kind == TokenKind::Arrow => {
+                // Relationship identifiers are accepted but not needed for OptaModel edges
+                self.advance();
+                let source_id = if word == "this" {
+                    parent.map(|p| p.id.clone())
+                } else {
+                    None
+                };
+                self.parse_relationship(token, source_id)
+            }
+            TokenKind::Arrow => self.parse_relationship(token, parent.map(|p| p.id.clone())),
+            TokenKind::Word(word)
+                if matches!(
+                    word.to_ascii_lowercase().as_str(),
+                    "person" | "softwaresystem" | "system" | "container" | "component"
+                ) =>
+            {
+                self.parse_element(Some(identifier), parent)
+            }
+            _ => Err(self.unexpected(&token, "after `=`")),
+        }
+    }
+
+    fn parse_element(
+        &mut self,
+        identifier: Option<Token>,
+        parent: Option<&ElementRef>,
+    ) -> std::result::Result<(), ParseError> {
+        let keyword = self.advance();
+        let node_type = match &keyword.kind {
+            TokenKind::Word(word) => match word.to_ascii_lowercase().as_str() {
+                "person" => NodeType::Person,
+                "container" => NodeType::Container,
+                "component" => NodeType::Component,
+                _ => NodeType::System,
+            },
+            _ => return Err(self.unexpected(&keyword, "where an element was expected")),
+        };
+
+        self.check_containment(&keyword, &node_type, parent)?;
+
+        // Compact form: `container API "API Gateway" "Node.js"`
+        let compact_identifier = match (&identifier, &self.peek().kind) {
+            (None, TokenKind::Word(_)) => Some(self.advance()),
+            _ => None,
+        };
+
+        let args = self.take_strings();
+        let Some(name) = args.first().cloned() else {
+            return Err(self.error_at(&keyword, "expected element name"));
+        };
+
+        let mut node = OptaNode::new(String::new(), name, node_type.clone());
+        if compact_identifier.is_some() {
+            match node_type {
+                NodeType::Container | NodeType::Component => {
+                    node.technology = args.get(1).cloned();
+                    node.description = args.get(2).cloned();
+                }
+                NodeType::System | NodeType::Person => {
+                    node.description = args.get(1).cloned();
+                }
+            }
+        } else {
+            node.description = args.get(1).cloned();
+            match node_type {
+                NodeType::Container | NodeType::Component => {
+                    node.technology = args.get(2).cloned();
+                    node.tags = split_tags(args.get(3..).unwrap_or_default());
+                }
+                NodeType::System | NodeType::Person => {
+                    node.tags = split_tags(args.get(2..).unwrap_or_default());
+                }
+            }
+        }
+
+        let identifier = identifier.or(compact_identifier);
+        node.id = match &identifier {
+            Some(token) => {
+                let name = self.word_value(token)?;
+                let id = match parent {
+                    Some(parent) if self.hierarchical_identifiers => {
+                        format!("{}.{}", parent.id, name)
+                    }
+                    _ => name,
+                };
+                if self.node_ids.contains(&id) {
+                    return Err(
+                        self.error_at(token, format!("duplicate element identifier `{}`", id))
+                    );
+                }
+                id
+            }
+            None => self.generate_id(&node.name),
+        };
+        node.parent = parent.map(|p| p.id.clone());
+
+        let element = ElementRef {
+            id: node.id.clone(),
+            node_type,
+        };
+        self.node_ids.insert(node.id.clone());
+        self.workspace.model.add_node(node);
+
+        if matches!(self.peek().kind, TokenKind::LBrace) {
+            self.block(|p| p.parse_model_statement(Some(&element)))?;
+        }
+        self.end_statement()
+    }
+
+    fn check_containment(
+        &self,
+        keyword: &Token,
+        node_type: &NodeType,
+        parent: Option<&ElementRef>,
+    ) -> std::result::Result<(), ParseError> {
+        let Some(parent) = parent else {
+            return Ok(());
+        };
+        let allowed = match node_type {
+            NodeType::Container => parent.node_type == NodeType::System,
+            NodeType::Component => parent.node_type == NodeType::Container,
+            NodeType::System | NodeType::Person => false,
+        };
+        if allowed {
+            Ok(())
+        } else {
+            Err(self.error_at(
+                keyword,
+                format!(
+                    "{} cannot be nested inside {} `{}`",
+                    node_type_name(node_type),
+                    node_type_name(&parent.node_type),
+                    parent.id
+                ),
+            ))
+        }
+    }
+
+    fn parse_element_property(
+        &mut self,
+        element: &ElementRef,
+    ) -> std::result::Result<(), ParseError> {
+        let keyword = self.advance();
+        let keyword_name = self.word_value(&keyword)?.to_ascii_lowercase();
+        match keyword_name.as_str() {
+            "properties" => {
+                let properties = self.parse_properties()?;
+                if let Some(node) = self.workspace.model.find_node_mut(&element.id) {
+                    node.properties.extend(properties);
+                }
+            }
+            "url" => {
+                self.take_values();
+            }
+            "description" => {
+                let description = self.expect_string("description")?;
+                if let Some(node) = self.workspace.model.find_node_mut(&element.id) {
+                    node.description = Some(description);
+                }
+            }
+            "technology" => {
+                let technology = self.expect_string("technology")?;
+                if let Some(node) = self.workspace.model.find_node_mut(&element.id) {
+                    node.technology = Some(technology);
+                }
+            }
+            _ => {
+                let tags = split_tags(&self.take_strings());
+                if let Some(node) = self.workspace.model.find_node_mut(&element.id) {
+                    node.tags.extend(tags);
+                }
+            }
+        }
+        self.end_statement()
+    }
+
+    /// Parse `source -> destination "description" "technology" "tags" { ... }`.
+    ///
+    /// `source` is the token before the arrow (or the arrow itself when the
+    /// source is implied by the enclosing element); `source_id` is set when the
+    /// source is already known and doesn't need resolving.
+    fn parse_relationship(
+        &mut self,
+        source: Token,
+        source_id: Option<String>,
+    ) -> std::result::Result<(), ParseError> {
+        let arrow = self.advance();
+        if source.kind == TokenKind::Arrow && source_id.is_none() {
+            return Err(self.error_at(
+                &arrow,
+                "relationship without a source is only allowed inside an element block",
+            ));
+        }
+
+        let destination = self.advance();
+        if !matches!(destination.kind, TokenKind::Word(_)) {
+            return Err(self.unexpected(
+                &destination,
+                "where a relationship destination was expected",
+            ));
+        }
+
+        let args = self.take_strings();
+        let mut relationship = PendingRelationship {
+            source,
+            source_id,
+            destination,
+            description: args.first().cloned(),
+            technology: args.get(1).cloned(),
+            tags: split_tags(args.get(2..).unwrap_or_default()),
+            properties: BTreeMap::new(),
+        };
+
+        if matches!(self.peek().kind, TokenKind::LBrace) {
+            self.block(|p| {
+                let token = p.peek().clone();
+                let keyword = match &token.kind {
+                    TokenKind::Word(word) => word.to_ascii_lowercase(),
+                    _ => return Err(p.unexpected(&token, "in relationship")),
+                };
+                p.advance();
+                match keyword.as_str() {
+                    "tags" => relationship.tags.extend(split_tags(&p.take_strings())),
+                    "description" => {
+                        relationship.description = Some(p.expect_string("description")?)
+                    }
+                    "technology" => relationship.technology = Some(p.expect_string("technology")?),
+                    "url" => {
+                        p.take_values();
+                    }
+                    "properties" => relationship.properties.extend(p.parse_properties()?),
+                    "perspectives" => return p.skip_block_statement(),
+                    _ => return Err(p.unexpected(&token, "in relationship")),
+                }
+                p.end_statement()
+            })?;
+        }
+
+        self.relationships.push(relationship);
+        self.end_statement()
+    }
+
+    fn parse_views_statement(&mut self) -> std::result::Result<(), ParseError> {
+        let token = self.peek().clone();
+        let keyword = match &token.kind {
+            TokenKind::Directive(_) => return self.parse_directive(),
+            TokenKind::Word(word) => word.to_ascii_lowercase(),
+            _ => return Err(self.unexpected(&token, "in views")),
+        };
+
+        let kind = match keyword.as_str() {
+            "systemlandscape" => ViewKind::SystemLandscape,
+            "systemcontext" => ViewKind::SystemContext,
+            "container" => ViewKind::Container,
+            "component" => ViewKind::Component,
+            "dynamic" => ViewKind::Dynamic,
+            "deployment" => ViewKind::Deployment,
+            "filtered" => ViewKind::Filtered,
+            "custom" => ViewKind::Custom,
+            "styles" => {
+                self.advance();
+                self.block(|p| p.parse_style_statement())?;
+                return self.end_statement();
+            }
+            "theme" | "themes" => {
+                self.advance();
+                self.take_values();
+                return self.end_statement();
+            }
+            "image" | "branding" | "terminology" | "properties" | "configuration" => {
+                return self.skip_statement();
+            }
+            _ => return Err(self.unexpected(&token, "in views")),
+        };
+        self.advance();
+
+        let mut environment = None;
+        let scope = match kind {
+            ViewKind::SystemContext | ViewKind::Container | ViewKind::Component => {
+                let scope = self.advance();
+                if !matches!(scope.kind, TokenKind::Word(_)) {
+                    return Err(self.error_at(
+                        &scope,
+                        format!("{} view requires a scope element", kind.key_prefix()),
+                    ));
+                }
+                Some(scope)
+            }
+            ViewKind::Dynamic | ViewKind::Deployment => {
+                let scope = self.advance();
+                if !matches!(scope.kind, TokenKind::Word(_)) {
+                    return Err(self.error_at(
+                        &scope,
+                        format!("{} view requires a scope element or `*`", kind.key_prefix()),
+                    ));
+                }
+                if kind == ViewKind::Deployment {
+                    let name = self.advance();
+                    match name.kind {
+                        TokenKind::Str(name) | TokenKind::Word(name) => environment = Some(name),
+                        _ => {
+                            return Err(self
+                                .error_at(&name, "deployment view requires an environment name"));
+                        }
+                    }
+                }
+                (self.word_value(&scope)? != "*").then_some(scope)
+            }
+            ViewKind::Filtered => {
+                // filtered <baseKey> <include|exclude> <tags>
+                let values = self.take_values();
+                let mut view = ViewDefinition::new(kind, String::new());
+                if let Some(base_key) = values.first() {
+                    view.extensions
+                        .insert("baseViewKey".to_string(), base_key.clone().into());
+                }
+                if let [_, mode, tags, ..] = values.as_slice() {
+                    let tags = split_tags(std::slice::from_ref(tags));
+                    if mode.eq_ignore_ascii_case("exclude") {
+                        view.exclude = tags;
+                    } else {
+                        view.include = tags;
+                    }
+                }
+                view.key = values
+                    .get(3)
+                    .cloned()
+                    .unwrap_or_else(|| self.generate_view_key(kind));
+                view.description = values.get(4).cloned();
+                self.views.push(PendingView { view, scope: None });
+                return self.end_statement();
+            }
+            _ => None,
+        };
+
+        let args = self.take_values();
+        let key = match args.first() {
+            Some(key) => key.clone(),
+            None => self.generate_view_key(kind),
+        };
+        let mut view = ViewDefinition::new(kind, key);
+        if kind == ViewKind::Custom {
+            view.title = args.get(1).cloned();
+            view.description = args.get(2).cloned();
+        } else {
+            view.description = args.get(1).cloned();
+        }
+        if let Some(environment) = environment {
+            view.extensions
+                .insert("environment".to_string(), environment.into());
+        }
+
+        if matches!(self.peek().kind, TokenKind::LBrace) {
+            self.block(|p| p.parse_view_body_statement(&mut view))?;
+        }
+
+        self.views.push(PendingView { view, scope });
+        self.end_statement()
+    }
+
+    fn parse_view_body_statement(
+        &mut self,
+        view: &mut ViewDefinition,
+    ) -> std::result::Result<(), ParseError> {
+        let token = self.peek().clone();
+        let keyword = match &token.kind {
+            TokenKind::Word(_) if self.peek_nth(1).kind == TokenKind::Arrow => {
+                // Dynamic view steps reference the model's relationships; nothing to record
+                return self.skip_statement();
+            }
+            TokenKind::LBrace => return self.skip_statement(),
+            TokenKind::Word(word) => word.to_ascii_lowercase(),
+            _ => return Err(self.unexpected(&token, "in view")),
+        };
+        self.advance();
+
+        match keyword.as_str() {
+            "include" => view.include.extend(self.take_values()),
+            "exclude" => view.exclude.extend(self.take_values()),
+            "autolayout" => {
+                let values = self.take_values();
+                let mut layout = AutoLayout::default();
+                if let Some(direction) = values.first() {
+                    layout.direction = RankDirection::parse(direction).ok_or_else(|| {
+                        self.error_at(
+                            &token,
+                            format!("unknown autoLayout direction `{}`", direction),
+                        )
+                    })?;
+                }
+                layout.rank_separation = values.get(1).and_then(|v| v.parse().ok());
+                layout.node_separation = values.get(2).and_then(|v| v.parse().ok());
+                view.auto_layout = Some(layout);
+            }
+            "title" => view.title = Some(self.expect_string("title")?),
+            "description" => view.description = Some(self.expect_string("description")?),
+            "default" => {}
+            "animation" | "properties" => {
+                self.take_values();
+                if matches!(self.peek().kind, TokenKind::LBrace) {
+                    self.skip_braces()?;
+                }
+            }
+            _ => return Err(self.unexpected(&token, "in view")),
+        }
+        self.end_statement()
+    }
+
+    fn parse_style_statement(&mut self) -> std::result::Result<(), ParseError> {
+        let token = self.advance();
+        let target = match &token.kind {
+            TokenKind::Word(word) if word.eq_ignore_ascii_case("element") => StyleTarget::Element,
+            TokenKind::Word(word) if word.eq_ignore_ascii_case("relationship") => {
+                StyleTarget::Relationship
+            }
+            TokenKind::Word(word)
+                if word.eq_ignore_ascii_case("light") || word.eq_ignore_ascii_case("dark") =>
+            {
+                // Color-scheme specific style sets; flatten them into the main list
+                self.block(|p| p.parse_style_statement())?;
+                return self.end_statement();
+            }
+            _ => return Err(self.unexpected(&token, "in styles")),
+        };
+
+        let tag = self.expect_string("style tag")?;
+        let mut properties = BTreeMap::new();
+        if matches!(self.peek().kind, TokenKind::LBrace) {
+            self.block(|p| {
+                let property = p.advance();
+                let name = p.word_value(&property)?;
+                if matches!(p.peek().kind, TokenKind::LBrace) {
+                    p.skip_braces()?;
+                } else {
+                    properties.insert(name, p.take_values().join(" "));
+                }
+                p.end_statement()
+            })?;
+        }
+
+        self.workspace.styles.push(StyleRule {
+            target,
+            tag,
+            properties,
+        });
+        self.end_statement()
+    }
+
+    fn parse_directive(&mut self) -> std::result::Result<(), ParseError> {
+        let directive = self.advance();
+        let TokenKind::Directive(name) = &directive.kind else {
+            return Err(self.unexpected(&directive, "where a directive was expected"));
+        };
+
+        match name.as_str() {
+            "!include" => self.include(&directive),
+            "!identifiers" => {
+                let mode = self.advance();
+                match self.word_value(&mode)?.to_ascii_lowercase().as_str() {
+                    "hierarchical" => self.hierarchical_identifiers = true,
+                    "flat" => self.hierarchical_identifiers = false,
+                    other => {
+                        return Err(
+                            self.error_at(&mode, format!("unknown identifier mode `{}`", other))
+                        );
+                    }
+                }
+                self.end_statement()
+            }
+            _ => {
+                log::debug!("Skipping unsupported directive {}", name);
+                self.take_values();
+                if matches!(self.peek().kind, TokenKind::LBrace) {
+                    self.skip_braces()?;
+                }
+                self.end_statement()
+            }
+        }
+    }
+
+    fn include(&mut self, directive: &Token) -> std::result::Result<(), ParseError> {
+        let target = self.advance();
+        let target_path = match &target.kind {
+            TokenKind::Word(value) | TokenKind::Str(value) => value.clone(),
+            _ => return Err(self.unexpected(&target, "after `!include`")),
+        };
+        self.end_statement()?;
+
+        let including = &self.sources[directive.source];
+        let Some(base_dir) = including
+            .path
+            .as_ref()
+            .map(|p| p.parent().unwrap_or(Path::new("")).to_path_buf())
+        else {
+            return Err(self.error_at(
+                directive,
+                "`!include` requires parsing from a file (use parse_workspace_file)",
+            ));
+        };
+        if including.depth + 1 > MAX_INCLUDE_DEPTH {
+            return Err(self.error_at(directive, "`!include` nested too deeply"));
+        }
+        if target_path.starts_with("http://") || target_path.starts_with("https://") {
+            return Err(self.error_at(&target, "including URLs is not supported"));
+        }
+        let depth = including.depth + 1;
+
+        let resolved = base_dir.join(&target_path);
+        let files = if resolved.is_dir() {
+            let mut files = std::fs::read_dir(&resolved)
+                .map_err(|e| self.error_at(&target, format!("failed to read directory: {}", e)))?
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|path| path.is_file())
+                .collect::<Vec<_>>();
+            files.sort();
+            files
+        } else {
+            vec![resolved]
+        };
+
+        let mut included = Vec::new();
+        for file in files {
+            let text = std::fs::read_to_string(&file).map_err(|e| {
+                self.error_at(
+                    &target,
+                    format!("failed to include {}: {}", file.display(), e),
+                )
+            })?;
+            let source = self.sources.len();
+            self.sources.push(SourceFile {
+                path: Some(file),
+                depth,
+            });
+            let mut tokens = tokenize(&text, source).map_err(|e| self.with_source(e, source))?;
+            tokens.retain(|t| t.kind != TokenKind::Eof);
+            included.extend(tokens);
+        }
+
+        self.tokens.splice(self.pos..self.pos, included);
+        Ok(())
+    }
+
+    /// Parse `properties { name value ... }`, with the current token being the opening brace
+    fn parse_properties(&mut self) -> std::result::Result<BTreeMap<String, String>, ParseError> {
+        let mut properties = BTreeMap::new();
+        if !matches!(self.peek().kind, TokenKind::LBrace) {
+            return Err(self.unexpected(&self.peek().clone(), "where `{` was expected"));
+        }
+        self.block(|p| {
+            let mut values = p.take_values().into_iter();
+            let Some(name) = values.next() else {
+                return Err(p.unexpected(&p.peek().clone(), "in properties"));
+            };
+            properties.insert(name, values.collect::<Vec<_>>().join(" "));
+            p.end_statement()
+        })?;
+        Ok(properties)
+    }
+
+    fn resolve_relationships(&mut self) -> std::result::Result<(), ParseError> {
+        for relationship in std::mem::take(&mut self.relationships) {
+            let from = match relationship.source_id {
+                Some(id) => id,
+                None => self.resolve_reference(&relationship.source)?,
+            };
+            let to = self.resolve_reference(&relationship.destination)?;
+
+            let mut edge = OptaEdge::new(from, to).with_tags(relationship.tags);
+            edge.label = relationship.description;
+            edge.technology = relationship.technology;
+            edge.properties = relationship.properties;
+            self.workspace.model.add_edge(edge);
+        }
+        Ok(())
+    }
+
+    fn resolve_views(&mut self) -> std::result::Result<(), ParseError> {
+        for PendingView { mut view, scope } in std::mem::take(&mut self.views) {
+            if let Some(scope) = scope {
+                view.scope = Some(self.resolve_reference(&scope)?);
+            }
+            self.workspace.views.push(view);
+        }
+        Ok(())
+    }
+
+    fn resolve_reference(&self, token: &Token) -> std::result::Result<String, ParseError> {
+        let name = self.word_value(token)?;
+        if self.node_ids.contains(&name) {
+            return Ok(name);
+        }
+        Err(self.error_at(token, format!("unknown element `{}`", name)))
+    }
+
+    fn generate_id(&mut self, name: &str) -> String {
+        let mut base: String = name
+            .chars()
+            .map(|c| if c.is_alphanumeric() { c } else { '_' })
+            .collect();
+        if base.is_empty() {
+            base = "element".to_string();
+        }
+
+        let mut id = base.clone();
+        let mut suffix = 2;
+        while self.node_ids.contains(&id) {
+            id = format!("{}_{}", base, suffix);
+            suffix += 1;
+        }
+        id
+    }
+
+    fn generate_view_key(&mut self, kind: ViewKind) -> String {
+        let count = self.view_counts.entry(kind.key_prefix()).or_insert(0);
+        *count += 1;
+        format!("{}-{:03}", kind.key_prefix(), count)
+    }
+
+    /// Parse `{ statement* }`, with the current token being the opening brace
+    fn block(
+        &mut self,
+        mut statement: impl FnMut(&mut Self) -> std::result::Result<(), ParseError>,
+    ) -> std::result::Result<(), ParseError> {
+        let open = self.advance();
+        if open.kind != TokenKind::LBrace {
+            return Err(self.unexpected(&open, "where `{` was expected"));
+        }
+
+        loop {
+            self.skip_newlines();
+            match self.peek().kind {
+                TokenKind::RBrace => {
+                    self.advance();
+                    return Ok(());
+                }
+                TokenKind::Eof => {
+                    return Err(self.error_at(&open, "unclosed block: expected `}`"));
+                }
+                _ => statement(self)?,
+            }
+        }
+    }
+
+    /// Skip the rest of the current statement, including any block it opens
+    fn skip_statement(&mut self) -> std::result::Result<(), ParseError> {
+        loop {
+            match self.peek().kind {
+                TokenKind::Newline | TokenKind::RBrace | TokenKind::Eof => break,
+                TokenKind::LBrace => self.skip_braces()?,
+                _ => {
+                    self.advance();
+                }
+            }
+        }
+        self.end_statement()
+    }
+
+    fn skip_block_statement(&mut self) -> std::result::Result<(), ParseError> {
+        if matches!(self.peek().kind, TokenKind::LBrace) {
+            self.skip_braces()?;
+        }
+        self.end_statement()
+    }
+
+    fn skip_braces(&mut self) -> std::result::Result<(), ParseError> {
+        let open = self.advance();
+        let mut depth = 1;
+        while depth > 0 {
+            let token = self.advance();
+            match token.kind {
+                TokenKind::LBrace => depth += 1,
+                TokenKind::RBrace => depth -= 1,
+                TokenKind::Eof => return Err(self.error_at(&open, "unclosed block: expected `}`")),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn end_statement(&mut self) -> std::result::Result<(), ParseError> {
+        match self.peek().kind {
+            TokenKind::Newline => {
+                self.advance();
+                Ok(())
+            }
+            TokenKind::RBrace | TokenKind::Eof => Ok(()),
+            _ => Err(self.unexpected(&self.peek().clone(), "at end of statement")),
+        }
+    }
+
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos.min(self.tokens.len() - 1)]
+    }
+
+    fn peek_nth(&self, n: usize) -> &Token {
+        &self.tokens[(self.pos + n).min(self.tokens.len() - 1)]
+    }
+
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(&self.peek().kind, TokenKind::Word(word) if word.eq_ignore_ascii_case(keyword))
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.peek().clone();
+        if token.kind != TokenKind::Eof {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn skip_newlines(&mut self) {
+        while self.peek().kind == TokenKind::Newline {
+            self.advance();
+        }
+    }
+
+    fn take_strings(&mut self) -> Vec<String> {
+        let mut values = Vec::new();
+        while let TokenKind::Str(value) = &self.peek().kind {
+            values.push(value.clone());
+            self.advance();
+        }
+        values
+    }
+
+    /// Collect the remaining words and strings on the current line
+    fn take_values(&mut self) -> Vec<String> {
+        let mut values = Vec::new();
+        while let TokenKind::Word(value) | TokenKind::Str(value) = &self.peek().kind {
+            values.push(value.clone());
+            self.advance();
+        }
+        values
+    }
+
+    fn expect_string(&mut self, what: &str) -> std::result::Result<String, ParseError> {
+        let token = self.advance();
+        match token.kind {
+            TokenKind::Str(value) => Ok(value),
+            _ => Err(self.error_at(&token, format!("expected {} as a quoted string", what))),
+        }
+    }
+
+    fn word_value(&self, token: &Token) -> std::result::Result<String, ParseError> {
+        match &token.kind {
+            TokenKind::Word(value) => Ok(value.clone()),
+            _ => Err(self.unexpected(token, "where an identifier was expected")),
+        }
+    }
+
+    fn unexpected(&self, token: &Token, context: &str) -> ParseError {
+        let found = match &token.kind {
+            TokenKind::Word(word) => format!("`{}`", word),
+            TokenKind::Str(value) => format!("string \"{}\"", value),
+            TokenKind::Directive(name) => format!("directive `{}`", name),
+            TokenKind::LBrace => "`{`".to_string(),
+            TokenKind::RBrace => "`}`".to_string(),
+            TokenKind::Arrow => "`->`".to_string(),
+            TokenKind::Equals => "`=`".to_string(),
+            TokenKind::Newline => "end of line".to_string(),
+            TokenKind::Eof => "end of input".to_string(),
+        };
+        self.error_at(token, format!("unexpected {} {}", found, context))
+    }
+
+    fn error_at(&self, token: &Token, message: impl Into<String>) -> ParseError {
+        self.with_source(ParseError::new(message, token.span), token.source)
+    }
+
+    fn with_source(&self, mut error: ParseError, source: usize) -> ParseError {
+        error.source_name = self
+            .sources
+            .get(source)
+            .and_then(|s| s.path.as_ref())
+            .map(|p| p.display().to_string());
+        error
+    }
+}
+
+/// Split DSL tag arguments: each argument may itself be a comma-separated list
+fn split_tags(args: &[String]) -> Vec<String> {
+    args.iter()
+        .flat_map(|arg| arg.split(','))
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn node_type_name(node_type: &NodeType) -> &'static str {
+    match node_type {
+        NodeType::System => "softwareSystem",
+        NodeType::Container => "container",
+        NodeType::Component => "component",
+        NodeType::Person => "person",
+    }
 }
 
 #[cfg(test)]
@@ -204,4 +1222,214 @@ Auth -> DB "reads credentials"
         assert!(model.find_node("User").is_some());
         assert!(model.find_node("Auth").is_some());
     }
+
+    #[test]
+    fn test_parse_structurizr_workspace() {
+        let dsl = r#"
+workspace "Big Bank" "Internet banking" {
+    !identifiers flat
+
+    model {
+        customer = person "Customer" "A bank customer" "External"
+
+        bank = softwareSystem "Internet Banking" {
+            description "Allows customers to view accounts"
+            web = container "Web App" "Delivers the SPA" "Java and Spring MVC"
+            api = container "API Application" "JSON/HTTPS API" "Java" {
+                signin = component "Sign In Controller" "Handles sign in" "Spring MVC" {
+                    tags "Controller, Security"
+                    properties {
+                        owner "Identity Team"
+                    }
+                }
+                -> db "Reads from" "JDBC"
+            }
+            db = container "Database" "Stores accounts" "Oracle" "Database"
+        }
+
+        customer -> web "Visits" "HTTPS"
+        web -> api {
+            tags "Async"
+        }
+        signin -> db "Reads credentials"
+    }
+
+    views {
+        systemContext bank "Context" {
+            include *
+            autoLayout lr 300 100
+        }
+        container bank {
+            include *
+        }
+        styles {
+            element "Database" {
+                shape Cylinder
+                background #1168bd
+            }
+        }
+    }
+}
+"#;
+        let workspace = parse_workspace::<TestBackend>(dsl).unwrap();
+        assert_eq!(workspace.name.as_deref(), Some("Big Bank"));
+
+        let model = &workspace.model;
+        assert_eq!(model.node_count(), 6);
+        assert_eq!(model.edge_count(), 4);
+
+        let bank = model.find_node("bank").unwrap();
+        assert_eq!(bank.node_type, NodeType::System);
+        assert_eq!(bank.parent, None);
+        assert_eq!(
+            bank.description.as_deref(),
+            Some("Allows customers to view accounts")
+        );
+
+        let api = model.find_node("api").unwrap();
+        assert_eq!(api.parent.as_deref(), Some("bank"));
+        assert_eq!(api.description.as_deref(), Some("JSON/HTTPS API"));
+        assert_eq!(api.technology.as_deref(), Some("Java"));
+
+        let signin = model.find_node("signin").unwrap();
+        assert_eq!(signin.parent.as_deref(), Some("api"));
+        assert_eq!(signin.tags, vec!["Controller", "Security"]);
+        assert_eq!(
+            signin.properties.get("owner").map(String::as_str),
+            Some("Identity Team")
+        );
+
+        assert_eq!(model.find_node("customer").unwrap().tags, vec!["External"]);
+        assert_eq!(model.find_node("db").unwrap().tags, vec!["Database"]);
+
+        let implicit = model.edges.iter().find(|e| e.from == "api").unwrap();
+        assert_eq!(implicit.to, "db");
+        assert_eq!(implicit.technology.as_deref(), Some("JDBC"));
+        let async_edge = model.edges.iter().find(|e| e.from == "web").unwrap();
+        assert_eq!(async_edge.tags, vec!["Async"]);
+
+        assert_eq!(workspace.views.len(), 2);
+        let context = workspace.find_view("Context").unwrap();
+        assert_eq!(context.kind, ViewKind::SystemContext);
+        assert_eq!(context.scope.as_deref(), Some("bank"));
+        assert_eq!(context.include, vec!["*"]);
+        let layout = context.auto_layout.unwrap();
+        assert_eq!(layout.direction, RankDirection::LeftRight);
+        assert_eq!(layout.rank_separation, Some(300));
+        assert!(workspace.find_view("Container-001").is_some());
+
+        assert_eq!(workspace.styles.len(), 1);
+        assert_eq!(workspace.styles[0].tag, "Database");
+        assert_eq!(
+            workspace.styles[0]
+                .properties
+                .get("background")
+                .map(String::as_str),
+            Some("#1168bd")
+        );
+    }
+
+    #[test]
+    fn test_hierarchical_identifiers() {
+        let dsl = r#"
+!identifiers hierarchical
+model {
+    ss = softwareSystem "Shop" {
+        api = container "API" {
+            orders = component "Orders"
+        }
+        db = container "DB"
+    }
+    ss.api.orders -> ss.db "writes"
+}
+"#;
+        let model = parse_c4_dsl::<TestBackend>(dsl).unwrap();
+        assert_eq!(
+            model.find_node("ss.api.orders").unwrap().parent.as_deref(),
+            Some("ss.api")
+        );
+        assert_eq!(model.edges[0].from, "ss.api.orders");
+        assert_eq!(model.edges[0].to, "ss.db");
+    }
+
+    #[test]
+    fn test_comments_groups_and_unnamed_elements() {
+        let dsl = r#"
+// Line comment
+# Hash comment
+/* Block
+   comment */
+group "Internal" {
+    softwareSystem "Billing System"
+    user = person "User"
+}
+"#;
+        let model = parse_c4_dsl::<TestBackend>(dsl).unwrap();
+        assert_eq!(model.node_count(), 2);
+        assert!(model.find_node("Billing_System").is_some());
+    }
+
+    #[test]
+    fn test_error_reports_line_and_column() {
+        let dsl = "container API \"API\"\nAPI -> Missing \"calls\"\n";
+        let error = parse_workspace::<TestBackend>(dsl).unwrap_err();
+        assert_eq!(error.span.line, 2);
+        assert_eq!(error.span.column, 8);
+        assert!(error.to_string().contains("unknown element `Missing`"));
+
+        let error = parse_workspace::<TestBackend>("model {\n  a = person \"A\"\n").unwrap_err();
+        assert_eq!((error.span.line, error.span.column), (1, 7));
+    }
+
+    #[test]
+    fn test_invalid_nesting_is_rejected() {
+        let dsl = r#"
+model {
+    p = person "User" {
+        c = container "Nope"
+    }
+}
+"#;
+        let error = parse_workspace::<TestBackend>(dsl).unwrap_err();
+        assert_eq!(error.span.line, 4);
+        assert!(error.message.contains("cannot be nested"));
+    }
+
+    #[test]
+    fn test_include_from_file() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("people.dsl"),
+            "user = person \"User\"\nadmin = person \"Admin\"\n",
+        )
+        .unwrap();
+        let main = dir.path().join("workspace.dsl");
+        std::fs::write(
+            &main,
+            "workspace {\n  model {\n    !include people.dsl // shared with other workspaces\n    app = softwareSystem \"App\"\n    user -> app \"uses\"\n  }\n}\n",
+        )
+        .unwrap();
+
+        let workspace = parse_workspace_file::<TestBackend>(&main).unwrap();
+        assert_eq!(workspace.model.node_count(), 3);
+        assert_eq!(workspace.model.edge_count(), 1);
+
+        let error = parse_workspace::<TestBackend>("!include people.dsl").unwrap_err();
+        assert!(error.message.contains("requires parsing from a file"));
+    }
+
+    #[test]
+    fn test_include_url_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let main = dir.path().join("workspace.dsl");
+        std::fs::write(
+            &main,
+            "workspace {\n  model {\n    !include https://example.com/people.dsl\n  }\n}\n",
+        )
+        .unwrap();
+
+        let error = parse_workspace_file::<TestBackend>(&main).unwrap_err();
+        assert_eq!(error.span.line, 3);
+        assert!(error.message.contains("including URLs is not supported"));
+    }
 }
@@ -0,0 +1,382 @@
+use super::{ParseError, Span};
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum TokenKind {
+    /// Bare word: keywords, identifiers, `*`, colors such as `#1168bd`
+    Word(String),
+    /// Quoted string (`"..."`) or text block (`"""..."""`), with escapes resolved
+    Str(String),
+    /// Preprocessor-style directive such as `!include` or `!identifiers`
+    Directive(String),
+    LBrace,
+    RBrace,
+    Arrow,
+    Equals,
+    Newline,
+    Eof,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct Token {
+    pub kind: TokenKind,
+    pub span: Span,
+    /// Index into the parser's source list, so errors in `!include`d files point at the right file
+    pub source: usize,
+}
+
+/// Split Structurizr DSL text into tokens.
+///
+/// The DSL is line-oriented, so newlines are significant and emitted as tokens
+/// (consecutive blank lines collapse into one). Comments (`//`, `/* */`, and `#`
+/// at the start of a line) are dropped. Comments only start where a token
+/// could, and never at the argument of a directive, since paths and URLs may
+/// start with `//` or `/*`; a comment after the argument is fine.
+pub(crate) fn tokenize(input: &str, source: usize) -> Result<Vec<Token>, ParseError> {
+    Lexer {
+        input,
+        chars: input.char_indices().peekable(),
+        line: 1,
+        column: 1,
+        source,
+        tokens: Vec::new(),
+    }
+    .run()
+}
+
+struct Lexer<'a> {
+    input: &'a str,
+    chars: std::iter::Peekable<std::str::CharIndices<'a>>,
+    line: usize,
+    column: usize,
+    source: usize,
+    tokens: Vec<Token>,
+}
+
+impl Lexer<'_> {
+    fn run(mut self) -> Result<Vec<Token>, ParseError> {
+        while let Some(&(offset, ch)) = self.chars.peek() {
+            let (line, column) = (self.line, self.column);
+            match ch {
+                '\n' => {
+                    self.bump();
+                    self.push_newline(offset, line, column);
+                }
+                c if c.is_whitespace() => {
+                    self.bump();
+                }
+                '\\' if self.peek_second() == Some('\n') => {
+                    // Line continuation: the statement carries on to the next line
+                    self.bump();
+                    self.bump();
+                }
+                '/' if self.peek_second() == Some('/') && !self.at_directive_argument() => {
+                    self.skip_line()
+                }
+                '/' if self.peek_second() == Some('*') && !self.at_directive_argument() => {
+                    self.skip_block_comment(offset)?
+                }
+                '#' if self.at_line_start() => self.skip_line(),
+                '{' => {
+                    self.bump();
+                    self.push(TokenKind::LBrace, offset, offset + 1, line, column);
+                }
+                '}' => {
+                    self.bump();
+                    self.push(TokenKind::RBrace, offset, offset + 1, line, column);
+                }
+                '=' => {
+                    self.bump();
+                    self.push(TokenKind::Equals, offset, offset + 1, line, column);
+                }
+                '-' if self.peek_second() == Some('>') => {
+                    self.bump();
+                    self.bump();
+                    self.push(TokenKind::Arrow, offset, offset + 2, line, column);
+                }
+                '"' => self.lex_string(offset)?,
+                _ => self.lex_word(offset),
+            }
+        }
+
+        let end = self.input.len();
+        let (line, column) = (self.line, self.column);
+        self.push_newline(end, line, column);
+        self.push(TokenKind::Eof, end, end, line, column);
+        Ok(self.tokens)
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let (_, ch) = self.chars.next()?;
+        if ch == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(ch)
+    }
+
+    fn peek_second(&self) -> Option<char> {
+        let mut lookahead = self.chars.clone();
+        lookahead.next();
+        lookahead.next().map(|(_, ch)| ch)
+    }
+
+    fn offset(&mut self) -> usize {
+        self.chars
+            .peek()
+            .map(|&(offset, _)| offset)
+            .unwrap_or(self.input.len())
+    }
+
+    fn at_line_start(&self) -> bool {
+        matches!(
+            self.tokens.last().map(|t| &t.kind),
+            None | Some(TokenKind::Newline)
+        )
+    }
+
+    fn at_directive_argument(&self) -> bool {
+        matches!(
+            self.tokens.last().map(|t| &t.kind),
+            Some(TokenKind::Directive(_))
+        )
+    }
+
+    fn push(&mut self, kind: TokenKind, start: usize, end: usize, line: usize, column: usize) {
+        self.tokens.push(Token {
+            kind,
+            span: Span {
+                start,
+                end,
+                line,
+                column,
+            },
+            source: self.source,
+        });
+    }
+
+    fn push_newline(&mut self, offset: usize, line: usize, column: usize) {
+        if !self.at_line_start() {
+            self.push(TokenKind::Newline, offset, offset, line, column);
+        }
+    }
+
+    fn skip_line(&mut self) {
+        while let Some(&(_, ch)) = self.chars.peek() {
+            if ch == '\n' {
+                break;
+            }
+            self.bump();
+        }
+    }
+
+    fn skip_block_comment(&mut self, start: usize) -> Result<(), ParseError> {
+        let (line, column) = (self.line, self.column);
+        self.bump();
+        self.bump();
+        while let Some(ch) = self.bump() {
+            if ch == '*' && self.chars.peek().map(|&(_, c)| c) == Some('/') {
+                self.bump();
+                return Ok(());
+            }
+        }
+        Err(self.error("unterminated block comment", start, line, column))
+    }
+
+    fn lex_string(&mut self, start: usize) -> Result<(), ParseError> {
+        let (line, column) = (self.line, self.column);
+        if self.input[start..].starts_with("\"\"\"") {
+            return self.lex_text_block(start, line, column);
+        }
+
+        self.bump();
+        let mut value = String::new();
+        loop {
+            match self.bump() {
+                Some('"') => break,
+                Some('\\') => match self.bump() {
+                    Some('n') => value.push('\n'),
+                    Some('t') => value.push('\t'),
+                    Some(other) => value.push(other),
+                    None => break,
+                },
+                Some('\n') | None => {
+                    return Err(self.error("unterminated string literal", start, line, column));
+                }
+                Some(ch) => value.push(ch),
+            }
+        }
+
+        let end = self.offset();
+        self.push(TokenKind::Str(value), start, end, line, column);
+        Ok(())
+    }
+
+    fn lex_text_block(
+        &mut self,
+        start: usize,
+        line: usize,
+        column: usize,
+    ) -> Result<(), ParseError> {
+        for _ in 0..3 {
+            self.bump();
+        }
+        let body_start = self.offset();
+        let Some(body_len) = self.input[body_start..].find("\"\"\"") else {
+            return Err(self.error("unterminated text block", start, line, column));
+        };
+        let body = &self.input[body_start..body_start + body_len];
+        let value = dedent(body);
+
+        for _ in 0..body.chars().count() + 3 {
+            self.bump();
+        }
+        let end = self.offset();
+        self.push(TokenKind::Str(value), start, end, line, column);
+        Ok(())
+    }
+
+    fn lex_word(&mut self, start: usize) {
+        let (line, column) = (self.line, self.column);
+        let mut value = String::new();
+        while let Some(&(_, ch)) = self.chars.peek() {
+            if ch.is_whitespace() || matches!(ch, '{' | '}' | '"' | '=') {
+                break;
+            }
+            if ch == '-' && self.peek_second() == Some('>') {
+                break;
+            }
+            value.push(ch);
+            self.bump();
+        }
+
+        let end = self.offset();
+        let kind = if value.len() > 1 && value.starts_with('!') {
+            TokenKind::Directive(value.to_ascii_lowercase())
+        } else {
+            TokenKind::Word(value)
+        };
+        self.push(kind, start, end, line, column);
+    }
+
+    fn error(&self, message: &str, start: usize, line: usize, column: usize) -> ParseError {
+        ParseError::new(
+            message,
+            Span {
+                start,
+                end: self.input.len(),
+                line,
+                column,
+            },
+        )
+    }
+}
+
+/// Strip the common leading indentation from a `"""` text block
+fn dedent(body: &str) -> String {
+    let body = body.strip_prefix('\n').unwrap_or(body);
+    let indent = body
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+
+    body.lines()
+        .map(|line| line.get(indent..).unwrap_or(line.trim_start()))
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim_end()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(input: &str) -> Vec<TokenKind> {
+        tokenize(input, 0)
+            .unwrap()
+            .into_iter()
+            .map(|t| t.kind)
+            .collect()
+    }
+
+    #[test]
+    fn test_tokenize_relationship() {
+        assert_eq!(
+            kinds(r#"a->b "uses""#),
+            vec![
+                TokenKind::Word("a".to_string()),
+                TokenKind::Arrow,
+                TokenKind::Word("b".to_string()),
+                TokenKind::Str("uses".to_string()),
+                TokenKind::Newline,
+                TokenKind::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_comments_and_colors() {
+        let tokens = kinds(
+            "# heading comment\nbackground #1168bd // trailing\n/* block\ncomment */ shape Person",
+        );
+        assert_eq!(
+            tokens,
+            vec![
+                TokenKind::Word("background".to_string()),
+                TokenKind::Word("#1168bd".to_string()),
+                TokenKind::Newline,
+                TokenKind::Word("shape".to_string()),
+                TokenKind::Word("Person".to_string()),
+                TokenKind::Newline,
+                TokenKind::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_directive_arguments_are_not_comments() {
+        assert_eq!(
+            kinds(
+                "!include https://example.com/model.dsl // shared model\n!include //server/share/*.dsl /* all */"
+            ),
+            vec![
+                TokenKind::Directive("!include".to_string()),
+                TokenKind::Word("https://example.com/model.dsl".to_string()),
+                TokenKind::Newline,
+                TokenKind::Directive("!include".to_string()),
+                TokenKind::Word("//server/share/*.dsl".to_string()),
+                TokenKind::Newline,
+                TokenKind::Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_text_block_and_escapes() {
+        let tokens = kinds(
+            "description \"\"\"\n    first\n      second\n    \"\"\"\nname \"say \\\"hi\\\"\"",
+        );
+        assert_eq!(tokens[1], TokenKind::Str("first\n  second".to_string()));
+        assert_eq!(tokens[4], TokenKind::Str("say \"hi\"".to_string()));
+    }
+
+    #[test]
+    fn test_spans_track_lines_and_columns() {
+        let tokens = tokenize("model {\n  user = person \"User\"\n}", 0).unwrap();
+        let person = tokens
+            .iter()
+            .find(|t| t.kind == TokenKind::Word("person".to_string()))
+            .unwrap();
+        assert_eq!((person.span.line, person.span.column), (2, 10));
+    }
+
+    #[test]
+    fn test_unterminated_string_reports_position() {
+        let error = tokenize("a -> b\nb -> c \"oops", 0).unwrap_err();
+        assert_eq!((error.span.line, error.span.column), (2, 8));
+    }
+}
@@ -0,0 +1,432 @@
+//! Architecture rules (fitness functions) checked against an `OptaModel`
+//!
+//! Rules are declared as JSON so they can live next to the workspace and be
+//! checked in CI:
+//!
+//! ```json
+//! {
+//!   "rules": [
+//!     {
+//!       "name": "web must not reach into db",
+//!       "constraint": "forbidden_dependency",
+//!       "from": { "type": "Component", "tag": "web" },
+//!       "to": { "tag": "db" }
+//!     },
+//!     {
+//!       "name": "services only talk to the gateway",
+//!       "constraint": "allowed_dependencies",
+//!       "from": { "name": "*Service" },
+//!       "to": [{ "id": "gateway" }, { "tag": "Database" }]
+//!     },
+//!     {
+//!       "name": "containers declare a technology",
+//!       "constraint": "required",
+//!       "select": { "type": "Container" },
+//!       "require": "technology",
+//!       "severity": 0.5
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! Violations are reported as [`AntiPattern::RuleViolation`], so rules added to
+//! [`AntiPatternConfig::rules`](crate::AntiPatternConfig) show up in DOT/SVG
+//! coloring and pattern telemetry like any built-in detector.
+
+use crate::{AntiPattern, NodeType, OptaModel, OptaNode, Result};
+use burn::tensor::backend::Backend;
+use serde::{Deserialize, Serialize};
+
+/// Matches elements by type, tag, name and identifier; unset fields match anything
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct Selector {
+    #[serde(default, rename = "type", skip_serializing_if = "Option::is_none")]
+    pub node_type: Option<NodeType>,
+    /// Tag the element must carry (case-insensitive)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    /// Element name; `*` matches any run of characters
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+}
+
+impl Selector {
+    pub fn matches<B: Backend>(&self, node: &OptaNode<B>) -> bool {
+        self.node_type.as_ref().is_none_or(|t| *t == node.node_type)
+            && self.tag.as_deref().is_none_or(|tag| node.has_tag(tag))
+            && self
+                .name
+                .as_deref()
+                .is_none_or(|pattern| wildcard_match(pattern, &node.name))
+            && self.id.as_deref().is_none_or(|id| id == node.id)
+    }
+}
+
+/// Attribute an element must have for a [`Constraint::Required`] rule
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Requirement {
+    Technology,
+    Description,
+    /// The element must be nested inside another element
+    Parent,
+    Tag(String),
+    Property(String),
+}
+
+impl Requirement {
+    fn is_met<B: Backend>(&self, node: &OptaNode<B>) -> bool {
+        let non_empty = |value: &Option<String>| value.as_deref().is_some_and(|v| !v.is_empty());
+        match self {
+            Requirement::Technology => non_empty(&node.technology),
+            Requirement::Description => non_empty(&node.description),
+            Requirement::Parent => node.parent.is_some(),
+            Requirement::Tag(tag) => node.has_tag(tag),
+            Requirement::Property(key) => node.properties.contains_key(key),
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Requirement::Technology => "a technology".to_string(),
+            Requirement::Description => "a description".to_string(),
+            Requirement::Parent => "a parent element".to_string(),
+            Requirement::Tag(tag) => format!("the tag '{}'", tag),
+            Requirement::Property(key) => format!("the property '{}'", key),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "constraint", rename_all = "snake_case")]
+pub enum Constraint {
+    /// Elements matching `from` must not depend on elements matching `to`
+    ForbiddenDependency { from: Selector, to: Selector },
+    /// Elements matching `from` may only depend on elements matching one of `to`
+    AllowedDependencies { from: Selector, to: Vec<Selector> },
+    /// Every element matching `select` must satisfy `require`
+    Required {
+        select: Selector,
+        require: Requirement,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ArchitectureRule {
+    pub name: String,
+    #[serde(flatten)]
+    pub constraint: Constraint,
+    /// Severity of each violation; 1.0 colors violating elements like a cycle
+    #[serde(default = "default_severity")]
+    pub severity: f32,
+}
+
+fn default_severity() -> f32 {
+    1.0
+}
+
+impl ArchitectureRule {
+    pub fn new(name: impl Into<String>, constraint: Constraint) -> Self {
+        Self {
+            name: name.into(),
+            constraint,
+            severity: default_severity(),
+        }
+    }
+
+    pub fn with_severity(mut self, severity: f32) -> Self {
+        self.severity = severity;
+        self
+    }
+
+    /// Check the rule against `model`, one violation per offending element or dependency
+    pub fn check<B: Backend>(&self, model: &OptaModel<B>) -> Vec<AntiPattern> {
+        match &self.constraint {
+            Constraint::ForbiddenDependency { from, to } => model
+                .edges
+                .iter()
+                .filter_map(|edge| {
+                    let source = model.find_node(&edge.from).filter(|n| from.matches(n))?;
+                    let target = model.find_node(&edge.to).filter(|n| to.matches(n))?;
+                    Some(self.violation(
+                        vec![source.id.clone(), target.id.clone()],
+                        format!("{} must not depend on {}", source.id, target.id),
+                        format!(
+                            "Remove the dependency from {} to {} or route it through an allowed element",
+                            source.id, target.id
+                        ),
+                    ))
+                })
+                .collect(),
+            Constraint::AllowedDependencies { from, to } => model
+                .edges
+                .iter()
+                .filter_map(|edge| {
+                    let source = model.find_node(&edge.from).filter(|n| from.matches(n))?;
+                    let target = model
+                        .find_node(&edge.to)
+                        .filter(|n| !to.iter().any(|selector| selector.matches(n)))?;
+                    Some(self.violation(
+                        vec![source.id.clone(), target.id.clone()],
+                        format!("{} may not depend on {}", source.id, target.id),
+                        format!(
+                            "Replace the dependency from {} to {} with one on an allowed element",
+                            source.id, target.id
+                        ),
+                    ))
+                })
+                .collect(),
+            Constraint::Required { select, require } => model
+                .nodes
+                .iter()
+                .filter(|node| select.matches(node) && !require.is_met(node))
+                .map(|node| {
+                    self.violation(
+                        vec![node.id.clone()],
+                        format!("{} lacks {}", node.id, require.describe()),
+                        format!("Add {} to {}", require.describe(), node.id),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    fn violation(&self, nodes: Vec<String>, message: String, remediation: String) -> AntiPattern {
+        AntiPattern::RuleViolation {
+            rule: self.name.clone(),
+            nodes,
+            message,
+            remediation,
+            severity: self.severity,
+        }
+    }
+}
+
+/// A set of architecture rules, as read from a rules file
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct RuleSet {
+    pub rules: Vec<ArchitectureRule>,
+}
+
+impl RuleSet {
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json).map_err(|e| anyhow::anyhow!("Invalid rules file: {}", e))
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Check every rule, returning all violations in rule order
+    pub fn check<B: Backend>(&self, model: &OptaModel<B>) -> Vec<AntiPattern> {
+        check_rules(model, &self.rules)
+    }
+}
+
+/// Check `rules` against `model`, returning all violations in rule order
+pub fn check_rules<B: Backend>(
+    model: &OptaModel<B>,
+    rules: &[ArchitectureRule],
+) -> Vec<AntiPattern> {
+    rules.iter().flat_map(|rule| rule.check(model)).collect()
+}
+
+/// Glob-style match where `*` stands for any (possibly empty) run of characters
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let Some(first) = parts.next() else {
+        return text.is_empty();
+    };
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        // No `*` in the pattern
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(idx) => rest = &rest[idx + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::OptaEdge;
+    use crate::{AntiPatternConfig, detect_anti_patterns, to_dot};
+    use burn::backend::ndarray::NdArray;
+
+    type TestBackend = NdArray<f32>;
+
+    fn layered_model() -> OptaModel<TestBackend> {
+        let mut model = OptaModel::new();
+        model.add_node(
+            OptaNode::new(
+                "ui".to_string(),
+                "Checkout UI".to_string(),
+                NodeType::Component,
+            )
+            .with_tags(vec!["web".to_string()]),
+        );
+        model.add_node(
+            OptaNode::new(
+                "orders".to_string(),
+                "OrderService".to_string(),
+                NodeType::Container,
+            )
+            .with_technology("Rust".to_string()),
+        );
+        model.add_node(
+            OptaNode::new(
+                "store".to_string(),
+                "Order Store".to_string(),
+                NodeType::Container,
+            )
+            .with_tags(vec!["db".to_string()]),
+        );
+        model.add_edge(OptaEdge::new("ui".to_string(), "orders".to_string()));
+        model.add_edge(OptaEdge::new("ui".to_string(), "store".to_string()));
+        model.add_edge(OptaEdge::new("orders".to_string(), "store".to_string()));
+        model
+    }
+
+    const RULES: &str = r#"{
+        "rules": [
+            {
+                "name": "web must not reach into db",
+                "constraint": "forbidden_dependency",
+                "from": { "type": "Component", "tag": "web" },
+                "to": { "tag": "db" }
+            },
+            {
+                "name": "services only call the gateway",
+                "constraint": "allowed_dependencies",
+                "from": { "name": "*Service" },
+                "to": [{ "id": "gateway" }]
+            },
+            {
+                "name": "containers declare a technology",
+                "constraint": "required",
+                "select": { "type": "Container" },
+                "require": "technology",
+                "severity": 0.5
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn test_rules_report_violations() {
+        let rules = RuleSet::from_json(RULES).unwrap();
+        let violations = rules.check(&layered_model());
+
+        let summary: Vec<(String, Vec<String>, f32)> = violations
+            .iter()
+            .map(|v| match v {
+                AntiPattern::RuleViolation {
+                    rule,
+                    nodes,
+                    severity,
+                    ..
+                } => (rule.clone(), nodes.clone(), *severity),
+                other => panic!("unexpected pattern {:?}", other),
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    "web must not reach into db".to_string(),
+                    vec!["ui".to_string(), "store".to_string()],
+                    1.0
+                ),
+                (
+                    "services only call the gateway".to_string(),
+                    vec!["orders".to_string(), "store".to_string()],
+                    1.0
+                ),
+                (
+                    "containers declare a technology".to_string(),
+                    vec!["store".to_string()],
+                    0.5
+                ),
+            ]
+        );
+        assert_eq!(
+            violations[2].description(),
+            "Rule 'containers declare a technology' violated: store lacks a technology"
+        );
+        assert_eq!(violations[2].remediation(), "Add a technology to store");
+    }
+
+    #[test]
+    fn test_rules_flow_into_anti_patterns_and_dot() {
+        let model = layered_model();
+        let config = AntiPatternConfig {
+            rules: vec![ArchitectureRule::new(
+                "no web to db",
+                Constraint::ForbiddenDependency {
+                    from: Selector {
+                        tag: Some("web".to_string()),
+                        ..Default::default()
+                    },
+                    to: Selector {
+                        tag: Some("DB".to_string()),
+                        ..Default::default()
+                    },
+                },
+            )],
+            ..Default::default()
+        };
+
+        let patterns = detect_anti_patterns(&model, &config).unwrap();
+        assert!(
+            patterns
+                .iter()
+                .any(|p| p.kind() == "rule_violation" && p.affected_nodes() == ["ui", "store"])
+        );
+
+        let dot = to_dot(&model, Some(&config)).unwrap();
+        assert!(dot.contains("\"ui\" [label=\"Checkout UI\", fillcolor=\"#ff4444\""));
+        assert!(dot.contains("// Rule violation: no web to db: ui must not depend on store"));
+    }
+
+    #[test]
+    fn test_rule_set_round_trip() {
+        let rules = RuleSet::from_json(RULES).unwrap();
+        assert_eq!(
+            rules.rules[2].constraint,
+            Constraint::Required {
+                select: Selector {
+                    node_type: Some(NodeType::Container),
+                    ..Default::default()
+                },
+                require: Requirement::Technology,
+            }
+        );
+        let reparsed = RuleSet::from_json(&rules.to_json().unwrap()).unwrap();
+        assert_eq!(reparsed, rules);
+
+        assert!(
+            RuleSet::from_json(r#"{"rules": [{"name": "x", "constraint": "bogus"}]}"#).is_err()
+        );
+    }
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("*Service", "OrderService"));
+        assert!(wildcard_match("Order*", "OrderService"));
+        assert!(wildcard_match("*der*vi*", "OrderService"));
+        assert!(wildcard_match("OrderService", "OrderService"));
+        assert!(wildcard_match("*", ""));
+        assert!(!wildcard_match("*Service", "OrderServices"));
+        assert!(!wildcard_match("Order", "OrderService"));
+        assert!(!wildcard_match("a*a", "a"));
+    }
+}
@@ -0,0 +1,1130 @@
+//! Import and export of the Structurizr workspace JSON format
+//!
+//! Elements, relationships, tags, properties, views (with stored element
+//! positions) and styles map onto [`Workspace`]. Everything else in the
+//! document (documentation, deployment nodes, branding, per-element fields
+//! such as `location` or `url`, implied relationships) is kept verbatim in the
+//! workspace's `extensions` so that import followed by export reproduces the
+//! original document.
+
+use crate::workspace::{
+    AutoLayout, RankDirection, StyleRule, StyleTarget, ViewDefinition, ViewElement, ViewKind,
+    Workspace,
+};
+use crate::{C4Level, NodeType, OptaNode, Result, model::OptaEdge};
+use burn::tensor::backend::Backend;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashSet};
+
+/// Relationship property used to carry `OptaEdge::weight`, which Structurizr has no field for
+const WEIGHT_PROPERTY: &str = "optacore.weight";
+
+const ELEMENT_EXTENSION_PREFIX: &str = "model.element.";
+const RELATIONSHIP_EXTENSION_PREFIX: &str = "model.relationship.";
+const IMPLIED_RELATIONSHIPS_PREFIX: &str = "model.impliedRelationships.";
+const VIEW_RELATIONSHIP_EXTENSION_PREFIX: &str = "relationship.";
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct JsonWorkspace {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    properties: BTreeMap<String, String>,
+    #[serde(default)]
+    model: JsonModel,
+    #[serde(default)]
+    views: JsonViews,
+    #[serde(flatten)]
+    extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct JsonModel {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    people: Vec<JsonElement>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    software_systems: Vec<JsonElement>,
+    #[serde(flatten)]
+    extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct JsonElement {
+    id: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    technology: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tags: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    properties: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    relationships: Vec<Map<String, Value>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    containers: Vec<JsonElement>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    components: Vec<JsonElement>,
+    #[serde(flatten)]
+    extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct JsonRelationship {
+    id: String,
+    source_id: String,
+    destination_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    technology: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tags: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    properties: BTreeMap<String, String>,
+    #[serde(flatten)]
+    extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct JsonViews {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    system_landscape_views: Vec<JsonView>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    system_context_views: Vec<JsonView>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    container_views: Vec<JsonView>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    component_views: Vec<JsonView>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    dynamic_views: Vec<JsonView>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    deployment_views: Vec<JsonView>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    filtered_views: Vec<JsonView>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    custom_views: Vec<JsonView>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    configuration: Option<JsonConfiguration>,
+    #[serde(flatten)]
+    extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct JsonView {
+    #[serde(default)]
+    key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    software_system_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    container_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    element_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    mode: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    elements: Vec<JsonElementView>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    relationships: Vec<Map<String, Value>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    automatic_layout: Option<JsonAutomaticLayout>,
+    #[serde(flatten)]
+    extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct JsonElementView {
+    id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    x: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    y: Option<i32>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct JsonAutomaticLayout {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rank_direction: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rank_separation: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    node_separation: Option<u32>,
+    #[serde(flatten)]
+    extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct JsonConfiguration {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    styles: Option<JsonStyles>,
+    #[serde(flatten)]
+    extra: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct JsonStyles {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    elements: Vec<Map<String, Value>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    relationships: Vec<Map<String, Value>>,
+}
+
+/// Import a Structurizr workspace JSON document
+pub fn from_structurizr_json<B: Backend>(json: &str) -> Result<Workspace<B>> {
+    let document: JsonWorkspace = serde_json::from_str(json)?;
+    let mut workspace = Workspace::new();
+
+    workspace.name = document.name;
+    workspace.description = document.description;
+    workspace.properties = document.properties;
+    workspace.extensions.extend(document.extra);
+    for (key, value) in document.model.extra {
+        workspace.extensions.insert(format!("model.{}", key), value);
+    }
+
+    let mut relationships = Vec::new();
+    for person in document.model.people {
+        import_element(
+            &mut workspace,
+            person,
+            NodeType::Person,
+            None,
+            &mut relationships,
+        )?;
+    }
+    for system in document.model.software_systems {
+        import_element(
+            &mut workspace,
+            system,
+            NodeType::System,
+            None,
+            &mut relationships,
+        )?;
+    }
+    for relationship in relationships {
+        import_relationship(&mut workspace, relationship)?;
+    }
+
+    let views = document.views;
+    for (key, value) in views.extra {
+        workspace.extensions.insert(format!("views.{}", key), value);
+    }
+    for (kind, list) in [
+        (ViewKind::SystemLandscape, views.system_landscape_views),
+        (ViewKind::SystemContext, views.system_context_views),
+        (ViewKind::Container, views.container_views),
+        (ViewKind::Component, views.component_views),
+        (ViewKind::Dynamic, views.dynamic_views),
+        (ViewKind::Deployment, views.deployment_views),
+        (ViewKind::Filtered, views.filtered_views),
+        (ViewKind::Custom, views.custom_views),
+    ] {
+        for view in list {
+            workspace.views.push(import_view(kind, view));
+        }
+    }
+
+    if let Some(configuration) = views.configuration {
+        if !configuration.extra.is_empty() {
+            workspace.extensions.insert(
+                "views.configuration".to_string(),
+                Value::Object(configuration.extra),
+            );
+        }
+        if let Some(styles) = configuration.styles {
+            for (target, list) in [
+                (StyleTarget::Element, styles.elements),
+                (StyleTarget::Relationship, styles.relationships),
+            ] {
+                for style in list {
+                    workspace.styles.push(import_style(target, style));
+                }
+            }
+        }
+    }
+
+    Ok(workspace)
+}
+
+/// Export a workspace as a Structurizr workspace JSON document
+///
+/// Views without an explicit element list (for example ones parsed from DSL
+/// `include` statements) get their elements resolved from the model. Fails if
+/// a container or component has no parent, since Structurizr requires
+/// containers to live in software systems and components in containers.
+pub fn to_structurizr_json<B: Backend>(workspace: &Workspace<B>) -> Result<String> {
+    Ok(serde_json::to_string_pretty(&to_structurizr_value(
+        workspace,
+    )?)?)
+}
+
+fn to_structurizr_value<B: Backend>(workspace: &Workspace<B>) -> Result<Value> {
+    let model = &workspace.model;
+    let relationship_ids = assign_relationship_ids(workspace);
+
+    for node in &model.nodes {
+        let parent_type = model.parent(&node.id).map(|p| &p.node_type);
+        match (&node.node_type, parent_type) {
+            (NodeType::Container, Some(NodeType::System))
+            | (NodeType::Component, Some(NodeType::Container))
+            | (NodeType::Person | NodeType::System, _) => {}
+            (node_type, _) => anyhow::bail!(
+                "{:?} {} must be nested inside a {} to export to Structurizr",
+                node_type,
+                node.id,
+                if *node_type == NodeType::Container {
+                    "software system"
+                } else {
+                    "container"
+                }
+            ),
+        }
+    }
+
+    let mut document = JsonWorkspace {
+        name: workspace.name.clone(),
+        description: workspace.description.clone(),
+        properties: workspace.properties.clone(),
+        ..Default::default()
+    };
+
+    for node in &model.nodes {
+        let list = match node.node_type {
+            NodeType::Person => &mut document.model.people,
+            NodeType::System => &mut document.model.software_systems,
+            NodeType::Container | NodeType::Component => continue,
+        };
+        list.push(export_element(workspace, node, &relationship_ids)?);
+    }
+
+    for view in &workspace.views {
+        let list = match view.kind {
+            ViewKind::SystemLandscape => &mut document.views.system_landscape_views,
+            ViewKind::SystemContext => &mut document.views.system_context_views,
+            ViewKind::Container => &mut document.views.container_views,
+            ViewKind::Component => &mut document.views.component_views,
+            ViewKind::Dynamic => &mut document.views.dynamic_views,
+            ViewKind::Deployment => &mut document.views.deployment_views,
+            ViewKind::Filtered => &mut document.views.filtered_views,
+            ViewKind::Custom => &mut document.views.custom_views,
+        };
+        list.push(export_view(workspace, view, &relationship_ids));
+    }
+
+    let mut configuration = JsonConfiguration::default();
+    if let Some(Value::Object(extra)) = workspace.extensions.get("views.configuration") {
+        configuration.extra = extra.clone();
+    }
+    if !workspace.styles.is_empty() {
+        let mut styles = JsonStyles::default();
+        for style in &workspace.styles {
+            let list = match style.target {
+                StyleTarget::Element => &mut styles.elements,
+                StyleTarget::Relationship => &mut styles.relationships,
+            };
+            list.push(export_style(style));
+        }
+        configuration.styles = Some(styles);
+    }
+    if configuration.styles.is_some() || !configuration.extra.is_empty() {
+        document.views.configuration = Some(configuration);
+    }
+
+    for (key, value) in &workspace.extensions {
+        if let Some(key) = key.strip_prefix("model.") {
+            if !key.starts_with("element.")
+                && !key.starts_with("relationship.")
+                && !key.starts_with("impliedRelationships.")
+            {
+                document.model.extra.insert(key.to_string(), value.clone());
+            }
+        } else if let Some(key) = key.strip_prefix("views.") {
+            if key != "configuration" {
+                document.views.extra.insert(key.to_string(), value.clone());
+            }
+        } else {
+            document.extra.insert(key.clone(), value.clone());
+        }
+    }
+
+    Ok(serde_json::to_value(document)?)
+}
+
+fn import_element<B: Backend>(
+    workspace: &mut Workspace<B>,
+    element: JsonElement,
+    node_type: NodeType,
+    parent: Option<&str>,
+    relationships: &mut Vec<Map<String, Value>>,
+) -> Result<()> {
+    if workspace.model.find_node(&element.id).is_some() {
+        anyhow::bail!("Duplicate element id: {}", element.id);
+    }
+
+    let mut node = OptaNode::new(element.id.clone(), element.name, node_type.clone());
+    node.description = element.description;
+    node.technology = element.technology;
+    node.tags = strip_default_tags(element.tags.as_deref(), default_element_tags(&node_type));
+    node.properties = element.properties;
+    node.parent = parent.map(str::to_string);
+    workspace.model.add_node(node);
+
+    if !element.extra.is_empty() {
+        workspace.extensions.insert(
+            format!("{}{}", ELEMENT_EXTENSION_PREFIX, element.id),
+            Value::Object(element.extra),
+        );
+    }
+
+    let mut implied = Vec::new();
+    for relationship in element.relationships {
+        if relationship.contains_key("linkedRelationshipId") {
+            implied.push(Value::Object(relationship));
+        } else {
+            relationships.push(relationship);
+        }
+    }
+    if !implied.is_empty() {
+        workspace.extensions.insert(
+            format!("{}{}", IMPLIED_RELATIONSHIPS_PREFIX, element.id),
+            Value::Array(implied),
+        );
+    }
+
+    for container in element.containers {
+        import_element(
+            workspace,
+            container,
+            NodeType::Container,
+            Some(&element.id),
+            relationships,
+        )?;
+    }
+    for component in element.components {
+        import_element(
+            workspace,
+            component,
+            NodeType::Component,
+            Some(&element.id),
+            relationships,
+        )?;
+    }
+    Ok(())
+}
+
+fn import_relationship<B: Backend>(
+    workspace: &mut Workspace<B>,
+    relationship: Map<String, Value>,
+) -> Result<()> {
+    let relationship: JsonRelationship = serde_json::from_value(Value::Object(relationship))?;
+    for endpoint in [&relationship.source_id, &relationship.destination_id] {
+        if workspace.model.find_node(endpoint).is_none() {
+            anyhow::bail!(
+                "Relationship {} references unknown element {}",
+                relationship.id,
+                endpoint
+            );
+        }
+    }
+
+    let mut edge = OptaEdge::new(relationship.source_id, relationship.destination_id);
+    edge.id = Some(relationship.id.clone());
+    edge.label = relationship.description;
+    edge.technology = relationship.technology;
+    edge.tags = strip_default_tags(relationship.tags.as_deref(), &["Relationship"]);
+    edge.properties = relationship.properties;
+    if let Some(weight) = edge.properties.remove(WEIGHT_PROPERTY) {
+        edge.weight = weight
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid {} value: {}", WEIGHT_PROPERTY, weight))?;
+    }
+    workspace.model.add_edge(edge);
+
+    if !relationship.extra.is_empty() {
+        workspace.extensions.insert(
+            format!("{}{}", RELATIONSHIP_EXTENSION_PREFIX, relationship.id),
+            Value::Object(relationship.extra),
+        );
+    }
+    Ok(())
+}
+
+fn import_view(kind: ViewKind, view: JsonView) -> ViewDefinition {
+    let mut definition = ViewDefinition::new(kind, view.key);
+    definition.title = view.title;
+    definition.description = view.description;
+    definition.scope = view
+        .software_system_id
+        .or(view.container_id)
+        .or(view.element_id);
+    definition.extensions = view.extra.into_iter().collect();
+
+    if kind == ViewKind::Filtered {
+        if view.mode.as_deref() == Some("Exclude") {
+            definition.exclude = view.tags;
+        } else {
+            definition.include = view.tags;
+        }
+    }
+
+    definition.elements = view
+        .elements
+        .into_iter()
+        .map(|e| ViewElement {
+            id: e.id,
+            x: e.x,
+            y: e.y,
+        })
+        .collect();
+
+    for mut relationship in view.relationships {
+        let Some(Value::String(id)) = relationship.remove("id") else {
+            continue;
+        };
+        if !relationship.is_empty() {
+            definition.extensions.insert(
+                format!("{}{}", VIEW_RELATIONSHIP_EXTENSION_PREFIX, id),
+                Value::Object(relationship),
+            );
+        }
+        definition.relationships.push(id);
+    }
+
+    if let Some(layout) = view.automatic_layout {
+        definition.auto_layout = Some(AutoLayout {
+            direction: layout
+                .rank_direction
+                .as_deref()
+                .and_then(RankDirection::parse)
+                .unwrap_or_default(),
+            rank_separation: layout.rank_separation,
+            node_separation: layout.node_separation,
+        });
+        if !layout.extra.is_empty() {
+            definition
+                .extensions
+                .insert("automaticLayout".to_string(), Value::Object(layout.extra));
+        }
+    }
+
+    definition
+}
+
+fn import_style(target: StyleTarget, mut style: Map<String, Value>) -> StyleRule {
+    let tag = match style.remove("tag") {
+        Some(Value::String(tag)) => tag,
+        _ => String::new(),
+    };
+    let properties = style
+        .into_iter()
+        .map(|(key, value)| {
+            let value = match value {
+                Value::String(value) => value,
+                other => other.to_string(),
+            };
+            (key, value)
+        })
+        .collect();
+
+    StyleRule {
+        target,
+        tag,
+        properties,
+    }
+}
+
+fn export_element<B: Backend>(
+    workspace: &Workspace<B>,
+    node: &OptaNode<B>,
+    relationship_ids: &[String],
+) -> Result<JsonElement> {
+    let model = &workspace.model;
+    let mut element = JsonElement {
+        id: node.id.clone(),
+        name: node.name.clone(),
+        description: node.description.clone(),
+        technology: node.technology.clone(),
+        tags: Some(join_tags(default_element_tags(&node.node_type), &node.tags)),
+        properties: node.properties.clone(),
+        ..Default::default()
+    };
+    if let Some(Value::Object(extra)) = workspace
+        .extensions
+        .get(&format!("{}{}", ELEMENT_EXTENSION_PREFIX, node.id))
+    {
+        element.extra = extra.clone();
+    }
+
+    for (edge, id) in model.edges.iter().zip(relationship_ids) {
+        if edge.from == node.id {
+            element
+                .relationships
+                .push(export_relationship(workspace, edge, id)?);
+        }
+    }
+    if let Some(Value::Array(implied)) = workspace
+        .extensions
+        .get(&format!("{}{}", IMPLIED_RELATIONSHIPS_PREFIX, node.id))
+    {
+        for relationship in implied {
+            if let Value::Object(relationship) = relationship {
+                element.relationships.push(relationship.clone());
+            }
+        }
+    }
+
+    for child in model.children(&node.id) {
+        let exported = export_element(workspace, child, relationship_ids)?;
+        match child.node_type {
+            NodeType::Container => element.containers.push(exported),
+            NodeType::Component => element.components.push(exported),
+            NodeType::System | NodeType::Person => {}
+        }
+    }
+
+    Ok(element)
+}
+
+fn export_relationship<B: Backend>(
+    workspace: &Workspace<B>,
+    edge: &OptaEdge,
+    id: &str,
+) -> Result<Map<String, Value>> {
+    let mut relationship = JsonRelationship {
+        id: id.to_string(),
+        source_id: edge.from.clone(),
+        destination_id: edge.to.clone(),
+        description: edge.label.clone(),
+        technology: edge.technology.clone(),
+        tags: Some(join_tags(&["Relationship"], &edge.tags)),
+        properties: edge.properties.clone(),
+        ..Default::default()
+    };
+    if edge.weight != 1.0 {
+        relationship
+            .properties
+            .insert(WEIGHT_PROPERTY.to_string(), edge.weight.to_string());
+    }
+    if let Some(Value::Object(extra)) = workspace
+        .extensions
+        .get(&format!("{}{}", RELATIONSHIP_EXTENSION_PREFIX, id))
+    {
+        relationship.extra = extra.clone();
+    }
+
+    match serde_json::to_value(relationship)? {
+        Value::Object(map) => Ok(map),
+        _ => unreachable!("relationships serialize to JSON objects"),
+    }
+}
+
+fn export_view<B: Backend>(
+    workspace: &Workspace<B>,
+    view: &ViewDefinition,
+    relationship_ids: &[String],
+) -> JsonView {
+    let mut exported = JsonView {
+        key: view.key.clone(),
+        title: view.title.clone(),
+        description: view.description.clone(),
+        ..Default::default()
+    };
+    match view.kind {
+        ViewKind::SystemContext | ViewKind::Container | ViewKind::Deployment => {
+            exported.software_system_id = view.scope.clone();
+        }
+        ViewKind::Component => exported.container_id = view.scope.clone(),
+        ViewKind::Dynamic => exported.element_id = view.scope.clone(),
+        ViewKind::Filtered => {
+            if view.exclude.is_empty() {
+                exported.mode = Some("Include".to_string());
+                exported.tags = view.include.clone();
+            } else {
+                exported.mode = Some("Exclude".to_string());
+                exported.tags = view.exclude.clone();
+            }
+        }
+        ViewKind::SystemLandscape | ViewKind::Custom => {}
+    }
+
+    let mut relationship_extensions = Map::new();
+    for (key, value) in &view.extensions {
+        if let Some(id) = key.strip_prefix(VIEW_RELATIONSHIP_EXTENSION_PREFIX) {
+            relationship_extensions.insert(id.to_string(), value.clone());
+        } else if key != "automaticLayout" {
+            exported.extra.insert(key.clone(), value.clone());
+        }
+    }
+
+    if view.elements.is_empty() && view.kind != ViewKind::Filtered {
+        let elements = resolve_view_elements(&workspace.model, view);
+        exported.elements = elements
+            .iter()
+            .map(|id| JsonElementView {
+                id: id.clone(),
+                x: None,
+                y: None,
+            })
+            .collect();
+        if view.relationships.is_empty() {
+            let included: HashSet<&str> = elements.iter().map(String::as_str).collect();
+            for (edge, id) in workspace.model.edges.iter().zip(relationship_ids) {
+                if included.contains(edge.from.as_str()) && included.contains(edge.to.as_str()) {
+                    exported.relationships.push(relationship_entry(id, None));
+                }
+            }
+        }
+    } else {
+        exported.elements = view
+            .elements
+            .iter()
+            .map(|e| JsonElementView {
+                id: e.id.clone(),
+                x: e.x,
+                y: e.y,
+            })
+            .collect();
+    }
+    for id in &view.relationships {
+        exported
+            .relationships
+            .push(relationship_entry(id, relationship_extensions.get(id)));
+    }
+
+    if let Some(layout) = &view.auto_layout {
+        let extra = match view.extensions.get("automaticLayout") {
+            Some(Value::Object(extra)) => extra.clone(),
+            _ => Map::new(),
+        };
+        exported.automatic_layout = Some(JsonAutomaticLayout {
+            rank_direction: Some(rank_direction_name(layout.direction).to_string()),
+            rank_separation: layout.rank_separation,
+            node_separation: layout.node_separation,
+            extra,
+        });
+    }
+
+    exported
+}
+
+fn export_style(style: &StyleRule) -> Map<String, Value> {
+    let mut map = Map::new();
+    map.insert("tag".to_string(), Value::String(style.tag.clone()));
+    for (key, value) in &style.properties {
+        // Numbers, booleans and nested objects were stringified on import (or
+        // written as bare words in DSL); restore their JSON types
+        let value = match serde_json::from_str::<Value>(value) {
+            Ok(parsed) if !parsed.is_string() => parsed,
+            _ => Value::String(value.clone()),
+        };
+        map.insert(key.clone(), value);
+    }
+    map
+}
+
+/// Element ids shown in a view that has no explicit element list, from its
+/// `include`/`exclude` expressions
+///
+/// `*` expands to the view's default scope; identifiers are matched directly.
+/// Richer expressions (`element.tag==...`, relationship filters) are ignored.
+fn resolve_view_elements<B: Backend>(
+    model: &crate::OptaModel<B>,
+    view: &ViewDefinition,
+) -> Vec<String> {
+    let mut included: Vec<String> = Vec::new();
+    let push = |id: &str, included: &mut Vec<String>| {
+        if !included.iter().any(|existing| existing == id) {
+            included.push(id.to_string());
+        }
+    };
+
+    for expression in &view.include {
+        if expression == "*" {
+            for id in default_view_elements(model, view) {
+                push(&id, &mut included);
+            }
+        } else if model.find_node(expression).is_some() {
+            push(expression, &mut included);
+        }
+    }
+
+    included.retain(|id| !view.exclude.contains(id));
+    included
+}
+
+fn default_view_elements<B: Backend>(
+    model: &crate::OptaModel<B>,
+    view: &ViewDefinition,
+) -> Vec<String> {
+    match (view.kind, view.scope.as_deref()) {
+        (ViewKind::SystemLandscape, _) => model
+            .nodes
+            .iter()
+            .filter(|n| C4Level::SystemContext.includes(&n.node_type))
+            .map(|n| n.id.clone())
+            .collect(),
+        (ViewKind::SystemContext, Some(scope)) => {
+            let mut ids = vec![scope.to_string()];
+            for edge in model.implied_edges(C4Level::SystemContext) {
+                if edge.from == scope {
+                    ids.push(edge.to);
+                } else if edge.to == scope {
+                    ids.push(edge.from);
+                }
+            }
+            ids
+        }
+        (ViewKind::Container | ViewKind::Component, Some(scope)) => model
+            .scoped_view(scope)
+            .map(|scoped| {
+                scoped
+                    .nodes
+                    .into_iter()
+                    .filter(|n| n.id != scope)
+                    .map(|n| n.id)
+                    .collect()
+            })
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    }
+}
+
+/// Ids for every model edge, in model order: existing ids are kept, and edges
+/// without one get a fresh numeric id that collides with no element or relationship
+fn assign_relationship_ids<B: Backend>(workspace: &Workspace<B>) -> Vec<String> {
+    let model = &workspace.model;
+    let mut used: HashSet<String> = model.nodes.iter().map(|n| n.id.clone()).collect();
+    used.extend(model.edges.iter().filter_map(|e| e.id.clone()));
+
+    let mut next = model.nodes.len() + 1;
+    model
+        .edges
+        .iter()
+        .map(|edge| match &edge.id {
+            Some(id) => id.clone(),
+            None => {
+                while used.contains(&next.to_string()) {
+                    next += 1;
+                }
+                used.insert(next.to_string());
+                next.to_string()
+            }
+        })
+        .collect()
+}
+
+fn relationship_entry(id: &str, extra: Option<&Value>) -> Map<String, Value> {
+    let mut entry = match extra {
+        Some(Value::Object(extra)) => extra.clone(),
+        _ => Map::new(),
+    };
+    entry.insert("id".to_string(), Value::String(id.to_string()));
+    entry
+}
+
+fn default_element_tags(node_type: &NodeType) -> &'static [&'static str] {
+    match node_type {
+        NodeType::Person => &["Element", "Person"],
+        NodeType::System => &["Element", "Software System"],
+        NodeType::Container => &["Element", "Container"],
+        NodeType::Component => &["Element", "Component"],
+    }
+}
+
+fn strip_default_tags(tags: Option<&str>, defaults: &[&str]) -> Vec<String> {
+    tags.unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty() && !defaults.contains(tag))
+        .map(str::to_string)
+        .collect()
+}
+
+fn join_tags(defaults: &[&str], tags: &[String]) -> String {
+    defaults
+        .iter()
+        .copied()
+        .chain(tags.iter().map(String::as_str))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn rank_direction_name(direction: RankDirection) -> &'static str {
+    match direction {
+        RankDirection::TopBottom => "TopBottom",
+        RankDirection::BottomTop => "BottomTop",
+        RankDirection::LeftRight => "LeftRight",
+        RankDirection::RightLeft => "RightLeft",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{OptaOptimizer, parse_workspace};
+    use burn::backend::ndarray::NdArray;
+
+    type TestBackend = NdArray<f32>;
+
+    const BIG_BANK: &str = r##"{
+  "id": 1,
+  "name": "Big Bank plc",
+  "description": "Internet banking system",
+  "lastModifiedDate": "2024-05-01T10:00:00Z",
+  "properties": { "structurizr.dsl.identifier": "flat" },
+  "documentation": { "sections": [] },
+  "model": {
+    "people": [
+      {
+        "id": "1",
+        "name": "Personal Banking Customer",
+        "description": "A customer of the bank",
+        "tags": "Element,Person,Customer",
+        "location": "External",
+        "relationships": [
+          {
+            "id": "5",
+            "sourceId": "1",
+            "destinationId": "3",
+            "description": "Visits",
+            "technology": "HTTPS",
+            "tags": "Relationship"
+          },
+          {
+            "id": "9",
+            "sourceId": "1",
+            "destinationId": "2",
+            "description": "Visits",
+            "technology": "HTTPS",
+            "tags": "Relationship",
+            "linkedRelationshipId": "5"
+          }
+        ]
+      }
+    ],
+    "softwareSystems": [
+      {
+        "id": "2",
+        "name": "Internet Banking System",
+        "tags": "Element,Software System",
+        "location": "Internal",
+        "properties": { "owner": "Digital" },
+        "containers": [
+          {
+            "id": "3",
+            "name": "Web Application",
+            "technology": "Java and Spring MVC",
+            "tags": "Element,Container",
+            "relationships": [
+              {
+                "id": "6",
+                "sourceId": "3",
+                "destinationId": "4",
+                "description": "Reads from",
+                "tags": "Relationship,Async",
+                "properties": { "optacore.weight": "2.5" }
+              }
+            ]
+          },
+          {
+            "id": "4",
+            "name": "Database",
+            "tags": "Element,Container,Database",
+            "components": [
+              { "id": "7", "name": "Schema", "tags": "Element,Component" }
+            ]
+          }
+        ]
+      }
+    ],
+    "deploymentNodes": [ { "id": "8", "name": "Live", "environment": "Live" } ]
+  },
+  "views": {
+    "containerViews": [
+      {
+        "key": "Containers",
+        "softwareSystemId": "2",
+        "description": "Containers of the banking system",
+        "paperSize": "A5_Landscape",
+        "elements": [
+          { "id": "1", "x": 100, "y": 50 },
+          { "id": "3", "x": 400, "y": 50 },
+          { "id": "4", "x": 400, "y": 350 }
+        ],
+        "relationships": [ { "id": "5" }, { "id": "6", "vertices": [ { "x": 10, "y": 20 } ] } ],
+        "automaticLayout": {
+          "rankDirection": "LeftRight",
+          "rankSeparation": 300,
+          "nodeSeparation": 300,
+          "edgeSeparation": 0,
+          "vertices": false
+        }
+      }
+    ],
+    "filteredViews": [
+      { "key": "External", "baseViewKey": "Containers", "mode": "Exclude", "tags": [ "Database" ] }
+    ],
+    "configuration": {
+      "branding": {},
+      "styles": {
+        "elements": [
+          { "tag": "Person", "shape": "Person", "background": "#08427b", "fontSize": 22 },
+          { "tag": "Database", "shape": "Cylinder", "metadata": false }
+        ],
+        "relationships": [ { "tag": "Async", "dashed": true } ]
+      },
+      "terminology": {}
+    }
+  }
+}"##;
+
+    #[test]
+    fn test_import_structurizr_json() {
+        let workspace = from_structurizr_json::<TestBackend>(BIG_BANK).unwrap();
+        assert_eq!(workspace.name.as_deref(), Some("Big Bank plc"));
+
+        let model = &workspace.model;
+        assert_eq!(model.node_count(), 5);
+        assert_eq!(model.edge_count(), 2);
+
+        let customer = model.find_node("1").unwrap();
+        assert_eq!(customer.node_type, NodeType::Person);
+        assert_eq!(customer.tags, vec!["Customer"]);
+
+        let schema = model.find_node("7").unwrap();
+        assert_eq!(schema.node_type, NodeType::Component);
+        assert_eq!(schema.parent.as_deref(), Some("4"));
+        assert_eq!(
+            model
+                .find_node("2")
+                .unwrap()
+                .properties
+                .get("owner")
+                .map(String::as_str),
+            Some("Digital")
+        );
+
+        let reads = model
+            .edges
+            .iter()
+            .find(|e| e.id.as_deref() == Some("6"))
+            .unwrap();
+        assert_eq!(reads.tags, vec!["Async"]);
+        assert_eq!(reads.weight, 2.5);
+        assert!(reads.properties.is_empty());
+
+        let view = workspace.find_view("Containers").unwrap();
+        assert_eq!(view.kind, ViewKind::Container);
+        assert_eq!(view.scope.as_deref(), Some("2"));
+        assert_eq!(view.elements[2].y, Some(350));
+        assert_eq!(view.relationships, vec!["5", "6"]);
+        assert_eq!(
+            view.auto_layout.unwrap().direction,
+            RankDirection::LeftRight
+        );
+
+        let filtered = workspace.find_view("External").unwrap();
+        assert_eq!(filtered.exclude, vec!["Database"]);
+
+        assert_eq!(workspace.styles.len(), 3);
+        assert_eq!(
+            workspace.styles[0]
+                .properties
+                .get("fontSize")
+                .map(String::as_str),
+            Some("22")
+        );
+    }
+
+    #[test]
+    fn test_structurizr_json_round_trip_is_lossless() {
+        let workspace = from_structurizr_json::<TestBackend>(BIG_BANK).unwrap();
+        let exported = to_structurizr_json(&workspace).unwrap();
+
+        let original: Value = serde_json::from_str(BIG_BANK).unwrap();
+        let round_tripped: Value = serde_json::from_str(&exported).unwrap();
+        assert_eq!(original, round_tripped);
+    }
+
+    #[test]
+    fn test_export_dsl_workspace_with_layout() {
+        let dsl = r#"
+workspace "Shop" {
+    model {
+        user = person "User"
+        shop = softwareSystem "Shop" {
+            web = container "Web" "Storefront" "React"
+            api = container "API"
+        }
+        user -> web "Browses"
+        web -> api "Calls" "HTTPS" "Sync"
+    }
+    views {
+        container shop "Containers" {
+            include *
+        }
+    }
+}
+"#;
+        let mut workspace = parse_workspace::<TestBackend>(dsl).unwrap();
+        workspace.model.build_adjacency_matrix();
+        OptaOptimizer::<TestBackend>::new(20, 0.1)
+            .optimize_layout(&mut workspace.model)
+            .unwrap();
+        workspace.store_layout("Containers").unwrap();
+
+        let exported = to_structurizr_json(&workspace).unwrap();
+        let document: Value = serde_json::from_str(&exported).unwrap();
+
+        let shop = &document["model"]["softwareSystems"][0];
+        assert_eq!(shop["tags"], "Element,Software System");
+        assert_eq!(shop["containers"][0]["technology"], "React");
+        let calls = &shop["containers"][0]["relationships"][0];
+        assert_eq!(calls["destinationId"], "api");
+        assert_eq!(calls["tags"], "Relationship,Sync");
+
+        let view = &document["views"]["containerViews"][0];
+        assert_eq!(view["softwareSystemId"], "shop");
+        // Every positioned node is stored, since the view had no explicit element list
+        let elements = view["elements"].as_array().unwrap();
+        assert_eq!(elements.len(), 4);
+        assert!(
+            elements
+                .iter()
+                .all(|e| e["x"].is_number() && e["y"].is_number())
+        );
+
+        let mut reimported = from_structurizr_json::<TestBackend>(&exported).unwrap();
+        assert_eq!(reimported.model.node_count(), 4);
+        assert_eq!(reimported.model.edge_count(), 2);
+        assert_eq!(
+            reimported.model.find_node("api").unwrap().parent.as_deref(),
+            Some("shop")
+        );
+        assert_eq!(reimported.apply_layout("Containers").unwrap(), 4);
+    }
+
+    #[test]
+    fn test_export_rejects_orphan_containers() {
+        let workspace = parse_workspace::<TestBackend>("container API \"API\"").unwrap();
+        assert!(to_structurizr_json(&workspace).is_err());
+    }
+}
@@ -32,7 +32,7 @@ impl TelemetryEvent {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LayoutConvergedPayload {
     pub iterations: usize,
-    pub final_temperature: f32,
+    pub final_temperature: Option<f32>,
     pub duration_ms: u64,
     pub node_count: usize,
     pub edge_count: usize,
@@ -107,7 +107,7 @@ impl TelemetryLogger {
     pub fn log_layout_converged(
         &self,
         iterations: usize,
-        final_temperature: f32,
+        final_temperature: Option<f32>,
         duration_ms: u64,
         node_count: usize,
         edge_count: usize,
@@ -222,17 +222,34 @@ mod tests {
             .with_file(path.clone())
             .unwrap();
 
-        logger.log_layout_converged(50, 0.01, 123, 10, 15).unwrap();
+        logger
+            .log_layout_converged(50, Some(0.01), 123, 10, 15)
+            .unwrap();
+        // Layouts without a temperature schedule report none
+        logger.log_layout_converged(8, None, 4, 3, 2).unwrap();
 
         let mut content = String::new();
         File::open(path)
             .unwrap()
             .read_to_string(&mut content)
             .unwrap();
-
-        assert!(content.contains("layout.converged"));
-        assert!(content.contains("\"iterations\":50"));
-        assert!(content.contains("\"node_count\":10"));
+        let events: Vec<TelemetryEvent> = content
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(events.len(), 2);
+
+        let converged: LayoutConvergedPayload =
+            serde_json::from_value(events[0].payload.clone()).unwrap();
+        assert_eq!(events[0].event_type, "layout.converged");
+        assert_eq!(converged.iterations, 50);
+        assert_eq!(converged.final_temperature, Some(0.01));
+        assert_eq!(converged.node_count, 10);
+
+        let converged: LayoutConvergedPayload =
+            serde_json::from_value(events[1].payload.clone()).unwrap();
+        assert_eq!(converged.iterations, 8);
+        assert_eq!(converged.final_temperature, None);
     }
 
     #[test]
@@ -1,6 +1,12 @@
 use crate::{AntiPattern, AntiPatternConfig, NodeType, OptaModel, detect_anti_patterns};
 use burn::tensor::backend::Backend;
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
+
+mod c4_text;
+mod svg;
+
+pub use c4_text::{to_mermaid, to_plantuml};
+pub use svg::{SvgOptions, to_svg, to_svg_with_options};
 
 /// Export model to Graphviz DOT format with severity-based coloring
 pub fn to_dot<B: Backend>(
@@ -34,8 +40,8 @@ pub fn to_dot<B: Backend>(
         } else {
             "filled"
         };
-        let is_in_cycle = cycle_nodes.contains(&node.id);
-        let label = format!("{}", node.name);
+        let is_in_cycle = cycle_nodes.contains_key(&node.id);
+        let label = &node.name;
 
         dot.push_str(&format!(
             "  \"{}\" [label=\"{}\", fillcolor=\"{}\", shape={}, style=\"{}\"",
@@ -74,7 +80,7 @@ pub fn to_dot<B: Backend>(
         let label = edge.label.as_deref().unwrap_or("");
         let weight = edge.weight;
 
-        let is_cycle_edge = cycle_nodes.contains(&edge.from) && cycle_nodes.contains(&edge.to);
+        let is_cycle_edge = is_cycle_edge(&cycle_nodes, &edge.from, &edge.to);
 
         let (penwidth, color) = if is_cycle_edge {
             (3.0, ", color=\"#ff0000\"")
@@ -118,7 +124,53 @@ pub fn to_dot<B: Backend>(
                         node_id, out_degree, severity
                     ));
                 }
+                AntiPattern::LayeringViolation {
+                    from,
+                    to,
+                    bypassed_container,
+                    severity,
+                } => {
+                    dot.push_str(&format!(
+                        "  // Layering violation: {} -> {} bypasses {} (severity={:.2})\n",
+                        from, to, bypassed_container, severity
+                    ));
+                }
+                AntiPattern::GodComponent {
+                    node_id,
+                    betweenness,
+                    severity,
+                } => {
+                    dot.push_str(&format!(
+                        "  // God component: {} (betweenness={:.2}, severity={:.2})\n",
+                        node_id, betweenness, severity
+                    ));
+                }
+                AntiPattern::SharedDatabase {
+                    node_id,
+                    accessors,
+                    severity,
+                } => {
+                    dot.push_str(&format!(
+                        "  // Shared database: {} used by {:?} (severity={:.2})\n",
+                        node_id, accessors, severity
+                    ));
+                }
+                AntiPattern::ChattyInterface {
+                    from,
+                    to,
+                    weight,
+                    severity,
+                } => {
+                    dot.push_str(&format!(
+                        "  // Chatty interface: {} -> {} (weight={:.1}, severity={:.2})\n",
+                        from, to, weight, severity
+                    ));
+                }
+                AntiPattern::RuleViolation { rule, message, .. } => {
+                    dot.push_str(&format!("  // Rule violation: {}: {}\n", rule, message));
+                }
             }
+            dot.push_str(&format!("  //   Remediation: {}\n", pattern.remediation()));
         }
     }
 
@@ -167,42 +219,24 @@ fn build_severity_map(patterns: &[AntiPattern]) -> HashMap<String, f32> {
     let mut severity_map: HashMap<String, f32> = HashMap::new();
 
     for pattern in patterns {
-        match pattern {
-            AntiPattern::Cycle { nodes } => {
-                for node_id in nodes {
-                    let current = severity_map.entry(node_id.clone()).or_insert(0.0);
-                    *current = (*current).max(1.0);
-                }
-            }
-            AntiPattern::Bottleneck {
-                node_id, severity, ..
-            } => {
-                let current = severity_map.entry(node_id.clone()).or_insert(0.0);
-                *current = (*current).max(*severity);
-            }
-            AntiPattern::IsolatedComponent { node_id } => {
-                let current = severity_map.entry(node_id.clone()).or_insert(0.0);
-                *current = (*current).max(0.3);
-            }
-            AntiPattern::OverCoupling {
-                node_id, severity, ..
-            } => {
-                let current = severity_map.entry(node_id.clone()).or_insert(0.0);
-                *current = (*current).max(*severity);
-            }
+        let severity = pattern.severity();
+        for node_id in pattern.affected_nodes() {
+            let current = severity_map.entry(node_id).or_insert(0.0);
+            *current = (*current).max(severity);
         }
     }
 
     severity_map
 }
 
-fn extract_cycle_nodes(patterns: &[AntiPattern]) -> HashSet<String> {
-    let mut cycle_nodes = HashSet::new();
+/// Maps each node in a cycle to the index of its strongly connected component
+fn extract_cycle_nodes(patterns: &[AntiPattern]) -> HashMap<String, usize> {
+    let mut cycle_nodes = HashMap::new();
 
-    for pattern in patterns {
+    for (index, pattern) in patterns.iter().enumerate() {
         if let AntiPattern::Cycle { nodes } = pattern {
             for node_id in nodes {
-                cycle_nodes.insert(node_id.clone());
+                cycle_nodes.insert(node_id.clone(), index);
             }
         }
     }
@@ -210,6 +244,13 @@ fn extract_cycle_nodes(patterns: &[AntiPattern]) -> HashSet<String> {
     cycle_nodes
 }
 
+/// Whether an edge runs inside one cycle, rather than between two separate ones
+fn is_cycle_edge(cycle_nodes: &HashMap<String, usize>, from: &str, to: &str) -> bool {
+    cycle_nodes
+        .get(from)
+        .is_some_and(|component| cycle_nodes.get(to) == Some(component))
+}
+
 fn node_type_to_shape(node_type: &NodeType) -> &'static str {
     match node_type {
         NodeType::System => "box3d",
@@ -302,4 +343,28 @@ mod tests {
         let dot = to_dot(&model, Some(&config)).unwrap();
         assert!(dot.contains("style=\"filled,dashed\""));
     }
+
+    #[test]
+    fn test_remediation_comments_and_cycle_edges() {
+        let mut model = OptaModel::<TestBackend>::new();
+        for id in ["a", "b", "c", "d"] {
+            model.add_node(OptaNode::new(
+                id.to_string(),
+                id.to_uppercase(),
+                NodeType::Container,
+            ));
+        }
+        model.add_edge(OptaEdge::new("a".to_string(), "b".to_string()));
+        model.add_edge(OptaEdge::new("b".to_string(), "a".to_string()));
+        model.add_edge(OptaEdge::new("c".to_string(), "d".to_string()));
+        model.add_edge(OptaEdge::new("d".to_string(), "c".to_string()));
+        // Joins two separate cycles, so it is not part of either
+        model.add_edge(OptaEdge::new("b".to_string(), "c".to_string()));
+
+        let dot = to_dot(&model, Some(&AntiPatternConfig::default())).unwrap();
+        assert_eq!(dot.matches("  // Cycle:").count(), 2);
+        assert_eq!(dot.matches("//   Remediation: Break the cycle").count(), 2);
+        assert!(dot.contains("\"a\" -> \"b\" [label=\"\", penwidth=3, color=\"#ff0000\"]"));
+        assert!(dot.contains("\"b\" -> \"c\" [label=\"\", penwidth=1]"));
+    }
 }
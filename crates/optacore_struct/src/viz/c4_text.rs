@@ -0,0 +1,281 @@
+use crate::{NodeType, OptaModel, OptaNode};
+use burn::tensor::backend::Backend;
+use std::fmt::Write;
+
+/// Export model as a C4-PlantUML diagram
+///
+/// The diagram type (context, container or component) follows the most
+/// detailed element in the model. Elements that contain others become
+/// boundaries; the `External`, `Database` and `Queue` tags select the
+/// matching `_Ext`, `Db` and `Queue` macros.
+pub fn to_plantuml<B: Backend>(model: &OptaModel<B>) -> crate::Result<String> {
+    render(model, Dialect::PlantUml)
+}
+
+/// Export model as a Mermaid C4 diagram, ready for a ```` ```mermaid ```` block in Markdown
+pub fn to_mermaid<B: Backend>(model: &OptaModel<B>) -> crate::Result<String> {
+    render(model, Dialect::Mermaid)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Dialect {
+    PlantUml,
+    Mermaid,
+}
+
+fn render<B: Backend>(model: &OptaModel<B>, dialect: Dialect) -> crate::Result<String> {
+    let diagram = if model
+        .nodes
+        .iter()
+        .any(|n| n.node_type == NodeType::Component)
+    {
+        "Component"
+    } else if model
+        .nodes
+        .iter()
+        .any(|n| n.node_type == NodeType::Container)
+    {
+        "Container"
+    } else {
+        "Context"
+    };
+
+    let mut out = String::new();
+    match dialect {
+        Dialect::PlantUml => {
+            out.push_str("@startuml\n");
+            writeln!(out, "!include <C4/C4_{diagram}>")?;
+            out.push('\n');
+        }
+        Dialect::Mermaid => writeln!(out, "C4{diagram}")?,
+    }
+
+    let indent = match dialect {
+        Dialect::PlantUml => 0,
+        Dialect::Mermaid => 1,
+    };
+    // Elements whose parent is missing from the model are drawn at the top level
+    for node in model.nodes.iter().filter(|n| {
+        n.parent
+            .as_ref()
+            .is_none_or(|p| model.find_node(p).is_none())
+    }) {
+        write_element(&mut out, model, node, indent, &mut Vec::new())?;
+    }
+
+    if !model.edges.is_empty() && dialect == Dialect::PlantUml {
+        out.push('\n');
+    }
+    for edge in &model.edges {
+        write_indent(&mut out, indent);
+        write!(
+            out,
+            "Rel({}, {}, \"{}\"",
+            alias(&edge.from),
+            alias(&edge.to),
+            quote(edge.label.as_deref().unwrap_or(""))
+        )?;
+        if let Some(technology) = &edge.technology {
+            write!(out, ", \"{}\"", quote(technology))?;
+        }
+        out.push_str(")\n");
+    }
+
+    if dialect == Dialect::PlantUml {
+        out.push_str("@enduml\n");
+    }
+    Ok(out)
+}
+
+fn write_element<'a, B: Backend>(
+    out: &mut String,
+    model: &'a OptaModel<B>,
+    node: &'a OptaNode<B>,
+    depth: usize,
+    path: &mut Vec<&'a str>,
+) -> crate::Result<()> {
+    // Guard against parent cycles, which would otherwise recurse forever
+    if path.contains(&node.id.as_str()) {
+        return Ok(());
+    }
+
+    let children = model.children(&node.id);
+    write_indent(out, depth);
+    if children.is_empty() {
+        write!(
+            out,
+            "{}({}, \"{}\"",
+            element_macro(node),
+            alias(&node.id),
+            quote(&node.name)
+        )?;
+        if matches!(node.node_type, NodeType::Container | NodeType::Component) {
+            write!(
+                out,
+                ", \"{}\"",
+                quote(node.technology.as_deref().unwrap_or(""))
+            )?;
+        }
+        if let Some(description) = &node.description {
+            write!(out, ", \"{}\"", quote(description))?;
+        }
+        out.push_str(")\n");
+        return Ok(());
+    }
+
+    let boundary = match node.node_type {
+        NodeType::System => "System_Boundary",
+        NodeType::Container => "Container_Boundary",
+        NodeType::Component | NodeType::Person => "Boundary",
+    };
+    writeln!(
+        out,
+        "{boundary}({}, \"{}\") {{",
+        alias(&node.id),
+        quote(&node.name)
+    )?;
+    path.push(&node.id);
+    for child in children {
+        write_element(out, model, child, depth + 1, path)?;
+    }
+    path.pop();
+    write_indent(out, depth);
+    out.push_str("}\n");
+    Ok(())
+}
+
+fn element_macro<B: Backend>(node: &OptaNode<B>) -> String {
+    let base = match node.node_type {
+        NodeType::Person => "Person",
+        NodeType::System => "System",
+        NodeType::Container => "Container",
+        NodeType::Component => "Component",
+    };
+    let variant = if node.node_type == NodeType::Person {
+        ""
+    } else if node.has_tag("Database") {
+        "Db"
+    } else if node.has_tag("Queue") {
+        "Queue"
+    } else {
+        ""
+    };
+    let external = if node.has_tag("External") { "_Ext" } else { "" };
+    format!("{base}{variant}{external}")
+}
+
+fn write_indent(out: &mut String, depth: usize) {
+    for _ in 0..depth {
+        out.push_str("    ");
+    }
+}
+
+/// Identifier usable as a macro alias in both dialects
+fn alias(id: &str) -> String {
+    let mut alias: String = id
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if !alias.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        alias.insert(0, '_');
+    }
+    alias
+}
+
+/// Neither dialect supports escaped quotes inside string arguments
+fn quote(text: &str) -> String {
+    text.replace('"', "'").replace(['\n', '\r'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::OptaEdge;
+    use burn::backend::ndarray::NdArray;
+
+    type TestBackend = NdArray<f32>;
+
+    fn shop_model() -> OptaModel<TestBackend> {
+        let mut model = OptaModel::new();
+        model.add_node(
+            OptaNode::new(
+                "customer".to_string(),
+                "Customer".to_string(),
+                NodeType::Person,
+            )
+            .with_description("Buys \"things\"".to_string()),
+        );
+        model.add_node(OptaNode::new(
+            "shop".to_string(),
+            "Shop".to_string(),
+            NodeType::System,
+        ));
+        model.add_node(
+            OptaNode::new(
+                "web-app".to_string(),
+                "Web".to_string(),
+                NodeType::Container,
+            )
+            .with_technology("React".to_string())
+            .with_parent("shop".to_string()),
+        );
+        model.add_node(
+            OptaNode::new("db".to_string(), "Orders".to_string(), NodeType::Container)
+                .with_parent("shop".to_string())
+                .with_tags(vec!["Database".to_string()]),
+        );
+        model.add_node(
+            OptaNode::new(
+                "payments".to_string(),
+                "Payments".to_string(),
+                NodeType::System,
+            )
+            .with_tags(vec!["External".to_string()]),
+        );
+        model.add_edge(
+            OptaEdge::new("customer".to_string(), "web-app".to_string())
+                .with_label("Uses".to_string())
+                .with_technology("HTTPS".to_string()),
+        );
+        model.add_edge(OptaEdge::new("web-app".to_string(), "db".to_string()));
+        model.add_edge(OptaEdge::new("web-app".to_string(), "payments".to_string()));
+        model
+    }
+
+    #[test]
+    fn test_plantuml_export() {
+        let puml = to_plantuml(&shop_model()).unwrap();
+        assert!(puml.starts_with("@startuml\n!include <C4/C4_Container>\n"));
+        assert!(puml.contains("Person(customer, \"Customer\", \"Buys 'things'\")\n"));
+        assert!(puml.contains("System_Boundary(shop, \"Shop\") {\n"));
+        assert!(puml.contains("    Container(web_app, \"Web\", \"React\")\n"));
+        assert!(puml.contains("    ContainerDb(db, \"Orders\", \"\")\n"));
+        assert!(puml.contains("System_Ext(payments, \"Payments\")\n"));
+        assert!(puml.contains("Rel(customer, web_app, \"Uses\", \"HTTPS\")\n"));
+        assert!(puml.contains("Rel(web_app, db, \"\")\n"));
+        assert!(puml.ends_with("@enduml\n"));
+    }
+
+    #[test]
+    fn test_mermaid_export() {
+        let mermaid = to_mermaid(&shop_model()).unwrap();
+        assert!(mermaid.starts_with("C4Container\n"));
+        assert!(mermaid.contains("    System_Boundary(shop, \"Shop\") {\n"));
+        assert!(mermaid.contains("        ContainerDb(db, \"Orders\", \"\")\n"));
+        assert!(mermaid.contains("    Rel(customer, web_app, \"Uses\", \"HTTPS\")\n"));
+        assert!(!mermaid.contains("@startuml"));
+    }
+
+    #[test]
+    fn test_diagram_level_and_aliases() {
+        let mut model = OptaModel::<TestBackend>::new();
+        model.add_node(OptaNode::new(
+            "1st system".to_string(),
+            "First".to_string(),
+            NodeType::System,
+        ));
+        let mermaid = to_mermaid(&model).unwrap();
+        assert!(mermaid.starts_with("C4Context\n"));
+        assert!(mermaid.contains("System(_1st_system, \"First\")"));
+    }
+}
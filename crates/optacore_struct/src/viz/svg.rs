@@ -0,0 +1,443 @@
+use super::{
+    build_severity_map, extract_cycle_nodes, is_cycle_edge, node_type_to_shape, severity_to_color,
+};
+use crate::{AntiPatternConfig, OptaModel, detect_anti_patterns};
+use burn::tensor::backend::Backend;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// Options for [`to_svg_with_options`]
+#[derive(Debug, Clone)]
+pub struct SvgOptions {
+    /// Anti-pattern detection used for severity coloring; `None` draws every node in the default color
+    pub anti_patterns: Option<AntiPatternConfig>,
+    pub node_width: f32,
+    pub node_height: f32,
+    /// Space between a boundary and the elements inside it
+    pub padding: f32,
+    /// Margin around the whole drawing
+    pub margin: f32,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        Self {
+            anti_patterns: None,
+            node_width: 160.0,
+            node_height: 100.0,
+            padding: 30.0,
+            margin: 20.0,
+        }
+    }
+}
+
+/// Render a laid-out model as a standalone SVG document with severity-based coloring
+///
+/// Uses the node positions from a previous layout run; nodes without one are
+/// an error. Elements that contain others are drawn as dashed boundaries around
+/// their children rather than as shapes of their own.
+pub fn to_svg<B: Backend>(
+    model: &OptaModel<B>,
+    config: Option<&AntiPatternConfig>,
+) -> crate::Result<String> {
+    to_svg_with_options(
+        model,
+        &SvgOptions {
+            anti_patterns: config.cloned(),
+            ..Default::default()
+        },
+    )
+}
+
+pub fn to_svg_with_options<B: Backend>(
+    model: &OptaModel<B>,
+    options: &SvgOptions,
+) -> crate::Result<String> {
+    let patterns = match &options.anti_patterns {
+        Some(config) => detect_anti_patterns(model, config)?,
+        None => Vec::new(),
+    };
+    let severity_map = build_severity_map(&patterns);
+    let cycle_nodes = extract_cycle_nodes(&patterns);
+
+    let leaves: Vec<_> = model
+        .nodes
+        .iter()
+        .filter(|n| model.children(&n.id).is_empty())
+        .collect();
+    let mut centers = HashMap::new();
+    for node in &leaves {
+        let position = node.get_position().ok_or_else(|| {
+            anyhow::anyhow!("Node {} has no position; run a layout first", node.id)
+        })?;
+        centers.insert(node.id.as_str(), position);
+    }
+
+    // Layout units vary by algorithm, so spread the drawing until no two boxes overlap
+    let scale = leaf_scale(&centers, options);
+    let mut boxes: HashMap<&str, Rect> = centers
+        .iter()
+        .map(|(&id, &(x, y))| {
+            let rect = Rect {
+                x: x * scale - options.node_width / 2.0,
+                y: y * scale - options.node_height / 2.0,
+                width: options.node_width,
+                height: options.node_height,
+            };
+            (id, rect)
+        })
+        .collect();
+
+    // Boundaries enclose their descendants; deepest first so nested boundaries nest
+    let mut boundaries: Vec<(&str, usize)> = model
+        .nodes
+        .iter()
+        .filter(|n| !model.children(&n.id).is_empty())
+        .map(|n| (n.id.as_str(), model.ancestors(&n.id).len()))
+        .collect();
+    boundaries.sort_by_key(|&(_, depth)| Reverse(depth));
+    for &(id, _) in &boundaries {
+        let enclosed = model
+            .children(id)
+            .iter()
+            .filter_map(|child| boxes.get(child.id.as_str()).copied())
+            .reduce(Rect::union);
+        if let Some(rect) = enclosed {
+            boxes.insert(id, rect.expand(options.padding, options.padding * 1.5));
+        }
+    }
+
+    let extent = boxes
+        .values()
+        .copied()
+        .reduce(Rect::union)
+        .unwrap_or_default();
+    let (offset_x, offset_y) = (options.margin - extent.x, options.margin - extent.y);
+    let width = extent.width + 2.0 * options.margin;
+    let height = extent.height + 2.0 * options.margin;
+
+    let mut svg = String::new();
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width:.0}" height="{height:.0}" viewBox="0 0 {width:.0} {height:.0}" font-family="Helvetica, Arial, sans-serif">"#
+    )?;
+    svg.push_str("  <defs>\n");
+    for (id, color) in [("arrow", "#555555"), ("arrow-cycle", "#ff0000")] {
+        writeln!(
+            svg,
+            r#"    <marker id="{id}" viewBox="0 0 10 10" refX="10" refY="5" markerWidth="8" markerHeight="8" orient="auto-start-reverse"><path d="M 0 0 L 10 5 L 0 10 z" fill="{color}"/></marker>"#
+        )?;
+    }
+    svg.push_str("  </defs>\n");
+    writeln!(
+        svg,
+        r##"  <rect width="100%" height="100%" fill="#f8f8f8"/>"##
+    )?;
+    writeln!(
+        svg,
+        r#"  <g transform="translate({offset_x:.2} {offset_y:.2})">"#
+    )?;
+
+    // Outermost boundaries first, so inner ones are drawn on top
+    for &(id, _) in boundaries.iter().rev() {
+        let (Some(rect), Some(node)) = (boxes.get(id), model.find_node(id)) else {
+            continue;
+        };
+        writeln!(
+            svg,
+            r##"    <g class="boundary" id="{}"><rect x="{:.2}" y="{:.2}" width="{:.2}" height="{:.2}" rx="6" fill="none" stroke="#888888" stroke-dasharray="8 4"/><text x="{:.2}" y="{:.2}" font-size="12" font-weight="bold" fill="#555555">{}</text></g>"##,
+            escape(id),
+            rect.x,
+            rect.y,
+            rect.width,
+            rect.height,
+            rect.x + 8.0,
+            rect.y + 18.0,
+            escape(&node.name),
+        )?;
+    }
+
+    for edge in &model.edges {
+        let (Some(from), Some(to)) = (boxes.get(edge.from.as_str()), boxes.get(edge.to.as_str()))
+        else {
+            continue;
+        };
+        if edge.from == edge.to {
+            continue;
+        }
+        let start = from.border_point(to.center());
+        let end = to.border_point(from.center());
+        let is_cycle_edge = is_cycle_edge(&cycle_nodes, &edge.from, &edge.to);
+        let (stroke_width, color, marker) = if is_cycle_edge {
+            (3.0, "#ff0000", "arrow-cycle")
+        } else {
+            (
+                (1.0 + (edge.weight - 1.0) * 0.5).max(0.5),
+                "#555555",
+                "arrow",
+            )
+        };
+
+        writeln!(
+            svg,
+            r#"    <line x1="{:.2}" y1="{:.2}" x2="{:.2}" y2="{:.2}" stroke="{color}" stroke-width="{stroke_width:.2}" marker-end="url(#{marker})"/>"#,
+            start.0, start.1, end.0, end.1
+        )?;
+        if let Some(label) = edge.label.as_deref().filter(|l| !l.is_empty()) {
+            writeln!(
+                svg,
+                r##"    <text x="{:.2}" y="{:.2}" font-size="10" text-anchor="middle" fill="#333333">{}</text>"##,
+                (start.0 + end.0) / 2.0,
+                (start.1 + end.1) / 2.0 - 4.0,
+                escape(label)
+            )?;
+        }
+    }
+
+    for node in &leaves {
+        let rect = boxes[node.id.as_str()];
+        let severity = severity_map.get(&node.id).copied().unwrap_or(0.0);
+        let fill = severity_to_color(severity);
+        // Same conventions as `to_dot`: dashed border for isolated nodes,
+        // bold label for high severity, double border for cycle members
+        let dash = if severity > 0.0 && severity < 0.5 {
+            r#" stroke-dasharray="6 3""#
+        } else {
+            ""
+        };
+        let weight = if severity >= 0.7 { "bold" } else { "normal" };
+
+        writeln!(svg, r#"    <g class="node" id="{}">"#, escape(&node.id))?;
+        if severity > 0.0 {
+            writeln!(svg, "      <title>Severity: {:.2}</title>", severity)?;
+        }
+        let shape = node_type_to_shape(&node.node_type);
+        svg.push_str(&shape_svg(shape, &rect, &fill, dash));
+        if cycle_nodes.contains_key(&node.id) {
+            svg.push_str(&shape_svg(shape, &rect.expand(4.0, 4.0), "none", ""));
+        }
+        let (cx, cy) = rect.center();
+        writeln!(
+            svg,
+            r##"      <text x="{cx:.2}" y="{cy:.2}" font-size="12" font-weight="{weight}" text-anchor="middle" dominant-baseline="middle" fill="#333333">{}</text>"##,
+            escape(&node.name)
+        )?;
+        if let Some(technology) = &node.technology {
+            writeln!(
+                svg,
+                r##"      <text x="{cx:.2}" y="{:.2}" font-size="10" text-anchor="middle" fill="#555555">[{}]</text>"##,
+                cy + 18.0,
+                escape(technology)
+            )?;
+        }
+        svg.push_str("    </g>\n");
+    }
+
+    svg.push_str("  </g>\n</svg>\n");
+    Ok(svg)
+}
+
+/// SVG outline for one of the Graphviz shape names from `node_type_to_shape`
+fn shape_svg(shape: &str, rect: &Rect, fill: &str, dash: &str) -> String {
+    let Rect {
+        x,
+        y,
+        width,
+        height,
+    } = *rect;
+    let stroke = format!(r##"fill="{fill}" stroke="#333333" stroke-width="1.2"{dash}"##);
+    match shape {
+        "ellipse" => format!(
+            "      <ellipse cx=\"{:.2}\" cy=\"{:.2}\" rx=\"{:.2}\" ry=\"{:.2}\" {stroke}/>\n",
+            x + width / 2.0,
+            y + height / 2.0,
+            width / 2.0,
+            height / 2.0
+        ),
+        "box3d" => {
+            let depth = 6.0;
+            format!(
+                "      <path d=\"M {:.2} {:.2} l {depth} -{depth} h {:.2} v {:.2} l -{depth} {depth}\" {stroke}/>\n      <rect x=\"{x:.2}\" y=\"{y:.2}\" width=\"{:.2}\" height=\"{:.2}\" {stroke}/>\n",
+                x,
+                y,
+                width - depth,
+                height - depth,
+                width - depth,
+                height - depth
+            )
+        }
+        "component" => {
+            let tab = format!("width=\"12\" height=\"8\" {stroke}");
+            format!(
+                "      <rect x=\"{x:.2}\" y=\"{y:.2}\" width=\"{width:.2}\" height=\"{height:.2}\" {stroke}/>\n      <rect x=\"{:.2}\" y=\"{:.2}\" {tab}/>\n      <rect x=\"{:.2}\" y=\"{:.2}\" {tab}/>\n",
+                x - 6.0,
+                y + height * 0.25,
+                x - 6.0,
+                y + height * 0.6
+            )
+        }
+        _ => format!(
+            "      <rect x=\"{x:.2}\" y=\"{y:.2}\" width=\"{width:.2}\" height=\"{height:.2}\" rx=\"4\" {stroke}/>\n"
+        ),
+    }
+}
+
+/// Smallest factor (at least 1) that keeps every pair of node boxes apart
+fn leaf_scale(centers: &HashMap<&str, (f32, f32)>, options: &SvgOptions) -> f32 {
+    let points: Vec<(f32, f32)> = centers.values().copied().collect();
+    let needed_x = options.node_width + options.padding;
+    let needed_y = options.node_height + options.padding;
+
+    let mut scale: f32 = 1.0;
+    for (i, a) in points.iter().enumerate() {
+        for b in &points[i + 1..] {
+            let dx = (a.0 - b.0).abs().max(f32::EPSILON);
+            let dy = (a.1 - b.1).abs().max(f32::EPSILON);
+            scale = scale.max((needed_x / dx).min(needed_y / dy));
+        }
+    }
+    // Coincident nodes would otherwise blow the drawing up to infinity
+    scale.min(1e4)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Rect {
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+}
+
+impl Rect {
+    fn center(&self) -> (f32, f32) {
+        (self.x + self.width / 2.0, self.y + self.height / 2.0)
+    }
+
+    fn union(self, other: Rect) -> Rect {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        Rect {
+            x,
+            y,
+            width: (self.x + self.width).max(other.x + other.width) - x,
+            height: (self.y + self.height).max(other.y + other.height) - y,
+        }
+    }
+
+    /// Grow by `margin` on the sides and bottom and by `header` at the top, leaving room for a label
+    fn expand(self, margin: f32, header: f32) -> Rect {
+        Rect {
+            x: self.x - margin,
+            y: self.y - header,
+            width: self.width + 2.0 * margin,
+            height: self.height + margin + header,
+        }
+    }
+
+    /// Where the segment from the center towards `target` leaves the rectangle
+    fn border_point(&self, target: (f32, f32)) -> (f32, f32) {
+        let (cx, cy) = self.center();
+        let (dx, dy) = (target.0 - cx, target.1 - cy);
+        if dx == 0.0 && dy == 0.0 {
+            return (cx, cy);
+        }
+        let scale_x = if dx != 0.0 {
+            (self.width / 2.0) / dx.abs()
+        } else {
+            f32::MAX
+        };
+        let scale_y = if dy != 0.0 {
+            (self.height / 2.0) / dy.abs()
+        } else {
+            f32::MAX
+        };
+        let t = scale_x.min(scale_y).min(1.0);
+        (cx + dx * t, cy + dy * t)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::OptaEdge;
+    use crate::{NodeType, OptaNode};
+    use burn::backend::ndarray::NdArray;
+
+    type TestBackend = NdArray<f32>;
+
+    fn node(id: &str, node_type: NodeType, x: f32, y: f32) -> OptaNode<TestBackend> {
+        let mut node = OptaNode::new(id.to_string(), format!("{id} <svc>"), node_type);
+        node.set_position(x, y);
+        node
+    }
+
+    #[test]
+    fn test_svg_renders_shapes_edges_and_boundaries() {
+        let mut model = OptaModel::<TestBackend>::new();
+        model.add_node(node("user", NodeType::Person, 0.0, 0.0));
+        model.add_node(OptaNode::new(
+            "shop".to_string(),
+            "Shop".to_string(),
+            NodeType::System,
+        ));
+        model
+            .add_node(node("web", NodeType::Container, 0.0, 200.0).with_parent("shop".to_string()));
+        model.add_node(
+            node("api", NodeType::Container, 250.0, 200.0).with_parent("shop".to_string()),
+        );
+        model.add_edge(
+            OptaEdge::new("user".to_string(), "web".to_string()).with_label("Uses".to_string()),
+        );
+        model.add_edge(OptaEdge::new("web".to_string(), "api".to_string()));
+
+        let svg = to_svg(&model, None).unwrap();
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(svg.contains("<ellipse"));
+        assert_eq!(svg.matches("<line").count(), 2);
+        assert!(svg.contains(">Uses</text>"));
+        assert!(svg.contains(r#"<g class="boundary" id="shop">"#));
+        assert!(svg.contains("user &lt;svc&gt;"));
+        assert!(svg.trim_end().ends_with("</svg>"));
+    }
+
+    #[test]
+    fn test_svg_severity_coloring() {
+        let mut model = OptaModel::<TestBackend>::new();
+        model.add_node(node("a", NodeType::Container, 0.0, 0.0));
+        model.add_node(node("b", NodeType::Container, 300.0, 0.0));
+        model.add_node(node("lonely", NodeType::Component, 0.0, 300.0));
+        model.add_edge(OptaEdge::new("a".to_string(), "b".to_string()));
+        model.add_edge(OptaEdge::new("b".to_string(), "a".to_string()));
+
+        let svg = to_svg(&model, Some(&AntiPatternConfig::default())).unwrap();
+        assert!(svg.contains("fill=\"#ff4444\""));
+        assert!(svg.contains("marker-end=\"url(#arrow-cycle)\""));
+        assert!(svg.contains("stroke-dasharray=\"6 3\""));
+        assert!(svg.contains("<title>Severity: 0.30</title>"));
+    }
+
+    #[test]
+    fn test_svg_scales_small_layouts_and_requires_positions() {
+        let mut model = OptaModel::<TestBackend>::new();
+        model.add_node(node("a", NodeType::Container, 0.0, 0.0));
+        model.add_node(node("b", NodeType::Container, 1.0, 0.0));
+        let svg = to_svg(&model, None).unwrap();
+        // 1 layout unit apart, scaled so the 160-wide boxes sit 190 apart
+        assert!(svg.contains("width=\"390\""));
+
+        model.add_node(OptaNode::new(
+            "c".to_string(),
+            "C".to_string(),
+            NodeType::Container,
+        ));
+        assert!(to_svg(&model, None).is_err());
+    }
+}
@@ -6,12 +6,26 @@
 //! - Offline-capable PWA deployment
 //! - Edge deployment for OptaEdge assistant
 
-use crate::{NodeType, OptaModel, OptaNode, OptaOptimizer, detect_anti_patterns, parse_c4_dsl};
+use crate::{
+    AntiPatternConfig, LayoutMetrics, LayoutMetricsConfig, NodeType, OptaModel, OptaNode,
+    OptaOptimizer, RuleSet, compute_layout_metrics, detect_anti_patterns, parse_c4_dsl, to_mermaid,
+    to_plantuml, to_svg,
+};
 use burn::backend::ndarray::NdArray;
+use serde::Serialize;
 use wasm_bindgen::prelude::*;
 
 type WasmBackend = NdArray<f32>;
 
+/// JSON returned by `layoutMetrics`
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LayoutMetricsReport {
+    #[serde(flatten)]
+    metrics: LayoutMetrics,
+    quality_score: f32,
+}
+
 #[wasm_bindgen]
 extern "C" {
     #[wasm_bindgen(js_namespace = console)]
@@ -65,27 +79,74 @@ impl WasmOptaModel {
         self.inner.add_edge(edge);
     }
 
+    #[wasm_bindgen(js_name = setParent)]
+    pub fn set_parent(&mut self, child: String, parent: Option<String>) -> Result<(), JsValue> {
+        self.inner
+            .set_parent(&child, parent.as_deref())
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
     #[wasm_bindgen(js_name = buildAdjacencyMatrix)]
     pub fn build_adjacency_matrix(&mut self) {
         self.inner.build_adjacency_matrix();
     }
 
+    /// Run the force-directed layout and return its `OptimizationStats` as JSON
+    ///
+    /// With `seed` the initial placement is reproducible; with `warmStart` the
+    /// run continues from the nodes' current positions.
     #[wasm_bindgen(js_name = optimize)]
-    pub fn optimize(&mut self, iterations: usize) -> Result<(), JsValue> {
-        let optimizer = OptaOptimizer::<WasmBackend>::new(iterations, 0.1);
-        optimizer
+    pub fn optimize(
+        &mut self,
+        iterations: usize,
+        seed: Option<u64>,
+        warm_start: Option<bool>,
+    ) -> Result<String, JsValue> {
+        let mut optimizer = OptaOptimizer::<WasmBackend>::new(iterations, 0.1)
+            .with_warm_start(warm_start.unwrap_or(false));
+        if let Some(seed) = seed {
+            optimizer = optimizer.with_seed(seed);
+        }
+        let stats = optimizer
             .optimize_layout(&mut self.inner)
-            .map_err(|e| JsValue::from_str(&e.to_string()))
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+
+        serde_json::to_string(&stats).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// Fix a node at `(x, y)`, or release it when `pinned` is false
+    #[wasm_bindgen(js_name = setPinned)]
+    pub fn set_pinned(&mut self, id: &str, pinned: bool, x: f32, y: f32) -> Result<(), JsValue> {
+        let node = self
+            .inner
+            .find_node_mut(id)
+            .ok_or_else(|| JsValue::from_str(&format!("Unknown node: {}", id)))?;
+        if pinned {
+            node.pin(x, y);
+        } else {
+            node.pinned = false;
+        }
+        Ok(())
     }
 
     #[wasm_bindgen(js_name = detectAntiPatterns)]
     pub fn detect_anti_patterns(&self) -> Result<String, JsValue> {
-        let patterns =
-            detect_anti_patterns(&self.inner).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        let patterns = detect_anti_patterns(&self.inner, &AntiPatternConfig::default())
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
 
         serde_json::to_string(&patterns).map_err(|e| JsValue::from_str(&e.to_string()))
     }
 
+    /// Check a rules file (see `RuleSet`) against the model; returns the violations as JSON
+    #[wasm_bindgen(js_name = checkRules)]
+    pub fn check_rules(&self, rules_json: &str) -> Result<String, JsValue> {
+        let rules =
+            RuleSet::from_json(rules_json).map_err(|e| JsValue::from_str(&e.to_string()))?;
+
+        serde_json::to_string(&rules.check(&self.inner))
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
     #[wasm_bindgen(js_name = toJson)]
     pub fn to_json(&self) -> Result<String, JsValue> {
         let nodes: Vec<_> = self
@@ -103,6 +164,8 @@ impl WasmOptaModel {
                         NodeType::Person => "person",
                     },
                     "position": n.get_position(),
+                    "parent": n.parent,
+                    "pinned": n.pinned,
                 })
             })
             .collect();
@@ -120,12 +183,52 @@ impl WasmOptaModel {
             })
             .collect();
 
-        serde_json::json!({
+        Ok(serde_json::json!({
             "nodes": nodes,
             "edges": edges,
         })
-        .to_string()
-        .map_err(|e| JsValue::from_str(&e.to_string()))
+        .to_string())
+    }
+
+    /// Score the current layout; returns `LayoutMetrics` plus a `qualityScore` as JSON
+    ///
+    /// `nodeWidth`/`nodeHeight` set the box size used for overlap detection.
+    #[wasm_bindgen(js_name = layoutMetrics)]
+    pub fn layout_metrics(
+        &self,
+        node_width: Option<f32>,
+        node_height: Option<f32>,
+    ) -> Result<String, JsValue> {
+        let defaults = LayoutMetricsConfig::default();
+        let config = LayoutMetricsConfig {
+            node_width: node_width.unwrap_or(defaults.node_width),
+            node_height: node_height.unwrap_or(defaults.node_height),
+        };
+        let metrics = compute_layout_metrics(&self.inner, &config)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+
+        let report = LayoutMetricsReport {
+            quality_score: metrics.quality_score(self.inner.edge_count(), self.inner.node_count()),
+            metrics,
+        };
+        serde_json::to_string(&report).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// Render the current layout as SVG, colored by anti-pattern severity
+    #[wasm_bindgen(js_name = toSvg)]
+    pub fn to_svg(&self) -> Result<String, JsValue> {
+        to_svg(&self.inner, Some(&AntiPatternConfig::default()))
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    #[wasm_bindgen(js_name = toPlantUml)]
+    pub fn to_plantuml(&self) -> Result<String, JsValue> {
+        to_plantuml(&self.inner).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    #[wasm_bindgen(js_name = toMermaid)]
+    pub fn to_mermaid(&self) -> Result<String, JsValue> {
+        to_mermaid(&self.inner).map_err(|e| JsValue::from_str(&e.to_string()))
     }
 
     #[wasm_bindgen(js_name = nodeCount)]
@@ -151,7 +254,7 @@ pub fn parse_c4_dsl_wasm(input: &str) -> Result<WasmOptaModel, JsValue> {
 pub fn main() {
     #[cfg(feature = "wasm")]
     {
-        std::panic::set_hook(Box::new(console_error_panic_hook::panic_hook));
+        std::panic::set_hook(Box::new(console_error_panic_hook::hook));
         console_log!("OptaCore-Struct WASM module loaded");
     }
 }
@@ -0,0 +1,271 @@
+use crate::{OptaModel, Result};
+use burn::tensor::backend::Backend;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// A complete architecture workspace: the model plus the views and styles defined over it
+///
+/// Mirrors the top-level structure of a Structurizr workspace
+/// (`workspace { model { ... } views { ... } }`).
+#[derive(Debug)]
+pub struct Workspace<B: Backend> {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub model: OptaModel<B>,
+    pub views: Vec<ViewDefinition>,
+    pub styles: Vec<StyleRule>,
+    pub properties: BTreeMap<String, String>,
+    /// Sections of an imported workspace that OptaCore doesn't model
+    /// (documentation, deployment nodes, branding, ...), keyed by dotted JSON
+    /// path such as `model.deploymentNodes`, and written back unchanged on export
+    pub extensions: BTreeMap<String, serde_json::Value>,
+}
+
+impl<B: Backend> Workspace<B> {
+    pub fn new() -> Self {
+        Self {
+            name: None,
+            description: None,
+            model: OptaModel::new(),
+            views: Vec::new(),
+            styles: Vec::new(),
+            properties: BTreeMap::new(),
+            extensions: BTreeMap::new(),
+        }
+    }
+
+    pub fn find_view(&self, key: &str) -> Option<&ViewDefinition> {
+        self.views.iter().find(|v| v.key == key)
+    }
+
+    pub fn find_view_mut(&mut self, key: &str) -> Option<&mut ViewDefinition> {
+        self.views.iter_mut().find(|v| v.key == key)
+    }
+
+    /// Record the model's current node positions in view `key`
+    ///
+    /// Elements already in the view are updated in place; positioned nodes that
+    /// the view doesn't list yet are only added when the view has no explicit
+    /// element list, so a curated view isn't widened by accident.
+    pub fn store_layout(&mut self, key: &str) -> Result<()> {
+        let view = self
+            .views
+            .iter_mut()
+            .find(|v| v.key == key)
+            .ok_or_else(|| anyhow::anyhow!("Unknown view: {}", key))?;
+
+        let add_missing = view.elements.is_empty();
+        for node in &self.model.nodes {
+            let Some((x, y)) = node.get_position() else {
+                continue;
+            };
+            match view.elements.iter_mut().find(|e| e.id == node.id) {
+                Some(element) => {
+                    element.x = Some(x.round() as i32);
+                    element.y = Some(y.round() as i32);
+                }
+                None if add_missing => view.elements.push(ViewElement {
+                    id: node.id.clone(),
+                    x: Some(x.round() as i32),
+                    y: Some(y.round() as i32),
+                }),
+                None => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Set model node positions from those stored in view `key`, returning how many were applied
+    pub fn apply_layout(&mut self, key: &str) -> Result<usize> {
+        let view = self
+            .views
+            .iter()
+            .find(|v| v.key == key)
+            .ok_or_else(|| anyhow::anyhow!("Unknown view: {}", key))?;
+
+        let mut applied = 0;
+        for element in &view.elements {
+            let (Some(x), Some(y)) = (element.x, element.y) else {
+                continue;
+            };
+            if let Some(node) = self.model.find_node_mut(&element.id) {
+                node.set_position(x as f32, y as f32);
+                applied += 1;
+            }
+        }
+        Ok(applied)
+    }
+}
+
+impl<B: Backend> Default for Workspace<B> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ViewKind {
+    SystemLandscape,
+    SystemContext,
+    Container,
+    Component,
+    Dynamic,
+    Deployment,
+    Filtered,
+    Custom,
+}
+
+impl ViewKind {
+    /// Name used when generating a key for a view that was declared without one
+    pub fn key_prefix(&self) -> &'static str {
+        match self {
+            ViewKind::SystemLandscape => "SystemLandscape",
+            ViewKind::SystemContext => "SystemContext",
+            ViewKind::Container => "Container",
+            ViewKind::Component => "Component",
+            ViewKind::Dynamic => "Dynamic",
+            ViewKind::Deployment => "Deployment",
+            ViewKind::Filtered => "Filtered",
+            ViewKind::Custom => "Custom",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ViewDefinition {
+    pub kind: ViewKind,
+    pub key: String,
+    /// Element the view is scoped to (the software system of a container view, etc.)
+    pub scope: Option<String>,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    /// Raw `include` expressions, e.g. `*`, element identifiers or `element.tag==Database`
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    pub auto_layout: Option<AutoLayout>,
+    /// Elements placed in the view, with stored positions when laid out
+    pub elements: Vec<ViewElement>,
+    /// Identifiers of the relationships shown in the view
+    pub relationships: Vec<String>,
+    /// Format-specific view settings that OptaCore doesn't interpret
+    /// (deployment environment, base view key, animations, paper size, ...)
+    pub extensions: BTreeMap<String, serde_json::Value>,
+}
+
+impl ViewDefinition {
+    pub fn new(kind: ViewKind, key: String) -> Self {
+        Self {
+            kind,
+            key,
+            scope: None,
+            title: None,
+            description: None,
+            include: Vec::new(),
+            exclude: Vec::new(),
+            auto_layout: None,
+            elements: Vec::new(),
+            relationships: Vec::new(),
+            extensions: BTreeMap::new(),
+        }
+    }
+}
+
+/// An element placed in a view, with its stored diagram coordinates
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ViewElement {
+    pub id: String,
+    pub x: Option<i32>,
+    pub y: Option<i32>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum RankDirection {
+    #[default]
+    TopBottom,
+    BottomTop,
+    LeftRight,
+    RightLeft,
+}
+
+impl RankDirection {
+    /// Parse the DSL's short (`tb`, `lr`) and long (`TopBottom`) spellings
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "tb" | "topbottom" => Some(RankDirection::TopBottom),
+            "bt" | "bottomtop" => Some(RankDirection::BottomTop),
+            "lr" | "leftright" => Some(RankDirection::LeftRight),
+            "rl" | "rightleft" => Some(RankDirection::RightLeft),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub struct AutoLayout {
+    pub direction: RankDirection,
+    pub rank_separation: Option<u32>,
+    pub node_separation: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum StyleTarget {
+    Element,
+    Relationship,
+}
+
+/// A style applied to every element or relationship carrying `tag`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StyleRule {
+    pub target: StyleTarget,
+    pub tag: String,
+    pub properties: BTreeMap<String, String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{NodeType, OptaNode};
+    use burn::backend::ndarray::NdArray;
+
+    type TestBackend = NdArray<f32>;
+
+    #[test]
+    fn test_store_and_apply_layout() {
+        let mut workspace = Workspace::<TestBackend>::new();
+        for id in ["a", "b"] {
+            workspace.model.add_node(OptaNode::new(
+                id.to_string(),
+                id.to_uppercase(),
+                NodeType::Container,
+            ));
+        }
+        workspace
+            .model
+            .find_node_mut("a")
+            .unwrap()
+            .set_position(10.4, 20.6);
+        workspace.views.push(ViewDefinition::new(
+            ViewKind::Container,
+            "containers".to_string(),
+        ));
+
+        workspace.store_layout("containers").unwrap();
+        let view = workspace.find_view("containers").unwrap();
+        assert_eq!(
+            view.elements,
+            vec![ViewElement {
+                id: "a".to_string(),
+                x: Some(10),
+                y: Some(21),
+            }]
+        );
+
+        workspace.find_view_mut("containers").unwrap().elements[0].x = Some(300);
+        assert_eq!(workspace.apply_layout("containers").unwrap(), 1);
+        assert_eq!(
+            workspace.model.find_node("a").unwrap().get_position(),
+            Some((300.0, 21.0))
+        );
+        assert!(workspace.store_layout("missing").is_err());
+    }
+}
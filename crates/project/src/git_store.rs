@@ -30,8 +30,9 @@ use git::{
     parse_git_remote_url,
     repository::{
         Branch, CommitDetails, CommitDiff, CommitFile, CommitOptions, DiffType, FetchOptions,
-        GitRepository, GitRepositoryCheckpoint, PushOptions, Remote, RemoteCommandOutput, RepoPath,
-        ResetMode, UpstreamTrackingStatus, Worktree as GitWorktree,
+        GitRepository, GitRepositoryCheckpoint, LogEntry, LogOptions, PushOptions, RebaseAction,
+        RebaseState, RebaseTodoEntry, Remote, RemoteCommandOutput, RepoPath, ResetMode,
+        SequencerOperation, UpstreamTrackingStatus, Worktree as GitWorktree,
     },
     stash::{GitStash, StashEntry},
     status::{
@@ -485,12 +486,27 @@ impl GitStore {
         client.add_entity_request_handler(Self::handle_stash_pop);
         client.add_entity_request_handler(Self::handle_stash_apply);
         client.add_entity_request_handler(Self::handle_stash_drop);
+        client.add_entity_request_handler(Self::handle_get_rebase_todo);
+        client.add_entity_request_handler(Self::handle_rebase);
+        client.add_entity_request_handler(Self::handle_get_rebase_state);
+        client.add_entity_request_handler(Self::handle_rebase_continue);
+        client.add_entity_request_handler(Self::handle_rebase_skip);
+        client.add_entity_request_handler(Self::handle_rebase_abort);
+        client.add_entity_request_handler(Self::handle_cherry_pick);
+        client.add_entity_request_handler(Self::handle_revert);
+        client.add_entity_request_handler(Self::handle_get_sequencer_operation);
+        client.add_entity_request_handler(Self::handle_sequencer_continue);
+        client.add_entity_request_handler(Self::handle_sequencer_abort);
+        client.add_entity_request_handler(Self::handle_amend_commit);
         client.add_entity_request_handler(Self::handle_commit);
         client.add_entity_request_handler(Self::handle_run_hook);
         client.add_entity_request_handler(Self::handle_reset);
         client.add_entity_request_handler(Self::handle_show);
         client.add_entity_request_handler(Self::handle_load_commit_diff);
         client.add_entity_request_handler(Self::handle_file_history);
+        client.add_entity_request_handler(Self::handle_log);
+        client.add_entity_request_handler(Self::handle_create_tag);
+        client.add_entity_request_handler(Self::handle_checkout_commit);
         client.add_entity_request_handler(Self::handle_checkout_files);
         client.add_entity_request_handler(Self::handle_open_commit_message_buffer);
         client.add_entity_request_handler(Self::handle_set_index_text);
@@ -2039,6 +2055,228 @@ impl GitStore {
         Ok(proto::Ack {})
     }
 
+    async fn handle_get_rebase_todo(
+        this: Entity<Self>,
+        envelope: TypedEnvelope<proto::GitGetRebaseTodo>,
+        mut cx: AsyncApp,
+    ) -> Result<proto::GitGetRebaseTodoResponse> {
+        let repository_id = RepositoryId::from_proto(envelope.payload.repository_id);
+        let repository_handle = Self::repository_for_request(&this, repository_id, &mut cx)?;
+
+        let entries = repository_handle
+            .update(&mut cx, |repository_handle, _| {
+                repository_handle.rebase_todo(envelope.payload.base)
+            })?
+            .await??;
+
+        Ok(proto::GitGetRebaseTodoResponse {
+            entries: entries.iter().map(rebase_todo_entry_to_proto).collect(),
+        })
+    }
+
+    async fn handle_rebase(
+        this: Entity<Self>,
+        envelope: TypedEnvelope<proto::GitRebase>,
+        mut cx: AsyncApp,
+    ) -> Result<proto::GitRebaseResponse> {
+        let repository_id = RepositoryId::from_proto(envelope.payload.repository_id);
+        let repository_handle = Self::repository_for_request(&this, repository_id, &mut cx)?;
+        let todo = envelope
+            .payload
+            .todo
+            .iter()
+            .map(proto_to_rebase_todo_entry)
+            .collect();
+
+        let state = repository_handle
+            .update(&mut cx, |repository_handle, _| {
+                repository_handle.rebase(envelope.payload.base, todo)
+            })?
+            .await??;
+
+        Ok(proto::GitRebaseResponse {
+            state: state.as_ref().map(rebase_state_to_proto),
+        })
+    }
+
+    async fn handle_get_rebase_state(
+        this: Entity<Self>,
+        envelope: TypedEnvelope<proto::GitGetRebaseState>,
+        mut cx: AsyncApp,
+    ) -> Result<proto::GitRebaseResponse> {
+        let repository_id = RepositoryId::from_proto(envelope.payload.repository_id);
+        let repository_handle = Self::repository_for_request(&this, repository_id, &mut cx)?;
+
+        let state = repository_handle
+            .update(&mut cx, |repository_handle, _| {
+                repository_handle.rebase_state()
+            })?
+            .await??;
+
+        Ok(proto::GitRebaseResponse {
+            state: state.as_ref().map(rebase_state_to_proto),
+        })
+    }
+
+    async fn handle_rebase_continue(
+        this: Entity<Self>,
+        envelope: TypedEnvelope<proto::GitRebaseContinue>,
+        mut cx: AsyncApp,
+    ) -> Result<proto::GitRebaseResponse> {
+        let repository_id = RepositoryId::from_proto(envelope.payload.repository_id);
+        let repository_handle = Self::repository_for_request(&this, repository_id, &mut cx)?;
+
+        let state = repository_handle
+            .update(&mut cx, |repository_handle, _| {
+                repository_handle.rebase_continue()
+            })?
+            .await??;
+
+        Ok(proto::GitRebaseResponse {
+            state: state.as_ref().map(rebase_state_to_proto),
+        })
+    }
+
+    async fn handle_rebase_skip(
+        this: Entity<Self>,
+        envelope: TypedEnvelope<proto::GitRebaseSkip>,
+        mut cx: AsyncApp,
+    ) -> Result<proto::GitRebaseResponse> {
+        let repository_id = RepositoryId::from_proto(envelope.payload.repository_id);
+        let repository_handle = Self::repository_for_request(&this, repository_id, &mut cx)?;
+
+        let state = repository_handle
+            .update(&mut cx, |repository_handle, _| {
+                repository_handle.rebase_skip()
+            })?
+            .await??;
+
+        Ok(proto::GitRebaseResponse {
+            state: state.as_ref().map(rebase_state_to_proto),
+        })
+    }
+
+    async fn handle_rebase_abort(
+        this: Entity<Self>,
+        envelope: TypedEnvelope<proto::GitRebaseAbort>,
+        mut cx: AsyncApp,
+    ) -> Result<proto::Ack> {
+        let repository_id = RepositoryId::from_proto(envelope.payload.repository_id);
+        let repository_handle = Self::repository_for_request(&this, repository_id, &mut cx)?;
+
+        repository_handle
+            .update(&mut cx, |repository_handle, _| {
+                repository_handle.rebase_abort()
+            })?
+            .await??;
+
+        Ok(proto::Ack {})
+    }
+
+    async fn handle_cherry_pick(
+        this: Entity<Self>,
+        envelope: TypedEnvelope<proto::GitCherryPick>,
+        mut cx: AsyncApp,
+    ) -> Result<proto::Ack> {
+        let repository_id = RepositoryId::from_proto(envelope.payload.repository_id);
+        let repository_handle = Self::repository_for_request(&this, repository_id, &mut cx)?;
+
+        repository_handle
+            .update(&mut cx, |repository_handle, _| {
+                repository_handle.cherry_pick(envelope.payload.commits)
+            })?
+            .await??;
+
+        Ok(proto::Ack {})
+    }
+
+    async fn handle_revert(
+        this: Entity<Self>,
+        envelope: TypedEnvelope<proto::GitRevert>,
+        mut cx: AsyncApp,
+    ) -> Result<proto::Ack> {
+        let repository_id = RepositoryId::from_proto(envelope.payload.repository_id);
+        let repository_handle = Self::repository_for_request(&this, repository_id, &mut cx)?;
+
+        repository_handle
+            .update(&mut cx, |repository_handle, _| {
+                repository_handle.revert(envelope.payload.commits)
+            })?
+            .await??;
+
+        Ok(proto::Ack {})
+    }
+
+    async fn handle_get_sequencer_operation(
+        this: Entity<Self>,
+        envelope: TypedEnvelope<proto::GitGetSequencerOperation>,
+        mut cx: AsyncApp,
+    ) -> Result<proto::GitSequencerResponse> {
+        let repository_id = RepositoryId::from_proto(envelope.payload.repository_id);
+        let repository_handle = Self::repository_for_request(&this, repository_id, &mut cx)?;
+
+        let operation = repository_handle
+            .update(&mut cx, |repository_handle, _| {
+                repository_handle.sequencer_operation()
+            })?
+            .await??;
+
+        Ok(sequencer_operation_to_proto(operation))
+    }
+
+    async fn handle_sequencer_continue(
+        this: Entity<Self>,
+        envelope: TypedEnvelope<proto::GitSequencerContinue>,
+        mut cx: AsyncApp,
+    ) -> Result<proto::GitSequencerResponse> {
+        let repository_id = RepositoryId::from_proto(envelope.payload.repository_id);
+        let repository_handle = Self::repository_for_request(&this, repository_id, &mut cx)?;
+
+        let operation = repository_handle
+            .update(&mut cx, |repository_handle, _| {
+                repository_handle.sequencer_continue()
+            })?
+            .await??;
+
+        Ok(sequencer_operation_to_proto(operation))
+    }
+
+    async fn handle_sequencer_abort(
+        this: Entity<Self>,
+        envelope: TypedEnvelope<proto::GitSequencerAbort>,
+        mut cx: AsyncApp,
+    ) -> Result<proto::Ack> {
+        let repository_id = RepositoryId::from_proto(envelope.payload.repository_id);
+        let repository_handle = Self::repository_for_request(&this, repository_id, &mut cx)?;
+
+        repository_handle
+            .update(&mut cx, |repository_handle, _| {
+                repository_handle.sequencer_abort()
+            })?
+            .await??;
+
+        Ok(proto::Ack {})
+    }
+
+    async fn handle_amend_commit(
+        this: Entity<Self>,
+        envelope: TypedEnvelope<proto::GitAmendCommit>,
+        mut cx: AsyncApp,
+    ) -> Result<proto::GitRebaseResponse> {
+        let repository_id = RepositoryId::from_proto(envelope.payload.repository_id);
+        let repository_handle = Self::repository_for_request(&this, repository_id, &mut cx)?;
+
+        let state = repository_handle
+            .update(&mut cx, |repository_handle, _| {
+                repository_handle.amend_commit(envelope.payload.commit)
+            })?
+            .await??;
+
+        Ok(proto::GitRebaseResponse {
+            state: state.as_ref().map(rebase_state_to_proto),
+        })
+    }
+
     async fn handle_set_index_text(
         this: Entity<Self>,
         envelope: TypedEnvelope<proto::SetIndexText>,
@@ -2363,7 +2601,10 @@ impl GitStore {
 
         let commit_diff = repository_handle
             .update(&mut cx, |repository_handle, _| {
-                repository_handle.load_commit_diff(envelope.payload.commit)
+                match envelope.payload.base {
+                    Some(base) => repository_handle.compare_commits(base, envelope.payload.commit),
+                    None => repository_handle.load_commit_diff(envelope.payload.commit),
+                }
             })?
             .await??;
         Ok(proto::LoadCommitDiffResponse {
@@ -2413,6 +2654,74 @@ impl GitStore {
         })
     }
 
+    async fn handle_log(
+        this: Entity<Self>,
+        envelope: TypedEnvelope<proto::GitLog>,
+        mut cx: AsyncApp,
+    ) -> Result<proto::GitLogResponse> {
+        let repository_id = RepositoryId::from_proto(envelope.payload.repository_id);
+        let repository_handle = Self::repository_for_request(&this, repository_id, &mut cx)?;
+        let payload = envelope.payload;
+        let options = LogOptions {
+            all_refs: payload.all_refs,
+            author: payload.author,
+            message: payload.message,
+            path: payload
+                .path
+                .as_deref()
+                .map(RepoPath::from_proto)
+                .transpose()?,
+            since: payload.since,
+            until: payload.until,
+        };
+        let skip = payload.skip as usize;
+        let limit = payload.limit.map(|l| l as usize);
+
+        let entries = repository_handle
+            .update(&mut cx, |repository_handle, _| {
+                repository_handle.log(options, skip, limit)
+            })?
+            .await??;
+
+        Ok(proto::GitLogResponse {
+            entries: entries.iter().map(log_entry_to_proto).collect(),
+        })
+    }
+
+    async fn handle_create_tag(
+        this: Entity<Self>,
+        envelope: TypedEnvelope<proto::GitCreateTag>,
+        mut cx: AsyncApp,
+    ) -> Result<proto::Ack> {
+        let repository_id = RepositoryId::from_proto(envelope.payload.repository_id);
+        let repository_handle = Self::repository_for_request(&this, repository_id, &mut cx)?;
+
+        repository_handle
+            .update(&mut cx, |repository_handle, _| {
+                repository_handle.create_tag(envelope.payload.name, envelope.payload.commit)
+            })?
+            .await??;
+
+        Ok(proto::Ack {})
+    }
+
+    async fn handle_checkout_commit(
+        this: Entity<Self>,
+        envelope: TypedEnvelope<proto::GitCheckoutCommit>,
+        mut cx: AsyncApp,
+    ) -> Result<proto::Ack> {
+        let repository_id = RepositoryId::from_proto(envelope.payload.repository_id);
+        let repository_handle = Self::repository_for_request(&this, repository_id, &mut cx)?;
+
+        repository_handle
+            .update(&mut cx, |repository_handle, _| {
+                repository_handle.checkout_commit(envelope.payload.commit)
+            })?
+            .await??;
+
+        Ok(proto::Ack {})
+    }
+
     async fn handle_reset(
         this: Entity<Self>,
         envelope: TypedEnvelope<proto::GitReset>,
@@ -4085,12 +4394,30 @@ impl Repository {
     }
 
     pub fn load_commit_diff(&mut self, commit: String) -> oneshot::Receiver<Result<CommitDiff>> {
+        self.load_diff(None, commit)
+    }
+
+    /// Loads the changes from `base` to `head`, which needn't be related.
+    pub fn compare_commits(
+        &mut self,
+        base: String,
+        head: String,
+    ) -> oneshot::Receiver<Result<CommitDiff>> {
+        self.load_diff(Some(base), head)
+    }
+
+    fn load_diff(
+        &mut self,
+        base: Option<String>,
+        commit: String,
+    ) -> oneshot::Receiver<Result<CommitDiff>> {
         let id = self.id;
         self.send_job(None, move |git_repo, cx| async move {
             match git_repo {
-                RepositoryState::Local(LocalRepositoryState { backend, .. }) => {
-                    backend.load_commit(commit, cx).await
-                }
+                RepositoryState::Local(LocalRepositoryState { backend, .. }) => match base {
+                    Some(base) => backend.compare_commits(base, commit, cx).await,
+                    None => backend.load_commit(commit, cx).await,
+                },
                 RepositoryState::Remote(RemoteRepositoryState {
                     client, project_id, ..
                 }) => {
@@ -4099,6 +4426,7 @@ impl Repository {
                             project_id: project_id.0,
                             repository_id: id.to_proto(),
                             commit,
+                            base,
                         })
                         .await?;
                     Ok(CommitDiff {
@@ -4168,43 +4496,127 @@ impl Repository {
         })
     }
 
-    fn buffer_store(&self, cx: &App) -> Option<Entity<BufferStore>> {
-        Some(self.git_store.upgrade()?.read(cx).buffer_store.clone())
-    }
-
-    fn save_buffers<'a>(
-        &self,
-        entries: impl IntoIterator<Item = &'a RepoPath>,
-        cx: &mut Context<Self>,
-    ) -> Vec<Task<anyhow::Result<()>>> {
-        let mut save_futures = Vec::new();
-        if let Some(buffer_store) = self.buffer_store(cx) {
-            buffer_store.update(cx, |buffer_store, cx| {
-                for path in entries {
-                    let Some(project_path) = self.repo_path_to_project_path(path, cx) else {
-                        continue;
-                    };
-                    if let Some(buffer) = buffer_store.get_by_path(&project_path)
-                        && buffer
-                            .read(cx)
-                            .file()
-                            .is_some_and(|file| file.disk_state().exists())
-                        && buffer.read(cx).has_unsaved_edits()
-                    {
-                        save_futures.push(buffer_store.save_buffer(buffer, cx));
-                    }
+    pub fn log(
+        &mut self,
+        options: LogOptions,
+        skip: usize,
+        limit: Option<usize>,
+    ) -> oneshot::Receiver<Result<Vec<LogEntry>>> {
+        let id = self.id;
+        self.send_job(None, move |git_repo, _cx| async move {
+            match git_repo {
+                RepositoryState::Local(LocalRepositoryState { backend, .. }) => {
+                    backend.log(options, skip, limit).await
                 }
-            })
-        }
-        save_futures
+                RepositoryState::Remote(RemoteRepositoryState { client, project_id }) => {
+                    let response = client
+                        .request(proto::GitLog {
+                            project_id: project_id.0,
+                            repository_id: id.to_proto(),
+                            skip: skip as u64,
+                            limit: limit.map(|l| l as u64),
+                            all_refs: options.all_refs,
+                            author: options.author,
+                            message: options.message,
+                            path: options.path.map(|path| path.to_proto()),
+                            since: options.since,
+                            until: options.until,
+                        })
+                        .await?;
+                    Ok(response.entries.iter().map(proto_to_log_entry).collect())
+                }
+            }
+        })
     }
 
-    pub fn stage_entries(
-        &mut self,
-        entries: Vec<RepoPath>,
-        cx: &mut Context<Self>,
-    ) -> Task<anyhow::Result<()>> {
-        if entries.is_empty() {
+    pub fn create_tag(&mut self, name: String, commit: String) -> oneshot::Receiver<Result<()>> {
+        let id = self.id;
+        self.send_job(
+            Some(format!("git tag {name}").into()),
+            move |repo, _cx| async move {
+                match repo {
+                    RepositoryState::Local(LocalRepositoryState { backend, .. }) => {
+                        backend.create_tag(name, commit).await
+                    }
+                    RepositoryState::Remote(RemoteRepositoryState { project_id, client }) => {
+                        client
+                            .request(proto::GitCreateTag {
+                                project_id: project_id.0,
+                                repository_id: id.to_proto(),
+                                name,
+                                commit,
+                            })
+                            .await?;
+
+                        Ok(())
+                    }
+                }
+            },
+        )
+    }
+
+    pub fn checkout_commit(&mut self, commit: String) -> oneshot::Receiver<Result<()>> {
+        let id = self.id;
+        self.send_job(
+            Some(format!("git checkout --detach {commit}").into()),
+            move |repo, _cx| async move {
+                match repo {
+                    RepositoryState::Local(LocalRepositoryState { backend, .. }) => {
+                        backend.checkout_commit(commit).await
+                    }
+                    RepositoryState::Remote(RemoteRepositoryState { project_id, client }) => {
+                        client
+                            .request(proto::GitCheckoutCommit {
+                                project_id: project_id.0,
+                                repository_id: id.to_proto(),
+                                commit,
+                            })
+                            .await?;
+
+                        Ok(())
+                    }
+                }
+            },
+        )
+    }
+
+    fn buffer_store(&self, cx: &App) -> Option<Entity<BufferStore>> {
+        Some(self.git_store.upgrade()?.read(cx).buffer_store.clone())
+    }
+
+    fn save_buffers<'a>(
+        &self,
+        entries: impl IntoIterator<Item = &'a RepoPath>,
+        cx: &mut Context<Self>,
+    ) -> Vec<Task<anyhow::Result<()>>> {
+        let mut save_futures = Vec::new();
+        if let Some(buffer_store) = self.buffer_store(cx) {
+            buffer_store.update(cx, |buffer_store, cx| {
+                for path in entries {
+                    let Some(project_path) = self.repo_path_to_project_path(path, cx) else {
+                        continue;
+                    };
+                    if let Some(buffer) = buffer_store.get_by_path(&project_path)
+                        && buffer
+                            .read(cx)
+                            .file()
+                            .is_some_and(|file| file.disk_state().exists())
+                        && buffer.read(cx).has_unsaved_edits()
+                    {
+                        save_futures.push(buffer_store.save_buffer(buffer, cx));
+                    }
+                }
+            })
+        }
+        save_futures
+    }
+
+    pub fn stage_entries(
+        &mut self,
+        entries: Vec<RepoPath>,
+        cx: &mut Context<Self>,
+    ) -> Task<anyhow::Result<()>> {
+        if entries.is_empty() {
             return Task::ready(Ok(()));
         }
         let id = self.id;
@@ -4537,6 +4949,317 @@ impl Repository {
         })
     }
 
+    pub fn rebase_todo(&mut self, base: String) -> oneshot::Receiver<Result<Vec<RebaseTodoEntry>>> {
+        let id = self.id;
+        self.send_job(None, move |repo, _cx| async move {
+            match repo {
+                RepositoryState::Local(LocalRepositoryState { backend, .. }) => {
+                    backend.rebase_todo(base).await
+                }
+                RepositoryState::Remote(RemoteRepositoryState { project_id, client }) => {
+                    let response = client
+                        .request(proto::GitGetRebaseTodo {
+                            project_id: project_id.0,
+                            repository_id: id.to_proto(),
+                            base,
+                        })
+                        .await?;
+
+                    Ok(response
+                        .entries
+                        .iter()
+                        .map(proto_to_rebase_todo_entry)
+                        .collect())
+                }
+            }
+        })
+    }
+
+    pub fn rebase(
+        &mut self,
+        base: String,
+        todo: Vec<RebaseTodoEntry>,
+    ) -> oneshot::Receiver<Result<Option<RebaseState>>> {
+        let id = self.id;
+        self.send_job(
+            Some(format!("git rebase --interactive {base}").into()),
+            move |repo, _cx| async move {
+                match repo {
+                    RepositoryState::Local(LocalRepositoryState {
+                        backend,
+                        environment,
+                        ..
+                    }) => backend.rebase(base, todo, environment).await,
+                    RepositoryState::Remote(RemoteRepositoryState { project_id, client }) => {
+                        let response = client
+                            .request(proto::GitRebase {
+                                project_id: project_id.0,
+                                repository_id: id.to_proto(),
+                                base,
+                                todo: todo.iter().map(rebase_todo_entry_to_proto).collect(),
+                            })
+                            .await?;
+
+                        Ok(response.state.as_ref().map(proto_to_rebase_state))
+                    }
+                }
+            },
+        )
+    }
+
+    pub fn rebase_state(&mut self) -> oneshot::Receiver<Result<Option<RebaseState>>> {
+        let id = self.id;
+        self.send_job(None, move |repo, _cx| async move {
+            match repo {
+                RepositoryState::Local(LocalRepositoryState { backend, .. }) => {
+                    backend.rebase_state().await
+                }
+                RepositoryState::Remote(RemoteRepositoryState { project_id, client }) => {
+                    let response = client
+                        .request(proto::GitGetRebaseState {
+                            project_id: project_id.0,
+                            repository_id: id.to_proto(),
+                        })
+                        .await?;
+
+                    Ok(response.state.as_ref().map(proto_to_rebase_state))
+                }
+            }
+        })
+    }
+
+    pub fn rebase_continue(&mut self) -> oneshot::Receiver<Result<Option<RebaseState>>> {
+        let id = self.id;
+        self.send_job(
+            Some("git rebase --continue".into()),
+            move |repo, _cx| async move {
+                match repo {
+                    RepositoryState::Local(LocalRepositoryState {
+                        backend,
+                        environment,
+                        ..
+                    }) => backend.rebase_continue(environment).await,
+                    RepositoryState::Remote(RemoteRepositoryState { project_id, client }) => {
+                        let response = client
+                            .request(proto::GitRebaseContinue {
+                                project_id: project_id.0,
+                                repository_id: id.to_proto(),
+                            })
+                            .await?;
+
+                        Ok(response.state.as_ref().map(proto_to_rebase_state))
+                    }
+                }
+            },
+        )
+    }
+
+    pub fn rebase_skip(&mut self) -> oneshot::Receiver<Result<Option<RebaseState>>> {
+        let id = self.id;
+        self.send_job(
+            Some("git rebase --skip".into()),
+            move |repo, _cx| async move {
+                match repo {
+                    RepositoryState::Local(LocalRepositoryState {
+                        backend,
+                        environment,
+                        ..
+                    }) => backend.rebase_skip(environment).await,
+                    RepositoryState::Remote(RemoteRepositoryState { project_id, client }) => {
+                        let response = client
+                            .request(proto::GitRebaseSkip {
+                                project_id: project_id.0,
+                                repository_id: id.to_proto(),
+                            })
+                            .await?;
+
+                        Ok(response.state.as_ref().map(proto_to_rebase_state))
+                    }
+                }
+            },
+        )
+    }
+
+    pub fn rebase_abort(&mut self) -> oneshot::Receiver<Result<()>> {
+        let id = self.id;
+        self.send_job(
+            Some("git rebase --abort".into()),
+            move |repo, _cx| async move {
+                match repo {
+                    RepositoryState::Local(LocalRepositoryState {
+                        backend,
+                        environment,
+                        ..
+                    }) => backend.rebase_abort(environment).await,
+                    RepositoryState::Remote(RemoteRepositoryState { project_id, client }) => {
+                        client
+                            .request(proto::GitRebaseAbort {
+                                project_id: project_id.0,
+                                repository_id: id.to_proto(),
+                            })
+                            .await?;
+
+                        Ok(())
+                    }
+                }
+            },
+        )
+    }
+
+    pub fn cherry_pick(&mut self, commits: Vec<String>) -> oneshot::Receiver<Result<()>> {
+        let id = self.id;
+        self.send_job(
+            Some(format!("git cherry-pick {}", commits.join(" ")).into()),
+            move |repo, _cx| async move {
+                match repo {
+                    RepositoryState::Local(LocalRepositoryState {
+                        backend,
+                        environment,
+                        ..
+                    }) => backend.cherry_pick(commits, environment).await,
+                    RepositoryState::Remote(RemoteRepositoryState { project_id, client }) => {
+                        client
+                            .request(proto::GitCherryPick {
+                                project_id: project_id.0,
+                                repository_id: id.to_proto(),
+                                commits,
+                            })
+                            .await?;
+
+                        Ok(())
+                    }
+                }
+            },
+        )
+    }
+
+    pub fn revert(&mut self, commits: Vec<String>) -> oneshot::Receiver<Result<()>> {
+        let id = self.id;
+        self.send_job(
+            Some(format!("git revert {}", commits.join(" ")).into()),
+            move |repo, _cx| async move {
+                match repo {
+                    RepositoryState::Local(LocalRepositoryState {
+                        backend,
+                        environment,
+                        ..
+                    }) => backend.revert(commits, environment).await,
+                    RepositoryState::Remote(RemoteRepositoryState { project_id, client }) => {
+                        client
+                            .request(proto::GitRevert {
+                                project_id: project_id.0,
+                                repository_id: id.to_proto(),
+                                commits,
+                            })
+                            .await?;
+
+                        Ok(())
+                    }
+                }
+            },
+        )
+    }
+
+    /// The cherry-pick or revert that stopped on a conflict, if any.
+    pub fn sequencer_operation(&mut self) -> oneshot::Receiver<Result<Option<SequencerOperation>>> {
+        let id = self.id;
+        self.send_job(None, move |repo, _cx| async move {
+            match repo {
+                RepositoryState::Local(LocalRepositoryState { backend, .. }) => {
+                    backend.sequencer_operation().await
+                }
+                RepositoryState::Remote(RemoteRepositoryState { project_id, client }) => {
+                    let response = client
+                        .request(proto::GitGetSequencerOperation {
+                            project_id: project_id.0,
+                            repository_id: id.to_proto(),
+                        })
+                        .await?;
+
+                    Ok(proto_to_sequencer_operation(&response))
+                }
+            }
+        })
+    }
+
+    pub fn sequencer_continue(&mut self) -> oneshot::Receiver<Result<Option<SequencerOperation>>> {
+        let id = self.id;
+        self.send_job(None, move |repo, _cx| async move {
+            match repo {
+                RepositoryState::Local(LocalRepositoryState {
+                    backend,
+                    environment,
+                    ..
+                }) => backend.sequencer_continue(environment).await,
+                RepositoryState::Remote(RemoteRepositoryState { project_id, client }) => {
+                    let response = client
+                        .request(proto::GitSequencerContinue {
+                            project_id: project_id.0,
+                            repository_id: id.to_proto(),
+                        })
+                        .await?;
+
+                    Ok(proto_to_sequencer_operation(&response))
+                }
+            }
+        })
+    }
+
+    pub fn sequencer_abort(&mut self) -> oneshot::Receiver<Result<()>> {
+        let id = self.id;
+        self.send_job(None, move |repo, _cx| async move {
+            match repo {
+                RepositoryState::Local(LocalRepositoryState {
+                    backend,
+                    environment,
+                    ..
+                }) => backend.sequencer_abort(environment).await,
+                RepositoryState::Remote(RemoteRepositoryState { project_id, client }) => {
+                    client
+                        .request(proto::GitSequencerAbort {
+                            project_id: project_id.0,
+                            repository_id: id.to_proto(),
+                        })
+                        .await?;
+
+                    Ok(())
+                }
+            }
+        })
+    }
+
+    /// Folds the staged changes into `commit`, an earlier commit on the
+    /// current branch.
+    pub fn amend_commit(
+        &mut self,
+        commit: String,
+    ) -> oneshot::Receiver<Result<Option<RebaseState>>> {
+        let id = self.id;
+        self.send_job(
+            Some(format!("git commit --fixup={commit}").into()),
+            move |repo, _cx| async move {
+                match repo {
+                    RepositoryState::Local(LocalRepositoryState {
+                        backend,
+                        environment,
+                        ..
+                    }) => backend.amend_commit(commit, environment).await,
+                    RepositoryState::Remote(RemoteRepositoryState { project_id, client }) => {
+                        let response = client
+                            .request(proto::GitAmendCommit {
+                                project_id: project_id.0,
+                                repository_id: id.to_proto(),
+                                commit,
+                            })
+                            .await?;
+
+                        Ok(response.state.as_ref().map(proto_to_rebase_state))
+                    }
+                }
+            },
+        )
+    }
+
     pub fn run_hook(&mut self, hook: RunHook, _cx: &mut App) -> oneshot::Receiver<Result<()>> {
         let id = self.id;
         self.send_job(
@@ -6096,6 +6819,101 @@ fn proto_to_commit_details(proto: &proto::GitCommitDetails) -> CommitDetails {
     }
 }
 
+fn rebase_todo_entry_to_proto(entry: &RebaseTodoEntry) -> proto::GitRebaseTodoEntry {
+    let action = match entry.action {
+        RebaseAction::Pick => proto::git_rebase_todo_entry::Action::Pick,
+        RebaseAction::Reword => proto::git_rebase_todo_entry::Action::Reword,
+        RebaseAction::Squash => proto::git_rebase_todo_entry::Action::Squash,
+        RebaseAction::Fixup => proto::git_rebase_todo_entry::Action::Fixup,
+        RebaseAction::Drop => proto::git_rebase_todo_entry::Action::Drop,
+    };
+    proto::GitRebaseTodoEntry {
+        action: action as i32,
+        sha: entry.sha.to_string(),
+        subject: entry.subject.to_string(),
+        message: entry.message.as_ref().map(|message| message.to_string()),
+    }
+}
+
+fn proto_to_rebase_todo_entry(proto: &proto::GitRebaseTodoEntry) -> RebaseTodoEntry {
+    let action = match proto.action() {
+        proto::git_rebase_todo_entry::Action::Pick => RebaseAction::Pick,
+        proto::git_rebase_todo_entry::Action::Reword => RebaseAction::Reword,
+        proto::git_rebase_todo_entry::Action::Squash => RebaseAction::Squash,
+        proto::git_rebase_todo_entry::Action::Fixup => RebaseAction::Fixup,
+        proto::git_rebase_todo_entry::Action::Drop => RebaseAction::Drop,
+    };
+    RebaseTodoEntry {
+        action,
+        sha: proto.sha.clone().into(),
+        subject: proto.subject.clone().into(),
+        message: proto.message.clone().map(Into::into),
+    }
+}
+
+fn rebase_state_to_proto(state: &RebaseState) -> proto::GitRebaseState {
+    proto::GitRebaseState {
+        head_name: state.head_name.as_ref().map(|name| name.to_string()),
+        onto: state.onto.to_string(),
+        stopped_sha: state.stopped_sha.as_ref().map(|sha| sha.to_string()),
+        done: state.done.iter().map(rebase_todo_entry_to_proto).collect(),
+        todo: state.todo.iter().map(rebase_todo_entry_to_proto).collect(),
+    }
+}
+
+fn proto_to_rebase_state(proto: &proto::GitRebaseState) -> RebaseState {
+    RebaseState {
+        head_name: proto.head_name.clone().map(Into::into),
+        onto: proto.onto.clone().into(),
+        stopped_sha: proto.stopped_sha.clone().map(Into::into),
+        done: proto.done.iter().map(proto_to_rebase_todo_entry).collect(),
+        todo: proto.todo.iter().map(proto_to_rebase_todo_entry).collect(),
+    }
+}
+
+fn sequencer_operation_to_proto(
+    operation: Option<SequencerOperation>,
+) -> proto::GitSequencerResponse {
+    let operation = operation.map(|operation| match operation {
+        SequencerOperation::CherryPick => proto::git_sequencer_response::Operation::CherryPick,
+        SequencerOperation::Revert => proto::git_sequencer_response::Operation::Revert,
+    });
+    proto::GitSequencerResponse {
+        operation: operation.map(|operation| operation as i32),
+    }
+}
+
+fn proto_to_sequencer_operation(proto: &proto::GitSequencerResponse) -> Option<SequencerOperation> {
+    proto.operation.is_some().then(|| match proto.operation() {
+        proto::git_sequencer_response::Operation::CherryPick => SequencerOperation::CherryPick,
+        proto::git_sequencer_response::Operation::Revert => SequencerOperation::Revert,
+    })
+}
+
+fn log_entry_to_proto(entry: &LogEntry) -> proto::GitLogEntry {
+    proto::GitLogEntry {
+        sha: entry.sha.to_string(),
+        parents: entry.parents.iter().map(|sha| sha.to_string()).collect(),
+        subject: entry.subject.to_string(),
+        commit_timestamp: entry.commit_timestamp,
+        author_name: entry.author_name.to_string(),
+        author_email: entry.author_email.to_string(),
+        refs: entry.refs.iter().map(|name| name.to_string()).collect(),
+    }
+}
+
+fn proto_to_log_entry(proto: &proto::GitLogEntry) -> LogEntry {
+    LogEntry {
+        sha: proto.sha.clone().into(),
+        parents: proto.parents.iter().cloned().map(Into::into).collect(),
+        subject: proto.subject.clone().into(),
+        commit_timestamp: proto.commit_timestamp,
+        author_name: proto.author_name.clone().into(),
+        author_email: proto.author_email.clone().into(),
+        refs: proto.refs.iter().cloned().map(Into::into).collect(),
+    }
+}
+
 async fn compute_snapshot(
     id: RepositoryId,
     work_directory_abs_path: Arc<Path>,
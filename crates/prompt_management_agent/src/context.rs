@@ -1,13 +1,22 @@
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
+use crate::tokenizer::TokenCounter;
+
 /// Context extracted from Zed workspace
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ZedContext {
     pub active_file: Option<ActiveFile>,
     pub selected_text: Option<Selection>,
+    /// Further selections when the editor has several cursors
+    #[serde(default)]
+    pub other_selections: Vec<Selection>,
     pub project_info: Option<ProjectInfo>,
+    /// Most recent first
     pub recent_edits: Vec<RecentEdit>,
+    /// Nearest to the cursor first
+    #[serde(default)]
+    pub diagnostics: Vec<DiagnosticInfo>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -15,6 +24,9 @@ pub struct ActiveFile {
     pub path: PathBuf,
     pub language: Option<String>,
     pub cursor_line: Option<usize>,
+    /// Lines surrounding the cursor
+    #[serde(default)]
+    pub excerpt: Option<Selection>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -38,6 +50,21 @@ pub struct RecentEdit {
     pub timestamp: i64,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiagnosticInfo {
+    pub path: PathBuf,
+    pub line: usize,
+    pub severity: DiagnosticSeverity,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DiagnosticSeverity {
+    Error,
+    Warning,
+    Info,
+}
+
 impl ZedContext {
     /// Create context from current Zed state
     pub fn from_zed_workspace(
@@ -48,32 +75,321 @@ impl ZedContext {
         Self {
             active_file,
             selected_text,
+            other_selections: Vec::new(),
             project_info,
             recent_edits: Vec::new(),
+            diagnostics: Vec::new(),
         }
     }
 
-    /// Get token count estimate for this context
-    pub fn estimated_tokens(&self) -> usize {
+    pub fn with_recent_edits(mut self, recent_edits: Vec<RecentEdit>) -> Self {
+        self.recent_edits = recent_edits;
+        self
+    }
+
+    pub fn with_diagnostics(mut self, diagnostics: Vec<DiagnosticInfo>) -> Self {
+        self.diagnostics = diagnostics;
+        self
+    }
+
+    /// Count the tokens this context adds to a prompt
+    pub fn estimated_tokens(&self, counter: &dyn TokenCounter) -> usize {
         let mut tokens = 0;
 
         if let Some(file) = &self.active_file {
-            tokens += file.path.to_string_lossy().len() / 4 + 5;
+            tokens += counter.count_tokens(&file.path.to_string_lossy()) + 5;
+            if let Some(language) = &file.language {
+                tokens += counter.count_tokens(language);
+            }
+            if let Some(excerpt) = &file.excerpt {
+                tokens += counter.count_tokens(&excerpt.content) + 5;
+            }
         }
 
-        if let Some(selection) = &self.selected_text {
-            tokens += selection.content.len() / 4;
+        for selection in self.selected_text.iter().chain(&self.other_selections) {
+            tokens += counter.count_tokens(&selection.content) + 5;
         }
 
         if let Some(project) = &self.project_info {
-            tokens += project.name.len() / 4 + 5;
+            tokens += counter.count_tokens(&project.name) + 5;
+        }
+
+        for edit in &self.recent_edits {
+            tokens += counter.count_tokens(&edit.file.to_string_lossy())
+                + counter.count_tokens(&edit.description)
+                + 2;
+        }
+
+        for diagnostic in &self.diagnostics {
+            tokens += counter.count_tokens(&diagnostic.path.to_string_lossy())
+                + counter.count_tokens(&diagnostic.message)
+                + 5;
         }
 
         tokens
     }
 
+    /// Drop the least relevant context until it fits in `max_tokens`
+    ///
+    /// Recent edits go first, then the excerpt around the cursor, diagnostics
+    /// furthest from the cursor and extra selections. The primary selection
+    /// is cut short from the end as a last resort.
+    pub fn fit_to_budget(&mut self, max_tokens: usize, counter: &dyn TokenCounter) {
+        loop {
+            let tokens = self.estimated_tokens(counter);
+            if tokens <= max_tokens {
+                return;
+            }
+
+            if self.recent_edits.pop().is_some()
+                || self
+                    .active_file
+                    .as_mut()
+                    .is_some_and(|file| file.excerpt.take().is_some())
+                || self.diagnostics.pop().is_some()
+                || self.other_selections.pop().is_some()
+            {
+                continue;
+            }
+
+            let Some(selection) = &mut self.selected_text else {
+                return;
+            };
+            // Drop lines in proportion to the excess so large selections
+            // don't take a recount per line
+            let selection_tokens = counter.count_tokens(&selection.content).max(1);
+            let lines = selection.content.lines().count();
+            let excess = tokens - max_tokens;
+            let drop = (lines * excess).div_ceil(selection_tokens).max(1);
+            if drop >= lines {
+                self.selected_text = None;
+            } else {
+                truncate_lines(selection, lines - drop);
+            }
+        }
+    }
+
     /// Check if context is useful for refinement
     pub fn is_useful(&self) -> bool {
-        self.active_file.is_some() || self.selected_text.is_some()
+        self.active_file.is_some() || self.selected_text.is_some() || !self.diagnostics.is_empty()
+    }
+}
+
+/// Keep only the first `keep` lines of a selection
+fn truncate_lines(selection: &mut Selection, keep: usize) {
+    if let Some((end, _)) = selection
+        .content
+        .match_indices('\n')
+        .nth(keep.saturating_sub(1))
+    {
+        selection.content.truncate(end);
+        selection.end_line = selection.start_line + keep.saturating_sub(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One token per whitespace-separated word keeps the arithmetic readable
+    struct WordCounter;
+
+    impl TokenCounter for WordCounter {
+        fn count_tokens(&self, text: &str) -> usize {
+            text.split_whitespace().count()
+        }
+    }
+
+    #[test]
+    fn test_fit_to_budget() {
+        let selection = (1..=20)
+            .map(|line| format!("let value_{line} = {line};"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let mut context = ZedContext::from_zed_workspace(
+            Some(ActiveFile {
+                path: PathBuf::from("src/lib.rs"),
+                language: Some("Rust".to_string()),
+                cursor_line: Some(10),
+                excerpt: Some(Selection {
+                    content: "fn main() {\n    run();\n}".to_string(),
+                    start_line: 9,
+                    end_line: 11,
+                }),
+            }),
+            Some(Selection {
+                content: selection,
+                start_line: 1,
+                end_line: 20,
+            }),
+            None,
+        )
+        .with_recent_edits(vec![RecentEdit {
+            file: PathBuf::from("src/lib.rs"),
+            description: "Edited lines 3-4".to_string(),
+            timestamp: 0,
+        }])
+        .with_diagnostics(vec![DiagnosticInfo {
+            path: PathBuf::from("src/lib.rs"),
+            line: 10,
+            severity: DiagnosticSeverity::Error,
+            message: "cannot find function `run`".to_string(),
+        }]);
+        let counter = WordCounter;
+        assert_eq!(context.estimated_tokens(&counter), 118);
+
+        // Context that already fits is left alone
+        context.fit_to_budget(200, &counter);
+        assert_eq!(context.recent_edits.len(), 1);
+
+        // Edits, the excerpt and diagnostics go before the selection shrinks
+        context.fit_to_budget(60, &counter);
+        assert!(context.recent_edits.is_empty());
+        assert!(context.diagnostics.is_empty());
+        assert!(context.active_file.as_ref().unwrap().excerpt.is_none());
+        let selection = context.selected_text.as_ref().unwrap();
+        assert_eq!(selection.end_line, 12);
+        assert!(selection.content.ends_with("let value_12 = 12;"));
+        assert!(context.estimated_tokens(&counter) <= 60);
+
+        context.fit_to_budget(5, &counter);
+        assert!(context.selected_text.is_none());
+        assert!(context.active_file.is_some());
+    }
+
+    fn selection(content: &str, start_line: usize) -> Selection {
+        Selection {
+            content: content.to_string(),
+            start_line,
+            end_line: start_line + content.lines().count() - 1,
+        }
+    }
+
+    fn diagnostic(line: usize, message: &str) -> DiagnosticInfo {
+        DiagnosticInfo {
+            path: PathBuf::from("src/lib.rs"),
+            line,
+            severity: DiagnosticSeverity::Warning,
+            message: message.to_string(),
+        }
+    }
+
+    fn edit(description: &str) -> RecentEdit {
+        RecentEdit {
+            file: PathBuf::from("src/lib.rs"),
+            description: description.to_string(),
+            timestamp: 0,
+        }
+    }
+
+    /// Context with one item of each kind, and two where order matters
+    fn full_context() -> ZedContext {
+        let mut context = ZedContext::from_zed_workspace(
+            Some(ActiveFile {
+                path: PathBuf::from("src/lib.rs"),
+                language: Some("Rust".to_string()),
+                cursor_line: Some(10),
+                excerpt: Some(selection("fn main() {\n    run();\n}", 9)),
+            }),
+            Some(selection("let x = 1;", 10)),
+            None,
+        )
+        .with_recent_edits(vec![edit("Renamed run"), edit("Edited lines 3-4")])
+        .with_diagnostics(vec![
+            diagnostic(10, "unused variable `x`"),
+            diagnostic(40, "unused import"),
+        ]);
+        context.other_selections = vec![selection("let y = 2;", 20)];
+        context
+    }
+
+    #[test]
+    fn test_fit_to_budget_smaller_than_selection() {
+        let content = (1..=20)
+            .map(|line| format!("let value_{line} = {line};"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let mut context = ZedContext::from_zed_workspace(None, Some(selection(&content, 1)), None);
+        let counter = WordCounter;
+        assert_eq!(context.estimated_tokens(&counter), 85);
+
+        context.fit_to_budget(30, &counter);
+        let selection = context.selected_text.as_ref().unwrap();
+        assert_eq!((selection.start_line, selection.end_line), (1, 6));
+        assert!(selection.content.starts_with("let value_1 = 1;"));
+        assert!(selection.content.ends_with("let value_6 = 6;"));
+        assert!(context.estimated_tokens(&counter) <= 30);
+
+        // A budget that can't hold a single line drops the selection
+        context.fit_to_budget(3, &counter);
+        assert!(context.selected_text.is_none());
+        assert_eq!(context.estimated_tokens(&counter), 0);
+    }
+
+    #[test]
+    fn test_fit_to_budget_with_empty_diagnostics() {
+        let mut context = full_context().with_diagnostics(Vec::new());
+        let counter = WordCounter;
+        let active_file_tokens = 7;
+        let selection_tokens = 9;
+
+        // Without diagnostics to drop, extra selections are next in line
+        context.fit_to_budget(active_file_tokens + selection_tokens, &counter);
+        assert!(context.recent_edits.is_empty());
+        assert!(context.diagnostics.is_empty());
+        assert!(context.other_selections.is_empty());
+        assert_eq!(
+            context.selected_text.as_ref().unwrap().content,
+            "let x = 1;"
+        );
+        assert_eq!(
+            context.estimated_tokens(&counter),
+            active_file_tokens + selection_tokens
+        );
+        assert!(context.is_useful());
+    }
+
+    #[test]
+    fn test_fit_to_budget_priority_order() {
+        let mut context = full_context();
+        let counter = WordCounter;
+
+        // Each step only just exceeds the budget, so exactly one item goes
+        let step = |context: &mut ZedContext| {
+            let budget = context.estimated_tokens(&counter) - 1;
+            context.fit_to_budget(budget, &counter);
+        };
+
+        step(&mut context);
+        assert_eq!(context.recent_edits.len(), 1);
+        assert_eq!(context.recent_edits[0].description, "Renamed run");
+
+        step(&mut context);
+        assert!(context.recent_edits.is_empty());
+        assert!(context.active_file.as_ref().unwrap().excerpt.is_some());
+
+        step(&mut context);
+        assert!(context.active_file.as_ref().unwrap().excerpt.is_none());
+        assert_eq!(context.diagnostics.len(), 2);
+
+        step(&mut context);
+        assert_eq!(context.diagnostics.len(), 1);
+        assert_eq!(context.diagnostics[0].line, 10);
+
+        step(&mut context);
+        assert!(context.diagnostics.is_empty());
+        assert_eq!(context.other_selections.len(), 1);
+
+        step(&mut context);
+        assert!(context.other_selections.is_empty());
+        assert!(context.selected_text.is_some());
+
+        step(&mut context);
+        assert!(context.selected_text.is_none());
+
+        // The active file itself is never dropped
+        step(&mut context);
+        assert!(context.active_file.is_some());
+        assert_eq!(context.estimated_tokens(&counter), 7);
     }
 }
@@ -1,88 +1,170 @@
-use anyhow::{Context, Result};
-use std::path::PathBuf;
+use anyhow::{anyhow, bail, Context, Result};
+use candle_core::quantized::gguf_file;
+use candle_core::{Device, Tensor};
+use candle_transformers::generation::LogitsProcessor;
+use candle_transformers::models::{quantized_llama, quantized_phi3};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 
-/// Wrapper around Mistral.rs inference engine
+use crate::tokenizer::{default_token_counter, GgufTokenizer, TokenCounter};
+
+/// Sampling parameters used when generating from the local model
+#[derive(Debug, Clone)]
+pub struct SamplingConfig {
+    /// Values close to zero switch to greedy decoding
+    pub temperature: f64,
+    pub top_p: Option<f64>,
+    pub repeat_penalty: f32,
+    pub repeat_last_n: usize,
+    pub seed: u64,
+}
+
+impl Default for SamplingConfig {
+    fn default() -> Self {
+        Self {
+            temperature: 0.2,
+            top_p: Some(0.9),
+            repeat_penalty: 1.1,
+            repeat_last_n: 64,
+            seed: 299_792_458,
+        }
+    }
+}
+
+/// Local inference engine running quantized GGUF models on the CPU
 pub struct MistralRsEngine {
     model_path: PathBuf,
-    // Note: Actual mistralrs integration will be completed once we verify
-    // the exact API surface. For now, this is the architectural placeholder.
+    model: Option<Arc<Mutex<LocalModel>>>,
+    tokenizer: Option<Arc<GgufTokenizer>>,
+    sampling: SamplingConfig,
 }
 
 impl MistralRsEngine {
-    /// Initialize the Mistral.rs inference engine
+    /// Initialize the inference engine with a model from the cache directory
+    ///
+    /// When the model has not been downloaded yet the engine still starts,
+    /// and intent analysis falls back to keyword heuristics.
     pub async fn new(model_id: &str) -> Result<Self> {
-        log::info!("Initializing Mistral.rs engine with model: {}", model_id);
+        log::info!(
+            "Initializing local inference engine with model: {}",
+            model_id
+        );
 
-        // Get model cache directory
-        let cache_dir = dirs::cache_dir()
-            .context("Failed to get cache directory")?
-            .join("optafly-zed")
-            .join("models");
+        let models_dir = models_dir()?;
+        tokio::fs::create_dir_all(&models_dir).await?;
 
-        tokio::fs::create_dir_all(&cache_dir).await?;
+        let model_path = models_dir.join(model_dir_name(model_id)?);
+        let model_path = match resolve_model_file(&model_path) {
+            Some(model_file) => model_file,
+            None => {
+                log::warn!(
+                    "Model not found at {:?}, using heuristic intent analysis. \
+                    Install it with ModelManager to enable local inference.",
+                    model_path
+                );
+                return Ok(Self {
+                    model_path,
+                    model: None,
+                    tokenizer: None,
+                    sampling: SamplingConfig::default(),
+                });
+            }
+        };
 
-        let model_path = cache_dir.join(model_id.replace('/', "_"));
+        Self::from_path(model_path).await
+    }
 
-        // TODO: Download model from HuggingFace if not present
-        // This will use hf-hub crate to download GGUF models
-        if !model_path.exists() {
-            log::warn!(
-                "Model not found at {:?}. Will need to download.",
-                model_path
-            );
-            // For Phase 2a, we create the structure
-            // Model downloading will be implemented in Phase 2b
-        }
+    /// Load a GGUF model from a file, or from the first `.gguf` file in a directory
+    pub async fn from_path(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let model_path = resolve_model_file(&path)
+            .with_context(|| format!("No GGUF model found at {:?}", path))?;
+
+        let load_path = model_path.clone();
+        let model = tokio::task::spawn_blocking(move || LocalModel::load(&load_path)).await??;
+        log::info!(
+            "Loaded {} model from {:?} ({} tokens in vocabulary)",
+            model.architecture.name(),
+            model_path,
+            model.tokenizer.vocab_size()
+        );
 
-        Ok(Self { model_path })
+        Ok(Self {
+            model_path,
+            tokenizer: Some(model.tokenizer.clone()),
+            model: Some(Arc::new(Mutex::new(model))),
+            sampling: SamplingConfig::default(),
+        })
+    }
+
+    pub fn with_sampling(mut self, sampling: SamplingConfig) -> Self {
+        self.sampling = sampling;
+        self
+    }
+
+    pub fn model_path(&self) -> &Path {
+        &self.model_path
+    }
+
+    pub fn is_model_loaded(&self) -> bool {
+        self.model.is_some()
     }
 
-    /// Run inference on a prompt
+    /// Counts tokens with the loaded model's tokenizer, or the default BPE
+    /// when running heuristically
+    pub fn token_counter(&self) -> Arc<dyn TokenCounter> {
+        match &self.tokenizer {
+            Some(tokenizer) => tokenizer.clone(),
+            None => default_token_counter(),
+        }
+    }
+
+    /// Run inference on a prompt, formatted as a single user turn
     pub async fn infer(&self, prompt: &str, max_tokens: usize) -> Result<String> {
         log::debug!("Running inference: {} tokens", max_tokens);
 
-        // TODO: Actual mistralrs inference call
-        // For now, return placeholder that shows the structure works
-
-        // Placeholder response that demonstrates prompt analysis
-        let response = format!(
-            "Analyzed prompt: '{}'\n\
-            Suggested improvements:\n\
-            1. Add specific context about the code/file\n\
-            2. Include expected behavior or error details\n\
-            3. Structure request with clear objectives",
-            prompt
-        );
+        let model = self
+            .model
+            .clone()
+            .with_context(|| format!("No local model loaded from {:?}", self.model_path))?;
+        let prompt = prompt.to_string();
+        let sampling = self.sampling.clone();
 
-        Ok(response)
+        tokio::task::spawn_blocking(move || {
+            let mut model = model
+                .lock()
+                .map_err(|_| anyhow!("Local model lock poisoned"))?;
+            model.generate(&prompt, max_tokens, &sampling)
+        })
+        .await?
     }
 
     /// Analyze user intent from prompt
     pub async fn analyze_intent(&self, user_input: &str) -> Result<PromptIntent> {
         log::debug!("Analyzing intent for: {}", user_input);
 
+        if !self.is_model_loaded() {
+            return Ok(heuristic_intent(user_input));
+        }
+
         let analysis_prompt = format!(
-            "Analyze this user request and identify:\n\
-            1. Primary intent (debug, implement, refactor, explain, etc.)\n\
-            2. Missing information that would improve the request\n\
-            3. Optimal structure for the prompt\n\n\
-            User request: {}\n\n\
-            Analysis:",
+            "Classify this request to a coding assistant. Reply with only a JSON object \
+            with the keys \"primary_action\" (one of debug, implement, refactor, explain, \
+            optimize, document, other), \"missing_context\" (a list of details that would \
+            improve the request) and \"confidence\" (a number between 0 and 1).\n\n\
+            Request: {}",
             user_input
         );
 
-        let _response = self.infer(&analysis_prompt, 256).await?;
-
-        // Parse response into structured intent
-        // For now, use heuristics until full inference is implemented
-        Ok(PromptIntent {
-            primary_action: classify_action(user_input),
-            missing_context: vec![
-                "file location".to_string(),
-                "specific error or behavior".to_string(),
-            ],
-            confidence: 0.85,
-        })
+        let response = self.infer(&analysis_prompt, 128).await?;
+        match parse_intent(&response) {
+            Some(intent) => Ok(intent),
+            None => {
+                log::warn!("Could not parse intent from model output: {}", response);
+                Ok(heuristic_intent(user_input))
+            }
+        }
     }
 }
 
@@ -105,6 +187,57 @@ pub enum PromptAction {
     Other(String),
 }
 
+#[derive(Deserialize)]
+struct IntentResponse {
+    primary_action: String,
+    #[serde(default)]
+    missing_context: Vec<String>,
+    #[serde(default)]
+    confidence: Option<f32>,
+}
+
+/// Parse the JSON object produced by the model, ignoring any text around it
+fn parse_intent(response: &str) -> Option<PromptIntent> {
+    let start = response.find('{')?;
+    let end = response.rfind('}')?;
+    if end < start {
+        return None;
+    }
+    let parsed: IntentResponse = serde_json::from_str(&response[start..=end]).ok()?;
+
+    let primary_action = match parsed.primary_action.trim().to_lowercase().as_str() {
+        "debug" | "fix" => PromptAction::Debug,
+        "implement" | "add" | "create" => PromptAction::Implement,
+        "refactor" => PromptAction::Refactor,
+        "explain" => PromptAction::Explain,
+        "optimize" | "optimise" => PromptAction::Optimize,
+        "document" => PromptAction::Document,
+        _ => PromptAction::Other(parsed.primary_action),
+    };
+
+    Some(PromptIntent {
+        primary_action,
+        missing_context: parsed
+            .missing_context
+            .into_iter()
+            .filter(|item| !item.trim().is_empty())
+            .collect(),
+        confidence: parsed.confidence.unwrap_or(0.5).clamp(0.0, 1.0),
+    })
+}
+
+/// Keyword based intent used when no model is available
+fn heuristic_intent(user_input: &str) -> PromptIntent {
+    PromptIntent {
+        primary_action: classify_action(user_input),
+        missing_context: vec![
+            "file location".to_string(),
+            "specific error or behavior".to_string(),
+        ],
+        confidence: 0.85,
+    }
+}
+
 /// Classify the primary action from user input
 fn classify_action(input: &str) -> PromptAction {
     let lower = input.to_lowercase();
@@ -126,9 +259,274 @@ fn classify_action(input: &str) -> PromptAction {
     }
 }
 
+pub(crate) fn models_dir() -> Result<PathBuf> {
+    Ok(dirs::cache_dir()
+        .context("Failed to get cache directory")?
+        .join("optafly-zed")
+        .join("models"))
+}
+
+/// Directory name for a model id within the models directory
+///
+/// Ids are paths like `owner/name`. Empty segments, `.` and `..` are rejected,
+/// and every byte other than ASCII letters, digits, `.`, `_` and `-` is
+/// percent-encoded, so `/` becomes `%2F` and distinct ids never share a
+/// directory.
+pub(crate) fn model_dir_name(model_id: &str) -> Result<String> {
+    if model_id
+        .split('/')
+        .any(|segment| segment.is_empty() || segment == "." || segment == "..")
+    {
+        bail!("Invalid model id {:?}", model_id);
+    }
+    let mut name = String::with_capacity(model_id.len());
+    for byte in model_id.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'.' | b'_' | b'-') {
+            name.push(byte as char);
+        } else {
+            name.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    Ok(name)
+}
+
+/// Accept either a `.gguf` file or a directory containing one
+pub(crate) fn resolve_model_file(path: &Path) -> Option<PathBuf> {
+    if path.is_file() {
+        return Some(path.to_path_buf());
+    }
+    let mut with_extension = path.as_os_str().to_owned();
+    with_extension.push(".gguf");
+    let with_extension = PathBuf::from(with_extension);
+    if with_extension.is_file() {
+        return Some(with_extension);
+    }
+    let mut candidates: Vec<PathBuf> = std::fs::read_dir(path)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "gguf"))
+        .collect();
+    candidates.sort();
+    candidates.into_iter().next()
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Architecture {
+    Llama,
+    Phi3,
+}
+
+impl Architecture {
+    fn name(&self) -> &'static str {
+        match self {
+            Architecture::Llama => "llama",
+            Architecture::Phi3 => "phi3",
+        }
+    }
+}
+
+#[derive(Clone)]
+enum Weights {
+    Llama(quantized_llama::ModelWeights),
+    Phi3(quantized_phi3::ModelWeights),
+}
+
+impl Weights {
+    fn forward(&mut self, input: &Tensor, index_pos: usize) -> candle_core::Result<Tensor> {
+        match self {
+            Weights::Llama(model) => model.forward(input, index_pos),
+            Weights::Phi3(model) => model.forward(input, index_pos),
+        }
+    }
+
+    /// The llama weights reset their KV cache when a sequence starts at position
+    /// zero, but the phi3 cache only ever grows.
+    fn resets_kv_cache(&self) -> bool {
+        matches!(self, Weights::Llama(_))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum ChatTemplate {
+    Phi3,
+    Llama2,
+}
+
+impl ChatTemplate {
+    fn format(&self, prompt: &str) -> String {
+        match self {
+            ChatTemplate::Phi3 => format!("<|user|>\n{}<|end|>\n<|assistant|>\n", prompt),
+            ChatTemplate::Llama2 => format!("[INST] {} [/INST]", prompt),
+        }
+    }
+}
+
+struct LocalModel {
+    architecture: Architecture,
+    /// Never run when they keep their KV cache, so clones start with an empty one
+    weights: Weights,
+    tokenizer: Arc<GgufTokenizer>,
+    chat_template: ChatTemplate,
+    stop_tokens: Vec<u32>,
+    context_length: usize,
+    device: Device,
+}
+
+impl LocalModel {
+    fn load(path: &Path) -> Result<Self> {
+        let device = Device::Cpu;
+        let mut file = std::fs::File::open(path)
+            .with_context(|| format!("Failed to open model {:?}", path))?;
+        let content = gguf_file::Content::read(&mut file)
+            .with_context(|| format!("Failed to read GGUF header from {:?}", path))?;
+
+        let architecture = match content
+            .metadata
+            .get("general.architecture")
+            .and_then(|value| value.to_string().ok())
+            .map(String::as_str)
+        {
+            Some("llama") => Architecture::Llama,
+            Some("phi3") => Architecture::Phi3,
+            Some(other) => bail!("Unsupported model architecture '{}'", other),
+            None => bail!("GGUF file has no general.architecture"),
+        };
+        let context_length = content
+            .metadata
+            .get(&format!("{}.context_length", architecture.name()))
+            .and_then(|value| value.to_u32().ok())
+            .unwrap_or(2048) as usize;
+
+        let tokenizer = GgufTokenizer::from_gguf(&content)?;
+        let chat_template = if tokenizer.token_id("<|user|>").is_some() {
+            ChatTemplate::Phi3
+        } else {
+            ChatTemplate::Llama2
+        };
+        let stop_tokens = tokenizer
+            .eos_token()
+            .into_iter()
+            .chain(
+                ["<|end|>", "<|endoftext|>"]
+                    .iter()
+                    .filter_map(|t| tokenizer.token_id(t)),
+            )
+            .collect();
+
+        let weights = Self::load_weights(architecture, content, &mut file, &device)?;
+
+        Ok(Self {
+            architecture,
+            weights,
+            tokenizer: Arc::new(tokenizer),
+            chat_template,
+            stop_tokens,
+            context_length,
+            device,
+        })
+    }
+
+    fn load_weights(
+        architecture: Architecture,
+        content: gguf_file::Content,
+        file: &mut std::fs::File,
+        device: &Device,
+    ) -> Result<Weights> {
+        Ok(match architecture {
+            Architecture::Llama => Weights::Llama(quantized_llama::ModelWeights::from_gguf(
+                content, file, device,
+            )?),
+            Architecture::Phi3 => Weights::Phi3(quantized_phi3::ModelWeights::from_gguf(
+                false, content, file, device,
+            )?),
+        })
+    }
+
+    fn generate(
+        &mut self,
+        prompt: &str,
+        max_tokens: usize,
+        sampling: &SamplingConfig,
+    ) -> Result<String> {
+        // Clones share the loaded tensors, so this only costs a fresh KV cache
+        let mut fresh_weights;
+        let weights = if self.weights.resets_kv_cache() {
+            &mut self.weights
+        } else {
+            fresh_weights = self.weights.clone();
+            &mut fresh_weights
+        };
+
+        let formatted = self.chat_template.format(prompt);
+        let mut tokens = self.tokenizer.encode(&formatted, true);
+
+        // Keep the end of the prompt, where the request and template live
+        let budget = self.context_length.saturating_sub(max_tokens).max(1);
+        if tokens.len() > budget {
+            log::warn!(
+                "Prompt is {} tokens, truncating to fit the {} token context",
+                tokens.len(),
+                self.context_length
+            );
+            tokens.drain(..tokens.len() - budget);
+        }
+
+        let mut logits_processor =
+            LogitsProcessor::new(sampling.seed, Some(sampling.temperature), sampling.top_p);
+        let mut generated = Vec::new();
+        let mut index_pos = 0;
+        let mut input = tokens.clone();
+
+        for _ in 0..max_tokens {
+            let input_tensor = Tensor::new(input.as_slice(), &self.device)?.unsqueeze(0)?;
+            let logits = weights.forward(&input_tensor, index_pos)?.squeeze(0)?;
+            index_pos += input.len();
+
+            let logits = if sampling.repeat_penalty == 1.0 {
+                logits
+            } else {
+                let start = tokens.len().saturating_sub(sampling.repeat_last_n);
+                candle_transformers::utils::apply_repeat_penalty(
+                    &logits,
+                    sampling.repeat_penalty,
+                    &tokens[start..],
+                )?
+            };
+
+            let next_token = logits_processor.sample(&logits)?;
+            if self.stop_tokens.contains(&next_token) {
+                break;
+            }
+            tokens.push(next_token);
+            generated.push(next_token);
+            input = vec![next_token];
+        }
+
+        Ok(self.tokenizer.decode(&generated).trim().to_string())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_model::{self, TEST_MODEL_PIECES, TEST_MODEL_RESPONSE};
+
+    fn greedy() -> SamplingConfig {
+        SamplingConfig {
+            temperature: 0.0,
+            ..Default::default()
+        }
+    }
+
+    async fn test_engine(name: &str) -> MistralRsEngine {
+        let dir =
+            std::env::temp_dir().join(format!("pma-inference-{}-{}", name, std::process::id()));
+        let path = dir.join("test-model.gguf");
+        test_model::write_test_model(&path).unwrap();
+        let engine = MistralRsEngine::from_path(&dir).await.unwrap();
+        std::fs::remove_dir_all(dir).ok();
+        engine
+    }
 
     #[test]
     fn test_action_classification() {
@@ -145,4 +543,55 @@ mod tests {
             PromptAction::Explain
         ));
     }
+
+    #[tokio::test]
+    async fn test_generation_is_deterministic_and_bounded() {
+        let engine = test_engine("generate").await.with_sampling(greedy());
+        assert!(engine.is_model_loaded());
+
+        let response = engine.infer("hello world", 256).await.unwrap();
+        assert_eq!(response, TEST_MODEL_RESPONSE);
+        // The KV cache is reset between requests
+        assert_eq!(engine.infer("hello", 256).await.unwrap(), response);
+
+        let truncated = engine.infer("hello world", 2).await.unwrap();
+        assert_eq!(truncated, TEST_MODEL_PIECES[..2].concat().trim());
+
+        // Seeded sampling is reproducible as well
+        let sampled = test_engine("sample").await;
+        assert_eq!(
+            sampled.infer("hello world", 256).await.unwrap(),
+            TEST_MODEL_RESPONSE
+        );
+    }
+
+    #[tokio::test]
+    async fn test_intent_comes_from_model_output() {
+        let engine = test_engine("intent").await.with_sampling(greedy());
+
+        // Keywords alone would classify this as debugging
+        let intent = engine.analyze_intent("fix this bug").await.unwrap();
+        assert!(matches!(intent.primary_action, PromptAction::Refactor));
+        assert_eq!(intent.missing_context, ["file location"]);
+        assert!((intent.confidence - 0.9).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn test_parse_intent() {
+        let intent = parse_intent(
+            "Sure! Here is the analysis:\n{\"primary_action\": \"Optimize\", \"confidence\": 1.7}\nDone.",
+        )
+        .unwrap();
+        assert!(matches!(intent.primary_action, PromptAction::Optimize));
+        assert!(intent.missing_context.is_empty());
+        assert_eq!(intent.confidence, 1.0);
+
+        let intent = parse_intent("{\"primary_action\": \"translate\"}").unwrap();
+        assert!(
+            matches!(intent.primary_action, PromptAction::Other(ref action) if action == "translate")
+        );
+
+        assert!(parse_intent("no json here").is_none());
+        assert!(parse_intent("{\"confidence\": 0.4}").is_none());
+    }
 }
@@ -2,18 +2,33 @@ pub mod inference;
 pub mod refinement;
 pub mod context;
 pub mod user_interaction;
+pub mod tokenizer;
+pub mod model_manager;
+#[cfg(any(test, feature = "test-support"))]
+pub mod test_model;
 
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-
-pub use inference::MistralRsEngine;
-pub use refinement::{PromptRefinement, RefinedPrompt};
-pub use context::ZedContext;
+use std::sync::Arc;
+
+pub use inference::{MistralRsEngine, SamplingConfig};
+pub use model_manager::{
+    InstalledModel, LocalSource, MirrorSource, ModelManager, ModelSource, ModelSpec,
+};
+pub use refinement::{
+    ChangePlacement, ChangeStatus, ChangeType, PromptChange, PromptRefinement, RefinedPrompt,
+    RefinementInput, RefinementPipeline, Refiner,
+};
+pub use context::{
+    ActiveFile, DiagnosticInfo, DiagnosticSeverity, ProjectInfo, RecentEdit, Selection, ZedContext,
+};
+pub use tokenizer::{default_token_counter, BpeTokenCounter, TokenCounter};
 pub use user_interaction::ApprovalResult;
 
 /// Main Prompt Management Agent that orchestrates prompt refinement
 pub struct PromptManagementAgent {
     inference_engine: MistralRsEngine,
+    pipeline: RefinementPipeline,
     config: AgentConfig,
 }
 
@@ -45,10 +60,21 @@ impl PromptManagementAgent {
 
         Ok(Self {
             inference_engine,
+            pipeline: RefinementPipeline::default(),
             config,
         })
     }
 
+    /// Replace the default refiners
+    pub fn with_pipeline(mut self, pipeline: RefinementPipeline) -> Self {
+        self.pipeline = pipeline;
+        self
+    }
+
+    pub fn config(&self) -> &AgentConfig {
+        &self.config
+    }
+
     /// Analyze and refine a user prompt
     pub async fn refine_prompt(
         &self,
@@ -57,10 +83,15 @@ impl PromptManagementAgent {
     ) -> Result<RefinedPrompt> {
         log::info!("Refining prompt: {} chars", user_input.len());
 
+        let mut context = context.clone();
+        let token_counter = self.inference_engine.token_counter();
+        context.fit_to_budget(self.config.max_context_tokens, token_counter.as_ref());
+
         // Generate refinement using local inference
-        let refinement = PromptRefinement::generate(
+        let refinement = PromptRefinement::generate_with(
+            &self.pipeline,
             user_input,
-            context,
+            &context,
             &self.inference_engine,
             &self.config,
         )
@@ -74,6 +105,11 @@ impl PromptManagementAgent {
         Ok(refinement.into_refined_prompt(user_input))
     }
 
+    /// Count tokens the way the local model does
+    pub fn token_counter(&self) -> Arc<dyn TokenCounter> {
+        self.inference_engine.token_counter()
+    }
+
     /// Check if refinement meets quality threshold
     pub fn should_suggest_refinement(&self, quality_score: f32) -> bool {
         quality_score >= self.config.refinement_threshold
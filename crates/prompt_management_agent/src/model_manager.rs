@@ -0,0 +1,716 @@
+use anyhow::{bail, Context, Result};
+use async_trait::async_trait;
+use candle_core::quantized::gguf_file;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncWriteExt;
+
+use crate::inference::{model_dir_name, models_dir, resolve_model_file};
+use crate::AgentConfig;
+
+const REGISTRY_FILE: &str = "models.json";
+const STAGING_DIR: &str = ".staging";
+
+/// A model to install
+#[derive(Debug, Clone, Default)]
+pub struct ModelSpec {
+    /// Identifier used for `AgentConfig::model_path`, e.g. `microsoft/Phi-3-mini-4k-instruct-gguf`
+    pub id: String,
+    /// File name within the model repository or directory
+    pub file: Option<String>,
+    /// Expected SHA-256 of the GGUF file, as lowercase hex
+    pub sha256: Option<String>,
+}
+
+impl ModelSpec {
+    pub fn new(id: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_file(mut self, file: impl Into<String>) -> Self {
+        self.file = Some(file.into());
+        self
+    }
+
+    pub fn with_sha256(mut self, sha256: impl Into<String>) -> Self {
+        self.sha256 = Some(sha256.into().to_lowercase());
+        self
+    }
+}
+
+/// Somewhere a GGUF model file can be fetched from
+#[async_trait]
+pub trait ModelSource: Send + Sync {
+    /// Short description recorded with installed models
+    fn description(&self) -> String;
+
+    /// Write the model's GGUF file to `destination`
+    async fn fetch(&self, spec: &ModelSpec, destination: &Path) -> Result<()>;
+}
+
+/// Installs models from a GGUF file or a directory of models, without network access
+///
+/// For a directory, the model is looked up as `<dir>/<file>` when the spec names
+/// a file, then as `<dir>/<id with '/' written as '%2F'>` (a file or a directory
+/// containing one), and finally as the first `.gguf` file in the directory.
+pub struct LocalSource {
+    path: PathBuf,
+}
+
+impl LocalSource {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    fn resolve(&self, spec: &ModelSpec) -> Option<PathBuf> {
+        if self.path.is_file() {
+            return Some(self.path.clone());
+        }
+        if let Some(file) = &spec.file {
+            let candidate = self.path.join(file);
+            if candidate.is_file() {
+                return Some(candidate);
+            }
+        }
+        model_dir_name(&spec.id)
+            .ok()
+            .and_then(|name| resolve_model_file(&self.path.join(name)))
+            .or_else(|| resolve_model_file(&self.path))
+    }
+}
+
+#[async_trait]
+impl ModelSource for LocalSource {
+    fn description(&self) -> String {
+        format!("local:{}", self.path.display())
+    }
+
+    async fn fetch(&self, spec: &ModelSpec, destination: &Path) -> Result<()> {
+        let source = self
+            .resolve(spec)
+            .with_context(|| format!("No GGUF file for {} in {:?}", spec.id, self.path))?;
+        tokio::fs::copy(&source, destination)
+            .await
+            .with_context(|| format!("Failed to copy model from {:?}", source))?;
+        Ok(())
+    }
+}
+
+/// Downloads models from a Hugging Face compatible mirror
+///
+/// Files are fetched from `{base_url}/{id}/resolve/{revision}/{file}`, so the
+/// spec must name the file to download.
+pub struct MirrorSource {
+    base_url: String,
+    revision: String,
+    auth_token: Option<String>,
+    client: reqwest::Client,
+}
+
+impl MirrorSource {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            revision: "main".to_string(),
+            auth_token: None,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    pub fn with_revision(mut self, revision: impl Into<String>) -> Self {
+        self.revision = revision.into();
+        self
+    }
+
+    pub fn with_auth_token(mut self, auth_token: impl Into<String>) -> Self {
+        self.auth_token = Some(auth_token.into());
+        self
+    }
+}
+
+#[async_trait]
+impl ModelSource for MirrorSource {
+    fn description(&self) -> String {
+        format!("mirror:{}", self.base_url)
+    }
+
+    async fn fetch(&self, spec: &ModelSpec, destination: &Path) -> Result<()> {
+        let file = spec
+            .file
+            .as_deref()
+            .with_context(|| format!("Downloading {} requires a file name", spec.id))?;
+        let url = format!(
+            "{}/{}/resolve/{}/{}",
+            self.base_url, spec.id, self.revision, file
+        );
+        log::info!("Downloading model from {}", url);
+
+        let mut request = self.client.get(&url);
+        if let Some(token) = &self.auth_token {
+            request = request.bearer_auth(token);
+        }
+        let mut response = request
+            .send()
+            .await
+            .with_context(|| format!("Failed to download {}", url))?
+            .error_for_status()?;
+
+        let mut output = tokio::fs::File::create(destination).await?;
+        while let Some(chunk) = response.chunk().await? {
+            output.write_all(&chunk).await?;
+        }
+        output.flush().await?;
+        Ok(())
+    }
+}
+
+/// Metadata recorded for an installed model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InstalledModel {
+    pub id: String,
+    pub file_name: String,
+    pub sha256: String,
+    pub size_bytes: u64,
+    pub architecture: Option<String>,
+    pub quantization: Option<String>,
+    pub context_length: Option<usize>,
+    pub source: String,
+    pub installed_at: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct ModelRegistry {
+    active: Option<String>,
+    models: Vec<InstalledModel>,
+}
+
+/// Installs, verifies and switches between local GGUF models
+///
+/// Each model lives in `<models_dir>/<id with '/' written as '%2F'>/`, the
+/// same location `MistralRsEngine::new` loads from, and is described in
+/// `<models_dir>/models.json`.
+pub struct ModelManager {
+    models_dir: PathBuf,
+    registry: ModelRegistry,
+}
+
+impl ModelManager {
+    /// Open the manager for the default cache directory
+    pub async fn open_default() -> Result<Self> {
+        Self::open(models_dir()?).await
+    }
+
+    pub async fn open(models_dir: impl Into<PathBuf>) -> Result<Self> {
+        let models_dir = models_dir.into();
+        tokio::fs::create_dir_all(&models_dir).await?;
+
+        let registry_path = models_dir.join(REGISTRY_FILE);
+        let registry = if registry_path.exists() {
+            let content = tokio::fs::read_to_string(&registry_path).await?;
+            serde_json::from_str(&content).context("Failed to parse model registry")?
+        } else {
+            ModelRegistry::default()
+        };
+
+        Ok(Self {
+            models_dir,
+            registry,
+        })
+    }
+
+    pub fn models_dir(&self) -> &Path {
+        &self.models_dir
+    }
+
+    pub fn list(&self) -> &[InstalledModel] {
+        &self.registry.models
+    }
+
+    pub fn get(&self, id: &str) -> Option<&InstalledModel> {
+        self.registry.models.iter().find(|model| model.id == id)
+    }
+
+    /// The model selected with [`ModelManager::set_active`], initially the first one installed
+    pub fn active(&self) -> Option<&InstalledModel> {
+        self.registry.active.as_deref().and_then(|id| self.get(id))
+    }
+
+    /// Path of the GGUF file for an installed model
+    pub fn model_path(&self, model: &InstalledModel) -> PathBuf {
+        // Installed ids were validated by `install`
+        self.models_dir
+            .join(model_dir_name(&model.id).unwrap_or_default())
+            .join(&model.file_name)
+    }
+
+    /// Fetch a model from `source`, verify its checksum and register it
+    ///
+    /// Installing an id that is already present replaces the previous file.
+    pub async fn install(
+        &mut self,
+        source: &dyn ModelSource,
+        spec: &ModelSpec,
+    ) -> Result<InstalledModel> {
+        if spec.id.trim().is_empty() {
+            bail!("Model id must not be empty");
+        }
+        let dir_name = model_dir_name(&spec.id)?;
+        log::info!("Installing model {} from {}", spec.id, source.description());
+
+        let staging_dir = self.models_dir.join(STAGING_DIR);
+        tokio::fs::create_dir_all(&staging_dir).await?;
+        let staged = staging_dir.join(format!("{}-{}.partial", dir_name, std::process::id()));
+
+        let result = self.install_staged(source, spec, &dir_name, &staged).await;
+        if staged.exists() {
+            tokio::fs::remove_file(&staged).await.ok();
+        }
+        let staged_dir = staged.with_extension("dir");
+        if staged_dir.exists() {
+            tokio::fs::remove_dir_all(&staged_dir).await.ok();
+        }
+        result
+    }
+
+    async fn install_staged(
+        &mut self,
+        source: &dyn ModelSource,
+        spec: &ModelSpec,
+        dir_name: &str,
+        staged: &Path,
+    ) -> Result<InstalledModel> {
+        source.fetch(spec, staged).await?;
+
+        let sha256 = sha256_file(staged).await?;
+        if let Some(expected) = &spec.sha256 {
+            if *expected != sha256 {
+                bail!(
+                    "Checksum mismatch for {}: expected {}, got {}",
+                    spec.id,
+                    expected,
+                    sha256
+                );
+            }
+        }
+
+        let metadata_path = staged.to_path_buf();
+        let metadata =
+            tokio::task::spawn_blocking(move || read_gguf_metadata(&metadata_path)).await??;
+
+        let file_name = spec
+            .file
+            .as_deref()
+            .and_then(|file| Path::new(file).file_name())
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| format!("{}.gguf", dir_name));
+
+        // Assemble the model's directory next to the staged file, so the
+        // previous install stays usable until the new one replaces it
+        let staged_dir = staged.with_extension("dir");
+        tokio::fs::create_dir_all(&staged_dir).await?;
+        tokio::fs::rename(staged, staged_dir.join(&file_name)).await?;
+        let size_bytes = tokio::fs::metadata(staged_dir.join(&file_name))
+            .await?
+            .len();
+
+        let model_dir = self.models_dir.join(dir_name);
+        let previous_dir = move_aside(&model_dir, &staged.with_extension("old")).await?;
+        if let Err(error) = tokio::fs::rename(&staged_dir, &model_dir).await {
+            if let Some(previous_dir) = &previous_dir {
+                tokio::fs::rename(previous_dir, &model_dir).await.ok();
+            }
+            return Err(error).context("Failed to move model into place");
+        }
+        if let Some(previous_dir) = previous_dir {
+            remove_dir(&previous_dir).await;
+        }
+
+        let model = InstalledModel {
+            id: spec.id.clone(),
+            file_name,
+            sha256,
+            size_bytes,
+            architecture: metadata.architecture,
+            quantization: metadata.quantization,
+            context_length: metadata.context_length,
+            source: source.description(),
+            installed_at: unix_timestamp(),
+        };
+
+        self.registry
+            .models
+            .retain(|existing| existing.id != model.id);
+        self.registry.models.push(model.clone());
+        if self.active().is_none() {
+            self.registry.active = Some(model.id.clone());
+        }
+        self.save().await?;
+
+        log::info!(
+            "Installed {} ({} bytes, {})",
+            model.id,
+            model.size_bytes,
+            model
+                .quantization
+                .as_deref()
+                .unwrap_or("unknown quantization")
+        );
+        Ok(model)
+    }
+
+    /// Recompute an installed model's checksum and compare it with the recorded one
+    pub async fn verify(&self, id: &str) -> Result<bool> {
+        let model = self
+            .get(id)
+            .with_context(|| format!("Model {} is not installed", id))?;
+        Ok(sha256_file(&self.model_path(model)).await? == model.sha256)
+    }
+
+    /// Delete an installed model, clearing the selection if it was active
+    pub async fn remove(&mut self, id: &str) -> Result<()> {
+        if self.get(id).is_none() {
+            bail!("Model {} is not installed", id);
+        }
+        let dir_name = model_dir_name(id)?;
+        let model_dir = self.models_dir.join(&dir_name);
+        let staging_dir = self.models_dir.join(STAGING_DIR);
+        tokio::fs::create_dir_all(&staging_dir).await?;
+        let aside = staging_dir.join(format!("{}-{}.old", dir_name, std::process::id()));
+        let removed_dir = move_aside(&model_dir, &aside).await?;
+
+        let previous_registry = self.registry.clone();
+        self.registry.models.retain(|model| model.id != id);
+        if self.registry.active.as_deref() == Some(id) {
+            self.registry.active = self.registry.models.first().map(|model| model.id.clone());
+        }
+        if let Err(error) = self.save().await {
+            // Keep the model if the registry still lists it
+            self.registry = previous_registry;
+            if let Some(removed_dir) = &removed_dir {
+                tokio::fs::rename(removed_dir, &model_dir).await.ok();
+            }
+            return Err(error);
+        }
+        if let Some(removed_dir) = removed_dir {
+            remove_dir(&removed_dir).await;
+        }
+        Ok(())
+    }
+
+    /// Switch the active model
+    pub async fn set_active(&mut self, id: &str) -> Result<()> {
+        if self.get(id).is_none() {
+            bail!("Model {} is not installed", id);
+        }
+        self.registry.active = Some(id.to_string());
+        self.save().await
+    }
+
+    /// Point `config` at the active model, capping the context budget to what it supports
+    pub fn apply_to_config(&self, config: &mut AgentConfig) -> Option<InstalledModel> {
+        let model = self.active()?;
+        config.model_path = model.id.clone();
+        if let Some(context_length) = model.context_length {
+            config.max_context_tokens = config.max_context_tokens.min(context_length);
+        }
+        Some(model.clone())
+    }
+
+    async fn save(&self) -> Result<()> {
+        let path = self.models_dir.join(REGISTRY_FILE);
+        let temp_path = path.with_extension("json.tmp");
+        let content = serde_json::to_string_pretty(&self.registry)?;
+        tokio::fs::write(&temp_path, content).await?;
+        tokio::fs::rename(&temp_path, &path)
+            .await
+            .context("Failed to save model registry")?;
+        Ok(())
+    }
+}
+
+struct GgufMetadata {
+    architecture: Option<String>,
+    quantization: Option<String>,
+    context_length: Option<usize>,
+}
+
+fn read_gguf_metadata(path: &Path) -> Result<GgufMetadata> {
+    let mut file = std::fs::File::open(path)?;
+    let content = gguf_file::Content::read(&mut file)
+        .with_context(|| format!("{:?} is not a valid GGUF file", path))?;
+
+    let architecture = content
+        .metadata
+        .get("general.architecture")
+        .and_then(|value| value.to_string().ok())
+        .cloned();
+    let context_length = architecture.as_ref().and_then(|architecture| {
+        content
+            .metadata
+            .get(&format!("{}.context_length", architecture))
+            .and_then(|value| value.to_u32().ok())
+            .map(|length| length as usize)
+    });
+
+    // Prefer the declared file type, otherwise report the most common tensor type
+    let quantization = content
+        .metadata
+        .get("general.file_type")
+        .and_then(|value| value.to_u32().ok())
+        .and_then(file_type_name)
+        .map(str::to_string)
+        .or_else(|| {
+            let mut counts: HashMap<String, usize> = HashMap::new();
+            for info in content.tensor_infos.values() {
+                *counts.entry(format!("{:?}", info.ggml_dtype)).or_default() += 1;
+            }
+            counts
+                .into_iter()
+                .max_by(|(a_name, a), (b_name, b)| a.cmp(b).then_with(|| b_name.cmp(a_name)))
+                .map(|(name, _)| name)
+        });
+
+    Ok(GgufMetadata {
+        architecture,
+        quantization,
+        context_length,
+    })
+}
+
+/// Names for llama.cpp's `general.file_type` values
+fn file_type_name(file_type: u32) -> Option<&'static str> {
+    Some(match file_type {
+        0 => "F32",
+        1 => "F16",
+        2 => "Q4_0",
+        3 => "Q4_1",
+        7 => "Q8_0",
+        8 => "Q5_0",
+        9 => "Q5_1",
+        10 => "Q2_K",
+        11 => "Q3_K_S",
+        12 => "Q3_K_M",
+        13 => "Q3_K_L",
+        14 => "Q4_K_S",
+        15 => "Q4_K_M",
+        16 => "Q5_K_S",
+        17 => "Q5_K_M",
+        18 => "Q6_K",
+        32 => "BF16",
+        _ => return None,
+    })
+}
+
+async fn sha256_file(path: &Path) -> Result<String> {
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        let mut file = std::fs::File::open(&path)
+            .with_context(|| format!("Failed to open {:?} for hashing", path))?;
+        let mut hasher = Sha256::new();
+        let mut buffer = vec![0; 1 << 16];
+        loop {
+            let read = file.read(&mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buffer[..read]);
+        }
+        Ok(format!("{:x}", hasher.finalize()))
+    })
+    .await?
+}
+
+/// Rename `dir`, if it exists, to `aside`, returning where it went
+async fn move_aside(dir: &Path, aside: &Path) -> Result<Option<PathBuf>> {
+    if !dir.exists() {
+        return Ok(None);
+    }
+    if aside.exists() {
+        tokio::fs::remove_dir_all(aside).await?;
+    }
+    tokio::fs::rename(dir, aside)
+        .await
+        .with_context(|| format!("Failed to move {:?} aside", dir))?;
+    Ok(Some(aside.to_path_buf()))
+}
+
+/// Delete a directory that has already been replaced, which is best-effort
+async fn remove_dir(dir: &Path) {
+    if let Err(error) = tokio::fs::remove_dir_all(dir).await {
+        log::warn!("Failed to delete {:?}: {}", dir, error);
+    }
+}
+
+fn unix_timestamp() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_model;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("pma-models-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[tokio::test]
+    async fn test_install_from_local_directory() {
+        let dir = temp_dir("install");
+        let source_dir = dir.join("downloads");
+        test_model::write_test_model(&source_dir.join("test%2Ftiny-model").join("tiny.gguf"))
+            .unwrap();
+
+        let mut manager = ModelManager::open(dir.join("models")).await.unwrap();
+        let model = manager
+            .install(
+                &LocalSource::new(&source_dir),
+                &ModelSpec::new("test/tiny-model"),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(model.architecture.as_deref(), Some("llama"));
+        assert_eq!(model.quantization.as_deref(), Some("F32"));
+        assert_eq!(model.context_length, Some(4096));
+        assert_eq!(model.sha256.len(), 64);
+        let path = manager.model_path(&model);
+        assert_eq!(model.size_bytes, std::fs::metadata(&path).unwrap().len());
+        assert!(manager.verify("test/tiny-model").await.unwrap());
+
+        // The installed layout is what the engine loads from
+        let engine = crate::MistralRsEngine::from_path(path.parent().unwrap())
+            .await
+            .unwrap();
+        assert!(engine.is_model_loaded());
+
+        // Reinstalling with the recorded checksum succeeds, a wrong one is rejected
+        let spec = ModelSpec::new("test/tiny-model").with_sha256(&model.sha256);
+        manager
+            .install(&LocalSource::new(&source_dir), &spec)
+            .await
+            .unwrap();
+        let spec = ModelSpec::new("test/other").with_sha256("00".repeat(32));
+        let error = manager
+            .install(
+                &LocalSource::new(source_dir.join("test%2Ftiny-model")),
+                &spec,
+            )
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("Checksum mismatch"));
+        assert!(manager.get("test/other").is_none());
+        assert!(!manager.models_dir().join("test%2Fother").exists());
+        let staged = std::fs::read_dir(manager.models_dir().join(STAGING_DIR)).unwrap();
+        assert_eq!(staged.count(), 0);
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_switch_and_remove_models() {
+        let dir = temp_dir("switch");
+        let model_file = dir.join("tiny.gguf");
+        test_model::write_test_model(&model_file).unwrap();
+        let source = LocalSource::new(&model_file);
+
+        let mut manager = ModelManager::open(dir.join("models")).await.unwrap();
+        manager
+            .install(&source, &ModelSpec::new("first"))
+            .await
+            .unwrap();
+        manager
+            .install(&source, &ModelSpec::new("second"))
+            .await
+            .unwrap();
+        assert_eq!(manager.active().unwrap().id, "first");
+
+        manager.set_active("second").await.unwrap();
+        assert!(manager.set_active("missing").await.is_err());
+
+        // The registry survives reopening
+        let mut manager = ModelManager::open(dir.join("models")).await.unwrap();
+        assert_eq!(manager.list().len(), 2);
+        let mut config = AgentConfig {
+            max_context_tokens: 8192,
+            ..Default::default()
+        };
+        manager.apply_to_config(&mut config).unwrap();
+        assert_eq!(config.model_path, "second");
+        assert_eq!(config.max_context_tokens, 4096);
+
+        manager.remove("second").await.unwrap();
+        assert!(!manager.models_dir().join("second").exists());
+        assert_eq!(manager.active().unwrap().id, "first");
+        manager.remove("first").await.unwrap();
+        assert!(manager.active().is_none());
+        assert!(manager.apply_to_config(&mut config).is_none());
+        let staged = std::fs::read_dir(manager.models_dir().join(STAGING_DIR)).unwrap();
+        assert_eq!(staged.count(), 0);
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_model_ids_map_to_distinct_directories() {
+        assert_eq!(model_dir_name("owner/model").unwrap(), "owner%2Fmodel");
+        assert_eq!(model_dir_name("owner_model").unwrap(), "owner_model");
+        assert_eq!(model_dir_name("owner%2Fmodel").unwrap(), "owner%252Fmodel");
+        for id in [
+            "..",
+            "../model",
+            "owner/../model",
+            "owner//model",
+            "/model",
+            "model/",
+        ] {
+            assert!(model_dir_name(id).is_err(), "{id} should be rejected");
+        }
+
+        let dir = temp_dir("ids");
+        let model_file = dir.join("tiny.gguf");
+        test_model::write_test_model(&model_file).unwrap();
+        let source = LocalSource::new(&model_file);
+
+        let mut manager = ModelManager::open(dir.join("models")).await.unwrap();
+        let slash = manager
+            .install(&source, &ModelSpec::new("a/b"))
+            .await
+            .unwrap();
+        let underscore = manager
+            .install(&source, &ModelSpec::new("a_b"))
+            .await
+            .unwrap();
+        assert_ne!(manager.model_path(&slash), manager.model_path(&underscore));
+        manager.remove("a_b").await.unwrap();
+        assert!(manager.verify("a/b").await.unwrap());
+
+        assert!(manager
+            .install(&source, &ModelSpec::new("../escape"))
+            .await
+            .is_err());
+        assert!(!dir.join("escape").exists());
+
+        // Reinstalling replaces the model without leaving the old copy behind
+        manager
+            .install(&source, &ModelSpec::new("a/b"))
+            .await
+            .unwrap();
+        assert!(manager.verify("a/b").await.unwrap());
+        let staged = std::fs::read_dir(manager.models_dir().join(STAGING_DIR)).unwrap();
+        assert_eq!(staged.count(), 0);
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
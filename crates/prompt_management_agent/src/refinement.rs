@@ -1,28 +1,37 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 
-use crate::context::ZedContext;
-use crate::inference::{MistralRsEngine, PromptIntent};
+use crate::context::{DiagnosticSeverity, ZedContext};
+use crate::inference::{MistralRsEngine, PromptAction, PromptIntent};
+use crate::tokenizer::default_token_counter;
+use crate::user_interaction::ApprovalResult;
 use crate::AgentConfig;
 
 /// Represents a refined prompt with improvements
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RefinedPrompt {
     pub original: String,
+    /// The original with every change that has not been rejected applied
     pub refined: String,
     pub changes: Vec<PromptChange>,
     pub quality_score: f32,
     pub estimated_cache_hit_improvement: f32,
 }
 
+/// A single piece of text a refiner proposes adding around the original prompt
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PromptChange {
+    pub id: usize,
     pub change_type: ChangeType,
     pub description: String,
+    pub text: String,
+    pub placement: ChangePlacement,
     pub tokens_added: usize,
+    #[serde(default)]
+    pub status: ChangeStatus,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ChangeType {
     AddedContext,
     AddedStructure,
@@ -32,6 +41,372 @@ pub enum ChangeType {
     ImprovedClarity,
 }
 
+/// Where a change's text goes relative to the original prompt
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChangePlacement {
+    Before,
+    After,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChangeStatus {
+    #[default]
+    Pending,
+    Accepted,
+    Rejected,
+}
+
+impl ChangeType {
+    /// Safe changes only add facts taken from the workspace, without
+    /// changing what the user asked for
+    pub fn is_safe(&self) -> bool {
+        matches!(
+            self,
+            ChangeType::AddedFileLocation | ChangeType::AddedCodeSnippet | ChangeType::AddedContext
+        )
+    }
+
+    fn quality_gain(&self) -> f32 {
+        match self {
+            ChangeType::AddedCodeSnippet => 0.25,
+            ChangeType::AddedStructure => 0.20,
+            ChangeType::AddedContext => 0.20,
+            ChangeType::AddedFileLocation => 0.15,
+            ChangeType::AddedExpectedBehavior => 0.15,
+            ChangeType::ImprovedClarity => 0.05,
+        }
+    }
+}
+
+impl PromptChange {
+    pub fn new(
+        change_type: ChangeType,
+        placement: ChangePlacement,
+        description: impl Into<String>,
+        text: impl Into<String>,
+    ) -> Self {
+        let text = text.into();
+        Self {
+            id: 0,
+            change_type,
+            description: description.into(),
+            tokens_added: estimate_tokens(&text) + 2,
+            text,
+            placement,
+            status: ChangeStatus::Pending,
+        }
+    }
+}
+
+/// Everything a refiner can look at when proposing a change
+pub struct RefinementInput<'a> {
+    pub user_input: &'a str,
+    pub context: &'a ZedContext,
+    pub intent: &'a PromptIntent,
+}
+
+/// One step of the refinement pipeline
+pub trait Refiner: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Propose a change, or `None` when this refiner has nothing to add
+    fn refine(&self, input: &RefinementInput) -> Option<PromptChange>;
+}
+
+/// An ordered list of refiners; each may contribute one change
+pub struct RefinementPipeline {
+    refiners: Vec<Box<dyn Refiner>>,
+}
+
+impl Default for RefinementPipeline {
+    fn default() -> Self {
+        Self::empty()
+            .with_refiner(FileContextRefiner)
+            .with_refiner(SelectionRefiner)
+            .with_refiner(DiagnosticsRefiner::default())
+            .with_refiner(RecentEditsRefiner::default())
+            .with_refiner(ExpectedBehaviorRefiner)
+            .with_refiner(StructureRefiner::default())
+    }
+}
+
+impl RefinementPipeline {
+    pub fn empty() -> Self {
+        Self {
+            refiners: Vec::new(),
+        }
+    }
+
+    pub fn with_refiner(mut self, refiner: impl Refiner + 'static) -> Self {
+        self.refiners.push(Box::new(refiner));
+        self
+    }
+
+    pub fn refiner_names(&self) -> Vec<&'static str> {
+        self.refiners.iter().map(|refiner| refiner.name()).collect()
+    }
+
+    pub fn run(&self, input: &RefinementInput) -> Vec<PromptChange> {
+        self.refiners
+            .iter()
+            .filter_map(|refiner| {
+                let change = refiner.refine(input)?;
+                log::debug!("{} proposed: {}", refiner.name(), change.description);
+                Some(change)
+            })
+            .enumerate()
+            .map(|(id, change)| PromptChange { id, ..change })
+            .collect()
+    }
+}
+
+/// Mentions the active file when the prompt doesn't already name one
+pub struct FileContextRefiner;
+
+impl Refiner for FileContextRefiner {
+    fn name(&self) -> &'static str {
+        "file_context"
+    }
+
+    fn refine(&self, input: &RefinementInput) -> Option<PromptChange> {
+        let active_file = input.context.active_file.as_ref()?;
+        if !should_add_file_context(input.user_input) {
+            return None;
+        }
+
+        let location = match active_file.cursor_line {
+            Some(line) => format!("`{}` (line {})", active_file.path.display(), line),
+            None => format!("`{}`", active_file.path.display()),
+        };
+        Some(PromptChange::new(
+            ChangeType::AddedFileLocation,
+            ChangePlacement::Before,
+            format!("Added file context: {}", active_file.path.display()),
+            format!("In file {}:", location),
+        ))
+    }
+}
+
+/// Includes the selected code when the prompt refers to it, or the code
+/// around the cursor when nothing is selected
+pub struct SelectionRefiner;
+
+impl Refiner for SelectionRefiner {
+    fn name(&self) -> &'static str {
+        "selection"
+    }
+
+    fn refine(&self, input: &RefinementInput) -> Option<PromptChange> {
+        let (selection, description) = match &input.context.selected_text {
+            Some(selection) => (selection, "Added selected code snippet for context"),
+            None => (
+                input.context.active_file.as_ref()?.excerpt.as_ref()?,
+                "Added the code around the cursor for context",
+            ),
+        };
+        if selection.content.trim().is_empty() || !should_add_code_snippet(input.user_input) {
+            return None;
+        }
+
+        let language = input
+            .context
+            .active_file
+            .as_ref()
+            .and_then(|file| file.language.as_deref())
+            .unwrap_or("");
+        Some(PromptChange::new(
+            ChangeType::AddedCodeSnippet,
+            ChangePlacement::After,
+            description,
+            format!(
+                "Relevant code (lines {}-{}):\n```{}\n{}\n```",
+                selection.start_line, selection.end_line, language, selection.content
+            ),
+        ))
+    }
+}
+
+/// Lists errors and warnings from the workspace for debugging requests
+pub struct DiagnosticsRefiner {
+    pub max_diagnostics: usize,
+}
+
+impl Default for DiagnosticsRefiner {
+    fn default() -> Self {
+        Self { max_diagnostics: 5 }
+    }
+}
+
+impl Refiner for DiagnosticsRefiner {
+    fn name(&self) -> &'static str {
+        "diagnostics"
+    }
+
+    fn refine(&self, input: &RefinementInput) -> Option<PromptChange> {
+        if !matches!(input.intent.primary_action, PromptAction::Debug) {
+            return None;
+        }
+
+        let mut diagnostics: Vec<_> = input
+            .context
+            .diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity != DiagnosticSeverity::Info)
+            .filter(|diagnostic| !input.user_input.contains(diagnostic.message.as_str()))
+            .collect();
+        if diagnostics.is_empty() {
+            return None;
+        }
+        diagnostics.sort_by_key(|diagnostic| diagnostic.severity != DiagnosticSeverity::Error);
+        diagnostics.truncate(self.max_diagnostics);
+
+        let lines: Vec<String> = diagnostics
+            .iter()
+            .map(|diagnostic| {
+                let severity = match diagnostic.severity {
+                    DiagnosticSeverity::Error => "error",
+                    DiagnosticSeverity::Warning => "warning",
+                    DiagnosticSeverity::Info => "info",
+                };
+                format!(
+                    "- {}:{}: {}: {}",
+                    diagnostic.path.display(),
+                    diagnostic.line,
+                    severity,
+                    diagnostic.message
+                )
+            })
+            .collect();
+        Some(PromptChange::new(
+            ChangeType::AddedContext,
+            ChangePlacement::After,
+            format!("Added {} diagnostic(s) from the workspace", lines.len()),
+            format!("Current diagnostics:\n{}", lines.join("\n")),
+        ))
+    }
+}
+
+/// Mentions what changed recently, since new bugs tend to live there
+pub struct RecentEditsRefiner {
+    pub max_edits: usize,
+}
+
+impl Default for RecentEditsRefiner {
+    fn default() -> Self {
+        Self { max_edits: 3 }
+    }
+}
+
+impl Refiner for RecentEditsRefiner {
+    fn name(&self) -> &'static str {
+        "recent_edits"
+    }
+
+    fn refine(&self, input: &RefinementInput) -> Option<PromptChange> {
+        if !matches!(input.intent.primary_action, PromptAction::Debug)
+            || input.context.recent_edits.is_empty()
+        {
+            return None;
+        }
+
+        let lines: Vec<String> = input
+            .context
+            .recent_edits
+            .iter()
+            .take(self.max_edits)
+            .map(|edit| format!("- {}: {}", edit.file.display(), edit.description))
+            .collect();
+        Some(PromptChange::new(
+            ChangeType::AddedContext,
+            ChangePlacement::After,
+            format!("Added {} recent edit(s)", lines.len()),
+            format!("Recent edits:\n{}", lines.join("\n")),
+        ))
+    }
+}
+
+/// Asks for the expected behavior when a bug report doesn't state it
+pub struct ExpectedBehaviorRefiner;
+
+impl Refiner for ExpectedBehaviorRefiner {
+    fn name(&self) -> &'static str {
+        "expected_behavior"
+    }
+
+    fn refine(&self, input: &RefinementInput) -> Option<PromptChange> {
+        if !matches!(input.intent.primary_action, PromptAction::Debug) {
+            return None;
+        }
+        let lower = input.user_input.to_lowercase();
+        if ["expect", "should", "instead", "supposed to"]
+            .iter()
+            .any(|phrase| lower.contains(phrase))
+        {
+            return None;
+        }
+
+        let has_errors = input
+            .context
+            .diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.severity == DiagnosticSeverity::Error);
+        let text = if has_errors {
+            "Expected behavior: the code builds without the errors above and keeps its current behavior."
+        } else {
+            "Expected behavior: the code runs without errors and produces the intended result."
+        };
+        Some(PromptChange::new(
+            ChangeType::AddedExpectedBehavior,
+            ChangePlacement::After,
+            "Stated the expected behavior",
+            text,
+        ))
+    }
+}
+
+/// Adds a numbered list of steps suited to the detected intent
+pub struct StructureRefiner {
+    pub min_confidence: f32,
+}
+
+impl Default for StructureRefiner {
+    fn default() -> Self {
+        Self {
+            min_confidence: 0.7,
+        }
+    }
+}
+
+impl Refiner for StructureRefiner {
+    fn name(&self) -> &'static str {
+        "structure"
+    }
+
+    fn refine(&self, input: &RefinementInput) -> Option<PromptChange> {
+        if input.intent.confidence <= self.min_confidence {
+            return None;
+        }
+        let steps = match input.intent.primary_action {
+            PromptAction::Debug => {
+                "Please:\n1. Identify the root cause\n2. Suggest a fix\n3. Explain how to prevent this in the future"
+            }
+            PromptAction::Implement => {
+                "Please:\n1. Design the approach\n2. Implement the feature\n3. Add appropriate error handling"
+            }
+            PromptAction::Refactor => {
+                "Please:\n1. Analyze current structure\n2. Propose improvements\n3. Implement the refactoring"
+            }
+            _ => return None,
+        };
+        Some(PromptChange::new(
+            ChangeType::AddedStructure,
+            ChangePlacement::After,
+            "Added structured format for clarity",
+            steps,
+        ))
+    }
+}
+
 /// Helper for generating prompt refinements
 pub struct PromptRefinement {
     pub refined_text: String,
@@ -40,85 +415,71 @@ pub struct PromptRefinement {
 }
 
 impl PromptRefinement {
-    /// Generate a refined prompt using local inference
+    /// Generate a refined prompt using local inference and the default refiners
     pub async fn generate(
         user_input: &str,
         context: &ZedContext,
         engine: &MistralRsEngine,
         config: &AgentConfig,
+    ) -> Result<Self> {
+        Self::generate_with(
+            &RefinementPipeline::default(),
+            user_input,
+            context,
+            engine,
+            config,
+        )
+        .await
+    }
+
+    pub async fn generate_with(
+        pipeline: &RefinementPipeline,
+        user_input: &str,
+        context: &ZedContext,
+        engine: &MistralRsEngine,
+        config: &AgentConfig,
     ) -> Result<Self> {
         log::debug!("Generating refinement for: {}", user_input);
 
-        // Analyze intent
         let intent = engine.analyze_intent(user_input).await?;
+        Ok(Self::from_intent(
+            pipeline, user_input, context, &intent, config,
+        ))
+    }
 
-        // Build refinement based on intent and context
-        let mut refined_text = user_input.to_string();
-        let mut changes = Vec::new();
-        let mut quality_improvement = 0.0f32;
-
-        // Add file context if available and relevant
-        if let Some(active_file) = &context.active_file {
-            if should_add_file_context(user_input) {
-                refined_text = format!(
-                    "In file `{}`:\n\n{}",
-                    active_file.path.display(),
-                    refined_text
-                );
-                changes.push(PromptChange {
-                    change_type: ChangeType::AddedFileLocation,
-                    description: format!("Added file context: {}", active_file.path.display()),
-                    tokens_added: 8,
-                });
-                quality_improvement += 0.15;
-            }
-        }
-
-        // Add code snippet if available
-        if let Some(selection) = &context.selected_text {
-            if !selection.content.is_empty() && should_add_code_snippet(user_input) {
-                refined_text = format!(
-                    "{}\n\nRelevant code:\n```{}\n{}\n```",
-                    refined_text,
-                    context
-                        .active_file
-                        .as_ref()
-                        .and_then(|f| f.language.as_deref())
-                        .unwrap_or(""),
-                    selection.content
-                );
-                changes.push(PromptChange {
-                    change_type: ChangeType::AddedCodeSnippet,
-                    description: "Added selected code snippet for context".to_string(),
-                    tokens_added: estimate_tokens(&selection.content) + 10,
-                });
-                quality_improvement += 0.25;
-            }
-        }
-
-        // Add structure based on intent
-        if intent.confidence > 0.7 {
-            let structured = add_structure_for_intent(&refined_text, &intent);
-            if structured != refined_text {
-                refined_text = structured;
-                changes.push(PromptChange {
-                    change_type: ChangeType::AddedStructure,
-                    description: "Added structured format for clarity".to_string(),
-                    tokens_added: 12,
-                });
-                quality_improvement += 0.20;
+    fn from_intent(
+        pipeline: &RefinementPipeline,
+        user_input: &str,
+        context: &ZedContext,
+        intent: &PromptIntent,
+        config: &AgentConfig,
+    ) -> Self {
+        let mut changes = pipeline.run(&RefinementInput {
+            user_input,
+            context,
+            intent,
+        });
+        if config.auto_approve_safe_refinements {
+            for change in &mut changes {
+                if change.change_type.is_safe() {
+                    change.status = ChangeStatus::Accepted;
+                }
             }
         }
 
-        // Calculate final quality score
-        let base_quality = calculate_base_quality(user_input);
-        let quality_score = (base_quality + quality_improvement).min(1.0);
-
-        Ok(Self {
-            refined_text,
+        let quality_improvement: f32 = changes
+            .iter()
+            .map(|change| change.change_type.quality_gain())
+            .sum();
+        let quality_score = (calculate_base_quality(user_input) + quality_improvement).min(1.0);
+
+        Self {
+            refined_text: compose(user_input, &changes, |change| {
+                change.status != ChangeStatus::Rejected
+            }),
             changes,
             quality_score,
-        })
+        }
     }
 
     /// Convert to RefinedPrompt with cache hit improvement estimate
@@ -136,6 +497,112 @@ impl PromptRefinement {
 }
 
 impl RefinedPrompt {
+    /// A refinement with no changes, used when refinement is skipped
+    pub fn unchanged(original: &str) -> Self {
+        Self {
+            original: original.to_string(),
+            refined: original.to_string(),
+            changes: Vec::new(),
+            quality_score: calculate_base_quality(original),
+            estimated_cache_hit_improvement: 0.0,
+        }
+    }
+
+    /// Whether any change still waits for the user to accept or reject it
+    pub fn needs_review(&self) -> bool {
+        self.changes
+            .iter()
+            .any(|change| change.status == ChangeStatus::Pending)
+    }
+
+    pub fn accept(&mut self, id: usize) {
+        self.set_status(id, ChangeStatus::Accepted);
+    }
+
+    pub fn reject(&mut self, id: usize) {
+        self.set_status(id, ChangeStatus::Rejected);
+    }
+
+    /// Replace a change's text and accept it; empty text rejects it instead
+    pub fn edit(&mut self, id: usize, text: &str) {
+        if let Some(change) = self.changes.iter_mut().find(|change| change.id == id) {
+            if text.trim().is_empty() {
+                change.status = ChangeStatus::Rejected;
+            } else {
+                change.text = text.trim_end().to_string();
+                change.tokens_added = estimate_tokens(&change.text) + 2;
+                change.status = ChangeStatus::Accepted;
+            }
+        }
+        self.refresh();
+    }
+
+    pub fn accept_all(&mut self) {
+        for change in &mut self.changes {
+            if change.status == ChangeStatus::Pending {
+                change.status = ChangeStatus::Accepted;
+            }
+        }
+        self.refresh();
+    }
+
+    pub fn reject_all(&mut self) {
+        for change in &mut self.changes {
+            change.status = ChangeStatus::Rejected;
+        }
+        self.refresh();
+    }
+
+    /// The prompt with only accepted changes applied
+    pub fn final_prompt(&self) -> String {
+        compose(&self.original, &self.changes, |change| {
+            change.status == ChangeStatus::Accepted
+        })
+    }
+
+    /// Accepted text to insert before and after the original prompt
+    pub fn accepted_insertions(&self) -> (String, String) {
+        let accepted = |placement| {
+            self.changes
+                .iter()
+                .filter(move |change| {
+                    change.status == ChangeStatus::Accepted && change.placement == placement
+                })
+                .map(|change| change.text.as_str())
+                .collect::<Vec<_>>()
+        };
+        let before = accepted(ChangePlacement::Before);
+        let after = accepted(ChangePlacement::After);
+        (
+            if before.is_empty() {
+                String::new()
+            } else {
+                format!("{}\n\n", before.join("\n\n"))
+            },
+            if after.is_empty() {
+                String::new()
+            } else {
+                format!("\n\n{}", after.join("\n\n"))
+            },
+        )
+    }
+
+    /// Summarize the user's decisions
+    pub fn approval_result(&self) -> ApprovalResult {
+        let accepted = self
+            .changes
+            .iter()
+            .filter(|change| change.status == ChangeStatus::Accepted)
+            .count();
+        if accepted == 0 {
+            ApprovalResult::Rejected
+        } else if accepted == self.changes.len() && self.final_prompt() == self.refined {
+            ApprovalResult::Accepted
+        } else {
+            ApprovalResult::Modified(self.final_prompt())
+        }
+    }
+
     /// Get highlighted changes for UI display
     pub fn highlight_changes(&self) -> Vec<ChangeHighlight> {
         self.changes
@@ -154,6 +621,27 @@ impl RefinedPrompt {
             })
             .collect()
     }
+
+    fn set_status(&mut self, id: usize, status: ChangeStatus) {
+        if let Some(change) = self.changes.iter_mut().find(|change| change.id == id) {
+            change.status = status;
+        }
+        self.refresh();
+    }
+
+    fn refresh(&mut self) {
+        self.refined = compose(&self.original, &self.changes, |change| {
+            change.status != ChangeStatus::Rejected
+        });
+        self.estimated_cache_hit_improvement = estimate_cache_hit_improvement(
+            &self
+                .changes
+                .iter()
+                .filter(|change| change.status != ChangeStatus::Rejected)
+                .cloned()
+                .collect::<Vec<_>>(),
+        );
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -163,6 +651,27 @@ pub struct ChangeHighlight {
     pub tokens: usize,
 }
 
+/// Surround the original prompt with the text of the included changes
+fn compose(
+    original: &str,
+    changes: &[PromptChange],
+    include: impl Fn(&PromptChange) -> bool,
+) -> String {
+    let mut parts: Vec<&str> = changes
+        .iter()
+        .filter(|change| change.placement == ChangePlacement::Before && include(change))
+        .map(|change| change.text.as_str())
+        .collect();
+    parts.push(original);
+    parts.extend(
+        changes
+            .iter()
+            .filter(|change| change.placement == ChangePlacement::After && include(change))
+            .map(|change| change.text.as_str()),
+    );
+    parts.join("\n\n")
+}
+
 fn should_add_file_context(input: &str) -> bool {
     // Don't add if already mentions file
     !input.contains(".rs") && !input.contains("file") && !input.contains("src/")
@@ -177,32 +686,6 @@ fn should_add_code_snippet(input: &str) -> bool {
         || lower.contains("bug")
 }
 
-fn add_structure_for_intent(text: &str, intent: &PromptIntent) -> String {
-    use crate::inference::PromptAction;
-
-    match intent.primary_action {
-        PromptAction::Debug => {
-            format!(
-                "{}\n\nPlease:\n1. Identify the root cause\n2. Suggest a fix\n3. Explain how to prevent this in the future",
-                text
-            )
-        }
-        PromptAction::Implement => {
-            format!(
-                "{}\n\nPlease:\n1. Design the approach\n2. Implement the feature\n3. Add appropriate error handling",
-                text
-            )
-        }
-        PromptAction::Refactor => {
-            format!(
-                "{}\n\nPlease:\n1. Analyze current structure\n2. Propose improvements\n3. Implement the refactoring",
-                text
-            )
-        }
-        _ => text.to_string(),
-    }
-}
-
 fn calculate_base_quality(input: &str) -> f32 {
     let mut score: f32 = 0.3; // Base score
 
@@ -233,8 +716,7 @@ fn calculate_base_quality(input: &str) -> f32 {
 }
 
 fn estimate_tokens(text: &str) -> usize {
-    // Rough estimate: 1 token ≈ 4 characters
-    text.len() / 4
+    default_token_counter().count_tokens(text)
 }
 
 fn estimate_cache_hit_improvement(changes: &[PromptChange]) -> f32 {
@@ -257,6 +739,47 @@ fn estimate_cache_hit_improvement(changes: &[PromptChange]) -> f32 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::context::{ActiveFile, DiagnosticInfo, Selection};
+    use std::path::PathBuf;
+
+    fn debug_intent() -> PromptIntent {
+        PromptIntent {
+            primary_action: PromptAction::Debug,
+            missing_context: Vec::new(),
+            confidence: 0.9,
+        }
+    }
+
+    fn workspace_context() -> ZedContext {
+        ZedContext {
+            active_file: Some(ActiveFile {
+                path: PathBuf::from("src/parser.rs"),
+                language: Some("rust".to_string()),
+                cursor_line: Some(42),
+                excerpt: None,
+            }),
+            selected_text: Some(Selection {
+                content: "let x = items[0];".to_string(),
+                start_line: 42,
+                end_line: 42,
+            }),
+            diagnostics: vec![
+                DiagnosticInfo {
+                    path: PathBuf::from("src/parser.rs"),
+                    line: 40,
+                    severity: DiagnosticSeverity::Warning,
+                    message: "unused variable `y`".to_string(),
+                },
+                DiagnosticInfo {
+                    path: PathBuf::from("src/parser.rs"),
+                    line: 42,
+                    severity: DiagnosticSeverity::Error,
+                    message: "cannot index into a value of type `Option<Vec<u8>>`".to_string(),
+                },
+            ],
+            ..Default::default()
+        }
+    }
 
     #[test]
     fn test_base_quality_calculation() {
@@ -269,7 +792,113 @@ mod tests {
 
     #[test]
     fn test_token_estimation() {
-        let text = "This is a test"; // 14 chars
-        assert_eq!(estimate_tokens(text), 3); // ~3 tokens
+        assert_eq!(estimate_tokens("This is a test"), 4);
+    }
+
+    #[test]
+    fn test_pipeline_runs_each_refiner() {
+        let refinement = PromptRefinement::from_intent(
+            &RefinementPipeline::default(),
+            "fix this crash",
+            &workspace_context(),
+            &debug_intent(),
+            &AgentConfig::default(),
+        );
+
+        let types: Vec<_> = refinement
+            .changes
+            .iter()
+            .map(|change| change.change_type)
+            .collect();
+        assert_eq!(
+            types,
+            [
+                ChangeType::AddedFileLocation,
+                ChangeType::AddedCodeSnippet,
+                ChangeType::AddedContext,
+                ChangeType::AddedExpectedBehavior,
+                ChangeType::AddedStructure,
+            ]
+        );
+        assert!(refinement
+            .changes
+            .iter()
+            .enumerate()
+            .all(|(ix, change)| change.id == ix && change.status == ChangeStatus::Pending));
+
+        // Errors are listed before warnings
+        let diagnostics = &refinement.changes[2].text;
+        assert!(diagnostics.find("error").unwrap() < diagnostics.find("warning").unwrap());
+        assert!(refinement.refined_text.starts_with(
+            "In file `src/parser.rs` (line 42):\n\nfix this crash\n\nRelevant code (lines 42-42):"
+        ));
+
+        let only_files = RefinementPipeline::empty().with_refiner(FileContextRefiner);
+        let refinement = PromptRefinement::from_intent(
+            &only_files,
+            "fix this crash",
+            &workspace_context(),
+            &debug_intent(),
+            &AgentConfig::default(),
+        );
+        assert_eq!(refinement.changes.len(), 1);
+    }
+
+    #[test]
+    fn test_review_decisions() {
+        let config = AgentConfig {
+            auto_approve_safe_refinements: true,
+            ..Default::default()
+        };
+        let mut refined = PromptRefinement::from_intent(
+            &RefinementPipeline::default(),
+            "fix this crash",
+            &workspace_context(),
+            &debug_intent(),
+            &config,
+        )
+        .into_refined_prompt("fix this crash");
+
+        // Only context from the workspace is approved automatically
+        let statuses: Vec<_> = refined.changes.iter().map(|change| change.status).collect();
+        assert_eq!(
+            statuses,
+            [
+                ChangeStatus::Accepted,
+                ChangeStatus::Accepted,
+                ChangeStatus::Accepted,
+                ChangeStatus::Pending,
+                ChangeStatus::Pending,
+            ]
+        );
+        assert!(refined.needs_review());
+
+        refined.reject(1);
+        refined.edit(3, "Expected behavior: an empty list returns None.\n");
+        refined.reject(4);
+        assert!(!refined.needs_review());
+        assert!(!refined.refined.contains("Relevant code"));
+
+        let final_prompt = refined.final_prompt();
+        assert!(
+            final_prompt.starts_with("In file `src/parser.rs` (line 42):\n\nfix this crash\n\n")
+        );
+        assert!(final_prompt.ends_with("Expected behavior: an empty list returns None."));
+        let (before, after) = refined.accepted_insertions();
+        assert_eq!(format!("{}fix this crash{}", before, after), final_prompt);
+        assert!(
+            matches!(refined.approval_result(), ApprovalResult::Modified(prompt) if prompt == final_prompt)
+        );
+
+        refined.reject_all();
+        assert!(matches!(
+            refined.approval_result(),
+            ApprovalResult::Rejected
+        ));
+        assert_eq!(refined.final_prompt(), "fix this crash");
+        assert_eq!(
+            refined.accepted_insertions(),
+            (String::new(), String::new())
+        );
     }
 }
@@ -0,0 +1,171 @@
+//! A tiny llama-architecture GGUF model for exercising inference offline.
+//!
+//! Attention and feed-forward weights are zero, so each logit only depends on
+//! the previous token through the embedding and output matrices. That turns
+//! the model into a fixed bigram chain: whatever the prompt, it answers with
+//! [`TEST_MODEL_RESPONSE`] followed by end-of-sequence.
+
+use anyhow::Result;
+use candle_core::quantized::{gguf_file, GgmlDType, QTensor};
+use candle_core::{Device, Tensor};
+use std::path::Path;
+
+/// The pieces the test model emits, in order
+pub const TEST_MODEL_PIECES: &[&str] = &[
+    "{\"primary_action\": ",
+    "\"refactor\", ",
+    "\"missing_context\": ",
+    "[\"file location\"], ",
+    "\"confidence\": 0.9",
+    "}",
+];
+
+pub const TEST_MODEL_RESPONSE: &str = "{\"primary_action\": \"refactor\", \"missing_context\": [\"file location\"], \"confidence\": 0.9}";
+
+const BOS: u32 = 1;
+const EOS: u32 = 2;
+
+/// (token, token type) pairs for the test vocabulary
+fn vocabulary() -> Vec<(String, i32)> {
+    let mut vocab = vec![
+        ("<unk>".to_string(), 2),
+        ("<s>".to_string(), 3),
+        ("</s>".to_string(), 3),
+        ("<0x0A>".to_string(), 6),
+        ("<0x21>".to_string(), 6),
+    ];
+    for c in ["\u{2581}", "h", "e", "l", "o", "w", "r", "d"] {
+        vocab.push((c.to_string(), 1));
+    }
+    for word in ["hello", "world"] {
+        for end in 1..=word.len() {
+            vocab.push((format!("\u{2581}{}", &word[..end]), 1));
+        }
+    }
+    for piece in TEST_MODEL_PIECES {
+        vocab.push((piece.to_string(), 1));
+    }
+    vocab
+}
+
+/// Write the test model to `path`
+pub fn write_test_model(path: &Path) -> Result<()> {
+    let vocab = vocabulary();
+    let vocab_size = vocab.len();
+    // One-hot embeddings need a dimension per token; rotary embeddings need it even
+    let hidden = (vocab_size + 1) & !1;
+    let feed_forward = 4;
+    let device = Device::Cpu;
+
+    let first_piece = (vocab_size - TEST_MODEL_PIECES.len()) as u32;
+    let successor = |token: u32| -> u32 {
+        if token < first_piece {
+            first_piece
+        } else if token as usize == vocab_size - 1 {
+            EOS
+        } else {
+            token + 1
+        }
+    };
+
+    let mut embeddings = vec![0f32; vocab_size * hidden];
+    let mut output = vec![0f32; vocab_size * hidden];
+    for token in 0..vocab_size {
+        embeddings[token * hidden + token] = 1.0;
+        output[successor(token as u32) as usize * hidden + token] = 1.0;
+    }
+
+    let quantize = |data: Vec<f32>, shape: &[usize]| -> Result<QTensor> {
+        let tensor = Tensor::from_vec(data, shape, &device)?;
+        Ok(QTensor::quantize(&tensor, GgmlDType::F32)?)
+    };
+    let zeros = |shape: &[usize]| quantize(vec![0.0; shape.iter().product()], shape);
+    let ones = |len: usize| quantize(vec![1.0; len], &[len]);
+
+    let tensors = vec![
+        (
+            "token_embd.weight",
+            quantize(embeddings, &[vocab_size, hidden])?,
+        ),
+        ("output.weight", quantize(output, &[vocab_size, hidden])?),
+        ("output_norm.weight", ones(hidden)?),
+        ("blk.0.attn_norm.weight", ones(hidden)?),
+        ("blk.0.ffn_norm.weight", ones(hidden)?),
+        ("blk.0.attn_q.weight", zeros(&[hidden, hidden])?),
+        ("blk.0.attn_k.weight", zeros(&[hidden, hidden])?),
+        ("blk.0.attn_v.weight", zeros(&[hidden, hidden])?),
+        ("blk.0.attn_output.weight", zeros(&[hidden, hidden])?),
+        ("blk.0.ffn_gate.weight", zeros(&[feed_forward, hidden])?),
+        ("blk.0.ffn_up.weight", zeros(&[feed_forward, hidden])?),
+        ("blk.0.ffn_down.weight", zeros(&[hidden, feed_forward])?),
+    ];
+
+    let string = |s: &str| gguf_file::Value::String(s.to_string());
+    let metadata = vec![
+        ("general.architecture", string("llama")),
+        ("general.name", string("optafly-test-model")),
+        ("llama.context_length", gguf_file::Value::U32(4096)),
+        (
+            "llama.embedding_length",
+            gguf_file::Value::U32(hidden as u32),
+        ),
+        (
+            "llama.feed_forward_length",
+            gguf_file::Value::U32(feed_forward as u32),
+        ),
+        ("llama.block_count", gguf_file::Value::U32(1)),
+        ("llama.attention.head_count", gguf_file::Value::U32(1)),
+        ("llama.attention.head_count_kv", gguf_file::Value::U32(1)),
+        (
+            "llama.rope.dimension_count",
+            gguf_file::Value::U32(hidden as u32),
+        ),
+        (
+            "llama.attention.layer_norm_rms_epsilon",
+            gguf_file::Value::F32(1e-5),
+        ),
+        ("tokenizer.ggml.model", string("llama")),
+        (
+            "tokenizer.ggml.tokens",
+            gguf_file::Value::Array(vocab.iter().map(|(token, _)| string(token)).collect()),
+        ),
+        (
+            "tokenizer.ggml.scores",
+            gguf_file::Value::Array(
+                vocab
+                    .iter()
+                    .map(|(token, _)| gguf_file::Value::F32(token.chars().count() as f32))
+                    .collect(),
+            ),
+        ),
+        (
+            "tokenizer.ggml.token_type",
+            gguf_file::Value::Array(
+                vocab
+                    .iter()
+                    .map(|(_, token_type)| gguf_file::Value::I32(*token_type))
+                    .collect(),
+            ),
+        ),
+        ("tokenizer.ggml.unknown_token_id", gguf_file::Value::U32(0)),
+        ("tokenizer.ggml.bos_token_id", gguf_file::Value::U32(BOS)),
+        ("tokenizer.ggml.eos_token_id", gguf_file::Value::U32(EOS)),
+    ];
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut file = std::fs::File::create(path)?;
+    gguf_file::write(
+        &mut file,
+        &metadata
+            .iter()
+            .map(|(key, value)| (*key, value))
+            .collect::<Vec<_>>(),
+        &tensors
+            .iter()
+            .map(|(name, tensor)| (*name, tensor))
+            .collect::<Vec<_>>(),
+    )?;
+    Ok(())
+}
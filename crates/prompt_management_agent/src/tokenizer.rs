@@ -0,0 +1,445 @@
+use anyhow::{bail, Context, Result};
+use candle_core::quantized::gguf_file;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap};
+use std::sync::{Arc, OnceLock};
+use tiktoken_rs::CoreBPE;
+
+const SPACE_MARKER: char = '\u{2581}';
+
+/// Token types as stored in `tokenizer.ggml.token_type`
+const TOKEN_TYPE_CONTROL: i32 = 3;
+const TOKEN_TYPE_USER_DEFINED: i32 = 4;
+const TOKEN_TYPE_BYTE: i32 = 6;
+
+/// Counts tokens for budgeting prompts and context
+pub trait TokenCounter: Send + Sync {
+    fn count_tokens(&self, text: &str) -> usize;
+}
+
+/// The `cl100k_base` BPE, used when no local model tokenizer is available
+pub struct BpeTokenCounter {
+    bpe: CoreBPE,
+}
+
+impl BpeTokenCounter {
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            bpe: tiktoken_rs::cl100k_base()?,
+        })
+    }
+}
+
+impl TokenCounter for BpeTokenCounter {
+    fn count_tokens(&self, text: &str) -> usize {
+        self.bpe.encode_ordinary(text).len()
+    }
+}
+
+/// Falls back to roughly four bytes per token if the BPE fails to load
+struct ApproximateTokenCounter;
+
+impl TokenCounter for ApproximateTokenCounter {
+    fn count_tokens(&self, text: &str) -> usize {
+        text.len().div_ceil(4)
+    }
+}
+
+/// The shared counter used when no model-specific tokenizer is known
+pub fn default_token_counter() -> Arc<dyn TokenCounter> {
+    static COUNTER: OnceLock<Arc<dyn TokenCounter>> = OnceLock::new();
+    COUNTER
+        .get_or_init(|| match BpeTokenCounter::new() {
+            Ok(counter) => Arc::new(counter),
+            Err(error) => {
+                log::error!("Failed to load the cl100k tokenizer: {:#}", error);
+                Arc::new(ApproximateTokenCounter)
+            }
+        })
+        .clone()
+}
+
+/// SentencePiece tokenizer rebuilt from the vocabulary embedded in a GGUF file
+///
+/// Only the `llama` tokenizer model (SentencePiece with byte fallback) is
+/// supported, which covers Phi-3, Mistral and Llama 2 derived models.
+pub struct GgufTokenizer {
+    tokens: Vec<String>,
+    scores: Vec<f32>,
+    token_types: Vec<i32>,
+    token_ids: HashMap<String, u32>,
+    /// Control and user-defined tokens, longest first, matched literally
+    special_tokens: Vec<(String, u32)>,
+    byte_tokens: [Option<u32>; 256],
+    unknown_token: Option<u32>,
+    bos_token: Option<u32>,
+    eos_token: Option<u32>,
+    add_space_prefix: bool,
+}
+
+impl GgufTokenizer {
+    pub fn from_gguf(content: &gguf_file::Content) -> Result<Self> {
+        let metadata = &content.metadata;
+        let model = metadata
+            .get("tokenizer.ggml.model")
+            .and_then(|value| value.to_string().ok())
+            .map(String::as_str)
+            .unwrap_or("llama");
+        if model != "llama" {
+            bail!("Unsupported tokenizer model '{}', expected 'llama'", model);
+        }
+
+        let tokens = metadata
+            .get("tokenizer.ggml.tokens")
+            .context("GGUF file has no tokenizer.ggml.tokens")?
+            .to_vec()?
+            .iter()
+            .map(|token| Ok(token.to_string()?.clone()))
+            .collect::<Result<Vec<String>>>()?;
+        let scores = match metadata.get("tokenizer.ggml.scores") {
+            Some(scores) => scores
+                .to_vec()?
+                .iter()
+                .map(|score| Ok(score.to_f32()?))
+                .collect::<Result<Vec<f32>>>()?,
+            None => vec![0.0; tokens.len()],
+        };
+        let token_types = match metadata.get("tokenizer.ggml.token_type") {
+            Some(types) => types
+                .to_vec()?
+                .iter()
+                .map(|token_type| Ok(token_type.to_i32()?))
+                .collect::<Result<Vec<i32>>>()?,
+            None => vec![1; tokens.len()],
+        };
+        if scores.len() != tokens.len() || token_types.len() != tokens.len() {
+            bail!("GGUF tokenizer metadata has mismatched lengths");
+        }
+
+        let token_id = |key: &str| {
+            metadata
+                .get(key)
+                .and_then(|value| value.to_u32().ok())
+                .filter(|id| (*id as usize) < tokens.len())
+        };
+        let unknown_token = token_id("tokenizer.ggml.unknown_token_id");
+        let bos_token = token_id("tokenizer.ggml.bos_token_id");
+        let eos_token = token_id("tokenizer.ggml.eos_token_id");
+        let add_space_prefix = metadata
+            .get("tokenizer.ggml.add_space_prefix")
+            .and_then(|value| value.to_bool().ok())
+            .unwrap_or(true);
+
+        let mut token_ids = HashMap::with_capacity(tokens.len());
+        let mut special_tokens = Vec::new();
+        let mut byte_tokens = [None; 256];
+        for (id, token) in tokens.iter().enumerate() {
+            let id = id as u32;
+            token_ids.entry(token.clone()).or_insert(id);
+            match token_types[id as usize] {
+                TOKEN_TYPE_CONTROL | TOKEN_TYPE_USER_DEFINED if !token.is_empty() => {
+                    special_tokens.push((token.clone(), id));
+                }
+                TOKEN_TYPE_BYTE => {
+                    if let Some(byte) = parse_byte_token(token) {
+                        byte_tokens[byte as usize] = Some(id);
+                    }
+                }
+                _ => {}
+            }
+        }
+        special_tokens.sort_by_key(|(token, _)| Reverse(token.len()));
+
+        Ok(Self {
+            tokens,
+            scores,
+            token_types,
+            token_ids,
+            special_tokens,
+            byte_tokens,
+            unknown_token,
+            bos_token,
+            eos_token,
+            add_space_prefix,
+        })
+    }
+
+    pub fn vocab_size(&self) -> usize {
+        self.tokens.len()
+    }
+
+    pub fn bos_token(&self) -> Option<u32> {
+        self.bos_token
+    }
+
+    pub fn eos_token(&self) -> Option<u32> {
+        self.eos_token
+    }
+
+    pub fn token_id(&self, token: &str) -> Option<u32> {
+        self.token_ids.get(token).copied()
+    }
+
+    /// Encode text, matching control tokens such as `<|user|>` literally
+    pub fn encode(&self, text: &str, add_bos: bool) -> Vec<u32> {
+        let mut ids = Vec::new();
+        if add_bos {
+            ids.extend(self.bos_token);
+        }
+
+        let mut remaining = text;
+        let mut at_start = true;
+        while !remaining.is_empty() {
+            let next_special = self
+                .special_tokens
+                .iter()
+                .filter_map(|(token, id)| {
+                    let ix = remaining.find(token.as_str())?;
+                    Some((ix, token.len(), *id))
+                })
+                .min_by_key(|(ix, _, _)| *ix);
+
+            let (plain, special) = match next_special {
+                Some((ix, len, id)) => (&remaining[..ix], Some((len, id))),
+                None => (remaining, None),
+            };
+            if !plain.is_empty() {
+                self.encode_plain(plain, at_start && self.add_space_prefix, &mut ids);
+            }
+            at_start = false;
+
+            match special {
+                Some((len, id)) => {
+                    ids.push(id);
+                    remaining = &remaining[plain.len() + len..];
+                }
+                None => break,
+            }
+        }
+        ids
+    }
+
+    /// Decode token ids back into text, skipping control tokens
+    pub fn decode(&self, ids: &[u32]) -> String {
+        let mut bytes = Vec::new();
+        for &id in ids {
+            let Some(token) = self.tokens.get(id as usize) else {
+                continue;
+            };
+            match self.token_types[id as usize] {
+                TOKEN_TYPE_CONTROL => {}
+                TOKEN_TYPE_BYTE => bytes.extend(parse_byte_token(token)),
+                _ => {
+                    let piece = token.replace(SPACE_MARKER, " ");
+                    bytes.extend_from_slice(piece.as_bytes());
+                }
+            }
+        }
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+
+    /// Greedy SentencePiece merging: repeatedly join the adjacent pair whose
+    /// concatenation is the highest scoring piece in the vocabulary.
+    fn encode_plain(&self, text: &str, add_space_prefix: bool, ids: &mut Vec<u32>) {
+        let mut normalized = String::with_capacity(text.len() + 3);
+        if add_space_prefix {
+            normalized.push(SPACE_MARKER);
+        }
+        normalized.extend(
+            text.chars()
+                .map(|c| if c == ' ' { SPACE_MARKER } else { c }),
+        );
+
+        let mut symbols: Vec<Symbol> = normalized
+            .char_indices()
+            .map(|(start, c)| Symbol {
+                start,
+                len: c.len_utf8(),
+                prev: None,
+                next: None,
+            })
+            .collect();
+        let count = symbols.len();
+        for (ix, symbol) in symbols.iter_mut().enumerate() {
+            symbol.prev = ix.checked_sub(1);
+            symbol.next = (ix + 1 < count).then_some(ix + 1);
+        }
+
+        let mut queue = BinaryHeap::new();
+        for left in 1..count {
+            self.push_bigram(&normalized, &symbols, left - 1, left, &mut queue);
+        }
+
+        while let Some(bigram) = queue.pop() {
+            let (left, right) = (bigram.left, bigram.right);
+            // Skip bigrams invalidated by an earlier merge
+            if symbols[left].len == 0
+                || symbols[right].len == 0
+                || symbols[left].len + symbols[right].len != bigram.len
+            {
+                continue;
+            }
+
+            symbols[left].len += symbols[right].len;
+            symbols[right].len = 0;
+            symbols[left].next = symbols[right].next;
+            if let Some(next) = symbols[right].next {
+                symbols[next].prev = Some(left);
+            }
+
+            if let Some(prev) = symbols[left].prev {
+                self.push_bigram(&normalized, &symbols, prev, left, &mut queue);
+            }
+            if let Some(next) = symbols[left].next {
+                self.push_bigram(&normalized, &symbols, left, next, &mut queue);
+            }
+        }
+
+        let mut current = (count > 0).then_some(0);
+        while let Some(ix) = current {
+            let symbol = &symbols[ix];
+            let piece = &normalized[symbol.start..symbol.start + symbol.len];
+            match self.token_ids.get(piece) {
+                Some(&id) => ids.push(id),
+                None => {
+                    for byte in piece.bytes() {
+                        if let Some(id) = self.byte_tokens[byte as usize].or(self.unknown_token) {
+                            ids.push(id);
+                        }
+                    }
+                }
+            }
+            current = symbol.next;
+        }
+    }
+
+    fn push_bigram(
+        &self,
+        text: &str,
+        symbols: &[Symbol],
+        left: usize,
+        right: usize,
+        queue: &mut BinaryHeap<Bigram>,
+    ) {
+        let start = symbols[left].start;
+        let len = symbols[left].len + symbols[right].len;
+        if let Some(&id) = self.token_ids.get(&text[start..start + len]) {
+            queue.push(Bigram {
+                score: self.scores[id as usize],
+                left,
+                right,
+                len,
+            });
+        }
+    }
+}
+
+impl TokenCounter for GgufTokenizer {
+    fn count_tokens(&self, text: &str) -> usize {
+        self.encode(text, false).len()
+    }
+}
+
+struct Symbol {
+    start: usize,
+    len: usize,
+    prev: Option<usize>,
+    next: Option<usize>,
+}
+
+struct Bigram {
+    score: f32,
+    left: usize,
+    right: usize,
+    len: usize,
+}
+
+impl PartialEq for Bigram {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Bigram {}
+
+impl PartialOrd for Bigram {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Bigram {
+    /// Highest score first, leftmost first on ties
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.score
+            .total_cmp(&other.score)
+            .then_with(|| other.left.cmp(&self.left))
+    }
+}
+
+/// Parse byte fallback tokens of the form `<0x0A>`
+fn parse_byte_token(token: &str) -> Option<u8> {
+    let hex = token.strip_prefix("<0x")?.strip_suffix('>')?;
+    u8::from_str_radix(hex, 16).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_model;
+    use std::sync::atomic::{self, AtomicUsize};
+
+    fn test_tokenizer() -> GgufTokenizer {
+        // Tests run in parallel, so each needs a file of its own
+        static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "pma-tokenizer-test-{}-{}.gguf",
+            std::process::id(),
+            NEXT_ID.fetch_add(1, atomic::Ordering::Relaxed)
+        ));
+        test_model::write_test_model(&path).unwrap();
+        let mut file = std::fs::File::open(&path).unwrap();
+        let content = gguf_file::Content::read(&mut file).unwrap();
+        std::fs::remove_file(&path).ok();
+        GgufTokenizer::from_gguf(&content).unwrap()
+    }
+
+    #[test]
+    fn test_encode_decode_round_trip() {
+        let tokenizer = test_tokenizer();
+
+        let ids = tokenizer.encode("hello world", false);
+        assert_eq!(
+            ids,
+            [
+                tokenizer.token_id("\u{2581}hello").unwrap(),
+                tokenizer.token_id("\u{2581}world").unwrap()
+            ]
+        );
+        assert_eq!(tokenizer.decode(&ids), " hello world");
+
+        // Characters missing from the vocabulary fall back to byte tokens
+        let ids = tokenizer.encode("hello!\n", true);
+        assert_eq!(ids[0], tokenizer.bos_token().unwrap());
+        assert_eq!(tokenizer.decode(&ids), " hello!\n");
+
+        // Control tokens are matched literally rather than split into pieces
+        let ids = tokenizer.encode("hello</s>", false);
+        assert_eq!(ids.last().copied(), tokenizer.eos_token());
+    }
+
+    #[test]
+    fn test_token_counters() {
+        let bpe = BpeTokenCounter::new().unwrap();
+        assert_eq!(bpe.count_tokens(""), 0);
+        assert_eq!(bpe.count_tokens("hello world"), 2);
+
+        // Four bytes per token, rounded up
+        assert_eq!(ApproximateTokenCounter.count_tokens(""), 0);
+        assert_eq!(ApproximateTokenCounter.count_tokens("hello world"), 3);
+
+        let gguf: &dyn TokenCounter = &test_tokenizer();
+        assert_eq!(gguf.count_tokens(""), 0);
+        assert_eq!(gguf.count_tokens("hello world"), 2);
+    }
+}
@@ -343,7 +343,26 @@ messages!(
     (RemoteStarted, Background),
     (GitGetWorktrees, Background),
     (GitWorktreesResponse, Background),
-    (GitCreateWorktree, Background)
+    (GitCreateWorktree, Background),
+    (GitGetRebaseTodo, Background),
+    (GitGetRebaseTodoResponse, Background),
+    (GitRebase, Background),
+    (GitRebaseResponse, Background),
+    (GitGetRebaseState, Background),
+    (GitRebaseContinue, Background),
+    (GitRebaseSkip, Background),
+    (GitRebaseAbort, Background),
+    (GitCherryPick, Background),
+    (GitRevert, Background),
+    (GitAmendCommit, Background),
+    (GitLog, Background),
+    (GitLogResponse, Background),
+    (GitCreateTag, Background),
+    (GitCheckoutCommit, Background),
+    (GitGetSequencerOperation, Background),
+    (GitSequencerContinue, Background),
+    (GitSequencerAbort, Background),
+    (GitSequencerResponse, Background)
 );
 
 request_messages!(
@@ -529,7 +548,22 @@ request_messages!(
     (GetAgentServerCommand, AgentServerCommand),
     (RemoteStarted, Ack),
     (GitGetWorktrees, GitWorktreesResponse),
-    (GitCreateWorktree, Ack)
+    (GitCreateWorktree, Ack),
+    (GitGetRebaseTodo, GitGetRebaseTodoResponse),
+    (GitRebase, GitRebaseResponse),
+    (GitGetRebaseState, GitRebaseResponse),
+    (GitRebaseContinue, GitRebaseResponse),
+    (GitRebaseSkip, GitRebaseResponse),
+    (GitRebaseAbort, Ack),
+    (GitCherryPick, Ack),
+    (GitRevert, Ack),
+    (GitAmendCommit, GitRebaseResponse),
+    (GitLog, GitLogResponse),
+    (GitCreateTag, Ack),
+    (GitCheckoutCommit, Ack),
+    (GitGetSequencerOperation, GitSequencerResponse),
+    (GitSequencerContinue, GitSequencerResponse),
+    (GitSequencerAbort, Ack)
 );
 
 lsp_messages!(
@@ -702,7 +736,22 @@ entity_messages!(
     ExternalAgentLoadingStatusUpdated,
     NewExternalAgentVersionAvailable,
     GitGetWorktrees,
-    GitCreateWorktree
+    GitCreateWorktree,
+    GitGetRebaseTodo,
+    GitRebase,
+    GitGetRebaseState,
+    GitRebaseContinue,
+    GitRebaseSkip,
+    GitRebaseAbort,
+    GitCherryPick,
+    GitRevert,
+    GitAmendCommit,
+    GitLog,
+    GitCreateTag,
+    GitCheckoutCommit,
+    GitGetSequencerOperation,
+    GitSequencerContinue,
+    GitSequencerAbort
 );
 
 entity_messages!(
@@ -228,6 +228,8 @@ impl PyO3Bridge {
                 .map(|s| s.to_string()),
             tokens_used: response_json["tokens_used"].as_u64().unwrap_or(0) as usize,
             latency_ms: response_json["latency_ms"].as_u64().unwrap_or(0),
+            model: response_json["model"].as_str().map(|s| s.to_string()),
+            miss_reason: None,
         };
 
         Ok(response)
@@ -2,7 +2,7 @@ use anyhow::Result;
 use pyo3::prelude::*;
 use pyo3::types::PyDict;
 
-use crate::types::{ProxyRequest, ProxyResponse};
+use crate::types::{MessageRole, ProxyRequest, ProxyResponse};
 
 /// Convert Rust ProxyRequest to Python dict
 pub fn request_to_pydict<'py>(py: Python<'py>, request: &ProxyRequest) -> Result<&'py PyDict> {
@@ -10,6 +10,31 @@ pub fn request_to_pydict<'py>(py: Python<'py>, request: &ProxyRequest) -> Result
 
     dict.set_item("prompt", &request.prompt)?;
     dict.set_item("api_key", &request.api_key)?;
+    if let Some(model) = &request.model {
+        dict.set_item("model", model)?;
+    }
+
+    let history: Vec<&PyDict> = request
+        .history
+        .iter()
+        .map(|message| {
+            let message_dict = PyDict::new(py);
+            let role = match message.role {
+                MessageRole::User => "user",
+                MessageRole::Assistant => "assistant",
+            };
+            message_dict.set_item("role", role).ok();
+            message_dict.set_item("content", &message.content).ok();
+            message_dict
+        })
+        .collect();
+    dict.set_item("history", history)?;
+    if !request.chunks.is_empty() {
+        dict.set_item("chunks", &request.chunks)?;
+    }
+    if request.skip_cache {
+        dict.set_item("skip_cache", true)?;
+    }
 
     // Convert cache anchors
     let anchors: Vec<&PyDict> = request
@@ -34,6 +59,7 @@ pub fn request_to_pydict<'py>(py: Python<'py>, request: &ProxyRequest) -> Result
     opt_dict.set_item("remove_redundancy", request.optimization.remove_redundancy)?;
     opt_dict.set_item("compress_context", request.optimization.compress_context)?;
     opt_dict.set_item("split_into_chunks", request.optimization.split_into_chunks)?;
+    opt_dict.set_item("tokens_saved", request.optimization.tokens_saved)?;
     dict.set_item("optimization", opt_dict)?;
 
     Ok(dict)
@@ -68,11 +94,17 @@ pub fn pydict_to_response(_py: Python, dict: &PyDict) -> Result<ProxyResponse> {
 
     let tokens_used = dict.get_item("tokens_used")?.unwrap().extract::<usize>()?;
     let latency_ms = dict.get_item("latency_ms")?.unwrap().extract::<u64>()?;
+    let model = match dict.get_item("model")? {
+        Some(model) => model.extract::<Option<String>>()?,
+        None => None,
+    };
 
     Ok(ProxyResponse {
         cache_status,
         response_text,
         tokens_used,
         latency_ms,
+        model,
+        miss_reason: None,
     })
 }
@@ -1,17 +1,24 @@
+#[cfg(feature = "python")]
 pub mod bridge;
+#[cfg(feature = "python")]
 pub mod conversions;
 pub mod telemetry;
 pub mod types;
 
+#[cfg(feature = "python")]
 use anyhow::Result;
+#[cfg(feature = "python")]
 use std::sync::Arc;
+#[cfg(feature = "python")]
 use tokio::sync::Mutex;
 
+#[cfg(feature = "python")]
 pub use bridge::PyO3Bridge;
 pub use telemetry::{CacheMissReason, HealthStatus, TelemetryConfig, TelemetryEvent};
-pub use types::{CacheStatus, ProxyRequest, ProxyResponse};
+pub use types::{CacheStatus, MessageRole, ProxyMessage, ProxyRequest, ProxyResponse};
 
 /// Initialize PyO3 and prepare Python interpreter
+#[cfg(feature = "python")]
 pub fn initialize_python() -> Result<()> {
     pyo3::prepare_freethreaded_python();
     log::info!("PyO3 initialized successfully");
@@ -19,10 +26,12 @@ pub fn initialize_python() -> Result<()> {
 }
 
 /// Global Python bridge instance
+#[cfg(feature = "python")]
 static BRIDGE_INSTANCE: once_cell::sync::Lazy<Arc<Mutex<Option<PyO3Bridge>>>> =
     once_cell::sync::Lazy::new(|| Arc::new(Mutex::new(None)));
 
 /// Initialize the global bridge instance
+#[cfg(feature = "python")]
 pub async fn initialize_bridge(widget_log_path: &str) -> Result<()> {
     let bridge = PyO3Bridge::new(widget_log_path).await?;
     let mut instance = BRIDGE_INSTANCE.lock().await;
@@ -31,11 +40,12 @@ pub async fn initialize_bridge(widget_log_path: &str) -> Result<()> {
 }
 
 /// Get reference to the global bridge
+#[cfg(feature = "python")]
 pub async fn get_bridge() -> Result<Arc<Mutex<Option<PyO3Bridge>>>> {
     Ok(BRIDGE_INSTANCE.clone())
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "python"))]
 mod tests {
     use super::*;
 
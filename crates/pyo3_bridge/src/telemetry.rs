@@ -45,6 +45,9 @@ pub enum TelemetryEvent {
         prompt_id: String,
         model_name: String,
         prompt_tokens: usize,
+        /// The user picked the model, rather than the router or the default
+        #[serde(default)]
+        user_selected: bool,
         timestamp: u64,
     },
 
@@ -83,6 +86,24 @@ pub enum TelemetryEvent {
         timestamp: u64,
     },
 
+    /// Prompt features the Burn-LM router based its decision on, joined
+    /// with cache events by prompt_id to train the routing network
+    RoutingFeatures {
+        prompt_id: String,
+        features: Vec<f32>,
+        timestamp: u64,
+    },
+
+    /// Burn-LM token optimizer compression, with the tokens each
+    /// transformation saved, to compare against `CacheHit.tokens_saved`
+    PromptCompression {
+        prompt_id: String,
+        tokens_before: usize,
+        tokens_after: usize,
+        transformations: HashMap<String, usize>,
+        timestamp: u64,
+    },
+
     /// Prompt management agent refinement
     PromptRefinement {
         prompt_id: String,
@@ -199,6 +220,31 @@ impl TelemetryEvent {
         }
     }
 
+    /// Create a RoutingFeatures event
+    pub fn routing_features(prompt_id: String, features: Vec<f32>) -> Self {
+        Self::RoutingFeatures {
+            prompt_id,
+            features,
+            timestamp: Self::now_ms(),
+        }
+    }
+
+    /// Create a PromptCompression event
+    pub fn prompt_compression(
+        prompt_id: String,
+        tokens_before: usize,
+        tokens_after: usize,
+        transformations: HashMap<String, usize>,
+    ) -> Self {
+        Self::PromptCompression {
+            prompt_id,
+            tokens_before,
+            tokens_after,
+            transformations,
+            timestamp: Self::now_ms(),
+        }
+    }
+
     /// Create a BridgeError event
     pub fn bridge_error(
         operation: impl Into<String>,
@@ -259,6 +305,8 @@ impl TelemetryEvent {
             | Self::BridgeInitialized { timestamp, .. }
             | Self::BridgeError { timestamp, .. }
             | Self::BurnRouterDecision { timestamp, .. }
+            | Self::RoutingFeatures { timestamp, .. }
+            | Self::PromptCompression { timestamp, .. }
             | Self::PromptRefinement { timestamp, .. }
             | Self::HealthCheck { timestamp, .. }
             | Self::CacheStats { timestamp, .. }
@@ -276,6 +324,8 @@ impl TelemetryEvent {
             | Self::ModelInferenceStart { prompt_id, .. }
             | Self::ModelInferenceEnd { prompt_id, .. }
             | Self::BurnRouterDecision { prompt_id, .. }
+            | Self::RoutingFeatures { prompt_id, .. }
+            | Self::PromptCompression { prompt_id, .. }
             | Self::PromptRefinement { prompt_id, .. } => Some(prompt_id),
             Self::BridgeError { prompt_id, .. } => prompt_id.as_deref(),
             _ => None,
@@ -294,6 +344,8 @@ impl TelemetryEvent {
             Self::BridgeInitialized { .. } => "bridge_initialized",
             Self::BridgeError { .. } => "bridge_error",
             Self::BurnRouterDecision { .. } => "burn_router_decision",
+            Self::RoutingFeatures { .. } => "routing_features",
+            Self::PromptCompression { .. } => "prompt_compression",
             Self::PromptRefinement { .. } => "prompt_refinement",
             Self::HealthCheck { .. } => "health_check",
             Self::CacheStats { .. } => "cache_stats",
@@ -321,11 +373,28 @@ impl TelemetryEvent {
             Self::CacheMiss { .. } => "debug",
             Self::PromptStart { .. }
             | Self::ModelInferenceStart { .. }
-            | Self::BurnRouterDecision { .. } => "debug",
+            | Self::BurnRouterDecision { .. }
+            | Self::RoutingFeatures { .. }
+            | Self::PromptCompression { .. } => "debug",
             _ => "info",
         }
     }
 
+    /// Parse a JSON-lines event log, skipping lines that aren't valid events
+    pub fn parse_log(log: &str) -> Vec<Self> {
+        log.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .filter_map(|line| match serde_json::from_str(line) {
+                Ok(event) => Some(event),
+                Err(error) => {
+                    log::debug!("Skipping malformed telemetry line: {}", error);
+                    None
+                }
+            })
+            .collect()
+    }
+
     /// Get correlation ID (alias for prompt_id for tracing compatibility)
     pub fn correlation_id(&self) -> Option<&str> {
         self.prompt_id()
@@ -486,6 +555,22 @@ mod tests {
         assert!(error.should_sample(0.01));
     }
 
+    #[test]
+    fn test_parse_log() {
+        let features = TelemetryEvent::routing_features("p1".to_string(), vec![0.5, 1.0]);
+        let miss = TelemetryEvent::cache_miss("p1".to_string(), CacheMissReason::CacheEmpty);
+        let log = format!(
+            "{}\nnot json\n\n{}\n",
+            serde_json::to_string(&features).unwrap(),
+            serde_json::to_string(&miss).unwrap()
+        );
+
+        let events = TelemetryEvent::parse_log(&log);
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].event_name(), "routing_features");
+        assert_eq!(events[1].prompt_id(), Some("p1"));
+    }
+
     #[test]
     fn test_config_per_event_sampling() {
         let config = TelemetryConfig::default();
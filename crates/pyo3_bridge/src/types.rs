@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::telemetry::CacheMissReason;
+
 /// Request sent to Widget-Log proxy
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProxyRequest {
@@ -7,6 +9,35 @@ pub struct ProxyRequest {
     pub cache_anchors: Vec<CacheAnchor>,
     pub api_key: String,
     pub optimization: TokenOptimization,
+    /// Model to answer a cache miss with, instead of the proxy's default
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Earlier messages in the conversation, sent ahead of the prompt. Files
+    /// and blocks elided from the prompt were sent in full here.
+    #[serde(default)]
+    pub history: Vec<ProxyMessage>,
+    /// The prompt split into parts that together make up `prompt`, sent as
+    /// separate content blocks of the last message. Empty to send it whole.
+    #[serde(default)]
+    pub chunks: Vec<String>,
+    /// Send the prompt straight to the API, without looking it up in or
+    /// adding it to the semantic cache
+    #[serde(default)]
+    pub skip_cache: bool,
+}
+
+/// A message sent earlier in the same conversation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProxyMessage {
+    pub role: MessageRole,
+    pub content: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MessageRole {
+    User,
+    Assistant,
 }
 
 /// Response from Widget-Log proxy
@@ -16,6 +47,12 @@ pub struct ProxyResponse {
     pub response_text: Option<String>,
     pub tokens_used: usize,
     pub latency_ms: u64,
+    /// Model that answered a cache miss, if the proxy reports it
+    #[serde(default)]
+    pub model: Option<String>,
+    /// Why the cache missed, if the proxy reports it
+    #[serde(default)]
+    pub miss_reason: Option<CacheMissReason>,
 }
 
 /// Cache hit or miss status
@@ -44,6 +81,9 @@ pub struct TokenOptimization {
     pub remove_redundancy: bool,
     pub compress_context: bool,
     pub split_into_chunks: bool,
+    /// Tokens removed from the prompt by the transformations above
+    #[serde(default)]
+    pub tokens_saved: usize,
 }
 
 impl Default for TokenOptimization {
@@ -52,6 +92,7 @@ impl Default for TokenOptimization {
             remove_redundancy: true,
             compress_context: false,
             split_into_chunks: false,
+            tokens_saved: 0,
         }
     }
 }
@@ -0,0 +1,303 @@
+//! A kernel for tests that speaks the Jupyter messaging protocol over ZMQ.
+//!
+//! It echoes the code of every execute request back as stdout, which is
+//! enough to drive a session through a full execution. Every other shell and
+//! control request gets a reply too, an error reply if the kernel doesn't
+//! handle it.
+
+use std::{
+    net::{IpAddr, Ipv4Addr},
+    path::PathBuf,
+};
+
+use anyhow::Result;
+use futures::{StreamExt as _, channel::mpsc};
+use gpui::{App, Entity, Task, TestAppContext, VisualTestContext, Window};
+use jupyter_protocol::{
+    ExecutionState, JupyterKernelspec, JupyterMessage, JupyterMessageContent, KernelInfoReply,
+    connection_info::{ConnectionInfo, Transport},
+};
+use serde_json::json;
+
+use super::{
+    KernelSession, KernelSpecification, LocalKernelSpecification, RunningKernel, connect_to_kernel,
+    peek_ports,
+};
+
+pub struct FakeKernel {
+    connection_info: ConnectionInfo,
+    control_requests: mpsc::UnboundedReceiver<JupyterMessage>,
+    _tasks: Vec<Task<Result<()>>>,
+}
+
+impl FakeKernel {
+    /// Bind the kernel's sockets and start answering requests.
+    pub async fn start(cx: &mut TestAppContext) -> Result<Self> {
+        Self::start_with_reply_order(false, cx).await
+    }
+
+    /// Like [`FakeKernel::start`], but execute requests are replied to before
+    /// their output is sent, as real kernels may since the reply and the
+    /// output go over different sockets.
+    pub async fn start_replying_early(cx: &mut TestAppContext) -> Result<Self> {
+        Self::start_with_reply_order(true, cx).await
+    }
+
+    async fn start_with_reply_order(reply_early: bool, cx: &mut TestAppContext) -> Result<Self> {
+        // The sockets run on the async-dispatcher runtime
+        cx.update(|cx| async_dispatcher::set_dispatcher(crate::zed_dispatcher(cx)));
+
+        let ip = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        let ports = peek_ports(ip).await?;
+        let connection_info = ConnectionInfo {
+            transport: Transport::TCP,
+            ip: ip.to_string(),
+            stdin_port: ports[0],
+            control_port: ports[1],
+            hb_port: ports[2],
+            shell_port: ports[3],
+            iopub_port: ports[4],
+            signature_scheme: "hmac-sha256".to_string(),
+            key: uuid::Uuid::new_v4().to_string(),
+            kernel_name: Some("fake".to_string()),
+        };
+
+        let session_id = uuid::Uuid::new_v4().to_string();
+        let mut iopub_socket =
+            runtimelib::create_kernel_iopub_connection(&connection_info, &session_id).await?;
+        let mut shell_socket =
+            runtimelib::create_kernel_shell_connection(&connection_info, &session_id).await?;
+        let mut control_socket =
+            runtimelib::create_kernel_control_connection(&connection_info, &session_id).await?;
+
+        let shell_task = cx.background_spawn(async move {
+            let mut execution_count = 0;
+            loop {
+                let request = shell_socket.read().await?;
+                let reply = |msg_type: &str, content| reply_to(&request, msg_type, content);
+
+                let JupyterMessageContent::ExecuteRequest(execute_request) = &request.content
+                else {
+                    shell_socket.send(shell_reply(&request)?).await?;
+                    continue;
+                };
+                execution_count += 1;
+
+                iopub_socket
+                    .send(reply("status", json!({"execution_state": "busy"}))?)
+                    .await?;
+                iopub_socket
+                    .send(reply(
+                        "execute_input",
+                        json!({
+                            "code": execute_request.code,
+                            "execution_count": execution_count,
+                        }),
+                    )?)
+                    .await?;
+                let execute_reply = reply(
+                    "execute_reply",
+                    json!({
+                        "status": "ok",
+                        "execution_count": execution_count,
+                        "payload": [],
+                        "user_expressions": {},
+                    }),
+                )?;
+                if reply_early {
+                    shell_socket.send(execute_reply.clone()).await?;
+                }
+                iopub_socket
+                    .send(reply(
+                        "stream",
+                        json!({"name": "stdout", "text": execute_request.code}),
+                    )?)
+                    .await?;
+                iopub_socket
+                    .send(reply("status", json!({"execution_state": "idle"}))?)
+                    .await?;
+                if !reply_early {
+                    shell_socket.send(execute_reply).await?;
+                }
+            }
+        });
+
+        let (control_requests_tx, control_requests) = mpsc::unbounded();
+        let control_task = cx.background_spawn(async move {
+            loop {
+                let request = control_socket.read().await?;
+                let reply = match &request.content {
+                    JupyterMessageContent::InterruptRequest(_) => {
+                        reply_to(&request, "interrupt_reply", json!({"status": "ok"}))?
+                    }
+                    JupyterMessageContent::ShutdownRequest(shutdown_request) => reply_to(
+                        &request,
+                        "shutdown_reply",
+                        json!({"status": "ok", "restart": shutdown_request.restart}),
+                    )?,
+                    _ => error_reply(&request)?,
+                };
+                control_socket.send(reply).await?;
+                control_requests_tx.unbounded_send(request).ok();
+            }
+        });
+
+        Ok(Self {
+            connection_info,
+            control_requests,
+            _tasks: vec![shell_task, control_task],
+        })
+    }
+
+    /// The next request the kernel answered on its control channel.
+    pub async fn next_control_request(&mut self) -> Option<JupyterMessage> {
+        self.control_requests.next().await
+    }
+
+    pub fn specification(&self) -> KernelSpecification {
+        KernelSpecification::Jupyter(LocalKernelSpecification {
+            name: "fake".into(),
+            path: PathBuf::new(),
+            kernelspec: JupyterKernelspec {
+                argv: vec![],
+                display_name: "Fake".into(),
+                language: "python".into(),
+                interrupt_mode: None,
+                metadata: None,
+                env: None,
+            },
+        })
+    }
+
+    /// Connect `session` to the kernel, as a native kernel does once its
+    /// process has started.
+    pub async fn connect<S: KernelSession + 'static>(
+        &self,
+        session: Entity<S>,
+        cx: &mut VisualTestContext,
+    ) -> Result<Box<dyn RunningKernel>> {
+        let connection_info = self.connection_info.clone();
+        let request_tx = cx
+            .update(|window, cx| {
+                window.spawn(cx, async move |cx| {
+                    connect_to_kernel(&connection_info, session, cx).await
+                })
+            })
+            .await?;
+
+        Ok(Box::new(ConnectedKernel {
+            request_tx,
+            working_directory: PathBuf::from("/"),
+            execution_state: ExecutionState::Idle,
+            kernel_info: None,
+        }))
+    }
+}
+
+fn reply_to(
+    request: &JupyterMessage,
+    msg_type: &str,
+    content: serde_json::Value,
+) -> Result<JupyterMessage> {
+    Ok(JupyterMessage::new(
+        JupyterMessageContent::from_type_and_content(msg_type, content)?,
+        Some(request),
+    ))
+}
+
+/// Reply to any shell request other than an execute request.
+fn shell_reply(request: &JupyterMessage) -> Result<JupyterMessage> {
+    match &request.content {
+        JupyterMessageContent::KernelInfoRequest(_) => reply_to(
+            request,
+            "kernel_info_reply",
+            json!({
+                "status": "ok",
+                "protocol_version": "5.3",
+                "implementation": "fake",
+                "implementation_version": "0.1.0",
+                "language_info": {
+                    "name": "python",
+                    "version": "3",
+                    "mimetype": "text/x-python",
+                    "file_extension": ".py",
+                },
+                "banner": "Fake",
+                "help_links": [],
+                "debugger": false,
+            }),
+        ),
+        JupyterMessageContent::CompleteRequest(complete_request) => reply_to(
+            request,
+            "complete_reply",
+            json!({
+                "status": "ok",
+                "matches": [],
+                "cursor_start": complete_request.cursor_pos,
+                "cursor_end": complete_request.cursor_pos,
+                "metadata": {},
+            }),
+        ),
+        JupyterMessageContent::InspectRequest(_) => reply_to(
+            request,
+            "inspect_reply",
+            json!({"status": "ok", "found": false, "data": {}, "metadata": {}}),
+        ),
+        _ => error_reply(request),
+    }
+}
+
+/// Reply to a request the kernel doesn't handle, so that a client waiting on
+/// the reply isn't left hanging.
+fn error_reply(request: &JupyterMessage) -> Result<JupyterMessage> {
+    let msg_type = &request.header.msg_type;
+    reply_to(
+        request,
+        &msg_type.replace("_request", "_reply"),
+        json!({
+            "status": "error",
+            "ename": "NotImplementedError",
+            "evalue": format!("the fake kernel doesn't handle {msg_type}"),
+            "traceback": [],
+        }),
+    )
+}
+
+#[derive(Debug)]
+struct ConnectedKernel {
+    request_tx: mpsc::Sender<JupyterMessage>,
+    working_directory: PathBuf,
+    execution_state: ExecutionState,
+    kernel_info: Option<KernelInfoReply>,
+}
+
+impl RunningKernel for ConnectedKernel {
+    fn request_tx(&self) -> mpsc::Sender<JupyterMessage> {
+        self.request_tx.clone()
+    }
+
+    fn working_directory(&self) -> &PathBuf {
+        &self.working_directory
+    }
+
+    fn execution_state(&self) -> &ExecutionState {
+        &self.execution_state
+    }
+
+    fn set_execution_state(&mut self, state: ExecutionState) {
+        self.execution_state = state;
+    }
+
+    fn kernel_info(&self) -> Option<&KernelInfoReply> {
+        self.kernel_info.as_ref()
+    }
+
+    fn set_kernel_info(&mut self, info: KernelInfoReply) {
+        self.kernel_info = Some(info);
+    }
+
+    fn force_shutdown(&mut self, _window: &mut Window, _cx: &mut App) -> Task<Result<()>> {
+        self.request_tx.close_channel();
+        Task::ready(Ok(()))
+    }
+}
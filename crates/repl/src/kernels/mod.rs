@@ -1,3 +1,5 @@
+#[cfg(test)]
+pub(crate) mod fake_kernel;
 mod native_kernel;
 use std::{fmt::Debug, future::Future, path::PathBuf};
 
@@ -6,7 +8,7 @@ use futures::{
     future::Shared,
     stream,
 };
-use gpui::{App, Entity, Task, Window};
+use gpui::{App, Context, Entity, Task, Window};
 use language::LanguageName;
 pub use native_kernel::*;
 
@@ -156,6 +158,13 @@ pub fn python_env_kernel_specifications(
     }
 }
 
+/// Whatever a running kernel reports to: the messages it sends and whether
+/// it failed.
+pub trait KernelSession: Sized {
+    fn route(&mut self, message: &JupyterMessage, window: &mut Window, cx: &mut Context<Self>);
+    fn kernel_errored(&mut self, error_message: String, cx: &mut Context<Self>);
+}
+
 pub trait RunningKernel: Send + Debug {
     fn request_tx(&self) -> mpsc::Sender<JupyterMessage>;
     fn working_directory(&self) -> &PathBuf;
@@ -5,7 +5,7 @@ use futures::{
     io::BufReader,
     stream::{FuturesUnordered, SelectAll, StreamExt},
 };
-use gpui::{App, AppContext as _, Entity, EntityId, Task, Window};
+use gpui::{App, AppContext as _, AsyncWindowContext, Entity, EntityId, Task, Window};
 use jupyter_protocol::{
     ExecutionState, JupyterKernelspec, JupyterMessage, JupyterMessageContent, KernelInfoReply,
     connection_info::{ConnectionInfo, Transport},
@@ -22,9 +22,7 @@ use std::{
 };
 use uuid::Uuid;
 
-use crate::Session;
-
-use super::RunningKernel;
+use super::{KernelSession, RunningKernel};
 
 #[derive(Debug, Clone)]
 pub struct LocalKernelSpecification {
@@ -74,7 +72,7 @@ impl LocalKernelSpecification {
 
 // Find a set of open ports. This creates a listener with port set to 0. The listener will be closed at the end when it goes out of scope.
 // There's a race condition between closing the ports and usage by a kernel, but it's inherent to the Jupyter protocol.
-async fn peek_ports(ip: IpAddr) -> Result<[u16; 5]> {
+pub(super) async fn peek_ports(ip: IpAddr) -> Result<[u16; 5]> {
     let mut addr_zeroport: SocketAddr = SocketAddr::new(ip, 0);
     addr_zeroport.set_port(0);
     let mut ports: [u16; 5] = [0; 5];
@@ -105,13 +103,13 @@ impl Debug for NativeRunningKernel {
 }
 
 impl NativeRunningKernel {
-    pub fn new(
+    pub fn new<S: KernelSession + 'static>(
         kernel_specification: LocalKernelSpecification,
         entity_id: EntityId,
         working_directory: PathBuf,
         fs: Arc<dyn Fs>,
         // todo: convert to weak view
-        session: Entity<Session>,
+        session: Entity<S>,
         window: &mut Window,
         cx: &mut App,
     ) -> Task<Result<Box<dyn RunningKernel>>> {
@@ -151,100 +149,7 @@ impl NativeRunningKernel {
                 .spawn()
                 .context("failed to start the kernel process")?;
 
-            let session_id = Uuid::new_v4().to_string();
-
-            let mut iopub_socket =
-                runtimelib::create_client_iopub_connection(&connection_info, "", &session_id)
-                    .await?;
-            let mut shell_socket =
-                runtimelib::create_client_shell_connection(&connection_info, &session_id).await?;
-            let mut control_socket =
-                runtimelib::create_client_control_connection(&connection_info, &session_id).await?;
-
-            let (request_tx, mut request_rx) =
-                futures::channel::mpsc::channel::<JupyterMessage>(100);
-
-            let (mut control_reply_tx, control_reply_rx) = futures::channel::mpsc::channel(100);
-            let (mut shell_reply_tx, shell_reply_rx) = futures::channel::mpsc::channel(100);
-
-            let mut messages_rx = SelectAll::new();
-            messages_rx.push(control_reply_rx);
-            messages_rx.push(shell_reply_rx);
-
-            cx.spawn({
-                let session = session.clone();
-
-                async move |cx| {
-                    while let Some(message) = messages_rx.next().await {
-                        session
-                            .update_in(cx, |session, window, cx| {
-                                session.route(&message, window, cx);
-                            })
-                            .ok();
-                    }
-                }
-            })
-            .detach();
-
-            // iopub task
-            let iopub_task = cx.spawn({
-                let session = session.clone();
-
-                async move |cx| -> anyhow::Result<()> {
-                    loop {
-                        let message = iopub_socket.read().await?;
-                        session
-                            .update_in(cx, |session, window, cx| {
-                                session.route(&message, window, cx);
-                            })
-                            .ok();
-                    }
-                }
-            });
-
-            let (mut control_request_tx, mut control_request_rx) =
-                futures::channel::mpsc::channel(100);
-            let (mut shell_request_tx, mut shell_request_rx) = futures::channel::mpsc::channel(100);
-
-            let routing_task = cx.background_spawn({
-                async move {
-                    while let Some(message) = request_rx.next().await {
-                        match message.content {
-                            JupyterMessageContent::DebugRequest(_)
-                            | JupyterMessageContent::InterruptRequest(_)
-                            | JupyterMessageContent::ShutdownRequest(_) => {
-                                control_request_tx.send(message).await?;
-                            }
-                            _ => {
-                                shell_request_tx.send(message).await?;
-                            }
-                        }
-                    }
-                    anyhow::Ok(())
-                }
-            });
-
-            let shell_task = cx.background_spawn({
-                async move {
-                    while let Some(message) = shell_request_rx.next().await {
-                        shell_socket.send(message).await.ok();
-                        let reply = shell_socket.read().await?;
-                        shell_reply_tx.send(reply).await?;
-                    }
-                    anyhow::Ok(())
-                }
-            });
-
-            let control_task = cx.background_spawn({
-                async move {
-                    while let Some(message) = control_request_rx.next().await {
-                        control_socket.send(message).await.ok();
-                        let reply = control_socket.read().await?;
-                        control_reply_tx.send(reply).await?;
-                    }
-                    anyhow::Ok(())
-                }
-            });
+            let request_tx = connect_to_kernel(&connection_info, session.clone(), cx).await?;
 
             let stderr = process.stderr.take();
 
@@ -274,41 +179,6 @@ impl NativeRunningKernel {
             })
             .detach();
 
-            cx.spawn({
-                let session = session.clone();
-                async move |cx| {
-                    async fn with_name(
-                        name: &'static str,
-                        task: Task<Result<()>>,
-                    ) -> (&'static str, Result<()>) {
-                        (name, task.await)
-                    }
-
-                    let mut tasks = FuturesUnordered::new();
-                    tasks.push(with_name("iopub task", iopub_task));
-                    tasks.push(with_name("shell task", shell_task));
-                    tasks.push(with_name("control task", control_task));
-                    tasks.push(with_name("routing task", routing_task));
-
-                    while let Some((name, result)) = tasks.next().await {
-                        if let Err(err) = result {
-                            log::error!("kernel: handling failed for {name}: {err:?}");
-
-                            session
-                                .update(cx, |session, cx| {
-                                    session.kernel_errored(
-                                        format!("handling failed for {name}: {err}"),
-                                        cx,
-                                    );
-                                    cx.notify();
-                                })
-                                .ok();
-                        }
-                    }
-                }
-            })
-            .detach();
-
             let status = process.status();
 
             let process_status_task = cx.spawn(async move |cx| {
@@ -350,6 +220,142 @@ impl NativeRunningKernel {
     }
 }
 
+/// Connect to the sockets of a kernel that is already running, routing
+/// everything it sends to `session`. Returns the channel requests to the
+/// kernel are sent on.
+pub(crate) async fn connect_to_kernel<S: KernelSession + 'static>(
+    connection_info: &ConnectionInfo,
+    session: Entity<S>,
+    cx: &mut AsyncWindowContext,
+) -> Result<mpsc::Sender<JupyterMessage>> {
+    let session_id = Uuid::new_v4().to_string();
+
+    let mut iopub_socket =
+        runtimelib::create_client_iopub_connection(connection_info, "", &session_id).await?;
+    let mut shell_socket =
+        runtimelib::create_client_shell_connection(connection_info, &session_id).await?;
+    let mut control_socket =
+        runtimelib::create_client_control_connection(connection_info, &session_id).await?;
+
+    let (request_tx, mut request_rx) = futures::channel::mpsc::channel::<JupyterMessage>(100);
+
+    let (mut control_reply_tx, control_reply_rx) = futures::channel::mpsc::channel(100);
+    let (mut shell_reply_tx, shell_reply_rx) = futures::channel::mpsc::channel(100);
+
+    let mut messages_rx = SelectAll::new();
+    messages_rx.push(control_reply_rx);
+    messages_rx.push(shell_reply_rx);
+
+    cx.spawn({
+        let session = session.clone();
+
+        async move |cx| {
+            while let Some(message) = messages_rx.next().await {
+                session
+                    .update_in(cx, |session, window, cx| {
+                        session.route(&message, window, cx);
+                    })
+                    .ok();
+            }
+        }
+    })
+    .detach();
+
+    // iopub task
+    let iopub_task = cx.spawn({
+        let session = session.clone();
+
+        async move |cx| -> anyhow::Result<()> {
+            loop {
+                let message = iopub_socket.read().await?;
+                session
+                    .update_in(cx, |session, window, cx| {
+                        session.route(&message, window, cx);
+                    })
+                    .ok();
+            }
+        }
+    });
+
+    let (mut control_request_tx, mut control_request_rx) = futures::channel::mpsc::channel(100);
+    let (mut shell_request_tx, mut shell_request_rx) = futures::channel::mpsc::channel(100);
+
+    let routing_task = cx.background_spawn({
+        async move {
+            while let Some(message) = request_rx.next().await {
+                match message.content {
+                    JupyterMessageContent::DebugRequest(_)
+                    | JupyterMessageContent::InterruptRequest(_)
+                    | JupyterMessageContent::ShutdownRequest(_) => {
+                        control_request_tx.send(message).await?;
+                    }
+                    _ => {
+                        shell_request_tx.send(message).await?;
+                    }
+                }
+            }
+            anyhow::Ok(())
+        }
+    });
+
+    let shell_task = cx.background_spawn({
+        async move {
+            while let Some(message) = shell_request_rx.next().await {
+                shell_socket.send(message).await.ok();
+                let reply = shell_socket.read().await?;
+                shell_reply_tx.send(reply).await?;
+            }
+            anyhow::Ok(())
+        }
+    });
+
+    let control_task = cx.background_spawn({
+        async move {
+            while let Some(message) = control_request_rx.next().await {
+                control_socket.send(message).await.ok();
+                let reply = control_socket.read().await?;
+                control_reply_tx.send(reply).await?;
+            }
+            anyhow::Ok(())
+        }
+    });
+
+    cx.spawn({
+        let session = session.clone();
+        async move |cx| {
+            async fn with_name(
+                name: &'static str,
+                task: Task<Result<()>>,
+            ) -> (&'static str, Result<()>) {
+                (name, task.await)
+            }
+
+            let mut tasks = FuturesUnordered::new();
+            tasks.push(with_name("iopub task", iopub_task));
+            tasks.push(with_name("shell task", shell_task));
+            tasks.push(with_name("control task", control_task));
+            tasks.push(with_name("routing task", routing_task));
+
+            while let Some((name, result)) = tasks.next().await {
+                if let Err(err) = result {
+                    log::error!("kernel: handling failed for {name}: {err:?}");
+
+                    session
+                        .update(cx, |session, cx| {
+                            session
+                                .kernel_errored(format!("handling failed for {name}: {err}"), cx);
+                            cx.notify();
+                        })
+                        .ok();
+                }
+            }
+        }
+    })
+    .detach();
+
+    Ok(request_tx)
+}
+
 impl RunningKernel for NativeRunningKernel {
     fn request_tx(&self) -> mpsc::Sender<JupyterMessage> {
         self.request_tx.clone()
@@ -1,4 +1,10 @@
 mod cell;
+mod cell_outputs;
+mod document;
+mod notebook_kernel;
 mod notebook_ui;
 pub use cell::*;
+pub use cell_outputs::*;
+pub use document::*;
+pub use notebook_kernel::*;
 pub use notebook_ui::*;
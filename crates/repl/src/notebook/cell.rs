@@ -1,21 +1,23 @@
 #![allow(unused, dead_code)]
 use std::sync::Arc;
 
-use editor::{Editor, EditorMode, MultiBuffer};
+use editor::{Editor, EditorEvent, EditorMode, MultiBuffer, MultiBufferOffset};
 use futures::future::Shared;
 use gpui::{
-    App, Entity, Hsla, RetainAllImageCache, Task, TextStyleRefinement, image_cache, prelude::*,
+    App, ClickEvent, Entity, EventEmitter, Focusable, Hsla, RetainAllImageCache, Subscription,
+    Task, TextStyleRefinement, image_cache, prelude::*,
 };
 use language::{Buffer, Language, LanguageRegistry};
 use markdown_preview::{markdown_parser::parse_markdown, markdown_renderer::render_markdown_block};
 use nbformat::v4::{CellId, CellMetadata, CellType};
+use runtimelib::JupyterMessageContent;
 use settings::Settings as _;
 use theme::ThemeSettings;
 use ui::{IconButtonShape, prelude::*};
 use util::ResultExt;
 
 use crate::{
-    notebook::{CODE_BLOCK_INSET, GUTTER_WIDTH},
+    notebook::{CODE_BLOCK_INSET, CellContent, CellOutputs, GUTTER_WIDTH, OutputChange},
     outputs::{Output, plain::TerminalOutput, user_error::ErrorView},
 };
 
@@ -85,6 +87,25 @@ pub enum Cell {
     Raw(Entity<RawCell>),
 }
 
+fn convert_output(output: &nbformat::v4::Output, window: &mut Window, cx: &mut App) -> Output {
+    match output {
+        nbformat::v4::Output::Stream { text, .. } => Output::Stream {
+            content: cx.new(|cx| TerminalOutput::from(&text.0, window, cx)),
+        },
+        nbformat::v4::Output::DisplayData(display_data) => {
+            Output::new(&display_data.data, None, window, cx)
+        }
+        nbformat::v4::Output::ExecuteResult(execute_result) => {
+            Output::new(&execute_result.data, None, window, cx)
+        }
+        nbformat::v4::Output::Error(error) => Output::ErrorOutput(ErrorView {
+            ename: error.ename.clone(),
+            evalue: error.evalue.clone(),
+            traceback: cx.new(|cx| TerminalOutput::from(&error.traceback.join("\n"), window, cx)),
+        }),
+    }
+}
+
 fn convert_outputs(
     outputs: &Vec<nbformat::v4::Output>,
     window: &mut Window,
@@ -92,23 +113,7 @@ fn convert_outputs(
 ) -> Vec<Output> {
     outputs
         .iter()
-        .map(|output| match output {
-            nbformat::v4::Output::Stream { text, .. } => Output::Stream {
-                content: cx.new(|cx| TerminalOutput::from(&text.0, window, cx)),
-            },
-            nbformat::v4::Output::DisplayData(display_data) => {
-                Output::new(&display_data.data, None, window, cx)
-            }
-            nbformat::v4::Output::ExecuteResult(execute_result) => {
-                Output::new(&execute_result.data, None, window, cx)
-            }
-            nbformat::v4::Output::Error(error) => Output::ErrorOutput(ErrorView {
-                ename: error.ename.clone(),
-                evalue: error.evalue.clone(),
-                traceback: cx
-                    .new(|cx| TerminalOutput::from(&error.traceback.join("\n"), window, cx)),
-            }),
-        })
+        .map(|output| convert_output(output, window, cx))
         .collect()
 }
 
@@ -130,35 +135,21 @@ impl Cell {
                 let source = source.join("");
 
                 let entity = cx.new(|cx| {
-                    let markdown_parsing_task = {
-                        let languages = languages.clone();
-                        let source = source.clone();
-
-                        cx.spawn_in(window, async move |this, cx| {
-                            let parsed_markdown = cx
-                                .background_spawn(async move {
-                                    parse_markdown(&source, None, Some(languages)).await
-                                })
-                                .await;
-
-                            this.update(cx, |cell: &mut MarkdownCell, _| {
-                                cell.parsed_markdown = Some(parsed_markdown);
-                            })
-                            .log_err();
-                        })
-                    };
-
-                    MarkdownCell {
-                        markdown_parsing_task,
+                    let mut cell = MarkdownCell {
+                        markdown_parsing_task: Task::ready(()),
                         image_cache: RetainAllImageCache::new(cx),
                         languages: languages.clone(),
                         id: id.clone(),
                         metadata: metadata.clone(),
                         source: source.clone(),
                         parsed_markdown: None,
+                        editor: None,
+                        editor_subscription: None,
                         selected: false,
                         cell_position: None,
-                    }
+                    };
+                    cell.parse_markdown(window, cx);
+                    cell
                 });
 
                 Cell::Markdown(entity)
@@ -171,41 +162,8 @@ impl Cell {
                 outputs,
             } => Cell::Code(cx.new(|cx| {
                 let text = source.join("");
+                let (buffer, editor_view) = cell_editor(text, window, cx);
 
-                let buffer = cx.new(|cx| Buffer::local(text.clone(), cx));
-                let multi_buffer = cx.new(|cx| MultiBuffer::singleton(buffer.clone(), cx));
-
-                let editor_view = cx.new(|cx| {
-                    let mut editor = Editor::new(
-                        EditorMode::AutoHeight {
-                            min_lines: 1,
-                            max_lines: Some(1024),
-                        },
-                        multi_buffer,
-                        None,
-                        window,
-                        cx,
-                    );
-
-                    let theme = ThemeSettings::get_global(cx);
-
-                    let refinement = TextStyleRefinement {
-                        font_family: Some(theme.buffer_font.family.clone()),
-                        font_size: Some(theme.buffer_font_size(cx).into()),
-                        color: Some(cx.theme().colors().editor_foreground),
-                        background_color: Some(gpui::transparent_black()),
-                        ..Default::default()
-                    };
-
-                    editor.set_text(text, window, cx);
-                    editor.set_show_gutter(false, cx);
-                    editor.set_text_style_refinement(refinement);
-
-                    // editor.set_read_only(true);
-                    editor
-                });
-
-                let buffer = buffer.clone();
                 let language_task = cx.spawn_in(window, async move |this, cx| {
                     let language = notebook_language.await;
 
@@ -221,6 +179,9 @@ impl Cell {
                     source: source.join(""),
                     editor: editor_view,
                     outputs: convert_outputs(outputs, window, cx),
+                    cell_outputs: CellOutputs::new(outputs.clone(), *execution_count),
+                    outputs_changed: false,
+                    running: false,
                     selected: false,
                     language_task,
                     cell_position: None,
@@ -239,6 +200,104 @@ impl Cell {
             })),
         }
     }
+
+    pub fn id(&self, cx: &App) -> CellId {
+        match self {
+            Cell::Code(cell) => cell.read(cx).id().clone(),
+            Cell::Markdown(cell) => cell.read(cx).id().clone(),
+            Cell::Raw(cell) => cell.read(cx).id().clone(),
+        }
+    }
+
+    /// The cell as it would be saved.
+    pub fn content(&self, cx: &App) -> CellContent {
+        match self {
+            Cell::Code(cell) => cell.read(cx).content(cx),
+            Cell::Markdown(cell) => {
+                let cell = cell.read(cx);
+                CellContent {
+                    id: cell.id.clone(),
+                    cell_type: CellType::Markdown,
+                    source: cell.text(cx),
+                    execution_count: None,
+                    outputs: None,
+                }
+            }
+            Cell::Raw(cell) => {
+                let cell = cell.read(cx);
+                CellContent {
+                    id: cell.id.clone(),
+                    cell_type: CellType::Raw,
+                    source: cell.source.clone(),
+                    execution_count: None,
+                    outputs: None,
+                }
+            }
+        }
+    }
+
+    /// The editor of a code cell, or of a markdown cell being edited.
+    pub fn editor(&self, cx: &App) -> Option<Entity<Editor>> {
+        match self {
+            Cell::Code(cell) => Some(cell.read(cx).editor.clone()),
+            Cell::Markdown(cell) => cell.read(cx).editor.clone(),
+            Cell::Raw(_) => None,
+        }
+    }
+}
+
+/// An auto-height editor for the source of a cell.
+fn cell_editor(
+    text: String,
+    window: &mut Window,
+    cx: &mut App,
+) -> (Entity<Buffer>, Entity<Editor>) {
+    let buffer = cx.new(|cx| Buffer::local(text.clone(), cx));
+    let multi_buffer = cx.new(|cx| MultiBuffer::singleton(buffer.clone(), cx));
+
+    let editor = cx.new(|cx| {
+        let mut editor = Editor::new(
+            EditorMode::AutoHeight {
+                min_lines: 1,
+                max_lines: Some(1024),
+            },
+            multi_buffer,
+            None,
+            window,
+            cx,
+        );
+
+        let theme = ThemeSettings::get_global(cx);
+
+        let refinement = TextStyleRefinement {
+            font_family: Some(theme.buffer_font.family.clone()),
+            font_size: Some(theme.buffer_font_size(cx).into()),
+            color: Some(cx.theme().colors().editor_foreground),
+            background_color: Some(gpui::transparent_black()),
+            ..Default::default()
+        };
+
+        editor.set_text(text, window, cx);
+        editor.set_show_gutter(false, cx);
+        editor.set_text_style_refinement(refinement);
+
+        // editor.set_read_only(true);
+        editor
+    });
+
+    (buffer, editor)
+}
+
+/// The offset of the newest cursor in a cell editor.
+pub(crate) fn cursor_offset(editor: &Entity<Editor>, cx: &mut App) -> usize {
+    editor.update(cx, |editor, cx| {
+        let display_snapshot = editor.display_snapshot(cx);
+        editor
+            .selections
+            .newest::<MultiBufferOffset>(&display_snapshot)
+            .head()
+            .0
+    })
 }
 
 pub trait RenderableCell: Render {
@@ -339,11 +398,87 @@ pub struct MarkdownCell {
     source: String,
     parsed_markdown: Option<markdown_preview::markdown_elements::ParsedMarkdown>,
     markdown_parsing_task: Task<()>,
+    /// Set while the source is being edited instead of rendered
+    editor: Option<Entity<Editor>>,
+    editor_subscription: Option<Subscription>,
     selected: bool,
     cell_position: Option<CellPosition>,
     languages: Arc<LanguageRegistry>,
 }
 
+impl MarkdownCell {
+    pub fn is_editing(&self) -> bool {
+        self.editor.is_some()
+    }
+
+    /// The source, including unfinished edits.
+    pub fn text(&self, cx: &App) -> String {
+        match &self.editor {
+            Some(editor) => editor.read(cx).text(cx),
+            None => self.source.clone(),
+        }
+    }
+
+    pub fn start_editing(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if self.editor.is_some() {
+            return;
+        }
+
+        let (buffer, editor) = cell_editor(self.source.clone(), window, cx);
+        let markdown = self.languages.language_for_name("Markdown");
+        cx.spawn_in(window, async move |_, cx| {
+            let language = markdown.await.log_err();
+            buffer
+                .update(cx, |buffer, cx| buffer.set_language(language, cx))
+                .log_err();
+        })
+        .detach();
+
+        window.focus(&editor.focus_handle(cx));
+        self.editor_subscription = Some(cx.subscribe(&editor, |_, _, event, cx| {
+            if let EditorEvent::BufferEdited = event {
+                cx.notify();
+            }
+        }));
+        self.editor = Some(editor);
+        cx.notify();
+    }
+
+    /// Stop editing and render the edited source.
+    pub fn finish_editing(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(editor) = self.editor.take() else {
+            return;
+        };
+        self.editor_subscription = None;
+
+        let source = editor.read(cx).text(cx);
+        if source != self.source {
+            self.source = source;
+            self.parse_markdown(window, cx);
+        }
+        cx.notify();
+    }
+
+    fn parse_markdown(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let languages = self.languages.clone();
+        let source = self.source.clone();
+
+        self.markdown_parsing_task = cx.spawn_in(window, async move |this, cx| {
+            let parsed_markdown = cx
+                .background_spawn(
+                    async move { parse_markdown(&source, None, Some(languages)).await },
+                )
+                .await;
+
+            this.update(cx, |cell: &mut MarkdownCell, cx| {
+                cell.parsed_markdown = Some(parsed_markdown);
+                cx.notify();
+            })
+            .log_err();
+        });
+    }
+}
+
 impl RenderableCell for MarkdownCell {
     const CELL_TYPE: CellType = CellType::Markdown;
 
@@ -388,8 +523,40 @@ impl RenderableCell for MarkdownCell {
 
 impl Render for MarkdownCell {
     fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        if let Some(editor) = self.editor.clone() {
+            return v_flex()
+                .size_full()
+                .children(self.cell_position_spacer(true, window, cx))
+                .child(
+                    h_flex()
+                        .w_full()
+                        .pr_6()
+                        .rounded_xs()
+                        .items_start()
+                        .gap(DynamicSpacing::Base08.rems(cx))
+                        .bg(self.selected_bg_color(window, cx))
+                        .child(self.gutter(window, cx))
+                        .child(
+                            div().py_1p5().w_full().child(
+                                div()
+                                    .flex()
+                                    .size_full()
+                                    .flex_1()
+                                    .py_3()
+                                    .px_5()
+                                    .rounded_lg()
+                                    .border_1()
+                                    .border_color(cx.theme().colors().border)
+                                    .bg(cx.theme().colors().editor_background)
+                                    .child(div().w_full().child(editor)),
+                            ),
+                        ),
+                )
+                .children(self.cell_position_spacer(false, window, cx));
+        }
+
         let Some(parsed) = self.parsed_markdown.as_ref() else {
-            return div();
+            return v_flex();
         };
 
         let mut markdown_render_context =
@@ -410,6 +577,12 @@ impl Render for MarkdownCell {
                     .child(self.gutter(window, cx))
                     .child(
                         v_flex()
+                            .id("markdown-cell")
+                            .on_click(cx.listener(|this, event: &ClickEvent, window, cx| {
+                                if event.click_count() > 1 {
+                                    this.start_editing(window, cx);
+                                }
+                            }))
                             .image_cache(self.image_cache.clone())
                             .size_full()
                             .flex_1()
@@ -428,6 +601,10 @@ impl Render for MarkdownCell {
     }
 }
 
+pub enum CodeCellEvent {
+    Run,
+}
+
 pub struct CodeCell {
     id: CellId,
     metadata: CellMetadata,
@@ -435,6 +612,11 @@ pub struct CodeCell {
     source: String,
     editor: Entity<editor::Editor>,
     outputs: Vec<Output>,
+    /// The outputs as they are saved to the notebook
+    cell_outputs: CellOutputs,
+    outputs_changed: bool,
+    /// Waiting on the kernel to finish running the cell
+    running: bool,
     selected: bool,
     cell_position: Option<CellPosition>,
     language_task: Task<()>,
@@ -449,7 +631,103 @@ impl CodeCell {
     }
 
     pub fn clear_outputs(&mut self) {
+        if !self.cell_outputs.is_empty() || self.execution_count.is_some() {
+            self.outputs_changed = true;
+        }
         self.outputs.clear();
+        self.cell_outputs.clear();
+        self.execution_count = None;
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    /// Clear the outputs of a previous run, ready for new ones from the kernel.
+    pub fn start_execution(&mut self, cx: &mut Context<Self>) {
+        self.clear_outputs();
+        self.running = true;
+        cx.notify();
+    }
+
+    /// The kernel won't send any more outputs for this run.
+    pub fn finish_execution(&mut self, cx: &mut Context<Self>) {
+        self.running = false;
+        cx.notify();
+    }
+
+    /// Stream a message from the kernel into the cell's outputs.
+    pub fn push_message(
+        &mut self,
+        message: &JupyterMessageContent,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(change) = self.cell_outputs.push_message(message).log_err() else {
+            return;
+        };
+
+        match &change {
+            OutputChange::None => {}
+            OutputChange::Cleared => self.outputs.clear(),
+            OutputChange::Pushed(index) => {
+                let output = convert_output(&self.cell_outputs.outputs()[*index], window, cx);
+                self.outputs.truncate(*index);
+                self.outputs.push(output);
+            }
+            OutputChange::Appended { index, text } => {
+                if let Some(Output::Stream { content }) = self.outputs.get(*index) {
+                    content.update(cx, |content, cx| content.append_text(text, cx));
+                }
+            }
+            OutputChange::Updated(indices) => {
+                for index in indices {
+                    let output = convert_output(&self.cell_outputs.outputs()[*index], window, cx);
+                    if let Some(view) = self.outputs.get_mut(*index) {
+                        *view = output;
+                    }
+                }
+            }
+        }
+
+        if change != OutputChange::None
+            || self.execution_count != self.cell_outputs.execution_count()
+        {
+            self.outputs_changed = true;
+        }
+        self.execution_count = self.cell_outputs.execution_count();
+        if let JupyterMessageContent::ExecuteReply(_) = message {
+            self.running = false;
+        }
+        cx.notify();
+    }
+
+    pub fn editor(&self) -> &Entity<Editor> {
+        &self.editor
+    }
+
+    /// The source, including unsaved edits.
+    pub fn text(&self, cx: &App) -> String {
+        self.editor.read(cx).text(cx)
+    }
+
+    /// The cell as it would be saved.
+    pub fn content(&self, cx: &App) -> CellContent {
+        CellContent {
+            id: self.id.clone(),
+            cell_type: CellType::Code,
+            source: self.text(cx),
+            execution_count: self.execution_count,
+            outputs: self
+                .outputs_changed
+                .then(|| self.cell_outputs.outputs().to_vec()),
+        }
+    }
+
+    /// Record that the cell's current content has been written to disk.
+    pub fn mark_saved(&mut self, cx: &App) {
+        self.source = self.text(cx);
+        self.outputs_changed = false;
     }
 
     fn output_control(&self) -> Option<CellControlType> {
@@ -502,6 +780,8 @@ impl CodeCell {
     }
 }
 
+impl EventEmitter<CodeCellEvent> for CodeCell {}
+
 impl RenderableCell for CodeCell {
     const CELL_TYPE: CellType = CellType::Code;
 
@@ -526,8 +806,8 @@ impl RenderableCell for CodeCell {
             CellControl::new("rerun-cell", CellControlType::RerunCell)
         } else {
             CellControl::new("run-cell", CellControlType::RunCell)
-                .on_click(cx.listener(move |this, _, window, cx| this.run(window, cx)))
-        };
+        }
+        .on_click(cx.listener(move |this, _, window, cx| this.run(window, cx)));
 
         Some(cell_control)
     }
@@ -552,8 +832,9 @@ impl RenderableCell for CodeCell {
 }
 
 impl RunnableCell for CodeCell {
-    fn run(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        println!("Running code cell: {}", self.id);
+    /// Ask the notebook to run this cell on its kernel.
+    fn run(&mut self, _window: &mut Window, cx: &mut Context<Self>) {
+        cx.emit(CodeCellEvent::Run);
     }
 
     fn execution_count(&self) -> Option<i32> {
@@ -642,6 +923,18 @@ impl Render for CodeCell {
                                             Output::Table { content, .. } => {
                                                 Some(content.clone().into_any_element())
                                             }
+                                            Output::Svg { content, .. } => {
+                                                Some(content.clone().into_any_element())
+                                            }
+                                            Output::Html { content, .. } => {
+                                                Some(content.clone().into_any_element())
+                                            }
+                                            Output::Latex { content, .. } => {
+                                                Some(content.clone().into_any_element())
+                                            }
+                                            Output::Json { content, .. } => {
+                                                Some(content.clone().into_any_element())
+                                            }
                                             Output::ErrorOutput(error_view) => {
                                                 error_view.render(window, cx)
                                             }
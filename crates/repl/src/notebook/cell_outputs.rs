@@ -0,0 +1,351 @@
+use anyhow::{Context as _, Result};
+use nbformat::v4::Output;
+use runtimelib::JupyterMessageContent;
+use serde::Serialize;
+use serde_json::{Value, json};
+
+/// How a message from the kernel changed the outputs of a cell.
+#[derive(Debug, PartialEq)]
+pub enum OutputChange {
+    /// Nothing visible changed
+    None,
+    /// All outputs were removed
+    Cleared,
+    /// A new output was added at this index, replacing any after it
+    Pushed(usize),
+    /// Text was added to the end of the stream output at this index
+    Appended { index: usize, text: String },
+    /// The outputs at these indices were replaced
+    Updated(Vec<usize>),
+}
+
+/// The outputs of a code cell in the form they are saved to the notebook,
+/// built up from the messages the kernel sends while the cell runs.
+#[derive(Default)]
+pub struct CellOutputs {
+    outputs: Vec<Output>,
+    /// Display ids are transient, so they aren't part of the saved outputs
+    display_ids: Vec<Option<String>>,
+    execution_count: Option<i32>,
+    /// Set by a `clear_output` that waits for the next output
+    clear_pending: bool,
+}
+
+impl CellOutputs {
+    pub fn new(outputs: Vec<Output>, execution_count: Option<i32>) -> Self {
+        Self {
+            display_ids: vec![None; outputs.len()],
+            outputs,
+            execution_count,
+            clear_pending: false,
+        }
+    }
+
+    pub fn outputs(&self) -> &[Output] {
+        &self.outputs
+    }
+
+    pub fn execution_count(&self) -> Option<i32> {
+        self.execution_count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.outputs.is_empty()
+    }
+
+    /// Remove all outputs and the execution count, as happens when a cell is
+    /// run again.
+    pub fn clear(&mut self) {
+        self.outputs.clear();
+        self.display_ids.clear();
+        self.execution_count = None;
+        self.clear_pending = false;
+    }
+
+    /// Apply a message the kernel sent in reply to this cell's execution.
+    pub fn push_message(&mut self, message: &JupyterMessageContent) -> Result<OutputChange> {
+        match message {
+            JupyterMessageContent::ExecuteInput(input) => {
+                self.execution_count = execution_count(&input.execution_count)?;
+                Ok(OutputChange::None)
+            }
+            JupyterMessageContent::ExecuteReply(reply) => {
+                if let Some(count) = execution_count(&reply.execution_count)? {
+                    self.execution_count = Some(count);
+                }
+                Ok(OutputChange::None)
+            }
+            JupyterMessageContent::StreamContent(stream) => {
+                let name = serde_json::to_value(&stream.name)?;
+                if !self.clear_pending
+                    && let Some(index) = self.outputs.len().checked_sub(1)
+                {
+                    let mut last = serde_json::to_value(&self.outputs[index])?;
+                    if last["output_type"] == "stream" && last["name"] == name {
+                        let text = multiline_text(&last["text"]) + &stream.text;
+                        last["text"] = Value::String(text);
+                        self.outputs[index] = output_from_json(last)?;
+                        return Ok(OutputChange::Appended {
+                            index,
+                            text: stream.text.clone(),
+                        });
+                    }
+                }
+
+                self.push(
+                    json!({
+                        "output_type": "stream",
+                        "name": name,
+                        "text": stream.text,
+                    }),
+                    None,
+                )
+            }
+            JupyterMessageContent::ExecuteResult(result) => {
+                let count = execution_count(&result.execution_count)?;
+                if count.is_some() {
+                    self.execution_count = count;
+                }
+                self.push(
+                    json!({
+                        "output_type": "execute_result",
+                        "execution_count": count,
+                        "data": serde_json::to_value(&result.data)?,
+                        "metadata": serde_json::to_value(&result.metadata)?,
+                    }),
+                    result
+                        .transient
+                        .as_ref()
+                        .and_then(|transient| transient.display_id.clone()),
+                )
+            }
+            JupyterMessageContent::DisplayData(display) => self.push(
+                json!({
+                    "output_type": "display_data",
+                    "data": serde_json::to_value(&display.data)?,
+                    "metadata": serde_json::to_value(&display.metadata)?,
+                }),
+                display
+                    .transient
+                    .as_ref()
+                    .and_then(|transient| transient.display_id.clone()),
+            ),
+            JupyterMessageContent::ErrorOutput(error) => self.push(
+                json!({
+                    "output_type": "error",
+                    "ename": error.ename,
+                    "evalue": error.evalue,
+                    "traceback": error.traceback,
+                }),
+                None,
+            ),
+            JupyterMessageContent::ClearOutput(clear) => {
+                if clear.wait {
+                    self.clear_pending = true;
+                    Ok(OutputChange::None)
+                } else {
+                    self.outputs.clear();
+                    self.display_ids.clear();
+                    self.clear_pending = false;
+                    Ok(OutputChange::Cleared)
+                }
+            }
+            JupyterMessageContent::UpdateDisplayData(update) => {
+                let Some(display_id) = update.transient.display_id.as_ref() else {
+                    return Ok(OutputChange::None);
+                };
+                let data = serde_json::to_value(&update.data)?;
+                let metadata = serde_json::to_value(&update.metadata)?;
+
+                let mut updated = Vec::new();
+                for (index, id) in self.display_ids.iter().enumerate() {
+                    if id.as_ref() != Some(display_id) {
+                        continue;
+                    }
+                    let mut output = serde_json::to_value(&self.outputs[index])?;
+                    output["data"] = data.clone();
+                    output["metadata"] = metadata.clone();
+                    self.outputs[index] = output_from_json(output)?;
+                    updated.push(index);
+                }
+
+                Ok(if updated.is_empty() {
+                    OutputChange::None
+                } else {
+                    OutputChange::Updated(updated)
+                })
+            }
+            _ => Ok(OutputChange::None),
+        }
+    }
+
+    fn push(&mut self, output: Value, display_id: Option<String>) -> Result<OutputChange> {
+        let output = output_from_json(output)?;
+        if std::mem::take(&mut self.clear_pending) {
+            self.outputs.clear();
+            self.display_ids.clear();
+        }
+        self.outputs.push(output);
+        self.display_ids.push(display_id);
+        Ok(OutputChange::Pushed(self.outputs.len() - 1))
+    }
+}
+
+fn output_from_json(output: Value) -> Result<Output> {
+    serde_json::from_value(output).context("converting kernel output to nbformat")
+}
+
+fn execution_count(count: &impl Serialize) -> Result<Option<i32>> {
+    Ok(serde_json::to_value(count)?
+        .as_i64()
+        .and_then(|count| i32::try_from(count).ok()))
+}
+
+/// nbformat allows text to be a string or a list of lines.
+fn multiline_text(text: &Value) -> String {
+    match text {
+        Value::String(text) => text.clone(),
+        Value::Array(lines) => lines.iter().filter_map(Value::as_str).collect(),
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(msg_type: &str, content: Value) -> JupyterMessageContent {
+        JupyterMessageContent::from_type_and_content(msg_type, content).unwrap()
+    }
+
+    fn outputs_json(outputs: &CellOutputs) -> Value {
+        serde_json::to_value(outputs.outputs()).unwrap()
+    }
+
+    #[test]
+    fn test_streams_are_merged() {
+        let mut outputs = CellOutputs::default();
+
+        let stdout = |text: &str| message("stream", json!({"name": "stdout", "text": text}));
+        assert_eq!(
+            outputs.push_message(&stdout("a\n")).unwrap(),
+            OutputChange::Pushed(0)
+        );
+        assert_eq!(
+            outputs.push_message(&stdout("b\n")).unwrap(),
+            OutputChange::Appended {
+                index: 0,
+                text: "b\n".into()
+            }
+        );
+        assert_eq!(
+            outputs
+                .push_message(&message(
+                    "stream",
+                    json!({"name": "stderr", "text": "oops\n"})
+                ))
+                .unwrap(),
+            OutputChange::Pushed(1)
+        );
+
+        let json = outputs_json(&outputs);
+        assert_eq!(multiline_text(&json[0]["text"]), "a\nb\n");
+        assert_eq!(json[1]["name"], "stderr");
+    }
+
+    #[test]
+    fn test_execution_results_and_errors() {
+        let mut outputs = CellOutputs::default();
+
+        outputs
+            .push_message(&message(
+                "execute_input",
+                json!({"code": "1 + 1", "execution_count": 3}),
+            ))
+            .unwrap();
+        outputs
+            .push_message(&message(
+                "execute_result",
+                json!({
+                    "execution_count": 3,
+                    "data": {"text/plain": "2"},
+                    "metadata": {},
+                }),
+            ))
+            .unwrap();
+        outputs
+            .push_message(&message(
+                "error",
+                json!({
+                    "ename": "ValueError",
+                    "evalue": "bad",
+                    "traceback": ["line 1", "line 2"],
+                }),
+            ))
+            .unwrap();
+
+        assert_eq!(outputs.execution_count(), Some(3));
+        let json = outputs_json(&outputs);
+        assert_eq!(json[0]["output_type"], "execute_result");
+        assert_eq!(json[0]["execution_count"], 3);
+        assert_eq!(multiline_text(&json[0]["data"]["text/plain"]), "2");
+        assert_eq!(json[1]["output_type"], "error");
+        assert_eq!(json[1]["traceback"], json!(["line 1", "line 2"]));
+    }
+
+    #[test]
+    fn test_clear_output_and_display_updates() {
+        let mut outputs = CellOutputs::default();
+
+        let display = |text: &str| {
+            message(
+                "display_data",
+                json!({
+                    "data": {"text/plain": text},
+                    "metadata": {},
+                    "transient": {"display_id": "progress"},
+                }),
+            )
+        };
+        outputs.push_message(&display("0%")).unwrap();
+        assert_eq!(
+            outputs
+                .push_message(&message(
+                    "update_display_data",
+                    json!({
+                        "data": {"text/plain": "50%"},
+                        "metadata": {},
+                        "transient": {"display_id": "progress"},
+                    }),
+                ))
+                .unwrap(),
+            OutputChange::Updated(vec![0])
+        );
+        assert_eq!(
+            multiline_text(&outputs_json(&outputs)[0]["data"]["text/plain"]),
+            "50%"
+        );
+
+        // Waiting clears only happen once there is something to replace them
+        assert_eq!(
+            outputs
+                .push_message(&message("clear_output", json!({"wait": true})))
+                .unwrap(),
+            OutputChange::None
+        );
+        assert_eq!(outputs.outputs().len(), 1);
+        assert_eq!(
+            outputs.push_message(&display("100%")).unwrap(),
+            OutputChange::Pushed(0)
+        );
+        assert_eq!(outputs.outputs().len(), 1);
+
+        assert_eq!(
+            outputs
+                .push_message(&message("clear_output", json!({"wait": false})))
+                .unwrap(),
+            OutputChange::Cleared
+        );
+        assert!(outputs.is_empty());
+    }
+}
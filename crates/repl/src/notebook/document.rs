@@ -0,0 +1,598 @@
+use std::fmt;
+
+use anyhow::{Context as _, Result};
+use collections::HashMap;
+use nbformat::v4::{Cell, CellId, CellType, Output};
+use serde::Serialize as _;
+use serde::de::{Deserialize, Deserializer, MapAccess, Visitor};
+use serde_json::value::RawValue;
+use serde_json::{Map, Value};
+
+/// The nbformat minor version notebooks are saved as.
+const NBFORMAT_MINOR: u32 = 5;
+
+/// Indentation Jupyter writes notebooks with.
+const DEFAULT_INDENT: usize = 1;
+
+/// A cell as the notebook editor currently has it.
+#[derive(Clone)]
+pub struct CellContent {
+    pub id: CellId,
+    pub cell_type: CellType,
+    pub source: String,
+    /// Only saved for code cells.
+    pub execution_count: Option<i32>,
+    /// `None` if the cell still has the outputs it was loaded with.
+    pub outputs: Option<Vec<Output>>,
+}
+
+impl CellContent {
+    pub fn new(cell_type: CellType, source: impl Into<String>) -> Self {
+        Self {
+            id: new_cell_id(),
+            cell_type,
+            source: source.into(),
+            execution_count: None,
+            outputs: None,
+        }
+    }
+
+    /// The cell as nbformat would have read it from disk.
+    pub fn to_nbformat(&self) -> Result<Cell> {
+        let mut cell = Map::new();
+        cell.insert("cell_type".into(), cell_type_name(&self.cell_type).into());
+        cell.insert("id".into(), serde_json::to_value(&self.id)?);
+        cell.insert("metadata".into(), Value::Object(Map::new()));
+        cell.insert("source".into(), source_lines(&self.source));
+        if let CellType::Code = self.cell_type {
+            cell.insert("execution_count".into(), self.execution_count.into());
+            cell.insert(
+                "outputs".into(),
+                serde_json::to_value(self.outputs.as_deref().unwrap_or_default())?,
+            );
+        }
+        serde_json::from_value(Value::Object(cell)).context("creating notebook cell")
+    }
+}
+
+/// A parsed .ipynb file that keeps the JSON it was read from.
+///
+/// Saving rewrites only the cells that changed. Everything else, including
+/// metadata nbformat doesn't know about, is written back byte for byte, so
+/// saving a notebook doesn't produce spurious diffs.
+pub struct NotebookDocument {
+    notebook: nbformat::v4::Notebook,
+    /// Top-level entries of the file, in their original order
+    entries: Vec<(String, Box<RawValue>)>,
+    /// The original JSON of each cell, in the same order as `notebook.cells`
+    raw_cells: Vec<Box<RawValue>>,
+    cell_indices: HashMap<CellId, usize>,
+    indent: usize,
+    trailing_newline: bool,
+    /// Notebooks older than v4.5 are upgraded, which gives every cell an id,
+    /// so none of their cells can be written back as they were
+    upgraded: bool,
+}
+
+impl NotebookDocument {
+    pub fn parse(text: &str) -> Result<Self> {
+        let (notebook, upgraded) = match nbformat::parse_notebook(text) {
+            Ok(nbformat::Notebook::V4(notebook)) => (notebook, false),
+            // 4.1 - 4.4 are converted to 4.5
+            Ok(nbformat::Notebook::Legacy(legacy_notebook)) => {
+                (nbformat::upgrade_legacy_notebook(legacy_notebook)?, true)
+            }
+            // Bad notebooks and notebooks v4.0 and below are not supported
+            Err(e) => {
+                anyhow::bail!("Failed to parse notebook: {:?}", e);
+            }
+        };
+
+        let RawEntries(entries) =
+            serde_json::from_str(text).context("Failed to parse notebook JSON")?;
+        let raw_cells: Vec<Box<RawValue>> = entries
+            .iter()
+            .find(|(key, _)| key == "cells")
+            .map(|(_, cells)| serde_json::from_str(cells.get()))
+            .transpose()
+            .context("Failed to parse notebook cells")?
+            .unwrap_or_default();
+        let upgraded = upgraded || raw_cells.len() != notebook.cells.len();
+
+        let cell_indices = notebook
+            .cells
+            .iter()
+            .enumerate()
+            .map(|(index, cell)| (cell.id().clone(), index))
+            .collect();
+
+        let indent = text
+            .lines()
+            .nth(1)
+            .map(|line| line.chars().take_while(|c| *c == ' ').count())
+            .filter(|indent| *indent > 0)
+            .unwrap_or(DEFAULT_INDENT);
+
+        Ok(Self {
+            notebook,
+            entries,
+            raw_cells,
+            cell_indices,
+            indent,
+            trailing_newline: text.ends_with('\n'),
+            upgraded,
+        })
+    }
+
+    pub fn notebook(&self) -> &nbformat::v4::Notebook {
+        &self.notebook
+    }
+
+    /// The cells as they were read from disk.
+    pub fn cell_contents(&self) -> Vec<CellContent> {
+        self.notebook
+            .cells
+            .iter()
+            .map(|cell| match cell {
+                Cell::Code {
+                    id,
+                    execution_count,
+                    source,
+                    ..
+                } => CellContent {
+                    id: id.clone(),
+                    cell_type: CellType::Code,
+                    source: source.concat(),
+                    execution_count: *execution_count,
+                    outputs: None,
+                },
+                Cell::Markdown { id, source, .. } => CellContent {
+                    id: id.clone(),
+                    cell_type: CellType::Markdown,
+                    source: source.concat(),
+                    execution_count: None,
+                    outputs: None,
+                },
+                Cell::Raw { id, source, .. } => CellContent {
+                    id: id.clone(),
+                    cell_type: CellType::Raw,
+                    source: source.concat(),
+                    execution_count: None,
+                    outputs: None,
+                },
+            })
+            .collect()
+    }
+
+    /// Whether saving `cells` would change anything the user can see.
+    pub fn has_changes(&self, cells: &[CellContent]) -> bool {
+        cells.len() != self.notebook.cells.len()
+            || cells.iter().enumerate().any(|(index, cell)| {
+                self.cell_indices.get(&cell.id) != Some(&index) || !self.is_unchanged(index, cell)
+            })
+    }
+
+    /// Serialize `cells` as an nbformat v4.5 notebook.
+    pub fn serialize(&self, cells: &[CellContent]) -> Result<String> {
+        let mut cell_texts = Vec::with_capacity(cells.len());
+        for cell in cells {
+            let original_index = self.cell_indices.get(&cell.id).copied();
+            let text = match original_index {
+                Some(index) if !self.upgraded && self.is_unchanged(index, cell) => {
+                    self.raw_cells[index].get().to_string()
+                }
+                _ => self.write_value(&self.cell_json(original_index, cell)?, 2)?,
+            };
+            cell_texts.push(text);
+        }
+
+        let cells_text = if cell_texts.is_empty() {
+            "[]".to_string()
+        } else {
+            let pad = self.pad(2);
+            let cells = cell_texts
+                .iter()
+                .map(|cell| format!("{pad}{cell}"))
+                .collect::<Vec<_>>()
+                .join(",\n");
+            format!("[\n{cells}\n{}]", self.pad(1))
+        };
+
+        let mut entries = self
+            .entries
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.get()))
+            .collect::<Vec<_>>();
+        if !entries.iter().any(|(key, _)| *key == "cells") {
+            entries.insert(0, ("cells", ""));
+        }
+
+        let minor = NBFORMAT_MINOR.to_string();
+        let mut text = String::from("{");
+        for (index, (key, value)) in entries.into_iter().enumerate() {
+            let value = match key {
+                "cells" => cells_text.as_str(),
+                "nbformat_minor" if self.upgraded => minor.as_str(),
+                _ => value,
+            };
+            if index > 0 {
+                text.push(',');
+            }
+            text.push('\n');
+            text.push_str(&self.pad(1));
+            text.push_str(&serde_json::to_string(key)?);
+            text.push_str(": ");
+            text.push_str(value);
+        }
+        text.push_str("\n}");
+        if self.trailing_newline {
+            text.push('\n');
+        }
+        Ok(text)
+    }
+
+    fn is_unchanged(&self, index: usize, cell: &CellContent) -> bool {
+        if cell.outputs.is_some() {
+            return false;
+        }
+        match (&self.notebook.cells[index], &cell.cell_type) {
+            (
+                Cell::Code {
+                    source,
+                    execution_count,
+                    ..
+                },
+                CellType::Code,
+            ) => *execution_count == cell.execution_count && source.concat() == cell.source,
+            (Cell::Markdown { source, .. }, CellType::Markdown)
+            | (Cell::Raw { source, .. }, CellType::Raw) => source.concat() == cell.source,
+            _ => false,
+        }
+    }
+
+    /// The JSON for a changed cell, starting from its original JSON so keys
+    /// nbformat doesn't know about are kept.
+    fn cell_json(&self, original_index: Option<usize>, cell: &CellContent) -> Result<Value> {
+        let original = original_index.and_then(|index| {
+            let raw = self.raw_cells.get(index)?;
+            match serde_json::from_str(raw.get()) {
+                Ok(Value::Object(original)) => Some(original),
+                _ => None,
+            }
+        });
+        let original_source = original_index.map(|index| match &self.notebook.cells[index] {
+            Cell::Code { source, .. }
+            | Cell::Markdown { source, .. }
+            | Cell::Raw { source, .. } => source.concat(),
+        });
+
+        let mut json = original.unwrap_or_default();
+        json.insert("cell_type".into(), cell_type_name(&cell.cell_type).into());
+        json.insert("id".into(), serde_json::to_value(&cell.id)?);
+        json.entry("metadata")
+            .or_insert_with(|| Value::Object(Map::new()));
+        if original_source.as_deref() != Some(cell.source.as_str()) || !json.contains_key("source")
+        {
+            json.insert("source".into(), source_lines(&cell.source));
+        }
+
+        // Each cell type only allows its own keys
+        match cell.cell_type {
+            CellType::Code => {
+                json.remove("attachments");
+                json.insert("execution_count".into(), cell.execution_count.into());
+                if let Some(outputs) = &cell.outputs {
+                    json.insert("outputs".into(), sort_keys(serde_json::to_value(outputs)?));
+                } else {
+                    json.entry("outputs")
+                        .or_insert_with(|| Value::Array(Vec::new()));
+                }
+            }
+            CellType::Markdown | CellType::Raw => {
+                json.remove("execution_count");
+                json.remove("outputs");
+            }
+        }
+
+        let mut entries = json.into_iter().collect::<Vec<_>>();
+        entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+        Ok(Value::Object(entries.into_iter().collect()))
+    }
+
+    /// Pretty-print a value the way the rest of the file is indented, as if
+    /// it were nested `depth` levels deep.
+    fn write_value(&self, value: &Value, depth: usize) -> Result<String> {
+        let indent = " ".repeat(self.indent);
+        let mut buffer = Vec::new();
+        let formatter = serde_json::ser::PrettyFormatter::with_indent(indent.as_bytes());
+        let mut serializer = serde_json::Serializer::with_formatter(&mut buffer, formatter);
+        value.serialize(&mut serializer)?;
+        let text = String::from_utf8(buffer)?;
+
+        let pad = self.pad(depth);
+        Ok(text
+            .lines()
+            .enumerate()
+            .map(|(index, line)| {
+                if index == 0 {
+                    line.to_string()
+                } else {
+                    format!("{pad}{line}")
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n"))
+    }
+
+    fn pad(&self, depth: usize) -> String {
+        " ".repeat(self.indent * depth)
+    }
+}
+
+pub fn new_cell_id() -> CellId {
+    serde_json::from_value(Value::String(uuid::Uuid::new_v4().to_string()))
+        .expect("a UUID is a valid cell id")
+}
+
+fn cell_type_name(cell_type: &CellType) -> &'static str {
+    match cell_type {
+        CellType::Code => "code",
+        CellType::Markdown => "markdown",
+        CellType::Raw => "raw",
+    }
+}
+
+/// Jupyter stores sources as a list of lines, each keeping its newline.
+fn source_lines(source: &str) -> Value {
+    Value::Array(
+        source
+            .split_inclusive('\n')
+            .map(|line| Value::String(line.to_string()))
+            .collect(),
+    )
+}
+
+/// Jupyter writes keys sorted, at every level.
+fn sort_keys(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries = map.into_iter().collect::<Vec<_>>();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key, sort_keys(value)))
+                    .collect(),
+            )
+        }
+        Value::Array(values) => Value::Array(values.into_iter().map(sort_keys).collect()),
+        value => value,
+    }
+}
+
+/// The top-level entries of a JSON object, unparsed and in file order.
+struct RawEntries(Vec<(String, Box<RawValue>)>);
+
+impl<'de> Deserialize<'de> for RawEntries {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct EntriesVisitor;
+
+        impl<'de> Visitor<'de> for EntriesVisitor {
+            type Value = RawEntries;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a JSON object")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<RawEntries, A::Error> {
+                let mut entries = Vec::new();
+                while let Some(entry) = map.next_entry::<String, Box<RawValue>>()? {
+                    entries.push(entry);
+                }
+                Ok(RawEntries(entries))
+            }
+        }
+
+        deserializer.deserialize_map(EntriesVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    // Written by Jupyter: one space of indentation, sorted keys, and metadata
+    // nbformat doesn't model
+    const NOTEBOOK: &str = indoc! {r##"
+        {
+         "cells": [
+          {
+           "cell_type": "markdown",
+           "id": "intro",
+           "metadata": {
+            "slideshow": {
+             "slide_type": "slide"
+            }
+           },
+           "source": [
+            "# Analysis\n",
+            "\n",
+            "Loads the data."
+           ]
+          },
+          {
+           "cell_type": "code",
+           "execution_count": 1,
+           "id": "load",
+           "metadata": {
+            "tags": [
+             "parameters"
+            ]
+           },
+           "outputs": [
+            {
+             "name": "stdout",
+             "output_type": "stream",
+             "text": [
+              "loaded 1e-05 rows\n"
+             ]
+            }
+           ],
+           "source": [
+            "import pandas as pd\n",
+            "df = pd.read_csv(\"data.csv\")"
+           ]
+          },
+          {
+           "cell_type": "raw",
+           "id": "notes",
+           "metadata": {},
+           "source": [
+            "plain text"
+           ]
+          }
+         ],
+         "metadata": {
+          "kernelspec": {
+           "display_name": "Python 3",
+           "language": "python",
+           "name": "python3"
+          },
+          "language_info": {
+           "name": "python",
+           "version": "3.12.1"
+          },
+          "widgets": {
+           "state": {}
+          }
+         },
+         "nbformat": 4,
+         "nbformat_minor": 5
+        }
+    "##};
+
+    fn find<'a>(cells: &'a mut [CellContent], id: &str) -> &'a mut CellContent {
+        cells
+            .iter_mut()
+            .find(|cell| cell.id.to_string() == id)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_round_trip_is_byte_identical() {
+        let document = NotebookDocument::parse(NOTEBOOK).unwrap();
+        let cells = document.cell_contents();
+        assert!(!document.has_changes(&cells));
+        assert_eq!(document.serialize(&cells).unwrap(), NOTEBOOK);
+    }
+
+    #[test]
+    fn test_saving_only_rewrites_changed_cells() {
+        let document = NotebookDocument::parse(NOTEBOOK).unwrap();
+        let mut cells = document.cell_contents();
+        find(&mut cells, "load").source =
+            "import polars as pd\ndf = pd.read_csv(\"data.csv\")".into();
+        assert!(document.has_changes(&cells));
+
+        let saved = document.serialize(&cells).unwrap();
+        let changed_lines = NOTEBOOK
+            .lines()
+            .zip(saved.lines())
+            .filter(|(before, after)| before != after)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            changed_lines,
+            vec![(
+                "    \"import pandas as pd\\n\",",
+                "    \"import polars as pd\\n\","
+            )]
+        );
+        assert_eq!(saved.lines().count(), NOTEBOOK.lines().count());
+
+        let reloaded = NotebookDocument::parse(&saved).unwrap();
+        assert!(!reloaded.has_changes(&cells));
+    }
+
+    #[test]
+    fn test_structural_edits() {
+        let document = NotebookDocument::parse(NOTEBOOK).unwrap();
+        let mut cells = document.cell_contents();
+
+        // Move the raw cell first, make it markdown, split the code cell and
+        // drop the intro
+        let raw = cells.remove(2);
+        cells.insert(
+            0,
+            CellContent {
+                cell_type: CellType::Markdown,
+                ..raw
+            },
+        );
+        let code = find(&mut cells, "load");
+        code.source = "import pandas as pd\n".into();
+        code.outputs = Some(Vec::new());
+        code.execution_count = None;
+        cells.push(CellContent::new(CellType::Code, "df.describe()"));
+        cells.retain(|cell| cell.id.to_string() != "intro");
+
+        let saved = document.serialize(&cells).unwrap();
+        let reloaded = NotebookDocument::parse(&saved).unwrap();
+        let contents = reloaded.cell_contents();
+        assert_eq!(
+            contents
+                .iter()
+                .map(|cell| (cell_type_name(&cell.cell_type), cell.source.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("markdown", "plain text"),
+                ("code", "import pandas as pd\n"),
+                ("code", "df.describe()"),
+            ]
+        );
+        assert_eq!(contents[2].id, cells[2].id);
+        assert!(!reloaded.has_changes(&contents));
+
+        // Unknown notebook metadata survives, cell metadata stays with its cell
+        let json: Value = serde_json::from_str(&saved).unwrap();
+        assert_eq!(
+            json["metadata"]["widgets"]["state"],
+            Value::Object(Map::new())
+        );
+        assert_eq!(json["cells"][1]["metadata"]["tags"][0], "parameters");
+        assert_eq!(json["cells"][1]["outputs"], Value::Array(Vec::new()));
+        assert!(json["cells"][0].get("outputs").is_none());
+        assert_eq!(json["cells"][2]["metadata"], Value::Object(Map::new()));
+    }
+
+    #[test]
+    fn test_legacy_notebooks_are_saved_as_v4_5() {
+        let legacy = indoc! {r#"
+            {
+             "cells": [
+              {
+               "cell_type": "code",
+               "execution_count": null,
+               "metadata": {},
+               "outputs": [],
+               "source": [
+                "print(1)"
+               ]
+              }
+             ],
+             "metadata": {},
+             "nbformat": 4,
+             "nbformat_minor": 4
+            }
+        "#};
+        let document = NotebookDocument::parse(legacy).unwrap();
+        let cells = document.cell_contents();
+        assert!(!document.has_changes(&cells));
+
+        let saved = document.serialize(&cells).unwrap();
+        let json: Value = serde_json::from_str(&saved).unwrap();
+        assert_eq!(json["nbformat_minor"], 5);
+        assert_eq!(json["cells"][0]["id"], cells[0].id.to_string());
+        assert_eq!(json["cells"][0]["source"][0], "print(1)");
+    }
+}
@@ -0,0 +1,622 @@
+use std::{path::PathBuf, sync::Arc, time::Duration};
+
+use anyhow::Result;
+use collections::HashMap;
+use futures::FutureExt as _;
+use gpui::{Context, EventEmitter, Task, Window};
+use nbformat::v4::CellId;
+use project::Fs;
+use runtimelib::{
+    ExecuteRequest, ExecutionState, InterruptRequest, JupyterMessage, JupyterMessageContent,
+    ShutdownRequest,
+};
+use util::ResultExt as _;
+
+use crate::KernelStatus;
+use crate::kernels::{
+    Kernel, KernelSession, KernelSpecification, NativeRunningKernel, RemoteRunningKernel,
+    RunningKernel,
+};
+
+pub enum NotebookKernelEvent {
+    /// The kernel sent a message about a cell it is running.
+    CellMessage {
+        cell_id: CellId,
+        message: JupyterMessage,
+    },
+    /// The kernel updated a display, which may be in any cell.
+    UpdateDisplayData(JupyterMessage),
+    /// Cells that were waiting on the kernel will get no more messages.
+    ExecutionsCancelled(Vec<CellId>),
+    StatusChanged,
+}
+
+/// An execute request the kernel may still send messages about.
+struct Execution {
+    cell_id: CellId,
+    /// The execute reply arrived on the shell channel
+    replied: bool,
+    /// The kernel went idle on the iopub channel, after the last output
+    idle: bool,
+}
+
+/// The kernel a notebook runs its cells on.
+pub struct NotebookKernel {
+    fs: Arc<dyn Fs>,
+    kernel: Kernel,
+    kernel_specification: KernelSpecification,
+    working_directory: PathBuf,
+    /// Outstanding execute requests, by message id
+    executions: HashMap<String, Execution>,
+    /// Bumped on every restart and shutdown, so that work started for an
+    /// earlier kernel doesn't touch the one that replaced it
+    generation: usize,
+}
+
+impl NotebookKernel {
+    pub fn new(
+        kernel_specification: KernelSpecification,
+        working_directory: PathBuf,
+        fs: Arc<dyn Fs>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let mut this = Self {
+            fs,
+            kernel: Kernel::StartingKernel(Task::ready(()).shared()),
+            kernel_specification,
+            working_directory,
+            executions: HashMap::default(),
+            generation: 0,
+        };
+        this.start_kernel(window, cx);
+        this
+    }
+
+    /// A notebook kernel connected to `fake_kernel`.
+    #[cfg(test)]
+    pub(crate) async fn connect_fake(
+        fake_kernel: &crate::kernels::fake_kernel::FakeKernel,
+        cx: &mut gpui::VisualTestContext,
+    ) -> gpui::Entity<Self> {
+        let fs = project::FakeFs::new(cx.executor());
+        let kernel = cx.update(|_, cx| {
+            cx.new(|_| Self {
+                fs,
+                kernel: Kernel::Shutdown,
+                kernel_specification: fake_kernel.specification(),
+                working_directory: PathBuf::from("/"),
+                executions: HashMap::default(),
+                generation: 0,
+            })
+        });
+        let running_kernel = fake_kernel.connect(kernel.clone(), cx).await.unwrap();
+        kernel.update(cx, |kernel, cx| {
+            kernel.set_kernel(Kernel::RunningKernel(running_kernel), cx);
+        });
+        kernel
+    }
+
+    pub fn kernel_specification(&self) -> &KernelSpecification {
+        &self.kernel_specification
+    }
+
+    pub fn status(&self) -> KernelStatus {
+        self.kernel.status()
+    }
+
+    pub fn is_executing(&self, cell_id: &CellId) -> bool {
+        self.executions
+            .values()
+            .any(|execution| execution.cell_id == *cell_id)
+    }
+
+    fn start_kernel(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let entity_id = cx.entity_id();
+        let this = cx.entity();
+
+        let kernel = match self.kernel_specification.clone() {
+            KernelSpecification::Jupyter(kernel_specification)
+            | KernelSpecification::PythonEnv(kernel_specification) => NativeRunningKernel::new(
+                kernel_specification,
+                entity_id,
+                self.working_directory.clone(),
+                self.fs.clone(),
+                this,
+                window,
+                cx,
+            ),
+            KernelSpecification::Remote(remote_kernel_specification) => RemoteRunningKernel::new(
+                remote_kernel_specification,
+                self.working_directory.clone(),
+                this,
+                window,
+                cx,
+            ),
+        };
+
+        self.wait_for_kernel(kernel, window, cx);
+    }
+
+    /// Use the kernel that `kernel` starts, unless the kernel was restarted or
+    /// shut down in the meantime, in which case it is shut down again.
+    fn wait_for_kernel(
+        &mut self,
+        kernel: Task<Result<Box<dyn RunningKernel>>>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let generation = self.generation;
+        let pending_kernel = cx
+            .spawn_in(window, async move |this, cx| {
+                let kernel = kernel.await;
+                this.update_in(cx, |this, window, cx| match kernel {
+                    Ok(mut kernel) if this.generation != generation => {
+                        kernel.force_shutdown(window, cx).detach_and_log_err(cx);
+                    }
+                    Ok(kernel) => this.set_kernel(Kernel::RunningKernel(kernel), cx),
+                    Err(_) if this.generation != generation => {}
+                    Err(err) => this.kernel_errored(err.to_string(), cx),
+                })
+                .ok();
+            })
+            .shared();
+
+        self.set_kernel(Kernel::StartingKernel(pending_kernel), cx);
+    }
+
+    pub(crate) fn set_kernel(&mut self, kernel: Kernel, cx: &mut Context<Self>) {
+        self.kernel = kernel;
+        cx.emit(NotebookKernelEvent::StatusChanged);
+        cx.notify();
+    }
+
+    /// Run `code` for the cell with `cell_id`. Requests sent while the kernel
+    /// is starting are sent once it has started.
+    pub fn execute(&mut self, cell_id: CellId, code: String, cx: &mut Context<Self>) {
+        let message: JupyterMessage = ExecuteRequest {
+            code,
+            ..ExecuteRequest::default()
+        }
+        .into();
+        self.executions.insert(
+            message.header.msg_id.clone(),
+            Execution {
+                cell_id: cell_id.clone(),
+                replied: false,
+                idle: false,
+            },
+        );
+
+        match &self.kernel {
+            Kernel::RunningKernel(_) => self.send(message),
+            Kernel::StartingKernel(task) => {
+                let task = task.clone();
+                cx.spawn(async move |this, cx| {
+                    task.await;
+                    this.update(cx, |this, _| {
+                        // Restarting the kernel cancels the request
+                        if this.executions.contains_key(&message.header.msg_id) {
+                            this.send(message);
+                        }
+                    })
+                    .ok();
+                })
+                .detach();
+            }
+            _ => {
+                self.executions.remove(&message.header.msg_id);
+                cx.emit(NotebookKernelEvent::ExecutionsCancelled(vec![cell_id]));
+            }
+        }
+    }
+
+    fn send(&mut self, message: JupyterMessage) {
+        if let Kernel::RunningKernel(kernel) = &mut self.kernel {
+            kernel.request_tx().try_send(message).log_err();
+        }
+    }
+
+    pub fn interrupt(&mut self, _cx: &mut Context<Self>) {
+        if let Kernel::RunningKernel(_) = &self.kernel {
+            self.send(InterruptRequest {}.into());
+        }
+    }
+
+    /// Drop every outstanding execution, telling the cells that were waiting.
+    fn cancel_executions(&mut self, cx: &mut Context<Self>) {
+        let cell_ids = self
+            .executions
+            .drain()
+            .map(|(_, execution)| execution.cell_id)
+            .collect();
+        cx.emit(NotebookKernelEvent::ExecutionsCancelled(cell_ids));
+    }
+
+    pub fn restart(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let kernel = std::mem::replace(&mut self.kernel, Kernel::Restarting);
+        self.generation += 1;
+        let generation = self.generation;
+        self.cancel_executions(cx);
+
+        match kernel {
+            Kernel::Restarting => {}
+            Kernel::RunningKernel(mut kernel) => {
+                // Forcing the shutdown closes the request channel, so the
+                // request has to be queued first
+                let message: JupyterMessage = ShutdownRequest { restart: true }.into();
+                kernel.request_tx().try_send(message).ok();
+                let forced = kernel.force_shutdown(window, cx);
+
+                cx.spawn_in(window, async move |this, cx| {
+                    // Wait for kernel to shutdown
+                    cx.background_executor().timer(Duration::from_secs(1)).await;
+                    forced.await.log_err();
+
+                    this.update_in(cx, |this, window, cx| {
+                        if this.generation == generation {
+                            this.start_kernel(window, cx);
+                        }
+                    })
+                    .ok();
+                })
+                .detach();
+            }
+            _ => self.start_kernel(window, cx),
+        }
+        cx.emit(NotebookKernelEvent::StatusChanged);
+        cx.notify();
+    }
+
+    pub fn shutdown(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let kernel = std::mem::replace(&mut self.kernel, Kernel::ShuttingDown);
+        self.generation += 1;
+        let generation = self.generation;
+        self.cancel_executions(cx);
+
+        if let Kernel::RunningKernel(mut kernel) = kernel {
+            let message: JupyterMessage = ShutdownRequest { restart: false }.into();
+            kernel.request_tx().try_send(message).ok();
+            let forced = kernel.force_shutdown(window, cx);
+
+            cx.spawn(async move |this, cx| {
+                forced.await.log_err();
+
+                this.update(cx, |this, cx| {
+                    if this.generation == generation {
+                        this.set_kernel(Kernel::Shutdown, cx);
+                    }
+                })
+                .ok();
+            })
+            .detach();
+        } else {
+            self.set_kernel(Kernel::Shutdown, cx);
+        }
+    }
+}
+
+impl KernelSession for NotebookKernel {
+    fn route(&mut self, message: &JupyterMessage, _window: &mut Window, cx: &mut Context<Self>) {
+        match &message.content {
+            JupyterMessageContent::Status(status) => {
+                self.kernel.set_execution_state(&status.execution_state);
+                cx.emit(NotebookKernelEvent::StatusChanged);
+                cx.notify();
+            }
+            JupyterMessageContent::KernelInfoReply(reply) => {
+                self.kernel.set_kernel_info(reply);
+                cx.notify();
+            }
+            JupyterMessageContent::UpdateDisplayData(_) => {
+                cx.emit(NotebookKernelEvent::UpdateDisplayData(message.clone()));
+                return;
+            }
+            _ => {}
+        }
+
+        let Some(parent_message_id) = message.parent_header.as_ref().map(|header| &header.msg_id)
+        else {
+            return;
+        };
+        let Some(execution) = self.executions.get_mut(parent_message_id) else {
+            return;
+        };
+        let cell_id = execution.cell_id.clone();
+
+        // The reply and the outputs come on different channels, so outputs
+        // can still arrive after the reply until the kernel goes idle
+        match &message.content {
+            JupyterMessageContent::ExecuteReply(_) => execution.replied = true,
+            JupyterMessageContent::Status(status)
+                if matches!(status.execution_state, ExecutionState::Idle) =>
+            {
+                execution.idle = true
+            }
+            _ => {}
+        }
+        if execution.replied && execution.idle {
+            self.executions.remove(parent_message_id);
+        }
+
+        cx.emit(NotebookKernelEvent::CellMessage {
+            cell_id,
+            message: message.clone(),
+        });
+    }
+
+    fn kernel_errored(&mut self, error_message: String, cx: &mut Context<Self>) {
+        log::error!("notebook kernel: {error_message}");
+        self.cancel_executions(cx);
+        self.set_kernel(Kernel::ErroredLaunch(error_message), cx);
+    }
+}
+
+impl EventEmitter<NotebookKernelEvent> for NotebookKernel {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernels::fake_kernel::FakeKernel;
+    use futures::{
+        StreamExt as _,
+        channel::{mpsc, oneshot},
+    };
+    use gpui::{Entity, TestAppContext, VisualTestContext};
+    use runtimelib::{CompleteRequest, KernelInfoRequest, Stdio};
+    use std::{cell::RefCell, rc::Rc};
+
+    /// Messages the kernel routed to cells, by cell id, and the cells whose
+    /// executions were cancelled.
+    #[derive(Default)]
+    struct CellEvents {
+        messages: Vec<(String, JupyterMessageContent)>,
+        cancelled: Vec<String>,
+    }
+
+    /// Connect a notebook kernel to `fake_kernel`. The returned receiver
+    /// yields once for every execution the kernel is done with.
+    async fn connect<'a>(
+        fake_kernel: &FakeKernel,
+        cx: &'a mut TestAppContext,
+    ) -> (
+        Entity<NotebookKernel>,
+        Rc<RefCell<CellEvents>>,
+        mpsc::UnboundedReceiver<()>,
+        &'a mut VisualTestContext,
+    ) {
+        let cx = cx.add_empty_window();
+        let kernel = NotebookKernel::connect_fake(fake_kernel, cx).await;
+
+        let events = Rc::new(RefCell::new(CellEvents::default()));
+        let (done_tx, done_rx) = mpsc::unbounded();
+        cx.update(|_, cx| {
+            let events = events.clone();
+            cx.subscribe(&kernel, move |kernel, event, cx| match event {
+                NotebookKernelEvent::CellMessage { cell_id, message } => {
+                    events
+                        .borrow_mut()
+                        .messages
+                        .push((cell_id.to_string(), message.content.clone()));
+                    if !kernel.read(cx).is_executing(cell_id) {
+                        done_tx.unbounded_send(()).ok();
+                    }
+                }
+                NotebookKernelEvent::ExecutionsCancelled(cell_ids) => events
+                    .borrow_mut()
+                    .cancelled
+                    .extend(cell_ids.iter().map(ToString::to_string)),
+                _ => {}
+            })
+            .detach();
+        });
+
+        (kernel, events, done_rx, cx)
+    }
+
+    #[gpui::test]
+    async fn test_cells_run_on_a_kernel(cx: &mut TestAppContext) {
+        cx.executor().allow_parking();
+        let fake_kernel = FakeKernel::start(cx).await.unwrap();
+        let (kernel, events, mut done_rx, cx) = connect(&fake_kernel, cx).await;
+
+        let cell_id = crate::notebook::new_cell_id();
+        kernel.update(cx, |kernel, cx| {
+            kernel.execute(cell_id.clone(), "print('hello')".into(), cx);
+            assert!(kernel.is_executing(&cell_id));
+        });
+
+        done_rx.next().await.unwrap();
+        kernel.read_with(cx, |kernel, _| assert!(!kernel.is_executing(&cell_id)));
+
+        let events = events.borrow();
+        assert!(
+            events
+                .messages
+                .iter()
+                .all(|(id, _)| *id == cell_id.to_string())
+        );
+        let streams = events
+            .messages
+            .iter()
+            .filter_map(|(_, content)| match content {
+                JupyterMessageContent::StreamContent(stream) => Some(stream),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(streams.len(), 1);
+        assert!(matches!(streams[0].name, Stdio::Stdout));
+        assert_eq!(streams[0].text, "print('hello')");
+    }
+
+    #[gpui::test]
+    async fn test_output_after_the_reply_reaches_the_cell(cx: &mut TestAppContext) {
+        cx.executor().allow_parking();
+        let fake_kernel = FakeKernel::start_replying_early(cx).await.unwrap();
+        let (kernel, events, mut done_rx, cx) = connect(&fake_kernel, cx).await;
+
+        let cell_id = crate::notebook::new_cell_id();
+        kernel.update(cx, |kernel, cx| {
+            kernel.execute(cell_id.clone(), "print('late')".into(), cx);
+        });
+
+        done_rx.next().await.unwrap();
+        kernel.read_with(cx, |kernel, _| assert!(!kernel.is_executing(&cell_id)));
+
+        // The output sent after the reply still reaches the cell
+        let events = events.borrow();
+        assert!(
+            events
+                .messages
+                .iter()
+                .all(|(id, _)| *id == cell_id.to_string())
+        );
+        assert!(
+            events
+                .messages
+                .iter()
+                .any(|(_, content)| matches!(content, JupyterMessageContent::ExecuteReply(_)))
+        );
+        assert!(events.messages.iter().any(|(_, content)| matches!(
+            content,
+            JupyterMessageContent::StreamContent(stream) if stream.text == "print('late')"
+        )));
+    }
+
+    #[gpui::test]
+    async fn test_cells_run_in_the_order_they_are_sent(cx: &mut TestAppContext) {
+        cx.executor().allow_parking();
+        let fake_kernel = FakeKernel::start(cx).await.unwrap();
+        let (kernel, events, mut done_rx, cx) = connect(&fake_kernel, cx).await;
+
+        // Running the cells above or below the selection sends each of them
+        // in turn. Requests that aren't executions are answered in between.
+        let cell_ids = (0..3)
+            .map(|_| crate::notebook::new_cell_id())
+            .collect::<Vec<_>>();
+        kernel.update(cx, |kernel, cx| {
+            kernel.send(KernelInfoRequest {}.into());
+            for (ix, cell_id) in cell_ids.iter().enumerate() {
+                kernel.execute(cell_id.clone(), format!("print({ix})"), cx);
+                kernel.send(
+                    CompleteRequest {
+                        code: "pri".into(),
+                        cursor_pos: 3,
+                    }
+                    .into(),
+                );
+            }
+        });
+        for _ in &cell_ids {
+            done_rx.next().await.unwrap();
+        }
+
+        let events = events.borrow();
+        let replies = events
+            .messages
+            .iter()
+            .filter(|(_, content)| matches!(content, JupyterMessageContent::ExecuteReply(_)))
+            .map(|(id, _)| id.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            replies,
+            cell_ids.iter().map(ToString::to_string).collect::<Vec<_>>()
+        );
+        let inputs = events
+            .messages
+            .iter()
+            .filter_map(|(id, content)| match content {
+                JupyterMessageContent::ExecuteInput(input) => {
+                    Some((id.clone(), input.code.clone()))
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            inputs,
+            cell_ids
+                .iter()
+                .enumerate()
+                .map(|(ix, id)| (id.to_string(), format!("print({ix})")))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[gpui::test]
+    async fn test_interrupt_is_sent_on_the_control_channel(cx: &mut TestAppContext) {
+        cx.executor().allow_parking();
+        let mut fake_kernel = FakeKernel::start(cx).await.unwrap();
+        let (kernel, _events, mut done_rx, cx) = connect(&fake_kernel, cx).await;
+
+        let cell_id = crate::notebook::new_cell_id();
+        kernel.update(cx, |kernel, cx| {
+            kernel.execute(cell_id.clone(), "print('hello')".into(), cx);
+            kernel.interrupt(cx);
+        });
+
+        let request = fake_kernel.next_control_request().await.unwrap();
+        assert!(matches!(
+            request.content,
+            JupyterMessageContent::InterruptRequest(_)
+        ));
+
+        // The kernel keeps running after an interrupt
+        done_rx.next().await.unwrap();
+        kernel.read_with(cx, |kernel, _| {
+            assert!(!kernel.is_executing(&cell_id));
+            assert!(kernel.status().is_connected());
+        });
+    }
+
+    #[gpui::test]
+    async fn test_restart_cancels_executions(cx: &mut TestAppContext) {
+        cx.executor().allow_parking();
+        let mut fake_kernel = FakeKernel::start(cx).await.unwrap();
+        let (kernel, events, _done_rx, cx) = connect(&fake_kernel, cx).await;
+
+        let cell_id = crate::notebook::new_cell_id();
+        kernel.update_in(cx, |kernel, window, cx| {
+            kernel.execute(cell_id.clone(), "print('hello')".into(), cx);
+            kernel.restart(window, cx);
+            assert!(!kernel.is_executing(&cell_id));
+            assert!(matches!(kernel.status(), KernelStatus::Restarting));
+        });
+        assert_eq!(events.borrow().cancelled, vec![cell_id.to_string()]);
+
+        let request = fake_kernel.next_control_request().await.unwrap();
+        assert!(matches!(
+            request.content,
+            JupyterMessageContent::ShutdownRequest(ShutdownRequest { restart: true })
+        ));
+    }
+
+    #[gpui::test]
+    async fn test_restart_ignores_the_kernel_it_replaced(cx: &mut TestAppContext) {
+        cx.executor().allow_parking();
+        let fake_kernel = FakeKernel::start(cx).await.unwrap();
+        let (kernel, _events, _done_rx, cx) = connect(&fake_kernel, cx).await;
+
+        // Restarting while the kernel is starting starts a second one, and the
+        // first can finish last.
+        let (stale_tx, stale_rx) = oneshot::channel::<Result<Box<dyn RunningKernel>>>();
+        let (current_tx, current_rx) = oneshot::channel::<Result<Box<dyn RunningKernel>>>();
+        kernel.update_in(cx, |kernel, window, cx| {
+            let stale = cx.background_spawn(async move { stale_rx.await? });
+            kernel.wait_for_kernel(stale, window, cx);
+            kernel.generation += 1;
+            let current = cx.background_spawn(async move { current_rx.await? });
+            kernel.wait_for_kernel(current, window, cx);
+        });
+
+        let running_kernel = fake_kernel.connect(kernel.clone(), cx).await.unwrap();
+        current_tx.send(Ok(running_kernel)).ok();
+        cx.run_until_parked();
+        kernel.read_with(cx, |kernel, _| assert!(kernel.status().is_connected()));
+
+        stale_tx
+            .send(Err(anyhow::anyhow!("the kernel failed to start")))
+            .ok();
+        cx.run_until_parked();
+        kernel.read_with(cx, |kernel, _| assert!(kernel.status().is_connected()));
+    }
+}
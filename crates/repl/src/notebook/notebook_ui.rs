@@ -5,33 +5,52 @@ use std::{path::PathBuf, sync::Arc};
 use anyhow::{Context as _, Result};
 use client::proto::ViewId;
 use collections::HashMap;
+use editor::EditorEvent;
 use feature_flags::{FeatureFlagAppExt as _, NotebookFeatureFlag};
 use futures::FutureExt;
 use futures::future::Shared;
 use gpui::{
     AnyElement, App, Entity, EventEmitter, FocusHandle, Focusable, ListScrollEvent, ListState,
-    Point, Task, actions, list, prelude::*,
+    Point, Subscription, Task, WeakEntity, actions, list, prelude::*,
 };
 use language::{Language, LanguageRegistry};
-use project::{Project, ProjectEntryId, ProjectPath};
+use project::{Fs, PathChange, Project, ProjectEntryId, ProjectPath};
 use ui::{Tooltip, prelude::*};
+use util::ResultExt as _;
 use workspace::item::{ItemEvent, SaveOptions, TabContentParams};
 use workspace::searchable::SearchableItemHandle;
 use workspace::{Item, ItemHandle, Pane, ProjectItem, ToolbarItemLocation};
 use workspace::{ToolbarItemEvent, ToolbarItemView};
 
-use super::{Cell, CellPosition, RenderableCell};
+use super::{
+    Cell, CellContent, CellPosition, CodeCellEvent, NotebookDocument, NotebookKernel,
+    NotebookKernelEvent, RenderableCell, cursor_offset,
+};
+use crate::KernelStatus;
+use crate::components::KernelSelector;
+use crate::kernels::KernelSpecification;
+use crate::repl_store::ReplStore;
 
-use nbformat::v4::CellId;
 use nbformat::v4::Metadata as NotebookMetadata;
+use nbformat::v4::{CellId, CellType};
 
 actions!(
     notebook,
     [
         /// Opens a Jupyter notebook file.
         OpenNotebook,
+        /// Runs the current cell.
+        RunCell,
         /// Runs all cells in the notebook.
         RunAll,
+        /// Runs the cells above the current cell.
+        RunCellsAbove,
+        /// Runs the current cell and the cells below it.
+        RunCellsBelow,
+        /// Interrupts the notebook's kernel.
+        InterruptKernel,
+        /// Restarts the notebook's kernel.
+        RestartKernel,
         /// Clears all cell outputs.
         ClearOutputs,
         /// Moves the current cell up.
@@ -42,6 +61,16 @@ actions!(
         AddMarkdownBlock,
         /// Adds a new code cell.
         AddCodeBlock,
+        /// Deletes the current cell.
+        DeleteCell,
+        /// Splits the current cell at the cursor.
+        SplitCell,
+        /// Changes the current cell into a code cell.
+        ConvertToCodeCell,
+        /// Changes the current cell into a markdown cell.
+        ConvertToMarkdownCell,
+        /// Changes the current cell into a raw cell.
+        ConvertToRawCell,
     ]
 );
 
@@ -77,6 +106,7 @@ pub struct NotebookEditor {
 
     focus_handle: FocusHandle,
     notebook_item: Entity<NotebookItem>,
+    notebook_language: Shared<Task<Option<Arc<Language>>>>,
 
     remote_id: Option<ViewId>,
     cell_list: ListState,
@@ -84,6 +114,13 @@ pub struct NotebookEditor {
     selected_cell_index: usize,
     cell_order: Vec<CellId>,
     cell_map: HashMap<CellId, Cell>,
+    cell_subscriptions: HashMap<CellId, Vec<Subscription>>,
+    /// The file changed on disk while there were unsaved edits
+    has_conflict: bool,
+    /// Started the first time a cell runs
+    kernel: Option<Entity<NotebookKernel>>,
+    _kernel_subscription: Option<Subscription>,
+    _subscriptions: Vec<Subscription>,
 }
 
 impl NotebookEditor {
@@ -96,49 +133,216 @@ impl NotebookEditor {
         let focus_handle = cx.focus_handle();
 
         let languages = project.read(cx).languages().clone();
-        let language_name = notebook_item.read(cx).language_name();
 
         let notebook_language = notebook_item.read(cx).notebook_language();
         let notebook_language = cx
             .spawn_in(window, async move |_, _| notebook_language.await)
             .shared();
 
-        let mut cell_order = vec![]; // Vec<CellId>
-        let mut cell_map = HashMap::default(); // HashMap<CellId, Cell>
+        let cell_list = ListState::new(0, gpui::ListAlignment::Top, px(1000.));
+
+        let worktree_id = notebook_item.read(cx).project_path.worktree_id;
+        ReplStore::global(cx).update(cx, |store, cx| {
+            store
+                .refresh_python_kernelspecs(worktree_id, &project, cx)
+                .detach_and_log_err(cx);
+        });
+
+        let subscriptions = vec![cx.subscribe_in(
+            &notebook_item,
+            window,
+            |this, notebook_item, event, window, cx| match event {
+                NotebookItemEvent::ChangedOnDisk => {
+                    if this.is_dirty(cx) {
+                        this.has_conflict = true;
+                        cx.emit(());
+                    } else {
+                        notebook_item
+                            .update(cx, |item, cx| item.reload(cx))
+                            .detach_and_log_err(cx);
+                    }
+                }
+                NotebookItemEvent::Reloaded => {
+                    this.has_conflict = false;
+                    this.load_cells(window, cx);
+                    cx.emit(());
+                }
+            },
+        )];
+
+        let mut this = Self {
+            project,
+            languages,
+            focus_handle,
+            notebook_item,
+            notebook_language,
+            remote_id: None,
+            cell_list,
+            selected_cell_index: 0,
+            cell_order: Vec::new(),
+            cell_map: HashMap::default(),
+            cell_subscriptions: HashMap::default(),
+            has_conflict: false,
+            kernel: None,
+            _kernel_subscription: None,
+            _subscriptions: subscriptions,
+        };
+        this.load_cells(window, cx);
+        this
+    }
 
-        for (index, cell) in notebook_item
+    /// Replace all cells with the ones in the notebook item.
+    fn load_cells(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let cells = self
+            .notebook_item
             .read(cx)
-            .notebook
-            .clone()
+            .document
+            .notebook()
             .cells
-            .iter()
-            .enumerate()
-        {
-            let cell_id = cell.id();
-            cell_order.push(cell_id.clone());
-            cell_map.insert(
-                cell_id.clone(),
-                Cell::load(cell, &languages, notebook_language.clone(), window, cx),
+            .clone();
+
+        self.cell_order.clear();
+        self.cell_map.clear();
+        self.cell_subscriptions.clear();
+        for cell in &cells {
+            let cell = Cell::load(
+                cell,
+                &self.languages,
+                self.notebook_language.clone(),
+                window,
+                cx,
             );
+            self.insert_cell(self.cell_order.len(), cell, window, cx);
         }
 
-        let notebook_handle = cx.entity().downgrade();
-        let cell_count = cell_order.len();
+        self.cell_list.reset(self.cell_order.len());
+        self.selected_cell_index = self
+            .selected_cell_index
+            .min(self.cell_order.len().saturating_sub(1));
+        cx.notify();
+    }
 
-        let this = cx.entity();
-        let cell_list = ListState::new(cell_count, gpui::ListAlignment::Top, px(1000.));
+    fn insert_cell(
+        &mut self,
+        index: usize,
+        cell: Cell,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let cell_id = cell.id(cx);
+        // Edits change whether the notebook is dirty
+        let subscriptions = match &cell {
+            Cell::Code(code_cell) => {
+                let editor = code_cell.read(cx).editor().clone();
+                vec![
+                    cx.subscribe(&editor, |_, _, event, cx| {
+                        if let EditorEvent::BufferEdited = event {
+                            cx.emit(());
+                        }
+                    }),
+                    cx.subscribe_in(
+                        code_cell,
+                        window,
+                        |this, code_cell, CodeCellEvent::Run, window, cx| {
+                            let cell_id = code_cell.read(cx).id().clone();
+                            if let Some(index) =
+                                this.cell_order.iter().position(|id| *id == cell_id)
+                            {
+                                this.run_cells(index..index + 1, window, cx);
+                            }
+                        },
+                    ),
+                ]
+            }
+            Cell::Markdown(markdown_cell) => {
+                vec![cx.observe(markdown_cell, |_, _, cx| cx.emit(()))]
+            }
+            Cell::Raw(raw_cell) => vec![cx.observe(raw_cell, |_, _, cx| cx.emit(()))],
+        };
 
-        Self {
-            project,
-            languages: languages.clone(),
-            focus_handle,
-            notebook_item,
-            remote_id: None,
-            cell_list,
-            selected_cell_index: 0,
-            cell_order: cell_order.clone(),
-            cell_map: cell_map.clone(),
+        self.cell_order.insert(index, cell_id.clone());
+        self.cell_map.insert(cell_id.clone(), cell);
+        self.cell_subscriptions.insert(cell_id, subscriptions);
+    }
+
+    /// Insert a new cell and show it in the list.
+    fn insert_content(
+        &mut self,
+        index: usize,
+        content: &CellContent,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Option<Cell> {
+        let cell = content.to_nbformat().log_err()?;
+        let cell = Cell::load(
+            &cell,
+            &self.languages,
+            self.notebook_language.clone(),
+            window,
+            cx,
+        );
+        self.insert_cell(index, cell.clone(), window, cx);
+        self.cell_list.splice(index..index, 1);
+        Some(cell)
+    }
+
+    fn remove_cell(&mut self, index: usize) -> Option<Cell> {
+        if index >= self.cell_order.len() {
+            return None;
         }
+        let cell_id = self.cell_order.remove(index);
+        self.cell_subscriptions.remove(&cell_id);
+        self.cell_list.splice(index..index + 1, 0);
+        self.cell_map.remove(&cell_id)
+    }
+
+    fn selected_cell(&self) -> Option<&Cell> {
+        self.cell_order
+            .get(self.selected_cell_index)
+            .and_then(|cell_id| self.cell_map.get(cell_id))
+    }
+
+    /// The cells in order, as they would be saved.
+    fn cell_contents(&self, cx: &App) -> Vec<CellContent> {
+        self.cell_order
+            .iter()
+            .filter_map(|cell_id| self.cell_map.get(cell_id))
+            .map(|cell| cell.content(cx))
+            .collect()
+    }
+
+    /// Write the cells to `path` and mark them as saved.
+    fn save_cells(
+        &mut self,
+        path: PathBuf,
+        project_path: ProjectPath,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<()>> {
+        self.finish_editing_unselected(window, cx);
+        let cells = self.cell_contents(cx);
+        let save = self
+            .notebook_item
+            .update(cx, |item, cx| item.save(cells, path, project_path, cx));
+
+        cx.spawn(async move |this, cx| {
+            save.await?;
+            this.update(cx, |this, cx| {
+                for cell in this.cell_map.values() {
+                    if let Cell::Code(code_cell) = cell {
+                        code_cell.update(cx, |cell, cx| cell.mark_saved(cx));
+                    }
+                }
+                this.has_conflict = false;
+                cx.emit(());
+                cx.notify();
+            })
+        })
+    }
+
+    fn cells_changed(&mut self, cx: &mut Context<Self>) {
+        cx.emit(());
+        cx.notify();
     }
 
     fn has_outputs(&self, window: &mut Window, cx: &mut Context<Self>) -> bool {
@@ -154,15 +358,248 @@ impl NotebookEditor {
     fn clear_outputs(&mut self, window: &mut Window, cx: &mut Context<Self>) {
         for cell in self.cell_map.values() {
             if let Cell::Code(code_cell) = cell {
-                code_cell.update(cx, |cell, _cx| {
+                code_cell.update(cx, |cell, cx| {
                     cell.clear_outputs();
+                    cx.notify();
                 });
             }
         }
+        self.cells_changed(cx);
+    }
+
+    /// The kernel the notebook says it was written for, if it is installed,
+    /// or else the one selected for the worktree.
+    fn kernel_specification(&self, cx: &App) -> Option<KernelSpecification> {
+        let worktree_id = self.notebook_item.read(cx).project_path.worktree_id;
+        let store = ReplStore::global(cx).read(cx);
+        let kernel_name = self
+            .notebook_item
+            .read(cx)
+            .document()
+            .notebook()
+            .metadata
+            .kernelspec
+            .as_ref()
+            .map(|kernelspec| kernelspec.name.clone());
+
+        kernel_name
+            .and_then(|kernel_name| {
+                store
+                    .kernel_specifications_for_worktree(worktree_id)
+                    .find(|spec| spec.name().as_ref() == kernel_name)
+                    .cloned()
+            })
+            .or_else(|| {
+                let language = self.notebook_language.clone().now_or_never().flatten();
+                store.active_kernelspec(worktree_id, language, cx)
+            })
+    }
+
+    fn ensure_kernel(
+        &mut self,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Option<Entity<NotebookKernel>> {
+        if let Some(kernel) = &self.kernel {
+            return Some(kernel.clone());
+        }
+        let Some(kernel_specification) = self.kernel_specification(cx) else {
+            log::warn!("notebook: no kernel available to run cells");
+            return None;
+        };
+        self.start_kernel(kernel_specification, window, cx);
+        self.kernel.clone()
+    }
+
+    /// Start a kernel for the notebook, shutting down the one it had.
+    fn start_kernel(
+        &mut self,
+        kernel_specification: KernelSpecification,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.shutdown_kernel(window, cx);
+
+        let working_directory = self
+            .notebook_item
+            .read(cx)
+            .path
+            .parent()
+            .map(|path| path.to_path_buf())
+            .unwrap_or_default();
+        let fs = ReplStore::global(cx).read(cx).fs().clone();
+        let kernel = cx
+            .new(|cx| NotebookKernel::new(kernel_specification, working_directory, fs, window, cx));
+        self.attach_kernel(kernel, window, cx);
+    }
+
+    /// Run the notebook's cells on `kernel` from now on.
+    fn attach_kernel(
+        &mut self,
+        kernel: Entity<NotebookKernel>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self._kernel_subscription = Some(cx.subscribe_in(&kernel, window, Self::on_kernel_event));
+        self.kernel = Some(kernel);
+        cx.notify();
+    }
+
+    fn shutdown_kernel(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if let Some(kernel) = self.kernel.take() {
+            kernel.update(cx, |kernel, cx| kernel.shutdown(window, cx));
+        }
+        self._kernel_subscription = None;
+        for cell in self.cell_map.values() {
+            if let Cell::Code(code_cell) = cell {
+                code_cell.update(cx, |cell, cx| cell.finish_execution(cx));
+            }
+        }
+    }
+
+    fn on_kernel_event(
+        &mut self,
+        _: &Entity<NotebookKernel>,
+        event: &NotebookKernelEvent,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        match event {
+            NotebookKernelEvent::CellMessage { cell_id, message } => {
+                if let Some(Cell::Code(code_cell)) = self.cell_map.get(cell_id) {
+                    code_cell.update(cx, |cell, cx| {
+                        cell.push_message(&message.content, window, cx)
+                    });
+                    cx.emit(());
+                }
+            }
+            NotebookKernelEvent::UpdateDisplayData(message) => {
+                // A display can be updated from any cell
+                for cell in self.cell_map.values() {
+                    if let Cell::Code(code_cell) = cell {
+                        code_cell.update(cx, |cell, cx| {
+                            cell.push_message(&message.content, window, cx)
+                        });
+                    }
+                }
+                cx.emit(());
+            }
+            NotebookKernelEvent::ExecutionsCancelled(cell_ids) => {
+                for cell_id in cell_ids {
+                    if let Some(Cell::Code(code_cell)) = self.cell_map.get(cell_id) {
+                        code_cell.update(cx, |cell, cx| cell.finish_execution(cx));
+                    }
+                }
+            }
+            NotebookKernelEvent::StatusChanged => cx.notify(),
+        }
+    }
+
+    /// Send the code cells in `range` to the kernel, in order.
+    fn run_cells(
+        &mut self,
+        range: std::ops::Range<usize>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let code_cells = self
+            .cell_order
+            .get(range)
+            .into_iter()
+            .flatten()
+            .filter_map(|cell_id| match self.cell_map.get(cell_id) {
+                Some(Cell::Code(code_cell)) => Some(code_cell.clone()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        if code_cells.is_empty() {
+            return;
+        }
+        let Some(kernel) = self.ensure_kernel(window, cx) else {
+            return;
+        };
+
+        for code_cell in code_cells {
+            let (cell_id, code) = {
+                let cell = code_cell.read(cx);
+                (cell.id().clone(), cell.text(cx))
+            };
+            // Like Jupyter, empty cells are cleared without running
+            code_cell.update(cx, |cell, cx| {
+                cell.start_execution(cx);
+                if code.trim().is_empty() {
+                    cell.finish_execution(cx);
+                }
+            });
+            if !code.trim().is_empty() {
+                kernel.update(cx, |kernel, cx| kernel.execute(cell_id, code, cx));
+            }
+        }
+        self.cells_changed(cx);
+    }
+
+    fn run_selected_cell(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let index = self.selected_cell_index;
+        self.run_cells(index..index + 1, window, cx);
+    }
+
+    fn run_all_cells(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.run_cells(0..self.cell_count(), window, cx);
     }
 
-    fn run_cells(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        println!("Cells would all run here, if that was implemented!");
+    fn run_cells_above(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.run_cells(0..self.selected_cell_index, window, cx);
+    }
+
+    fn run_cells_below(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.run_cells(self.selected_cell_index..self.cell_count(), window, cx);
+    }
+
+    fn interrupt_kernel(&mut self, _window: &mut Window, cx: &mut Context<Self>) {
+        if let Some(kernel) = &self.kernel {
+            kernel.update(cx, |kernel, cx| kernel.interrupt(cx));
+        }
+    }
+
+    fn restart_kernel(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if let Some(kernel) = &self.kernel {
+            kernel.update(cx, |kernel, cx| kernel.restart(window, cx));
+        }
+    }
+
+    fn render_kernel_selector(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let worktree_id = self.notebook_item.read(cx).project_path.worktree_id;
+        let kernel = self.kernel.as_ref().map(|kernel| kernel.read(cx));
+        let (tooltip, icon_color) = match kernel {
+            Some(kernel) => {
+                let status = kernel.status();
+                let color = match status {
+                    KernelStatus::Idle => Color::Success,
+                    KernelStatus::Busy => Color::Accent,
+                    KernelStatus::Error => Color::Error,
+                    _ => Color::Muted,
+                };
+                let name = kernel.kernel_specification().name();
+                (format!("{name}: {}", status.to_string()), color)
+            }
+            None => ("Select Kernel".to_string(), Color::Default),
+        };
+
+        let this = cx.entity().downgrade();
+        KernelSelector::new(
+            Box::new(move |kernel_specification, window, cx| {
+                this.update(cx, |this, cx| {
+                    ReplStore::global(cx).update(cx, |store, cx| {
+                        store.set_active_kernelspec(worktree_id, kernel_specification.clone(), cx)
+                    });
+                    this.start_kernel(kernel_specification, window, cx);
+                })
+                .ok();
+            }),
+            worktree_id,
+            IconButton::new("repl", IconName::ReplNeutral).icon_color(icon_color),
+            Tooltip::text(tooltip),
+        )
     }
 
     fn open_notebook(&mut self, _: &OpenNotebook, _window: &mut Window, _cx: &mut Context<Self>) {
@@ -170,19 +607,128 @@ impl NotebookEditor {
     }
 
     fn move_cell_up(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        println!("Move cell up triggered");
+        let index = self.selected_cell_index;
+        if index == 0 || index >= self.cell_order.len() {
+            return;
+        }
+        self.cell_order.swap(index - 1, index);
+        self.cell_list.splice(index - 1..index + 1, 2);
+        self.set_selected_index(index - 1, true, window, cx);
+        self.cells_changed(cx);
     }
 
     fn move_cell_down(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        println!("Move cell down triggered");
+        let index = self.selected_cell_index;
+        if index + 1 >= self.cell_order.len() {
+            return;
+        }
+        self.cell_order.swap(index, index + 1);
+        self.cell_list.splice(index..index + 2, 2);
+        self.set_selected_index(index + 1, true, window, cx);
+        self.cells_changed(cx);
     }
 
     fn add_markdown_block(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        println!("Add markdown block triggered");
+        self.add_cell(CellType::Markdown, window, cx);
     }
 
     fn add_code_block(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        println!("Add code block triggered");
+        self.add_cell(CellType::Code, window, cx);
+    }
+
+    /// Add an empty cell below the selected one and start editing it.
+    fn add_cell(&mut self, cell_type: CellType, window: &mut Window, cx: &mut Context<Self>) {
+        let index = if self.cell_order.is_empty() {
+            0
+        } else {
+            self.selected_cell_index + 1
+        };
+        let Some(cell) = self.insert_content(index, &CellContent::new(cell_type, ""), window, cx)
+        else {
+            return;
+        };
+
+        self.set_selected_index(index, true, window, cx);
+        match cell {
+            Cell::Code(code_cell) => {
+                let editor = code_cell.read(cx).editor().clone();
+                window.focus(&editor.focus_handle(cx));
+            }
+            Cell::Markdown(markdown_cell) => {
+                markdown_cell.update(cx, |cell, cx| cell.start_editing(window, cx));
+            }
+            Cell::Raw(_) => {}
+        }
+        self.cells_changed(cx);
+    }
+
+    fn delete_cell(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if self.remove_cell(self.selected_cell_index).is_none() {
+            return;
+        }
+        let index = self
+            .selected_cell_index
+            .min(self.cell_order.len().saturating_sub(1));
+        self.set_selected_index(index, true, window, cx);
+        self.cells_changed(cx);
+    }
+
+    /// Split the selected cell at the cursor, moving everything after it into
+    /// a new cell of the same type.
+    fn split_cell(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(cell) = self.selected_cell().cloned() else {
+            return;
+        };
+        let Some(editor) = cell.editor(cx) else {
+            return;
+        };
+
+        let text = editor.read(cx).text(cx);
+        let offset = cursor_offset(&editor, cx).min(text.len());
+        let (before, after) = text.split_at(offset);
+        let before = before.trim_end().to_string();
+        let after = after.trim_start_matches('\n').to_string();
+
+        editor.update(cx, |editor, cx| editor.set_text(before, window, cx));
+        let content = CellContent::new(cell.content(cx).cell_type, after);
+        let index = self.selected_cell_index + 1;
+        if self.insert_content(index, &content, window, cx).is_some() {
+            self.set_selected_index(index, true, window, cx);
+        }
+        self.cells_changed(cx);
+    }
+
+    /// Change the type of the selected cell, keeping its id and source.
+    fn convert_cell(&mut self, cell_type: CellType, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(cell) = self.selected_cell() else {
+            return;
+        };
+        let mut content = cell.content(cx);
+        if content.cell_type == cell_type {
+            return;
+        }
+        content.cell_type = cell_type;
+        content.execution_count = None;
+        content.outputs = None;
+
+        let index = self.selected_cell_index;
+        self.remove_cell(index);
+        self.insert_content(index, &content, window, cx);
+        self.cells_changed(cx);
+    }
+
+    /// Render markdown cells that are no longer selected.
+    fn finish_editing_unselected(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        for (index, cell_id) in self.cell_order.iter().enumerate() {
+            if index == self.selected_cell_index {
+                continue;
+            }
+            if let Some(Cell::Markdown(markdown_cell)) = self.cell_map.get(cell_id)
+                && markdown_cell.read(cx).is_editing()
+            {
+                markdown_cell.update(cx, |cell, cx| cell.finish_editing(window, cx));
+            }
+        }
     }
 
     fn cell_count(&self) -> usize {
@@ -205,6 +751,7 @@ impl NotebookEditor {
         let current_index = self.selected_cell_index;
 
         // in the future we may have some `on_cell_change` event that we want to fire here
+        self.finish_editing_unselected(window, cx);
 
         if jump_to_index {
             self.jump_to_cell(current_index, window, cx);
@@ -423,7 +970,37 @@ impl NotebookEditor {
                     ))
                     .child(
                         Self::button_group(window, cx)
-                            .child(IconButton::new("repl", IconName::ReplNeutral)),
+                            .child(
+                                Self::render_notebook_control(
+                                    "interrupt-kernel",
+                                    IconName::Stop,
+                                    window,
+                                    cx,
+                                )
+                                .disabled(self.kernel.is_none())
+                                .tooltip(move |window, cx| {
+                                    Tooltip::for_action("Interrupt kernel", &InterruptKernel, cx)
+                                })
+                                .on_click(|_, window, cx| {
+                                    window.dispatch_action(Box::new(InterruptKernel), cx);
+                                }),
+                            )
+                            .child(
+                                Self::render_notebook_control(
+                                    "restart-kernel",
+                                    IconName::RotateCw,
+                                    window,
+                                    cx,
+                                )
+                                .disabled(self.kernel.is_none())
+                                .tooltip(move |window, cx| {
+                                    Tooltip::for_action("Restart kernel", &RestartKernel, cx)
+                                })
+                                .on_click(|_, window, cx| {
+                                    window.dispatch_action(Box::new(RestartKernel), cx);
+                                }),
+                            )
+                            .child(self.render_kernel_selector(cx)),
                     ),
             )
     }
@@ -484,7 +1061,20 @@ impl Render for NotebookEditor {
             .on_action(
                 cx.listener(|this, &ClearOutputs, window, cx| this.clear_outputs(window, cx)),
             )
-            .on_action(cx.listener(|this, &RunAll, window, cx| this.run_cells(window, cx)))
+            .on_action(cx.listener(|this, &RunCell, window, cx| this.run_selected_cell(window, cx)))
+            .on_action(cx.listener(|this, &RunAll, window, cx| this.run_all_cells(window, cx)))
+            .on_action(
+                cx.listener(|this, &RunCellsAbove, window, cx| this.run_cells_above(window, cx)),
+            )
+            .on_action(
+                cx.listener(|this, &RunCellsBelow, window, cx| this.run_cells_below(window, cx)),
+            )
+            .on_action(
+                cx.listener(|this, &InterruptKernel, window, cx| this.interrupt_kernel(window, cx)),
+            )
+            .on_action(
+                cx.listener(|this, &RestartKernel, window, cx| this.restart_kernel(window, cx)),
+            )
             .on_action(cx.listener(|this, &MoveCellUp, window, cx| this.move_cell_up(window, cx)))
             .on_action(
                 cx.listener(|this, &MoveCellDown, window, cx| this.move_cell_down(window, cx)),
@@ -495,6 +1085,17 @@ impl Render for NotebookEditor {
             .on_action(
                 cx.listener(|this, &AddCodeBlock, window, cx| this.add_code_block(window, cx)),
             )
+            .on_action(cx.listener(|this, &DeleteCell, window, cx| this.delete_cell(window, cx)))
+            .on_action(cx.listener(|this, &SplitCell, window, cx| this.split_cell(window, cx)))
+            .on_action(cx.listener(|this, &ConvertToCodeCell, window, cx| {
+                this.convert_cell(CellType::Code, window, cx)
+            }))
+            .on_action(cx.listener(|this, &ConvertToMarkdownCell, window, cx| {
+                this.convert_cell(CellType::Markdown, window, cx)
+            }))
+            .on_action(cx.listener(|this, &ConvertToRawCell, window, cx| {
+                this.convert_cell(CellType::Raw, window, cx)
+            }))
             .on_action(cx.listener(Self::select_next))
             .on_action(cx.listener(Self::select_previous))
             .on_action(cx.listener(Self::select_first))
@@ -540,13 +1141,26 @@ impl Focusable for NotebookEditor {
 pub struct NotebookItem {
     path: PathBuf,
     project_path: ProjectPath,
+    project: WeakEntity<Project>,
     languages: Arc<LanguageRegistry>,
-    // Raw notebook data
-    notebook: nbformat::v4::Notebook,
-    // Store our version of the notebook in memory (cell_order, cell_map)
-    id: ProjectEntryId,
+    fs: Arc<dyn Fs>,
+    // Raw notebook data, kept so saving can write back what it doesn't change
+    document: NotebookDocument,
+    /// The file contents as of the last load or save
+    text: String,
+    is_deleted: bool,
+    _subscription: Subscription,
+}
+
+pub enum NotebookItemEvent {
+    /// The file was changed by something other than this notebook.
+    ChangedOnDisk,
+    /// The notebook was read from disk again.
+    Reloaded,
 }
 
+impl EventEmitter<NotebookItemEvent> for NotebookItem {}
+
 impl project::ProjectItem for NotebookItem {
     fn try_open(
         project: &Entity<Project>,
@@ -564,37 +1178,43 @@ impl project::ProjectItem for NotebookItem {
                     .read_with(cx, |project, cx| project.absolute_path(&path, cx))?
                     .with_context(|| format!("finding the absolute path of {path:?}"))?;
 
-                // todo: watch for changes to the file
-                let file_content = fs.load(abs_path.as_path()).await?;
-                let notebook = nbformat::parse_notebook(&file_content);
+                let text = fs.load(abs_path.as_path()).await?;
+                let document = NotebookDocument::parse(&text)?;
 
-                let notebook = match notebook {
-                    Ok(nbformat::Notebook::V4(notebook)) => notebook,
-                    // 4.1 - 4.4 are converted to 4.5
-                    Ok(nbformat::Notebook::Legacy(legacy_notebook)) => {
-                        // TODO: Decide if we want to mutate the notebook by including Cell IDs
-                        // and any other conversions
-
-                        nbformat::upgrade_legacy_notebook(legacy_notebook)?
-                    }
-                    // Bad notebooks and notebooks v4.0 and below are not supported
-                    Err(e) => {
-                        anyhow::bail!("Failed to parse notebook: {:?}", e);
-                    }
-                };
-
-                let id = project
+                project
                     .update(cx, |project, cx| {
                         project.entry_for_path(&path, cx).map(|entry| entry.id)
                     })?
                     .context("Entry not found")?;
 
-                cx.new(|_| NotebookItem {
-                    path: abs_path,
-                    project_path: path,
-                    languages,
-                    notebook,
-                    id,
+                cx.new(|cx| {
+                    let subscription = cx.subscribe(
+                        &project,
+                        |this: &mut Self, _, event: &project::Event, cx| {
+                            if let project::Event::WorktreeUpdatedEntries(worktree_id, entries) =
+                                event
+                                && *worktree_id == this.project_path.worktree_id
+                            {
+                                for (entry_path, _, change) in entries.iter() {
+                                    if *entry_path == this.project_path.path {
+                                        this.file_changed(*change, cx);
+                                    }
+                                }
+                            }
+                        },
+                    );
+
+                    NotebookItem {
+                        path: abs_path,
+                        project_path: path,
+                        project: project.downgrade(),
+                        languages,
+                        fs,
+                        document,
+                        text,
+                        is_deleted: false,
+                        _subscription: subscription,
+                    }
                 })
             }))
         } else {
@@ -602,8 +1222,13 @@ impl project::ProjectItem for NotebookItem {
         }
     }
 
-    fn entry_id(&self, _: &App) -> Option<ProjectEntryId> {
-        Some(self.id)
+    // Looked up each time, since saving as another file changes the entry
+    fn entry_id(&self, cx: &App) -> Option<ProjectEntryId> {
+        self.project
+            .upgrade()?
+            .read(cx)
+            .entry_for_path(&self.project_path, cx)
+            .map(|entry| entry.id)
     }
 
     fn project_path(&self, _: &App) -> Option<ProjectPath> {
@@ -617,14 +1242,12 @@ impl project::ProjectItem for NotebookItem {
 
 impl NotebookItem {
     pub fn language_name(&self) -> Option<String> {
-        self.notebook
-            .metadata
+        let metadata = &self.document.notebook().metadata;
+        metadata
             .language_info
             .as_ref()
             .map(|l| l.name.clone())
-            .or(self
-                .notebook
-                .metadata
+            .or(metadata
                 .kernelspec
                 .as_ref()
                 .and_then(|spec| spec.language.clone()))
@@ -642,6 +1265,73 @@ impl NotebookItem {
             }
         }
     }
+
+    pub fn document(&self) -> &NotebookDocument {
+        &self.document
+    }
+
+    fn file_changed(&mut self, change: PathChange, cx: &mut Context<Self>) {
+        if let PathChange::Removed = change {
+            self.is_deleted = true;
+            return;
+        }
+        self.is_deleted = false;
+
+        // Saving also changes the file, so only report contents we didn't write
+        let fs = self.fs.clone();
+        let path = self.path.clone();
+        cx.spawn(async move |this, cx| {
+            let text = fs.load(&path).await?;
+            this.update(cx, |this, cx| {
+                if text != this.text {
+                    cx.emit(NotebookItemEvent::ChangedOnDisk);
+                }
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
+    /// Read the notebook from disk, discarding the cells it was loaded with.
+    pub fn reload(&mut self, cx: &mut Context<Self>) -> Task<Result<()>> {
+        let fs = self.fs.clone();
+        let path = self.path.clone();
+        cx.spawn(async move |this, cx| {
+            let text = fs.load(&path).await?;
+            let document = NotebookDocument::parse(&text)?;
+            this.update(cx, |this, cx| {
+                this.document = document;
+                this.text = text;
+                this.is_deleted = false;
+                cx.emit(NotebookItemEvent::Reloaded);
+            })
+        })
+    }
+
+    /// Write `cells` to `path`, which becomes the notebook's path.
+    fn save(
+        &mut self,
+        cells: Vec<CellContent>,
+        path: PathBuf,
+        project_path: ProjectPath,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<()>> {
+        let text = match self.document.serialize(&cells) {
+            Ok(text) => text,
+            Err(error) => return Task::ready(Err(error)),
+        };
+        let fs = self.fs.clone();
+        cx.spawn(async move |this, cx| {
+            fs.atomic_write(path.clone(), text.clone()).await?;
+            let document = NotebookDocument::parse(&text)?;
+            this.update(cx, |this, _| {
+                this.document = document;
+                this.text = text;
+                this.path = path;
+                this.project_path = project_path;
+                this.is_deleted = false;
+            })
+        })
+    }
 }
 
 impl EventEmitter<()> for NotebookEditor {}
@@ -732,6 +1422,10 @@ impl Item for NotebookEditor {
             .into_any_element()
     }
 
+    fn to_item_events(_: &Self::Event, mut f: impl FnMut(ItemEvent)) {
+        f(ItemEvent::UpdateTab)
+    }
+
     fn tab_content_text(&self, _detail: usize, cx: &App) -> SharedString {
         let path = &self.notebook_item.read(cx).path;
         let title = path
@@ -769,49 +1463,66 @@ impl Item for NotebookEditor {
         // TODO
     }
 
-    // TODO
     fn can_save(&self, _cx: &App) -> bool {
-        false
+        true
     }
-    // TODO
+
+    fn can_save_as(&self, _cx: &App) -> bool {
+        true
+    }
+
     fn save(
         &mut self,
         _options: SaveOptions,
         _project: Entity<Project>,
-        _window: &mut Window,
-        _cx: &mut Context<Self>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
     ) -> Task<Result<()>> {
-        unimplemented!("save() must be implemented if can_save() returns true")
+        let (path, project_path) = {
+            let item = self.notebook_item.read(cx);
+            (item.path.clone(), item.project_path.clone())
+        };
+        self.save_cells(path, project_path, window, cx)
     }
 
-    // TODO
     fn save_as(
         &mut self,
-        _project: Entity<Project>,
-        _path: ProjectPath,
-        _window: &mut Window,
-        _cx: &mut Context<Self>,
+        project: Entity<Project>,
+        path: ProjectPath,
+        window: &mut Window,
+        cx: &mut Context<Self>,
     ) -> Task<Result<()>> {
-        unimplemented!("save_as() must be implemented if can_save() returns true")
+        let Some(abs_path) = project.read(cx).absolute_path(&path, cx) else {
+            return Task::ready(Err(anyhow::anyhow!(
+                "failed to find the absolute path of {path:?}"
+            )));
+        };
+        self.save_cells(abs_path, path, window, cx)
     }
-    // TODO
+
     fn reload(
         &mut self,
         _project: Entity<Project>,
         _window: &mut Window,
-        _cx: &mut Context<Self>,
+        cx: &mut Context<Self>,
     ) -> Task<Result<()>> {
-        unimplemented!("reload() must be implemented if can_save() returns true")
+        // Cells are reloaded when the item reports it was reloaded
+        self.notebook_item.update(cx, |item, cx| item.reload(cx))
     }
 
     fn is_dirty(&self, cx: &App) -> bool {
-        self.cell_map.values().any(|cell| {
-            if let Cell::Code(code_cell) = cell {
-                code_cell.read(cx).is_dirty(cx)
-            } else {
-                false
-            }
-        })
+        self.notebook_item
+            .read(cx)
+            .document
+            .has_changes(&self.cell_contents(cx))
+    }
+
+    fn has_conflict(&self, _cx: &App) -> bool {
+        self.has_conflict
+    }
+
+    fn has_deleted_file(&self, cx: &App) -> bool {
+        self.notebook_item.read(cx).is_deleted
     }
 }
 
@@ -834,3 +1545,264 @@ impl ProjectItem for NotebookEditor {
         Self::new(project, item, window, cx)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernels::fake_kernel::FakeKernel;
+    use futures::{StreamExt as _, channel::mpsc};
+    use gpui::{TestAppContext, VisualTestContext};
+    use indoc::indoc;
+    use project::FakeFs;
+    use runtimelib::JupyterMessageContent;
+    use serde_json::json;
+    use settings::SettingsStore;
+    use util::{path, rel_path::rel_path};
+
+    const NOTEBOOK: &str = indoc! {r#"
+        {
+         "cells": [
+          {
+           "cell_type": "code",
+           "execution_count": null,
+           "id": "greet",
+           "metadata": {},
+           "outputs": [],
+           "source": [
+            "print('hello')"
+           ]
+          }
+         ],
+         "metadata": {},
+         "nbformat": 4,
+         "nbformat_minor": 5
+        }
+    "#};
+
+    fn init_test(cx: &mut TestAppContext) -> Arc<FakeFs> {
+        let fs = FakeFs::new(cx.executor());
+        cx.update(|cx| {
+            let store = SettingsStore::test(cx);
+            cx.set_global(store);
+            theme::init(theme::LoadThemes::JustBase, cx);
+            editor::init(cx);
+            command_palette_hooks::init(cx);
+            ReplStore::init_test(fs.clone(), cx);
+        });
+        fs
+    }
+
+    /// Open `/project/notebook.ipynb` in a notebook editor.
+    async fn open_notebook<'a>(
+        fs: &Arc<FakeFs>,
+        cx: &'a mut TestAppContext,
+    ) -> (Entity<NotebookEditor>, &'a mut VisualTestContext) {
+        let project = Project::test(fs.clone(), [path!("/project").as_ref()], cx).await;
+        let project_path = project
+            .read_with(cx, |project, cx| {
+                project.find_project_path(path!("/project/notebook.ipynb"), cx)
+            })
+            .unwrap();
+        let notebook_item = cx
+            .update(|cx| {
+                <NotebookItem as project::ProjectItem>::try_open(&project, &project_path, cx)
+            })
+            .unwrap()
+            .await
+            .unwrap();
+
+        let cx = cx.add_empty_window();
+        let editor = cx.new_window_entity(|window, cx| {
+            NotebookEditor::new(project, notebook_item, window, cx)
+        });
+        (editor, cx)
+    }
+
+    fn cell_sources(editor: &Entity<NotebookEditor>, cx: &mut VisualTestContext) -> Vec<String> {
+        editor.read_with(cx, |editor, cx| {
+            editor
+                .cell_contents(cx)
+                .into_iter()
+                .map(|cell| cell.source)
+                .collect()
+        })
+    }
+
+    fn edit_first_cell(editor: &Entity<NotebookEditor>, text: &str, cx: &mut VisualTestContext) {
+        editor.update_in(cx, |editor, window, cx| {
+            let Some(Cell::Code(code_cell)) = editor.cell_map.get(&editor.cell_order[0]) else {
+                panic!("expected a code cell");
+            };
+            let cell_editor = code_cell.read(cx).editor().clone();
+            cell_editor.update(cx, |cell_editor, cx| cell_editor.set_text(text, window, cx));
+        });
+    }
+
+    async fn write_notebook(fs: &FakeFs, path: &str, source: &str) {
+        let text = NOTEBOOK.replace("print('hello')", source);
+        fs.insert_file(path, text.into_bytes()).await;
+    }
+
+    #[gpui::test]
+    async fn test_changes_on_disk_reload_a_clean_notebook(cx: &mut TestAppContext) {
+        let fs = init_test(cx);
+        fs.insert_tree(path!("/project"), json!({ "notebook.ipynb": NOTEBOOK }))
+            .await;
+        let (editor, cx) = open_notebook(&fs, cx).await;
+        assert_eq!(cell_sources(&editor, cx), ["print('hello')"]);
+
+        write_notebook(&fs, path!("/project/notebook.ipynb"), "print('bye')").await;
+        cx.run_until_parked();
+        assert_eq!(cell_sources(&editor, cx), ["print('bye')"]);
+        editor.read_with(cx, |editor, cx| {
+            assert!(!editor.has_conflict(cx));
+            assert!(!editor.is_dirty(cx));
+        });
+    }
+
+    #[gpui::test]
+    async fn test_changes_on_disk_conflict_with_unsaved_edits(cx: &mut TestAppContext) {
+        let fs = init_test(cx);
+        fs.insert_tree(path!("/project"), json!({ "notebook.ipynb": NOTEBOOK }))
+            .await;
+        let (editor, cx) = open_notebook(&fs, cx).await;
+
+        edit_first_cell(&editor, "print('edited')", cx);
+        write_notebook(&fs, path!("/project/notebook.ipynb"), "print('bye')").await;
+        cx.run_until_parked();
+
+        // The edits are kept until the notebook is reloaded
+        assert_eq!(cell_sources(&editor, cx), ["print('edited')"]);
+        editor.read_with(cx, |editor, cx| {
+            assert!(editor.has_conflict(cx));
+            assert!(editor.is_dirty(cx));
+        });
+
+        let project = editor.read_with(cx, |editor, _| editor.project.clone());
+        editor
+            .update_in(cx, |editor, window, cx| {
+                Item::reload(editor, project, window, cx)
+            })
+            .await
+            .unwrap();
+        cx.run_until_parked();
+        assert_eq!(cell_sources(&editor, cx), ["print('bye')"]);
+        editor.read_with(cx, |editor, cx| {
+            assert!(!editor.has_conflict(cx));
+            assert!(!editor.is_dirty(cx));
+        });
+    }
+
+    #[gpui::test]
+    async fn test_save_as_moves_the_notebook_to_the_new_file(cx: &mut TestAppContext) {
+        let fs = init_test(cx);
+        fs.insert_tree(path!("/project"), json!({ "notebook.ipynb": NOTEBOOK }))
+            .await;
+        let (editor, cx) = open_notebook(&fs, cx).await;
+        let project = editor.read_with(cx, |editor, _| editor.project.clone());
+        let (worktree_id, original_entry) = editor.read_with(cx, |editor, cx| {
+            let item = editor.notebook_item.read(cx);
+            (
+                item.project_path.worktree_id,
+                project::ProjectItem::entry_id(item, cx),
+            )
+        });
+        assert!(original_entry.is_some());
+
+        edit_first_cell(&editor, "print('copied')", cx);
+        let copy_path = ProjectPath {
+            worktree_id,
+            path: rel_path("copy.ipynb").into(),
+        };
+        editor
+            .update_in(cx, |editor, window, cx| {
+                Item::save_as(editor, project.clone(), copy_path.clone(), window, cx)
+            })
+            .await
+            .unwrap();
+        cx.run_until_parked();
+
+        let copy_entry = project.read_with(cx, |project, cx| {
+            project.entry_for_path(&copy_path, cx).map(|entry| entry.id)
+        });
+        assert!(copy_entry.is_some());
+        assert_ne!(copy_entry, original_entry);
+        editor.read_with(cx, |editor, cx| {
+            let item = editor.notebook_item.read(cx);
+            assert_eq!(item.project_path, copy_path);
+            assert_eq!(project::ProjectItem::entry_id(item, cx), copy_entry);
+            assert!(!editor.is_dirty(cx));
+        });
+
+        // Changes to the original no longer reach the notebook, but changes
+        // to the copy do
+        write_notebook(&fs, path!("/project/notebook.ipynb"), "print('bye')").await;
+        cx.run_until_parked();
+        assert_eq!(cell_sources(&editor, cx), ["print('copied')"]);
+
+        write_notebook(&fs, path!("/project/copy.ipynb"), "print('changed')").await;
+        cx.run_until_parked();
+        assert_eq!(cell_sources(&editor, cx), ["print('changed')"]);
+        editor.read_with(cx, |editor, cx| assert!(!editor.has_conflict(cx)));
+    }
+
+    #[gpui::test]
+    async fn test_saving_writes_the_outputs_of_run_cells(cx: &mut TestAppContext) {
+        cx.executor().allow_parking();
+        let fs = init_test(cx);
+        fs.insert_tree(path!("/project"), json!({ "notebook.ipynb": NOTEBOOK }))
+            .await;
+        let fake_kernel = FakeKernel::start(cx).await.unwrap();
+        let (editor, cx) = open_notebook(&fs, cx).await;
+
+        let kernel = NotebookKernel::connect_fake(&fake_kernel, cx).await;
+        let (message_tx, mut message_rx) = mpsc::unbounded();
+        cx.update(|_, cx| {
+            cx.subscribe(&kernel, move |_, event, _| {
+                if let NotebookKernelEvent::CellMessage { message, .. } = event {
+                    message_tx.unbounded_send(message.content.clone()).ok();
+                }
+            })
+            .detach();
+        });
+        editor.update_in(cx, |editor, window, cx| {
+            editor.attach_kernel(kernel.clone(), window, cx);
+            editor.run_all_cells(window, cx);
+        });
+
+        // The reply and the output arrive on different channels
+        let (mut replied, mut printed) = (false, false);
+        while !(replied && printed) {
+            match message_rx.next().await.unwrap() {
+                JupyterMessageContent::ExecuteReply(_) => replied = true,
+                JupyterMessageContent::StreamContent(_) => printed = true,
+                _ => {}
+            }
+        }
+        editor.read_with(cx, |editor, cx| assert!(editor.is_dirty(cx)));
+
+        let project = editor.read_with(cx, |editor, _| editor.project.clone());
+        editor
+            .update_in(cx, |editor, window, cx| {
+                Item::save(editor, SaveOptions::default(), project, window, cx)
+            })
+            .await
+            .unwrap();
+        editor.read_with(cx, |editor, cx| assert!(!editor.is_dirty(cx)));
+
+        let saved = fs
+            .load(path!("/project/notebook.ipynb").as_ref())
+            .await
+            .unwrap();
+        let saved: serde_json::Value = serde_json::from_str(&saved).unwrap();
+        assert_eq!(saved["cells"][0]["execution_count"], json!(1));
+        assert_eq!(
+            saved["cells"][0]["outputs"],
+            json!([{
+                "name": "stdout",
+                "output_type": "stream",
+                "text": ["print('hello')"],
+            }])
+        );
+    }
+}
@@ -14,8 +14,11 @@
 //! The module supports several output types, including:
 //! - Plain text
 //! - Markdown
-//! - Images (PNG and JPEG)
+//! - Images (PNG, JPEG and SVG)
 //! - Tables
+//! - HTML, as far as it can be expressed in Markdown
+//! - LaTeX math
+//! - JSON, as a collapsible tree
 //! - Error messages
 //!
 //! ## Clipboard Support
@@ -42,12 +45,24 @@ use ui::{
     Window, div, h_flex, prelude::*, v_flex,
 };
 
+mod html;
+use html::HtmlView;
+
 mod image;
 use image::ImageView;
 
+mod json;
+use json::JsonView;
+
+mod latex;
+use latex::LatexView;
+
 mod markdown;
 use markdown::MarkdownView;
 
+mod svg;
+use svg::SvgView;
+
 mod table;
 use table::TableView;
 
@@ -61,16 +76,62 @@ use workspace::Workspace;
 /// When deciding what to render from a collection of mediatypes, we need to rank them in order of importance
 fn rank_mime_type(mimetype: &MimeType) -> usize {
     match mimetype {
-        MimeType::DataTable(_) => 6,
-        MimeType::Png(_) => 4,
-        MimeType::Jpeg(_) => 3,
-        MimeType::Markdown(_) => 2,
+        MimeType::DataTable(_) => 9,
+        MimeType::Svg(_) => 8,
+        MimeType::Png(_) => 7,
+        MimeType::Jpeg(_) => 6,
+        MimeType::Markdown(_) => 5,
+        MimeType::Html(_) => 4,
+        MimeType::Latex(_) => 3,
+        MimeType::Json(_) => 2,
         MimeType::Plain(_) => 1,
         // All other media types are not supported in Zed at this time
         _ => 0,
     }
 }
 
+fn rank_mime_type_without_html(mimetype: &MimeType) -> usize {
+    match mimetype {
+        MimeType::Html(_) => 0,
+        _ => rank_mime_type(mimetype),
+    }
+}
+
+/// The media type picked by [`richest_mime_type`]
+#[derive(Debug)]
+enum RichestOutput<'a> {
+    /// `text/html`, along with the Markdown it converts to
+    Html {
+        html: &'a str,
+        markdown: anyhow::Result<String>,
+    },
+    Other(&'a MimeType),
+}
+
+/// Picks the media type to render from `data`. HTML that has nothing left
+/// once converted to Markdown, such as a widget's `<script>` tag, gives way
+/// to the next richest type, usually `text/plain`.
+fn richest_mime_type(data: &MimeBundle) -> Option<RichestOutput<'_>> {
+    match data.richest(rank_mime_type)? {
+        MimeType::Html(html) => {
+            let markdown = html::html_to_markdown(html);
+            if markdown
+                .as_ref()
+                .is_ok_and(|markdown| markdown.trim().is_empty())
+            {
+                if let Some(fallback) = data
+                    .richest(rank_mime_type_without_html)
+                    .filter(|mime_type| rank_mime_type_without_html(mime_type) > 0)
+                {
+                    return Some(RichestOutput::Other(fallback));
+                }
+            }
+            Some(RichestOutput::Html { html, markdown })
+        }
+        mime_type => Some(RichestOutput::Other(mime_type)),
+    }
+}
+
 pub(crate) trait OutputContent {
     fn clipboard_content(&self, window: &Window, cx: &App) -> Option<ClipboardItem>;
     fn has_clipboard_content(&self, _window: &Window, _cx: &App) -> bool {
@@ -124,6 +185,22 @@ pub enum Output {
         content: Entity<MarkdownView>,
         display_id: Option<String>,
     },
+    Svg {
+        content: Entity<SvgView>,
+        display_id: Option<String>,
+    },
+    Html {
+        content: Entity<HtmlView>,
+        display_id: Option<String>,
+    },
+    Latex {
+        content: Entity<LatexView>,
+        display_id: Option<String>,
+    },
+    Json {
+        content: Entity<JsonView>,
+        display_id: Option<String>,
+    },
     ClearOutputWaitMarker,
 }
 
@@ -213,6 +290,10 @@ impl Output {
             Self::Image { content, .. } => Some(content.clone().into_any_element()),
             Self::Message(message) => Some(div().child(message.clone()).into_any_element()),
             Self::Table { content, .. } => Some(content.clone().into_any_element()),
+            Self::Svg { content, .. } => Some(content.clone().into_any_element()),
+            Self::Html { content, .. } => Some(content.clone().into_any_element()),
+            Self::Latex { content, .. } => Some(content.clone().into_any_element()),
+            Self::Json { content, .. } => Some(content.clone().into_any_element()),
             Self::ErrorOutput(error_view) => error_view.render(window, cx),
             Self::ClearOutputWaitMarker => None,
         };
@@ -323,6 +404,18 @@ impl Output {
                 Self::Table { content, .. } => {
                     Self::render_output_controls(content.clone(), workspace, window, cx)
                 }
+                Self::Svg { content, .. } => {
+                    Self::render_output_controls(content.clone(), workspace, window, cx)
+                }
+                Self::Html { content, .. } => {
+                    Self::render_output_controls(content.clone(), workspace, window, cx)
+                }
+                Self::Latex { content, .. } => {
+                    Self::render_output_controls(content.clone(), workspace, window, cx)
+                }
+                Self::Json { content, .. } => {
+                    Self::render_output_controls(content.clone(), workspace, window, cx)
+                }
                 Self::ClearOutputWaitMarker => None,
             })
     }
@@ -336,6 +429,10 @@ impl Output {
             Output::Message(_) => None,
             Output::Table { display_id, .. } => display_id.clone(),
             Output::Markdown { display_id, .. } => display_id.clone(),
+            Output::Svg { display_id, .. } => display_id.clone(),
+            Output::Html { display_id, .. } => display_id.clone(),
+            Output::Latex { display_id, .. } => display_id.clone(),
+            Output::Json { display_id, .. } => display_id.clone(),
             Output::ClearOutputWaitMarker => None,
         }
     }
@@ -346,7 +443,21 @@ impl Output {
         window: &mut Window,
         cx: &mut App,
     ) -> Self {
-        match data.richest(rank_mime_type) {
+        let mime_type = match richest_mime_type(data) {
+            Some(RichestOutput::Html { html, markdown }) => {
+                return match markdown {
+                    Ok(markdown) => Output::Html {
+                        content: cx.new(|cx| HtmlView::new(html.to_string(), markdown, cx)),
+                        display_id,
+                    },
+                    Err(error) => Output::Message(format!("Failed to render HTML: {}", error)),
+                };
+            }
+            Some(RichestOutput::Other(mime_type)) => Some(mime_type),
+            None => None,
+        };
+
+        match mime_type {
             Some(MimeType::Plain(text)) => Output::Plain {
                 content: cx.new(|cx| TerminalOutput::from(text, window, cx)),
                 display_id,
@@ -369,6 +480,21 @@ impl Output {
                 content: cx.new(|cx| TableView::new(data, window, cx)),
                 display_id,
             },
+            Some(MimeType::Svg(text)) => Output::Svg {
+                content: cx.new(|cx| SvgView::from(text.clone(), cx)),
+                display_id,
+            },
+            Some(MimeType::Latex(text)) => Output::Latex {
+                content: cx.new(|_| LatexView::from(text.clone())),
+                display_id,
+            },
+            Some(MimeType::Json(json)) => match serde_json::to_value(json) {
+                Ok(value) => Output::Json {
+                    content: cx.new(|_| JsonView::from(value)),
+                    display_id,
+                },
+                Err(error) => Output::Message(format!("Failed to load JSON: {}", error)),
+            },
             // Any other media types are not supported
             _ => Output::Message("Unsupported media type".to_string()),
         }
@@ -615,3 +741,59 @@ impl Render for ExecutionView {
             .into_any_element()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn bundle(data: serde_json::Value) -> MimeBundle {
+        serde_json::from_value(data).unwrap()
+    }
+
+    #[test]
+    fn test_json_ranks_below_rich_outputs() {
+        let data = bundle(json!({
+            "application/json": {"a": 1},
+            "text/html": "<p>a</p>",
+            "text/plain": "{'a': 1}",
+        }));
+        assert!(matches!(
+            richest_mime_type(&data),
+            Some(RichestOutput::Html { .. })
+        ));
+
+        let data = bundle(json!({
+            "application/json": {"a": 1},
+            "text/plain": "{'a': 1}",
+        }));
+        assert!(matches!(
+            richest_mime_type(&data),
+            Some(RichestOutput::Other(MimeType::Json(_)))
+        ));
+    }
+
+    #[test]
+    fn test_script_only_html_falls_back_to_plain_text() {
+        let data = bundle(json!({
+            "text/html": "<script>require(['widget'], function(w) { w.render(); })</script>",
+            "text/plain": "Widget(value=1)",
+        }));
+        match richest_mime_type(&data) {
+            Some(RichestOutput::Other(MimeType::Plain(text))) => {
+                assert_eq!(text, "Widget(value=1)")
+            }
+            other => panic!("expected text/plain, got {other:?}"),
+        }
+
+        let data = bundle(json!({
+            "text/html": "<script>render()</script>",
+        }));
+        match richest_mime_type(&data) {
+            Some(RichestOutput::Html { markdown, .. }) => {
+                assert!(markdown.unwrap().trim().is_empty())
+            }
+            other => panic!("expected text/html, got {other:?}"),
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use std::{cell::RefCell, rc::Rc};
+
+use anyhow::Result;
+use gpui::{App, ClipboardItem, Entity, Window};
+use html_to_markdown::{TagHandler, convert_html_to_markdown, markdown};
+use language::Buffer;
+use ui::{IntoElement, prelude::*};
+
+use crate::outputs::{OutputContent, markdown::MarkdownView};
+
+/// HtmlView renders HTML outputs, such as pandas `_repr_html_` tables, by
+/// converting the subset of HTML that Markdown can express.
+///
+/// Scripts, styles and anything else outside that subset are dropped.
+pub struct HtmlView {
+    html: String,
+    markdown: Entity<MarkdownView>,
+}
+
+impl HtmlView {
+    /// `markdown` is `html` converted with [`html_to_markdown`].
+    pub fn new(html: String, markdown: String, cx: &mut App) -> Self {
+        Self {
+            html,
+            markdown: cx.new(|cx| MarkdownView::from(markdown, cx)),
+        }
+    }
+}
+
+pub fn html_to_markdown(html: &str) -> Result<String> {
+    let mut handlers: Vec<TagHandler> = vec![
+        Rc::new(RefCell::new(markdown::WebpageChromeRemover)),
+        Rc::new(RefCell::new(markdown::ParagraphHandler)),
+        Rc::new(RefCell::new(markdown::HeadingHandler)),
+        Rc::new(RefCell::new(markdown::ListHandler)),
+        Rc::new(RefCell::new(markdown::TableHandler::new())),
+        Rc::new(RefCell::new(markdown::StyledTextHandler)),
+        Rc::new(RefCell::new(markdown::CodeHandler)),
+    ];
+    convert_html_to_markdown(html.as_bytes(), &mut handlers)
+}
+
+impl Render for HtmlView {
+    fn render(&mut self, _window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
+        self.markdown.clone()
+    }
+}
+
+impl OutputContent for HtmlView {
+    fn clipboard_content(&self, _window: &Window, _cx: &App) -> Option<ClipboardItem> {
+        Some(ClipboardItem::new_string(self.html.clone()))
+    }
+
+    fn has_clipboard_content(&self, _window: &Window, _cx: &App) -> bool {
+        true
+    }
+
+    fn has_buffer_content(&self, _window: &Window, _cx: &App) -> bool {
+        true
+    }
+
+    fn buffer_content(&mut self, _: &mut Window, cx: &mut App) -> Option<Entity<Buffer>> {
+        let buffer = cx.new(|cx| {
+            let mut buffer = Buffer::local(self.html.clone(), cx)
+                .with_language(language::PLAIN_TEXT.clone(), cx);
+            buffer.set_capability(language::Capability::ReadOnly, cx);
+            buffer
+        });
+        Some(buffer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    #[test]
+    fn test_html_to_markdown_drops_scripts_and_styles() {
+        let html = indoc! {r#"
+            <div>
+            <style scoped>.dataframe tbody tr th { vertical-align: top; }</style>
+            <script>alert("hi")</script>
+            <h3>Results</h3>
+            <p>Some <strong>bold</strong> text</p>
+            </div>
+        "#};
+
+        let markdown = html_to_markdown(html).unwrap();
+        assert!(!markdown.contains("alert"));
+        assert!(!markdown.contains("vertical-align"));
+        assert!(markdown.contains("### Results"));
+        assert!(markdown.contains("**bold**"));
+    }
+
+    #[test]
+    fn test_html_tables_become_markdown_tables() {
+        let html = indoc! {r#"
+            <table class="dataframe">
+              <thead><tr><th>a</th><th>b</th></tr></thead>
+              <tbody><tr><td>1</td><td>2</td></tr></tbody>
+            </table>
+        "#};
+
+        let markdown = html_to_markdown(html).unwrap();
+        assert!(markdown.contains("| a | b |"), "{markdown}");
+        assert!(markdown.contains("| --- | --- |"), "{markdown}");
+        assert!(markdown.contains("| 1 | 2 |"), "{markdown}");
+    }
+
+    #[test]
+    fn test_script_only_html_has_nothing_to_render() {
+        let html = indoc! {r#"
+            <div id="widget"></div>
+            <script type="application/vnd.jupyter.widget-view+json">
+            {"model_id": "1234", "version_major": 2}
+            </script>
+        "#};
+
+        assert!(html_to_markdown(html).unwrap().trim().is_empty());
+        let markdown = html_to_markdown("<p>Some text</p>").unwrap();
+        assert!(markdown.contains("Some text"), "{markdown}");
+    }
+}
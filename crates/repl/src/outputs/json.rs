@@ -0,0 +1,356 @@
+use collections::{HashMap, HashSet};
+use gpui::{App, ClipboardItem, Entity, Window};
+use language::Buffer;
+use serde_json::Value;
+use ui::{Disclosure, IntoElement, Label, h_flex, prelude::*, v_flex};
+
+use crate::outputs::OutputContent;
+
+/// Objects and arrays nested deeper than this start out collapsed.
+const EXPANDED_DEPTH: usize = 2;
+
+/// Children of an expanded object or array shown at a time.
+const CHILDREN_PAGE_SIZE: usize = 100;
+
+/// JsonView renders `application/json` outputs as a tree whose objects and
+/// arrays can be collapsed.
+///
+/// Large objects and arrays show their children a page at a time, so an
+/// output with thousands of items doesn't render thousands of rows.
+pub struct JsonView {
+    value: Value,
+    /// JSON pointers to the objects and arrays that are expanded
+    expanded: HashSet<String>,
+    /// How many children each object or array shows, when more than a page
+    shown: HashMap<String, usize>,
+    /// The rows for the current expansion, rebuilt when it changes
+    rows: Vec<JsonRow>,
+}
+
+/// One line of the tree as it is currently expanded.
+#[derive(Debug, PartialEq)]
+struct JsonRow {
+    depth: usize,
+    pointer: String,
+    key: Option<String>,
+    text: String,
+    /// Whether an object or array is expanded, for rows that can be
+    is_expanded: Option<bool>,
+    /// Whether this row stands for the children of `pointer` not shown yet
+    is_more: bool,
+}
+
+impl JsonView {
+    pub fn from(value: Value) -> Self {
+        let mut expanded = HashSet::default();
+        collect_containers(&value, String::new(), 0, &mut |pointer, depth| {
+            if depth < EXPANDED_DEPTH {
+                expanded.insert(pointer);
+            }
+        });
+        let mut this = Self {
+            value,
+            expanded,
+            shown: HashMap::default(),
+            rows: Vec::new(),
+        };
+        this.rows = this.rows();
+        this
+    }
+
+    fn toggle(&mut self, pointer: &str, cx: &mut Context<Self>) {
+        if !self.expanded.remove(pointer) {
+            self.expanded.insert(pointer.to_string());
+        }
+        self.rows = self.rows();
+        cx.notify();
+    }
+
+    fn show_more(&mut self, pointer: &str, cx: &mut Context<Self>) {
+        *self
+            .shown
+            .entry(pointer.to_string())
+            .or_insert(CHILDREN_PAGE_SIZE) += CHILDREN_PAGE_SIZE;
+        self.rows = self.rows();
+        cx.notify();
+    }
+
+    fn rows(&self) -> Vec<JsonRow> {
+        let mut rows = Vec::new();
+        push_rows(
+            &self.value,
+            None,
+            String::new(),
+            0,
+            &self.expanded,
+            &self.shown,
+            &mut rows,
+        );
+        rows
+    }
+
+    fn pretty_json(&self) -> String {
+        serde_json::to_string_pretty(&self.value).unwrap_or_default()
+    }
+}
+
+fn collect_containers(
+    value: &Value,
+    pointer: String,
+    depth: usize,
+    f: &mut impl FnMut(String, usize),
+) {
+    let children: Box<dyn Iterator<Item = (String, &Value)>> = match value {
+        Value::Object(map) => Box::new(map.iter().map(|(key, value)| (escape(key), value))),
+        Value::Array(items) => Box::new(
+            items
+                .iter()
+                .enumerate()
+                .map(|(index, value)| (index.to_string(), value)),
+        ),
+        _ => return,
+    };
+    for (key, child) in children {
+        collect_containers(child, format!("{pointer}/{key}"), depth + 1, f);
+    }
+    f(pointer, depth);
+}
+
+fn push_rows(
+    value: &Value,
+    key: Option<String>,
+    pointer: String,
+    depth: usize,
+    expanded: &HashSet<String>,
+    shown: &HashMap<String, usize>,
+    rows: &mut Vec<JsonRow>,
+) {
+    let (open, close, len) = match value {
+        Value::Object(map) => ("{", "}", map.len()),
+        Value::Array(items) => ("[", "]", items.len()),
+        scalar => {
+            rows.push(JsonRow {
+                depth,
+                pointer,
+                key,
+                text: scalar.to_string(),
+                is_expanded: None,
+                is_more: false,
+            });
+            return;
+        }
+    };
+
+    let is_expanded = len > 0 && expanded.contains(&pointer);
+    let text = if is_expanded {
+        open.to_string()
+    } else if len == 0 {
+        format!("{open}{close}")
+    } else {
+        format!("{open}…{close} {len} {}", count_noun(value, len))
+    };
+    rows.push(JsonRow {
+        depth,
+        pointer: pointer.clone(),
+        key,
+        text,
+        is_expanded: (len > 0).then_some(is_expanded),
+        is_more: false,
+    });
+    if !is_expanded {
+        return;
+    }
+
+    let limit = shown.get(&pointer).copied().unwrap_or(CHILDREN_PAGE_SIZE);
+    match value {
+        Value::Object(map) => {
+            for (child_key, child) in map.iter().take(limit) {
+                let child_pointer = format!("{pointer}/{}", escape(child_key));
+                push_rows(
+                    child,
+                    Some(child_key.clone()),
+                    child_pointer,
+                    depth + 1,
+                    expanded,
+                    shown,
+                    rows,
+                );
+            }
+        }
+        Value::Array(items) => {
+            for (index, child) in items.iter().enumerate().take(limit) {
+                let child_pointer = format!("{pointer}/{index}");
+                push_rows(child, None, child_pointer, depth + 1, expanded, shown, rows);
+            }
+        }
+        _ => {}
+    }
+    if len > limit {
+        let hidden = len - limit;
+        rows.push(JsonRow {
+            depth: depth + 1,
+            pointer: pointer.clone(),
+            key: None,
+            text: format!("… {hidden} more {}", count_noun(value, hidden)),
+            is_expanded: None,
+            is_more: true,
+        });
+    }
+    rows.push(JsonRow {
+        depth,
+        pointer,
+        key: None,
+        text: close.to_string(),
+        is_expanded: None,
+        is_more: false,
+    });
+}
+
+fn count_noun(value: &Value, count: usize) -> &'static str {
+    match (value, count) {
+        (Value::Object(_), 1) => "key",
+        (Value::Object(_), _) => "keys",
+        (_, 1) => "item",
+        _ => "items",
+    }
+}
+
+/// Escape a key for use in a JSON pointer.
+fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+impl Render for JsonView {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        v_flex()
+            .py_1()
+            .font_buffer(cx)
+            .children(self.rows.iter().enumerate().map(|(ix, row)| {
+                let pointer = row.pointer.clone();
+                let line = h_flex().gap_1().pl(rems(row.depth as f32));
+                if row.is_more {
+                    return line.child(div().w(rems(1.))).child(
+                        Button::new(("json-show-more", ix), row.text.clone())
+                            .color(Color::Muted)
+                            .on_click(
+                                cx.listener(move |this, _, _, cx| this.show_more(&pointer, cx)),
+                            ),
+                    );
+                }
+                line.map(|this| match row.is_expanded {
+                    Some(is_expanded) => this
+                        .child(Disclosure::new(("json-toggle", ix), is_expanded).on_click(
+                            cx.listener(move |this, _, _, cx| this.toggle(&pointer, cx)),
+                        )),
+                    None => this.child(div().w(rems(1.))),
+                })
+                .children(
+                    row.key
+                        .as_ref()
+                        .map(|key| Label::new(format!("{key}:")).color(Color::Accent)),
+                )
+                .child(Label::new(row.text.clone()).color(
+                    match row.is_expanded {
+                        Some(false) => Color::Muted,
+                        _ => Color::Default,
+                    },
+                ))
+            }))
+    }
+}
+
+impl OutputContent for JsonView {
+    fn clipboard_content(&self, _window: &Window, _cx: &App) -> Option<ClipboardItem> {
+        Some(ClipboardItem::new_string(self.pretty_json()))
+    }
+
+    fn has_clipboard_content(&self, _window: &Window, _cx: &App) -> bool {
+        true
+    }
+
+    fn has_buffer_content(&self, _window: &Window, _cx: &App) -> bool {
+        true
+    }
+
+    fn buffer_content(&mut self, _: &mut Window, cx: &mut App) -> Option<Entity<Buffer>> {
+        let buffer = cx.new(|cx| {
+            let mut buffer = Buffer::local(self.pretty_json(), cx)
+                .with_language(language::PLAIN_TEXT.clone(), cx);
+            buffer.set_capability(language::Capability::ReadOnly, cx);
+            buffer
+        });
+        Some(buffer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn lines(view: &JsonView) -> Vec<String> {
+        view.rows()
+            .into_iter()
+            .map(|row| {
+                let key = row.key.map(|key| format!("{key}: ")).unwrap_or_default();
+                format!("{}{key}{}", "  ".repeat(row.depth), row.text)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_deeply_nested_values_start_collapsed() {
+        let view = JsonView::from(json!({
+            "name": "run",
+            "tags": [],
+            "metrics": {"loss": 0.5, "history": [1, 2, 3]},
+        }));
+
+        assert_eq!(
+            lines(&view),
+            [
+                "{",
+                "  name: \"run\"",
+                "  tags: []",
+                "  metrics: {",
+                "    loss: 0.5",
+                "    history: […] 3 items",
+                "  }",
+                "}",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_toggling_containers() {
+        let mut view = JsonView::from(json!({"a/b": {"c": {"d": 1}}}));
+        assert!(view.expanded.contains("/a~1b"));
+        assert!(!view.expanded.contains("/a~1b/c"));
+
+        view.expanded.insert("/a~1b/c".into());
+        assert!(lines(&view).contains(&"      d: 1".to_string()));
+
+        view.expanded.remove("");
+        assert_eq!(lines(&view), ["{…} 1 key"]);
+    }
+
+    #[test]
+    fn test_large_arrays_show_a_page_of_items() {
+        let mut view = JsonView::from(json!((0..250).collect::<Vec<_>>()));
+        assert_eq!(view.rows, view.rows());
+
+        let rows = lines(&view);
+        assert_eq!(rows.len(), CHILDREN_PAGE_SIZE + 3);
+        assert_eq!(rows[CHILDREN_PAGE_SIZE], "  99");
+        assert_eq!(rows[CHILDREN_PAGE_SIZE + 1], "  … 150 more items");
+        assert_eq!(rows[CHILDREN_PAGE_SIZE + 2], "]");
+
+        view.shown.insert(String::new(), 2 * CHILDREN_PAGE_SIZE);
+        assert!(lines(&view).contains(&"  … 50 more items".to_string()));
+
+        view.shown.insert(String::new(), 250);
+        let rows = lines(&view);
+        assert_eq!(rows.len(), 252);
+        assert!(!rows.iter().any(|row| row.contains("more")));
+    }
+}
@@ -0,0 +1,348 @@
+use std::{iter::Peekable, str::Chars};
+
+use gpui::{App, ClipboardItem, Window};
+use ui::{IntoElement, div, prelude::*};
+
+use crate::outputs::OutputContent;
+
+/// LatexView renders LaTeX math, such as SymPy's `_repr_latex_`, as Unicode
+/// text: Greek letters and symbols, super- and subscripts, fractions and
+/// roots.
+pub struct LatexView {
+    source: String,
+    text: String,
+}
+
+impl LatexView {
+    pub fn from(source: String) -> Self {
+        let text = latex_to_unicode(&source);
+        Self { source, text }
+    }
+}
+
+impl Render for LatexView {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        div().py_1().font_buffer(cx).child(self.text.clone())
+    }
+}
+
+impl OutputContent for LatexView {
+    fn clipboard_content(&self, _window: &Window, _cx: &App) -> Option<ClipboardItem> {
+        Some(ClipboardItem::new_string(self.source.clone()))
+    }
+
+    fn has_clipboard_content(&self, _window: &Window, _cx: &App) -> bool {
+        true
+    }
+}
+
+/// Convert LaTeX math to the closest Unicode text. Anything that can't be
+/// converted is kept as written.
+pub fn latex_to_unicode(source: &str) -> String {
+    let source = strip_math_delimiters(source.trim());
+    let mut chars = source.chars().peekable();
+    let text = convert(&mut chars, None);
+    text.lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn strip_math_delimiters(source: &str) -> &str {
+    for (start, end) in [("$$", "$$"), ("\\[", "\\]"), ("\\(", "\\)"), ("$", "$")] {
+        if let Some(inner) = source
+            .strip_prefix(start)
+            .and_then(|source| source.strip_suffix(end))
+        {
+            return inner.trim();
+        }
+    }
+    source
+}
+
+/// Convert until the end of input or the `until` character.
+fn convert(chars: &mut Peekable<Chars>, until: Option<char>) -> String {
+    let mut text = String::new();
+    while let Some(char) = chars.next() {
+        if Some(char) == until {
+            break;
+        }
+        match char {
+            '\\' => text.push_str(&command(chars)),
+            '{' => text.push_str(&convert(chars, Some('}'))),
+            '^' => text.push_str(&script(chars, superscript, '^')),
+            '_' => text.push_str(&script(chars, subscript, '_')),
+            '&' => {}
+            '~' => text.push(' '),
+            char => text.push(char),
+        }
+    }
+    text
+}
+
+/// A single argument: a braced group, a command or one character.
+fn argument(chars: &mut Peekable<Chars>) -> String {
+    while chars.next_if(|char| char.is_whitespace()).is_some() {}
+    match chars.next() {
+        Some('{') => convert(chars, Some('}')),
+        Some('\\') => command(chars),
+        Some(char) => char.to_string(),
+        None => String::new(),
+    }
+}
+
+fn script(chars: &mut Peekable<Chars>, map: fn(char) -> Option<char>, marker: char) -> String {
+    let argument = argument(chars).split_whitespace().collect::<String>();
+    match argument.chars().map(map).collect::<Option<String>>() {
+        Some(script) => script,
+        None if argument.chars().count() == 1 => format!("{marker}{argument}"),
+        None => format!("{marker}({argument})"),
+    }
+}
+
+fn command(chars: &mut Peekable<Chars>) -> String {
+    let mut name = String::new();
+    while let Some(char) = chars.next_if(|char| char.is_ascii_alphabetic()) {
+        name.push(char);
+    }
+    if name.is_empty() {
+        return match chars.next() {
+            Some('\\') => "\n".into(),
+            Some(',' | ';' | ':' | '!' | ' ') => " ".into(),
+            Some(char) => char.to_string(),
+            None => String::new(),
+        };
+    }
+
+    match name.as_str() {
+        "frac" | "dfrac" | "tfrac" => {
+            let numerator = argument(chars);
+            let denominator = argument(chars);
+            format!(
+                "{}/{}",
+                parenthesize(&numerator),
+                parenthesize(&denominator)
+            )
+        }
+        "sqrt" => format!("√{}", parenthesize(&argument(chars))),
+        "text" | "mathrm" | "mathbf" | "mathit" | "mathsf" | "mathtt" | "operatorname"
+        | "boldsymbol" => argument(chars),
+        "mathbb" => {
+            let argument = argument(chars);
+            argument
+                .chars()
+                .map(|char| double_struck(char).unwrap_or(char))
+                .collect()
+        }
+        "left" | "right" | "big" | "Big" | "bigg" | "Bigg" | "displaystyle" => String::new(),
+        "quad" | "qquad" => " ".into(),
+        "begin" | "end" => {
+            // Environments like `aligned` only affect layout
+            argument(chars);
+            String::new()
+        }
+        name => match symbol(name) {
+            Some(symbol) => symbol.into(),
+            None if FUNCTIONS.contains(&name) => name.into(),
+            None => format!("\\{name}"),
+        },
+    }
+}
+
+fn parenthesize(text: &str) -> String {
+    if text.chars().count() <= 1 || text.chars().all(|char| char.is_alphanumeric()) {
+        text.into()
+    } else {
+        format!("({text})")
+    }
+}
+
+/// Operators like `\sin` that are written upright, as their name.
+const FUNCTIONS: &[&str] = &[
+    "sin", "cos", "tan", "cot", "sec", "csc", "sinh", "cosh", "tanh", "log", "ln", "exp", "lim",
+    "max", "min", "sup", "inf", "det", "arg", "deg", "gcd",
+];
+
+fn symbol(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "alpha" => "α",
+        "beta" => "β",
+        "gamma" => "γ",
+        "delta" => "δ",
+        "epsilon" | "varepsilon" => "ε",
+        "zeta" => "ζ",
+        "eta" => "η",
+        "theta" | "vartheta" => "θ",
+        "iota" => "ι",
+        "kappa" => "κ",
+        "lambda" => "λ",
+        "mu" => "μ",
+        "nu" => "ν",
+        "xi" => "ξ",
+        "pi" | "varpi" => "π",
+        "rho" | "varrho" => "ρ",
+        "sigma" | "varsigma" => "σ",
+        "tau" => "τ",
+        "upsilon" => "υ",
+        "phi" | "varphi" => "φ",
+        "chi" => "χ",
+        "psi" => "ψ",
+        "omega" => "ω",
+        "Gamma" => "Γ",
+        "Delta" => "Δ",
+        "Theta" => "Θ",
+        "Lambda" => "Λ",
+        "Xi" => "Ξ",
+        "Pi" => "Π",
+        "Sigma" => "Σ",
+        "Upsilon" => "Υ",
+        "Phi" => "Φ",
+        "Psi" => "Ψ",
+        "Omega" => "Ω",
+        "times" => "×",
+        "cdot" => "·",
+        "div" => "÷",
+        "pm" => "±",
+        "mp" => "∓",
+        "leq" | "le" => "≤",
+        "geq" | "ge" => "≥",
+        "neq" | "ne" => "≠",
+        "approx" => "≈",
+        "equiv" => "≡",
+        "sim" => "∼",
+        "propto" => "∝",
+        "infty" => "∞",
+        "sum" => "∑",
+        "prod" => "∏",
+        "int" => "∫",
+        "oint" => "∮",
+        "partial" => "∂",
+        "nabla" => "∇",
+        "in" => "∈",
+        "notin" => "∉",
+        "subset" => "⊂",
+        "subseteq" => "⊆",
+        "cup" => "∪",
+        "cap" => "∩",
+        "emptyset" => "∅",
+        "forall" => "∀",
+        "exists" => "∃",
+        "neg" => "¬",
+        "wedge" | "land" => "∧",
+        "vee" | "lor" => "∨",
+        "to" | "rightarrow" => "→",
+        "leftarrow" => "←",
+        "Rightarrow" | "implies" => "⇒",
+        "Leftarrow" => "⇐",
+        "leftrightarrow" => "↔",
+        "Leftrightarrow" | "iff" => "⇔",
+        "mapsto" => "↦",
+        "ldots" | "dots" => "…",
+        "cdots" => "⋯",
+        "circ" => "∘",
+        "degree" => "°",
+        "langle" => "⟨",
+        "rangle" => "⟩",
+        "lfloor" => "⌊",
+        "rfloor" => "⌋",
+        "lceil" => "⌈",
+        "rceil" => "⌉",
+        "hbar" => "ℏ",
+        "ell" => "ℓ",
+        _ => return None,
+    })
+}
+
+fn superscript(char: char) -> Option<char> {
+    Some(match char {
+        '0' => '⁰',
+        '1' => '¹',
+        '2' => '²',
+        '3' => '³',
+        '4' => '⁴',
+        '5' => '⁵',
+        '6' => '⁶',
+        '7' => '⁷',
+        '8' => '⁸',
+        '9' => '⁹',
+        '+' => '⁺',
+        '-' | '−' => '⁻',
+        '=' => '⁼',
+        '(' => '⁽',
+        ')' => '⁾',
+        'n' => 'ⁿ',
+        'i' => 'ⁱ',
+        'T' => 'ᵀ',
+        _ => return None,
+    })
+}
+
+fn subscript(char: char) -> Option<char> {
+    Some(match char {
+        '0' => '₀',
+        '1' => '₁',
+        '2' => '₂',
+        '3' => '₃',
+        '4' => '₄',
+        '5' => '₅',
+        '6' => '₆',
+        '7' => '₇',
+        '8' => '₈',
+        '9' => '₉',
+        '+' => '₊',
+        '-' | '−' => '₋',
+        '=' => '₌',
+        '(' => '₍',
+        ')' => '₎',
+        'a' => 'ₐ',
+        'e' => 'ₑ',
+        'i' => 'ᵢ',
+        'j' => 'ⱼ',
+        'k' => 'ₖ',
+        'n' => 'ₙ',
+        'x' => 'ₓ',
+        _ => return None,
+    })
+}
+
+fn double_struck(char: char) -> Option<char> {
+    Some(match char {
+        'N' => 'ℕ',
+        'Z' => 'ℤ',
+        'Q' => 'ℚ',
+        'R' => 'ℝ',
+        'C' => 'ℂ',
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_symbols_and_scripts() {
+        assert_eq!(latex_to_unicode("$x^2 + y_1$"), "x² + y₁");
+        assert_eq!(latex_to_unicode("$$\\alpha \\leq \\beta$$"), "α ≤ β");
+        assert_eq!(latex_to_unicode("$e^{i \\pi} = -1$"), "e^(iπ) = -1");
+        assert_eq!(latex_to_unicode("$x_{10}^{n+1}$"), "x₁₀ⁿ⁺¹");
+        assert_eq!(latex_to_unicode("$\\sin x$"), "sin x");
+    }
+
+    #[test]
+    fn test_fractions_and_roots() {
+        assert_eq!(latex_to_unicode("$\\frac{1}{2}$"), "1/2");
+        assert_eq!(latex_to_unicode("$\\frac{a + b}{c}$"), "(a + b)/c");
+        assert_eq!(latex_to_unicode("$\\sqrt{x^2 + 1}$"), "√(x² + 1)");
+        assert_eq!(latex_to_unicode("$\\left(\\frac{x}{y}\\right)$"), "(x/y)");
+    }
+
+    #[test]
+    fn test_unknown_commands_are_kept() {
+        assert_eq!(latex_to_unicode("$\\aleph_0$"), "\\aleph₀");
+        assert_eq!(
+            latex_to_unicode("\\begin{aligned}a &= 1 \\\\ b &= 2\\end{aligned}"),
+            "a = 1\nb = 2"
+        );
+    }
+}
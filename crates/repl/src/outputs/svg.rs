@@ -0,0 +1,86 @@
+use std::sync::Arc;
+
+use gpui::{App, ClipboardItem, Entity, RenderImage, Task, Window, img};
+use language::Buffer;
+use ui::{IntoElement, Styled, div, prelude::*};
+
+use crate::outputs::OutputContent;
+
+/// SvgView renders an SVG output, rasterized with the same renderer gpui
+/// uses for SVG images and previews.
+pub struct SvgView {
+    source: String,
+    image: Option<Result<Arc<RenderImage>, SharedString>>,
+    _render_task: Task<()>,
+}
+
+impl SvgView {
+    pub fn from(source: String, cx: &mut Context<Self>) -> Self {
+        const SCALE_FACTOR: f32 = 1.0;
+
+        let renderer = cx.svg_renderer();
+        let bytes = source.clone().into_bytes();
+        let rendering = cx
+            .background_executor()
+            .spawn(async move { renderer.render_single_frame(&bytes, SCALE_FACTOR, true) });
+
+        let render_task = cx.spawn(async move |this, cx| {
+            let image = rendering.await.map_err(|error| error.to_string().into());
+            this.update(cx, |this, cx| {
+                this.image = Some(image);
+                cx.notify();
+            })
+            .ok();
+        });
+
+        Self {
+            source,
+            image: None,
+            _render_task: render_task,
+        }
+    }
+}
+
+impl Render for SvgView {
+    fn render(&mut self, _window: &mut Window, _cx: &mut Context<Self>) -> impl IntoElement {
+        match &self.image {
+            Some(Ok(image)) => {
+                let size = image.size(0);
+                div()
+                    .w(px(size.width.0 as f32))
+                    .h(px(size.height.0 as f32))
+                    .max_w_full()
+                    .child(img(image.clone()).size_full())
+                    .into_any_element()
+            }
+            Some(Err(error)) => div()
+                .child(format!("Failed to render SVG: {error}"))
+                .into_any_element(),
+            None => div().into_any_element(),
+        }
+    }
+}
+
+impl OutputContent for SvgView {
+    fn clipboard_content(&self, _window: &Window, _cx: &App) -> Option<ClipboardItem> {
+        Some(ClipboardItem::new_string(self.source.clone()))
+    }
+
+    fn has_clipboard_content(&self, _window: &Window, _cx: &App) -> bool {
+        true
+    }
+
+    fn has_buffer_content(&self, _window: &Window, _cx: &App) -> bool {
+        true
+    }
+
+    fn buffer_content(&mut self, _: &mut Window, cx: &mut App) -> Option<Entity<Buffer>> {
+        let buffer = cx.new(|cx| {
+            let mut buffer = Buffer::local(self.source.clone(), cx)
+                .with_language(language::PLAIN_TEXT.clone(), cx);
+            buffer.set_capability(language::Capability::ReadOnly, cx);
+            buffer
+        });
+        Some(buffer)
+    }
+}
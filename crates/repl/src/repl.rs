@@ -34,7 +34,7 @@ pub fn init(fs: Arc<dyn Fs>, cx: &mut App) {
     ReplStore::init(fs, cx);
 }
 
-fn zed_dispatcher(cx: &mut App) -> impl Dispatcher {
+pub(crate) fn zed_dispatcher(cx: &mut App) -> impl Dispatcher {
     struct ZedDispatcher {
         dispatcher: Arc<dyn PlatformDispatcher>,
     }
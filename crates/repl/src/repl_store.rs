@@ -41,6 +41,13 @@ impl ReplStore {
         cx.set_global(GlobalReplStore(store))
     }
 
+    /// Sets up the store without looking for kernels on the machine.
+    #[cfg(test)]
+    pub(crate) fn init_test(fs: Arc<dyn Fs>, cx: &mut App) {
+        let store = cx.new(move |cx| Self::new(fs, cx));
+        cx.set_global(GlobalReplStore(store))
+    }
+
     pub fn global(cx: &App) -> Entity<Self> {
         cx.global::<GlobalReplStore>().0.clone()
     }
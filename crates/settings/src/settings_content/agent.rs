@@ -106,6 +106,12 @@ pub struct AgentSettingsContent {
     ///
     /// Default: 4
     pub message_editor_min_lines: Option<usize>,
+    /// Whether to shorten requests by replacing files that were already sent
+    /// earlier in the thread, unchanged, with a placeholder. Tool results are
+    /// always sent in full.
+    ///
+    /// Default: false
+    pub compress_requests: Option<bool>,
 }
 
 impl AgentSettingsContent {
@@ -630,6 +630,49 @@ fn test_history() {
     assert_eq!(buffer.text(), "X12cde6");
 }
 
+#[test]
+fn test_undo_history() {
+    let start = Instant::now();
+    let mut buffer = Buffer::new(ReplicaId::LOCAL, BufferId::new(1).unwrap(), "123456");
+    buffer.set_group_interval(Duration::from_millis(300));
+    assert_eq!(buffer.undo_history().count(), 0);
+
+    let transaction_1 = buffer.start_transaction_at(start).unwrap();
+    buffer.edit([(2..4, "cd")]);
+    buffer.end_transaction_at(start);
+
+    // Grouped with the first transaction, which now ends at this edit
+    let grouped_at = start + Duration::from_millis(100);
+    buffer.start_transaction_at(grouped_at);
+    buffer.edit([(4..5, "e")]);
+    buffer.end_transaction_at(grouped_at);
+
+    let later = grouped_at + buffer.transaction_group_interval() + Duration::from_millis(1);
+    let transaction_3 = buffer.start_transaction_at(later).unwrap();
+    buffer.edit([(0..1, "a")]);
+    buffer.end_transaction_at(later);
+    assert_eq!(buffer.text(), "a2cde6");
+
+    let history = |buffer: &Buffer| {
+        buffer
+            .undo_history()
+            .map(|entry| (entry.transaction_id(), entry.last_edit_at()))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        history(&buffer),
+        [(transaction_3, later), (transaction_1, grouped_at)]
+    );
+
+    buffer.undo();
+    assert_eq!(history(&buffer), [(transaction_1, grouped_at)]);
+    buffer.redo();
+    assert_eq!(
+        history(&buffer),
+        [(transaction_3, later), (transaction_1, grouped_at)]
+    );
+}
+
 #[test]
 fn test_finalize_last_transaction() {
     let now = Instant::now();
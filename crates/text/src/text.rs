@@ -141,6 +141,10 @@ impl HistoryEntry {
     pub fn transaction_id(&self) -> TransactionId {
         self.transaction.id
     }
+
+    pub fn last_edit_at(&self) -> Instant {
+        self.last_edit_at
+    }
 }
 
 struct History {
@@ -1390,6 +1394,11 @@ impl Buffer {
         self.history.redo_stack.last()
     }
 
+    /// The transactions that can be undone, most recent first.
+    pub fn undo_history(&self) -> impl '_ + Iterator<Item = &HistoryEntry> {
+        self.history.undo_stack.iter().rev()
+    }
+
     pub fn start_transaction(&mut self) -> Option<TransactionId> {
         self.start_transaction_at(Instant::now())
     }
@@ -1,13 +1,53 @@
 use anyhow::{Context, Result};
+use burn_lm_router::ModelTier;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::PathBuf;
 
+use crate::semantic_cache::SemanticCacheConfig;
+
+/// Which implementation serves the semantic prompt cache
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CacheBackend {
+    /// In-process cache, no Python required
+    #[default]
+    Native,
+    /// The Python Widget-Log proxy, via PyO3
+    Python,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct WidgetLogConfig {
     pub widget_log_dir: PathBuf,
     pub proxy_host: String,
     pub proxy_port: u16,
     pub auto_start: bool,
+    #[serde(default)]
+    pub cache_backend: CacheBackend,
+    #[serde(default)]
+    pub semantic_cache: SemanticCacheConfig,
+    /// Anthropic model the native cache answers misses with
+    #[serde(default = "default_model")]
+    pub model: String,
+    /// Models to answer misses with for each tier the routing network
+    /// predicts; tiers without an entry use `model`
+    #[serde(default)]
+    pub tier_models: HashMap<ModelTier, String>,
+    /// Zed settings file pointed at the proxy
+    #[serde(default = "default_zed_settings_path")]
+    pub zed_settings_path: PathBuf,
+}
+
+fn default_model() -> String {
+    "claude-opus-4-20250514".to_string()
+}
+
+fn default_zed_settings_path() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("zed")
+        .join("settings.json")
 }
 
 impl Default for WidgetLogConfig {
@@ -22,6 +62,11 @@ impl Default for WidgetLogConfig {
             proxy_host: "127.0.0.1".to_string(),
             proxy_port: 8443,
             auto_start: true,
+            cache_backend: CacheBackend::default(),
+            semantic_cache: SemanticCacheConfig::default(),
+            model: default_model(),
+            tier_models: HashMap::new(),
+            zed_settings_path: default_zed_settings_path(),
         }
     }
 }
@@ -98,6 +143,21 @@ impl WidgetLogConfig {
         Ok(())
     }
 
+    /// Location of the native semantic cache database
+    pub fn semantic_cache_path(&self) -> PathBuf {
+        self.widget_log_dir.join("semantic-cache.db")
+    }
+
+    /// JSON-lines telemetry the routing network is trained on
+    pub fn routing_telemetry_path(&self) -> PathBuf {
+        self.widget_log_dir.join("routing-telemetry.jsonl")
+    }
+
+    /// Trained routing network weights, used instead of heuristics when present
+    pub fn routing_weights_path(&self) -> PathBuf {
+        self.widget_log_dir.join("routing-weights.json")
+    }
+
     pub fn get_config_dir() -> PathBuf {
         dirs::config_dir()
             .unwrap_or_else(|| PathBuf::from("."))
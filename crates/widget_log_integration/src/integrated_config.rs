@@ -1,4 +1,5 @@
 use anyhow::Result;
+use prompt_management_agent::{AgentConfig, ModelManager};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use tokio::fs;
@@ -90,6 +91,18 @@ impl Default for IntegratedConfig {
     }
 }
 
+impl PromptManagementSection {
+    /// The prompt management agent configuration for this section
+    pub fn agent_config(&self) -> AgentConfig {
+        AgentConfig {
+            model_path: self.model.clone(),
+            refinement_threshold: self.refinement_threshold,
+            auto_approve_safe_refinements: self.auto_approve_safe_refinements,
+            max_context_tokens: self.max_context_tokens,
+        }
+    }
+}
+
 impl IntegratedConfig {
     /// Load configuration from file or create default
     pub async fn load_or_default() -> Result<Self> {
@@ -108,6 +121,20 @@ impl IntegratedConfig {
         }
     }
 
+    /// Load the prompt management agent configuration, preferring the model
+    /// made active in the model manager over the configured one
+    pub async fn load_agent_config() -> Result<AgentConfig> {
+        let config = Self::load_or_default().await?;
+        let mut agent_config = config.prompt_management.agent_config();
+        match ModelManager::open_default().await {
+            Ok(models) => {
+                models.apply_to_config(&mut agent_config);
+            }
+            Err(error) => log::warn!("Failed to open the model manager: {error:#}"),
+        }
+        Ok(agent_config)
+    }
+
     /// Save configuration to file
     pub async fn save(&self) -> Result<()> {
         let config_path = Self::get_config_path();
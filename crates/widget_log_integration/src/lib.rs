@@ -1,20 +1,34 @@
 mod config;
 pub mod integrated_config;
 mod lifecycle;
+pub mod native_proxy;
 pub mod prompt_manager;
+mod rotating_log;
+pub mod semantic_cache;
 mod zed_config;
 
-pub use config::WidgetLogConfig;
+pub use config::{CacheBackend, WidgetLogConfig};
 pub use integrated_config::IntegratedConfig;
-pub use lifecycle::WidgetLogProcess;
+pub use lifecycle::{ProxyState, SupervisorConfig, WidgetLogProcess};
+pub use native_proxy::NativeCacheProxy;
 pub use prompt_manager::{AgenticResponse, IntegratedPromptManager, ManagerStats};
+pub use semantic_cache::{
+    CacheLookup, Embedder, HashingEmbedder, SemanticCache, SemanticCacheConfig,
+};
+pub use zed_config::{SettingsBackupEntry, SettingsUpdate};
 
-use anyhow::Result;
+use anyhow::{Context, Result};
+use std::sync::Arc;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
 
 /// Main Widget-Log integration manager
 pub struct WidgetLogManager {
     config: WidgetLogConfig,
     process: Option<WidgetLogProcess>,
+    semantic_cache: Option<Arc<SemanticCache>>,
+    /// Keeps settings.json pointed at the proxy when it is restarted on another port
+    settings_sync: Option<JoinHandle<()>>,
 }
 
 impl WidgetLogManager {
@@ -28,7 +42,24 @@ impl WidgetLogManager {
         // Initialize directory structure
         config.initialize_widget_log_dir().await?;
 
+        // The native cache runs in-process, so there is no proxy to spawn
+        if config.cache_backend == CacheBackend::Native {
+            let semantic_cache =
+                SemanticCache::open(&config.semantic_cache_path(), config.semantic_cache.clone())?;
+            log::info!(
+                "Native semantic cache ready ({} entries)",
+                semantic_cache.len()
+            );
+            return Ok(Self {
+                config,
+                process: None,
+                semantic_cache: Some(Arc::new(semantic_cache)),
+                settings_sync: None,
+            });
+        }
+
         let mut process = WidgetLogProcess::new(config.proxy_port, config.proxy_host.clone());
+        let mut settings_sync = None;
 
         // Start proxy if auto_start is enabled
         if config.auto_start {
@@ -37,9 +68,10 @@ impl WidgetLogManager {
                     log::info!("Widget-Log proxy started successfully");
 
                     // Configure Zed settings
-                    if let Err(e) = Self::configure_zed(&config, &process).await {
+                    if let Err(e) = Self::configure_zed(&config, &process.get_proxy_url()).await {
                         log::warn!("Failed to configure Zed settings: {}", e);
                     }
+                    settings_sync = Some(Self::sync_zed_settings(&config, &process));
                 }
                 Err(e) => {
                     log::error!("Failed to start Widget-Log proxy: {}", e);
@@ -47,6 +79,8 @@ impl WidgetLogManager {
                     return Ok(Self {
                         config,
                         process: None,
+                        semantic_cache: None,
+                        settings_sync: None,
                     });
                 }
             }
@@ -55,19 +89,82 @@ impl WidgetLogManager {
         Ok(Self {
             config,
             process: Some(process),
+            semantic_cache: None,
+            settings_sync,
         })
     }
 
     /// Configure Zed settings with Widget-Log proxy
-    async fn configure_zed(config: &WidgetLogConfig, process: &WidgetLogProcess) -> Result<()> {
+    async fn configure_zed(config: &WidgetLogConfig, proxy_url: &str) -> Result<()> {
         let auth_token = zed_config::get_auth_token(&config.widget_log_dir).await?;
-        let proxy_url = process.get_proxy_url();
-        zed_config::configure_zed_settings(&auth_token, &proxy_url).await?;
+        zed_config::configure_zed_settings(
+            &config.zed_settings_path,
+            &config.widget_log_dir,
+            &auth_token,
+            proxy_url,
+        )
+        .await?;
         Ok(())
     }
 
+    /// Reconfigure Zed whenever the supervisor brings the proxy back up on a different port
+    fn sync_zed_settings(config: &WidgetLogConfig, process: &WidgetLogProcess) -> JoinHandle<()> {
+        let config = config.clone();
+        let mut configured_port = process.port();
+        let mut state = process.subscribe();
+        tokio::spawn(async move {
+            while state.changed().await.is_ok() {
+                let port = match &*state.borrow_and_update() {
+                    ProxyState::Healthy { port, .. } if *port != configured_port => *port,
+                    _ => continue,
+                };
+                log::info!(
+                    "Widget-Log proxy moved to port {}, updating Zed settings",
+                    port
+                );
+                let proxy_url = lifecycle::proxy_url(&config.proxy_host, port);
+                match Self::configure_zed(&config, &proxy_url).await {
+                    Ok(()) => configured_port = port,
+                    Err(e) => log::warn!("Failed to configure Zed settings: {}", e),
+                }
+            }
+        })
+    }
+
+    /// Preview the settings.json edit that points Zed at the running proxy
+    pub async fn preview_zed_configuration(&self) -> Result<SettingsUpdate> {
+        let proxy_url = self
+            .get_proxy_url()
+            .context("Widget-Log proxy not running")?;
+        let auth_token = zed_config::get_auth_token(&self.config.widget_log_dir).await?;
+        zed_config::plan_configure(
+            &self.config.zed_settings_path,
+            &self.config.widget_log_dir,
+            &auth_token,
+            &proxy_url,
+        )
+        .await
+    }
+
+    /// Restore the Zed settings that were replaced when configuring Widget-Log
+    pub async fn unconfigure_zed(&self) -> Result<()> {
+        zed_config::unconfigure_zed_settings(
+            &self.config.zed_settings_path,
+            &self.config.widget_log_dir,
+        )
+        .await
+    }
+
+    /// The in-process semantic cache, when using the native backend
+    pub fn semantic_cache(&self) -> Option<Arc<SemanticCache>> {
+        self.semantic_cache.clone()
+    }
+
     /// Start the Widget-Log proxy (if not already running)
     pub async fn start(&mut self) -> Result<()> {
+        if self.config.cache_backend == CacheBackend::Native {
+            return Ok(());
+        }
         if let Some(process) = &mut self.process {
             process.start(self.config.widget_log_dir.clone()).await?;
         } else {
@@ -87,9 +184,29 @@ impl WidgetLogManager {
         Ok(())
     }
 
+    /// Stop the Widget-Log proxy and wait for it to exit
+    pub async fn shutdown(&mut self) -> Result<()> {
+        if let Some(process) = &mut self.process {
+            process.shutdown().await?;
+        }
+        Ok(())
+    }
+
+    /// Current state of the proxy, or `None` when caching does not use one
+    pub fn proxy_state(&self) -> Option<ProxyState> {
+        self.process.as_ref().map(|p| p.state())
+    }
+
+    /// Watch proxy state changes, e.g. to keep a status indicator up to date
+    pub fn subscribe_proxy_state(&self) -> Option<watch::Receiver<ProxyState>> {
+        self.process.as_ref().map(|p| p.subscribe())
+    }
+
     /// Check if Widget-Log proxy is healthy
     pub async fn health_check(&self) -> Result<bool> {
-        if let Some(process) = &self.process {
+        if self.semantic_cache.is_some() {
+            Ok(true)
+        } else if let Some(process) = &self.process {
             process.health_check().await
         } else {
             Ok(false)
@@ -103,7 +220,9 @@ impl WidgetLogManager {
 
     /// Get Widget-Log statistics
     pub async fn get_stats(&self) -> Result<serde_json::Value> {
-        if let Some(process) = &self.process {
+        if let Some(semantic_cache) = &self.semantic_cache {
+            Ok(semantic_cache.stats())
+        } else if let Some(process) = &self.process {
             let client = reqwest::Client::builder()
                 .danger_accept_invalid_certs(true)
                 .timeout(std::time::Duration::from_secs(2))
@@ -111,8 +230,9 @@ impl WidgetLogManager {
 
             let auth_token = zed_config::get_auth_token(&self.config.widget_log_dir).await?;
             let url = format!(
-                "{}:/{}:{}/stats",
-                "https", self.config.proxy_host, self.config.proxy_port
+                "https://{}:{}/stats",
+                self.config.proxy_host,
+                process.port()
             );
 
             let response = client
@@ -134,6 +254,9 @@ impl WidgetLogManager {
 impl Drop for WidgetLogManager {
     fn drop(&mut self) {
         let _ = self.stop();
+        if let Some(settings_sync) = self.settings_sync.take() {
+            settings_sync.abort();
+        }
     }
 }
 
@@ -1,30 +1,190 @@
 use anyhow::{Context, Result};
-use std::path::PathBuf;
-use std::process::{Child, Command, Stdio};
-use tokio::time::{sleep, Duration};
+use serde::{Deserialize, Serialize};
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, timeout, Duration, Instant};
 
-pub struct WidgetLogProcess {
-    process: Option<Child>,
+use crate::rotating_log::RotatingLog;
+
+/// Written next to the proxy so a later session can adopt it
+const PID_FILE_NAME: &str = "widget-log.pid";
+const LOG_FILE_NAME: &str = "proxy.log";
+/// Ports tried after the preferred one before asking the OS for any free port
+const PORT_SEARCH_RANGE: u16 = 10;
+/// Health check interval while waiting for the proxy to come up
+const STARTUP_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Lifecycle state of the Widget-Log proxy, for display in the UI
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum ProxyState {
+    Stopped,
+    /// Launched and waiting for `/health` to respond
+    Starting {
+        attempt: u32,
+    },
+    Healthy {
+        port: u16,
+        adopted: bool,
+    },
+    /// Still running, but failing health checks
+    Degraded {
+        port: u16,
+        failed_checks: u32,
+    },
+    /// Exited unexpectedly; restarted after `retry_in_secs`, or given up on if `None`
+    Crashed {
+        exit_code: Option<i32>,
+        restarts: u32,
+        retry_in_secs: Option<u64>,
+    },
+}
+
+impl ProxyState {
+    pub fn is_healthy(&self) -> bool {
+        matches!(self, ProxyState::Healthy { .. })
+    }
+}
+
+/// Restart, health check, shutdown and log rotation settings for [`WidgetLogProcess`]
+#[derive(Debug, Clone)]
+pub struct SupervisorConfig {
+    pub startup_timeout: Duration,
+    pub health_check_interval: Duration,
+    /// Consecutive failed health checks before a running proxy counts as degraded
+    pub degraded_after: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// Restarts before giving up; the count resets once the proxy stays up for `stable_after`
+    pub max_restarts: u32,
+    pub stable_after: Duration,
+    /// How long to wait after SIGTERM before killing the proxy
+    pub shutdown_timeout: Duration,
+    pub log_max_bytes: u64,
+    pub log_max_files: usize,
+}
+
+impl Default for SupervisorConfig {
+    fn default() -> Self {
+        Self {
+            startup_timeout: Duration::from_secs(30),
+            health_check_interval: Duration::from_secs(5),
+            degraded_after: 3,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+            max_restarts: 5,
+            stable_after: Duration::from_secs(60),
+            shutdown_timeout: Duration::from_secs(5),
+            log_max_bytes: 10 * 1024 * 1024,
+            log_max_files: 5,
+        }
+    }
+}
+
+impl SupervisorConfig {
+    /// Delay before restart number `restarts` (starting at 1), doubling up to `max_backoff`
+    pub fn backoff(&self, restarts: u32) -> Duration {
+        let exponent = restarts.saturating_sub(1).min(16);
+        self.initial_backoff
+            .saturating_mul(1 << exponent)
+            .min(self.max_backoff)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PidFile {
+    pid: u32,
+    host: String,
     port: u16,
+}
+
+/// State shared between `WidgetLogProcess` and its supervisor task
+struct Shared {
     host: String,
+    port: Mutex<u16>,
+    pid: Mutex<Option<u32>>,
+    state: watch::Sender<ProxyState>,
+}
+
+impl Shared {
+    fn port(&self) -> u16 {
+        *self.port.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn set_port(&self, port: u16) {
+        *self.port.lock().unwrap_or_else(|e| e.into_inner()) = port;
+    }
+
+    fn pid(&self) -> Option<u32> {
+        *self.pid.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn set_pid(&self, pid: Option<u32>) {
+        *self.pid.lock().unwrap_or_else(|e| e.into_inner()) = pid;
+    }
+
+    fn set_state(&self, state: ProxyState) {
+        self.state.send_if_modified(|current| {
+            let changed = *current != state;
+            *current = state;
+            changed
+        });
+    }
+}
+
+/// Supervised Widget-Log proxy
+///
+/// The proxy's output goes to `logs/proxy.log`. It is restarted with
+/// exponential backoff when it exits, health checked while it runs, and
+/// stopped with SIGTERM before being killed. A proxy left running by an
+/// earlier session is adopted through its pidfile instead of starting a
+/// second one.
+pub struct WidgetLogProcess {
+    shared: Arc<Shared>,
+    config: SupervisorConfig,
+    shutdown: watch::Sender<bool>,
+    supervisor: Option<JoinHandle<()>>,
 }
 
 impl WidgetLogProcess {
     pub fn new(port: u16, host: String) -> Self {
+        let (state, _) = watch::channel(ProxyState::Stopped);
+        let (shutdown, _) = watch::channel(false);
         Self {
-            process: None,
-            port,
-            host,
+            shared: Arc::new(Shared {
+                host,
+                port: Mutex::new(port),
+                pid: Mutex::new(None),
+                state,
+            }),
+            config: SupervisorConfig::default(),
+            shutdown,
+            supervisor: None,
         }
     }
 
+    pub fn with_supervisor_config(mut self, config: SupervisorConfig) -> Self {
+        self.config = config;
+        self
+    }
+
     /// Start Widget-Log proxy server
     pub async fn start(&mut self, widget_log_dir: PathBuf) -> Result<()> {
-        log::info!("Starting Widget-Log proxy...");
+        if self
+            .supervisor
+            .as_ref()
+            .is_some_and(|supervisor| !supervisor.is_finished())
+        {
+            return Ok(());
+        }
 
-        // Check if Python is available
-        let python = self.detect_python()?;
-        log::info!("Using Python: {}", python);
+        log::info!("Starting Widget-Log proxy...");
 
         // Prepare environment
         let env_file = widget_log_dir.join(".env");
@@ -42,65 +202,110 @@ impl WidgetLogProcess {
             );
         }
 
-        // Start proxy
-        log::info!("Launching proxy at {}:{}", self.host, self.port);
-        let child = Command::new(&python)
-            .arg(&proxy_script)
-            .arg("--host")
-            .arg(&self.host)
-            .arg("--port")
-            .arg(self.port.to_string())
-            .current_dir(&widget_log_dir)
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .spawn()
-            .context("Failed to start Widget-Log proxy")?;
+        let log_path = widget_log_dir.join("logs").join(LOG_FILE_NAME);
+        let proxy_log = RotatingLog::open(
+            &log_path,
+            self.config.log_max_bytes,
+            self.config.log_max_files,
+        )?;
 
-        self.process = Some(child);
+        let pid_path = widget_log_dir.join(PID_FILE_NAME);
+        let adopted = adopt_running_proxy(&pid_path, &self.shared.host).await;
+        // An adopted proxy is already running, so Python is only looked for once it has to be restarted
+        let python = match &adopted {
+            Some(pid_file) => {
+                log::info!(
+                    "Adopting running Widget-Log proxy (pid {}) on port {}",
+                    pid_file.pid,
+                    pid_file.port
+                );
+                self.shared.set_port(pid_file.port);
+                None
+            }
+            None => {
+                let python = detect_python().await?;
+                log::info!("Using Python: {}", python);
+
+                let preferred = self.shared.port();
+                let port = find_free_port(&self.shared.host, preferred)?;
+                if port != preferred {
+                    log::warn!("Port {} is in use, using port {} instead", preferred, port);
+                }
+                self.shared.set_port(port);
+                Some(python)
+            }
+        };
+
+        self.shutdown.send_replace(false);
+        self.shared.set_state(ProxyState::Starting { attempt: 0 });
+
+        let supervisor = Supervisor {
+            shared: self.shared.clone(),
+            config: self.config.clone(),
+            python,
+            proxy_script,
+            widget_log_dir,
+            pid_path,
+            proxy_log: Arc::new(Mutex::new(proxy_log)),
+            shutdown: self.shutdown.subscribe(),
+        };
+        self.supervisor = Some(tokio::spawn(
+            supervisor.run(adopted.map(|pid_file| pid_file.pid)),
+        ));
 
         // Wait for proxy to be ready
-        self.wait_for_ready().await?;
+        if let Err(error) = self.wait_for_ready(&log_path).await {
+            self.shutdown().await?;
+            return Err(error);
+        }
 
         log::info!("Widget-Log proxy started successfully");
         Ok(())
     }
 
-    /// Stop Widget-Log proxy server
+    /// Ask the supervisor to stop the proxy without waiting for it to exit
+    ///
+    /// The proxy is sent SIGTERM right away, and killed by the supervisor if
+    /// it is still running after the shutdown timeout.
     pub fn stop(&mut self) -> Result<()> {
-        if let Some(mut process) = self.process.take() {
+        if self.supervisor.take().is_some() {
             log::info!("Stopping Widget-Log proxy");
-            process.kill().context("Failed to stop Widget-Log proxy")?;
+            self.shutdown.send_replace(true);
+            // Signal directly as well, in case the runtime driving the supervisor is shutting down
+            if let Some(pid) = self.shared.pid() {
+                terminate(pid);
+            }
+        }
+        Ok(())
+    }
+
+    /// Stop the proxy and wait until it has exited
+    pub async fn shutdown(&mut self) -> Result<()> {
+        if let Some(supervisor) = self.supervisor.take() {
+            log::info!("Stopping Widget-Log proxy");
+            self.shutdown.send_replace(true);
+            supervisor.await.context("Widget-Log supervisor panicked")?;
         }
         Ok(())
     }
 
     /// Check if proxy is healthy
     pub async fn health_check(&self) -> Result<bool> {
-        let client = reqwest::Client::builder()
-            .danger_accept_invalid_certs(true)
-            .timeout(Duration::from_secs(2))
-            .build()?;
+        Ok(check_health(&self.shared.host, self.port()).await)
+    }
 
-        let url = format!("https://{}:{}/health", self.host, self.port);
-        match client.get(&url).send().await {
-            Ok(resp) => Ok(resp.status().is_success()),
-            Err(_) => Ok(false),
-        }
+    pub fn state(&self) -> ProxyState {
+        self.shared.state.borrow().clone()
     }
 
-    fn detect_python(&self) -> Result<String> {
-        // Try python3, then python
-        for cmd in &["python3", "python"] {
-            if Command::new(cmd)
-                .arg("--version")
-                .output()
-                .map(|o| o.status.success())
-                .unwrap_or(false)
-            {
-                return Ok(cmd.to_string());
-            }
-        }
-        anyhow::bail!("Python 3 not found. Please install Python 3.8+")
+    /// Receive every state change, e.g. to update a status indicator
+    pub fn subscribe(&self) -> watch::Receiver<ProxyState> {
+        self.shared.state.subscribe()
+    }
+
+    /// Port the proxy listens on, which may differ from the configured one
+    pub fn port(&self) -> u16 {
+        self.shared.port()
     }
 
     async fn create_default_env(&self, env_file: &PathBuf) -> Result<()> {
@@ -123,21 +328,52 @@ impl WidgetLogProcess {
         base64::encode(&bytes)
     }
 
-    async fn wait_for_ready(&self) -> Result<()> {
+    async fn wait_for_ready(&self, log_path: &Path) -> Result<()> {
         log::info!("Waiting for Widget-Log proxy to be ready...");
 
-        for i in 0..30 {
-            sleep(Duration::from_secs(1)).await;
-            if self.health_check().await? {
-                log::info!("Widget-Log proxy ready after {} seconds", i + 1);
-                return Ok(());
+        let start = Instant::now();
+        let mut state = self.subscribe();
+        let ready = timeout(self.config.startup_timeout, async {
+            loop {
+                match &*state.borrow_and_update() {
+                    ProxyState::Healthy { .. } => return Ok(()),
+                    ProxyState::Stopped
+                    | ProxyState::Crashed {
+                        retry_in_secs: None,
+                        ..
+                    } => anyhow::bail!(
+                        "Widget-Log proxy exited during startup, see {}",
+                        log_path.display()
+                    ),
+                    _ => {}
+                }
+                state
+                    .changed()
+                    .await
+                    .context("Widget-Log supervisor stopped")?;
+            }
+        })
+        .await;
+
+        match ready {
+            Ok(result) => {
+                result?;
+                log::info!(
+                    "Widget-Log proxy ready after {:.1} seconds",
+                    start.elapsed().as_secs_f32()
+                );
+                Ok(())
             }
+            Err(_) => anyhow::bail!(
+                "Widget-Log proxy failed to start within {} seconds, see {}",
+                self.config.startup_timeout.as_secs(),
+                log_path.display()
+            ),
         }
-        anyhow::bail!("Widget-Log proxy failed to start within 30 seconds")
     }
 
     pub fn get_proxy_url(&self) -> String {
-        format!("https://{}:{}/v1", self.host, self.port)
+        proxy_url(&self.shared.host, self.port())
     }
 }
 
@@ -146,3 +382,615 @@ impl Drop for WidgetLogProcess {
         let _ = self.stop();
     }
 }
+
+enum Outcome {
+    Shutdown,
+    Exited(Option<i32>),
+}
+
+enum Event {
+    Exited(Option<i32>),
+    Shutdown,
+    HealthCheck,
+}
+
+/// Background task that owns the proxy process
+struct Supervisor {
+    shared: Arc<Shared>,
+    config: SupervisorConfig,
+    /// Detected on the first launch when the supervisor started by adopting a proxy
+    python: Option<String>,
+    proxy_script: PathBuf,
+    widget_log_dir: PathBuf,
+    pid_path: PathBuf,
+    proxy_log: Arc<Mutex<RotatingLog>>,
+    shutdown: watch::Receiver<bool>,
+}
+
+impl Supervisor {
+    async fn run(mut self, adopted_pid: Option<u32>) {
+        if let Some(pid) = adopted_pid {
+            if let Outcome::Shutdown = self.monitor_adopted(pid).await {
+                self.shared.set_state(ProxyState::Stopped);
+                return;
+            }
+            self.select_port();
+        }
+
+        let mut restarts = 0;
+        loop {
+            if *self.shutdown.borrow() {
+                break;
+            }
+
+            self.shared
+                .set_state(ProxyState::Starting { attempt: restarts });
+            let started = Instant::now();
+            let exit_code = match self.launch().await {
+                Ok(child) => match self.monitor(child).await {
+                    Outcome::Shutdown => break,
+                    Outcome::Exited(exit_code) => exit_code,
+                },
+                Err(error) => {
+                    self.log_event(&format!("Failed to launch proxy: {:#}", error));
+                    None
+                }
+            };
+            self.shared.set_pid(None);
+            let _ = std::fs::remove_file(&self.pid_path);
+
+            if started.elapsed() >= self.config.stable_after {
+                restarts = 0;
+            }
+            if restarts >= self.config.max_restarts {
+                self.log_event(&format!(
+                    "Proxy exited {} times in a row, giving up",
+                    restarts + 1
+                ));
+                self.shared.set_state(ProxyState::Crashed {
+                    exit_code,
+                    restarts,
+                    retry_in_secs: None,
+                });
+                return;
+            }
+
+            restarts += 1;
+            let delay = self.config.backoff(restarts);
+            self.log_event(&format!(
+                "Proxy exited with {}, restarting in {:?} (attempt {})",
+                exit_code.map_or("no exit code".to_string(), |code| format!("code {}", code)),
+                delay,
+                restarts
+            ));
+            self.shared.set_state(ProxyState::Crashed {
+                exit_code,
+                restarts,
+                retry_in_secs: Some(delay.as_secs()),
+            });
+
+            let mut shutdown = self.shutdown.clone();
+            tokio::select! {
+                _ = sleep(delay) => {}
+                _ = shutdown.wait_for(|stop| *stop) => break,
+            }
+
+            self.select_port();
+        }
+
+        self.shared.set_state(ProxyState::Stopped);
+    }
+
+    /// Something else may have taken our port while the proxy was down
+    fn select_port(&self) {
+        let port = self.shared.port();
+        match find_free_port(&self.shared.host, port) {
+            Ok(free_port) => self.shared.set_port(free_port),
+            Err(error) => log::warn!("Failed to find a free port: {:#}", error),
+        }
+    }
+
+    async fn launch(&mut self) -> Result<Child> {
+        let python = match &self.python {
+            Some(python) => python.clone(),
+            None => {
+                let python = detect_python().await?;
+                log::info!("Using Python: {}", python);
+                self.python = Some(python.clone());
+                python
+            }
+        };
+        self.spawn(&python)
+    }
+
+    fn spawn(&self, python: &str) -> Result<Child> {
+        let port = self.shared.port();
+        log::info!("Launching proxy at {}:{}", self.shared.host, port);
+
+        let mut child = Command::new(python)
+            .arg(&self.proxy_script)
+            .arg("--host")
+            .arg(&self.shared.host)
+            .arg("--port")
+            .arg(port.to_string())
+            .current_dir(&self.widget_log_dir)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("Failed to start Widget-Log proxy")?;
+
+        if let Some(stdout) = child.stdout.take() {
+            tokio::spawn(pipe_to_log(stdout, "stdout", self.proxy_log.clone()));
+        }
+        if let Some(stderr) = child.stderr.take() {
+            tokio::spawn(pipe_to_log(stderr, "stderr", self.proxy_log.clone()));
+        }
+
+        let pid = child.id();
+        self.shared.set_pid(pid);
+        if let Some(pid) = pid {
+            let pid_file = PidFile {
+                pid,
+                host: self.shared.host.clone(),
+                port,
+            };
+            if let Err(error) = serde_json::to_string(&pid_file)
+                .map_err(anyhow::Error::from)
+                .and_then(|content| Ok(std::fs::write(&self.pid_path, content)?))
+            {
+                log::warn!("Failed to write Widget-Log pidfile: {:#}", error);
+            }
+        }
+        self.log_event(&format!(
+            "Launched proxy (pid {}) on {}:{}",
+            pid.map_or("unknown".to_string(), |pid| pid.to_string()),
+            self.shared.host,
+            port
+        ));
+        Ok(child)
+    }
+
+    /// Watch a process we spawned until it exits or shutdown is requested
+    async fn monitor(&mut self, mut child: Child) -> Outcome {
+        let started = Instant::now();
+        let mut ever_healthy = false;
+        let mut failed_checks = 0;
+        let mut shutdown = self.shutdown.clone();
+
+        loop {
+            let poll = if ever_healthy {
+                self.config.health_check_interval
+            } else {
+                STARTUP_POLL_INTERVAL
+            };
+            let event = tokio::select! {
+                status = child.wait() => Event::Exited(status.ok().and_then(|status| status.code())),
+                _ = shutdown.wait_for(|stop| *stop) => Event::Shutdown,
+                _ = sleep(poll) => Event::HealthCheck,
+            };
+
+            match event {
+                Event::Exited(exit_code) => return Outcome::Exited(exit_code),
+                Event::Shutdown => {
+                    self.terminate_child(&mut child).await;
+                    return Outcome::Shutdown;
+                }
+                Event::HealthCheck => {
+                    let port = self.shared.port();
+                    if check_health(&self.shared.host, port).await {
+                        if !ever_healthy {
+                            self.log_event(&format!(
+                                "Proxy healthy after {:.1} seconds",
+                                started.elapsed().as_secs_f32()
+                            ));
+                        }
+                        ever_healthy = true;
+                        failed_checks = 0;
+                        self.shared.set_state(ProxyState::Healthy {
+                            port,
+                            adopted: false,
+                        });
+                    } else if ever_healthy {
+                        failed_checks += 1;
+                        if failed_checks >= self.config.degraded_after {
+                            self.shared.set_state(ProxyState::Degraded {
+                                port,
+                                failed_checks,
+                            });
+                        }
+                    } else if started.elapsed() >= self.config.startup_timeout {
+                        self.log_event("Proxy did not become healthy in time, killing it");
+                        let _ = child.kill().await;
+                        return Outcome::Exited(None);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Watch a proxy left running by an earlier session until it goes away
+    async fn monitor_adopted(&mut self, pid: u32) -> Outcome {
+        let port = self.shared.port();
+        self.shared.set_pid(Some(pid));
+        self.shared.set_state(ProxyState::Healthy {
+            port,
+            adopted: true,
+        });
+        self.log_event(&format!(
+            "Adopted running proxy (pid {}) on port {}",
+            pid, port
+        ));
+
+        let mut failed_checks = 0;
+        let mut shutdown = self.shutdown.clone();
+        loop {
+            let stop = tokio::select! {
+                _ = shutdown.wait_for(|stop| *stop) => true,
+                _ = sleep(self.config.health_check_interval) => false,
+            };
+
+            if stop {
+                self.terminate_pid(pid).await;
+                let _ = std::fs::remove_file(&self.pid_path);
+                self.shared.set_pid(None);
+                return Outcome::Shutdown;
+            }
+            if !is_alive(pid) {
+                self.log_event("Adopted proxy exited");
+                let _ = std::fs::remove_file(&self.pid_path);
+                self.shared.set_pid(None);
+                return Outcome::Exited(None);
+            }
+            if check_health(&self.shared.host, port).await {
+                failed_checks = 0;
+                self.shared.set_state(ProxyState::Healthy {
+                    port,
+                    adopted: true,
+                });
+            } else {
+                failed_checks += 1;
+                if failed_checks >= self.config.degraded_after {
+                    self.shared.set_state(ProxyState::Degraded {
+                        port,
+                        failed_checks,
+                    });
+                }
+            }
+        }
+    }
+
+    async fn terminate_child(&self, child: &mut Child) {
+        if let Some(pid) = child.id() {
+            if terminate(pid) {
+                if timeout(self.config.shutdown_timeout, child.wait())
+                    .await
+                    .is_ok()
+                {
+                    self.log_event("Proxy stopped");
+                    return;
+                }
+                self.log_event("Proxy did not exit after SIGTERM, killing it");
+            }
+        }
+        let _ = child.kill().await;
+    }
+
+    async fn terminate_pid(&self, pid: u32) {
+        if terminate(pid) {
+            let deadline = Instant::now() + self.config.shutdown_timeout;
+            while Instant::now() < deadline {
+                if !is_alive(pid) {
+                    self.log_event("Proxy stopped");
+                    return;
+                }
+                sleep(Duration::from_millis(100)).await;
+            }
+            self.log_event("Proxy did not exit after SIGTERM, killing it");
+        }
+        force_kill(pid);
+    }
+
+    /// Record supervisor decisions in the proxy log as well as the app log
+    fn log_event(&self, message: &str) {
+        log::info!("Widget-Log: {}", message);
+        let mut proxy_log = self.proxy_log.lock().unwrap_or_else(|e| e.into_inner());
+        if let Err(error) = proxy_log.write_line("supervisor", message) {
+            log::warn!("Failed to write Widget-Log proxy log: {:#}", error);
+        }
+    }
+}
+
+async fn pipe_to_log(
+    output: impl AsyncRead + Unpin,
+    source: &'static str,
+    proxy_log: Arc<Mutex<RotatingLog>>,
+) {
+    let mut lines = BufReader::new(output).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        let mut proxy_log = proxy_log.lock().unwrap_or_else(|e| e.into_inner());
+        if let Err(error) = proxy_log.write_line(source, &line) {
+            log::warn!("Failed to write Widget-Log proxy log: {:#}", error);
+            return;
+        }
+    }
+}
+
+/// URL of the OpenAI-compatible API served by a proxy on `port`
+pub(crate) fn proxy_url(host: &str, port: u16) -> String {
+    format!("https://{}:{}/v1", host, port)
+}
+
+async fn detect_python() -> Result<String> {
+    // Try python3, then python
+    for cmd in &["python3", "python"] {
+        if Command::new(cmd)
+            .arg("--version")
+            .output()
+            .await
+            .map(|o| o.status.success())
+            .unwrap_or(false)
+        {
+            return Ok(cmd.to_string());
+        }
+    }
+    anyhow::bail!("Python 3 not found. Please install Python 3.8+")
+}
+
+async fn check_health(host: &str, port: u16) -> bool {
+    let Ok(client) = reqwest::Client::builder()
+        .danger_accept_invalid_certs(true)
+        .timeout(Duration::from_secs(2))
+        .build()
+    else {
+        return false;
+    };
+
+    let url = format!("https://{}:{}/health", host, port);
+    match client.get(&url).send().await {
+        Ok(resp) => resp.status().is_success(),
+        Err(_) => false,
+    }
+}
+
+/// A proxy from the pidfile that is still running and healthy
+async fn adopt_running_proxy(pid_path: &Path, host: &str) -> Option<PidFile> {
+    let content = tokio::fs::read_to_string(pid_path).await.ok()?;
+    let pid_file = serde_json::from_str::<PidFile>(&content).ok();
+    if let Some(pid_file) = pid_file {
+        if pid_file.host == host
+            && is_alive(pid_file.pid)
+            && check_health(&pid_file.host, pid_file.port).await
+        {
+            return Some(pid_file);
+        }
+    }
+    log::info!("Removing stale Widget-Log pidfile");
+    let _ = tokio::fs::remove_file(pid_path).await;
+    None
+}
+
+/// `preferred` if it is free, else one of the next few ports, else any free port
+fn find_free_port(host: &str, preferred: u16) -> Result<u16> {
+    let candidates = (0..PORT_SEARCH_RANGE)
+        .filter_map(|offset| preferred.checked_add(offset))
+        .filter(|port| *port != 0);
+    for port in candidates {
+        if TcpListener::bind((host, port)).is_ok() {
+            return Ok(port);
+        }
+    }
+    let listener = TcpListener::bind((host, 0))
+        .with_context(|| format!("No free port available on {}", host))?;
+    Ok(listener.local_addr()?.port())
+}
+
+#[cfg(unix)]
+fn send_signal(pid: u32, signal: libc::c_int) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+    // SAFETY: kill has no memory safety requirements
+    unsafe { libc::kill(pid, signal) == 0 }
+}
+
+/// Ask the process to exit, returning false if it could not be signalled
+#[cfg(unix)]
+fn terminate(pid: u32) -> bool {
+    send_signal(pid, libc::SIGTERM)
+}
+
+#[cfg(unix)]
+fn is_alive(pid: u32) -> bool {
+    send_signal(pid, 0)
+}
+
+#[cfg(unix)]
+fn force_kill(pid: u32) {
+    send_signal(pid, libc::SIGKILL);
+}
+
+// Without signals, the supervisor falls back to killing its own child, and
+// proxies from earlier sessions are never adopted since they cannot be stopped.
+#[cfg(not(unix))]
+fn terminate(_pid: u32) -> bool {
+    false
+}
+
+#[cfg(not(unix))]
+fn is_alive(_pid: u32) -> bool {
+    false
+}
+
+#[cfg(not(unix))]
+fn force_kill(_pid: u32) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_doubles_up_to_max() {
+        let config = SupervisorConfig::default();
+        let delays: Vec<u64> = (1..=8).map(|n| config.backoff(n).as_secs()).collect();
+        assert_eq!(delays, [1, 2, 4, 8, 16, 32, 60, 60]);
+        assert_eq!(config.backoff(u32::MAX), config.max_backoff);
+    }
+
+    #[test]
+    fn test_find_free_port_skips_used_port() {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let used = listener.local_addr().unwrap().port();
+
+        let port = find_free_port("127.0.0.1", used).unwrap();
+        assert_ne!(port, used);
+        assert!(TcpListener::bind(("127.0.0.1", port)).is_ok());
+
+        drop(listener);
+        assert_eq!(find_free_port("127.0.0.1", used).unwrap(), used);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_stale_pidfile_is_removed() {
+        let dir = std::env::temp_dir().join(format!("widget-log-pid-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let pid_path = dir.join(PID_FILE_NAME);
+
+        // Our own pid is alive, but nothing answers health checks on port 1
+        let pid_file = PidFile {
+            pid: std::process::id(),
+            host: "127.0.0.1".to_string(),
+            port: 1,
+        };
+        std::fs::write(&pid_path, serde_json::to_string(&pid_file).unwrap()).unwrap();
+        assert!(is_alive(pid_file.pid));
+
+        assert!(adopt_running_proxy(&pid_path, "127.0.0.1").await.is_none());
+        assert!(!pid_path.exists());
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[cfg(all(unix, feature = "python"))]
+    #[tokio::test]
+    async fn test_crashed_proxy_restarts_on_a_new_port() {
+        let dir =
+            std::env::temp_dir().join(format!("widget-log-restart-test-{}", std::process::id()));
+        let app_dir = dir.join("app");
+        std::fs::create_dir_all(&app_dir).unwrap();
+        std::fs::write(
+            app_dir.join("secure_proxy.py"),
+            include_str!("../test_data/fake_proxy.py"),
+        )
+        .unwrap();
+        std::fs::write(
+            app_dir.join("fake_proxy_cert.pem"),
+            include_str!("../test_data/fake_proxy_cert.pem"),
+        )
+        .unwrap();
+        std::fs::write(
+            app_dir.join("fake_proxy_key.pem"),
+            include_str!("../test_data/fake_proxy_key.pem"),
+        )
+        .unwrap();
+
+        let config = crate::WidgetLogConfig {
+            widget_log_dir: dir.clone(),
+            proxy_port: TcpListener::bind(("127.0.0.1", 0))
+                .unwrap()
+                .local_addr()
+                .unwrap()
+                .port(),
+            zed_settings_path: dir.join("settings.json"),
+            ..Default::default()
+        };
+        let mut process = WidgetLogProcess::new(config.proxy_port, config.proxy_host.clone())
+            .with_supervisor_config(SupervisorConfig {
+                startup_timeout: Duration::from_secs(20),
+                health_check_interval: Duration::from_millis(100),
+                initial_backoff: Duration::from_millis(500),
+                ..Default::default()
+            });
+        process.start(dir.clone()).await.unwrap();
+        let first_port = process.port();
+        crate::WidgetLogManager::configure_zed(&config, &process.get_proxy_url())
+            .await
+            .unwrap();
+        let first_url = proxy_url("127.0.0.1", first_port);
+        let settings = std::fs::read_to_string(&config.zed_settings_path).unwrap();
+        assert!(settings.contains(&first_url));
+        let settings_sync = crate::WidgetLogManager::sync_zed_settings(&config, &process);
+
+        // Keep the crashed proxy's port busy so the restart has to move
+        let client = reqwest::Client::builder()
+            .danger_accept_invalid_certs(true)
+            .build()
+            .unwrap();
+        let _ = client
+            .get(format!("https://127.0.0.1:{}/crash", first_port))
+            .send()
+            .await;
+        let _first_port_taken = timeout(Duration::from_secs(5), async {
+            loop {
+                if let Ok(listener) = TcpListener::bind(("127.0.0.1", first_port)) {
+                    return listener;
+                }
+                sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .unwrap();
+
+        let mut state = process.subscribe();
+        let second_port = timeout(Duration::from_secs(20), async {
+            loop {
+                match &*state.borrow_and_update() {
+                    ProxyState::Healthy { port, .. } if *port != first_port => return *port,
+                    _ => {}
+                }
+                state.changed().await.unwrap();
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(process.port(), second_port);
+
+        let settings_url = proxy_url("127.0.0.1", second_port);
+        timeout(Duration::from_secs(5), async {
+            loop {
+                let settings = std::fs::read_to_string(&config.zed_settings_path).unwrap();
+                if settings.contains(&settings_url) {
+                    break;
+                }
+                sleep(Duration::from_millis(50)).await;
+            }
+        })
+        .await
+        .unwrap();
+        let settings = std::fs::read_to_string(&config.zed_settings_path).unwrap();
+        assert!(!settings.contains(&first_url));
+
+        settings_sync.abort();
+        process.shutdown().await.unwrap();
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn test_state_serialization() {
+        let state = ProxyState::Crashed {
+            exit_code: Some(1),
+            restarts: 2,
+            retry_in_secs: Some(2),
+        };
+        assert_eq!(
+            serde_json::to_value(&state).unwrap(),
+            serde_json::json!({
+                "state": "crashed",
+                "exit_code": 1,
+                "restarts": 2,
+                "retry_in_secs": 2
+            })
+        );
+    }
+}
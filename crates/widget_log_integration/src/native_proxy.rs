@@ -0,0 +1,413 @@
+use anyhow::{Context, Result};
+use pyo3_bridge::{CacheMissReason, CacheStatus, MessageRole, ProxyRequest, ProxyResponse};
+use serde::Deserialize;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::semantic_cache::{CacheLookup, SemanticCache};
+
+const ANTHROPIC_API_URL: &str = "https://api.anthropic.com";
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+/// Drop-in replacement for `PyO3Bridge` that serves prompts from a
+/// [`SemanticCache`] and forwards misses straight to the Anthropic API
+pub struct NativeCacheProxy {
+    cache: Arc<SemanticCache>,
+    client: reqwest::Client,
+    api_url: String,
+    model: String,
+    max_tokens: usize,
+}
+
+impl NativeCacheProxy {
+    /// Answer cache misses with `model`, unless a request names another one
+    pub fn new(cache: Arc<SemanticCache>, model: impl Into<String>) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(300))
+            .build()
+            .context("Failed to build HTTP client")?;
+
+        Ok(Self {
+            cache,
+            client,
+            api_url: ANTHROPIC_API_URL.to_string(),
+            model: model.into(),
+            max_tokens: 8192,
+        })
+    }
+
+    pub fn with_api_url(mut self, api_url: impl Into<String>) -> Self {
+        self.api_url = api_url.into();
+        self
+    }
+
+    pub fn with_max_tokens(mut self, max_tokens: usize) -> Self {
+        self.max_tokens = max_tokens;
+        self
+    }
+
+    pub fn cache(&self) -> &SemanticCache {
+        &self.cache
+    }
+
+    /// Answer from the cache, or call the API and cache the response
+    ///
+    /// Responses are only reused for requests answered by the same model.
+    /// Requests with `skip_cache` set always go to the API and are not cached.
+    pub async fn send_to_proxy(&self, request: ProxyRequest) -> Result<ProxyResponse> {
+        let start = Instant::now();
+        let key = cache_key(&request);
+        let model = request.model.clone().unwrap_or_else(|| self.model.clone());
+
+        let miss_reason = if request.skip_cache {
+            log::debug!("Skipping semantic cache for this request");
+            CacheMissReason::CacheDisabled
+        } else {
+            let cache = self.cache.clone();
+            let lookup_model = model.clone();
+            let lookup_key = key.clone();
+            let lookup =
+                tokio::task::spawn_blocking(move || cache.lookup(&lookup_model, &lookup_key))
+                    .await
+                    .context("Failed to spawn blocking task")??;
+
+            match lookup {
+                CacheLookup::Hit {
+                    response,
+                    similarity_score,
+                    ..
+                } => {
+                    let latency_ms = start.elapsed().as_millis() as u64;
+                    return Ok(ProxyResponse {
+                        cache_status: CacheStatus::Hit {
+                            response: response.clone(),
+                            latency_ms,
+                            similarity_score,
+                        },
+                        response_text: Some(response),
+                        tokens_used: 0,
+                        latency_ms,
+                        model: None,
+                        miss_reason: None,
+                    });
+                }
+                CacheLookup::Miss { reason, .. } => {
+                    log::debug!("Semantic cache miss: {:?}", reason);
+                    reason
+                }
+            }
+        };
+
+        let (response, tokens_used) = self.call_api(&request, &model).await?;
+
+        if !request.skip_cache {
+            let cache = self.cache.clone();
+            let cached_model = model.clone();
+            let cached_response = response.clone();
+            tokio::task::spawn_blocking(move || {
+                cache.insert(&cached_model, &key, &cached_response, tokens_used)
+            })
+            .await
+            .context("Failed to spawn blocking task")??;
+        }
+
+        Ok(ProxyResponse {
+            cache_status: CacheStatus::Miss,
+            response_text: Some(response),
+            tokens_used,
+            latency_ms: start.elapsed().as_millis() as u64,
+            model: Some(model),
+            miss_reason: Some(miss_reason),
+        })
+    }
+
+    async fn call_api(&self, request: &ProxyRequest, model: &str) -> Result<(String, usize)> {
+        if request.api_key.is_empty() {
+            anyhow::bail!("No Anthropic API key configured");
+        }
+
+        let mut messages = request
+            .history
+            .iter()
+            .map(|message| {
+                let role = match message.role {
+                    MessageRole::User => "user",
+                    MessageRole::Assistant => "assistant",
+                };
+                serde_json::json!({ "role": role, "content": message.content })
+            })
+            .collect::<Vec<_>>();
+        let content = if request.chunks.is_empty() {
+            serde_json::json!(request.prompt)
+        } else {
+            request
+                .chunks
+                .iter()
+                .map(|chunk| serde_json::json!({ "type": "text", "text": chunk }))
+                .collect()
+        };
+        messages.push(serde_json::json!({ "role": "user", "content": content }));
+
+        let body = serde_json::json!({
+            "model": model,
+            "max_tokens": self.max_tokens,
+            "messages": messages,
+        });
+
+        let response = self
+            .client
+            .post(format!(
+                "{}/v1/messages",
+                self.api_url.trim_end_matches('/')
+            ))
+            .header("x-api-key", &request.api_key)
+            .header("anthropic-version", ANTHROPIC_VERSION)
+            .json(&body)
+            .send()
+            .await
+            .context("Failed to reach Anthropic API")?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let text = response.text().await.unwrap_or_default();
+            anyhow::bail!("Anthropic API returned {}: {}", status, text);
+        }
+
+        let message: MessagesResponse = response
+            .json()
+            .await
+            .context("Failed to parse Anthropic API response")?;
+
+        let text = message
+            .content
+            .into_iter()
+            .filter_map(|block| block.text)
+            .collect::<Vec<_>>()
+            .join("");
+        let tokens_used = message.usage.input_tokens + message.usage.output_tokens;
+        Ok((text, tokens_used))
+    }
+
+    /// Cache statistics, in the same shape as `PyO3Bridge::get_cache_stats`
+    pub async fn get_cache_stats(&self) -> Result<serde_json::Value> {
+        Ok(self.cache.stats())
+    }
+}
+
+/// The text a request is cached under. A follow-up prompt only means
+/// something after the messages before it, and may refer to files that were
+/// only sent in full earlier, so the whole conversation is the key.
+fn cache_key(request: &ProxyRequest) -> String {
+    if request.history.is_empty() {
+        return request.prompt.clone();
+    }
+    let mut key = String::new();
+    for message in &request.history {
+        key.push_str(&message.content);
+        key.push_str("\n\n");
+    }
+    key.push_str(&request.prompt);
+    key
+}
+
+#[derive(Deserialize)]
+struct MessagesResponse {
+    content: Vec<ContentBlock>,
+    usage: Usage,
+}
+
+#[derive(Deserialize)]
+struct ContentBlock {
+    #[serde(default)]
+    text: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Usage {
+    input_tokens: usize,
+    output_tokens: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::semantic_cache::SemanticCacheConfig;
+    use pyo3_bridge::types::TokenOptimization;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::Mutex;
+
+    const MODEL: &str = "claude-sonnet-4";
+
+    /// A Messages API that answers every request and records the bodies it got
+    struct FakeApi {
+        url: String,
+        requests: Arc<Mutex<Vec<serde_json::Value>>>,
+    }
+
+    impl FakeApi {
+        fn start() -> Self {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let url = format!("http://{}", listener.local_addr().unwrap());
+            let requests = Arc::new(Mutex::new(Vec::new()));
+            let recorded = requests.clone();
+            std::thread::spawn(move || {
+                for stream in listener.incoming() {
+                    let Ok(mut stream) = stream else { break };
+                    let mut reader = BufReader::new(stream.try_clone().unwrap());
+                    let mut content_length = 0;
+                    loop {
+                        let mut line = String::new();
+                        reader.read_line(&mut line).unwrap();
+                        let line = line.trim_end();
+                        if line.is_empty() {
+                            break;
+                        }
+                        if let Some((name, value)) = line.split_once(':') {
+                            if name.eq_ignore_ascii_case("content-length") {
+                                content_length = value.trim().parse().unwrap();
+                            }
+                        }
+                    }
+                    let mut body = vec![0; content_length];
+                    reader.read_exact(&mut body).unwrap();
+
+                    let mut recorded = recorded.lock().unwrap();
+                    recorded.push(serde_json::from_slice(&body).unwrap());
+                    let response = serde_json::json!({
+                        "content": [{ "type": "text", "text": format!("answer {}", recorded.len()) }],
+                        "usage": { "input_tokens": 10, "output_tokens": 5 },
+                    })
+                    .to_string();
+                    write!(
+                        stream,
+                        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        response.len(),
+                        response
+                    )
+                    .unwrap();
+                }
+            });
+            Self { url, requests }
+        }
+
+        fn requests(&self) -> Vec<serde_json::Value> {
+            self.requests.lock().unwrap().clone()
+        }
+    }
+
+    fn proxy(api: &FakeApi) -> NativeCacheProxy {
+        let cache = SemanticCache::open_in_memory(SemanticCacheConfig::default()).unwrap();
+        NativeCacheProxy::new(Arc::new(cache), MODEL)
+            .unwrap()
+            .with_api_url(&api.url)
+    }
+
+    fn request(prompt: &str) -> ProxyRequest {
+        ProxyRequest {
+            prompt: prompt.to_string(),
+            cache_anchors: Vec::new(),
+            api_key: "test-key".to_string(),
+            optimization: TokenOptimization::default(),
+            model: None,
+            history: Vec::new(),
+            chunks: Vec::new(),
+            skip_cache: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_miss_calls_api_and_caches_the_response() {
+        let api = FakeApi::start();
+        let proxy = proxy(&api);
+
+        let response = proxy
+            .send_to_proxy(request("How do I reverse a Vec in Rust?"))
+            .await
+            .unwrap();
+        assert!(matches!(response.cache_status, CacheStatus::Miss));
+        assert!(matches!(
+            response.miss_reason,
+            Some(CacheMissReason::CacheEmpty)
+        ));
+        assert_eq!(response.response_text.as_deref(), Some("answer 1"));
+        assert_eq!(response.tokens_used, 15);
+        assert_eq!(response.model.as_deref(), Some(MODEL));
+        assert_eq!(api.requests()[0]["model"], MODEL);
+
+        let response = proxy
+            .send_to_proxy(request("How do I reverse a Vec in Rust?"))
+            .await
+            .unwrap();
+        match response.cache_status {
+            CacheStatus::Hit { response, .. } => assert_eq!(response, "answer 1"),
+            CacheStatus::Miss => panic!("expected a cache hit"),
+        }
+        assert_eq!(api.requests().len(), 1);
+
+        let response = proxy
+            .send_to_proxy(request("Explain the borrow checker's two-phase borrows"))
+            .await
+            .unwrap();
+        assert!(matches!(
+            response.miss_reason,
+            Some(CacheMissReason::SimilarityBelowThreshold)
+        ));
+        assert_eq!(api.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_responses_are_not_shared_between_models() {
+        let api = FakeApi::start();
+        let proxy = proxy(&api);
+
+        proxy
+            .send_to_proxy(request("How do I reverse a Vec in Rust?"))
+            .await
+            .unwrap();
+
+        let mut other_model = request("How do I reverse a Vec in Rust?");
+        other_model.model = Some("claude-haiku-4".to_string());
+        let response = proxy.send_to_proxy(other_model.clone()).await.unwrap();
+        assert!(matches!(response.cache_status, CacheStatus::Miss));
+        assert!(matches!(
+            response.miss_reason,
+            Some(CacheMissReason::CacheEmpty)
+        ));
+        assert_eq!(response.response_text.as_deref(), Some("answer 2"));
+        assert_eq!(api.requests()[1]["model"], "claude-haiku-4");
+
+        let response = proxy.send_to_proxy(other_model).await.unwrap();
+        match response.cache_status {
+            CacheStatus::Hit { response, .. } => assert_eq!(response, "answer 2"),
+            CacheStatus::Miss => panic!("expected a cache hit"),
+        }
+        assert_eq!(proxy.cache().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_skip_cache_bypasses_lookup_and_insert() {
+        let api = FakeApi::start();
+        let proxy = proxy(&api);
+
+        proxy
+            .send_to_proxy(request("How do I reverse a Vec in Rust?"))
+            .await
+            .unwrap();
+
+        let mut uncached = request("How do I reverse a Vec in Rust?");
+        uncached.skip_cache = true;
+        let response = proxy.send_to_proxy(uncached.clone()).await.unwrap();
+        assert!(matches!(response.cache_status, CacheStatus::Miss));
+        assert!(matches!(
+            response.miss_reason,
+            Some(CacheMissReason::CacheDisabled)
+        ));
+        assert_eq!(response.response_text.as_deref(), Some("answer 2"));
+
+        uncached.prompt = "What is a lifetime?".to_string();
+        proxy.send_to_proxy(uncached).await.unwrap();
+        assert_eq!(api.requests().len(), 3);
+        assert_eq!(proxy.cache().len(), 1);
+    }
+}
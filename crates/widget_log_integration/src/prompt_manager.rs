@@ -1,19 +1,49 @@
-use anyhow::{Context, Result};
+#[cfg(feature = "python")]
+use anyhow::Context;
+use anyhow::Result;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 
 use burn_lm_router::BurnLmRouter;
-use prompt_management_agent::{AgentConfig, PromptManagementAgent, RefinedPrompt, ZedContext};
-use pyo3_bridge::{ProxyRequest, PyO3Bridge};
+use prompt_management_agent::{ChangeStatus, PromptManagementAgent, RefinedPrompt, ZedContext};
+use pyo3_bridge::telemetry::{CacheMissReason, TelemetryEvent};
+#[cfg(feature = "python")]
+use pyo3_bridge::PyO3Bridge;
+use pyo3_bridge::{MessageRole, ProxyMessage, ProxyRequest, ProxyResponse};
 
 use crate::config::WidgetLogConfig;
+use crate::integrated_config::IntegratedConfig;
 use crate::lifecycle::WidgetLogProcess;
+use crate::native_proxy::NativeCacheProxy;
+use crate::rotating_log::RotatingLog;
+use crate::semantic_cache::SemanticCache;
+
+/// Size at which the routing telemetry log is rotated
+const TELEMETRY_MAX_BYTES: u64 = 10 * 1024 * 1024;
+/// Routing telemetry files kept, including the current one
+const TELEMETRY_MAX_FILES: usize = 3;
 
 /// Integrated prompt manager that orchestrates all layers
 pub struct IntegratedPromptManager {
     management_agent: Option<PromptManagementAgent>,
+    native_cache: Option<NativeCacheProxy>,
+    #[cfg(feature = "python")]
     pyo3_bridge: Option<PyO3Bridge>,
     burn_router: BurnLmRouter,
     widget_log_process: Option<WidgetLogProcess>,
     config: WidgetLogConfig,
+    routing_telemetry: Option<Mutex<RotatingLog>>,
+    next_prompt_id: AtomicU64,
+    conversation: Vec<ConversationTurn>,
+}
+
+/// A prompt answered earlier in the conversation
+struct ConversationTurn {
+    /// The prompt as written, before compression
+    prompt: String,
+    /// The prompt as it was sent, with repeated files and blocks elided
+    sent_prompt: String,
+    response: String,
 }
 
 impl IntegratedPromptManager {
@@ -21,15 +51,32 @@ impl IntegratedPromptManager {
     pub async fn new(config: WidgetLogConfig) -> Result<Self> {
         log::info!("Initializing IntegratedPromptManager");
 
-        // Initialize Burn-LM router (heuristic mode, no backend needed in Phase 2a)
-        let burn_router = BurnLmRouter::new();
+        // Initialize Burn-LM router, with the trained routing network if there is one
+        let burn_router = BurnLmRouter::new().load_weights(&config.routing_weights_path());
+
+        let routing_telemetry = match RotatingLog::open(
+            config.routing_telemetry_path(),
+            TELEMETRY_MAX_BYTES,
+            TELEMETRY_MAX_FILES,
+        ) {
+            Ok(log) => Some(Mutex::new(log)),
+            Err(error) => {
+                log::warn!("Routing telemetry disabled: {:#}", error);
+                None
+            }
+        };
 
         Ok(Self {
             management_agent: None,
+            native_cache: None,
+            #[cfg(feature = "python")]
             pyo3_bridge: None,
             burn_router,
             widget_log_process: None,
             config,
+            routing_telemetry,
+            next_prompt_id: AtomicU64::new(0),
+            conversation: Vec::new(),
         })
     }
 
@@ -41,14 +88,41 @@ impl IntegratedPromptManager {
 
         log::info!("Initializing Prompt Management Agent");
 
-        let agent_config = AgentConfig::default();
+        let agent_config = IntegratedConfig::load_agent_config().await?;
         let agent = PromptManagementAgent::new(agent_config).await?;
 
         self.management_agent = Some(agent);
         Ok(())
     }
 
+    /// Initialize the in-process semantic cache (no Python required)
+    pub async fn initialize_native_cache(&mut self) -> Result<()> {
+        if self.native_cache.is_some() {
+            return Ok(());
+        }
+
+        log::info!("Initializing native semantic cache");
+
+        let path = self.config.semantic_cache_path();
+        let cache_config = self.config.semantic_cache.clone();
+        let cache =
+            tokio::task::spawn_blocking(move || SemanticCache::open(&path, cache_config)).await??;
+
+        self.native_cache = Some(NativeCacheProxy::new(
+            Arc::new(cache),
+            self.config.model.clone(),
+        )?);
+        Ok(())
+    }
+
+    /// Use an already opened semantic cache, e.g. the one owned by `WidgetLogManager`
+    pub fn set_semantic_cache(&mut self, cache: Arc<SemanticCache>) -> Result<()> {
+        self.native_cache = Some(NativeCacheProxy::new(cache, self.config.model.clone())?);
+        Ok(())
+    }
+
     /// Initialize PyO3 bridge (requires Widget-Log running)
+    #[cfg(feature = "python")]
     pub async fn initialize_pyo3_bridge(&mut self) -> Result<()> {
         if self.pyo3_bridge.is_some() {
             return Ok(());
@@ -70,6 +144,11 @@ impl IntegratedPromptManager {
         Ok(())
     }
 
+    /// Start a new conversation, so the next prompt is sent without history
+    pub fn clear_conversation(&mut self) {
+        self.conversation.clear();
+    }
+
     /// Complete flow from user input to Anthropic response
     pub async fn handle_user_query(
         &mut self,
@@ -84,36 +163,140 @@ impl IntegratedPromptManager {
             agent.refine_prompt(user_input, context).await?
         } else {
             log::info!("Stage 1: Skipping refinement (agent not initialized)");
-            // Use original prompt
-            RefinedPrompt {
-                original: user_input.to_string(),
-                refined: user_input.to_string(),
-                changes: Vec::new(),
-                quality_score: 0.5,
-                estimated_cache_hit_improvement: 0.0,
-            }
+            RefinedPrompt::unchanged(user_input)
         };
 
-        // For now, auto-approve refined prompts
-        // TODO: Add user approval UI in Phase 2b
-        let final_prompt = &refined_prompt.refined;
+        // Changes that were not approved automatically go back to the user
+        if refined_prompt.needs_review() {
+            log::info!(
+                "Refinement has {} change(s), waiting for approval",
+                refined_prompt.changes.len()
+            );
+            return Ok(AgenticResponse::AwaitingApproval { refined_prompt });
+        }
+
+        self.handle_approved_query(&refined_prompt).await
+    }
+
+    /// Continue a query once the user has reviewed the proposed refinements
+    ///
+    /// Changes that are still pending are left out of the prompt.
+    pub async fn handle_approved_query(
+        &mut self,
+        refined_prompt: &RefinedPrompt,
+    ) -> Result<AgenticResponse> {
+        let final_prompt = refined_prompt.final_prompt();
+        let refinement_applied = final_prompt != refined_prompt.original;
 
         // STAGE 2: Routing Decision (Burn-LM)
         log::info!("Stage 2: Determining routing strategy");
-        let routing_decision = self.burn_router.route_prompt(final_prompt).await?;
+        let history = self
+            .conversation
+            .iter()
+            .flat_map(|turn| [turn.prompt.as_str(), turn.response.as_str()])
+            .collect::<Vec<_>>();
+        let routing_decision = self
+            .burn_router
+            .route_prompt(&final_prompt, &history)
+            .await?;
+        let prompt_id = format!(
+            "{}-{}",
+            TelemetryEvent::now_ms(),
+            self.next_prompt_id.fetch_add(1, Ordering::Relaxed)
+        );
+        self.record_telemetry(TelemetryEvent::routing_features(
+            prompt_id.clone(),
+            routing_decision.features,
+        ))
+        .await;
 
-        // STAGE 3: PyO3 Bridge to Widget-Log (if available)
-        if let Some(bridge) = &self.pyo3_bridge {
-            log::info!("Stage 3: Sending to Widget-Log via PyO3");
+        let optimized_prompt = routing_decision.optimized_prompt;
+        self.record_telemetry(TelemetryEvent::prompt_compression(
+            prompt_id.clone(),
+            optimized_prompt.tokens_before,
+            optimized_prompt.tokens_after,
+            optimized_prompt
+                .reports
+                .iter()
+                .map(|report| {
+                    (
+                        report.transformation.name().to_string(),
+                        report.tokens_saved,
+                    )
+                })
+                .collect(),
+        ))
+        .await;
 
-            let proxy_request = ProxyRequest {
-                prompt: final_prompt.to_string(),
-                cache_anchors: routing_decision.cache_anchors,
-                api_key: std::env::var("ANTHROPIC_API_KEY").unwrap_or_default(),
-                optimization: routing_decision.token_optimization,
-            };
+        // STAGE 3: Semantic cache, native or via PyO3 (if available). Prompts
+        // the router doesn't expect to repeat skip the cache and go to the API.
+        let use_cache = routing_decision.use_cache;
+        let proxy_request = ProxyRequest {
+            prompt: optimized_prompt.prompt.clone(),
+            chunks: optimized_prompt.chunks.clone(),
+            cache_anchors: routing_decision.cache_anchors,
+            api_key: std::env::var("ANTHROPIC_API_KEY").unwrap_or_default(),
+            optimization: routing_decision.token_optimization,
+            model: routing_decision
+                .model_tier
+                .and_then(|tier| self.config.tier_models.get(&tier).cloned()),
+            history: self
+                .conversation
+                .iter()
+                .flat_map(|turn| {
+                    [
+                        ProxyMessage {
+                            role: MessageRole::User,
+                            content: turn.sent_prompt.clone(),
+                        },
+                        ProxyMessage {
+                            role: MessageRole::Assistant,
+                            content: turn.response.clone(),
+                        },
+                    ]
+                })
+                .collect(),
+            skip_cache: !use_cache,
+        };
 
-            let proxy_response = bridge.send_to_proxy(proxy_request).await?;
+        let request_start = TelemetryEvent::now_ms();
+        if let Some(proxy_response) = self.send_to_cache(proxy_request).await? {
+            // The model only runs on a miss. It was picked by the router or is
+            // the default, so it is not used to label the prompt's tier.
+            if let (pyo3_bridge::CacheStatus::Miss, Some(model_name)) =
+                (&proxy_response.cache_status, &proxy_response.model)
+            {
+                self.record_telemetry(TelemetryEvent::ModelInferenceStart {
+                    prompt_id: prompt_id.clone(),
+                    model_name: model_name.clone(),
+                    prompt_tokens: optimized_prompt.tokens_after,
+                    user_selected: false,
+                    timestamp: request_start,
+                })
+                .await;
+            }
+
+            let outcome = match &proxy_response.cache_status {
+                pyo3_bridge::CacheStatus::Hit {
+                    latency_ms,
+                    similarity_score,
+                    ..
+                } => TelemetryEvent::cache_hit(
+                    prompt_id,
+                    *similarity_score,
+                    *latency_ms,
+                    proxy_response.tokens_used,
+                ),
+                pyo3_bridge::CacheStatus::Miss => {
+                    let reason = match &proxy_response.miss_reason {
+                        Some(reason) => reason.clone(),
+                        None if !use_cache => CacheMissReason::CacheDisabled,
+                        None => CacheMissReason::NoSimilarPrompt,
+                    };
+                    TelemetryEvent::cache_miss(prompt_id, reason)
+                }
+            };
+            self.record_telemetry(outcome).await;
 
             // Process response
             match proxy_response.cache_status {
@@ -127,20 +310,30 @@ impl IntegratedPromptManager {
                         latency_ms,
                         similarity_score
                     );
+                    self.conversation.push(ConversationTurn {
+                        prompt: final_prompt,
+                        sent_prompt: optimized_prompt.prompt,
+                        response: response.clone(),
+                    });
                     return Ok(AgenticResponse::Cached {
                         response,
                         latency_ms,
                         similarity_score,
-                        refinement_applied: !refined_prompt.changes.is_empty(),
+                        refinement_applied,
                     });
                 }
                 pyo3_bridge::CacheStatus::Miss => {
                     log::info!("Cache MISS - response from API");
                     if let Some(response_text) = proxy_response.response_text {
+                        self.conversation.push(ConversationTurn {
+                            prompt: final_prompt,
+                            sent_prompt: optimized_prompt.prompt,
+                            response: response_text.clone(),
+                        });
                         return Ok(AgenticResponse::Fresh {
                             response: response_text,
                             tokens_used: proxy_response.tokens_used,
-                            refinement_applied: !refined_prompt.changes.is_empty(),
+                            refinement_applied,
                         });
                     }
                 }
@@ -149,21 +342,76 @@ impl IntegratedPromptManager {
 
         // Fallback: return refinement info
         Ok(AgenticResponse::RefinementOnly {
-            refined_prompt: refined_prompt.refined,
-            changes_count: refined_prompt.changes.len(),
+            refined_prompt: final_prompt,
+            changes_count: refined_prompt
+                .changes
+                .iter()
+                .filter(|change| change.status == ChangeStatus::Accepted)
+                .count(),
         })
     }
 
+    /// Send the request to the native cache, falling back to the PyO3 bridge
+    async fn send_to_cache(&self, request: ProxyRequest) -> Result<Option<ProxyResponse>> {
+        if let Some(native_cache) = &self.native_cache {
+            log::info!("Stage 3: Looking up native semantic cache");
+            return native_cache.send_to_proxy(request).await.map(Some);
+        }
+
+        #[cfg(feature = "python")]
+        if let Some(bridge) = &self.pyo3_bridge {
+            log::info!("Stage 3: Sending to Widget-Log via PyO3");
+            return bridge.send_to_proxy(request).await.map(Some);
+        }
+
+        log::info!("Stage 3: Skipping cache (no backend initialized)");
+        Ok(None)
+    }
+
+    /// Append an event to the routing telemetry log, and forward it to
+    /// Widget-Log when the bridge is up. Telemetry is best-effort.
+    async fn record_telemetry(&self, event: TelemetryEvent) {
+        if let Some(telemetry_log) = &self.routing_telemetry {
+            let result = serde_json::to_string(&event)
+                .map_err(anyhow::Error::from)
+                .and_then(|line| {
+                    telemetry_log
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .append_line(&line)
+                });
+            if let Err(error) = result {
+                log::debug!("Failed to record routing telemetry: {}", error);
+            }
+        }
+
+        #[cfg(feature = "python")]
+        if let Some(bridge) = &self.pyo3_bridge {
+            bridge.send_telemetry(event).await.ok();
+        }
+    }
+
     /// Get performance statistics
     pub async fn get_stats(&self) -> Result<ManagerStats> {
         let mut stats = ManagerStats::default();
 
-        if let Some(bridge) = &self.pyo3_bridge {
-            stats.cache_stats = Some(bridge.get_cache_stats().await?);
+        if let Some(native_cache) = &self.native_cache {
+            stats.cache_stats = Some(native_cache.get_cache_stats().await?);
+        }
+
+        #[cfg(feature = "python")]
+        if stats.cache_stats.is_none() {
+            if let Some(bridge) = &self.pyo3_bridge {
+                stats.cache_stats = Some(bridge.get_cache_stats().await?);
+            }
         }
 
         stats.agent_initialized = self.management_agent.is_some();
-        stats.bridge_initialized = self.pyo3_bridge.is_some();
+        stats.native_cache_initialized = self.native_cache.is_some();
+        #[cfg(feature = "python")]
+        {
+            stats.bridge_initialized = self.pyo3_bridge.is_some();
+        }
 
         Ok(stats)
     }
@@ -187,12 +435,15 @@ pub enum AgenticResponse {
         refined_prompt: String,
         changes_count: usize,
     },
+    /// Proposed changes need review, see `IntegratedPromptManager::handle_approved_query`
+    AwaitingApproval { refined_prompt: RefinedPrompt },
 }
 
 /// Statistics from the integrated manager
 #[derive(Debug, Clone, Default)]
 pub struct ManagerStats {
     pub agent_initialized: bool,
+    pub native_cache_initialized: bool,
     pub bridge_initialized: bool,
     pub cache_stats: Option<serde_json::Value>,
 }
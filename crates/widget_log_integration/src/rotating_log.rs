@@ -0,0 +1,120 @@
+use anyhow::{Context, Result};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Append-only log file that is rotated once it grows past `max_bytes`
+///
+/// Rotation shifts `name.log` to `name.log.1`, `name.log.1` to `name.log.2`
+/// and so on, deleting the file that would become `name.log.{max_files}`.
+pub struct RotatingLog {
+    path: PathBuf,
+    max_bytes: u64,
+    max_files: usize,
+    file: File,
+    size: u64,
+}
+
+impl RotatingLog {
+    pub fn open(path: impl Into<PathBuf>, max_bytes: u64, max_files: usize) -> Result<Self> {
+        let path = path.into();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).context("Failed to create log directory")?;
+        }
+        let file = Self::open_file(&path)?;
+        let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+        Ok(Self {
+            path,
+            max_bytes: max_bytes.max(1),
+            max_files: max_files.max(1),
+            file,
+            size,
+        })
+    }
+
+    /// Append a timestamped line, tagged with where it came from
+    pub fn write_line(&mut self, source: &str, line: &str) -> Result<()> {
+        self.append_line(&format!(
+            "{} [{}] {}",
+            chrono::Local::now().format("%Y-%m-%dT%H:%M:%S%.3f"),
+            source,
+            line
+        ))
+    }
+
+    /// Append a line as is, for logs with their own format such as JSON lines
+    pub fn append_line(&mut self, line: &str) -> Result<()> {
+        let entry = format!("{}\n", line);
+        if self.size > 0 && self.size + entry.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+        self.file
+            .write_all(entry.as_bytes())
+            .context("Failed to write log")?;
+        self.size += entry.len() as u64;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> Result<()> {
+        let rotated = |index: usize| PathBuf::from(format!("{}.{}", self.path.display(), index));
+
+        if self.max_files == 1 {
+            std::fs::remove_file(&self.path).context("Failed to rotate log")?;
+        } else {
+            let _ = std::fs::remove_file(rotated(self.max_files - 1));
+            for index in (1..self.max_files - 1).rev() {
+                let from = rotated(index);
+                if from.exists() {
+                    std::fs::rename(&from, rotated(index + 1)).context("Failed to rotate log")?;
+                }
+            }
+            std::fs::rename(&self.path, rotated(1)).context("Failed to rotate log")?;
+        }
+
+        self.file = Self::open_file(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+
+    fn open_file(path: &Path) -> Result<File> {
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Failed to open log file {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotation_keeps_max_files() {
+        let dir = std::env::temp_dir().join(format!("rotating-log-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let path = dir.join("proxy.log");
+
+        let mut log = RotatingLog::open(&path, 200, 3).unwrap();
+        for i in 0..20 {
+            log.write_line("stdout", &format!("line {}", i)).unwrap();
+        }
+
+        let mut files: Vec<String> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        files.sort();
+        assert_eq!(files, ["proxy.log", "proxy.log.1", "proxy.log.2"]);
+
+        for file in &files {
+            assert!(std::fs::metadata(dir.join(file)).unwrap().len() <= 200);
+        }
+        let current = std::fs::read_to_string(&path).unwrap();
+        assert!(current.ends_with("[stdout] line 19\n"));
+        let previous = std::fs::read_to_string(dir.join("proxy.log.1")).unwrap();
+        assert!(!previous.contains("line 19"));
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
@@ -0,0 +1,829 @@
+use anyhow::{Context, Result};
+use pyo3_bridge::CacheMissReason;
+use serde::{Deserialize, Serialize};
+use sqlez::connection::Connection;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Semantic cache settings, matching the `cache` section of the Widget-Log config
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SemanticCacheConfig {
+    /// Minimum cosine similarity between prompts for a cache hit, or `None`
+    /// for the threshold the embedder was calibrated with. The Python
+    /// proxy's 0.85 was tuned for all-MiniLM-L6-v2 and doesn't carry over to
+    /// other embedders.
+    pub similarity_threshold: Option<f32>,
+    /// Entries older than this are never returned and are evicted on write
+    pub ttl_days: u64,
+    /// Least recently used entries are evicted beyond this count
+    pub max_entries: usize,
+}
+
+impl Default for SemanticCacheConfig {
+    fn default() -> Self {
+        Self {
+            similarity_threshold: None,
+            ttl_days: 30,
+            max_entries: 1000,
+        }
+    }
+}
+
+/// Turns prompts into unit-length vectors whose dot product measures similarity
+pub trait Embedder: Send + Sync {
+    /// Stored with each embedding; entries from a different embedder are re-embedded on load
+    fn id(&self) -> &str;
+    fn embed(&self, text: &str) -> Vec<f32>;
+    /// Lowest similarity at which two prompts reliably ask the same thing.
+    /// Scores from different embedders aren't comparable, so each embedder
+    /// is calibrated on its own.
+    fn similarity_threshold(&self) -> f32;
+}
+
+/// Calibrated on pairs of coding prompts: rewordings score 0.8-0.9, but
+/// prompts that swap one content word ("reverse a Vec" and "reverse a
+/// String") score up to 0.84, so only near-identical prompts may hit.
+const HASHING_SIMILARITY_THRESHOLD: f32 = 0.9;
+
+/// Dependency-free embedder using signed feature hashing of words, word
+/// bigrams and character trigrams
+///
+/// This is a lexical measure, not a semantic embedding like the
+/// all-MiniLM-L6-v2 vectors of the Python proxy: it catches rephrasings that
+/// share most of their vocabulary (casing, punctuation, small edits,
+/// reordering) without loading a model, but not synonyms, and scores prompts
+/// that differ in a single word as close. Its threshold is set high
+/// accordingly.
+pub struct HashingEmbedder {
+    id: String,
+    dimensions: usize,
+}
+
+impl HashingEmbedder {
+    pub fn new(dimensions: usize) -> Self {
+        let dimensions = dimensions.max(1);
+        Self {
+            id: format!("hashing-v1-{}", dimensions),
+            dimensions,
+        }
+    }
+
+    fn add_feature(&self, vector: &mut [f32], feature: &str, weight: f32) {
+        let hash = fnv1a(feature.as_bytes());
+        let index = (hash % self.dimensions as u64) as usize;
+        let sign = if hash >> 63 == 0 { 1.0 } else { -1.0 };
+        vector[index] += sign * weight;
+    }
+}
+
+impl Default for HashingEmbedder {
+    /// Wide enough that the features of a typical prompt rarely collide
+    fn default() -> Self {
+        Self::new(384)
+    }
+}
+
+impl Embedder for HashingEmbedder {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn embed(&self, text: &str) -> Vec<f32> {
+        let words: Vec<String> = text
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(|word| word.to_lowercase())
+            .collect();
+
+        let mut vector = vec![0.0; self.dimensions];
+        for word in &words {
+            self.add_feature(&mut vector, word, 1.0);
+            let padded: Vec<char> = format!("#{}#", word).chars().collect();
+            for trigram in padded.windows(3) {
+                let trigram: String = trigram.iter().collect();
+                self.add_feature(&mut vector, &format!("c:{}", trigram), 0.5);
+            }
+        }
+        for pair in words.windows(2) {
+            self.add_feature(&mut vector, &format!("w:{} {}", pair[0], pair[1]), 0.5);
+        }
+
+        normalize(&mut vector);
+        vector
+    }
+
+    fn similarity_threshold(&self) -> f32 {
+        HASHING_SIMILARITY_THRESHOLD
+    }
+}
+
+/// Result of a cache lookup
+#[derive(Debug, Clone)]
+pub enum CacheLookup {
+    Hit {
+        response: String,
+        /// Cosine similarity under the cache's embedder, only comparable to
+        /// scores from the same embedder
+        similarity_score: f32,
+        /// Tokens the original request used, and this hit saved
+        tokens_saved: usize,
+    },
+    Miss {
+        reason: CacheMissReason,
+        /// Similarity of the closest live entry, if any
+        best_similarity: Option<f32>,
+    },
+}
+
+struct CacheEntry {
+    id: i64,
+    model: String,
+    prompt: String,
+    embedding: Vec<f32>,
+    response: String,
+    tokens_used: usize,
+    created_at: u64,
+    last_hit_at: u64,
+}
+
+struct CacheState {
+    connection: Connection,
+    entries: Vec<CacheEntry>,
+    hits: u64,
+    misses: u64,
+    tokens_saved: u64,
+}
+
+/// In-process semantic prompt cache persisted with sqlez
+///
+/// Replaces the similarity lookup of the Python Widget-Log proxy: a prompt
+/// hits when a stored prompt younger than `ttl_days` has a cosine similarity
+/// of at least [`SemanticCache::similarity_threshold`]. Entries are kept per
+/// model, so a prompt answered by one model never hits for another.
+pub struct SemanticCache {
+    state: Mutex<CacheState>,
+    embedder: Box<dyn Embedder>,
+    config: SemanticCacheConfig,
+}
+
+impl SemanticCache {
+    /// Open (or create) the cache database at `path`
+    pub fn open(path: &Path, config: SemanticCacheConfig) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).context("Failed to create cache directory")?;
+        }
+        let connection = Connection::open_file(&path.to_string_lossy());
+        if !connection.persistent() {
+            log::warn!(
+                "Could not open semantic cache at {}, using an in-memory cache",
+                path.display()
+            );
+        }
+        Self::with_connection(connection, config, Box::new(HashingEmbedder::default()))
+    }
+
+    /// Cache that lives only as long as this process
+    pub fn open_in_memory(config: SemanticCacheConfig) -> Result<Self> {
+        Self::with_connection(
+            Connection::open_memory(None),
+            config,
+            Box::new(HashingEmbedder::default()),
+        )
+    }
+
+    pub fn with_embedder(mut self, embedder: Box<dyn Embedder>) -> Result<Self> {
+        self.embedder = embedder;
+        let state = self.state.get_mut().unwrap_or_else(|e| e.into_inner());
+        Self::load_entries(state, self.embedder.as_ref())?;
+        Ok(self)
+    }
+
+    fn with_connection(
+        connection: Connection,
+        config: SemanticCacheConfig,
+        embedder: Box<dyn Embedder>,
+    ) -> Result<Self> {
+        connection.exec(
+            r#"
+                CREATE TABLE IF NOT EXISTS semantic_cache (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    model TEXT NOT NULL DEFAULT '',
+                    prompt TEXT NOT NULL,
+                    embedder TEXT NOT NULL,
+                    embedding BLOB NOT NULL,
+                    response TEXT NOT NULL,
+                    tokens_used INTEGER NOT NULL,
+                    created_at INTEGER NOT NULL,
+                    last_hit_at INTEGER NOT NULL,
+                    hit_count INTEGER NOT NULL DEFAULT 0
+                )
+                "#,
+        )?()
+        .context("Failed to create semantic cache table")?;
+
+        // Caches written before entries were kept per model
+        let has_model_column = connection.select_row::<i64>(
+            "SELECT COUNT(*) FROM pragma_table_info('semantic_cache') WHERE name = 'model'",
+        )?()?
+        .unwrap_or(0)
+            > 0;
+        if !has_model_column {
+            connection.exec(
+                r#"
+                    ALTER TABLE semantic_cache ADD COLUMN model TEXT NOT NULL DEFAULT ''
+                    "#,
+            )?()
+            .context("Failed to add model to semantic cache table")?;
+        }
+
+        let mut state = CacheState {
+            connection,
+            entries: Vec::new(),
+            hits: 0,
+            misses: 0,
+            tokens_saved: 0,
+        };
+        Self::load_entries(&mut state, embedder.as_ref())?;
+
+        Ok(Self {
+            state: Mutex::new(state),
+            embedder,
+            config,
+        })
+    }
+
+    /// Read all rows into memory, re-embedding rows written by another embedder
+    fn load_entries(state: &mut CacheState, embedder: &dyn Embedder) -> Result<()> {
+        let rows = state
+            .connection
+            .select::<(i64, String, String, String, Vec<u8>, String, usize, u64, u64)>(
+            "SELECT id, model, prompt, embedder, embedding, response, tokens_used, created_at, last_hit_at
+                 FROM semantic_cache",
+        )?()
+        .context("Failed to read semantic cache")?;
+
+        let mut stale = Vec::new();
+        state.entries = rows
+            .into_iter()
+            .map(
+                |(
+                    id,
+                    model,
+                    prompt,
+                    embedder_id,
+                    blob,
+                    response,
+                    tokens_used,
+                    created_at,
+                    last_hit_at,
+                )| {
+                    let embedding = if embedder_id == embedder.id() {
+                        blob_to_embedding(&blob)
+                    } else {
+                        let embedding = embedder.embed(&prompt);
+                        stale.push((id, embedding.clone()));
+                        embedding
+                    };
+                    CacheEntry {
+                        id,
+                        model,
+                        prompt,
+                        embedding,
+                        response,
+                        tokens_used,
+                        created_at,
+                        last_hit_at,
+                    }
+                },
+            )
+            .collect();
+
+        if !stale.is_empty() {
+            log::info!(
+                "Re-embedding {} cached prompts with {}",
+                stale.len(),
+                embedder.id()
+            );
+            let mut update = state.connection.exec_bound::<(&str, Vec<u8>, i64)>(
+                "UPDATE semantic_cache SET embedder = ?, embedding = ? WHERE id = ?",
+            )?;
+            for (id, embedding) in stale {
+                update((embedder.id(), embedding_to_blob(&embedding), id))?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn config(&self) -> &SemanticCacheConfig {
+        &self.config
+    }
+
+    /// The configured threshold, or the embedder's calibrated one
+    pub fn similarity_threshold(&self) -> f32 {
+        self.config
+            .similarity_threshold
+            .unwrap_or_else(|| self.embedder.similarity_threshold())
+    }
+
+    /// Find the most similar live entry for `prompt` answered by `model`
+    pub fn lookup(&self, model: &str, prompt: &str) -> Result<CacheLookup> {
+        self.lookup_at(model, prompt, unix_now())
+    }
+
+    fn lookup_at(&self, model: &str, prompt: &str, now: u64) -> Result<CacheLookup> {
+        let query = self.embedder.embed(prompt);
+        let mut state = self.lock();
+
+        let best = state
+            .entries
+            .iter_mut()
+            .filter(|entry| entry.model == model && !self.is_expired(entry.created_at, now))
+            .map(|entry| (dot(&query, &entry.embedding), entry))
+            .max_by(|a, b| a.0.total_cmp(&b.0));
+
+        let lookup = match best {
+            None => CacheLookup::Miss {
+                reason: CacheMissReason::CacheEmpty,
+                best_similarity: None,
+            },
+            Some((similarity, _)) if similarity < self.similarity_threshold() => {
+                CacheLookup::Miss {
+                    reason: CacheMissReason::SimilarityBelowThreshold,
+                    best_similarity: Some(similarity),
+                }
+            }
+            Some((similarity, entry)) => {
+                entry.last_hit_at = now;
+                let hit = CacheLookup::Hit {
+                    response: entry.response.clone(),
+                    similarity_score: similarity.min(1.0),
+                    tokens_saved: entry.tokens_used,
+                };
+                let id = entry.id;
+                state.connection.exec_bound::<(u64, i64)>(
+                    "UPDATE semantic_cache SET last_hit_at = ?, hit_count = hit_count + 1 WHERE id = ?",
+                )?((now, id))?;
+                hit
+            }
+        };
+
+        match &lookup {
+            CacheLookup::Hit { tokens_saved, .. } => {
+                state.hits += 1;
+                state.tokens_saved += *tokens_saved as u64;
+            }
+            CacheLookup::Miss { .. } => state.misses += 1,
+        }
+        Ok(lookup)
+    }
+
+    /// Store `model`'s response for `prompt`, replacing an entry for the
+    /// identical prompt and model
+    pub fn insert(
+        &self,
+        model: &str,
+        prompt: &str,
+        response: &str,
+        tokens_used: usize,
+    ) -> Result<()> {
+        self.insert_at(model, prompt, response, tokens_used, unix_now())
+    }
+
+    fn insert_at(
+        &self,
+        model: &str,
+        prompt: &str,
+        response: &str,
+        tokens_used: usize,
+        now: u64,
+    ) -> Result<()> {
+        let embedding = self.embedder.embed(prompt);
+        let mut state = self.lock();
+
+        if let Some(index) = state
+            .entries
+            .iter()
+            .position(|e| e.model == model && e.prompt == prompt)
+        {
+            let id = state.entries.remove(index).id;
+            state
+                .connection
+                .exec_bound::<i64>("DELETE FROM semantic_cache WHERE id = ?")?(id)?;
+        }
+
+        state
+            .connection
+            .exec_bound::<(&str, &str, &str, Vec<u8>, &str, usize, u64, u64)>(
+                "INSERT INTO semantic_cache
+                (model, prompt, embedder, embedding, response, tokens_used, created_at, last_hit_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+            )?((
+            model,
+            prompt,
+            self.embedder.id(),
+            embedding_to_blob(&embedding),
+            response,
+            tokens_used,
+            now,
+            now,
+        ))?;
+        let id = state
+            .connection
+            .select_row::<i64>("SELECT last_insert_rowid()")?()?
+        .context("Failed to read cache entry id")?;
+
+        state.entries.push(CacheEntry {
+            id,
+            model: model.to_string(),
+            prompt: prompt.to_string(),
+            embedding,
+            response: response.to_string(),
+            tokens_used,
+            created_at: now,
+            last_hit_at: now,
+        });
+
+        self.evict(&mut state, now)
+    }
+
+    /// Drop expired entries, then the least recently used ones beyond `max_entries`
+    fn evict(&self, state: &mut CacheState, now: u64) -> Result<()> {
+        let mut evicted: Vec<i64> = state
+            .entries
+            .iter()
+            .filter(|entry| self.is_expired(entry.created_at, now))
+            .map(|entry| entry.id)
+            .collect();
+        state.entries.retain(|entry| !evicted.contains(&entry.id));
+
+        if state.entries.len() > self.config.max_entries {
+            state
+                .entries
+                .sort_by_key(|entry| (entry.last_hit_at, entry.created_at, entry.id));
+            let excess = state.entries.len() - self.config.max_entries;
+            evicted.extend(state.entries.drain(..excess).map(|entry| entry.id));
+        }
+
+        let mut delete = state
+            .connection
+            .exec_bound::<i64>("DELETE FROM semantic_cache WHERE id = ?")?;
+        for id in evicted {
+            delete(id)?;
+        }
+        Ok(())
+    }
+
+    /// Remove every entry
+    pub fn clear(&self) -> Result<()> {
+        let mut state = self.lock();
+        state.connection.exec("DELETE FROM semantic_cache")?()?;
+        state.entries.clear();
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Statistics in the shape returned by the Python proxy's `get_cache_stats`
+    pub fn stats(&self) -> serde_json::Value {
+        let state = self.lock();
+        let requests = state.hits + state.misses;
+        let hit_rate = if requests == 0 {
+            0.0
+        } else {
+            state.hits as f64 / requests as f64
+        };
+        serde_json::json!({
+            "backend": "native",
+            "entries": state.entries.len(),
+            "total_hits": state.hits,
+            "total_misses": state.misses,
+            "total_requests": requests,
+            "hit_rate": hit_rate,
+            "tokens_saved": state.tokens_saved,
+            "similarity_threshold": self.similarity_threshold(),
+            "ttl_days": self.config.ttl_days,
+            "embedder": self.embedder.id(),
+        })
+    }
+
+    fn is_expired(&self, created_at: u64, now: u64) -> bool {
+        now.saturating_sub(created_at) >= self.config.ttl_days.saturating_mul(SECONDS_PER_DAY)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CacheState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn normalize(vector: &mut [f32]) {
+    let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|v| *v /= norm);
+    }
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+fn embedding_to_blob(embedding: &[f32]) -> Vec<u8> {
+    embedding.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+fn blob_to_embedding(blob: &[u8]) -> Vec<f32> {
+    blob.chunks_exact(4)
+        .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODEL: &str = "claude-sonnet-4";
+
+    fn cache(config: SemanticCacheConfig) -> SemanticCache {
+        SemanticCache::open_in_memory(config).unwrap()
+    }
+
+    #[test]
+    fn test_similar_prompts_hit_and_different_prompts_miss() {
+        let cache = cache(SemanticCacheConfig::default());
+        assert!(matches!(
+            cache
+                .lookup(MODEL, "How do I reverse a Vec in Rust?")
+                .unwrap(),
+            CacheLookup::Miss {
+                reason: CacheMissReason::CacheEmpty,
+                ..
+            }
+        ));
+
+        cache
+            .insert(
+                MODEL,
+                "How do I reverse a Vec in Rust?",
+                "Use vec.reverse()",
+                120,
+            )
+            .unwrap();
+
+        match cache
+            .lookup(MODEL, "how do I reverse a vec in rust")
+            .unwrap()
+        {
+            CacheLookup::Hit {
+                response,
+                similarity_score,
+                tokens_saved,
+            } => {
+                assert_eq!(response, "Use vec.reverse()");
+                assert!(similarity_score > 0.99);
+                assert_eq!(tokens_saved, 120);
+            }
+            miss => panic!("expected hit, got {:?}", miss),
+        }
+
+        match cache
+            .lookup(MODEL, "Explain the borrow checker's two-phase borrows")
+            .unwrap()
+        {
+            CacheLookup::Miss {
+                reason: CacheMissReason::SimilarityBelowThreshold,
+                best_similarity: Some(similarity),
+            } => assert!(similarity < 0.85),
+            other => panic!("expected miss, got {:?}", other),
+        }
+
+        let stats = cache.stats();
+        assert_eq!(stats["total_hits"], 1);
+        assert_eq!(stats["total_misses"], 2);
+        assert_eq!(stats["tokens_saved"], 120);
+    }
+
+    #[test]
+    fn test_threshold_controls_hits() {
+        let prompt = "Summarize the changes in this pull request";
+        let variant = "Summarize the changes in this pull request for the release notes";
+
+        let strict = cache(SemanticCacheConfig {
+            similarity_threshold: Some(0.99),
+            ..Default::default()
+        });
+        strict.insert(MODEL, prompt, "summary", 10).unwrap();
+        assert!(matches!(
+            strict.lookup(MODEL, variant).unwrap(),
+            CacheLookup::Miss { .. }
+        ));
+
+        let fuzzy = cache(SemanticCacheConfig {
+            similarity_threshold: Some(0.6),
+            ..Default::default()
+        });
+        fuzzy.insert(MODEL, prompt, "summary", 10).unwrap();
+        assert!(matches!(
+            fuzzy.lookup(MODEL, variant).unwrap(),
+            CacheLookup::Hit { .. }
+        ));
+    }
+
+    #[test]
+    fn test_default_threshold_is_calibrated_for_the_embedder() {
+        let cache = cache(SemanticCacheConfig::default());
+        assert_eq!(cache.similarity_threshold(), HASHING_SIMILARITY_THRESHOLD);
+        cache
+            .insert(
+                MODEL,
+                "How do I reverse a Vec in Rust?",
+                "Use vec.reverse()",
+                120,
+            )
+            .unwrap();
+
+        // One word apart, but a different question
+        match cache
+            .lookup(MODEL, "How do I reverse a String in Rust?")
+            .unwrap()
+        {
+            CacheLookup::Miss {
+                reason: CacheMissReason::SimilarityBelowThreshold,
+                best_similarity: Some(similarity),
+            } => assert!(similarity > 0.8),
+            other => panic!("expected miss, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_entries_are_kept_per_model() {
+        let cache = cache(SemanticCacheConfig::default());
+        cache
+            .insert(
+                MODEL,
+                "How do I reverse a Vec in Rust?",
+                "Use vec.reverse()",
+                120,
+            )
+            .unwrap();
+        assert!(matches!(
+            cache
+                .lookup("claude-haiku-4", "How do I reverse a Vec in Rust?")
+                .unwrap(),
+            CacheLookup::Miss {
+                reason: CacheMissReason::CacheEmpty,
+                ..
+            }
+        ));
+
+        cache
+            .insert(
+                "claude-haiku-4",
+                "How do I reverse a Vec in Rust?",
+                "vec.reverse()",
+                80,
+            )
+            .unwrap();
+        assert_eq!(cache.len(), 2);
+        match cache
+            .lookup(MODEL, "How do I reverse a Vec in Rust?")
+            .unwrap()
+        {
+            CacheLookup::Hit { response, .. } => assert_eq!(response, "Use vec.reverse()"),
+            miss => panic!("expected hit, got {:?}", miss),
+        }
+    }
+
+    #[test]
+    fn test_ttl_and_lru_eviction() {
+        let cache = cache(SemanticCacheConfig {
+            ttl_days: 1,
+            max_entries: 2,
+            ..Default::default()
+        });
+        let day = SECONDS_PER_DAY;
+
+        cache.insert_at(MODEL, "first prompt", "1", 1, 0).unwrap();
+        assert!(matches!(
+            cache.lookup_at(MODEL, "first prompt", day - 1).unwrap(),
+            CacheLookup::Hit { .. }
+        ));
+        assert!(matches!(
+            cache.lookup_at(MODEL, "first prompt", day).unwrap(),
+            CacheLookup::Miss {
+                reason: CacheMissReason::CacheEmpty,
+                ..
+            }
+        ));
+
+        // Writing after expiry evicts the stale entry
+        cache
+            .insert_at(MODEL, "second prompt", "2", 1, day)
+            .unwrap();
+        assert_eq!(cache.len(), 1);
+
+        cache
+            .insert_at(MODEL, "third prompt", "3", 1, day + 1)
+            .unwrap();
+        cache.lookup_at(MODEL, "second prompt", day + 2).unwrap();
+        cache
+            .insert_at(MODEL, "fourth prompt", "4", 1, day + 3)
+            .unwrap();
+        // "third prompt" was used least recently
+        assert_eq!(cache.len(), 2);
+        assert!(matches!(
+            cache.lookup_at(MODEL, "third prompt", day + 4).unwrap(),
+            CacheLookup::Miss { .. }
+        ));
+    }
+
+    #[test]
+    fn test_entries_persist_and_reembed() {
+        let dir = std::env::temp_dir().join(format!(
+            "semantic-cache-test-{}-{}",
+            std::process::id(),
+            unix_now()
+        ));
+        let path = dir.join("cache.db");
+
+        {
+            let cache = SemanticCache::open(&path, SemanticCacheConfig::default()).unwrap();
+            cache
+                .insert(MODEL, "What is a lifetime?", "A scope", 42)
+                .unwrap();
+            cache
+                .insert(MODEL, "What is a lifetime?", "A region", 43)
+                .unwrap();
+            assert_eq!(cache.len(), 1);
+        }
+
+        let reopened = SemanticCache::open(&path, SemanticCacheConfig::default())
+            .unwrap()
+            .with_embedder(Box::new(HashingEmbedder::new(64)))
+            .unwrap();
+        match reopened.lookup(MODEL, "what is a lifetime").unwrap() {
+            CacheLookup::Hit { response, .. } => assert_eq!(response, "A region"),
+            miss => panic!("expected hit, got {:?}", miss),
+        }
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn test_caches_from_before_models_are_migrated() {
+        let connection = Connection::open_memory(None);
+        connection
+            .exec(
+                r#"
+                CREATE TABLE semantic_cache (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    prompt TEXT NOT NULL,
+                    embedder TEXT NOT NULL,
+                    embedding BLOB NOT NULL,
+                    response TEXT NOT NULL,
+                    tokens_used INTEGER NOT NULL,
+                    created_at INTEGER NOT NULL,
+                    last_hit_at INTEGER NOT NULL,
+                    hit_count INTEGER NOT NULL DEFAULT 0
+                )
+                "#,
+            )
+            .unwrap()()
+        .unwrap();
+
+        let cache = SemanticCache::with_connection(
+            connection,
+            SemanticCacheConfig::default(),
+            Box::new(HashingEmbedder::default()),
+        )
+        .unwrap();
+        cache
+            .insert(MODEL, "What is a lifetime?", "A scope", 42)
+            .unwrap();
+        assert!(matches!(
+            cache.lookup(MODEL, "What is a lifetime?").unwrap(),
+            CacheLookup::Hit { .. }
+        ));
+    }
+}
@@ -1,93 +1,425 @@
 use anyhow::{Context, Result};
-use serde_json::{json, Value};
-use std::path::PathBuf;
+use imara_diff::{diff, intern::InternedInput, Algorithm, UnifiedDiffBuilder};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use settings_json::{infer_json_indent_size, parse_json_with_comments, update_value_in_json_text};
+use std::path::{Path, PathBuf};
+
+/// Records what `configure_zed_settings` replaced, so it can be undone
+const BACKUP_FILE_NAME: &str = "zed-settings-backup.json";
+
+/// Models added to `language_models.anthropic.available_models`, keyed by name
+const CACHED_MODEL_NAMES: [&str; 2] = ["claude-3-5-sonnet-20241022", "claude-opus-4-20250514"];
+
+/// Previous value of a settings key, `None` if the key did not exist
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SettingsBackupEntry {
+    pub path: Vec<String>,
+    pub previous: Option<Value>,
+}
+
+/// A pending edit of Zed's settings.json, to be previewed with [`SettingsUpdate::diff`]
+/// before it is written with [`SettingsUpdate::apply`]
+#[derive(Debug, Clone)]
+pub struct SettingsUpdate {
+    pub settings_path: PathBuf,
+    pub old_text: String,
+    pub new_text: String,
+    backup_path: PathBuf,
+    /// Backup to store alongside the edit, `None` to remove it
+    backup: Option<Vec<SettingsBackupEntry>>,
+}
+
+impl SettingsUpdate {
+    pub fn has_changes(&self) -> bool {
+        self.old_text != self.new_text
+    }
+
+    /// Unified diff of the edit
+    pub fn diff(&self) -> String {
+        let input = InternedInput::new(self.old_text.as_str(), self.new_text.as_str());
+        let hunks = diff(
+            Algorithm::Histogram,
+            &input,
+            UnifiedDiffBuilder::new(&input),
+        );
+        if hunks.is_empty() {
+            return hunks;
+        }
+        let name = self.settings_path.display();
+        format!("--- {}\n+++ {}\n{}", name, name, hunks)
+    }
+
+    /// Write the edit, failing if settings.json changed since it was planned
+    pub async fn apply(&self) -> Result<()> {
+        let current = read_settings_text(&self.settings_path).await?;
+        if current != self.old_text {
+            anyhow::bail!(
+                "{} changed since the update was prepared",
+                self.settings_path.display()
+            );
+        }
+
+        // Record the backup before editing, and only drop it once the edit is written
+        if let Some(backup) = &self.backup {
+            let content = serde_json::to_string_pretty(backup)
+                .context("Failed to serialize settings backup")?;
+            tokio::fs::write(&self.backup_path, content)
+                .await
+                .context("Failed to write settings backup")?;
+        }
+
+        if self.has_changes() {
+            if let Some(parent) = self.settings_path.parent() {
+                tokio::fs::create_dir_all(parent)
+                    .await
+                    .context("Failed to create Zed config directory")?;
+            }
+            tokio::fs::write(&self.settings_path, &self.new_text)
+                .await
+                .context("Failed to write Zed settings")?;
+        }
+
+        if self.backup.is_none() && self.backup_path.exists() {
+            tokio::fs::remove_file(&self.backup_path)
+                .await
+                .context("Failed to remove settings backup")?;
+        }
+        Ok(())
+    }
+}
 
 /// Configure Zed settings to use Widget-Log proxy
-pub async fn configure_zed_settings(auth_token: &str, proxy_url: &str) -> Result<()> {
+pub async fn configure_zed_settings(
+    settings_path: &Path,
+    widget_log_dir: &Path,
+    auth_token: &str,
+    proxy_url: &str,
+) -> Result<()> {
     log::info!("Configuring Zed settings for Widget-Log");
 
-    let settings_path = get_zed_settings_path();
+    let update = plan_configure(settings_path, widget_log_dir, auth_token, proxy_url).await?;
+    if update.has_changes() {
+        log::info!("Updating Zed settings:\n{}", update.diff());
+    }
+    update.apply().await?;
+
+    log::info!(
+        "Zed settings configured successfully at: {}",
+        update.settings_path.display()
+    );
+    Ok(())
+}
 
-    // Create directory if needed
-    if let Some(parent) = settings_path.parent() {
-        tokio::fs::create_dir_all(parent)
-            .await
-            .context("Failed to create Zed config directory")?;
+/// Restore the settings replaced by `configure_zed_settings`
+pub async fn unconfigure_zed_settings(settings_path: &Path, widget_log_dir: &Path) -> Result<()> {
+    let update = plan_unconfigure(settings_path, widget_log_dir).await?;
+    if update.has_changes() {
+        log::info!("Restoring Zed settings:\n{}", update.diff());
     }
+    update.apply().await
+}
+
+/// Prepare the settings.json edit that points Zed at the Widget-Log proxy
+pub async fn plan_configure(
+    settings_path: &Path,
+    widget_log_dir: &Path,
+    auth_token: &str,
+    proxy_url: &str,
+) -> Result<SettingsUpdate> {
+    let backup_path = widget_log_dir.join(BACKUP_FILE_NAME);
+
+    let old_text = read_settings_text(settings_path).await?;
+    let mut backup = read_backup(&backup_path).await?.unwrap_or_default();
+    let new_text = configure_settings_text(&old_text, auth_token, proxy_url, &mut backup)
+        .with_context(|| format!("Failed to update {}", settings_path.display()))?;
+
+    Ok(SettingsUpdate {
+        settings_path: settings_path.to_path_buf(),
+        old_text,
+        new_text,
+        backup_path,
+        backup: Some(backup),
+    })
+}
+
+/// Prepare the settings.json edit that undoes `plan_configure`
+pub async fn plan_unconfigure(
+    settings_path: &Path,
+    widget_log_dir: &Path,
+) -> Result<SettingsUpdate> {
+    let backup_path = widget_log_dir.join(BACKUP_FILE_NAME);
 
-    // Read existing settings or create new
-    let mut settings: Value = if settings_path.exists() {
-        let content = tokio::fs::read_to_string(&settings_path)
-            .await
-            .context("Failed to read Zed settings")?;
-        serde_json::from_str(&content).unwrap_or_else(|_| {
-            log::warn!("Could not parse existing Zed settings, creating new");
-            json!({})
+    let old_text = read_settings_text(settings_path).await?;
+    let new_text = match read_backup(&backup_path).await? {
+        Some(backup) => unconfigure_settings_text(&old_text, &backup)
+            .with_context(|| format!("Failed to update {}", settings_path.display()))?,
+        None => {
+            log::info!("Zed settings were not configured for Widget-Log, nothing to restore");
+            old_text.clone()
+        }
+    };
+
+    Ok(SettingsUpdate {
+        settings_path: settings_path.to_path_buf(),
+        old_text,
+        new_text,
+        backup_path,
+        backup: None,
+    })
+}
+
+/// Insert or update the Widget-Log keys in settings.json text, keeping
+/// comments, formatting and unrelated settings
+///
+/// Previous values of the keys set here are appended to `backup`, unless an
+/// entry for the same key (or one of its parents) is already there from an
+/// earlier run. Only those keys are recorded, not the objects containing them,
+/// so settings added next to them later survive `unconfigure_settings_text`.
+pub fn configure_settings_text(
+    text: &str,
+    auth_token: &str,
+    proxy_url: &str,
+    backup: &mut Vec<SettingsBackupEntry>,
+) -> Result<String> {
+    let old_value = parse_settings(text)?;
+    let mut new_value = old_value.clone();
+
+    let anthropic = ["language_models", "anthropic"];
+    let available_models_path = [anthropic[0], anthropic[1], "available_models"];
+    let mut available_models: Vec<Value> = lookup(&old_value, &available_models_path)
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter(|model| {
+            let name = model.get("name").and_then(Value::as_str);
+            !name.is_some_and(|name| CACHED_MODEL_NAMES.contains(&name))
         })
+        .cloned()
+        .collect();
+    available_models.extend(cached_models());
+
+    let edits = [
+        (vec![anthropic[0], anthropic[1], "version"], json!("1")),
+        (
+            vec![anthropic[0], anthropic[1], "api_url"],
+            json!(proxy_url),
+        ),
+        (
+            vec![anthropic[0], anthropic[1], "low_speed_timeout_in_seconds"],
+            json!(60),
+        ),
+        (
+            available_models_path.to_vec(),
+            Value::Array(available_models),
+        ),
+        (vec!["http_client", "http2"], json!(true)),
+        (vec!["http_client", "timeout_in_seconds"], json!(120)),
+        (
+            vec!["http_client", "headers", "Authorization"],
+            json!(format!("Bearer {}", auth_token)),
+        ),
+    ];
+
+    for (path, value) in edits {
+        let already_backed_up = backup.iter().any(|entry| {
+            path.starts_with(&entry.path.iter().map(String::as_str).collect::<Vec<_>>())
+        });
+        if !already_backed_up {
+            backup.push(backup_entry(&old_value, &path));
+        }
+        set_path(&mut new_value, &path, value);
+    }
+
+    Ok(apply_settings_value(text, &old_value, &new_value))
+}
+
+/// Put back the values recorded by `configure_settings_text`
+///
+/// Keys that did not exist are removed, along with the objects that held them
+/// if nothing else was added to those since.
+pub fn unconfigure_settings_text(text: &str, backup: &[SettingsBackupEntry]) -> Result<String> {
+    let old_value = parse_settings(text)?;
+    let mut new_value = old_value.clone();
+
+    for entry in backup.iter().rev() {
+        let path: Vec<&str> = entry.path.iter().map(String::as_str).collect();
+        match &entry.previous {
+            Some(previous) => set_path(&mut new_value, &path, previous.clone()),
+            None => {
+                remove_path(&mut new_value, &path);
+                remove_empty_parents(&mut new_value, &path);
+            }
+        }
+    }
+
+    Ok(apply_settings_value(text, &old_value, &new_value))
+}
+
+fn cached_models() -> Vec<Value> {
+    let cache_configuration = json!({
+        "max_cache_anchors": 4,
+        "min_total_token": 2048,
+        "min_tokens_per_anchor": 1024,
+        "should_speculate": true,
+        "cache_ttl_seconds": 300
+    });
+    vec![
+        json!({
+            "name": CACHED_MODEL_NAMES[0],
+            "display_name": "Claude 3.5 Sonnet (Cached)",
+            "max_tokens": 8192,
+            "max_output_tokens": 8192,
+            "max_cache_anchors": 4,
+            "cache_configuration": cache_configuration,
+            "tool_override": {
+                "max_tokens": 8192
+            }
+        }),
+        json!({
+            "name": CACHED_MODEL_NAMES[1],
+            "display_name": "Claude Opus 4 (Cached)",
+            "max_tokens": 8192,
+            "max_output_tokens": 8192,
+            "max_cache_anchors": 4,
+            "cache_configuration": cache_configuration
+        }),
+    ]
+}
+
+/// Parse settings.json, treating an empty file as an empty object
+fn parse_settings(text: &str) -> Result<Value> {
+    if text.trim().is_empty() {
+        return Ok(Value::Object(Map::new()));
+    }
+    let value: Value = parse_json_with_comments(text).context("Zed settings are not valid JSON")?;
+    anyhow::ensure!(value.is_object(), "Zed settings must be a JSON object");
+    Ok(value)
+}
+
+/// Rewrite only the parts of `text` that differ between the two values
+fn apply_settings_value(text: &str, old_value: &Value, new_value: &Value) -> String {
+    let mut text = if text.trim().is_empty() {
+        "{\n}\n".to_string()
     } else {
-        log::info!("Creating new Zed settings file");
-        json!({})
+        text.to_string()
     };
+    let tab_size = infer_json_indent_size(&text);
+    update_value_in_json_text(
+        &mut text,
+        &mut Vec::new(),
+        tab_size,
+        old_value,
+        new_value,
+        &mut Vec::new(),
+    );
+    text
+}
 
-    // Merge Widget-Log configuration
-    settings["language_models"] = json!({
-        "anthropic": {
-            "version": "1",
-            "api_url": proxy_url,
-            "low_speed_timeout_in_seconds": 60,
-            "available_models": [
-                {
-                    "name": "claude-3-5-sonnet-20241022",
-                    "display_name": "Claude 3.5 Sonnet (Cached)",
-                    "max_tokens": 8192,
-                    "max_output_tokens": 8192,
-                    "max_cache_anchors": 4,
-                    "cache_configuration": {
-                        "max_cache_anchors": 4,
-                        "min_total_token": 2048,
-                        "min_tokens_per_anchor": 1024,
-                        "should_speculate": true,
-                        "cache_ttl_seconds": 300
-                    },
-                    "tool_override": {
-                        "max_tokens": 8192
-                    }
-                },
-                {
-                    "name": "claude-opus-4-20250514",
-                    "display_name": "Claude Opus 4 (Cached)",
-                    "max_tokens": 8192,
-                    "max_output_tokens": 8192,
-                    "max_cache_anchors": 4,
-                    "cache_configuration": {
-                        "max_cache_anchors": 4,
-                        "min_total_token": 2048,
-                        "min_tokens_per_anchor": 1024,
-                        "should_speculate": true,
-                        "cache_ttl_seconds": 300
-                    }
-                }
-            ]
+/// Entry for `path`, or for a parent on it that `set_path` replaces because
+/// it is not an object
+fn backup_entry(value: &Value, path: &[&str]) -> SettingsBackupEntry {
+    let mut current = value;
+    for (depth, key) in path.iter().enumerate() {
+        if !current.is_object() {
+            return SettingsBackupEntry {
+                path: path[..depth].iter().map(|key| key.to_string()).collect(),
+                previous: Some(current.clone()),
+            };
         }
-    });
+        match current.get(key) {
+            Some(next) => current = next,
+            None => {
+                return SettingsBackupEntry {
+                    path: path.iter().map(|key| key.to_string()).collect(),
+                    previous: None,
+                };
+            }
+        }
+    }
+    SettingsBackupEntry {
+        path: path.iter().map(|key| key.to_string()).collect(),
+        previous: Some(current.clone()),
+    }
+}
+
+fn lookup<'a>(value: &'a Value, path: &[&str]) -> Option<&'a Value> {
+    path.iter().try_fold(value, |value, key| value.get(key))
+}
 
-    settings["http_client"] = json!({
-        "http2": true,
-        "timeout_in_seconds": 120,
-        "headers": {
-            "Authorization": format!("Bearer {}", auth_token)
+/// Set `path` to `new_value`, replacing non-object parents with objects
+fn set_path(value: &mut Value, path: &[&str], new_value: Value) {
+    let Some((last, parents)) = path.split_last() else {
+        *value = new_value;
+        return;
+    };
+    let mut current = value;
+    for key in parents {
+        if !current.is_object() {
+            *current = Value::Object(Map::new());
         }
-    });
+        current = current
+            .as_object_mut()
+            .expect("just replaced with an object")
+            .entry(key.to_string())
+            .or_insert_with(|| Value::Object(Map::new()));
+    }
+    if !current.is_object() {
+        *current = Value::Object(Map::new());
+    }
+    if let Some(object) = current.as_object_mut() {
+        object.insert(last.to_string(), new_value);
+    }
+}
 
-    // Write back
-    let content = serde_json::to_string_pretty(&settings)
-        .context("Failed to serialize settings")?;
-    tokio::fs::write(&settings_path, content)
+fn remove_path(value: &mut Value, path: &[&str]) {
+    let Some((last, parents)) = path.split_last() else {
+        return;
+    };
+    let mut current = value;
+    for key in parents {
+        match current.get_mut(key) {
+            Some(next) => current = next,
+            None => return,
+        }
+    }
+    if let Some(object) = current.as_object_mut() {
+        object.remove(*last);
+    }
+}
+
+/// Remove the objects on `path` that are left empty, deepest first
+fn remove_empty_parents(value: &mut Value, path: &[&str]) {
+    for depth in (1..path.len()).rev() {
+        let parent = &path[..depth];
+        if !lookup(value, parent)
+            .and_then(Value::as_object)
+            .is_some_and(Map::is_empty)
+        {
+            return;
+        }
+        remove_path(value, parent);
+    }
+}
+
+async fn read_settings_text(settings_path: &Path) -> Result<String> {
+    if !settings_path.exists() {
+        return Ok(String::new());
+    }
+    tokio::fs::read_to_string(settings_path)
         .await
-        .context("Failed to write Zed settings")?;
+        .context("Failed to read Zed settings")
+}
 
-    log::info!("Zed settings configured successfully at: {}", settings_path.display());
-    Ok(())
+async fn read_backup(backup_path: &Path) -> Result<Option<Vec<SettingsBackupEntry>>> {
+    if !backup_path.exists() {
+        return Ok(None);
+    }
+    let content = tokio::fs::read_to_string(backup_path)
+        .await
+        .context("Failed to read settings backup")?;
+    let backup = serde_json::from_str(&content).context("Failed to parse settings backup")?;
+    Ok(Some(backup))
 }
 
 /// Get authentication token from Widget-Log .env file
@@ -113,9 +445,202 @@ pub async fn get_auth_token(widget_log_dir: &PathBuf) -> Result<String> {
     anyhow::bail!("WIDGET_LOG_AUTH_TOKEN not found in .env file")
 }
 
-fn get_zed_settings_path() -> PathBuf {
-    dirs::config_dir()
-        .unwrap_or_else(|| PathBuf::from("."))
-        .join("zed")
-        .join("settings.json")
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const USER_SETTINGS: &str = r#"// Zed settings
+{
+  // Keep my font
+  "buffer_font_size": 15,
+  "language_models": {
+    "anthropic": {
+      "api_url": "https://example.com" // my mirror
+    },
+    "openai": {
+      "api_url": "https://api.openai.com/v1"
+    }
+  }
+}
+"#;
+
+    #[test]
+    fn test_configure_preserves_comments_and_other_settings() {
+        let mut backup = Vec::new();
+        let text = configure_settings_text(
+            USER_SETTINGS,
+            "token",
+            "https://127.0.0.1:8443",
+            &mut backup,
+        )
+        .unwrap();
+
+        assert!(text.contains("// Zed settings"));
+        assert!(text.contains("// Keep my font"));
+        let value: Value = parse_json_with_comments(&text).unwrap();
+        assert_eq!(value["buffer_font_size"], 15);
+        assert_eq!(
+            value["language_models"]["openai"]["api_url"],
+            "https://api.openai.com/v1"
+        );
+        assert_eq!(
+            value["language_models"]["anthropic"]["api_url"],
+            "https://127.0.0.1:8443"
+        );
+        assert_eq!(
+            value["http_client"]["headers"]["Authorization"],
+            "Bearer token"
+        );
+        assert_eq!(
+            value["language_models"]["anthropic"]["available_models"]
+                .as_array()
+                .unwrap()
+                .len(),
+            2
+        );
+
+        // Configuring again changes nothing and keeps the original backup
+        let backup_before = backup.clone();
+        let again =
+            configure_settings_text(&text, "token", "https://127.0.0.1:8443", &mut backup).unwrap();
+        assert_eq!(again, text);
+        assert_eq!(backup, backup_before);
+    }
+
+    #[test]
+    fn test_unconfigure_restores_previous_values() {
+        let mut backup = Vec::new();
+        let configured = configure_settings_text(
+            USER_SETTINGS,
+            "token",
+            "https://127.0.0.1:8443",
+            &mut backup,
+        )
+        .unwrap();
+        let restored = unconfigure_settings_text(&configured, &backup).unwrap();
+
+        assert!(restored.contains("// Keep my font"));
+        let restored: Value = parse_json_with_comments(&restored).unwrap();
+        let original: Value = parse_json_with_comments(USER_SETTINGS).unwrap();
+        assert_eq!(restored, original);
+    }
+
+    #[test]
+    fn test_configure_keeps_user_models() {
+        let text = r#"{
+    "language_models": {
+        "anthropic": {
+            "available_models": [
+                { "name": "my-model", "max_tokens": 1000 },
+                { "name": "claude-opus-4-20250514", "max_tokens": 1 }
+            ]
+        }
+    }
+}"#;
+        let mut backup = Vec::new();
+        let configured = configure_settings_text(text, "t", "url", &mut backup).unwrap();
+        let value: Value = parse_json_with_comments(&configured).unwrap();
+        let names: Vec<&str> = value["language_models"]["anthropic"]["available_models"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|model| model["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            names,
+            ["my-model", CACHED_MODEL_NAMES[0], CACHED_MODEL_NAMES[1]]
+        );
+
+        let restored = unconfigure_settings_text(&configured, &backup).unwrap();
+        let restored: Value = parse_json_with_comments(&restored).unwrap();
+        assert_eq!(restored, parse_json_with_comments::<Value>(text).unwrap());
+    }
+
+    #[test]
+    fn test_empty_and_invalid_settings() {
+        let mut backup = Vec::new();
+        let configured = configure_settings_text("", "t", "url", &mut backup).unwrap();
+        assert_eq!(
+            backup.iter().map(|e| e.path.join(".")).collect::<Vec<_>>(),
+            [
+                "language_models.anthropic.version",
+                "language_models.anthropic.api_url",
+                "language_models.anthropic.low_speed_timeout_in_seconds",
+                "language_models.anthropic.available_models",
+                "http_client.http2",
+                "http_client.timeout_in_seconds",
+                "http_client.headers.Authorization",
+            ]
+        );
+        assert!(backup.iter().all(|entry| entry.previous.is_none()));
+        let restored = unconfigure_settings_text(&configured, &backup).unwrap();
+        assert_eq!(parse_settings(&restored).unwrap(), json!({}));
+
+        assert!(configure_settings_text("{ \"a\": ", "t", "url", &mut Vec::new()).is_err());
+        assert!(configure_settings_text("[]", "t", "url", &mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_unconfigure_keeps_settings_added_since() {
+        let mut backup = Vec::new();
+        let configured = configure_settings_text("", "t", "url", &mut backup).unwrap();
+
+        // The user sets up another provider and HTTP option in the meantime
+        let mut value = parse_settings(&configured).unwrap();
+        set_path(
+            &mut value,
+            &["language_models", "openai", "api_url"],
+            json!("https://api.openai.com/v1"),
+        );
+        set_path(
+            &mut value,
+            &["http_client", "proxy"],
+            json!("socks5://localhost"),
+        );
+        let edited =
+            apply_settings_value(&configured, &parse_settings(&configured).unwrap(), &value);
+
+        let restored = unconfigure_settings_text(&edited, &backup).unwrap();
+        assert_eq!(
+            parse_settings(&restored).unwrap(),
+            json!({
+                "language_models": {
+                    "openai": { "api_url": "https://api.openai.com/v1" }
+                },
+                "http_client": { "proxy": "socks5://localhost" }
+            })
+        );
+    }
+
+    #[test]
+    fn test_configure_replaces_non_object_parent() {
+        let text = r#"{ "http_client": false }"#;
+        let mut backup = Vec::new();
+        let configured = configure_settings_text(text, "t", "url", &mut backup).unwrap();
+        assert!(backup.contains(&SettingsBackupEntry {
+            path: vec!["http_client".to_string()],
+            previous: Some(json!(false)),
+        }));
+
+        let restored = unconfigure_settings_text(&configured, &backup).unwrap();
+        assert_eq!(
+            parse_settings(&restored).unwrap(),
+            parse_settings(text).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_diff_preview() {
+        let update = SettingsUpdate {
+            settings_path: PathBuf::from("settings.json"),
+            old_text: "{\n  \"a\": 1\n}\n".to_string(),
+            new_text: "{\n  \"a\": 2\n}\n".to_string(),
+            backup_path: PathBuf::from(BACKUP_FILE_NAME),
+            backup: None,
+        };
+        let diff = update.diff();
+        assert!(diff.starts_with("--- settings.json\n+++ settings.json\n"));
+        assert!(diff.contains("-  \"a\": 1\n"));
+        assert!(diff.contains("+  \"a\": 2\n"));
+    }
 }
@@ -0,0 +1,42 @@
+"""Stand-in for app/secure_proxy.py in the supervisor tests.
+
+Answers /health over HTTPS with the self-signed certificate next to it, and
+exits with code 1 after answering /crash.
+"""
+
+import argparse
+import http.server
+import os
+import ssl
+import threading
+
+parser = argparse.ArgumentParser()
+parser.add_argument("--host")
+parser.add_argument("--port", type=int)
+args = parser.parse_args()
+
+
+class Handler(http.server.BaseHTTPRequestHandler):
+    def do_GET(self):
+        if self.path not in ("/health", "/crash"):
+            self.send_error(404)
+            return
+        self.send_response(200)
+        self.send_header("Content-Length", "0")
+        self.end_headers()
+        if self.path == "/crash":
+            self.wfile.flush()
+            threading.Thread(target=lambda: os._exit(1)).start()
+
+    def log_message(self, format, *args):
+        pass
+
+
+here = os.path.dirname(os.path.abspath(__file__))
+context = ssl.SSLContext(ssl.PROTOCOL_TLS_SERVER)
+context.load_cert_chain(
+    os.path.join(here, "fake_proxy_cert.pem"), os.path.join(here, "fake_proxy_key.pem")
+)
+server = http.server.HTTPServer((args.host, args.port), Handler)
+server.socket = context.wrap_socket(server.socket, server_side=True)
+server.serve_forever()